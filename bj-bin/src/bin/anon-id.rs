@@ -0,0 +1,52 @@
+use bj_bin::config::{anon_id_path, load_identity, save_identity};
+use bj_bin::logging;
+use clap::{crate_authors, crate_name, crate_version, App, Arg};
+use std::error::Error;
+use std::path::PathBuf;
+
+fn main() -> Result<(), Box<dyn Error>> {
+    logging::init();
+    let matches = App::new(String::from(crate_name!()) + " anon-id")
+        .author(crate_authors!())
+        .version(crate_version!())
+        .about(
+            "Show the anonymous ID uploads are sent with, so a server can tell which are yours \
+            without an account. It's random, made the first time it's needed, and kept in \
+            ~/.config/bj-bin/anon-id.json",
+        )
+        .arg(
+            Arg::with_name("file")
+                .long("file")
+                .value_name("FILE")
+                .help("Keep the ID in FILE instead"),
+        )
+        .arg(
+            Arg::with_name("regenerate")
+                .long("regenerate")
+                .help("Replace the ID with a new one, opting back in if you'd opted out"),
+        )
+        .arg(
+            Arg::with_name("optout")
+                .long("opt-out")
+                .conflicts_with("regenerate")
+                .help("Forget the ID and upload without one from now on"),
+        )
+        .get_matches();
+    let path = match matches.value_of("file") {
+        Some(f) => PathBuf::from(f),
+        None => anon_id_path().ok_or("No home directory to keep the ID in. Give --file")?,
+    };
+    let mut ident = load_identity(&path)?;
+    if matches.is_present("regenerate") {
+        ident.regenerate();
+        save_identity(&path, &ident)?;
+    } else if matches.is_present("optout") {
+        ident.opt_out();
+        save_identity(&path, &ident)?;
+    }
+    match ident.id() {
+        Some(id) => println!("{}", id),
+        None => println!("Opted out. Run with --regenerate to opt back in"),
+    }
+    Ok(())
+}
@@ -1,12 +1,16 @@
+use bj_bin::logging;
 use bj_core::basicstrategy::rules;
 use bj_core::basicstrategy::BasicStrategy;
-use bj_core::resp::resps_from_buf;
+use bj_core::looseparse::resps_from_loose_str;
+use bj_core::resp::partial_resps_from_buf;
 use bj_core::table::Table;
 use clap::{crate_authors, crate_name, crate_version, App, Arg};
 use std::error::Error;
 use std::fs::OpenOptions;
+use std::io::Read;
 
 fn main() -> Result<(), Box<dyn Error>> {
+    logging::init();
     let matches = App::new(String::from(crate_name!()) + " attach-bs")
         .author(crate_authors!())
         .version(crate_version!())
@@ -15,6 +19,7 @@ fn main() -> Result<(), Box<dyn Error>> {
                 .short("i")
                 .long("input")
                 .value_name("STRAT_CARD")
+                .help("Strategy card to read. Cells marked '-' aren't learned yet, making it a partial card")
                 .default_value("/dev/stdin"),
         )
         .arg(
@@ -46,6 +51,35 @@ fn main() -> Result<(), Box<dyn Error>> {
                 .takes_value(true)
                 .possible_values(&["yes", "no"]),
         )
+        .arg(
+            Arg::with_name("maxsplits")
+                .long("max-splits")
+                .takes_value(true)
+                .possible_values(&["0", "1", "2", "3", "4", "5", "6", "7"])
+                .default_value("3")
+                .help("The most times the player may split in one round (3 is up to 4 hands)"),
+        )
+        .arg(
+            Arg::with_name("pays")
+                .long("blackjack-pays")
+                .takes_value(true)
+                .possible_values(&["3:2", "6:5"])
+                .default_value("3:2"),
+        )
+        .arg(
+            Arg::with_name("rsa")
+                .long("resplit-aces")
+                .takes_value(true)
+                .possible_values(&["yes", "no"])
+                .default_value("no"),
+        )
+        .arg(
+            Arg::with_name("hsa")
+                .long("hit-split-aces")
+                .takes_value(true)
+                .possible_values(&["yes", "no"])
+                .default_value("no"),
+        )
         .arg(
             Arg::with_name("hit17")
                 .long("hit-soft-17")
@@ -60,13 +94,41 @@ fn main() -> Result<(), Box<dyn Error>> {
                 .takes_value(true)
                 .possible_values(&["yes", "no"]),
         )
+        .arg(
+            Arg::with_name("nopeekloss")
+                .long("no-peek-loss")
+                .takes_value(true)
+                .possible_values(&["obo", "all"])
+                .default_value("obo")
+                .help("If the dealer doesn't peek, whether a dealer BJ takes only the original bet (obo) or all doubled and split bets too (all)"),
+        )
+        .arg(
+            Arg::with_name("loose")
+                .long("loose")
+                .help("Accept messy input, such as OCR output of a photographed card, and report every row that can't be understood"),
+        )
         .get_matches();
-    let table = Table::new(resps_from_buf(
-        OpenOptions::new()
-            .read(true)
-            // safe to unwrap because --input is required
-            .open(matches.value_of("input").unwrap())?,
-    )?)?;
+    // safe to unwrap because --input has a default
+    let mut fd = OpenOptions::new()
+        .read(true)
+        .open(matches.value_of("input").unwrap())?;
+    let partial = if matches.is_present("loose") {
+        let mut s = String::new();
+        fd.read_to_string(&mut s)?;
+        match resps_from_loose_str(&s) {
+            Ok(resps) => Table::new(resps.into_iter().map(Some))?,
+            Err(errs) => {
+                for e in &errs {
+                    log::error!("{}", e);
+                }
+                return Err(
+                    format!("{} problem(s) with the input strategy card", errs.len()).into(),
+                );
+            }
+        }
+    } else {
+        Table::new(partial_resps_from_buf(fd)?)?
+    };
     let decks = match matches.value_of("decks").unwrap() {
         "1" => rules::NumDecks::One,
         "2" => rules::NumDecks::Two,
@@ -85,6 +147,23 @@ fn main() -> Result<(), Box<dyn Error>> {
         "yes" => true,
         _ => panic!("Impossible das"),
     };
+    // safe to unwrap because possible values are all small numbers
+    let max_splits: u8 = matches.value_of("maxsplits").unwrap().parse().unwrap();
+    let blackjack_pays = match matches.value_of("pays").unwrap() {
+        "3:2" => rules::BlackjackPayout::ThreeToTwo,
+        "6:5" => rules::BlackjackPayout::SixToFive,
+        _ => panic!("Impossible blackjack-pays"),
+    };
+    let rsa = match matches.value_of("rsa").unwrap() {
+        "no" => false,
+        "yes" => true,
+        _ => panic!("Impossible resplit-aces"),
+    };
+    let hsa = match matches.value_of("hsa").unwrap() {
+        "no" => false,
+        "yes" => true,
+        _ => panic!("Impossible hit-split-aces"),
+    };
     let hit17 = match matches.value_of("hit17").unwrap() {
         "no" => false,
         "yes" => true,
@@ -95,14 +174,24 @@ fn main() -> Result<(), Box<dyn Error>> {
         "yes" => true,
         _ => panic!("Impossible peek"),
     };
+    let no_peek_loss = match matches.value_of("nopeekloss").unwrap() {
+        "obo" => rules::NoPeekLoss::OriginalBetsOnly,
+        "all" => rules::NoPeekLoss::AllBets,
+        _ => panic!("Impossible no-peek-loss"),
+    };
     let rules = rules::Rules {
         decks,
         double_after_split: das.into(),
         hit_soft_17: hit17.into(),
         peek_bj: peek.into(),
         surrender,
+        no_peek_loss,
+        max_splits: max_splits.into(),
+        resplit_aces: rsa.into(),
+        hit_split_aces: hsa.into(),
+        blackjack_pays,
     };
-    let bs: BasicStrategy = (Some(rules), table).into();
+    let bs = BasicStrategy::from_partial(Some(rules), partial)?;
     let fd = OpenOptions::new()
         .write(true)
         .create(true)
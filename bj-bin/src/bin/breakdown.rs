@@ -0,0 +1,54 @@
+use bj_bin::logging;
+use bj_bin::utils::read_maybexz;
+use bj_core::breakdown::{by_player, by_upcard, to_csv};
+use bj_core::playstats::PlayStats;
+use bj_core::table::Table;
+use clap::{crate_authors, crate_name, crate_version, App, Arg};
+use std::error::Error;
+use std::fs::OpenOptions;
+
+fn main() -> Result<(), Box<dyn Error>> {
+    logging::init();
+    let matches = App::new(String::from(crate_name!()) + " breakdown")
+        .author(crate_authors!())
+        .version(crate_version!())
+        .about("Show accuracy in play stats by dealer upcard or by player hand, for charting")
+        .arg(
+            Arg::with_name("stats")
+                .short("s")
+                .long("stats")
+                .value_name("FILE")
+                .help("Play stats to read")
+                .default_value("play-stats.json.xz"),
+        )
+        .arg(
+            Arg::with_name("by")
+                .long("by")
+                .possible_values(&["upcard", "player"])
+                .default_value("upcard")
+                .help("Add up the card's columns (upcard) or its rows (player)"),
+        )
+        .arg(
+            Arg::with_name("format")
+                .short("f")
+                .long("format")
+                .possible_values(&["csv", "json"])
+                .default_value("csv"),
+        )
+        .get_matches();
+    // safe to unwrap because all of these have defaults
+    let stats_fname = matches.value_of("stats").unwrap();
+    let stats: Table<PlayStats> = read_maybexz(
+        OpenOptions::new().read(true).open(stats_fname)?,
+        stats_fname.ends_with(".xz"),
+    )?;
+    let rows = match matches.value_of("by").unwrap() {
+        "player" => by_player(&stats),
+        _ => by_upcard(&stats),
+    };
+    match matches.value_of("format").unwrap() {
+        "json" => println!("{}", serde_json::to_string(&rows)?),
+        _ => print!("{}", to_csv(&rows)),
+    }
+    Ok(())
+}
@@ -1,3 +1,4 @@
+use bj_bin::logging;
 use bj_bin::utils::{read_maybexz, write_maybexz};
 use bj_core::playstats::PlayStats;
 use bj_core::table::Table;
@@ -12,6 +13,7 @@ arg_enum! {
 }
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
+    logging::init();
     let matches = App::new(String::from(crate_name!()) + " combine")
         .author(crate_authors!())
         .version(crate_version!())
@@ -52,7 +54,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
             // - aggregate it into the accumulator table
             // and if all goes succesfully, put final accumulated table in agg
             let agg = match inputs.into_iter().try_fold(agg, |mut acc, fname| {
-                eprintln!("Reading {}", fname);
+                log::info!("Reading {}", fname);
                 let fd = OpenOptions::new().read(true).open(&fname)?;
                 acc += read_maybexz(fd, fname.ends_with(".xz"))?;
                 Ok(acc)
@@ -60,15 +62,15 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                 Ok(v) => v,
                 Err(e) => return Err(e),
             };
-            let seen = agg.values().fold(0, |acc, stat| acc + stat.seen());
-            eprintln!("Total games played: {}", seen);
+            let seen = agg.fold_cells(0, |acc, _, stat| acc + stat.seen());
+            log::info!("Total games played: {}", seen);
             // try writing out result
             let out_fname = value_t!(matches, "output", String)?;
             let out = OpenOptions::new()
                 .write(true)
                 .create(true)
                 .open(&out_fname)?;
-            eprintln!("Writing {}", out_fname);
+            log::info!("Writing {}", out_fname);
             write_maybexz(out, &agg, out_fname.ends_with(".xz"))?;
         }
     }
@@ -1,12 +1,18 @@
+use bj_bin::config::{config_arg, Config};
+use bj_bin::logging;
 use bj_bin::prompt;
+use bj_core::betramp::BetRamp;
 use bj_core::count::{CountSystem, HiLo};
+use bj_core::countsim::{simulate, Constraints, SimOpts, SimReport};
 use bj_core::deck::{Card, Deck};
 use bj_core::hand::Hand;
+use bj_core::kelly::Advantage;
 use clap::{crate_authors, crate_name, crate_version, value_t, App, Arg};
 use std::io::{self, BufRead, BufReader, Write};
 
 fn prompt_for_num<D>(
     hand_or_card: &D,
+    aliases: &prompt::Aliases,
     in_buf: &mut impl BufRead,
     out_buf: &mut impl Write,
 ) -> io::Result<prompt::Command>
@@ -15,7 +21,7 @@ where
 {
     let s = &format!("count of {}", hand_or_card);
     loop {
-        match prompt::prompt(s, in_buf, out_buf)? {
+        match prompt::prompt(s, aliases, in_buf, out_buf)? {
             prompt::Command::SaveQuit | prompt::Command::Quit => break Ok(prompt::Command::Quit),
             prompt::Command::Num(n) => break Ok(prompt::Command::Num(n)),
             _ => {
@@ -26,7 +32,71 @@ where
     }
 }
 
+fn print_bet_ramp(ramp: &BetRamp) {
+    println!("Bet ramp with a 1-{} spread", ramp.spread());
+    let steps = ramp.steps();
+    for (i, step) in steps.iter().enumerate() {
+        // the first step's bet is also used below its count, so show it up to the next step
+        match (i, steps.get(1)) {
+            (0, Some(next)) => println!("  TC < {}: {} units", next.count, step.units),
+            (0, None) => println!("  Any TC: {} units", step.units),
+            _ => println!("  TC >= {}: {} units", step.count, step.units),
+        }
+    }
+}
+
+fn print_sim_report(name: &str, r: &SimReport, bankroll: Option<f64>) {
+    println!(
+        "  {:<5} edge {:+.3}%, {:.1}% of rounds with the edge, {:.2} units bet per round",
+        name,
+        r.edge() * 100.0,
+        r.share_with_edge() * 100.0,
+        r.units_bet as f64 / f64::from(r.rounds.max(1)),
+    );
+    println!(
+        "        {:+.1} units won, standard deviation {:.1} units",
+        r.units_won,
+        r.std_dev()
+    );
+    if let Some(bankroll) = bankroll {
+        println!(
+            "        {:.2}% risk of ruin with {} units",
+            r.risk_of_ruin(bankroll) * 100.0,
+            bankroll
+        );
+    }
+    let mut limited = vec![];
+    if r.rounds_watched > 0 {
+        limited.push(format!("watched {} rounds", r.rounds_watched));
+    }
+    if r.cover_plays > 0 {
+        limited.push(format!("{} cover plays", r.cover_plays));
+    }
+    if r.capped_bets > 0 {
+        limited.push(format!("{} bets cut to the table max", r.capped_bets));
+    }
+    if !limited.is_empty() {
+        println!("        {}", limited.join(", "));
+    }
+}
+
+/// Compare counting in a shoe game with counting against a continuous shuffle machine
+fn print_simulation(opts: SimOpts, ramp: &BetRamp, bankroll: Option<f64>) {
+    println!(
+        "{} rounds of {} cards from {} decks, shoe dealt to {:.0}% penetration",
+        opts.rounds,
+        opts.cards_per_round,
+        opts.num_decks,
+        opts.penetration * 100.0
+    );
+    let advantage = Advantage::new(None);
+    print_sim_report("Shoe", &simulate(&opts, ramp, &advantage), bankroll);
+    let csm = SimOpts { csm: true, ..opts };
+    print_sim_report("CSM", &simulate(&csm, ramp, &advantage), bankroll);
+}
+
 fn main() -> Result<(), Box<dyn std::error::Error>> {
+    logging::init();
     let matches = App::new(String::from(crate_name!()) + " count")
         .author(crate_authors!())
         .version(crate_version!())
@@ -38,7 +108,154 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                 .help("Number of cards to show at once")
                 .default_value("1"),
         )
+        .arg(
+            Arg::with_name("betramp")
+                .long("bet-ramp")
+                .value_name("RAMP")
+                .help(
+                    "Show what to bet at each true count, given as count:units steps separated \
+                     by commas. E.g. 1:1,2:2,3:4,4:6,5:8",
+                ),
+        )
+        .arg(
+            Arg::with_name("maxspread")
+                .long("max-spread")
+                .value_name("NUM")
+                .help("Refuse a bet ramp whose biggest bet is more than NUM times its smallest"),
+        )
+        .arg(
+            Arg::with_name("simulate")
+                .long("simulate")
+                .value_name("ROUNDS")
+                .help(
+                    "Bet the bet ramp (or a 1-8 spread) for ROUNDS rounds in a shoe game and \
+                     against a continuous shuffle machine, show how each went, and exit",
+                ),
+        )
+        .arg(
+            Arg::with_name("decks")
+                .long("decks")
+                .value_name("NUM")
+                .help("Number of decks to simulate")
+                .default_value("6"),
+        )
+        .arg(
+            Arg::with_name("penetration")
+                .long("penetration")
+                .value_name("FRAC")
+                .help("Fraction of the simulated shoe dealt before it is shuffled")
+                .default_value("0.75"),
+        )
+        .arg(
+            Arg::with_name("sessionrounds")
+                .long("session-rounds")
+                .value_name("NUM")
+                .requires("simulate")
+                .help("End each simulated session after NUM rounds and start over at a new shoe"),
+        )
+        .arg(
+            Arg::with_name("wongin")
+                .long("wong-in")
+                .value_name("TC")
+                .allow_hyphen_values(true)
+                .requires("simulate")
+                .help("After each shuffle, watch without betting until the true count reaches TC"),
+        )
+        .arg(
+            Arg::with_name("wongout")
+                .long("wong-out")
+                .value_name("TC")
+                .allow_hyphen_values(true)
+                .requires("simulate")
+                .help("Stop betting when the true count drops below TC, until the next shuffle"),
+        )
+        .arg(
+            Arg::with_name("coverevery")
+                .long("cover-every")
+                .value_name("NUM")
+                .requires("simulate")
+                .help("Flat bet the smallest bet every NUM rounds, whatever the count"),
+        )
+        .arg(
+            Arg::with_name("tablemin")
+                .long("table-min")
+                .value_name("UNITS")
+                .requires("simulate")
+                .help("Never bet less than UNITS units, the table's minimum"),
+        )
+        .arg(
+            Arg::with_name("tablemax")
+                .long("table-max")
+                .value_name("UNITS")
+                .requires("simulate")
+                .help("Never bet more than UNITS units, the table's maximum"),
+        )
+        .arg(
+            Arg::with_name("bankroll")
+                .long("bankroll")
+                .value_name("UNITS")
+                .requires("simulate")
+                .help("Show the risk of ruin with a bankroll of UNITS units"),
+        )
+        .arg(config_arg())
         .get_matches();
+    let mut ramp = None;
+    if let Some(s) = matches.value_of("betramp") {
+        let r: BetRamp = s.parse()?;
+        if matches.is_present("maxspread") {
+            r.check_spread(value_t!(matches, "maxspread", u32)?)?;
+        }
+        print_bet_ramp(&r);
+        ramp = Some(r);
+    }
+    if matches.is_present("simulate") {
+        let num_decks = value_t!(matches, "decks", u8)?;
+        if num_decks == 0 {
+            return Err("Must simulate at least 1 deck".into());
+        }
+        let opt = |name| -> Result<Option<u32>, clap::Error> {
+            match matches.is_present(name) {
+                true => Ok(Some(value_t!(matches, name, u32)?)),
+                false => Ok(None),
+            }
+        };
+        let tc = |name| -> Result<Option<i8>, clap::Error> {
+            match matches.is_present(name) {
+                true => Ok(Some(value_t!(matches, name, i8)?)),
+                false => Ok(None),
+            }
+        };
+        let constraints = Constraints {
+            session_rounds: opt("sessionrounds")?,
+            wong_in: tc("wongin")?,
+            wong_out: tc("wongout")?,
+            cover_every: opt("coverevery")?,
+            table_min: opt("tablemin")?,
+            table_max: opt("tablemax")?,
+        };
+        if let (Some(min), Some(max)) = (constraints.table_min, constraints.table_max) {
+            if min > max {
+                return Err("The table minimum can't be more than its maximum".into());
+            }
+        }
+        if constraints.session_rounds == Some(0) || constraints.cover_every == Some(0) {
+            return Err("Sessions and cover plays need at least 1 round".into());
+        }
+        let opts = SimOpts {
+            num_decks,
+            penetration: value_t!(matches, "penetration", f32)?,
+            rounds: value_t!(matches, "simulate", u32)?,
+            constraints,
+            ..SimOpts::default()
+        };
+        let bankroll = match matches.is_present("bankroll") {
+            true => Some(value_t!(matches, "bankroll", f64)?),
+            false => None,
+        };
+        print_simulation(opts, &ramp.unwrap_or_default(), bankroll);
+        return Ok(());
+    }
+    let aliases = Config::load(matches.value_of("config"))?.aliases()?;
     let num_cards = value_t!(matches, "cards", usize)?;
     if num_cards == 0 {
         return Err("Must specify at least 1 card".into());
@@ -50,14 +267,14 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     loop {
         let (actual, cmd) = if num_cards == 1 {
             let c = deck.draw()?;
-            let cmd = prompt_for_num(&c, &mut input, &mut output)?;
+            let cmd = prompt_for_num(&c, &aliases, &mut input, &mut output)?;
             (hilo.card_value(c), cmd)
         } else {
             let cards = (0..num_cards)
                 .map(|_| deck.draw().unwrap())
                 .collect::<Vec<Card>>();
             let h = Hand::new(&cards);
-            let cmd = prompt_for_num(&h, &mut input, &mut output)?;
+            let cmd = prompt_for_num(&h, &aliases, &mut input, &mut output)?;
             (hilo.cards_value(&cards), cmd)
         };
         let val = match cmd {
@@ -0,0 +1,88 @@
+use bj_bin::logging;
+use bj_bin::utils::{create_if_not_exist, read_maybexz, write_maybexz};
+use bj_core::basicstrategy::BasicStrategy;
+use bj_core::import::{import_csv, CsvMapping, PRESETS};
+use bj_core::playstats::PlayStats;
+use bj_core::resp::resps_from_buf;
+use bj_core::table::Table;
+use clap::{crate_authors, crate_name, crate_version, App, Arg};
+use std::fs::{self, OpenOptions};
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    logging::init();
+    let matches = App::new(String::from(crate_name!()) + " import")
+        .author(crate_authors!())
+        .version(crate_version!())
+        .about("Add play history exported by another trainer as CSV to play stats")
+        .arg(
+            Arg::with_name("input")
+                .short("i")
+                .long("input")
+                .value_name("FILE")
+                .help("CSV file exported by the other trainer")
+                .required(true),
+        )
+        .arg(
+            Arg::with_name("mapping")
+                .short("m")
+                .long("mapping")
+                .value_name("PRESET|FILE")
+                .help(&format!(
+                    "Which CSV columns hold what. Either a preset ({}) or a JSON file with \
+                     player, dealer, action, correct, and delimiter keys",
+                    PRESETS.join(", ")
+                ))
+                .default_value("generic"),
+        )
+        .arg(
+            Arg::with_name("table")
+                .short("t")
+                .long("table")
+                .value_name("FILE")
+                .help("Table of ideal moves to grade actions against")
+                .required(true),
+        )
+        .arg(
+            Arg::with_name("stats")
+                .short("s")
+                .long("stats")
+                .value_name("FILE")
+                .help("Add to play stats in the file")
+                .default_value("play-stats.json.xz"),
+        )
+        .get_matches();
+    // safe to unwrap because these are required or have defaults
+    let mapping_arg = matches.value_of("mapping").unwrap();
+    let mapping = match CsvMapping::preset(mapping_arg) {
+        Some(m) => m,
+        None => serde_json::from_str(&fs::read_to_string(mapping_arg)?)?,
+    };
+    let table = Table::new_checked(resps_from_buf(
+        OpenOptions::new()
+            .read(true)
+            .open(matches.value_of("table").unwrap())?,
+    )?)?;
+    let bs = BasicStrategy::new(None, table);
+    let csv = fs::read_to_string(matches.value_of("input").unwrap())?;
+    let stats_fname = matches.value_of("stats").unwrap();
+    let xz = stats_fname.ends_with(".xz");
+    create_if_not_exist(stats_fname, &Table::new(vec![PlayStats::new(); 360])?)?;
+    let fd = OpenOptions::new().read(true).open(stats_fname)?;
+    let mut stats: Table<PlayStats> = read_maybexz(fd, xz)?;
+    let summary = import_csv(&csv, &mapping, &bs, &mut stats)?;
+    for (line, why) in &summary.skipped {
+        log::warn!("Skipped line {}: {}", line, why);
+    }
+    let fd = OpenOptions::new()
+        .write(true)
+        .truncate(true)
+        .open(stats_fname)?;
+    write_maybexz(fd, &stats, xz)?;
+    println!(
+        "Imported {} decisions into {} ({} skipped)",
+        summary.imported,
+        stats_fname,
+        summary.skipped.len()
+    );
+    Ok(())
+}
@@ -0,0 +1,169 @@
+use bj_bin::config::config_dir;
+use bj_bin::logging;
+use bj_core::basicstrategy::BasicStrategy;
+use bj_core::journal::{summarize, JournalEntry};
+use bj_core::kelly::Advantage;
+use clap::{crate_authors, crate_name, crate_version, value_t, App, Arg, SubCommand};
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs::{self, OpenOptions};
+use std::io;
+use std::path::PathBuf;
+
+/// The journal at path, or an empty one if there's no file there yet
+fn load(path: &PathBuf) -> Result<Vec<JournalEntry>, Box<dyn Error>> {
+    match OpenOptions::new().read(true).open(path) {
+        Ok(fd) => serde_json::from_reader(fd)
+            .map_err(|e| format!("Bad journal in {}: {}", path.display(), e).into()),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(vec![]),
+        Err(e) => Err(e.into()),
+    }
+}
+
+fn save(path: &PathBuf, entries: &[JournalEntry]) -> Result<(), Box<dyn Error>> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let fd = OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(path)?;
+    serde_json::to_writer_pretty(fd, entries)?;
+    Ok(())
+}
+
+/// The strategy card with the given name: a bundled one, or else a JSON file
+fn load_card(name: &str) -> Result<BasicStrategy, Box<dyn Error>> {
+    Ok(match bj_data::dataset(name) {
+        Some(d) => d.strategy_card()?,
+        None => serde_json::from_reader(OpenOptions::new().read(true).open(name)?)?,
+    })
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    logging::init();
+    let matches = App::new(String::from(crate_name!()) + " journal")
+        .author(crate_authors!())
+        .version(crate_version!())
+        .about("Keep a journal of real casino sessions, and see how they add up")
+        .arg(
+            Arg::with_name("journal")
+                .long("journal")
+                .value_name("FILE")
+                .help("Use FILE instead of ~/.config/bj-bin/journal.json"),
+        )
+        .subcommand(
+            SubCommand::with_name("add")
+                .about("Record a session")
+                .arg(
+                    Arg::with_name("date")
+                        .long("date")
+                        .value_name("DATE")
+                        .help("When, e.g. 2026-03-14")
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("venue")
+                        .long("venue")
+                        .value_name("NAME")
+                        .help("Where")
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("rules")
+                        .long("rules")
+                        .value_name("CARD")
+                        .help("Strategy card with the session's rules: a JSON file or a card name"),
+                )
+                .arg(
+                    Arg::with_name("hours")
+                        .long("hours")
+                        .value_name("HOURS")
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("buyin")
+                        .long("buy-in")
+                        .value_name("AMOUNT")
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("result")
+                        .long("result")
+                        .value_name("AMOUNT")
+                        .help("What was won, or lost if negative")
+                        .allow_hyphen_values(true)
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("avgbet")
+                        .long("avg-bet")
+                        .value_name("AMOUNT")
+                        .help("The average bet, to compare the result to the house edge"),
+                ),
+        )
+        .subcommand(SubCommand::with_name("list").about("List every session"))
+        .subcommand(SubCommand::with_name("summary").about("Add up every session"))
+        .get_matches();
+    let path = match matches.value_of("journal") {
+        Some(f) => PathBuf::from(f),
+        None => config_dir()
+            .ok_or("No home directory to keep the journal in. Use --journal")?
+            .join("journal.json"),
+    };
+    let mut entries = load(&path)?;
+    match matches.subcommand() {
+        ("add", Some(m)) => {
+            let rules = m.value_of("rules").map(|r| r.to_string());
+            if let Some(r) = &rules {
+                // make sure the rules can be found when it's time to add things up
+                load_card(r)?;
+            }
+            let entry = JournalEntry {
+                // safe to unwrap because they're required
+                date: m.value_of("date").unwrap().to_string(),
+                venue: m.value_of("venue").unwrap().to_string(),
+                rules,
+                hours: value_t!(m, "hours", f64)?,
+                buy_in: value_t!(m, "buyin", f64)?,
+                result: value_t!(m, "result", f64)?,
+                avg_bet: match m.value_of("avgbet") {
+                    Some(_) => Some(value_t!(m, "avgbet", f64)?),
+                    None => None,
+                },
+            };
+            entry.check()?;
+            println!("{}", entry);
+            entries.push(entry);
+            save(&path, &entries)?;
+        }
+        ("list", _) => {
+            for entry in &entries {
+                println!("{}", entry);
+            }
+        }
+        _ => {
+            let mut edges = HashMap::new();
+            for name in entries.iter().filter_map(|e| e.rules.as_ref()) {
+                if !edges.contains_key(name) {
+                    let bs = load_card(name)?;
+                    edges.insert(name.clone(), Advantage::new(bs.rules.as_ref()).at(0.0));
+                }
+            }
+            let typical = Advantage::new(None).at(0.0);
+            let summary = summarize(&entries, |e| {
+                e.rules
+                    .as_ref()
+                    .and_then(|name| edges.get(name))
+                    .copied()
+                    .unwrap_or(typical)
+            });
+            match summary {
+                Some(summary) => println!("{}", summary),
+                None => println!("No sessions in {}", path.display()),
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,92 @@
+use bj_bin::logging;
+use bj_core::basicstrategy::BasicStrategy;
+use bj_core::kelly::{kelly_bet, kelly_fraction, Advantage};
+use clap::{crate_authors, crate_name, crate_version, value_t, App, Arg};
+use std::fs::OpenOptions;
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    logging::init();
+    let matches = App::new(String::from(crate_name!()) + " kelly")
+        .author(crate_authors!())
+        .version(crate_version!())
+        .about("How much to bet at a true count with the Kelly criterion")
+        .arg(
+            Arg::with_name("bankroll")
+                .short("b")
+                .long("bankroll")
+                .value_name("AMOUNT")
+                .help("The whole bankroll, in any unit. Bets are given in the same unit")
+                .required(true),
+        )
+        .arg(
+            Arg::with_name("truecount")
+                .short("c")
+                .long("true-count")
+                .value_name("TC")
+                .help("The true count")
+                .default_value("0"),
+        )
+        .arg(
+            Arg::with_name("advantage")
+                .short("a")
+                .long("advantage")
+                .value_name("PERCENT")
+                .help("Use this advantage instead of estimating it from the true count")
+                .allow_hyphen_values(true),
+        )
+        .arg(
+            Arg::with_name("variance")
+                .short("v")
+                .long("variance")
+                .value_name("VAR")
+                .help(
+                    "Variance of a hand, in squared bets, instead of working it out from the rules",
+                ),
+        )
+        .arg(
+            Arg::with_name("fraction")
+                .short("f")
+                .long("fraction")
+                .value_name("FRAC")
+                .help("Fraction of the full Kelly bet to bet")
+                .default_value("0.5"),
+        )
+        .arg(Arg::with_name("card").long("card").value_name("FILE").help(
+            "Basic strategy card whose rules to account for: a JSON file or a bundled card name",
+        ))
+        .get_matches();
+    let bankroll = value_t!(matches, "bankroll", f64)?;
+    let fraction = value_t!(matches, "fraction", f64)?;
+    let bs: Option<BasicStrategy> = match matches.value_of("card") {
+        Some(name) => match bj_data::dataset(name) {
+            Some(d) => Some(d.strategy_card()?),
+            None => Some(serde_json::from_reader(
+                OpenOptions::new().read(true).open(name)?,
+            )?),
+        },
+        None => None,
+    };
+    let advantage = Advantage::new(bs.as_ref().and_then(|bs| bs.rules.as_ref()));
+    let variance = if matches.is_present("variance") {
+        value_t!(matches, "variance", f64)?
+    } else {
+        advantage.variance
+    };
+    let adv = if matches.is_present("advantage") {
+        value_t!(matches, "advantage", f64)? / 100.0
+    } else {
+        advantage.at(value_t!(matches, "truecount", f32)?)
+    };
+    println!("Advantage: {:.2}%", adv * 100.0);
+    println!(
+        "Full Kelly: {:.2}% of bankroll ({:.2})",
+        kelly_fraction(adv, variance) * 100.0,
+        kelly_bet(bankroll, adv, variance, 1.0)
+    );
+    println!(
+        "{} Kelly: {:.2}",
+        fraction,
+        kelly_bet(bankroll, adv, variance, fraction)
+    );
+    Ok(())
+}
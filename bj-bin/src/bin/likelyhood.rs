@@ -1,11 +1,17 @@
+use bj_bin::logging;
+use bj_core::basicstrategy::BasicStrategy;
 use bj_core::deck::Deck;
+use bj_core::frequency::cell_frequencies;
 use bj_core::hand::Hand;
+use bj_core::resp::resps_from_buf;
 use bj_core::table::Table;
 use clap::{crate_authors, crate_name, crate_version, value_t, App, Arg};
 use rayon::prelude::*;
+use std::fs::OpenOptions;
 use std::io::{self, Write};
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
+    logging::init();
     let matches = App::new(String::from(crate_name!()) + " likelyhood")
         .author(crate_authors!())
         .version(crate_version!())
@@ -17,7 +23,34 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                 .help("Number of hands to simulate")
                 .default_value("10"),
         )
+        .arg(
+            Arg::with_name("exact")
+                .long("exact")
+                .help("Calculate exact frequencies instead of simulating hands. Blackjacks are left out"),
+        )
+        .arg(
+            Arg::with_name("table")
+                .short("t")
+                .long("table")
+                .value_name("FILE")
+                .help("Calculate exact frequencies, also counting hands reached by hitting as this card says to"),
+        )
         .get_matches();
+    if matches.is_present("exact") || matches.is_present("table") {
+        let bs = match matches.value_of("table") {
+            Some(fname) => Some(BasicStrategy::new(
+                None,
+                Table::new_checked(resps_from_buf(OpenOptions::new().read(true).open(fname)?)?)?,
+            )),
+            None => None,
+        };
+        let (hard, soft, pair) = cell_frequencies(bs.as_ref()).into_values_sorted();
+        let mut fd = io::stdout();
+        subtable(&mut fd, hard, "Hard")?;
+        subtable(&mut fd, soft, "Soft")?;
+        subtable(&mut fd, pair, "Pair")?;
+        return Ok(());
+    }
     let num_hands = value_t!(matches, "num", usize)?;
     let num_threads = 10;
     assert_eq!(num_hands % num_threads, 0);
@@ -44,19 +77,9 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         }
         table
     };
-    let (hard, soft, pair) = table.into_values_sorted();
-    let hard: Vec<f64> = hard
-        .into_iter()
-        .map(|v| v as f64 / num_hands as f64)
-        .collect();
-    let soft: Vec<f64> = soft
-        .into_iter()
-        .map(|v| v as f64 / num_hands as f64)
-        .collect();
-    let pair: Vec<f64> = pair
-        .into_iter()
-        .map(|v| v as f64 / num_hands as f64)
-        .collect();
+    let (hard, soft, pair) = table
+        .map(|_, v| v as f64 / num_hands as f64)
+        .into_values_sorted();
     let mut fd = io::stdout();
     subtable(&mut fd, hard, "Hard")?;
     subtable(&mut fd, soft, "Soft")?;
@@ -0,0 +1,95 @@
+use bj_bin::logging;
+use bj_bin::utils::read_json_lines;
+use bj_core::basicstrategy::BasicStrategy;
+use bj_core::kelly::Advantage;
+use bj_core::luck::{analyze, session_log, SessionHand};
+use clap::{crate_authors, crate_name, crate_version, value_t, App, Arg};
+use std::error::Error;
+use std::fs::OpenOptions;
+use std::io::{BufRead, BufReader};
+use xz2::read::XzDecoder;
+
+type Hands = Box<dyn Iterator<Item = Result<SessionHand, Box<dyn Error>>>>;
+
+/// The hands of the log at fname, read as they're needed. JSON lines if it's named .jsonl, and
+/// CSV otherwise, either compressed if it also ends with .xz.
+fn open_log(fname: &str) -> Result<Hands, Box<dyn Error>> {
+    let fd = OpenOptions::new().read(true).open(fname)?;
+    let xz = fname.ends_with(".xz");
+    if fname.trim_end_matches(".xz").ends_with(".jsonl") {
+        return Ok(Box::new(
+            read_json_lines(fd, xz).map(|h| h.map_err(|e| e.into())),
+        ));
+    }
+    let fd: Box<dyn BufRead> = if xz {
+        Box::new(BufReader::new(XzDecoder::new_multi_decoder(fd)))
+    } else {
+        Box::new(BufReader::new(fd))
+    };
+    Ok(Box::new(session_log(fd)?.map(|h| h.map_err(|e| e.into()))))
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    logging::init();
+    let matches = App::new(String::from(crate_name!()) + " luck")
+        .author(crate_authors!())
+        .version(crate_version!())
+        .about(
+            "Compare a played session's result to what was expected, to tell bad luck from bad \
+            play",
+        )
+        .arg(
+            Arg::with_name("log")
+                .value_name("LOG")
+                .help(
+                    "The session's hands, one per line. As CSV, with a header naming the \
+                    columns: bet and result (net, e.g. -10 for a lost 10 unit bet) are required, \
+                    true_count is optional, and any others are ignored. If named .jsonl, as JSON \
+                    objects like those 'play --history' writes. Either can be xz compressed if \
+                    also named .xz, and is read as it's analyzed, so can be very long",
+                )
+                .required(true),
+        )
+        .arg(
+            Arg::with_name("variance")
+                .short("v")
+                .long("variance")
+                .value_name("VAR")
+                .help(
+                    "Variance of a hand, in squared bets, instead of working it out from the rules",
+                ),
+        )
+        .arg(Arg::with_name("card").long("card").value_name("FILE").help(
+            "Basic strategy card whose rules to account for: a JSON file or a bundled card name",
+        ))
+        .get_matches();
+    let bs: Option<BasicStrategy> = match matches.value_of("card") {
+        Some(name) => match bj_data::dataset(name) {
+            Some(d) => Some(d.strategy_card()?),
+            None => Some(serde_json::from_reader(
+                OpenOptions::new().read(true).open(name)?,
+            )?),
+        },
+        None => None,
+    };
+    let mut advantage = Advantage::new(bs.as_ref().and_then(|bs| bs.rules.as_ref()));
+    if matches.is_present("variance") {
+        advantage.variance = value_t!(matches, "variance", f64)?;
+    }
+    // safe to unwrap because the log is required
+    let hands = open_log(matches.value_of("log").unwrap())?;
+    // analyze the hands up to the first bad one, then give up with its error
+    let mut err = None;
+    let report = analyze(
+        hands.map_while(|h| h.map_err(|e| err = Some(e)).ok()),
+        &advantage,
+    );
+    if let Some(e) = err {
+        return Err(e);
+    }
+    match report {
+        Some(report) => println!("{}", report),
+        None => println!("The log has no hands"),
+    }
+    Ok(())
+}
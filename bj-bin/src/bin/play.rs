@@ -1,28 +1,42 @@
+use bj_bin::chips::Chips;
+use bj_bin::config::{config_arg, Config};
+use bj_bin::hud::Hud;
+use bj_bin::logging;
 use bj_bin::prompt;
-use bj_core::deck::{Card, Deck};
-use bj_core::hand::{Hand, HandError};
-use bj_core::resp::Resp;
+use bj_bin::render::Style;
+use bj_bin::utils::JsonLinesWriter;
+use bj_core::bankroll::Bankroll;
+use bj_core::basicstrategy::rules::SplitState;
+use bj_core::betramp::BetRamp;
+use bj_core::button::GameButton;
+use bj_core::count::DECK_LEN;
+use bj_core::deck::{Card, Deck, DeckError};
+use bj_core::hand::Hand;
+use bj_core::holecard::DealerHand;
+use bj_core::kelly::Advantage;
+use bj_core::luck::{analyze, SessionHand};
+use bj_core::round::{Acted, Round, RoundError};
+use bj_core::settle::{settle, PlayedHand, Settlement};
+use clap::{crate_authors, crate_name, crate_version, App, Arg};
+use std::fs::{File, OpenOptions};
 use std::io::{self, BufRead, BufReader, Write};
 
-fn hand_with_value(hand: &Hand) -> String {
-    format!(
-        "{} ({}{})",
-        hand,
-        hand.value(),
-        if hand.is_soft() { "s" } else { "" },
-    )
-}
-
-fn _prompt_for_bet(
+/// Ask for the round's bet. Returns the bet, or None if the player quit.
+fn prompt_for_bet(
+    bankroll: &Bankroll,
+    aliases: &prompt::Aliases,
     in_buf: &mut impl BufRead,
     out_buf: &mut impl Write,
-) -> io::Result<prompt::Command> {
-    let s = "bet";
+) -> io::Result<Option<f64>> {
+    let s = format!("bet ({} chips)", bankroll.chips);
     loop {
-        if let prompt::Command::Bet(amt) = prompt::prompt(s, in_buf, out_buf)? {
-            break Ok(prompt::Command::Bet(amt));
-        } else {
-            writeln!(out_buf, "Expecting bet. E.g. 'bet 10'")?;
+        match prompt::prompt(&s, aliases, in_buf, out_buf)? {
+            prompt::Command::Quit | prompt::Command::SaveQuit => break Ok(None),
+            prompt::Command::Bet(amt) => match bankroll.covers(f64::from(amt)) {
+                Ok(()) => break Ok(Some(f64::from(amt))),
+                Err(e) => writeln!(out_buf, "{}", e)?,
+            },
+            _ => writeln!(out_buf, "Expecting bet. E.g. 'bet 10'")?,
         }
     }
 }
@@ -30,12 +44,14 @@ fn _prompt_for_bet(
 fn prompt_for_resp(
     p: &Hand,
     d: Card,
+    style: Style,
+    aliases: &prompt::Aliases,
     in_buf: &mut impl BufRead,
     out_buf: &mut impl Write,
 ) -> io::Result<prompt::Command> {
-    let s = &format!("{} / {}", hand_with_value(&p), d);
+    let s = format!("{} / {}", style.hand_with_value(p), style.card(d));
     loop {
-        match prompt::prompt(s, in_buf, out_buf)? {
+        match prompt::prompt(&s, aliases, in_buf, out_buf)? {
             prompt::Command::Save => {
                 writeln!(out_buf, "Nothing to save")?;
                 continue;
@@ -44,142 +60,446 @@ fn prompt_for_resp(
                 writeln!(out_buf, "Not time to bet")?;
                 continue;
             }
+            prompt::Command::Note(_) => {
+                writeln!(out_buf, "Notes can only be taken while training")?;
+                continue;
+            }
             prompt::Command::SaveQuit => break Ok(prompt::Command::Quit),
             cmd => break Ok(cmd),
         }
     }
 }
 
+/// Where the game's cards come from: an infinite deck, or a shoe whose cards are counted as the
+/// player sees them
+struct Shoe {
+    deck: Deck,
+    /// The count and the number of decks, if dealing from a shoe
+    count: Option<(Hud, u8)>,
+    dealt: u16,
+    /// The dealer's hole card, until it's turned over
+    hole: Option<Card>,
+}
+
+impl Shoe {
+    fn new(decks: Option<u8>, ramp: BetRamp) -> Self {
+        let (deck, count) = match decks {
+            None => (Deck::new_infinite(), None),
+            Some(n) => (
+                Deck::with_length(n.into()),
+                Some((Hud::new(n, ramp, bj_data::t1()), n)),
+            ),
+        };
+        Self {
+            deck,
+            count,
+            dealt: 0,
+            hole: None,
+        }
+    }
+
+    /// Deal a card face up, where the player sees it
+    fn draw(&mut self) -> Result<Card, DeckError> {
+        let card = self.deck.draw()?;
+        if let Some((hud, _)) = &mut self.count {
+            hud.observe(card);
+            self.dealt += 1;
+        }
+        Ok(card)
+    }
+
+    /// Deal the dealer's hole card face down. It's seen once reveal_hole() turns it over.
+    fn draw_hole(&mut self) -> Result<Card, DeckError> {
+        let card = self.deck.draw()?;
+        if self.count.is_some() {
+            self.dealt += 1;
+        }
+        self.hole = Some(card);
+        Ok(card)
+    }
+
+    /// Turn over the dealer's hole card, if it's still face down
+    fn reveal_hole(&mut self) {
+        if let (Some(card), Some((hud, _))) = (self.hole.take(), &mut self.count) {
+            hud.observe(card);
+        }
+    }
+
+    /// The count of the cards seen, if dealing from a shoe
+    fn hud(&mut self) -> Option<&mut Hud> {
+        self.count.as_mut().map(|(hud, _)| hud)
+    }
+
+    /// The HUD, if dealing from a shoe and it's shown
+    fn shown_hud(&mut self) -> Option<&mut Hud> {
+        self.hud().filter(|hud| hud.is_shown())
+    }
+
+    /// Get ready for the next round, shuffling if a quarter of the shoe (and at least 20 cards)
+    /// is all that's left, and return the true count it starts at. Always 0 with an infinite deck.
+    fn start_round(&mut self) -> f32 {
+        // the last round's hole card was turned over by the time it ended
+        self.reveal_hole();
+        let (hud, decks) = match &mut self.count {
+            None => return 0.0,
+            Some(c) => c,
+        };
+        let len = u16::from(*decks) * DECK_LEN;
+        if len - self.dealt < (len / 4).max(20) {
+            println!("shuffle");
+            self.deck.shuffle();
+            hud.shuffled();
+            self.dealt = 0;
+        }
+        hud.true_count()
+    }
+}
+
+/// Print what the round won, and how the session so far compares to what the count at the start
+/// of each round said to expect. Adds the round to the history file, if there is one.
+fn print_ticker(
+    settlement: &Settlement,
+    bet: f64,
+    true_count: f32,
+    advantage: &Advantage,
+    session: &mut Vec<SessionHand>,
+    history: &mut Option<JsonLinesWriter<File>>,
+    chips: &mut Option<Chips>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let hand = SessionHand {
+        bet,
+        true_count,
+        result: settlement.net(),
+    };
+    session.push(hand);
+    if let Some(history) = history {
+        history.write(&hand)?;
+        // so the round is kept even if play is killed instead of quit
+        history.flush()?;
+    }
+    // there's at least the round just played
+    let report = analyze(session.iter(), advantage).unwrap();
+    let unit = if chips.is_some() { "chips" } else { "units" };
+    println!(
+        "net {:+} {}; session {:+} {}, theoretical {:+.2} (TC {:+.1})",
+        settlement.net(),
+        unit,
+        report.actual,
+        unit,
+        report.expected,
+        true_count
+    );
+    if let Some(chips) = chips {
+        chips.settle(settlement)?;
+        println!("bankroll {} chips", chips.bankroll.chips);
+    }
+    Ok(())
+}
+
 fn main() -> Result<(), Box<dyn std::error::Error>> {
+    logging::init();
+    let matches = App::new(String::from(crate_name!()) + " play")
+        .author(crate_authors!())
+        .version(crate_version!())
+        .arg(
+            Arg::with_name("nocolor")
+                .long("no-color")
+                .help("Never use color in output"),
+        )
+        .arg(
+            Arg::with_name("decks")
+                .long("decks")
+                .value_name("N")
+                .possible_values(&["1", "2", "4", "6", "8"])
+                .help(
+                    "Deal from a shoe of N decks, counted with HiLo, instead of an infinite deck",
+                ),
+        )
+        .arg(Arg::with_name("hud").long("hud").requires("decks").help(
+            "Start with the heads up display of the count, what to bet, and index plays \
+                     shown. It can be shown or hidden at any time with 'hud'. For practice only",
+        ))
+        .arg(
+            Arg::with_name("betramp")
+                .long("bet-ramp")
+                .value_name("RAMP")
+                .requires("decks")
+                .help(
+                    "What the HUD says to bet at each true count, given as count:units steps \
+                     separated by commas. E.g. 1:1,2:2,3:4,4:6,5:8",
+                ),
+        )
+        .arg(
+            Arg::with_name("history")
+                .long("history")
+                .value_name("FILE")
+                .help(
+                    "Append each round's bet, true count, and result to FILE as a line of JSON, \
+                     for 'luck' to analyze later. Compressed with xz if FILE ends with .xz",
+                ),
+        )
+        .arg(
+            Arg::with_name("bankroll")
+                .long("bankroll")
+                .value_name("CHIPS")
+                .help(
+                    "Play for chips, starting with CHIPS of them, and bet on every round. Every \
+                     bet, double, split, and insurance has to be covered",
+                ),
+        )
+        .arg(
+            Arg::with_name("bankrollfile")
+                .long("bankroll-file")
+                .value_name("FILE")
+                .help(
+                    "Keep the bankroll in FILE between sessions, saving it after every round. A \
+                     new FILE starts with --bankroll chips. Compressed with xz if FILE ends with \
+                     .xz",
+                ),
+        )
+        .arg(config_arg())
+        .get_matches();
+    let aliases = Config::load(matches.value_of("config"))?.aliases()?;
+    let style = Style::detect(matches.is_present("nocolor"));
     let mut input = BufReader::new(io::stdin());
     let mut output = io::stdout();
-    let mut working_hands: Vec<Hand> = vec![];
-    let mut finished_hands: Vec<Hand> = vec![];
-    let mut deck = Deck::new_infinite();
+    let mut chips = Chips::load(
+        matches.value_of("bankrollfile"),
+        matches.value_of("bankroll"),
+    )?;
+    if chips.is_none() && matches.is_present("bankrollfile") {
+        return Err("A new bankroll file needs --bankroll CHIPS".into());
+    }
+    if let Some(chips) = &chips {
+        println!("bankroll: {}", chips.bankroll);
+    }
+    // there are no table rules here, so expect what a typical shoe game gives
+    let advantage = Advantage::new(None);
+    let mut session = vec![];
+    let mut history = match matches.value_of("history") {
+        Some(fname) => Some(JsonLinesWriter::new(
+            OpenOptions::new().create(true).append(true).open(fname)?,
+            fname.ends_with(".xz"),
+        )),
+        None => None,
+    };
+    let ramp = match matches.value_of("betramp") {
+        Some(s) => s.parse()?,
+        None => BetRamp::default(),
+    };
+    let mut shoe = Shoe::new(matches.value_of("decks").map(|d| d.parse().unwrap()), ramp);
+    if matches.is_present("hud") {
+        // safe to unwrap because --hud requires --decks
+        shoe.hud().unwrap().toggle();
+    }
     // play forever
     loop {
-        // make sure no left over hands
-        assert!(working_hands.is_empty());
-        assert!(finished_hands.is_empty());
-        //// Disabled bet stuff
-        //let bet = match prompt_for_bet(&mut input, &mut output)? {
-        //    prompt::Command::Bet(amt) => amt,
-        //    _ => unreachable!(),
-        //};
-        //println!("{:?}", bet);
-        // generate hands
-        // TODO handle dealer up 10 or up ace
-        let dealer = deck.draw()?;
-        working_hands.push(Hand::new(&[deck.draw()?, deck.draw()?]));
-        // keep looping while the player has an unfinished hand and we need a response from them.
-        // They will have more than one hand if they split, and if starting out with 2+ hands is
-        // ever implemented without updating this comment.
-        while let Some(mut hand) = working_hands.pop() {
-            // prompt them for their move. They can quit or they can stand/hit/etc.
-            // prompt_for_resp() will not return any other type of command
-            let resp = match prompt_for_resp(&hand, dealer, &mut input, &mut output)? {
-                prompt::Command::Quit => return Ok(()),
-                prompt::Command::Resp(r) => r,
-                _ => unreachable!(),
-            };
-            // player wants to stand/hit/etc. this hand. So handle that.
-            match resp {
-                Resp::DoubleElseHit | Resp::DoubleElseStand => {
-                    // TODO need to add to the bet if betting is implemented
-                    if !hand.can_double() {
-                        println!("Cannot double {}", hand_with_value(&hand));
-                        working_hands.push(hand);
-                        continue;
-                    }
-                    hand.push(deck.draw()?);
-                    println!(
-                        "dobule {}{}",
-                        hand_with_value(&hand),
-                        if hand.is_bust() { " bust" } else { "" }
-                    );
-                    finished_hands.push(hand);
-                }
-                Resp::Split => {
-                    // TODO need to have two bets if betting is implemented
-                    let (c1, c2) = match hand.split() {
-                        Err(HandError::CannotSplit(h)) => {
-                            println!("Cannot split {}", hand_with_value(&h));
-                            working_hands.push(h);
-                            continue;
-                        }
-                        Ok((c1, c2)) => (c1, c2),
-                        _ => unreachable!(),
-                    };
-                    working_hands.push(Hand::new(&[c2, deck.draw()?]));
-                    working_hands.push(Hand::new(&[c1, deck.draw()?]));
+        // without a bankroll, every round is a 1 unit bet
+        let bet = match &chips {
+            None => 1.0,
+            Some(c) if c.bankroll.is_broke() => {
+                println!("Out of chips");
+                c.print_session();
+                return Ok(());
+            }
+            Some(c) => match prompt_for_bet(&c.bankroll, &aliases, &mut input, &mut output)? {
+                Some(bet) => bet,
+                None => {
+                    c.print_session();
+                    return Ok(());
                 }
-                Resp::Stand => {
-                    println!("stand {}", hand_with_value(&hand));
-                    finished_hands.push(hand);
+            },
+        };
+        let true_count = shoe.start_round();
+        if let Some(hud) = shoe.shown_hud() {
+            println!("HUD: {}", hud.line());
+        }
+        // deal the way a dealer does: player, dealer up, player, dealer hole. There are no table
+        // rules here, so the dealer peeks like in most shoe games.
+        let (p1, up) = (shoe.draw()?, shoe.draw()?);
+        let (p2, hole) = (shoe.draw()?, shoe.draw_hole()?);
+        let player = Hand::new(&[p1, p2]);
+        let dealer = DealerHand::new(up, hole);
+        let mut insured = false;
+        if dealer.offers_insurance() {
+            if let Some(alert) = shoe.shown_hud().and_then(|hud| hud.insurance_alert()) {
+                println!("HUD: {}", alert);
+            }
+            let question = format!(
+                "{} / {} {}",
+                style.hand_with_value(&player),
+                style.card(up),
+                if player.is_blackjack() {
+                    "Even money?"
+                } else {
+                    "Insurance?"
                 }
-                Resp::Hit => {
-                    hand.push(deck.draw()?);
-                    if hand.is_bust() {
-                        println!("{} bust", hand_with_value(&hand));
-                        finished_hands.push(hand);
-                    } else {
-                        working_hands.push(hand);
+            );
+            insured = match prompt::prompt_yes_no(&question, &mut input, &mut output)? {
+                Some(yes) => yes,
+                None => {
+                    if let Some(c) = &mut chips {
+                        // the bet is already on the table
+                        c.forfeit(bet)?;
+                        c.print_session();
                     }
-                }
-                Resp::SurrenderElseHit | Resp::SurrenderElseStand | Resp::SurrenderElseSplit => {
-                    unimplemented!()
+                    return Ok(());
                 }
             };
-            // There must not be anything else at the end of this loop.
-            //
-            // Okay maybe there can be. But the preceeding match statement on the player's
-            // hit/stand/etc. choice sometimes continues back to the beginning of this while loop
-            // and sometimes falls through to here. As of now there is nothing here so the
-            // difference is inconsequential. But if something is added here, maybe continues
-            // should be added/removed.
         }
-        // Done with player. Have the dealer deal themself build their hand
-        assert!(working_hands.is_empty());
-        let mut dealer = Hand::new(&[dealer, deck.draw()?]);
-        // The implemented game is hit soft 17
-        while dealer.value() < 16 || (dealer.value() == 17 && dealer.is_soft()) {
-            println!("dealer {}", hand_with_value(&dealer));
-            dealer.push(deck.draw()?);
+        if let (true, Some(c)) = (insured, &chips) {
+            if let Err(e) = c.bankroll.covers(bet * 1.5) {
+                println!("Cannot insure: {}", e);
+                insured = false;
+            }
         }
-        println!("dealer {}", hand_with_value(&dealer));
-        // let the player know what happened
-        for hand in finished_hands.drain(0..) {
-            if hand.is_bust() {
-                println!("bust {}", hand_with_value(&hand));
-            } else if dealer.is_bust() {
-                assert!(!hand.is_bust());
-                println!("dealer bust; win {}", hand_with_value(&hand));
-            } else if hand.value() > dealer.value() {
-                assert!(!hand.is_bust());
-                assert!(!dealer.is_bust());
+        let insurance = if insured { bet / 2.0 } else { 0.0 };
+        // settle a round that ended before the player acted
+        let opening = [PlayedHand::new(player.clone(), SplitState::default())];
+        let peeked = dealer.peeks(None);
+        if peeked {
+            if dealer.is_blackjack() {
+                shoe.reveal_hole();
                 println!(
-                    "win {} over dealer {}",
-                    hand_with_value(&hand),
-                    hand_with_value(&dealer),
-                );
-            } else if hand.value() == dealer.value() {
-                assert!(!hand.is_bust());
-                assert!(!dealer.is_bust());
-                println!(
-                    "push {} with dealer {}",
-                    hand_with_value(&hand),
-                    hand_with_value(&dealer),
+                    "dealer blackjack {}",
+                    style.hand_with_value(&dealer.reveal())
                 );
+                let settlement = settle(bet, &opening, insurance, &dealer.reveal(), None);
+                if insured && player.is_blackjack() {
+                    println!("even money {}", style.hand_with_value(&player));
+                    print_ticker(
+                        &settlement,
+                        bet,
+                        true_count,
+                        &advantage,
+                        &mut session,
+                        &mut history,
+                        &mut chips,
+                    )?;
+                    continue;
+                }
+                if insured {
+                    println!("insurance pays 2:1");
+                }
+                if player.is_blackjack() {
+                    println!("push {}", style.hand_with_value(&player));
+                } else {
+                    println!("lose {}", style.hand_with_value(&player));
+                }
+                print_ticker(
+                    &settlement,
+                    bet,
+                    true_count,
+                    &advantage,
+                    &mut session,
+                    &mut history,
+                    &mut chips,
+                )?;
+                continue;
+            }
+            println!("dealer checks: no blackjack");
+            if insured && !player.is_blackjack() {
+                println!("insurance lost");
+            }
+        }
+        if player.is_blackjack() {
+            if insured {
+                println!("even money {}", style.hand_with_value(&player));
+            } else if peeked || !dealer.is_blackjack() {
+                println!("blackjack! win {}", style.hand_with_value(&player));
             } else {
-                assert!(!hand.is_bust());
-                assert!(!dealer.is_bust());
-                assert!(hand.value() < dealer.value());
-                println!(
-                    "lose {} to dealer {}",
-                    hand_with_value(&hand),
-                    hand_with_value(&dealer),
-                );
+                println!("push {}", style.hand_with_value(&dealer.reveal()));
+            }
+            let settlement = settle(bet, &opening, insurance, &dealer.reveal(), None);
+            print_ticker(
+                &settlement,
+                bet,
+                true_count,
+                &advantage,
+                &mut session,
+                &mut history,
+                &mut chips,
+            )?;
+            continue;
+        }
+        let up = dealer.up();
+        // there are no table rules here, so anything the hand allows goes and the dealer hits
+        // soft 17
+        let mut round = Round::new(player, dealer, None);
+        // keep looping while the player has an unfinished hand and we need a response from them.
+        // They will have more than one hand if they split.
+        while let Some((hand, _)) = round.current() {
+            let hand = hand.clone();
+            if let Some(hud) = shoe.shown_hud() {
+                println!("HUD: {}", hud.line());
+                if let Some(alert) = hud.alert(&hand, up) {
+                    println!("HUD: {}", alert);
+                }
             }
+            // prompt them for their move. They can quit, show or hide the HUD, or they can
+            // stand/hit/etc. prompt_for_resp() will not return any other type of command
+            let resp = match prompt_for_resp(&hand, up, style, &aliases, &mut input, &mut output)? {
+                prompt::Command::Quit => {
+                    if let Some(c) = &mut chips {
+                        // the chips on the table stay there
+                        c.forfeit(round.staked(bet) + insurance)?;
+                        c.print_session();
+                    }
+                    return Ok(());
+                }
+                prompt::Command::Resp(r) => r,
+                prompt::Command::Hud => {
+                    match shoe.hud().map(|hud| hud.toggle()) {
+                        Some(true) => {}
+                        Some(false) => println!("HUD hidden"),
+                        None => println!("The HUD needs a shoe to count, see --decks"),
+                    }
+                    continue;
+                }
+                _ => unreachable!(),
+            };
+            // doubling and splitting put up another bet. Illegal actions are left to the round
+            // to refuse.
+            let action = GameButton::from(resp);
+            let raises = [GameButton::Double, GameButton::Split].contains(&action)
+                && round.legal_actions().contains(&action);
+            if let (true, Some(c)) = (raises, &chips) {
+                if let Err(e) = c.bankroll.covers(round.staked(bet) + bet + insurance) {
+                    println!("Cannot {}: {}", action.to_string().to_lowercase(), e);
+                    continue;
+                }
+            }
+            // player wants to stand/hit/etc. this hand. Hits that don't bust and splits leave
+            // them with a hand to play, which the next prompt shows.
+            let played = match round.act(action, || shoe.draw()) {
+                Ok(Acted::Playing) => continue,
+                Ok(Acted::Finished(played)) => played,
+                Err(RoundError::Illegal(action, hand)) => {
+                    println!(
+                        "Cannot {} {}",
+                        action.to_string().to_lowercase(),
+                        style.hand_with_value(&hand)
+                    );
+                    continue;
+                }
+                Err(e) => return Err(e.into()),
+            };
+            println!("{}", style.played(&played));
+        }
+        // Done with player. Have the dealer turn over the hole card and build their hand
+        shoe.reveal_hole();
+        let end = round.finish(bet, insurance, || shoe.draw())?;
+        for line in style.round_end(&end) {
+            println!("{}", line);
         }
+        print_ticker(
+            &end.settlement,
+            bet,
+            true_count,
+            &advantage,
+            &mut session,
+            &mut history,
+            &mut chips,
+        )?;
     }
 }
@@ -1,11 +1,18 @@
+use bj_bin::logging;
+use bj_bin::utils::read_maybexz;
 use bj_core::basicstrategy::BasicStrategy;
-use bj_core::rendertable::{HTMLTableRenderer, HTMLTableRendererOpts, TXTTableRenderer};
+use bj_core::playstats::PlayStats;
+use bj_core::rendertable::{
+    HTMLTableRenderer, HTMLTableRendererOpts, SVGTableRenderer, TXTTableRenderer, TextTableRenderer,
+};
+use bj_core::table::{parse_cells, Table};
 use clap::{crate_authors, crate_name, crate_version, App, Arg};
 use std::error::Error;
 use std::fs::OpenOptions;
 use std::io::{BufWriter, Write};
 
 fn main() -> Result<(), Box<dyn Error>> {
+    logging::init();
     let matches = App::new(String::from(crate_name!()) + " render")
         .author(crate_authors!())
         .version(crate_version!())
@@ -28,10 +35,62 @@ fn main() -> Result<(), Box<dyn Error>> {
                 .short("f")
                 .long("format")
                 .takes_value(true)
-                .possible_values(&["html", "txt"])
-                .required(true),
+                .possible_values(&["html", "txt", "text", "svg"])
+                .required(true)
+                .help(
+                    "txt is the strategy card file format. text is an aligned grid with a \
+                    legend, for reading or pasting somewhere",
+                ),
+        )
+        .arg(
+            Arg::with_name("heatmap")
+                .long("heatmap")
+                .value_name("PLAY_STATS")
+                .help(
+                    "Instead of the card, render the accuracy in these play stats as a heatmap. \
+                    SVG only",
+                ),
+        )
+        .arg(
+            Arg::with_name("player")
+                .long("player")
+                .value_name("ROWS")
+                .multiple(true)
+                .number_of_values(1)
+                .help(
+                    "Only render these rows of the card, e.g. 'Hard 12-16', 'Soft 18', '8,8', or \
+                    just '12-16' for hard totals. Can be given more than once. TXT only",
+                ),
+        )
+        .arg(
+            Arg::with_name("dealer")
+                .long("dealer")
+                .value_name("UPCARDS")
+                .multiple(true)
+                .number_of_values(1)
+                .help("Only render these upcards, e.g. '2-6' or '7-9,A'. TXT only"),
         )
         .get_matches();
+    if let Some(stats_fname) = matches.value_of("heatmap") {
+        if matches.value_of("format") != Some("svg") {
+            return Err("--heatmap needs --format svg".into());
+        }
+        let stats: Table<PlayStats> = read_maybexz(
+            OpenOptions::new().read(true).open(stats_fname)?,
+            stats_fname.ends_with(".xz"),
+        )?;
+        let mut fd = BufWriter::new(
+            OpenOptions::new()
+                .write(true)
+                .create(true)
+                .truncate(true)
+                // safe to unwrap because --output has a default
+                .open(matches.value_of("output").unwrap())?,
+        );
+        SVGTableRenderer::render_heatmap(&mut fd, &stats)?;
+        fd.flush()?;
+        return Ok(());
+    }
     let bs_card: BasicStrategy = serde_json::from_reader(
         OpenOptions::new()
             .read(true)
@@ -49,10 +108,26 @@ fn main() -> Result<(), Box<dyn Error>> {
     let html_opts = HTMLTableRendererOpts {
         incl_bs_rules: true,
         cell_onclick_cb: None,
+        notes: None,
+        visibility: None,
+    };
+    let region = matches.is_present("player") || matches.is_present("dealer");
+    if region && matches.value_of("format") != Some("txt") {
+        return Err("--player and --dealer need --format txt".into());
+    }
+    let specs = |name| -> Vec<&str> {
+        matches
+            .values_of(name)
+            .map(|v| v.collect())
+            .unwrap_or_default()
     };
+    let cells = parse_cells(&specs("player"), &specs("dealer"))?;
     match matches.value_of("format").unwrap() {
+        "txt" if region => TXTTableRenderer::render_cells(&mut fd, &bs_card, &cells)?,
         "html" => HTMLTableRenderer::render(&mut fd, &bs_card, html_opts)?,
         "txt" => TXTTableRenderer::render(&mut fd, &bs_card)?,
+        "text" => TextTableRenderer::render(&mut fd, &bs_card)?,
+        "svg" => SVGTableRenderer::render(&mut fd, &bs_card)?,
         _ => unimplemented!(),
     };
     fd.flush()?;
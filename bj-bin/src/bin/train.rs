@@ -1,74 +1,355 @@
+use bj_bin::chips::{bankroll_fname, Chips};
+use bj_bin::config::{config_arg, Config};
+use bj_bin::logging;
+use bj_bin::practice::Practice;
 use bj_bin::prompt;
+use bj_bin::render::Style;
 use bj_bin::utils::{create_if_not_exist, read_maybexz, write_maybexz};
+use bj_core::basicstrategy::rules::Rules;
+use bj_core::basicstrategy::BasicStrategy;
+use bj_core::button::GameButton;
+use bj_core::confidence::{Percent, Z95};
+use bj_core::count::{CountSystem, HiLo};
 use bj_core::deck::{Card, Deck};
+use bj_core::drillpack::DrillPack;
 use bj_core::hand::Hand;
+use bj_core::holecard::DealerHand;
+use bj_core::notes::Notes;
+use bj_core::odds;
+use bj_core::placement::{self, Placement};
 use bj_core::playstats::PlayStats;
-use bj_core::resp::resps_from_buf;
-use bj_core::table::Table;
-use bj_core::utils::rand_next_hand;
+use bj_core::practice::PracticeTime;
+use bj_core::resp::{partial_resps_from_buf, Resp};
+use bj_core::round::{Acted, Round, RoundError};
+use bj_core::table::{dealer_card_from_desc, player_hand_from_desc, Table};
+use bj_core::trainer::{HandSource, Mode, Trainer, TrainerError};
 use clap::{arg_enum, crate_authors, crate_name, crate_version, value_t, App, Arg};
-use std::fmt;
+use std::convert::TryFrom;
 use std::fs::OpenOptions;
 use std::io::{self, BufRead, BufReader, Write};
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
 
 fn def_playstats_table() -> Table<PlayStats> {
     const NUM_CELLS: usize = 10 * (17 + 9 + 10);
     Table::new(vec![PlayStats::new(); NUM_CELLS]).unwrap()
 }
 
-fn print_game_stats(stats: &Table<PlayStats>) {
-    let (num_seen, num_correct) = stats
-        .values()
-        .fold((0, 0), |acc, s| (acc.0 + s.seen(), acc.1 + s.correct()));
+/// e.g. "92% ± 4% of 200 recorded games correct", where ± is a 95% confidence interval
+fn print_accuracy(stat: PlayStats, what: &str) {
+    match stat.accuracy(Z95) {
+        Some(acc) => println!("{} of {} {} correct", Percent(acc), stat.seen(), what),
+        None => println!("No {} yet", what),
+    }
+}
+
+fn print_game_stats(trainer: &Trainer) {
+    print_accuracy(
+        trainer
+            .stats()
+            .values()
+            .fold(PlayStats::new(), |acc, s| acc + *s),
+        "recorded games",
+    );
+    if let Some(acc) = trainer.weighted_accuracy() {
+        println!(
+            "{:.1}% weighted by how often each hand comes up",
+            acc * 100.0
+        );
+    }
+}
+
+fn print_practice(practice: &Practice) {
     println!(
-        "{:.2}% of {} recorded games correct",
-        num_correct as f32 / num_seen as f32 * 100.0,
-        num_seen
+        "{} practiced today, {} in all",
+        PracticeTime(practice.today()),
+        PracticeTime(practice.total())
     );
 }
 
-enum RandType {
-    Uniform,
-    Weighted,
+/// The bundled strategy card with the given name, or else the table of moves in the given file
+fn load_table(name: &str) -> Result<BasicStrategy, Box<dyn std::error::Error>> {
+    match bj_data::dataset(name) {
+        Some(d) => Ok(d.strategy_card()?),
+        None => Ok(BasicStrategy::from_partial(
+            None,
+            Table::new(partial_resps_from_buf(
+                OpenOptions::new().read(true).open(name)?,
+            )?)?,
+        )?),
+    }
 }
 
-impl fmt::Display for RandType {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let s = match self {
-            RandType::Uniform => "UR",
-            RandType::Weighted => "WR",
-        };
-        write!(f, "{}", s)
+/// The bundled drill pack with the given name, or else the pack in the given JSON file
+fn load_pack(name: &str) -> Result<DrillPack, Box<dyn std::error::Error>> {
+    match bj_data::dataset(name) {
+        Some(d) => Ok(d.drill_pack()?),
+        None => Ok(serde_json::from_reader(
+            OpenOptions::new().read(true).open(name)?,
+        )?),
+    }
+}
+
+/// e.g. "Stiffs vs 9, 10, A: 12/14 correct of 30", and whether the run was passed once it's over
+fn print_pack_progress(trainer: &Trainer) {
+    let (pack, progress) = match (trainer.pack(), trainer.pack_progress()) {
+        (Some(pack), Some(progress)) => (pack, progress),
+        _ => return,
+    };
+    println!(
+        "{}: {}/{} correct of {}",
+        pack.name, progress.correct, progress.played, progress.scoring.hands
+    );
+    match progress.passed() {
+        Some(true) => println!("Pack passed! Keep going to start another run"),
+        Some(false) => println!(
+            "Pack not passed: {}% needed. Keep going to start another run",
+            progress.scoring.pass_percent
+        ),
+        None => {}
+    }
+}
+
+fn source_label(source: HandSource) -> &'static str {
+    match source {
+        HandSource::Uniform => "UR",
+        HandSource::Weighted => "WR",
+    }
+}
+
+/// Count the hand about to be dealt, and choose how to deal it: uniformally at random every
+/// uni_rand_every hands, otherwise weighted by play stats
+fn set_next_source(trainer: &mut Trainer, hand_count: &mut u64, uni_rand_every: u64) {
+    *hand_count += 1;
+    if *hand_count == uni_rand_every {
+        *hand_count = 0;
+        trainer.set_source(HandSource::Uniform);
+    } else {
+        trainer.set_source(HandSource::Weighted);
     }
 }
 
+/// What to prompt with for a hand, e.g. "(WR 3/4) [T♣ 6♦] / 10♠"
+fn hand_prompt(p: &Hand, d: Card, source: HandSource, stat: PlayStats, style: Style) -> String {
+    format!(
+        "({} {}/{}) {} / {}",
+        source_label(source),
+        stat.correct(),
+        stat.seen(),
+        style.hand(p),
+        style.card(d)
+    )
+}
+
 fn prompt(
-    p: &Hand,
-    d: Card,
-    rand_type: RandType,
-    stat: PlayStats,
+    s: &str,
+    aliases: &prompt::Aliases,
     in_buf: &mut impl BufRead,
     out_buf: &mut impl Write,
 ) -> io::Result<prompt::Command> {
-    let s = &format!(
-        "({} {}/{}) {} / {}",
-        rand_type,
-        stat.correct(),
-        stat.seen(),
-        p,
-        d
-    );
     loop {
-        match prompt::prompt(s, in_buf, out_buf)? {
+        match prompt::prompt(s, aliases, in_buf, out_buf)? {
             prompt::Command::Bet(_) => {
                 writeln!(out_buf, "Cannot bet")?;
                 continue;
             }
+            prompt::Command::Hud => {
+                writeln!(out_buf, "The HUD is only for play")?;
+                continue;
+            }
             cmd => break Ok(cmd),
         }
     }
 }
 
+fn prompt_for_count(
+    aliases: &prompt::Aliases,
+    in_buf: &mut impl BufRead,
+    out_buf: &mut impl Write,
+) -> io::Result<prompt::Command> {
+    loop {
+        match prompt::prompt("running count", aliases, in_buf, out_buf)? {
+            prompt::Command::SaveQuit | prompt::Command::Quit => break Ok(prompt::Command::Quit),
+            prompt::Command::Num(n) => match i16::try_from(n) {
+                Ok(n) => break Ok(prompt::Command::Num(n.into())),
+                Err(_) => {
+                    writeln!(out_buf, "No running count gets that far from 0")?;
+                    continue;
+                }
+            },
+            _ => {
+                writeln!(out_buf, "Give either a number or 'quit'")?;
+                continue;
+            }
+        }
+    }
+}
+
+/// Play the rest of a hand the player was just graded on, starting with the response they gave,
+/// against a dealer hole card drawn now. Later decisions aren't graded. Every card dealt counts
+/// toward the running count. The hand is for bet chips if the player has them, and for a unit
+/// otherwise. Returns the command the player quit with, if they quit partway.
+#[allow(clippy::too_many_arguments)]
+fn play_full_hand(
+    player: Hand,
+    up: Card,
+    resp: Resp,
+    rules: Option<Rules>,
+    bet: f64,
+    chips: &mut Option<Chips>,
+    running_count: &mut i16,
+    aliases: &prompt::Aliases,
+    style: Style,
+) -> Result<Option<prompt::Command>, Box<dyn std::error::Error>> {
+    let hilo = HiLo::new();
+    let mut deck = Deck::new_infinite();
+    // the hand was asked about because the dealer doesn't have blackjack, if they peeked for it
+    let dealer = loop {
+        let dealer = DealerHand::new(up, deck.draw()?);
+        if !(dealer.peeks(rules.as_ref()) && dealer.is_blackjack()) {
+            break dealer;
+        }
+    };
+    let mut shoe = || {
+        let card = deck.draw()?;
+        *running_count += hilo.card_value(card);
+        Ok(card)
+    };
+    let mut round = Round::new(player, dealer, rules);
+    let mut resp = Some(resp);
+    while let Some((hand, _)) = round.current() {
+        let hand = hand.clone();
+        let resp = match resp.take() {
+            Some(resp) => resp,
+            None => match prompt(
+                &format!("{} / {}", style.hand_with_value(&hand), style.card(up)),
+                aliases,
+                &mut BufReader::new(io::stdin()),
+                &mut io::stdout(),
+            )? {
+                cmd @ prompt::Command::Quit | cmd @ prompt::Command::SaveQuit => {
+                    // the chips on the table stay there
+                    if let Some(c) = chips {
+                        c.forfeit(round.staked(bet))?;
+                    }
+                    return Ok(Some(cmd));
+                }
+                prompt::Command::Resp(r) => r,
+                _ => {
+                    println!("Finish playing the hand first");
+                    continue;
+                }
+            },
+        };
+        // doubling and splitting put up another bet. Illegal actions are left to the round to
+        // refuse.
+        let action = GameButton::from(resp);
+        let raises = [GameButton::Double, GameButton::Split].contains(&action)
+            && round.legal_actions().contains(&action);
+        if let (true, Some(c)) = (raises, &*chips) {
+            if let Err(e) = c.bankroll.covers(round.staked(bet) + bet) {
+                println!("Cannot {}: {}", action.to_string().to_lowercase(), e);
+                continue;
+            }
+        }
+        match round.act(action, &mut shoe) {
+            Ok(Acted::Playing) => {}
+            Ok(Acted::Finished(played)) => println!("{}", style.played(&played)),
+            Err(RoundError::Illegal(action, hand)) => println!(
+                "Cannot {} {}",
+                action.to_string().to_lowercase(),
+                style.hand_with_value(&hand)
+            ),
+            Err(e) => return Err(e.into()),
+        }
+    }
+    let end = round.finish(bet, 0.0, &mut shoe)?;
+    // turned over once the dealer played
+    *running_count += hilo.card_value(dealer.hole());
+    for line in style.round_end(&end) {
+        println!("{}", line);
+    }
+    match chips {
+        Some(c) => {
+            c.settle(&end.settlement)?;
+            println!(
+                "net {:+} chips; bankroll {} chips",
+                end.settlement.net(),
+                c.bankroll.chips
+            );
+        }
+        None => println!("net {:+} units", end.settlement.net()),
+    }
+    Ok(None)
+}
+
+/// Ask a placement quiz of the given number of hands, then add it to the trainer's stats with an
+/// estimate for every hand (see bj_core::placement). Returns false if the player quit partway.
+fn placement_quiz(
+    trainer: &mut Trainer,
+    questions: usize,
+    aliases: &prompt::Aliases,
+    style: Style,
+) -> Result<bool, Box<dyn std::error::Error>> {
+    let mut quiz = Placement::new(questions);
+    println!(
+        "Placement quiz: {} hands to find what you already know",
+        questions
+    );
+    while let Some(desc) = quiz.next_question() {
+        let player = player_hand_from_desc(desc)?;
+        let dealer = dealer_card_from_desc(desc)?;
+        let (answered, total) = quiz.progress();
+        let question = format!(
+            "(placement {}/{}) {} / {}",
+            answered + 1,
+            total,
+            style.hand(&player),
+            style.card(dealer)
+        );
+        let resp = match prompt(
+            &question,
+            aliases,
+            &mut BufReader::new(io::stdin()),
+            &mut io::stdout(),
+        )? {
+            prompt::Command::Resp(r) => r,
+            prompt::Command::Quit | prompt::Command::SaveQuit => return Ok(false),
+            _ => {
+                println!("Give a response like 'hit', or 'quit'");
+                continue;
+            }
+        };
+        let correct = placement::grade(trainer.strategy(), desc, resp.into())?;
+        quiz.answer(desc, correct);
+        if correct {
+            println!("{}", style.correct("correct"));
+        } else {
+            println!(
+                "{}. Should {} on {}",
+                style.wrong("wrong"),
+                trainer.strategy().resp(&player, dealer)?,
+                desc.label()
+            );
+        }
+    }
+    let mut stats = trainer.stats().clone();
+    quiz.apply(&mut stats);
+    let streak = trainer.streak();
+    trainer.set_stats(stats, streak);
+    Ok(true)
+}
+
+fn print_count_stats(stat: PlayStats) {
+    print_accuracy(stat, "count quizzes");
+}
+
 arg_enum! {
     #[derive(PartialEq, Debug)]
     enum StatsSaveStrat {
@@ -78,6 +359,7 @@ arg_enum! {
 }
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
+    logging::init();
     let matches = App::new(String::from(crate_name!()) + " sim")
         .author(crate_authors!())
         .version(crate_version!())
@@ -86,8 +368,13 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                 .short("t")
                 .long("table")
                 .value_name("FILE")
-                .help("Table of ideal moves")
-                .required(true),
+                .help(
+                    "Table of ideal moves. Cells marked '-' aren't learned yet, and are never \
+                    dealt. Or a bundled card's name, like t1, or 2d-s17-das-ls for 2 decks where \
+                    the dealer stands on soft 17, with double after split and late surrender \
+                    (1d, 2d, 4d; h17, s17; das, nodas; -ls or nothing)",
+                )
+                .required_unless("checkodds"),
         )
         .arg(
             Arg::with_name("stats")
@@ -106,6 +393,13 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                 .value_name("WHEN")
                 .help("When to save play statistics to disk"),
         )
+        .arg(
+            Arg::with_name("notes")
+                .long("notes")
+                .value_name("FILE")
+                .help("Read/write your notes on hands from the file. Use 'note <text>' to take a note on the current hand.")
+                .default_value("notes.json"),
+        )
         .arg(
             Arg::with_name("unirand")
             .long("rand-every")
@@ -113,17 +407,150 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
             .value_name("CNT")
             .help("Every CNT rolls, generate hand uniformally at random as opposed to weighted by play statistics. 0 means never, 1 means always.")
         )
+        .arg(
+            Arg::with_name("countevery")
+                .long("count-every")
+                .default_value("0")
+                .value_name("K")
+                .help("Keep a HiLo running count of every card shown and quiz on it every K hands. 0 means never."),
+        )
+        .arg(
+            Arg::with_name("hitstand")
+                .long("hit-stand")
+                .help("Beginner mode: only hit and stand are allowed, and hands are graded as if doubling, splitting, and surrendering aren't possible"),
+        )
+        .arg(
+            Arg::with_name("nopairs")
+                .long("no-pairs")
+                .requires("hitstand")
+                .help("With --hit-stand, never deal pairs"),
+        )
+        .arg(
+            Arg::with_name("nosofts")
+                .long("no-softs")
+                .requires("hitstand")
+                .help("With --hit-stand, never deal soft hands"),
+        )
+        .arg(
+            Arg::with_name("upcard")
+                .long("upcard")
+                .value_name("CARD")
+                .possible_values(&["2", "3", "4", "5", "6", "7", "8", "9", "10", "A"])
+                .help("Drill one column of the card: deal every hand against this dealer upcard"),
+        )
+        .arg(
+            Arg::with_name("frequency")
+                .long("frequency-weighting")
+                .help("Deal hands that come up often in real play more often"),
+        )
+        .arg(
+            Arg::with_name("playsplits")
+                .long("play-splits")
+                .help("After a correct split, play out each of the hands it makes"),
+        )
+        .arg(
+            Arg::with_name("fullhand")
+                .long("full-hand")
+                .conflicts_with("playsplits")
+                .help(
+                    "After each answer, play the rest of the hand against the dealer and see how \
+                     it ends. Only the first decision is graded",
+                ),
+        )
+        .arg(
+            Arg::with_name("bankroll")
+                .long("bankroll")
+                .value_name("CHIPS")
+                .requires("fullhand")
+                .help(
+                    "With --full-hand, play the hands out for chips, starting with CHIPS of them. \
+                     Bet with 'bet N', which stands until it's changed; until then it's 1 chip. \
+                     The bankroll is kept next to the stats file, e.g. \
+                     play-stats.bankroll.json.xz, and played for again with --full-hand whether \
+                     or not this is given",
+                ),
+        )
+        .arg(
+            Arg::with_name("multicard")
+                .long("multi-card")
+                .help("Sometimes deal hard and soft hands as three or four cards"),
+        )
+        .arg(
+            Arg::with_name("refresh")
+                .long("refresh-after")
+                .value_name("DAYS")
+                .help("Deal hands not seen in more than DAYS days as if they were new"),
+        )
+        .arg(
+            Arg::with_name("evenmoney")
+                .long("even-money")
+                .help("Ask whether to take even money on blackjacks against an ace"),
+        )
+        .arg(
+            Arg::with_name("pack")
+                .long("pack")
+                .value_name("NAME|FILE")
+                .help(
+                    "Play a drill pack: one of the bundled packs (stiffs, soft-doubles) or a pack \
+                    in a JSON file. Every hand is dealt from the pack, whatever the other dealing \
+                    options say",
+                ),
+        )
+        .arg(
+            Arg::with_name("placement")
+                .long("placement")
+                .value_name("HANDS")
+                .help(
+                    "Start with a placement quiz of HANDS hands, so hands are weighted by what \
+                    you already know from the start. 10 visits every part of the card once",
+                ),
+        )
+        .arg(
+            Arg::with_name("nocolor")
+                .long("no-color")
+                .help("Never use color in output"),
+        )
+        .arg(
+            Arg::with_name("checkodds")
+                .long("check-odds")
+                .value_name("HANDS")
+                .hidden(true)
+                .help(
+                    "Deal HANDS hands with each way of dealing, check they come up as often as \
+                    they should, and exit. Exits non-zero if any look biased",
+                ),
+        )
+        .arg(config_arg())
         .get_matches();
-    let mut deck = Deck::new_infinite();
-    let table = Table::new(resps_from_buf(
-        OpenOptions::new()
-            .read(true)
-            // safe to unwrap because --table is required
-            .open(matches.value_of("table").unwrap())?,
-    )?)?;
+    if matches.is_present("checkodds") {
+        let hands = value_t!(matches, "checkodds", u32)?;
+        let mut fair = true;
+        for report in odds::check_all(hands) {
+            fair &= report.passes(odds::ALPHA);
+            println!("{}", report);
+        }
+        if !fair {
+            return Err("Some hands aren't dealt as often as they should be".into());
+        }
+        return Ok(());
+    }
+    let aliases = Config::load(matches.value_of("config"))?.aliases()?;
+    // safe to unwrap because --table is required
+    let bs = load_table(matches.value_of("table").unwrap())?;
+    if bs.is_partial() && matches.is_present("placement") {
+        return Err("A placement quiz covers the whole card, so it needs a complete table".into());
+    }
     // safe to unwrap bc --stats is required
     let stats_fname = matches.value_of("stats").unwrap();
     let save_stats = value_t!(matches, "statssave", StatsSaveStrat)?;
+    // safe to unwrap bc --notes has a default
+    let notes_fname = matches.value_of("notes").unwrap();
+    let notes: Notes = if Path::new(notes_fname).exists() {
+        let fd = OpenOptions::new().read(true).open(notes_fname)?;
+        read_maybexz(fd, notes_fname.ends_with(".xz"))?
+    } else {
+        Notes::new()
+    };
     let uni_rand_every = {
         let val = value_t!(matches, "unirand", u64)?;
         if val == 0 {
@@ -132,9 +559,45 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
             val
         }
     };
+    let mode = if matches.is_present("hitstand") {
+        Mode::HitStand {
+            pairs: !matches.is_present("nopairs"),
+            softs: !matches.is_present("nosofts"),
+        }
+    } else {
+        Mode::Full
+    };
+    let style = Style::detect(matches.is_present("nocolor"));
+    let count_every = value_t!(matches, "countevery", u64)?;
+    let full_hand = matches.is_present("fullhand");
+    // chips to play full hands out for, kept with the stats unless they aren't saved, and what's
+    // bet on each hand
+    let mut chips = if full_hand {
+        let fname = bankroll_fname(stats_fname);
+        let file = match save_stats {
+            StatsSaveStrat::Never => None,
+            _ => Some(fname.as_str()),
+        };
+        Chips::load(file, matches.value_of("bankroll"))?
+    } else {
+        None
+    };
+    if let Some(c) = &chips {
+        println!("bankroll: {}", c.bankroll);
+        if c.bankroll.is_broke() {
+            println!("Out of chips, so hands are played for units");
+            chips = None;
+        }
+    }
+    let mut bet = 1.0;
+    // the running count of all cards shown this session, how many hands until the next quiz on
+    // it, and how the player has done on those quizzes. Count quizzes are not saved to disk.
+    let hilo = HiLo::new();
+    let mut running_count: i16 = 0;
+    let mut hands_until_count_quiz = count_every;
+    let mut count_stats = PlayStats::new();
     let mut hand_count = 0;
-    let mut streak_count = 0;
-    let mut stats = match save_stats {
+    let stats = match save_stats {
         StatsSaveStrat::Never => def_playstats_table(),
         _ => {
             create_if_not_exist(stats_fname, &def_playstats_table())?;
@@ -143,52 +606,164 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
             read_maybexz(fd, stats_fname.ends_with(".xz"))?
         }
     };
-    print_game_stats(&stats);
+    let mut trainer = Trainer::new(bs, HandSource::Weighted);
+    trainer.set_stats(stats, 0);
+    trainer.set_notes(notes);
+    trainer.set_mode(mode);
+    trainer.set_even_money(matches.is_present("evenmoney"));
+    trainer.set_frequency_weighting(matches.is_present("frequency"));
+    trainer.set_clock(Some(now));
+    trainer.set_play_splits(matches.is_present("playsplits"));
+    trainer.set_multi_card(matches.is_present("multicard"));
+    if matches.is_present("refresh") {
+        trainer.set_refresh_after(Some(value_t!(matches, "refresh", u32)?));
+    }
+    print_game_stats(&trainer);
+    trainer.set_upcard(match matches.value_of("upcard") {
+        None => None,
+        Some("A") => Some(11),
+        // safe to unwrap because clap checked it's one of the possible values
+        Some(v) => Some(v.parse().unwrap()),
+    })?;
+    if let Some(name) = matches.value_of("pack") {
+        let pack = load_pack(name)?;
+        println!("Drill pack: {}", pack.name);
+        if !pack.description.is_empty() {
+            println!("{}", pack.description);
+        }
+        trainer.set_pack(Some(pack))?;
+    }
+    if matches.is_present("placement")
+        && !placement_quiz(
+            &mut trainer,
+            value_t!(matches, "placement", usize)?,
+            &aliases,
+            style,
+        )?
+    {
+        return Ok(());
+    }
+    let mut practice = Practice::load()?;
+    print_practice(&practice);
+    set_next_source(&mut trainer, &mut hand_count, uni_rand_every);
+    trainer.next_hand();
+    practice.shown();
+    // whether the hand on the table has been added to the running count yet. The same hand is
+    // prompted for again if the player gives an illegal response to it.
+    let mut hand_counted = false;
     loop {
-        hand_count += 1;
-        let (player, dealer_up, rand_type) = if hand_count == uni_rand_every {
-            // played enough hands that we should generate the next hand uniformally at random.
-            // Reset hand count and do so.
-            hand_count = 0;
-            //println!("Uniformally random hand chosen, not based on play stats");
-            (
-                Hand::new(&[deck.draw()?, deck.draw()?]),
-                deck.draw()?,
-                RandType::Uniform,
-            )
-        } else {
-            // haven't played enough hands yet, so generate randomly using play stats for weight
-            let (h, d) = rand_next_hand(&stats);
-            (h, d, RandType::Weighted)
-        };
-        let current_stat = stats.get(&player, dealer_up)?;
-        let command = prompt(
-            &player,
-            dealer_up,
-            rand_type,
-            current_stat,
+        let (player, dealer_up) = trainer.hand();
+        let player = player.clone();
+        // every card that is shown to the player counts, regardless of where it came from
+        if !hand_counted {
+            if trainer.split_state().splits > 0 {
+                // a hand being played after a split. Only its last card is new: the rest, and the
+                // upcard, were already shown.
+                running_count += hilo.card_value(*player.cards().last().unwrap());
+                println!("Split hand, {} more to play", trainer.split_hands_waiting());
+            } else {
+                running_count += hilo.cards_value(&player.cards().copied().collect::<Vec<Card>>());
+                running_count += hilo.card_value(dealer_up);
+            }
+            hand_counted = true;
+            if let Some(note) = trainer.note() {
+                println!("Your note: {}", note);
+            }
+        }
+        if trainer.even_money_offered() {
+            let question = format!(
+                "{} / {} Even money?",
+                style.hand_with_value(&player),
+                style.card(dealer_up)
+            );
+            let take = match prompt::prompt_yes_no(
+                &question,
+                &mut BufReader::new(io::stdin()),
+                &mut io::stdout(),
+            )? {
+                Some(take) => take,
+                None => break,
+            };
+            set_next_source(&mut trainer, &mut hand_count, uni_rand_every);
+            // safe to unwrap because even money was offered
+            let graded = trainer.act_even_money(take)?.unwrap();
+            practice.answered()?;
+            hand_counted = false;
+            if graded.is_correct {
+                println!("{}", style.correct("correct"));
+            } else {
+                println!(
+                    "{}. Basic strategy never takes even money. Streak was {}",
+                    style.wrong("wrong"),
+                    graded.old_streak
+                );
+            }
+            print_pack_progress(&trainer);
+            continue;
+        }
+        let current_stat = trainer.stats().get(&player, dealer_up)?;
+        let command = prompt::prompt(
+            &hand_prompt(&player, dealer_up, trainer.source(), current_stat, style),
+            &aliases,
             &mut BufReader::new(io::stdin()),
             &mut io::stdout(),
         )?;
-        // handle easy commands first. New commands should either return from main() entirely or
-        // restart the loop
+        // handle easy commands first. New commands should either leave the loop or restart it
         match command {
-            prompt::Command::Quit => return Ok(()),
+            prompt::Command::Quit => break,
             prompt::Command::Save | prompt::Command::SaveQuit => {
-                // This saves play stats and restarts the loop, which means it acts like this hand
-                // never happened. This gives the player a way to skip a hand without consequences.
+                // This saves play stats and restarts the loop with a new hand, which means it acts
+                // like this hand never happened. This gives the player a way to skip a hand without
+                // consequences.
                 let fd = OpenOptions::new()
                     .write(true)
                     .truncate(true)
                     .open(stats_fname)?;
-                write_maybexz(fd, &stats, stats_fname.ends_with(".xz"))?;
-                print_game_stats(&stats);
+                write_maybexz(fd, trainer.stats(), stats_fname.ends_with(".xz"))?;
+                print_game_stats(&trainer);
+                print_practice(&practice);
                 if command == prompt::Command::SaveQuit {
-                    return Ok(());
+                    break;
                 }
+                set_next_source(&mut trainer, &mut hand_count, uni_rand_every);
+                trainer.next_hand();
+                hand_counted = false;
+                continue;
+            }
+            prompt::Command::Note(note) => {
+                // Save the note and ask about the same hand again
+                trainer.set_note(&note);
+                let fd = OpenOptions::new()
+                    .write(true)
+                    .create(true)
+                    .truncate(true)
+                    .open(notes_fname)?;
+                write_maybexz(fd, trainer.notes(), notes_fname.ends_with(".xz"))?;
+                if note.is_empty() {
+                    println!("Note removed");
+                } else {
+                    println!("Note saved");
+                }
+                continue;
+            }
+            prompt::Command::Bet(amt) => {
+                // bet on this hand and the ones after it, then ask about the same hand again
+                match &chips {
+                    Some(c) => match c.bankroll.covers(f64::from(amt)) {
+                        Ok(()) => {
+                            bet = f64::from(amt);
+                            println!("Betting {} chips a hand", bet);
+                        }
+                        Err(e) => println!("{}", e),
+                    },
+                    None => println!("Betting needs chips, see --bankroll"),
+                }
+                continue;
+            }
+            prompt::Command::Hud => {
+                println!("The HUD is only for play");
                 continue;
             }
-            prompt::Command::Bet(_) => unreachable!(),
             prompt::Command::Num(_) => unreachable!(),
             prompt::Command::Resp(_) => { /* will handle below */ }
         };
@@ -197,25 +772,46 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         } else {
             unreachable!("Should have handled non-Command::Resp already");
         };
-        // Handle the case that the user actually hit, stand, etc.
-        let best = table.get(&player, dealer_up)?;
-        print!("{} ", resp);
-        if resp == best {
-            streak_count += 1;
-            println!("correct");
+        // Handle the case that the user actually hit, stand, etc. Decide how the next hand will be
+        // dealt first, as the trainer deals it as soon as this one is graded.
+        let old_source = trainer.source();
+        let old_hand_count = hand_count;
+        set_next_source(&mut trainer, &mut hand_count, uni_rand_every);
+        let graded = match trainer.act(resp.into()) {
+            Ok(graded) => graded,
+            Err(e @ TrainerError::IllegalAction(..)) => {
+                // ask again about the same hand
+                println!("{}", e);
+                trainer.set_source(old_source);
+                hand_count = old_hand_count;
+                continue;
+            }
+            Err(e) => return Err(e.into()),
+        };
+        practice.answered()?;
+        hand_counted = false;
+        print!("{} ", graded.action);
+        if graded.is_correct {
+            println!("{}", style.correct("correct"));
         } else {
-            println!("wrong. Should {}. Streak was {}", best, streak_count);
-            streak_count = 0;
-        }
-        // increment the stats for this hand type
-        let mut stat = stats.get(&player, dealer_up)?;
-        stat.inc(resp == best);
-        stats.update(&player, dealer_up, stat)?;
-        // print stats if user got it wrong
-        if resp != best {
-            print_game_stats(&stats);
-        }
-        // maybe save
+            println!(
+                "{}. Should {} on {}. Streak was {}",
+                style.wrong("wrong"),
+                graded.correct,
+                graded.cell().label(),
+                graded.old_streak
+            );
+            if let Some(detail) = graded.fallback_detail() {
+                println!("{}", detail);
+            }
+            if let Some(note) = &graded.note {
+                println!("Your note: {}", note);
+            }
+            // print stats if user got it wrong
+            print_game_stats(&trainer);
+        }
+        print_pack_progress(&trainer);
+        // maybe save, before the hand is played out in case the player quits partway
         match save_stats {
             StatsSaveStrat::Never => {}
             StatsSaveStrat::EveryHand => {
@@ -223,9 +819,74 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                     .write(true)
                     .truncate(true)
                     .open(stats_fname)?;
-                write_maybexz(fd, &stats, stats_fname.ends_with(".xz"))?;
+                write_maybexz(fd, trainer.stats(), stats_fname.ends_with(".xz"))?;
+            }
+        }
+        if full_hand {
+            if let Some(c) = &chips {
+                // the bankroll may have shrunk below the bet
+                if c.bankroll.covers(bet).is_err() {
+                    bet = c.bankroll.chips.floor();
+                    println!("Betting {} chips, all that's left", bet);
+                }
+            }
+            let quit = play_full_hand(
+                player,
+                dealer_up,
+                resp,
+                trainer.strategy().rules.clone(),
+                bet,
+                &mut chips,
+                &mut running_count,
+                &aliases,
+                style,
+            )?;
+            if quit == Some(prompt::Command::SaveQuit) {
+                let fd = OpenOptions::new()
+                    .write(true)
+                    .truncate(true)
+                    .open(stats_fname)?;
+                write_maybexz(fd, trainer.stats(), stats_fname.ends_with(".xz"))?;
+                print_game_stats(&trainer);
+                print_practice(&practice);
+            }
+            if quit.is_some() {
+                break;
+            }
+            if chips.as_ref().is_some_and(|c| c.bankroll.is_broke()) {
+                println!("Out of chips, so the rest are played for units");
+                // safe to unwrap because it's broke
+                chips.take().unwrap().print_session();
+                bet = 1.0;
             }
         }
+        // maybe quiz on the count
+        if count_every > 0 {
+            hands_until_count_quiz -= 1;
+            if hands_until_count_quiz == 0 {
+                hands_until_count_quiz = count_every;
+                let val = match prompt_for_count(
+                    &aliases,
+                    &mut BufReader::new(io::stdin()),
+                    &mut io::stdout(),
+                )? {
+                    prompt::Command::Quit => break,
+                    // safe to unwrap because prompt_for_count() only gives counts that fit
+                    prompt::Command::Num(n) => i16::try_from(n).unwrap(),
+                    _ => unreachable!(),
+                };
+                count_stats.inc(val == running_count);
+                if val == running_count {
+                    println!("{}", style.correct("Count correct"));
+                } else {
+                    println!("{}. Count is {}", style.wrong("Count wrong"), running_count);
+                }
+                print_count_stats(count_stats);
+            }
+        }
+    }
+    if let Some(chips) = &chips {
+        chips.print_session();
     }
-    //Ok(())
+    Ok(())
 }
@@ -0,0 +1,74 @@
+use bj_bin::logging;
+use bj_core::basicstrategy::BasicStrategy;
+use bj_core::ev::misplays;
+use bj_core::validate::check_card;
+use clap::{crate_authors, crate_name, crate_version, App, Arg};
+use std::error::Error;
+use std::fs::OpenOptions;
+use std::process;
+
+/// The bundled card with the given name, or else the card in the given JSON file
+fn load_card(name: &str) -> Result<BasicStrategy, Box<dyn Error>> {
+    match bj_data::dataset(name) {
+        Some(d) => Ok(d.strategy_card()?),
+        None => Ok(serde_json::from_reader(
+            OpenOptions::new().read(true).open(name)?,
+        )?),
+    }
+}
+
+fn main() {
+    logging::init();
+    let matches = App::new(String::from(crate_name!()) + " validate")
+        .author(crate_authors!())
+        .version(crate_version!())
+        .about(
+            "Check strategy cards for cells no card should have, like splitting a non-pair or \
+            surrendering when the card's rules don't allow it. Exits non-zero if any card has \
+            problems or can't be loaded.",
+        )
+        .arg(
+            Arg::with_name("cards")
+                .value_name("CARD")
+                .multiple(true)
+                .required(true)
+                .help("A bundled card's name (e.g. t1) or a BasicStrategy JSON file"),
+        )
+        .arg(Arg::with_name("ev").long("ev").help(
+            "Also compute the best play in every cell for the card's rules, and count each \
+            cell that plays differently as a problem, with the EV it costs. Cards without \
+            rules can't be checked this way.",
+        ))
+        .get_matches();
+    let mut ok = true;
+    // safe to unwrap because CARD is required
+    for name in matches.values_of("cards").unwrap() {
+        let bs = match load_card(name) {
+            Ok(bs) => bs,
+            Err(e) => {
+                println!("{}: can't load: {}", name, e);
+                ok = false;
+                continue;
+            }
+        };
+        let mut problems: Vec<String> = check_card(&bs).iter().map(|p| p.to_string()).collect();
+        if matches.is_present("ev") {
+            match &bs.rules {
+                Some(rules) => problems.extend(misplays(&bs, rules).iter().map(|m| m.to_string())),
+                None => problems.push("has no rules to compute the best plays for".to_string()),
+            }
+        }
+        if problems.is_empty() {
+            println!("{}: OK", name);
+            continue;
+        }
+        ok = false;
+        println!("{}: {} problems", name, problems.len());
+        for p in problems {
+            println!("  {}", p);
+        }
+    }
+    if !ok {
+        process::exit(1);
+    }
+}
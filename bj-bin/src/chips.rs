@@ -0,0 +1,100 @@
+//! The player's chips, for the tools that can be played for them. See bj_core::bankroll.
+//!
+//! A bankroll can be kept in a file between sessions, saved after every round. play takes the
+//! file's name, and train keeps it next to its stats file (see bankroll_fname()).
+use crate::utils::{read_maybexz, write_maybexz};
+use bj_core::bankroll::Bankroll;
+use bj_core::settle::Settlement;
+use std::error::Error;
+use std::fs::OpenOptions;
+use std::path::Path;
+
+/// The player's chips, and where they're kept between sessions
+pub struct Chips {
+    pub bankroll: Bankroll,
+    /// The bankroll as it was when the session started
+    start: Bankroll,
+    file: Option<String>,
+}
+
+/// Where to keep the bankroll that goes with the given stats file, e.g. play-stats.bankroll.json.xz
+/// for play-stats.json.xz
+pub fn bankroll_fname(stats_fname: &str) -> String {
+    match stats_fname.rfind(".json") {
+        Some(i) => format!("{}.bankroll{}", &stats_fname[..i], &stats_fname[i..]),
+        None => format!("{}.bankroll", stats_fname),
+    }
+}
+
+impl Chips {
+    pub fn new(bankroll: Bankroll, file: Option<&str>) -> Self {
+        Self {
+            bankroll,
+            start: bankroll,
+            file: file.map(String::from),
+        }
+    }
+
+    /// The bankroll kept in file if there is one, or else a new one of start chips, kept in file
+    /// from now on if given. None if there's neither.
+    pub fn load(file: Option<&str>, start: Option<&str>) -> Result<Option<Self>, Box<dyn Error>> {
+        Ok(match (file, start) {
+            (Some(fname), _) if Path::new(fname).exists() => {
+                let fd = OpenOptions::new().read(true).open(fname)?;
+                let bankroll = read_maybexz(fd, fname.ends_with(".xz"))?;
+                Some(Self::new(bankroll, Some(fname)))
+            }
+            (fname, Some(start)) => Some(Self::new(Bankroll::new(start.parse()?), fname)),
+            (_, None) => None,
+        })
+    }
+
+    /// Pay out or collect the round, and save the bankroll if it's kept in a file
+    pub fn settle(&mut self, settlement: &Settlement) -> Result<(), Box<dyn Error>> {
+        self.bankroll.settle(settlement);
+        self.save()
+    }
+
+    /// Lose the stake of a round the player left partway through, and save the bankroll if it's
+    /// kept in a file
+    pub fn forfeit(&mut self, stake: f64) -> Result<(), Box<dyn Error>> {
+        self.bankroll.forfeit(stake);
+        println!("Left the round, losing the {} chips on it", stake);
+        self.save()
+    }
+
+    fn save(&self) -> Result<(), Box<dyn Error>> {
+        if let Some(fname) = &self.file {
+            let fd = OpenOptions::new()
+                .write(true)
+                .create(true)
+                .truncate(true)
+                .open(fname)?;
+            write_maybexz(fd, &self.bankroll, fname.ends_with(".xz"))?;
+        }
+        Ok(())
+    }
+
+    pub fn print_session(&self) {
+        println!("session: {}", self.bankroll.since(&self.start));
+        println!(
+            "bankroll: {} chips, {:+} over {} rounds, most ever {}",
+            self.bankroll.chips, self.bankroll.net, self.bankroll.rounds, self.bankroll.peak
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn next_to_stats() {
+        assert_eq!(
+            bankroll_fname("play-stats.json.xz"),
+            "play-stats.bankroll.json.xz"
+        );
+        assert_eq!(bankroll_fname("dir/stats.json"), "dir/stats.bankroll.json");
+        assert_eq!(bankroll_fname("stats"), "stats.bankroll");
+    }
+}
@@ -0,0 +1,126 @@
+//! The config file shared by the CLI tools.
+//!
+//! It's JSON, and every part of it is optional. The tools read it from --config if given, else
+//! from bj-bin/config.json in $XDG_CONFIG_HOME (or ~/.config if that isn't set), if it exists.
+//! For example, to press x to split and use German words to quit and save:
+//!
+//! ```json
+//! {"aliases": {"x": "p", "ende": "quit", "speichern": "save"}}
+//! ```
+use crate::prompt::{AliasError, Aliases};
+use bj_core::anonid::AnonIdentity;
+use clap::Arg;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::env;
+use std::error::Error;
+use std::fs::{self, OpenOptions};
+use std::io;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// Extra words for prompt commands, each mapped to the command it stands for. See
+    /// prompt::Aliases
+    pub aliases: BTreeMap<String, String>,
+}
+
+/// The directory the tools keep their files in: bj-bin in $XDG_CONFIG_HOME, or in ~/.config if
+/// that isn't set. None if there's no home directory to look in
+pub fn config_dir() -> Option<PathBuf> {
+    let dir = match env::var_os("XDG_CONFIG_HOME").filter(|d| !d.is_empty()) {
+        Some(d) => PathBuf::from(d),
+        None => PathBuf::from(env::var_os("HOME")?).join(".config"),
+    };
+    Some(dir.join("bj-bin"))
+}
+
+/// Where the config file is if --config isn't given. None if there's no home directory to look in
+pub fn default_path() -> Option<PathBuf> {
+    Some(config_dir()?.join("config.json"))
+}
+
+/// Where the player's anonymous ID (see bj_core::anonid) is kept. None if there's no home directory
+/// to look in
+pub fn anon_id_path() -> Option<PathBuf> {
+    Some(config_dir()?.join("anon-id.json"))
+}
+
+/// The player's anonymous identity kept at path, making and saving a new one if there isn't one
+pub fn load_identity(path: &Path) -> Result<AnonIdentity, Box<dyn Error>> {
+    match OpenOptions::new().read(true).open(path) {
+        Ok(fd) => serde_json::from_reader(fd)
+            .map_err(|e| format!("Bad anonymous ID in {}: {}", path.display(), e).into()),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {
+            let ident = AnonIdentity::new();
+            save_identity(path, &ident)?;
+            Ok(ident)
+        }
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Keep the player's anonymous identity at path, making its directory if needed
+pub fn save_identity(path: &Path, ident: &AnonIdentity) -> Result<(), Box<dyn Error>> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let fd = OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(path)?;
+    serde_json::to_writer(fd, ident)?;
+    Ok(())
+}
+
+/// The --config argument for tools that read the config file
+pub fn config_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("config")
+        .long("config")
+        .value_name("FILE")
+        .help("Read the config from FILE instead of ~/.config/bj-bin/config.json")
+}
+
+impl Config {
+    /// Read the config from the given file, or from the default path if none is given. It's fine
+    /// for there to be no file at the default path, but not at a given one.
+    pub fn load(fname: Option<&str>) -> Result<Self, Box<dyn Error>> {
+        let (path, required) = match fname {
+            Some(f) => (PathBuf::from(f), true),
+            None => match default_path() {
+                Some(p) => (p, false),
+                None => return Ok(Self::default()),
+            },
+        };
+        match OpenOptions::new().read(true).open(&path) {
+            Ok(fd) => serde_json::from_reader(fd)
+                .map_err(|e| format!("Bad config in {}: {}", path.display(), e).into()),
+            Err(e) if !required && e.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// The checked prompt command aliases
+    pub fn aliases(&self) -> Result<Aliases, AliasError> {
+        Aliases::new(&self.aliases)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn identity_persists() {
+        let dir = env::temp_dir().join(format!("bj-bin-anon-id-{}", std::process::id()));
+        let path = dir.join("sub").join("anon-id.json");
+        let ident = load_identity(&path).unwrap();
+        assert!(ident.id().is_some());
+        assert_eq!(load_identity(&path).unwrap(), ident);
+        save_identity(&path, &AnonIdentity::opted_out()).unwrap();
+        assert!(load_identity(&path).unwrap().is_opted_out());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
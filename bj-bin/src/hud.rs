@@ -0,0 +1,129 @@
+//! A heads up display of the count for `play`, for practicing counting while playing.
+//!
+//! The game tells the Hud about every card as the player sees it (Hud::observe()), which for the
+//! dealer's hole card is when it's turned over, and about every shuffle. Hud::line() is then what
+//! a counter would be keeping in their head: the HiLo running and true counts and what the bet
+//! ramp says to bet. Hud::alert() says when an index play (see bj_data::active_index_plays())
+//! changes what to do with the hand in front of the player. The game keeps counting whether or not
+//! the HUD is shown, so it can be toggled at any time.
+use bj_core::basicstrategy::BasicStrategy;
+use bj_core::betramp::BetRamp;
+use bj_core::count::StatefulHiLo;
+use bj_core::deck::Card;
+use bj_core::hand::Hand;
+use bj_core::holecard::takes_insurance;
+use bj_core::table::GameDesc;
+use bj_data::{active_index_plays, IndexPlay, INSURANCE_INDEX};
+
+pub struct Hud {
+    count: StatefulHiLo,
+    num_decks: u8,
+    ramp: BetRamp,
+    /// The card index plays are measured against
+    bs: BasicStrategy,
+    shown: bool,
+}
+
+impl Hud {
+    /// A HUD for a shoe of the given number of decks, hidden until toggled
+    pub fn new(num_decks: u8, ramp: BetRamp, bs: BasicStrategy) -> Self {
+        Self {
+            count: StatefulHiLo::new(num_decks),
+            num_decks,
+            ramp,
+            bs,
+            shown: false,
+        }
+    }
+
+    /// The player has seen the card
+    pub fn observe(&mut self, card: Card) {
+        self.count.update(card);
+    }
+
+    /// The shoe was shuffled, so the count starts over
+    pub fn shuffled(&mut self) {
+        self.count = StatefulHiLo::new(self.num_decks);
+    }
+
+    pub fn true_count(&self) -> f32 {
+        self.count.true_count()
+    }
+
+    pub fn is_shown(&self) -> bool {
+        self.shown
+    }
+
+    /// Show the HUD if it's hidden, or hide it if it's shown. Returns whether it's now shown.
+    pub fn toggle(&mut self) -> bool {
+        self.shown = !self.shown;
+        self.shown
+    }
+
+    /// e.g. "RC +3, TC +1.2, bet 2 units"
+    pub fn line(&self) -> String {
+        format!(
+            "RC {:+}, TC {:+.1}, bet {} units",
+            self.count.running_count(),
+            self.true_count(),
+            self.ramp.units(self.true_count())
+        )
+    }
+
+    /// The index play for the given hand at the current count, if there is one, e.g. "Deviation:
+    /// Stand on Hard 16 vs 10"
+    pub fn alert(&self, hand: &Hand, up: Card) -> Option<String> {
+        let cell = GameDesc::from_hand(hand, up);
+        active_index_plays(self.true_count(), &self.bs)
+            .into_iter()
+            .find(|p| matches!(p, IndexPlay::Cell(d, _) if d.cell == cell))
+            .map(|p| format!("Deviation: {}", p))
+    }
+
+    /// Whether the count says to take insurance, which basic strategy never does
+    pub fn insurance_alert(&self) -> Option<String> {
+        if takes_insurance(Some(self.true_count()), INSURANCE_INDEX) {
+            Some(format!("Deviation: {}", IndexPlay::Insurance))
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bj_core::deck::{Rank, Suit};
+
+    fn card(rank: Rank) -> Card {
+        Card::new(rank, Suit::Heart)
+    }
+
+    #[test]
+    fn count_and_alerts() {
+        let mut hud = Hud::new(1, BetRamp::default(), bj_data::t1());
+        assert!(!hud.is_shown());
+        assert!(hud.toggle());
+        assert_eq!(hud.line(), "RC +0, TC +0.0, bet 1 units");
+        let fifteen = Hand::new(&[card(Rank::RT), card(Rank::R5)]);
+        assert_eq!(hud.alert(&fifteen, card(Rank::RT)), None);
+        assert_eq!(hud.insurance_alert(), None);
+        // a handful of low cards out of a single deck
+        for _ in 0..2 {
+            for rank in &[Rank::R2, Rank::R3, Rank::R4, Rank::R5] {
+                hud.observe(card(*rank));
+            }
+        }
+        assert_eq!(hud.line(), "RC +8, TC +9.5, bet 8 units");
+        assert_eq!(
+            hud.alert(&fifteen, card(Rank::RT)).as_deref(),
+            Some("Deviation: Stand on Hard 15 vs 10")
+        );
+        assert_eq!(
+            hud.insurance_alert().as_deref(),
+            Some("Deviation: Take insurance")
+        );
+        hud.shuffled();
+        assert_eq!(hud.true_count(), 0.0);
+    }
+}
@@ -1,2 +1,8 @@
+pub mod chips;
+pub mod config;
+pub mod hud;
+pub mod logging;
+pub mod practice;
 pub mod prompt;
+pub mod render;
 pub mod utils;
@@ -0,0 +1,92 @@
+//! Leveled logging to stderr for the CLI tools, through the `log` crate's macros.
+//!
+//! Tools call init() first thing. Messages at info and above are shown by default. Set BJ_LOG to
+//! one of "off", "error", "warn", "info", "debug", or "trace" to see more or less, e.g.
+//! `BJ_LOG=debug bj-bin-train ...`. Debug and trace messages also say which module they're from,
+//! including bj-core's.
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use std::env;
+use std::io::{self, Write};
+use std::str::FromStr;
+
+/// The environment variable that sets the level
+pub const LEVEL_VAR: &str = "BJ_LOG";
+
+const DEFAULT_LEVEL: LevelFilter = LevelFilter::Info;
+
+struct StderrLogger;
+
+/// How a record is written, e.g. "Reading stats.json" or "warn: Skipped line 3"
+fn format_record(record: &Record) -> String {
+    match record.level() {
+        Level::Info => record.args().to_string(),
+        Level::Error | Level::Warn => {
+            format!(
+                "{}: {}",
+                record.level().to_string().to_lowercase(),
+                record.args()
+            )
+        }
+        Level::Debug | Level::Trace => format!(
+            "{} {}: {}",
+            record.level().to_string().to_lowercase(),
+            record.target(),
+            record.args()
+        ),
+    }
+}
+
+impl Log for StderrLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= log::max_level()
+    }
+
+    fn log(&self, record: &Record) {
+        if self.enabled(record.metadata()) {
+            // nowhere to report a failure to write to stderr
+            let _ = writeln!(io::stderr(), "{}", format_record(record));
+        }
+    }
+
+    fn flush(&self) {
+        let _ = io::stderr().flush();
+    }
+}
+
+static LOGGER: StderrLogger = StderrLogger;
+
+/// The level BJ_LOG asks for, or info if it's unset or not a level
+fn level_from_env() -> LevelFilter {
+    env::var(LEVEL_VAR)
+        .ok()
+        .and_then(|v| LevelFilter::from_str(&v).ok())
+        .unwrap_or(DEFAULT_LEVEL)
+}
+
+/// Start logging at the level BJ_LOG asks for. Only the first call does anything.
+pub fn init() {
+    if log::set_logger(&LOGGER).is_ok() {
+        log::set_max_level(level_from_env());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn format() {
+        let rec = |level| {
+            format_record(
+                &Record::builder()
+                    .level(level)
+                    .target("bj_core::deck")
+                    .args(format_args!("Shuffled"))
+                    .build(),
+            )
+        };
+        assert_eq!(rec(Level::Info), "Shuffled");
+        assert_eq!(rec(Level::Warn), "warn: Shuffled");
+        assert_eq!(rec(Level::Debug), "debug bj_core::deck: Shuffled");
+    }
+}
@@ -0,0 +1,90 @@
+//! Practice time for the CLI tools, kept per day in practice.json next to the config file. See
+//! bj_core::practice.
+//!
+//! Days are UTC days, as the tools don't know the local time zone.
+use crate::config::config_dir;
+use bj_core::practice::{date, PracticeLog, PracticeTimer};
+use std::error::Error;
+use std::fs::{self, OpenOptions};
+use std::io;
+use std::path::PathBuf;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+pub struct Practice {
+    /// Where the log is kept, or None if there's no home directory to keep it in
+    path: Option<PathBuf>,
+    log: PracticeLog,
+    timer: PracticeTimer,
+    start: Instant,
+}
+
+/// Today, in UTC
+fn today() -> String {
+    date(
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs()),
+    )
+}
+
+impl Practice {
+    /// The log so far, with nothing shown yet
+    pub fn load() -> Result<Self, Box<dyn Error>> {
+        let path = config_dir().map(|d| d.join("practice.json"));
+        let log = match path
+            .as_ref()
+            .map(|p| (p, OpenOptions::new().read(true).open(p)))
+        {
+            Some((p, Ok(fd))) => serde_json::from_reader(fd)
+                .map_err(|e| format!("Bad practice log in {}: {}", p.display(), e))?,
+            Some((_, Err(e))) if e.kind() != io::ErrorKind::NotFound => return Err(e.into()),
+            _ => PracticeLog::new(),
+        };
+        Ok(Self {
+            path,
+            log,
+            timer: PracticeTimer::new(),
+            start: Instant::now(),
+        })
+    }
+
+    fn now(&self) -> f64 {
+        self.start.elapsed().as_secs_f64()
+    }
+
+    /// Something to answer was just shown
+    pub fn shown(&mut self) {
+        let now = self.now();
+        self.timer.shown(now);
+    }
+
+    /// The player just answered: add the time it took to today's practice, and save
+    pub fn answered(&mut self) -> Result<(), Box<dyn Error>> {
+        let now = self.now();
+        if let Some(secs) = self.timer.answered(now) {
+            self.log.record(&today(), secs);
+            if let Some(path) = &self.path {
+                if let Some(dir) = path.parent() {
+                    fs::create_dir_all(dir)?;
+                }
+                let fd = OpenOptions::new()
+                    .write(true)
+                    .create(true)
+                    .truncate(true)
+                    .open(path)?;
+                serde_json::to_writer(fd, &self.log)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Seconds practiced today
+    pub fn today(&self) -> f64 {
+        self.log.on(&today())
+    }
+
+    /// Seconds practiced ever
+    pub fn total(&self) -> f64 {
+        self.log.total()
+    }
+}
@@ -1,5 +1,7 @@
 use bj_core::resp::Resp;
+use std::collections::{BTreeMap, HashMap};
 use std::io::{self, BufRead, Write};
+use thiserror::Error;
 
 type NumType = i32;
 type BetType = u32;
@@ -12,6 +14,10 @@ pub enum Command {
     Num(NumType),
     Bet(BetType),
     Resp(Resp),
+    /// Set the note on the current hand. Empty to remove it.
+    Note(String),
+    /// Show or hide the heads up display of the count
+    Hud,
 }
 
 impl std::fmt::Display for Command {
@@ -23,11 +29,86 @@ impl std::fmt::Display for Command {
             Command::Num(val) => write!(f, "Num({})", val),
             Command::Bet(amt) => write!(f, "Bet({})", amt),
             Command::Resp(r) => write!(f, "Resp({})", r),
+            Command::Note(s) => write!(f, "Note({})", s),
+            Command::Hud => write!(f, "Hud"),
         }
     }
 }
 
-fn command_from_str(s: &str) -> Option<Command> {
+/// The words command_from_str() knows on its own, in upper case
+const BUILTIN_WORDS: [&str; 12] = [
+    "H", "S", "D", "P", "QUIT", "SAVE", "SAVEQUIT", "BET", "B", "NOTE", "N", "HUD",
+];
+
+/// Extra words players can use for commands, e.g. "x" for "p" because P is awkward to reach on
+/// their keyboard. An alias stands in for the first word of what the player types, so an alias
+/// for "bet" still takes an amount and one for "note" still takes the note.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Aliases(HashMap<String, String>);
+
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum AliasError {
+    /// The alias is empty or has whitespace in it
+    #[error("'{0}' can't be an alias: it must be one word")]
+    BadAlias(String),
+    /// The alias is already a command, a number, or another alias for something else
+    #[error("'{0}' can't be an alias: it already means something")]
+    Collision(String),
+    /// (alias, command) The alias is for something that isn't a command
+    #[error("'{0}' is an alias for '{1}', which isn't a command")]
+    UnknownCommand(String, String),
+}
+
+impl Aliases {
+    /// Check the given aliases, which map each alias to the command it stands for. Case doesn't
+    /// matter for either.
+    pub fn new(aliases: &BTreeMap<String, String>) -> Result<Self, AliasError> {
+        let mut map: HashMap<String, String> = HashMap::new();
+        for (alias, command) in aliases {
+            let upper = alias.trim().to_ascii_uppercase();
+            if upper.is_empty() || upper.contains(char::is_whitespace) {
+                return Err(AliasError::BadAlias(alias.clone()));
+            }
+            let target = command.trim().to_ascii_uppercase();
+            if !BUILTIN_WORDS.contains(&target.as_str()) {
+                return Err(AliasError::UnknownCommand(alias.clone(), command.clone()));
+            }
+            let taken = BUILTIN_WORDS.contains(&upper.as_str())
+                || upper.parse::<NumType>().is_ok()
+                || map.get(&upper).is_some_and(|t| *t != target);
+            if taken {
+                return Err(AliasError::Collision(alias.clone()));
+            }
+            map.insert(upper, target);
+        }
+        Ok(Self(map))
+    }
+
+    /// s with its first word replaced by the command it's an alias for, if it's an alias
+    fn expand(&self, s: &str) -> String {
+        let mut split = s.trim_start().splitn(2, char::is_whitespace);
+        let word = split.next().unwrap_or("");
+        match self.0.get(&word.to_ascii_uppercase()) {
+            Some(command) => match split.next() {
+                Some(rest) => format!("{} {}", command, rest),
+                None => command.clone(),
+            },
+            None => s.to_string(),
+        }
+    }
+}
+
+fn command_from_str(s: &str, aliases: &Aliases) -> Option<Command> {
+    let s: &str = &aliases.expand(s);
+    // the note is everything after the command, with its case preserved
+    let mut note_split = s.trim_start().splitn(2, char::is_whitespace);
+    if let Some(word) = note_split.next() {
+        if word.eq_ignore_ascii_case("NOTE") || word.eq_ignore_ascii_case("N") {
+            return Some(Command::Note(
+                note_split.next().unwrap_or("").trim().to_string(),
+            ));
+        }
+    }
     let s: &str = &s.to_ascii_uppercase();
     let words: Vec<_> = s.split_whitespace().collect();
     if words.is_empty() {
@@ -39,6 +120,8 @@ fn command_from_str(s: &str) -> Option<Command> {
             Some(Command::Save)
         } else if words[0] == "SAVEQUIT" {
             Some(Command::SaveQuit)
+        } else if words[0] == "HUD" {
+            Some(Command::Hud)
         } else if let Ok(val) = words[0].parse::<NumType>() {
             Some(Command::Num(val))
         } else {
@@ -68,7 +151,12 @@ fn command_from_str(s: &str) -> Option<Command> {
     }
 }
 
-pub fn prompt(s: &str, in_buf: &mut impl BufRead, out_buf: &mut impl Write) -> io::Result<Command> {
+pub fn prompt(
+    s: &str,
+    aliases: &Aliases,
+    in_buf: &mut impl BufRead,
+    out_buf: &mut impl Write,
+) -> io::Result<Command> {
     loop {
         write!(out_buf, "{} > ", s)?;
         out_buf.flush()?;
@@ -78,7 +166,7 @@ pub fn prompt(s: &str, in_buf: &mut impl BufRead, out_buf: &mut impl Write) -> i
         if s.is_empty() {
             continue;
         }
-        if let Some(cmd) = command_from_str(&s) {
+        if let Some(cmd) = command_from_str(&s, aliases) {
             break Ok(cmd);
         } else {
             writeln!(out_buf, "Bad response: {}", s)?;
@@ -86,13 +174,41 @@ pub fn prompt(s: &str, in_buf: &mut impl BufRead, out_buf: &mut impl Write) -> i
     }
 }
 
+/// Ask a yes or no question. None if the player wants to quit instead.
+pub fn prompt_yes_no(
+    question: &str,
+    in_buf: &mut impl BufRead,
+    out_buf: &mut impl Write,
+) -> io::Result<Option<bool>> {
+    loop {
+        write!(out_buf, "{} (y/n) > ", question)?;
+        out_buf.flush()?;
+        let mut s = String::new();
+        if in_buf.read_line(&mut s)? == 0 {
+            break Ok(None);
+        }
+        match s.trim().to_ascii_lowercase().as_str() {
+            "" => continue,
+            "y" | "yes" => break Ok(Some(true)),
+            "n" | "no" => break Ok(Some(false)),
+            "quit" => break Ok(None),
+            _ => writeln!(out_buf, "Expecting y or n")?,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use super::{command_from_str, prompt, Command};
+    use super::{prompt, prompt_yes_no, AliasError, Aliases, Command};
     use bj_core::resp::Resp;
+    use std::collections::BTreeMap;
+
+    fn command_from_str(s: &str) -> Option<Command> {
+        super::command_from_str(s, &Aliases::default())
+    }
 
     fn prompt_with(stdin: &str) -> Command {
-        prompt("", &mut stdin.as_bytes(), &mut vec![]).unwrap()
+        prompt("", &Aliases::default(), &mut stdin.as_bytes(), &mut vec![]).unwrap()
     }
 
     #[test]
@@ -147,6 +263,13 @@ mod tests {
         assert_eq!(command_from_str("save"), Some(Command::Save));
     }
 
+    #[test]
+    fn hud() {
+        for s in &["hud", "HUD"] {
+            assert_eq!(command_from_str(s), Some(Command::Hud));
+        }
+    }
+
     #[test]
     fn savequit() {
         assert_eq!(command_from_str("savequit"), Some(Command::SaveQuit));
@@ -159,6 +282,15 @@ mod tests {
         assert_eq!(command_from_str("b 1"), Some(Command::Bet(1)));
     }
 
+    #[test]
+    fn note() {
+        assert_eq!(
+            command_from_str("note  Dealer 9 is NOT a bust card "),
+            Some(Command::Note("Dealer 9 is NOT a bust card".to_string()))
+        );
+        assert_eq!(command_from_str("N"), Some(Command::Note("".to_string())));
+    }
+
     #[test]
     fn invalid_command_from_str() {
         for s in &[
@@ -174,4 +306,51 @@ mod tests {
             assert!(command_from_str(s).is_none());
         }
     }
+
+    #[test]
+    fn aliases() {
+        let aliases = |pairs: &[(&str, &str)]| {
+            Aliases::new(
+                &pairs
+                    .iter()
+                    .map(|(a, c)| (a.to_string(), c.to_string()))
+                    .collect::<BTreeMap<_, _>>(),
+            )
+        };
+        let a = aliases(&[("x", "p"), ("Ü", "quit"), ("w", "bet"), ("m", "NOTE")]).unwrap();
+        let cmd = |s| super::command_from_str(s, &a);
+        assert_eq!(cmd("X"), Some(Command::Resp(Resp::Split)));
+        assert_eq!(cmd("Ü"), Some(Command::Quit));
+        assert_eq!(cmd("w 5"), Some(Command::Bet(5)));
+        assert_eq!(cmd("m Keep It"), Some(Command::Note("Keep It".to_string())));
+        // the built-in commands still work
+        assert_eq!(cmd("p"), Some(Command::Resp(Resp::Split)));
+        for (alias, err) in &[
+            ("p", AliasError::Collision("p".to_string())),
+            ("10", AliasError::Collision("10".to_string())),
+            ("a b", AliasError::BadAlias("a b".to_string())),
+        ] {
+            assert_eq!(aliases(&[(alias, "h")]), Err(err.clone()));
+        }
+        assert_eq!(
+            aliases(&[("x", "split")]),
+            Err(AliasError::UnknownCommand(
+                "x".to_string(),
+                "split".to_string()
+            ))
+        );
+        assert_eq!(
+            aliases(&[("x", "p"), ("X", "h")]),
+            Err(AliasError::Collision("x".to_string()))
+        );
+    }
+
+    #[test]
+    fn yes_no() {
+        let ask = |stdin: &str| prompt_yes_no("", &mut stdin.as_bytes(), &mut vec![]).unwrap();
+        assert_eq!(ask("\nmaybe\nY\n"), Some(true));
+        assert_eq!(ask("no\n"), Some(false));
+        assert_eq!(ask("quit\n"), None);
+        assert_eq!(ask(""), None);
+    }
 }
@@ -0,0 +1,254 @@
+//! Terminal output helpers shared by the CLI binaries.
+//!
+//! Cards are printed with suit glyphs when the terminal looks like it can handle UTF-8 and with
+//! plain ASCII suit letters otherwise. Red suits and correct/wrong feedback are colored with ANSI
+//! escapes when stdout is a terminal, unless the user asked for no color (with `--no-color` or by
+//! setting the `NO_COLOR` environment variable).
+use bj_core::deck::{Card, Suit};
+use bj_core::hand::Hand;
+use bj_core::outcome::Outcome;
+use bj_core::round::RoundEnd;
+use bj_core::settle::{PlayedHand, Settled};
+use std::env;
+use std::io::{self, IsTerminal};
+
+const RED: &str = "\x1b[31m";
+const GREEN: &str = "\x1b[32m";
+const BOLD_RED: &str = "\x1b[1;31m";
+const RESET: &str = "\x1b[0m";
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Style {
+    /// Whether or not to use ANSI color escapes
+    pub color: bool,
+    /// Whether or not to use unicode suit glyphs. If not, use ASCII letters
+    pub glyphs: bool,
+}
+
+impl Style {
+    /// No color and no unicode. Always safe to print.
+    pub fn plain() -> Self {
+        Self {
+            color: false,
+            glyphs: false,
+        }
+    }
+
+    /// Determine what stdout is capable of. `no_color` is whatever the user asked for on the
+    /// command line, and if true, color is never used.
+    pub fn detect(no_color: bool) -> Self {
+        let is_tty = io::stdout().is_terminal();
+        let dumb = env::var("TERM").map(|t| t == "dumb").unwrap_or(false);
+        let color = !no_color && is_tty && !dumb && env::var_os("NO_COLOR").is_none();
+        // The first of these that is set wins, just like the C library does it
+        let locale = ["LC_ALL", "LC_CTYPE", "LANG"]
+            .iter()
+            .filter_map(|k| env::var(k).ok())
+            .find(|v| !v.is_empty())
+            .unwrap_or_default()
+            .to_ascii_uppercase();
+        let glyphs = locale.contains("UTF-8") || locale.contains("UTF8");
+        Self { color, glyphs }
+    }
+
+    fn paint(self, code: &str, s: &str) -> String {
+        if self.color {
+            format!("{}{}{}", code, s, RESET)
+        } else {
+            s.to_string()
+        }
+    }
+
+    pub fn card(self, card: Card) -> String {
+        let suit = if self.glyphs {
+            card.suit().to_string()
+        } else {
+            match card.suit() {
+                Suit::Club => "c",
+                Suit::Diamond => "d",
+                Suit::Heart => "h",
+                Suit::Spade => "s",
+            }
+            .to_string()
+        };
+        let s = format!("{}{}", card.rank(), suit);
+        match card.suit() {
+            Suit::Heart | Suit::Diamond => self.paint(RED, &s),
+            Suit::Club | Suit::Spade => s,
+        }
+    }
+
+    pub fn hand(self, hand: &Hand) -> String {
+        format!(
+            "[{}]",
+            hand.cards()
+                .map(|&c| self.card(c))
+                .collect::<Vec<String>>()
+                .join(" ")
+        )
+    }
+
+    /// The hand followed by its value, e.g. "[Ah 6c] (17s)"
+    pub fn hand_with_value(self, hand: &Hand) -> String {
+        format!(
+            "{} ({}{})",
+            self.hand(hand),
+            hand.value(),
+            if hand.is_soft() { "s" } else { "" },
+        )
+    }
+
+    /// How the player finished a hand, e.g. "stand [Ts 8d] (18)"
+    pub fn played(self, played: &PlayedHand) -> String {
+        let h = self.hand_with_value(&played.hand);
+        if played.doubled {
+            let bust = if played.hand.is_bust() { " bust" } else { "" };
+            format!("double {}{}", h, bust)
+        } else if played.surrendered {
+            format!("surrender {}", h)
+        } else if played.hand.is_bust() {
+            format!("{} bust", h)
+        } else {
+            format!("stand {}", h)
+        }
+    }
+
+    /// What happened once the player finished every hand: the dealer's hand after each card they
+    /// drew, then how each of the player's hands did, one per line
+    pub fn round_end(self, end: &RoundEnd) -> Vec<String> {
+        let dealer = end.dealer.hand();
+        if end.dealer.is_blackjack() {
+            // only possible without a peek: every hand loses to it, 21 or not
+            return std::iter::once(format!("dealer blackjack {}", self.hand_with_value(dealer)))
+                .chain(
+                    end.hands
+                        .iter()
+                        .map(|played| format!("lose {}", self.hand_with_value(&played.hand))),
+                )
+                .collect();
+        }
+        let mut lines: Vec<String> = end
+            .dealer
+            .sequence()
+            .iter()
+            .map(|hand| format!("dealer {}", self.hand_with_value(hand)))
+            .collect();
+        for (played, result) in end.hands.iter().zip(&end.settlement.hands) {
+            let (h, d) = (
+                self.hand_with_value(&played.hand),
+                self.hand_with_value(dealer),
+            );
+            lines.push(match result.settled {
+                Settled::Surrendered => format!("surrendered {}", h),
+                _ if played.hand.is_bust() => format!("bust {}", h),
+                Settled::Played(Outcome::Win) if dealer.is_bust() => {
+                    format!("dealer bust; win {}", h)
+                }
+                Settled::Played(Outcome::Blackjack) => format!("blackjack! win {}", h),
+                Settled::Played(Outcome::Win) => format!("win {} over dealer {}", h, d),
+                Settled::Played(Outcome::Push) => format!("push {} with dealer {}", h, d),
+                Settled::Played(Outcome::Lose) | Settled::Returned => {
+                    format!("lose {} to dealer {}", h, d)
+                }
+            });
+        }
+        lines
+    }
+
+    /// Feedback for something the user got right
+    pub fn correct(self, s: &str) -> String {
+        self.paint(GREEN, s)
+    }
+
+    /// Feedback for something the user got wrong
+    pub fn wrong(self, s: &str) -> String {
+        self.paint(BOLD_RED, s)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Style;
+    use bj_core::button::GameButton;
+    use bj_core::deck::{Card, DeckError, Rank, Suit};
+    use bj_core::hand::Hand;
+    use bj_core::holecard::DealerHand;
+    use bj_core::round::{Acted, Round};
+
+    #[test]
+    fn plain_card() {
+        let s = Style::plain();
+        assert_eq!(s.card(Card::new(Rank::RA, Suit::Heart)), "Ah");
+        assert_eq!(s.card(Card::new(Rank::RT, Suit::Spade)), "Ts");
+    }
+
+    #[test]
+    fn plain_hand_with_value() {
+        let s = Style::plain();
+        let h = Hand::new(&[
+            Card::new(Rank::RA, Suit::Heart),
+            Card::new(Rank::R6, Suit::Club),
+        ]);
+        assert_eq!(s.hand_with_value(&h), "[Ah 6c] (17s)");
+    }
+
+    #[test]
+    fn color_only_red_suits() {
+        let s = Style {
+            color: true,
+            glyphs: false,
+        };
+        assert_eq!(s.card(Card::new(Rank::R2, Suit::Club)), "2c");
+        let red = s.card(Card::new(Rank::R2, Suit::Diamond));
+        assert!(red.starts_with('\x1b'));
+        assert!(red.contains("2d"));
+    }
+
+    #[test]
+    fn no_color_feedback() {
+        let s = Style::plain();
+        assert_eq!(s.correct("correct"), "correct");
+        assert_eq!(s.wrong("wrong"), "wrong");
+    }
+
+    #[test]
+    fn glyphs_use_core_suits() {
+        let s = Style {
+            color: false,
+            glyphs: true,
+        };
+        let c = Card::new(Rank::RK, Suit::Spade);
+        assert_eq!(s.card(c), c.to_string());
+    }
+
+    #[test]
+    fn round_end() {
+        let c = |r| Card::new(r, Suit::Club);
+        let mut round = Round::new(
+            Hand::new(&[c(Rank::R9), c(Rank::R9)]),
+            DealerHand::new(c(Rank::R6), c(Rank::RT)),
+            None,
+        );
+        let mut shoe = vec![c(Rank::RT), c(Rank::RK), c(Rank::R2), c(Rank::R4)].into_iter();
+        let mut draw = || shoe.next().ok_or(DeckError::OutOfCards);
+        round.act(GameButton::Split, &mut draw).unwrap();
+        match round.act(GameButton::Stand, &mut draw).unwrap() {
+            Acted::Finished(played) => {
+                assert_eq!(Style::plain().played(&played), "stand [9c Tc] (19)")
+            }
+            acted => panic!("{:?}", acted),
+        }
+        round.act(GameButton::Hit, &mut draw).unwrap();
+        round.act(GameButton::Stand, &mut draw).unwrap();
+        let end = round.finish(1.0, 0.0, &mut draw).unwrap();
+        assert_eq!(
+            Style::plain().round_end(&end),
+            vec![
+                "dealer [6c Tc] (16)",
+                "dealer [6c Tc 4c] (20)",
+                "lose [9c Tc] (19) to dealer [6c Tc 4c] (20)",
+                "win [9c Kc 2c] (21) over dealer [6c Tc 4c] (20)",
+            ]
+        );
+    }
+}
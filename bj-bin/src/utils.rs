@@ -1,6 +1,7 @@
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 use std::fs::OpenOptions;
-use std::io::{self, Read, Write};
+use std::io::{self, BufReader, BufWriter, Read, Write};
 use xz2::read::XzDecoder;
 use xz2::write::XzEncoder;
 
@@ -26,6 +27,77 @@ where
     }
 }
 
+/// Read a file of JSON values one after another, usually one per line, deserializing each only as
+/// it's needed so even a file with millions of them (e.g. a long history of hands) can be gone
+/// through in little memory. With xz, files made by appending to a compressed file are read to
+/// the end, not just to the end of the first stream.
+pub fn read_json_lines<T>(
+    fd: impl Read + 'static,
+    xz: bool,
+) -> impl Iterator<Item = Result<T, serde_json::error::Error>>
+where
+    T: DeserializeOwned,
+{
+    let fd: Box<dyn Read> = if xz {
+        Box::new(XzDecoder::new_multi_decoder(fd))
+    } else {
+        Box::new(fd)
+    };
+    serde_json::Deserializer::from_reader(BufReader::new(fd)).into_iter()
+}
+
+enum Sink<W: Write> {
+    Plain(BufWriter<W>),
+    Xz(XzEncoder<BufWriter<W>>),
+}
+
+/// Writes JSON values one per line, as read_json_lines() reads them. Call finish() when done, as
+/// a compressed file isn't complete until then.
+pub struct JsonLinesWriter<W: Write> {
+    sink: Sink<W>,
+}
+
+impl<W: Write> JsonLinesWriter<W> {
+    pub fn new(fd: W, xz: bool) -> Self {
+        let fd = BufWriter::new(fd);
+        Self {
+            sink: if xz {
+                Sink::Xz(XzEncoder::new(fd, 9))
+            } else {
+                Sink::Plain(fd)
+            },
+        }
+    }
+
+    fn out(&mut self) -> &mut dyn Write {
+        match &mut self.sink {
+            Sink::Plain(w) => w,
+            Sink::Xz(w) => w,
+        }
+    }
+
+    pub fn write<T>(&mut self, data: &T) -> Result<(), serde_json::error::Error>
+    where
+        T: Serialize,
+    {
+        let out = self.out();
+        serde_json::to_writer(&mut *out, data)?;
+        out.write_all(b"\n").map_err(serde_json::error::Error::io)
+    }
+
+    /// Write out what's been buffered so far, e.g. so it isn't lost if the program is killed
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.out().flush()
+    }
+
+    pub fn finish(self) -> io::Result<()> {
+        match self.sink {
+            Sink::Plain(mut w) => w.flush(),
+            Sink::Xz(w) => w.finish()?.flush(),
+        }
+    }
+}
+
 /// Create the given file if it doesn't already exist. If it needs to be created, fill it with the
 /// given serializable data. Otherwise don't use the given data at all. Bubbles up any file system
 /// errors (other than the error of "already exists." Panics if unable to serialize/write the data
@@ -53,3 +125,32 @@ where
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn json_lines() {
+        for &xz in &[false, true] {
+            let mut buf = vec![];
+            // two writers, as if the file was appended to
+            for range in &[0..3, 3..5] {
+                let mut w = JsonLinesWriter::new(&mut buf, xz);
+                for i in range.clone() {
+                    w.write(&(i, i.to_string())).unwrap();
+                }
+                w.finish().unwrap();
+            }
+            let read: Vec<(u32, String)> = read_json_lines(io::Cursor::new(buf), xz)
+                .collect::<Result<_, _>>()
+                .unwrap();
+            assert_eq!(read.len(), 5);
+            assert_eq!(read[4], (4, "4".to_string()));
+        }
+        let mut bad = read_json_lines::<u32>(&b"1\n2\nx\n"[..], false);
+        assert_eq!(bad.next().unwrap().unwrap(), 1);
+        assert_eq!(bad.next().unwrap().unwrap(), 2);
+        assert!(bad.next().unwrap().is_err());
+    }
+}
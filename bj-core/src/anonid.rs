@@ -0,0 +1,197 @@
+//! An anonymous ID for the player, so a server can tell which uploads (see crate::proto) are from
+//! the same player without them making an account.
+//!
+//! The ID is a random UUID (version 4) made on the player's own device and kept there, e.g. in
+//! localStorage or in the CLI tools' config directory. Nothing about the player goes into it. The
+//! player can make a new one at any time, which cuts them off from everything uploaded under the
+//! old one, or opt out and upload without one.
+#[cfg(feature = "rand")]
+use rand::prelude::*;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::convert::TryFrom;
+use std::fmt;
+use std::str::FromStr;
+use thiserror::Error;
+
+#[derive(Debug, PartialEq, Error)]
+#[error("'{0}' is not an anonymous ID")]
+pub struct AnonIdError(String);
+
+/// A random UUID, e.g. "3f2a9c1e-7b4d-4e8f-a0c2-5d6e7f8a9b0c"
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(into = "String", try_from = "String")
+)]
+pub struct AnonId([u8; 16]);
+
+impl AnonId {
+    /// A new random ID
+    #[cfg(feature = "rand")]
+    pub fn generate() -> Self {
+        let mut b: [u8; 16] = thread_rng().gen();
+        // the version (4, random) and variant (RFC 4122) bits
+        b[6] = (b[6] & 0x0f) | 0x40;
+        b[8] = (b[8] & 0x3f) | 0x80;
+        Self(b)
+    }
+}
+
+impl fmt::Display for AnonId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, b) in self.0.iter().enumerate() {
+            if [4, 6, 8, 10].contains(&i) {
+                write!(f, "-")?;
+            }
+            write!(f, "{:02x}", b)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for AnonId {
+    type Err = AnonIdError;
+
+    /// Parse the form Display writes, in either case
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || AnonIdError(s.to_string());
+        let groups: Vec<&str> = s.split('-').collect();
+        if groups.iter().map(|g| g.len()).collect::<Vec<_>>() != [8, 4, 4, 4, 12] {
+            return Err(err());
+        }
+        let hex = groups.concat();
+        if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(err());
+        }
+        let mut b = [0; 16];
+        for (i, byte) in b.iter_mut().enumerate() {
+            // can't fail, as every char is a hex digit
+            *byte = u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16).map_err(|_| err())?;
+        }
+        Ok(Self(b))
+    }
+}
+
+impl From<AnonId> for String {
+    fn from(id: AnonId) -> Self {
+        id.to_string()
+    }
+}
+
+impl TryFrom<String> for AnonId {
+    type Error = AnonIdError;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+/// What's kept on the player's device: their ID, or None if they've opted out
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct AnonIdentity {
+    id: Option<AnonId>,
+}
+
+impl AnonIdentity {
+    /// A player with a new ID
+    #[cfg(feature = "rand")]
+    pub fn new() -> Self {
+        Self {
+            id: Some(AnonId::generate()),
+        }
+    }
+
+    /// A player who has opted out of having an ID
+    pub fn opted_out() -> Self {
+        Self { id: None }
+    }
+
+    /// The ID to upload with, if the player hasn't opted out
+    pub fn id(&self) -> Option<AnonId> {
+        self.id
+    }
+
+    pub fn is_opted_out(&self) -> bool {
+        self.id.is_none()
+    }
+
+    /// Replace the ID with a new one, opting back in if the player had opted out
+    #[cfg(feature = "rand")]
+    pub fn regenerate(&mut self) {
+        self.id = Some(AnonId::generate());
+    }
+
+    /// Forget the ID and upload without one from now on
+    pub fn opt_out(&mut self) {
+        self.id = None;
+    }
+}
+
+#[cfg(feature = "rand")]
+impl Default for AnonIdentity {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        let s = "3f2a9c1e-7b4d-4e8f-a0c2-5d6e7f8a9b0c";
+        let id: AnonId = s.parse().unwrap();
+        assert_eq!(id.to_string(), s);
+        assert_eq!(s.to_uppercase().parse::<AnonId>(), Ok(id));
+        for bad in &[
+            "",
+            "3f2a9c1e7b4d4e8fa0c25d6e7f8a9b0c",
+            "3f2a9c1e-7b4d-4e8f-a0c2-5d6e7f8a9b0",
+            "3f2a9c1e-7b4d-4e8f-a0c2-5d6e7f8a9b0g",
+            "3f2a9c1e-7b4d-4e8f-a0c2-5d6e7f8a9b0c-",
+            "+f2a9c1e-7b4d-4e8f-a0c2-5d6e7f8a9b0c",
+        ] {
+            assert_eq!(bad.parse::<AnonId>(), Err(AnonIdError(bad.to_string())));
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "rand")]
+    fn generate() {
+        let id = AnonId::generate();
+        assert_ne!(id, AnonId::generate());
+        let s = id.to_string();
+        assert_eq!(s.parse(), Ok(id));
+        // a version 4 UUID
+        assert_eq!(&s[14..15], "4");
+        assert!("89ab".contains(&s[19..20]));
+    }
+
+    #[test]
+    #[cfg(feature = "rand")]
+    fn identity() {
+        let mut ident = AnonIdentity::new();
+        let first = ident.id().unwrap();
+        ident.regenerate();
+        assert_ne!(ident.id(), Some(first));
+        ident.opt_out();
+        assert!(ident.is_opted_out() && ident.id().is_none());
+        ident.regenerate();
+        assert!(ident.id().is_some());
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn serde() {
+        let s = r#"{"id":"3f2a9c1e-7b4d-4e8f-a0c2-5d6e7f8a9b0c"}"#;
+        let ident: AnonIdentity = serde_json::from_str(s).unwrap();
+        assert_eq!(serde_json::to_string(&ident).unwrap(), s);
+        let out = AnonIdentity::opted_out();
+        assert_eq!(serde_json::to_string(&out).unwrap(), r#"{"id":null}"#);
+        assert!(serde_json::from_str::<AnonIdentity>(r#"{"id":"nope"}"#).is_err());
+    }
+}
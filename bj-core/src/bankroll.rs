@@ -0,0 +1,169 @@
+//! The player's chips, for games played for (pretend) money.
+//!
+//! Every bet has to be covered: the player can't bet more than they have, and can only double,
+//! split, or insure if they can put up the extra chips too. Chips only change hands when a round
+//! is settled (see settle), and the bankroll keeps lifetime totals so the player can see how
+//! they've done across sessions. Payouts like 3:2 and half bets back on surrender make chips
+//! fractional.
+use crate::settle::Settlement;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use thiserror::Error;
+
+#[derive(Debug, PartialEq, Error)]
+pub enum BankrollError {
+    /// Bets have to be more than nothing
+    #[error("Must bet at least 1 chip")]
+    ZeroBet,
+    /// The player needs this many chips at risk, but only has this many
+    #[error("That needs {0} chips, but there are only {1}")]
+    NotEnough(f64, f64),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(default))]
+pub struct Bankroll {
+    pub chips: f64,
+    /// Rounds settled
+    pub rounds: u32,
+    /// The chips bet on every round, counting doubles, splits, and insurance
+    pub wagered: f64,
+    /// The chips won, or lost if negative
+    pub net: f64,
+    /// The most chips the player has had
+    pub peak: f64,
+}
+
+impl Default for Bankroll {
+    fn default() -> Self {
+        Self::new(0.0)
+    }
+}
+
+impl Bankroll {
+    pub fn new(chips: f64) -> Self {
+        Self {
+            chips,
+            rounds: 0,
+            wagered: 0.0,
+            net: 0.0,
+            peak: chips,
+        }
+    }
+
+    /// Check the player can have the given number of chips at risk at once, e.g. their bet, or
+    /// everything on the table after a double or split
+    pub fn covers(&self, at_risk: f64) -> Result<(), BankrollError> {
+        if at_risk <= 0.0 {
+            Err(BankrollError::ZeroBet)
+        } else if at_risk > self.chips {
+            Err(BankrollError::NotEnough(at_risk, self.chips))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Pay out or collect a settled round
+    pub fn settle(&mut self, settlement: &Settlement) {
+        let net = settlement.net();
+        self.chips += net;
+        self.rounds += 1;
+        self.wagered += settlement.hands.iter().map(|h| h.stake).sum::<f64>()
+            + if settlement.insurance > 0.0 {
+                settlement.insurance / 2.0
+            } else {
+                -settlement.insurance
+            };
+        self.net += net;
+        self.peak = self.peak.max(self.chips);
+    }
+
+    /// Lose the given chips on a round that wasn't played out, e.g. because the player left
+    /// partway through it
+    pub fn forfeit(&mut self, stake: f64) {
+        self.chips -= stake;
+        self.rounds += 1;
+        self.wagered += stake;
+        self.net -= stake;
+    }
+
+    /// Whether the player has run out of chips to bet with
+    pub fn is_broke(&self) -> bool {
+        self.chips < 1.0
+    }
+
+    /// How the bankroll changed since the given earlier one, e.g. at the start of a session
+    pub fn since(&self, start: &Self) -> Self {
+        Self {
+            chips: self.chips,
+            rounds: self.rounds - start.rounds,
+            wagered: self.wagered - start.wagered,
+            net: self.net - start.net,
+            peak: self.peak,
+        }
+    }
+}
+
+impl fmt::Display for Bankroll {
+    /// E.g. "1012.5 chips, +12.5 over 20 rounds with 340 chips bet"
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} chips, {:+} over {} rounds with {} chips bet",
+            self.chips, self.net, self.rounds, self.wagered
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::basicstrategy::rules::SplitState;
+    use crate::deck::Rank;
+    use crate::hand::tests::hand;
+    use crate::settle::{settle, PlayedHand};
+
+    #[test]
+    fn covers() {
+        let b = Bankroll::new(100.0);
+        assert_eq!(b.covers(0.0), Err(BankrollError::ZeroBet));
+        assert_eq!(b.covers(100.0), Ok(()));
+        assert_eq!(b.covers(120.0), Err(BankrollError::NotEnough(120.0, 100.0)));
+        assert!(!b.is_broke());
+        assert!(Bankroll::new(0.5).is_broke());
+    }
+
+    #[test]
+    fn settling() {
+        use Rank::*;
+        let mut b = Bankroll::new(100.0);
+        let dealer = hand(&[RT, R8]);
+        // a doubled 19 that wins and a 17 that loses, with insurance lost
+        let mut doubled = PlayedHand::new(hand(&[R9, R2, R8]), SplitState::default());
+        doubled.doubled = true;
+        let lost = PlayedHand::new(hand(&[R9, R8]), SplitState::default());
+        let s = settle(10.0, &[doubled, lost], 5.0, &dealer, None);
+        b.settle(&s);
+        assert_eq!(b.chips, 100.0 + 20.0 - 10.0 - 5.0);
+        assert_eq!((b.rounds, b.wagered, b.net, b.peak), (1, 35.0, 5.0, 105.0));
+        // a blackjack pays 3:2
+        let start = b;
+        let bj = PlayedHand::new(hand(&[RA, RK]), SplitState::default());
+        b.settle(&settle(10.0, &[bj], 0.0, &dealer, None));
+        assert_eq!(b.chips, 120.0);
+        let session = b.since(&start);
+        assert_eq!(
+            (session.rounds, session.wagered, session.net),
+            (1, 10.0, 15.0)
+        );
+        assert_eq!(
+            session.to_string(),
+            "120 chips, +15 over 1 rounds with 10 chips bet"
+        );
+        // leaving a doubled hand loses both bets
+        b.forfeit(20.0);
+        assert_eq!(b.chips, 100.0);
+        assert_eq!((b.rounds, b.wagered, b.net, b.peak), (3, 65.0, 0.0, 120.0));
+    }
+}
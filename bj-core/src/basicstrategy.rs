@@ -1,5 +1,9 @@
+use crate::composition::{self, CompKey};
+use crate::deck::Card;
+use crate::hand::{Hand, HandType};
 use crate::resp::Resp;
-use crate::table::Table;
+use crate::table::{player_hand_from_desc, GameDesc, Table, TableError};
+#[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
 /// Blackjack table rules that affect basic strategy
@@ -11,16 +15,32 @@ use serde::{Deserialize, Serialize};
 /// possible. E.g. A surrender-always-allowed, 3-deck, hit-17, no-double-after-split, and
 /// no-peek-bj game probably doesn't exist, but it's a valid set of rules.
 pub mod rules {
+    use crate::button::GameButton;
+    use crate::deck::Card;
+    use crate::hand::Hand;
+    #[cfg(feature = "serde")]
     use serde::{Deserialize, Serialize};
     use std::convert::From;
     use std::fmt;
-    #[derive(Serialize, Deserialize, PartialEq, Debug, Copy, Clone)]
+    #[derive(PartialEq, Eq, Hash, Debug, Copy, Clone)]
+    #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
     pub enum Surrender {
         No,
         Yes,
         NotAce,
     }
 
+    impl Surrender {
+        /// A short, stable name for the rule, e.g. for keeping stats under
+        pub fn id(self) -> &'static str {
+            match self {
+                Self::No => "no",
+                Self::Yes => "yes",
+                Self::NotAce => "notace",
+            }
+        }
+    }
+
     impl fmt::Display for Surrender {
         fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
             write!(
@@ -35,7 +55,8 @@ pub mod rules {
         }
     }
 
-    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    #[derive(PartialEq, Debug, Clone)]
+    #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
     pub enum NumDecks {
         One,
         Two,
@@ -58,7 +79,8 @@ pub mod rules {
         }
     }
 
-    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    #[derive(PartialEq, Debug, Clone)]
+    #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
     pub struct HitSoft17(bool);
 
     impl fmt::Display for HitSoft17 {
@@ -81,7 +103,8 @@ pub mod rules {
         }
     }
 
-    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    #[derive(PartialEq, Debug, Clone)]
+    #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
     pub struct DAS(bool);
 
     impl fmt::Display for DAS {
@@ -96,7 +119,106 @@ pub mod rules {
         }
     }
 
-    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    /// What a player blackjack pays
+    #[derive(PartialEq, Debug, Copy, Clone)]
+    #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+    pub enum BlackjackPayout {
+        ThreeToTwo,
+        SixToFive,
+    }
+
+    impl BlackjackPayout {
+        /// What a blackjack wins per unit bet
+        pub fn ratio(self) -> f64 {
+            match self {
+                Self::ThreeToTwo => 1.5,
+                Self::SixToFive => 1.2,
+            }
+        }
+    }
+
+    impl Default for BlackjackPayout {
+        /// Cards made before this rule existed assumed the traditional payout
+        fn default() -> Self {
+            Self::ThreeToTwo
+        }
+    }
+
+    impl fmt::Display for BlackjackPayout {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(
+                f,
+                "{}",
+                match self {
+                    Self::ThreeToTwo => "3:2",
+                    Self::SixToFive => "6:5",
+                }
+            )
+        }
+    }
+
+    /// The most times the player may split in one round. Each split makes one more hand, so 3
+    /// splits is up to 4 hands.
+    #[derive(PartialEq, Debug, Clone, Copy)]
+    #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+    pub struct MaxSplits(u8);
+
+    impl Default for MaxSplits {
+        /// Cards made before this rule existed assumed the usual limit of 4 hands
+        fn default() -> Self {
+            Self(3)
+        }
+    }
+
+    impl fmt::Display for MaxSplits {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "{} (up to {} hands)", self.0, u16::from(self.0) + 1)
+        }
+    }
+
+    impl From<u8> for MaxSplits {
+        fn from(val: u8) -> Self {
+            MaxSplits(val)
+        }
+    }
+
+    /// Whether a hand made by splitting aces may be split again if dealt another ace
+    #[derive(PartialEq, Debug, Clone, Copy, Default)]
+    #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+    pub struct ResplitAces(bool);
+
+    impl fmt::Display for ResplitAces {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "{}", if self.0 { "allowed" } else { "disallowed" })
+        }
+    }
+
+    impl From<bool> for ResplitAces {
+        fn from(val: bool) -> Self {
+            ResplitAces(val)
+        }
+    }
+
+    /// Whether a hand made by splitting aces may be played on. If not, it gets one card and must
+    /// stand.
+    #[derive(PartialEq, Debug, Clone, Copy, Default)]
+    #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+    pub struct HitSplitAces(bool);
+
+    impl fmt::Display for HitSplitAces {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "{}", if self.0 { "allowed" } else { "disallowed" })
+        }
+    }
+
+    impl From<bool> for HitSplitAces {
+        fn from(val: bool) -> Self {
+            HitSplitAces(val)
+        }
+    }
+
+    #[derive(PartialEq, Debug, Clone)]
+    #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
     pub struct PeekBJ(bool);
 
     impl fmt::Display for PeekBJ {
@@ -111,30 +233,592 @@ pub mod rules {
         }
     }
 
-    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    /// What the player loses when the dealer doesn't peek for blackjack, then turns one over after
+    /// the player has already doubled or split. Meaningless if the dealer peeks.
+    ///
+    /// The two behave very differently: when only original bets are lost (OBO) strategy is nearly
+    /// the same as a peek game, but when all bets are lost (ENHC, the common European no-hole-card
+    /// game) it's wrong to put more money out against a ten or ace in many spots where a peek game
+    /// would, such as doubling 11 or splitting 8s.
+    #[derive(PartialEq, Debug, Copy, Clone)]
+    #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+    pub enum NoPeekLoss {
+        OriginalBetsOnly,
+        AllBets,
+    }
+
+    impl Default for NoPeekLoss {
+        /// Cards made before this rule existed assumed a dealer blackjack costs the player nothing
+        /// beyond their original bet
+        fn default() -> Self {
+            Self::OriginalBetsOnly
+        }
+    }
+
+    impl fmt::Display for NoPeekLoss {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(
+                f,
+                "{}",
+                match self {
+                    Self::OriginalBetsOnly => "original bets only",
+                    Self::AllBets => "all bets",
+                }
+            )
+        }
+    }
+
+    #[derive(PartialEq, Debug, Clone)]
+    #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
     pub struct Rules {
         pub decks: NumDecks,
         pub hit_soft_17: HitSoft17,
         pub double_after_split: DAS,
         pub peek_bj: PeekBJ,
         pub surrender: Surrender,
+        #[cfg_attr(feature = "serde", serde(default))]
+        pub no_peek_loss: NoPeekLoss,
+        #[cfg_attr(feature = "serde", serde(default))]
+        pub max_splits: MaxSplits,
+        #[cfg_attr(feature = "serde", serde(default))]
+        pub resplit_aces: ResplitAces,
+        #[cfg_attr(feature = "serde", serde(default))]
+        pub hit_split_aces: HitSplitAces,
+        #[cfg_attr(feature = "serde", serde(default))]
+        pub blackjack_pays: BlackjackPayout,
+    }
+
+    impl Default for Rules {
+        /// A typical shoe game: the dealer stands on soft 17 and peeks, doubling after splitting is
+        /// allowed, there's no surrender, and the rest are their defaults
+        fn default() -> Self {
+            Self {
+                decks: NumDecks::FourPlus,
+                hit_soft_17: false.into(),
+                double_after_split: true.into(),
+                peek_bj: true.into(),
+                surrender: Surrender::No,
+                no_peek_loss: Default::default(),
+                max_splits: Default::default(),
+                resplit_aces: Default::default(),
+                hit_split_aces: Default::default(),
+                blackjack_pays: Default::default(),
+            }
+        }
+    }
+
+    /// Where a hand came from, for the rules that only apply after splitting
+    #[derive(PartialEq, Debug, Clone, Copy, Default)]
+    pub struct SplitState {
+        /// How many times the player has split so far this round. 0 if the hand wasn't split.
+        pub splits: u8,
+        /// Whether the hand was made by splitting aces
+        pub aces: bool,
+    }
+
+    /// Which actions the player may take on a hand. Standing is always allowed.
+    #[derive(PartialEq, Debug, Clone, Copy)]
+    pub struct Allowed {
+        pub hit: bool,
+        pub double: bool,
+        pub split: bool,
+        pub surrender: bool,
+    }
+
+    impl Allowed {
+        /// What's allowed on an opening hand when only the surrender rule is known: anything the
+        /// hand allows
+        pub fn unsplit(hand: &Hand, dealer: Card, surrender: Surrender) -> Self {
+            Self {
+                hit: true,
+                double: hand.can_double(),
+                split: hand.can_split(),
+                surrender: hand.can_surrender(surrender, dealer),
+            }
+        }
+
+        /// What the given rules allow, or what's allowed without rules if they aren't known
+        pub fn for_hand(
+            hand: &Hand,
+            dealer: Card,
+            rules: Option<&Rules>,
+            split: SplitState,
+        ) -> Self {
+            match rules {
+                None => Self::without_rules(hand, dealer, split),
+                Some(rules) => rules.allowed(hand, dealer, split),
+            }
+        }
+
+        /// Whether the given action is allowed
+        pub fn permits(self, action: GameButton) -> bool {
+            match action {
+                GameButton::Stand => true,
+                GameButton::Hit => self.hit,
+                GameButton::Double => self.double,
+                GameButton::Split => self.split,
+                GameButton::Surrender => self.surrender,
+            }
+        }
+
+        /// Every allowed action, in the order of GameButton::ALL
+        pub fn actions(self) -> Vec<GameButton> {
+            GameButton::ALL
+                .iter()
+                .copied()
+                .filter(|a| self.permits(*a))
+                .collect()
+        }
+
+        /// What's allowed when the rules aren't known: anything the hand allows, with surrender
+        /// allowed (as in Trainer::surrender_rule()) and the split limits all defaulted
+        pub fn without_rules(hand: &Hand, dealer: Card, split: SplitState) -> Self {
+            let locked = split.aces && !HitSplitAces::default().0;
+            Self {
+                hit: !locked,
+                double: hand.can_double() && !locked,
+                split: hand.can_split()
+                    && split.splits < MaxSplits::default().0
+                    && (!split.aces || ResplitAces::default().0),
+                surrender: split.splits == 0 && hand.can_surrender(Surrender::Yes, dealer),
+            }
+        }
+    }
+
+    impl Rules {
+        /// Whether a dealer blackjack can take the extra money a player put out by doubling or
+        /// splitting. Only true in no-peek games where all bets are lost (ENHC).
+        pub fn dealer_bj_takes_all_bets(&self) -> bool {
+            !self.peek_bj.0 && self.no_peek_loss == NoPeekLoss::AllBets
+        }
+
+        /// Whether the dealer checks their hole card for blackjack when showing an ace or a ten
+        pub fn dealer_peeks(&self) -> bool {
+            self.peek_bj.0
+        }
+
+        /// Whether the dealer hits a soft 17 instead of standing on it
+        pub fn dealer_hits_soft_17(&self) -> bool {
+            self.hit_soft_17.0
+        }
+
+        /// The chance the player is dealt a blackjack that the dealer doesn't push, and so is paid.
+        /// Exact for the number of decks, taking 4+ decks to be 6.
+        pub fn paid_blackjack_chance(&self) -> f64 {
+            let decks: f64 = match self.decks {
+                NumDecks::One => 1.0,
+                NumDecks::Two => 2.0,
+                NumDecks::Three => 3.0,
+                NumDecks::FourPlus => 6.0,
+            };
+            let (aces, tens, cards) = (4.0 * decks, 16.0 * decks, 52.0 * decks);
+            let player = 2.0 * aces * tens / (cards * (cards - 1.0));
+            // the dealer draws from what's left after the player's ace and ten
+            let dealer = 2.0 * (aces - 1.0) * (tens - 1.0) / ((cards - 2.0) * (cards - 3.0));
+            player * (1.0 - dealer)
+        }
+
+        /// How much the blackjack payout costs the player compared to 3:2, as a fraction of their
+        /// initial bet per hand. E.g. about 0.0136 for 6:5 with 4+ decks, and 0 for 3:2. This
+        /// adds directly to the house edge.
+        pub fn payout_cost(&self) -> f64 {
+            self.paid_blackjack_chance()
+                * (BlackjackPayout::ThreeToTwo.ratio() - self.blackjack_pays.ratio())
+        }
+
+        /// What these rules let the player do with the given hand. Hands made by splitting can't
+        /// surrender, can only double if doubling after splitting is allowed, and can only split
+        /// again up to max_splits. Hands made by splitting aces can only be played on (hit or
+        /// doubled) if hit_split_aces, and only split again if resplit_aces.
+        pub fn allowed(&self, hand: &Hand, dealer: Card, split: SplitState) -> Allowed {
+            let locked = split.aces && !self.hit_split_aces.0;
+            Allowed {
+                hit: !locked,
+                double: hand.can_double()
+                    && !locked
+                    && (split.splits == 0 || self.double_after_split.0),
+                split: hand.can_split()
+                    && split.splits < self.max_splits.0
+                    && (!split.aces || self.resplit_aces.0),
+                surrender: split.splits == 0 && hand.can_surrender(self.surrender, dealer),
+            }
+        }
+
+        /// A short, stable string naming the rules, e.g. "4+/h17/das/peek/yes/original" or
+        /// "4+/h17/das/peek/yes/original/ms1/pays6to5". Rules added after the first six only
+        /// appear when they aren't their default, so adding a rule with a default that keeps the
+        /// old behavior doesn't change the id of any existing rules.
+        pub fn id(&self) -> String {
+            let decks = match self.decks {
+                NumDecks::One => "1",
+                NumDecks::Two => "2",
+                NumDecks::Three => "3",
+                NumDecks::FourPlus => "4+",
+            };
+            let flag = |on: bool, name: &str| format!("{}{}", if on { "" } else { "no" }, name);
+            let mut id = format!(
+                "{}/{}/{}/{}/{}/{}",
+                decks,
+                flag(self.hit_soft_17.0, "h17"),
+                flag(self.double_after_split.0, "das"),
+                flag(self.peek_bj.0, "peek"),
+                self.surrender.id(),
+                match self.no_peek_loss {
+                    NoPeekLoss::OriginalBetsOnly => "original",
+                    NoPeekLoss::AllBets => "all",
+                },
+            );
+            if self.max_splits != MaxSplits::default() {
+                id.push_str(&format!("/ms{}", self.max_splits.0));
+            }
+            if self.resplit_aces != ResplitAces::default() {
+                id.push_str(&format!("/{}", flag(self.resplit_aces.0, "rsa")));
+            }
+            if self.hit_split_aces != HitSplitAces::default() {
+                id.push_str(&format!("/{}", flag(self.hit_split_aces.0, "hsa")));
+            }
+            if self.blackjack_pays != BlackjackPayout::default() {
+                id.push_str(match self.blackjack_pays {
+                    BlackjackPayout::ThreeToTwo => "/pays3to2",
+                    BlackjackPayout::SixToFive => "/pays6to5",
+                });
+            }
+            id
+        }
     }
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct BasicStrategy {
     pub rules: Option<rules::Rules>,
+    /// Cards that say to split a hand that isn't a pair don't deserialize
+    #[cfg_attr(
+        feature = "serde",
+        serde(deserialize_with = "crate::table::deserialize_resp_table")
+    )]
     pub table: Table<Resp>,
+    /// Plays that depend on the exact cards, overriding the table. Empty for most cards. See
+    /// the composition module.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Vec::is_empty")
+    )]
+    pub composition: Vec<(CompKey, Resp)>,
+    /// Cells the player hasn't learned yet, in card order, if this is a partial card (see
+    /// from_partial()). Their table cells hold a placeholder, so they shouldn't be trained on or
+    /// graded. Empty for a complete card.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Vec::is_empty")
+    )]
+    pub unlearned: Vec<GameDesc>,
 }
 
 impl BasicStrategy {
     pub fn new(rules: Option<rules::Rules>, table: Table<Resp>) -> Self {
-        Self { rules, table }
+        Self {
+            rules,
+            table,
+            composition: vec![],
+            unlearned: vec![],
+        }
+    }
+
+    /// A partial card, for a player learning the card a piece at a time: None marks a cell they
+    /// haven't learned yet. Refuses the same tables Table::new_checked() does.
+    pub fn from_partial(
+        rules: Option<rules::Rules>,
+        partial: Table<Option<Resp>>,
+    ) -> Result<Self, TableError> {
+        let unlearned = partial.cells_where(|resp| resp.is_none());
+        let table = partial.map(|_, resp| resp.unwrap_or(Resp::Hit));
+        table.check_resps()?;
+        Ok(Self {
+            unlearned,
+            ..Self::new(rules, table)
+        })
+    }
+
+    /// Whether the card has a play for the given cell, which is always true unless it's partial
+    pub fn is_learned(&self, desc: GameDesc) -> bool {
+        !self.unlearned.contains(&desc)
+    }
+
+    pub fn is_partial(&self) -> bool {
+        !self.unlearned.is_empty()
+    }
+
+    /// The card's table with None in every cell not learned yet
+    pub fn partial_table(&self) -> Table<Option<Resp>> {
+        self.table
+            .map(|desc, resp| Some(resp).filter(|_| self.is_learned(desc)))
+    }
+
+    /// What the card says to do with the given hand: its composition-dependent play if it has
+    /// one, otherwise the play in its table cell
+    pub fn resp(&self, player_hand: &Hand, dealer_shows: Card) -> Result<Resp, TableError> {
+        match composition::lookup(&self.composition, player_hand, dealer_shows) {
+            Some(resp) => Ok(resp),
+            None => self.table.get(player_hand, dealer_shows),
+        }
+    }
+
+    /// What the card says to do with the given pair when it can't be split: play it as the plain
+    /// total it is. The card doesn't have pairs of 2s (hard 4) or aces (soft 12) as totals, but
+    /// both should always be hit.
+    pub fn unsplit_resp(&self, player: &Hand, dealer: Card) -> Result<Resp, TableError> {
+        let desc = GameDesc::from_hand(player, dealer);
+        let total = if desc.player == 22 {
+            GameDesc::new(HandType::Soft, 12, desc.dealer)
+        } else {
+            GameDesc::new(HandType::Hard, desc.player, desc.dealer)
+        };
+        match player_hand_from_desc(total) {
+            Ok(hand) => self.table.get(&hand, dealer),
+            Err(_) => Ok(Resp::Hit),
+        }
+    }
+
+    /// A short id for the card, the same for identical cards and all but certainly different for
+    /// cards that differ in any rule or cell. It's stable across versions and platforms, so it's
+    /// fit for keeping things like stats under. New rules leave it alone unless they're set to
+    /// something other than their default (see Rules::id()).
+    ///
+    /// It's the FNV-1a 64 bit hash, in 16 lowercase hex digits, of the rules' id ("none" if there
+    /// are no rules), a newline, and the code of every cell in the order of as_values_sorted().
+    /// Composition-dependent plays, if any, follow as a newline and "key=code" for each, and cells
+    /// not learned yet, if any, as a newline and "-label" for each.
+    pub fn id(&self) -> String {
+        let rules = self.rules.as_ref().map(|r| r.id());
+        let (hards, softs, pairs) = self.table.as_values_sorted();
+        let mut s = rules.as_deref().unwrap_or("none").to_string();
+        s.push('\n');
+        for resp in hards.iter().chain(softs.iter()).chain(pairs.iter()) {
+            s.push_str(resp.code());
+        }
+        for (key, resp) in &self.composition {
+            s.push_str(&format!("\n{}={}", key, resp.code()));
+        }
+        for desc in &self.unlearned {
+            s.push_str(&format!("\n-{}", desc.label()));
+        }
+        format!("{:016x}", fnv1a64(s.as_bytes()))
     }
 }
 
+fn fnv1a64(bytes: &[u8]) -> u64 {
+    let mut h: u64 = 0xcbf2_9ce4_8422_2325;
+    for b in bytes {
+        h ^= u64::from(*b);
+        h = h.wrapping_mul(0x0000_0100_0000_01b3);
+    }
+    h
+}
+
 impl From<(Option<rules::Rules>, Table<Resp>)> for BasicStrategy {
     fn from((rules, table): (Option<rules::Rules>, Table<Resp>)) -> Self {
         Self::new(rules, table)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::rules::*;
+
+    fn rules(peek: bool, no_peek_loss: NoPeekLoss) -> Rules {
+        Rules {
+            peek_bj: peek.into(),
+            no_peek_loss,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn takes_all_bets() {
+        assert!(!rules(true, NoPeekLoss::OriginalBetsOnly).dealer_bj_takes_all_bets());
+        assert!(!rules(true, NoPeekLoss::AllBets).dealer_bj_takes_all_bets());
+        assert!(!rules(false, NoPeekLoss::OriginalBetsOnly).dealer_bj_takes_all_bets());
+        assert!(rules(false, NoPeekLoss::AllBets).dealer_bj_takes_all_bets());
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn old_rules_deserialize() {
+        // rules serialized before no_peek_loss existed
+        let s = r#"{"decks":"One","hit_soft_17":false,"double_after_split":true,"peek_bj":true,"surrender":"No"}"#;
+        let r: Rules = serde_json::from_str(s).unwrap();
+        assert_eq!(r.no_peek_loss, NoPeekLoss::OriginalBetsOnly);
+        assert_eq!(r.max_splits, MaxSplits::from(3));
+        assert_eq!(r.resplit_aces, false.into());
+        assert_eq!(r.hit_split_aces, false.into());
+        assert_eq!(r.blackjack_pays, BlackjackPayout::ThreeToTwo);
+    }
+
+    #[test]
+    fn payout_cost() {
+        let mut r = rules(true, NoPeekLoss::OriginalBetsOnly);
+        assert_eq!(r.payout_cost(), 0.0);
+        r.blackjack_pays = BlackjackPayout::SixToFive;
+        // (2 * 24 * 96 / (312 * 311)) * (1 - 2 * 23 * 95 / (310 * 309)) * 0.3
+        assert!((r.payout_cost() - 0.013597).abs() < 1e-6);
+        r.decks = NumDecks::One;
+        assert!(r.payout_cost() > 0.013597);
+    }
+
+    #[test]
+    fn allowed_after_split() {
+        use crate::deck::{Card, Rank, Suit};
+        use crate::hand::Hand;
+        let c = |r| Card::new(r, Suit::Heart);
+        let eights = Hand::new(&[c(Rank::R8), c(Rank::R8)]);
+        let aces = Hand::new(&[c(Rank::RA), c(Rank::RA)]);
+        let mut r = rules(true, NoPeekLoss::OriginalBetsOnly);
+        r.surrender = Surrender::Yes;
+        let opening = r.allowed(&eights, c(Rank::RT), SplitState::default());
+        assert!(opening.hit && opening.double && opening.split && opening.surrender);
+        let split = |splits, aces| SplitState { splits, aces };
+        let after = r.allowed(&eights, c(Rank::RT), split(1, false));
+        assert!(after.hit && after.double && after.split && !after.surrender);
+        assert!(!r.allowed(&eights, c(Rank::RT), split(3, false)).split);
+        r.double_after_split = false.into();
+        assert!(!r.allowed(&eights, c(Rank::RT), split(1, false)).double);
+        // split aces get one card each and stand, unless the rules say otherwise
+        let a = r.allowed(&aces, c(Rank::RT), split(1, true));
+        assert!(!a.hit && !a.double && !a.split);
+        r.resplit_aces = true.into();
+        r.hit_split_aces = true.into();
+        let a = r.allowed(&aces, c(Rank::RT), split(1, true));
+        assert!(a.hit && !a.double && a.split);
+    }
+
+    #[test]
+    fn card_id() {
+        use super::{fnv1a64, BasicStrategy};
+        use crate::resp::{resps_from_buf, Resp};
+        use crate::table::Table;
+        assert_eq!(fnv1a64(b""), 0xcbf29ce484222325);
+        assert_eq!(fnv1a64(b"a"), 0xaf63dc4c8601ec8c);
+        let table =
+            Table::new(resps_from_buf(&include_bytes!("../../t1.txt")[..]).unwrap()).unwrap();
+        let none = BasicStrategy::new(None, table.clone());
+        assert_eq!(none.id().len(), 16);
+        assert_eq!(none.id(), none.clone().id());
+        let ruled = BasicStrategy::new(Some(rules(true, NoPeekLoss::AllBets)), table.clone());
+        assert_eq!(
+            ruled.rules.as_ref().unwrap().id(),
+            "4+/noh17/das/peek/no/all"
+        );
+        // as it was before the split and payout rules existed, and as it is with them set
+        assert_eq!(ruled.id(), "eae9912e72cd8dce");
+        let mut split_rules = ruled.clone();
+        if let Some(r) = &mut split_rules.rules {
+            r.max_splits = 1.into();
+            r.hit_split_aces = true.into();
+            r.blackjack_pays = BlackjackPayout::SixToFive;
+        }
+        assert_eq!(
+            split_rules.rules.as_ref().unwrap().id(),
+            "4+/noh17/das/peek/no/all/ms1/hsa/pays6to5"
+        );
+        assert_ne!(ruled.id(), split_rules.id());
+        assert_ne!(none.id(), ruled.id());
+        let other = BasicStrategy::new(Some(rules(false, NoPeekLoss::AllBets)), table);
+        assert_ne!(ruled.id(), other.id());
+        let mut edited = ruled.clone();
+        let v = edited.table.values_mut().next().unwrap();
+        *v = if *v == Resp::Hit {
+            Resp::Stand
+        } else {
+            Resp::Hit
+        };
+        assert_ne!(ruled.id(), edited.id());
+    }
+
+    #[test]
+    fn composition() {
+        use super::BasicStrategy;
+        use crate::deck::{Card, Rank, Suit};
+        use crate::hand::Hand;
+        use crate::resp::{resps_from_buf, Resp};
+        use crate::table::Table;
+        let table =
+            Table::new(resps_from_buf(&include_bytes!("../../t1.txt")[..]).unwrap()).unwrap();
+        let plain = BasicStrategy::new(None, table);
+        let mut comp = plain.clone();
+        // T1 hits 12 vs 3, but 10+2 is better off standing
+        comp.composition
+            .push(("T,2 vs 3".parse().unwrap(), Resp::Stand));
+        let c = |r| Card::new(r, Suit::Spade);
+        let ten_two = Hand::new(&[c(Rank::RK), c(Rank::R2)]);
+        let seven_five = Hand::new(&[c(Rank::R7), c(Rank::R5)]);
+        assert_eq!(plain.resp(&ten_two, c(Rank::R3)).unwrap(), Resp::Hit);
+        assert_eq!(comp.resp(&ten_two, c(Rank::R3)).unwrap(), Resp::Stand);
+        assert_eq!(comp.resp(&seven_five, c(Rank::R3)).unwrap(), Resp::Hit);
+        assert_ne!(plain.id(), comp.id());
+    }
+
+    #[test]
+    fn partial() {
+        use super::BasicStrategy;
+        use crate::hand::HandType;
+        use crate::resp::{resps_from_buf, Resp};
+        use crate::table::{GameDesc, Table, TableError};
+        let full = BasicStrategy::new(
+            None,
+            Table::new(resps_from_buf(&include_bytes!("../../t1.txt")[..]).unwrap()).unwrap(),
+        );
+        let sixteen = GameDesc::new(HandType::Hard, 16, 10);
+        let partial_table = full
+            .table
+            .map(|desc, resp| Some(resp).filter(|_| desc != sixteen));
+        let partial = BasicStrategy::from_partial(None, partial_table.clone()).unwrap();
+        assert!(partial.is_partial() && !full.is_partial());
+        assert_eq!(partial.unlearned, vec![sixteen]);
+        assert!(!partial.is_learned(sixteen));
+        assert_eq!(partial.partial_table(), partial_table);
+        assert_ne!(partial.id(), full.id());
+        // even with the placeholder in the unlearned cell matching the full card
+        let mut hit = full.clone();
+        for (_, resp) in hit.table.iter_mut().filter(|(d, _)| **d == sixteen) {
+            *resp = Resp::Hit;
+        }
+        assert_ne!(partial.id(), hit.id());
+        // a partial card can't split a non-pair any more than a full one can
+        let bad = partial_table.map(|desc, resp| {
+            if desc == GameDesc::new(HandType::Hard, 12, 2) {
+                Some(Resp::Split)
+            } else {
+                resp
+            }
+        });
+        assert!(matches!(
+            BasicStrategy::from_partial(None, bad),
+            Err(TableError::IllegalResp(..))
+        ));
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn partial_serde() {
+        use super::BasicStrategy;
+        use crate::resp::resps_from_buf;
+        use crate::table::Table;
+        let full = BasicStrategy::new(
+            None,
+            Table::new(resps_from_buf(&include_bytes!("../../t1.txt")[..]).unwrap()).unwrap(),
+        );
+        // complete cards serialize as they always have
+        assert!(!serde_json::to_string(&full).unwrap().contains("unlearned"));
+        let partial = BasicStrategy::from_partial(
+            None,
+            full.table
+                .map(|desc, resp| Some(resp).filter(|_| desc.player != 16)),
+        )
+        .unwrap();
+        let s = serde_json::to_string(&partial).unwrap();
+        let back: BasicStrategy = serde_json::from_str(&s).unwrap();
+        assert_eq!(back.unlearned.len(), 30);
+        assert_eq!(back.id(), partial.id());
+    }
+}
@@ -0,0 +1,209 @@
+//! How much a counter bets at each true count.
+//!
+//! A BetRamp is a list of steps, each a true count and the number of units to bet once the true
+//! count reaches it. Below the first step, the first step's bet is used. True counts are floored
+//! before looking them up, so a true count of 2.9 bets the same as 2 and -0.5 the same as -1.
+//!
+//! As text, a ramp is its steps separated by commas, each as count:units. The default ramp is
+//! "1:1,2:2,3:4,4:6,5:8", a 1-8 spread.
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::convert::TryFrom;
+use std::fmt;
+use std::str::FromStr;
+use thiserror::Error;
+
+/// The widest spread (biggest bet / smallest bet) any ramp may have
+pub const MAX_SPREAD: u32 = 100;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct BetStep {
+    /// The true count at which this bet starts
+    pub count: i8,
+    pub units: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(try_from = "Vec<BetStep>", into = "Vec<BetStep>")
+)]
+pub struct BetRamp(Vec<BetStep>);
+
+#[derive(Debug, PartialEq, Error)]
+pub enum BetRampError {
+    /// The ramp has no steps
+    #[error("A bet ramp needs at least one step")]
+    Empty,
+    /// A step bets nothing
+    #[error("The bet at true count {0} is 0 units")]
+    ZeroUnits(i8),
+    /// The counts aren't strictly increasing: the step at this count comes after one at the same
+    /// or a higher count
+    #[error("True count {0} is out of order")]
+    CountOrder(i8),
+    /// The step at this count bets less than the one before it
+    #[error("The bet at true count {0} is less than the one before it")]
+    Decreasing(i8),
+    /// The spread is wider than allowed. (spread, max)
+    #[error("A 1-{0} spread is wider than the max of 1-{1}")]
+    SpreadTooWide(u32, u32),
+    /// A step couldn't be parsed from text
+    #[error("'{0}' is not a count:units step")]
+    Parse(String),
+}
+
+impl BetRamp {
+    /// A ramp with the given steps, in order of increasing count. The bets must never go down,
+    /// and the spread can't be more than MAX_SPREAD.
+    pub fn new(steps: Vec<BetStep>) -> Result<Self, BetRampError> {
+        if steps.is_empty() {
+            return Err(BetRampError::Empty);
+        }
+        if let Some(s) = steps.iter().find(|s| s.units == 0) {
+            return Err(BetRampError::ZeroUnits(s.count));
+        }
+        for pair in steps.windows(2) {
+            if pair[1].count <= pair[0].count {
+                return Err(BetRampError::CountOrder(pair[1].count));
+            }
+            if pair[1].units < pair[0].units {
+                return Err(BetRampError::Decreasing(pair[1].count));
+            }
+        }
+        let ramp = Self(steps);
+        ramp.check_spread(MAX_SPREAD)?;
+        Ok(ramp)
+    }
+
+    pub fn steps(&self) -> &[BetStep] {
+        &self.0
+    }
+
+    /// The number of units to bet at the given true count
+    pub fn units(&self, true_count: f32) -> u32 {
+        let count = true_count.floor();
+        self.0
+            .iter()
+            .rev()
+            .find(|s| f32::from(s.count) <= count)
+            .unwrap_or(&self.0[0])
+            .units
+    }
+
+    /// The biggest bet divided by the smallest, rounded down. E.g. 8 for a 1-8 spread.
+    pub fn spread(&self) -> u32 {
+        // steps never go down, so the first is the smallest and the last the biggest
+        self.0[self.0.len() - 1].units / self.0[0].units
+    }
+
+    /// Fail if the spread is wider than max, e.g. the most a player thinks they can get away with
+    pub fn check_spread(&self, max: u32) -> Result<(), BetRampError> {
+        match self.spread() {
+            spread if spread > max => Err(BetRampError::SpreadTooWide(spread, max)),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl Default for BetRamp {
+    fn default() -> Self {
+        "1:1,2:2,3:4,4:6,5:8".parse().unwrap()
+    }
+}
+
+impl TryFrom<Vec<BetStep>> for BetRamp {
+    type Error = BetRampError;
+
+    fn try_from(steps: Vec<BetStep>) -> Result<Self, Self::Error> {
+        Self::new(steps)
+    }
+}
+
+impl From<BetRamp> for Vec<BetStep> {
+    fn from(ramp: BetRamp) -> Self {
+        ramp.0
+    }
+}
+
+impl FromStr for BetRamp {
+    type Err = BetRampError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let steps = s
+            .split(',')
+            .map(|step| {
+                let bad = || BetRampError::Parse(step.trim().to_string());
+                let mut parts = step.split(':');
+                match (parts.next(), parts.next(), parts.next()) {
+                    (Some(count), Some(units), None) => Ok(BetStep {
+                        count: count.trim().parse().map_err(|_| bad())?,
+                        units: units.trim().parse().map_err(|_| bad())?,
+                    }),
+                    _ => Err(bad()),
+                }
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Self::new(steps)
+    }
+}
+
+impl fmt::Display for BetRamp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let steps: Vec<String> = self
+            .0
+            .iter()
+            .map(|s| format!("{}:{}", s.count, s.units))
+            .collect();
+        write!(f, "{}", steps.join(","))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn units() {
+        let ramp = BetRamp::default();
+        assert_eq!(ramp.spread(), 8);
+        assert_eq!(ramp.units(-3.0), 1);
+        assert_eq!(ramp.units(1.0), 1);
+        assert_eq!(ramp.units(2.9), 2);
+        assert_eq!(ramp.units(4.0), 6);
+        assert_eq!(ramp.units(12.5), 8);
+        let ramp: BetRamp = "-1:2,1:4".parse().unwrap();
+        assert_eq!(ramp.units(-0.5), 2);
+        assert_eq!(ramp.units(-1.5), 2);
+        assert_eq!(ramp.to_string(), "-1:2,1:4");
+    }
+
+    #[test]
+    fn invalid() {
+        let parse = |s: &str| s.parse::<BetRamp>();
+        assert_eq!(parse("2:1,1:2"), Err(BetRampError::CountOrder(1)));
+        assert_eq!(parse("1:4,2:2"), Err(BetRampError::Decreasing(2)));
+        assert_eq!(parse("1:0"), Err(BetRampError::ZeroUnits(1)));
+        assert_eq!(
+            parse("1:1,2:500"),
+            Err(BetRampError::SpreadTooWide(500, 100))
+        );
+        assert_eq!(parse("1"), Err(BetRampError::Parse("1".to_string())));
+        assert_eq!(BetRamp::new(vec![]), Err(BetRampError::Empty));
+        assert_eq!(
+            BetRamp::default().check_spread(4),
+            Err(BetRampError::SpreadTooWide(8, 4))
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde() {
+        let ramp = BetRamp::default();
+        let s = serde_json::to_string(&ramp).unwrap();
+        assert_eq!(serde_json::from_str::<BetRamp>(&s).unwrap(), ramp);
+        assert!(serde_json::from_str::<BetRamp>(r#"[{"count":1,"units":0}]"#).is_err());
+    }
+}
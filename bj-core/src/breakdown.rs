@@ -0,0 +1,159 @@
+//! Accuracy added up by dealer upcard (the columns of a strategy card) and by player hand (its
+//! rows).
+//!
+//! A single cell rarely has enough hands in it to say much, but a whole column does: knowing that
+//! they're weak against a dealer 9, or on soft 18, tells a player more than which of the 360 cells
+//! they missed last. by_upcard() and by_player() give one Breakdown per column or row, in strategy
+//! card order and including those with nothing seen, so a chart of them always has the same axis.
+//! to_csv() writes them out for a spreadsheet; the front ends write them as JSON.
+use crate::hand::HandType;
+use crate::playstats::PlayStats;
+use crate::table::{GameDesc, Table};
+#[cfg(feature = "serde")]
+use serde::Serialize;
+
+/// The header to_csv() writes
+pub const CSV_HEADER: &str = "label,seen,correct,accuracy";
+
+/// A column or row of a strategy card, and how the player has done on it
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct Breakdown {
+    /// e.g. "A" for a column, or "Hard 16", "Soft 18", or "8,8" for a row
+    pub label: String,
+    pub seen: u32,
+    pub correct: u32,
+    /// correct / seen, or None if nothing was seen
+    pub accuracy: Option<f32>,
+}
+
+impl Breakdown {
+    fn new(label: String, stats: PlayStats) -> Self {
+        let accuracy = match stats.seen() {
+            0 => None,
+            seen => Some(stats.correct() as f32 / seen as f32),
+        };
+        Self {
+            label,
+            seen: stats.seen(),
+            correct: stats.correct(),
+            accuracy,
+        }
+    }
+}
+
+/// Accuracy against each dealer upcard, 2 through A
+pub fn by_upcard(stats: &Table<PlayStats>) -> Vec<Breakdown> {
+    (2..=11)
+        .map(|dealer| {
+            let col = stats
+                .iter()
+                .filter(|(desc, _)| desc.dealer == dealer)
+                .fold(PlayStats::new(), |acc, (_, s)| acc + *s);
+            let label = GameDesc::new(HandType::Hard, 0, dealer).dealer_label();
+            Breakdown::new(label, col)
+        })
+        .collect()
+}
+
+/// Accuracy on each player hand: the hard, then soft, then pair rows of a strategy card
+pub fn by_player(stats: &Table<PlayStats>) -> Vec<Breakdown> {
+    // cells are in card order, so a row's cells are next to each other
+    stats
+        .iter()
+        .fold(
+            Vec::<(GameDesc, PlayStats)>::new(),
+            |mut rows, (desc, s)| {
+                match rows.last_mut() {
+                    Some((row, acc)) if (row.hand, row.player) == (desc.hand, desc.player) => {
+                        *acc += *s
+                    }
+                    _ => rows.push((*desc, *s)),
+                }
+                rows
+            },
+        )
+        .into_iter()
+        .map(|(desc, s)| Breakdown::new(desc.player_label(), s))
+        .collect()
+}
+
+/// The breakdowns as CSV, with CSV_HEADER and then one line each. accuracy is left empty if
+/// nothing was seen.
+pub fn to_csv(rows: &[Breakdown]) -> String {
+    let mut csv = format!("{}\n", CSV_HEADER);
+    for row in rows {
+        csv.push_str(&format!(
+            "{},{},{},{}\n",
+            csv_field(&row.label),
+            row.seen,
+            row.correct,
+            row.accuracy.map_or(String::new(), |a| format!("{:.4}", a))
+        ));
+    }
+    csv
+}
+
+/// Quote a field if it has a comma or quote in it, like the "8,8" row
+fn csv_field(s: &str) -> String {
+    if s.contains(',') || s.contains('"') {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::table::NUM_CELLS;
+
+    fn stats() -> Table<PlayStats> {
+        let mut stats = Table::new(vec![PlayStats::new(); NUM_CELLS]).unwrap();
+        for (label, seen, correct) in &[
+            ("Hard 16 vs 9", 4, 1),
+            ("Soft 18 vs 9", 2, 1),
+            ("Hard 16 vs A", 3, 3),
+            ("8,8 vs 2", 1, 0),
+        ] {
+            let desc: GameDesc = label.parse().unwrap();
+            for (_, s) in stats.iter_mut().filter(|(d, _)| **d == desc) {
+                s.inc_by(*correct, true);
+                s.inc_by(seen - correct, false);
+            }
+        }
+        stats
+    }
+
+    #[test]
+    fn upcards() {
+        let cols = by_upcard(&stats());
+        let labels: Vec<_> = cols.iter().map(|c| c.label.as_str()).collect();
+        assert_eq!(
+            labels,
+            vec!["2", "3", "4", "5", "6", "7", "8", "9", "10", "A"]
+        );
+        assert_eq!((cols[0].seen, cols[0].correct), (1, 0));
+        assert_eq!((cols[7].seen, cols[7].correct), (6, 2));
+        assert_eq!(cols[9].accuracy, Some(1.0));
+        assert_eq!(cols[1].accuracy, None);
+    }
+
+    #[test]
+    fn players() {
+        let rows = by_player(&stats());
+        assert_eq!(rows.len(), 17 + 9 + 10);
+        assert_eq!(rows[0].label, "Hard 5");
+        let row = |label: &str| rows.iter().find(|r| r.label == label).unwrap();
+        assert_eq!((row("Hard 16").seen, row("Hard 16").correct), (7, 4));
+        assert_eq!(row("Soft 18").accuracy, Some(0.5));
+        assert_eq!(row("8,8").seen, 1);
+        assert_eq!(rows.last().unwrap().label, "A,A");
+        let csv = to_csv(&rows);
+        let mut lines = csv.lines();
+        assert_eq!(lines.next(), Some(CSV_HEADER));
+        assert_eq!(lines.next(), Some("Hard 5,0,0,"));
+        assert!(csv.contains("\nHard 16,7,4,0.5714\n"));
+        assert!(csv.contains("\n\"8,8\",1,0,0.0000\n"));
+    }
+}
@@ -0,0 +1,107 @@
+use crate::basicstrategy::rules::{Allowed, Rules, SplitState};
+use crate::deck::Card;
+use crate::hand::Hand;
+use crate::resp::Resp;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(rename_all = "lowercase")
+)]
+pub enum GameButton {
+    Hit,
+    Stand,
+    Double,
+    Split,
+    Surrender,
+}
+
+impl GameButton {
+    /// Every button, in the order they're shown
+    pub const ALL: [Self; 5] = [
+        Self::Hit,
+        Self::Stand,
+        Self::Double,
+        Self::Split,
+        Self::Surrender,
+    ];
+}
+
+/// Every action the player may take on the given hand under the given rules (or anything the hand
+/// allows if they aren't known), in the order of GameButton::ALL. This is the one place legality is
+/// decided; front ends should use it (or Trainer::is_legal(), which does) instead of checking the
+/// hand themselves.
+pub fn legal_actions(
+    hand: &Hand,
+    dealer: Card,
+    rules: Option<&Rules>,
+    split: SplitState,
+) -> Vec<GameButton> {
+    Allowed::for_hand(hand, dealer, rules, split).actions()
+}
+
+impl fmt::Display for GameButton {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::Hit => "Hit",
+                Self::Stand => "Stand",
+                Self::Double => "Double",
+                Self::Split => "Split",
+                Self::Surrender => "Surrender",
+            }
+        )
+    }
+}
+
+impl From<Resp> for GameButton {
+    /// The button to press first to follow the given response, e.g. Double for DoubleElseHit
+    fn from(resp: Resp) -> Self {
+        match resp {
+            Resp::Hit => Self::Hit,
+            Resp::Stand => Self::Stand,
+            Resp::DoubleElseHit | Resp::DoubleElseStand => Self::Double,
+            Resp::Split => Self::Split,
+            Resp::SurrenderElseHit | Resp::SurrenderElseStand | Resp::SurrenderElseSplit => {
+                Self::Surrender
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::deck::{Rank, Suit};
+
+    #[test]
+    fn legal() {
+        use GameButton::*;
+        let c = |r| Card::new(r, Suit::Heart);
+        let eights = Hand::new(&[c(Rank::R8), c(Rank::R8)]);
+        let opening = SplitState::default();
+        assert_eq!(
+            legal_actions(&eights, c(Rank::RT), None, opening),
+            vec![Hit, Stand, Double, Split, Surrender]
+        );
+        let after = SplitState {
+            splits: 1,
+            aces: false,
+        };
+        assert_eq!(
+            legal_actions(&eights, c(Rank::RT), None, after),
+            vec![Hit, Stand, Double, Split]
+        );
+        let three = Hand::new(&[c(Rank::R2), c(Rank::R3), c(Rank::R4)]);
+        assert_eq!(
+            legal_actions(&three, c(Rank::R5), None, opening),
+            vec![Hit, Stand]
+        );
+    }
+}
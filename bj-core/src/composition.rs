@@ -0,0 +1,193 @@
+//! Composition-dependent cells: strategy that depends on which cards make up a hand, not just its
+//! total.
+//!
+//! A Table has one cell per (hand type, total, dealer value), so 10+2 and 7+5 vs 4 share a cell,
+//! as do all of a dealer's tens. Some advanced cards split those cells. A CompKey names the exact
+//! ranks of the player's cards and the dealer's up card, and composition-dependent cards keep a
+//! short list of (CompKey, value) overrides alongside their Table.
+//!
+//! Looking a hand up in the overrides tries, in order:
+//!
+//! 1. its exact ranks, so "T,2 vs K" only matches a ten and a deuce against a king;
+//! 2. its ranks with every J, Q, and K counted as T, so "T,2 vs T" matches 10+2 or Q+2 against
+//!    any ten;
+//!
+//! and if neither is there, callers fall back to the coarse Table cell.
+//!
+//! As text, a CompKey is like a GameDesc label with every card spelled out, e.g. "T,2 vs 4" or
+//! "7,5 vs K". The player's cards can be in any order.
+#[cfg(feature = "rand")]
+use crate::deck::rand_suit;
+use crate::deck::{Card, Rank};
+use crate::hand::Hand;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+use thiserror::Error;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct CompKey {
+    /// The ranks of the player's cards, sorted
+    player: Vec<Rank>,
+    dealer: Rank,
+}
+
+#[derive(Debug, PartialEq, Error)]
+pub enum CompKeyError {
+    /// The string isn't like "T,2 vs 4"
+    #[error("'{0}' is not like 'T,2 vs 4'")]
+    BadKey(String),
+    /// The player needs at least two cards
+    #[error("The player needs at least two cards")]
+    TooFewCards,
+}
+
+fn ten_value(rank: Rank) -> Rank {
+    match rank {
+        Rank::RJ | Rank::RQ | Rank::RK => Rank::RT,
+        r => r,
+    }
+}
+
+/// The rank written as 2-9, T or 10, J, Q, K, or A, in either case
+pub fn parse_rank(s: &str) -> Option<Rank> {
+    Some(match s.trim().to_ascii_uppercase().as_str() {
+        "2" => Rank::R2,
+        "3" => Rank::R3,
+        "4" => Rank::R4,
+        "5" => Rank::R5,
+        "6" => Rank::R6,
+        "7" => Rank::R7,
+        "8" => Rank::R8,
+        "9" => Rank::R9,
+        "T" | "10" => Rank::RT,
+        "J" => Rank::RJ,
+        "Q" => Rank::RQ,
+        "K" => Rank::RK,
+        "A" => Rank::RA,
+        _ => return None,
+    })
+}
+
+impl CompKey {
+    pub fn new(player: &[Rank], dealer: Rank) -> Result<Self, CompKeyError> {
+        if player.len() < 2 {
+            return Err(CompKeyError::TooFewCards);
+        }
+        let mut player = player.to_vec();
+        player.sort();
+        Ok(Self { player, dealer })
+    }
+
+    /// The key for exactly the given hand and dealer card
+    pub fn from_hand(hand: &Hand, dealer: Card) -> Self {
+        let mut player: Vec<Rank> = hand.cards().map(|c| c.rank()).collect();
+        player.sort();
+        Self {
+            player,
+            dealer: dealer.rank(),
+        }
+    }
+
+    /// A hand and dealer card with exactly these ranks, in random suits
+    #[cfg(feature = "rand")]
+    pub fn rand_hand(&self) -> (Hand, Card) {
+        let cards: Vec<Card> = self
+            .player
+            .iter()
+            .map(|r| Card::new(*r, rand_suit()))
+            .collect();
+        (Hand::new(&cards), Card::new(self.dealer, rand_suit()))
+    }
+
+    /// This key with every J, Q, and K counted as T
+    fn ten_valued(&self) -> Self {
+        let mut player: Vec<Rank> = self.player.iter().map(|r| ten_value(*r)).collect();
+        player.sort();
+        Self {
+            player,
+            dealer: ten_value(self.dealer),
+        }
+    }
+}
+
+impl fmt::Display for CompKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // biggest first, the way people say them: T,2 not 2,T
+        let player: Vec<String> = self.player.iter().rev().map(|r| r.to_string()).collect();
+        write!(f, "{} vs {}", player.join(","), self.dealer)
+    }
+}
+
+impl FromStr for CompKey {
+    type Err = CompKeyError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let bad = || CompKeyError::BadKey(s.to_string());
+        let lower = s.to_ascii_lowercase();
+        let mut halves = lower.split(" vs ");
+        let (player, dealer) = match (halves.next(), halves.next(), halves.next()) {
+            (Some(p), Some(d), None) => (p, d),
+            _ => return Err(bad()),
+        };
+        let player = player
+            .split(',')
+            .map(parse_rank)
+            .collect::<Option<Vec<_>>>()
+            .ok_or_else(bad)?;
+        Self::new(&player, parse_rank(dealer).ok_or_else(bad)?)
+    }
+}
+
+/// Find the override for the given hand, if there is one. See the module docs for the order keys
+/// are tried in.
+pub fn lookup<T: Copy>(overrides: &[(CompKey, T)], hand: &Hand, dealer: Card) -> Option<T> {
+    let exact = CompKey::from_hand(hand, dealer);
+    let find = |key: &CompKey| overrides.iter().find(|(k, _)| k == key).map(|(_, v)| *v);
+    find(&exact).or_else(|| find(&exact.ten_valued()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::deck::Suit;
+
+    fn card(rank: Rank) -> Card {
+        Card::new(rank, Suit::Club)
+    }
+
+    #[test]
+    fn keys() {
+        let key: CompKey = "2,t vs k".parse().unwrap();
+        assert_eq!(key, CompKey::new(&[Rank::RT, Rank::R2], Rank::RK).unwrap());
+        assert_eq!(key.to_string(), "T,2 vs K");
+        assert_eq!(
+            "7,5,4 vs 10".parse::<CompKey>().unwrap().to_string(),
+            "7,5,4 vs T"
+        );
+        assert_eq!("T vs 4".parse::<CompKey>(), Err(CompKeyError::TooFewCards));
+        for bad in &["T,2", "T,2 vs", "T,X vs 4", "T,2 vs 4 vs 5"] {
+            assert!(bad.parse::<CompKey>().is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn fallback() {
+        let overrides = vec![
+            ("T,2 vs K".parse().unwrap(), 1),
+            ("T,2 vs T".parse().unwrap(), 2),
+            ("7,5 vs 4".parse().unwrap(), 3),
+        ];
+        let hand = |a, b| Hand::new(&[card(a), card(b)]);
+        let get = |h: &Hand, d| lookup(&overrides, h, card(d));
+        // exact match beats the ten-valued one
+        assert_eq!(get(&hand(Rank::RT, Rank::R2), Rank::RK), Some(1));
+        assert_eq!(get(&hand(Rank::R2, Rank::RQ), Rank::RJ), Some(2));
+        assert_eq!(get(&hand(Rank::R5, Rank::R7), Rank::R4), Some(3));
+        // same total, different cards
+        assert_eq!(get(&hand(Rank::R8, Rank::R4), Rank::R4), None);
+        assert_eq!(get(&hand(Rank::RT, Rank::R2), Rank::R9), None);
+    }
+}
@@ -0,0 +1,89 @@
+//! A confusion matrix of what the player pressed against what the card said to do.
+//!
+//! Each cell counts how many times the player pressed a button when the correct response was a
+//! given Resp. Compound responses like SurrenderElseStand can be gotten wrong in two ways: by not
+//! taking the first choice when it was allowed (Mistake::Primary), or by not taking the fallback
+//! when it wasn't (Mistake::Fallback). Both kinds are counted too, so a player who knows to
+//! surrender 16 vs T but hits it when they can't surrender can be told that it's the fallback they
+//! don't know.
+use crate::button::GameButton;
+use crate::correct_resp::Mistake;
+use crate::resp::Resp;
+use std::collections::HashMap;
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Confusion {
+    /// Times each button was pressed, by (correct response, button)
+    cells: HashMap<(Resp, GameButton), u32>,
+    /// Wrong answers of each kind
+    mistakes: HashMap<Mistake, u32>,
+}
+
+impl Confusion {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Count pressing btn when the correct response was correct. mistake is what they got wrong,
+    /// if anything. See correct_resp::mistake().
+    pub fn record(&mut self, correct: Resp, btn: GameButton, mistake: Option<Mistake>) {
+        *self.cells.entry((correct, btn)).or_insert(0) += 1;
+        if let Some(m) = mistake {
+            *self.mistakes.entry(m).or_insert(0) += 1;
+        }
+    }
+
+    /// Times btn was pressed when the correct response was correct
+    pub fn count(&self, correct: Resp, btn: GameButton) -> u32 {
+        self.cells.get(&(correct, btn)).copied().unwrap_or(0)
+    }
+
+    /// Wrong answers of the given kind
+    pub fn mistakes(&self, kind: Mistake) -> u32 {
+        self.mistakes.get(&kind).copied().unwrap_or(0)
+    }
+
+    /// Every non-empty cell as (correct response, button, count), most common first
+    pub fn cells(&self) -> Vec<(Resp, GameButton, u32)> {
+        let mut cells: Vec<_> = self.cells.iter().map(|((r, b), n)| (*r, *b, *n)).collect();
+        cells.sort_by(|a, b| {
+            b.2.cmp(&a.2)
+                .then_with(|| a.0.to_string().cmp(&b.0.to_string()))
+                .then_with(|| a.1.to_string().cmp(&b.1.to_string()))
+        });
+        cells
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn record() {
+        let mut c = Confusion::new();
+        assert!(c.is_empty());
+        c.record(Resp::SurrenderElseStand, GameButton::Surrender, None);
+        c.record(
+            Resp::SurrenderElseStand,
+            GameButton::Hit,
+            Some(Mistake::Fallback),
+        );
+        c.record(
+            Resp::SurrenderElseStand,
+            GameButton::Hit,
+            Some(Mistake::Fallback),
+        );
+        c.record(Resp::Stand, GameButton::Hit, Some(Mistake::Primary));
+        assert_eq!(c.count(Resp::SurrenderElseStand, GameButton::Hit), 2);
+        assert_eq!(c.count(Resp::Stand, GameButton::Stand), 0);
+        assert_eq!(c.mistakes(Mistake::Fallback), 2);
+        assert_eq!(c.mistakes(Mistake::Primary), 1);
+        assert_eq!(c.cells()[0], (Resp::SurrenderElseStand, GameButton::Hit, 2));
+        assert_eq!(c.cells().len(), 3);
+    }
+}
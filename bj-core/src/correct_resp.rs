@@ -1,9 +1,10 @@
+use crate::basicstrategy::rules::{Allowed, Surrender};
 use crate::button::GameButton;
-use bj_core::basicstrategy::rules::Surrender;
-use bj_core::deck::Card;
-use bj_core::hand::Hand;
-use bj_core::resp::Resp;
+use crate::deck::Card;
+use crate::hand::Hand;
+use crate::resp::Resp;
 
+/// Whether pressing btn follows the correct response for an unsplit hand
 pub fn is_correct_resp_button(
     btn: GameButton,
     correct: Resp,
@@ -11,36 +12,76 @@ pub fn is_correct_resp_button(
     surrender_rule: Surrender,
 ) -> bool {
     let (player, dealer) = hand;
-    let can_double = player.can_double();
-    let can_surrender = player.can_surrender(surrender_rule, dealer);
-    match btn {
-        GameButton::Split => correct == Resp::Split,
-        GameButton::Hit => {
-            correct == Resp::Hit
-                || correct == Resp::DoubleElseHit && !can_double
-                || correct == Resp::SurrenderElseHit && !can_surrender
-        }
-        GameButton::Stand => {
-            correct == Resp::Stand
-                || correct == Resp::DoubleElseStand && !can_double
-                || correct == Resp::SurrenderElseStand && !can_surrender
+    is_correct_allowed(
+        btn,
+        correct,
+        Allowed::unsplit(player, dealer, surrender_rule),
+    )
+}
+
+/// Which part of the correct response a wrong answer got wrong
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum Mistake {
+    /// The player didn't take the response's first choice (e.g. Surrender for SurrenderElseStand)
+    /// though they could have. Every mistake on a simple response like Hit is this kind.
+    Primary,
+    /// The first choice wasn't allowed, and the player didn't take the fallback (e.g. they hit on
+    /// SurrenderElseStand when they couldn't surrender)
+    Fallback,
+}
+
+/// What the player got wrong by pressing btn, or None if it follows the correct response. See
+/// expected_button().
+pub fn mistake(btn: GameButton, correct: Resp, allowed: Allowed) -> Option<Mistake> {
+    let expected = expected_button(correct, allowed);
+    if btn == expected {
+        None
+    } else if expected != GameButton::from(correct) {
+        Some(Mistake::Fallback)
+    } else {
+        Some(Mistake::Primary)
+    }
+}
+
+/// Whether pressing btn follows the correct response, given what the player may do. See
+/// expected_button().
+pub fn is_correct_allowed(btn: GameButton, correct: Resp, allowed: Allowed) -> bool {
+    btn == expected_button(correct, allowed)
+}
+
+/// The button that follows the correct response, given what the player may do. Compound
+/// responses fall back when their first choice isn't allowed, and hitting falls back to standing
+/// (e.g. on split aces). If correct is Split but splitting isn't allowed, the caller must first
+/// replace it with the response for the hand's plain total.
+pub fn expected_button(correct: Resp, allowed: Allowed) -> GameButton {
+    let or_else = |first, can, fallback| if can { first } else { fallback };
+    let expected = match correct {
+        Resp::Hit => GameButton::Hit,
+        Resp::Stand => GameButton::Stand,
+        Resp::Split => GameButton::Split,
+        Resp::DoubleElseHit => or_else(GameButton::Double, allowed.double, GameButton::Hit),
+        Resp::DoubleElseStand => or_else(GameButton::Double, allowed.double, GameButton::Stand),
+        Resp::SurrenderElseHit => {
+            or_else(GameButton::Surrender, allowed.surrender, GameButton::Hit)
         }
-        GameButton::Double => {
-            can_double && (correct == Resp::DoubleElseHit || correct == Resp::DoubleElseStand)
+        Resp::SurrenderElseStand => {
+            or_else(GameButton::Surrender, allowed.surrender, GameButton::Stand)
         }
-        GameButton::Surrender => {
-            can_surrender
-                && (correct == Resp::SurrenderElseHit
-                    || correct == Resp::SurrenderElseStand
-                    || correct == Resp::SurrenderElseSplit)
+        Resp::SurrenderElseSplit => {
+            or_else(GameButton::Surrender, allowed.surrender, GameButton::Split)
         }
-    }
+    };
+    or_else(
+        expected,
+        allowed.hit || expected != GameButton::Hit,
+        GameButton::Stand,
+    )
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "rand"))]
 mod tests {
     use super::*;
-    use bj_core::deck::{rand_card, Rank};
+    use crate::deck::{rand_card, Rank};
     use rand::prelude::*;
 
     const NUM_RAND_HANDS: usize = 5000;
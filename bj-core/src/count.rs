@@ -1,5 +1,16 @@
+//! Card counting systems, and keeping a count of a shoe with one.
+//!
+//! Each system is a CountSystem, which gives every card a value. HiLo is the one the index plays
+//! and betting advice (e.g. kelly) are for, and the others are for drilling. KO is
+//! unbalanced: a whole deck counts to +4, so its true count isn't centered on zero the way the
+//! balanced systems' are, and KO counters bet off the running count instead. AnySystem is any of
+//! them, picked at run time, e.g. by name.
 use crate::deck::{Card, Rank};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 use std::default::Default;
+use std::fmt;
+use std::str::FromStr;
 pub const DECK_LEN: u16 = 52;
 
 pub trait CountSystem {
@@ -9,7 +20,7 @@ pub trait CountSystem {
     }
 }
 
-#[derive(Default)]
+#[derive(Default, Debug, Clone, Copy, PartialEq)]
 pub struct HiLo;
 
 impl HiLo {
@@ -28,28 +39,224 @@ impl CountSystem for HiLo {
     }
 }
 
-pub struct StatefulHiLo {
-    hl: HiLo,
+/// Knock-Out, which is HiLo with 7s counted low. It's unbalanced: a whole deck counts to +4.
+#[derive(Default, Debug, Clone, Copy, PartialEq)]
+pub struct KO;
+
+impl KO {
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+impl CountSystem for KO {
+    fn card_value(&self, card: Card) -> i16 {
+        match card.rank() {
+            Rank::R2 | Rank::R3 | Rank::R4 | Rank::R5 | Rank::R6 | Rank::R7 => 1,
+            Rank::R8 | Rank::R9 => 0,
+            Rank::RT | Rank::RJ | Rank::RQ | Rank::RK | Rank::RA => -1,
+        }
+    }
+}
+
+/// Hi-Opt I, which leaves 2s and aces out
+#[derive(Default, Debug, Clone, Copy, PartialEq)]
+pub struct HiOptI;
+
+impl HiOptI {
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+impl CountSystem for HiOptI {
+    fn card_value(&self, card: Card) -> i16 {
+        match card.rank() {
+            Rank::R3 | Rank::R4 | Rank::R5 | Rank::R6 => 1,
+            Rank::R2 | Rank::R7 | Rank::R8 | Rank::R9 | Rank::RA => 0,
+            Rank::RT | Rank::RJ | Rank::RQ | Rank::RK => -1,
+        }
+    }
+}
+
+/// Hi-Opt II, a level 2 count that leaves aces out
+#[derive(Default, Debug, Clone, Copy, PartialEq)]
+pub struct HiOptII;
+
+impl HiOptII {
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+impl CountSystem for HiOptII {
+    fn card_value(&self, card: Card) -> i16 {
+        match card.rank() {
+            Rank::R4 | Rank::R5 => 2,
+            Rank::R2 | Rank::R3 | Rank::R6 | Rank::R7 => 1,
+            Rank::R8 | Rank::R9 | Rank::RA => 0,
+            Rank::RT | Rank::RJ | Rank::RQ | Rank::RK => -2,
+        }
+    }
+}
+
+/// Omega II, a level 2 count that counts 9s high and leaves aces out
+#[derive(Default, Debug, Clone, Copy, PartialEq)]
+pub struct OmegaII;
+
+impl OmegaII {
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+impl CountSystem for OmegaII {
+    fn card_value(&self, card: Card) -> i16 {
+        match card.rank() {
+            Rank::R4 | Rank::R5 | Rank::R6 => 2,
+            Rank::R2 | Rank::R3 | Rank::R7 => 1,
+            Rank::R8 | Rank::RA => 0,
+            Rank::R9 => -1,
+            Rank::RT | Rank::RJ | Rank::RQ | Rank::RK => -2,
+        }
+    }
+}
+
+/// The Zen count, a level 2 count with aces at -1
+#[derive(Default, Debug, Clone, Copy, PartialEq)]
+pub struct Zen;
+
+impl Zen {
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+impl CountSystem for Zen {
+    fn card_value(&self, card: Card) -> i16 {
+        match card.rank() {
+            Rank::R4 | Rank::R5 | Rank::R6 => 2,
+            Rank::R2 | Rank::R3 | Rank::R7 => 1,
+            Rank::R8 | Rank::R9 => 0,
+            Rank::RA => -1,
+            Rank::RT | Rank::RJ | Rank::RQ | Rank::RK => -2,
+        }
+    }
+}
+
+/// Any of the count systems, picked at run time. Named "hilo", "ko", "hiopt1", "hiopt2",
+/// "omega2", and "zen".
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum AnySystem {
+    #[default]
+    #[cfg_attr(feature = "serde", serde(rename = "hilo"))]
+    HiLo,
+    #[cfg_attr(feature = "serde", serde(rename = "ko"))]
+    KO,
+    #[cfg_attr(feature = "serde", serde(rename = "hiopt1"))]
+    HiOptI,
+    #[cfg_attr(feature = "serde", serde(rename = "hiopt2"))]
+    HiOptII,
+    #[cfg_attr(feature = "serde", serde(rename = "omega2"))]
+    OmegaII,
+    #[cfg_attr(feature = "serde", serde(rename = "zen"))]
+    Zen,
+}
+
+impl AnySystem {
+    pub const ALL: [Self; 6] = [
+        Self::HiLo,
+        Self::KO,
+        Self::HiOptI,
+        Self::HiOptII,
+        Self::OmegaII,
+        Self::Zen,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::HiLo => "hilo",
+            Self::KO => "ko",
+            Self::HiOptI => "hiopt1",
+            Self::HiOptII => "hiopt2",
+            Self::OmegaII => "omega2",
+            Self::Zen => "zen",
+        }
+    }
+
+    /// Whether a whole deck counts to zero. Only KO doesn't.
+    pub fn is_balanced(self) -> bool {
+        self != Self::KO
+    }
+}
+
+impl CountSystem for AnySystem {
+    fn card_value(&self, card: Card) -> i16 {
+        match self {
+            Self::HiLo => HiLo.card_value(card),
+            Self::KO => KO.card_value(card),
+            Self::HiOptI => HiOptI.card_value(card),
+            Self::HiOptII => HiOptII.card_value(card),
+            Self::OmegaII => OmegaII.card_value(card),
+            Self::Zen => Zen.card_value(card),
+        }
+    }
+}
+
+impl fmt::Display for AnySystem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for AnySystem {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .iter()
+            .find(|sys| sys.name() == s.to_lowercase())
+            .copied()
+            .ok_or_else(|| format!("Unknown count system {}", s))
+    }
+}
+
+/// A count of a shoe with the given system
+pub struct StatefulCount<S: CountSystem> {
+    system: S,
     num_decks: u8,
     seen_cards: u16,
     count: i16,
 }
 
-impl StatefulHiLo {
+pub type StatefulHiLo = StatefulCount<HiLo>;
+
+impl<S: CountSystem + Default> StatefulCount<S> {
     pub fn new(num_decks: u8) -> Self {
+        Self::with_system(S::default(), num_decks)
+    }
+}
+
+impl<S: CountSystem> StatefulCount<S> {
+    pub fn with_system(system: S, num_decks: u8) -> Self {
         assert!(num_decks > 0);
         Self {
-            hl: HiLo::new(),
+            system,
             num_decks,
             seen_cards: 0,
             count: 0,
         }
     }
 
+    pub fn system(&self) -> &S {
+        &self.system
+    }
+
     pub fn update(&mut self, card: Card) {
         assert!(self.seen_cards < u16::from(self.num_decks) * DECK_LEN);
         self.seen_cards += 1;
-        self.count += self.hl.card_value(card);
+        self.count += self.system.card_value(card);
     }
 
     pub fn update_many(&mut self, cards: &[Card]) {
@@ -58,6 +265,8 @@ impl StatefulHiLo {
         }
     }
 
+    /// The running count per deck left. For an unbalanced system like KO it drifts up as the
+    /// shoe is dealt, even when the cards left are no richer in tens.
     pub fn true_count(&self) -> f32 {
         let decks_remaining =
             f32::from(self.num_decks) - (f32::from(self.seen_cards) / f32::from(DECK_LEN));
@@ -70,11 +279,17 @@ impl StatefulHiLo {
     pub fn running_count(&self) -> i16 {
         self.count
     }
+
+    /// Forget every card seen, as when the cards are shuffled
+    pub fn reset(&mut self) {
+        self.seen_cards = 0;
+        self.count = 0;
+    }
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "rand"))]
 mod hilo_tests {
-    use super::{CountSystem, HiLo, StatefulHiLo};
+    use super::{AnySystem, CountSystem, HiLo, StatefulCount, StatefulHiLo};
     use crate::deck::{Deck, Rank};
 
     #[test]
@@ -94,6 +309,38 @@ mod hilo_tests {
         }
     }
 
+    #[test]
+    fn other_systems() {
+        use crate::deck::{Card, Suit};
+        let mut d = Deck::new();
+        let mut cards = vec![];
+        while let Ok(c) = d.draw() {
+            cards.push(c);
+        }
+        for sys in AnySystem::ALL.iter() {
+            let expect = if sys.is_balanced() { 0 } else { 4 };
+            assert_eq!(sys.cards_value(&cards), expect, "{}", sys);
+            assert_eq!(sys.to_string().parse(), Ok(*sys));
+        }
+        assert!("ZEN".parse::<AnySystem>().is_ok());
+        assert!("wong halves".parse::<AnySystem>().is_err());
+        let value = |sys: AnySystem, rank| sys.card_value(Card::new(rank, Suit::Club));
+        assert_eq!(value(AnySystem::KO, Rank::R7), 1);
+        assert_eq!(value(AnySystem::HiOptI, Rank::R2), 0);
+        assert_eq!(value(AnySystem::HiOptII, Rank::R5), 2);
+        assert_eq!(value(AnySystem::OmegaII, Rank::R9), -1);
+        assert_eq!(value(AnySystem::Zen, Rank::RA), -1);
+        assert_eq!(value(AnySystem::Zen, Rank::RK), -2);
+        // counts keep the system's running count
+        let mut zen = StatefulCount::with_system(AnySystem::Zen, 1);
+        zen.update_many(&[
+            Card::new(Rank::R5, Suit::Club),
+            Card::new(Rank::R3, Suit::Club),
+        ]);
+        assert_eq!(zen.running_count(), 3);
+        assert_eq!(*zen.system(), AnySystem::Zen);
+    }
+
     #[test]
     fn full_deck_correct() {
         // store a full deck in a vector and get its count. Should be 0.
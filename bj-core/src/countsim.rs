@@ -0,0 +1,312 @@
+//! What counting is worth in a shoe game, and why it's worth nothing against a continuous shuffle
+//! machine.
+//!
+//! simulate() deals rounds of cards and, before each, bets what a bet ramp says to at the HiLo true
+//! count, adding up the player's advantage (see kelly::Advantage) on every unit bet. A shoe is
+//! dealt down to its penetration before it's shuffled, so the count wanders away from zero and the
+//! ramp's big bets go out when the player has the edge. A continuous shuffle machine (see
+//! Deck::new_csm()) takes back every round's cards, so the true count before a round is always
+//! zero and the ramp never leaves its smallest bet.
+//!
+//! A real counter can't always bet the ramp. Constraints model what gets in the way: sessions that
+//! end before the shoe does, sitting out a shoe until the count is good (wonging in) and leaving
+//! it once the count goes bad (wonging out), flat bets now and then to look like a tourist (cover
+//! plays), and the table's limits. The default is no constraints at all.
+//!
+//! Rounds are a fixed number of cards, and the edge and variance are those kelly works out for a
+//! round off the top of a shoe at the count, not for the cards actually left, so the numbers are
+//! for comparing games and ways of playing them with each other more than for planning a
+//! bankroll.
+use crate::betramp::BetRamp;
+use crate::count::StatefulHiLo;
+use crate::deck::Deck;
+use crate::kelly::Advantage;
+
+/// Cards in a typical heads up round: the player's and dealer's hands, and a hit or two
+pub const CARDS_PER_ROUND: u8 = 5;
+
+/// What keeps the player from betting the ramp every round
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Constraints {
+    /// Rounds played before the session ends, at least 1. The next one starts at another table,
+    /// with a freshly shuffled shoe and the count back at zero.
+    pub session_rounds: Option<u32>,
+    /// After a shuffle, watch rounds without betting until the true count reaches this
+    pub wong_in: Option<i8>,
+    /// Stop betting when the true count drops below this, and watch until the next shuffle
+    pub wong_out: Option<i8>,
+    /// Every this many rounds played, bet the ramp's smallest bet whatever the count
+    pub cover_every: Option<u32>,
+    /// The table's smallest bet, in units. Smaller bets are raised to it.
+    pub table_min: Option<u32>,
+    /// The table's biggest bet, in units. Bigger bets are cut to it.
+    pub table_max: Option<u32>,
+}
+
+impl Constraints {
+    /// What to bet, given what the ramp says to and whether this round is a cover play. Bets are
+    /// raised to the table's minimum before they are cut to its maximum. The second value is
+    /// whether the table's maximum cut the bet.
+    fn bet(&self, ramp: &BetRamp, true_count: f32, cover: bool) -> (u32, bool) {
+        let units = if cover {
+            ramp.units(f32::MIN)
+        } else {
+            ramp.units(true_count)
+        };
+        let units = units.max(self.table_min.unwrap_or(0));
+        match self.table_max {
+            Some(max) if units > max => (max, true),
+            _ => (units, false),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SimOpts {
+    pub num_decks: u8,
+    /// The fraction of a shoe dealt before it is shuffled, e.g. 0.75. Unused for a CSM.
+    pub penetration: f32,
+    pub cards_per_round: u8,
+    pub rounds: u32,
+    /// Deal from a continuous shuffle machine instead of a shoe
+    pub csm: bool,
+    pub constraints: Constraints,
+}
+
+impl Default for SimOpts {
+    fn default() -> Self {
+        Self {
+            num_decks: 6,
+            penetration: 0.75,
+            cards_per_round: CARDS_PER_ROUND,
+            rounds: 100_000,
+            csm: false,
+            constraints: Constraints::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct SimReport {
+    /// Rounds the player bet on
+    pub rounds: u32,
+    /// Rounds dealt while the player watched without betting, because they hadn't wonged in yet
+    /// or had wonged out
+    pub rounds_watched: u32,
+    /// Rounds that started with the player having the edge
+    pub rounds_with_edge: u32,
+    /// Rounds flat bet as cover
+    pub cover_plays: u32,
+    /// Rounds the table's maximum bet cut the player's bet
+    pub capped_bets: u32,
+    pub units_bet: u64,
+    /// The expected units won, negative if lost
+    pub units_won: f64,
+    /// The variance of the units won, in squared units
+    pub variance: f64,
+}
+
+impl SimReport {
+    /// The fraction of rounds that started with the player having the edge
+    pub fn share_with_edge(&self) -> f64 {
+        if self.rounds == 0 {
+            0.0
+        } else {
+            f64::from(self.rounds_with_edge) / f64::from(self.rounds)
+        }
+    }
+
+    /// The expected units won per unit bet, negative if the house has the edge
+    pub fn edge(&self) -> f64 {
+        if self.units_bet == 0 {
+            0.0
+        } else {
+            self.units_won / self.units_bet as f64
+        }
+    }
+
+    /// The standard deviation of the units won over every round
+    pub fn std_dev(&self) -> f64 {
+        self.variance.sqrt()
+    }
+
+    /// The chance of ever losing a bankroll of the given number of units, playing this way for as
+    /// long as it takes. 1 if the player doesn't have the edge.
+    pub fn risk_of_ruin(&self, bankroll: f64) -> f64 {
+        if self.units_won <= 0.0 || self.variance <= 0.0 {
+            1.0
+        } else {
+            (-2.0 * self.units_won * bankroll / self.variance).exp()
+        }
+    }
+}
+
+/// Deal the given number of rounds, betting with the given ramp wherever the constraints let the
+/// player, who has the given advantage at each count
+pub fn simulate(opts: &SimOpts, ramp: &BetRamp, advantage: &Advantage) -> SimReport {
+    let num_decks = opts.num_decks.max(1);
+    let per_round = usize::from(opts.cards_per_round.max(1));
+    let limits = &opts.constraints;
+    let mut deck = if opts.csm {
+        Deck::new_csm(usize::from(num_decks))
+    } else {
+        Deck::with_length(usize::from(num_decks))
+    };
+    let cut = (f32::from(num_decks) * 52.0 * opts.penetration.clamp(0.0, 1.0)) as usize;
+    let mut count = StatefulHiLo::new(num_decks);
+    let mut report = SimReport::default();
+    // whether the player is betting, and whether they've wonged out of this shoe
+    let mut seated = limits.wong_in.is_none();
+    let mut left = false;
+    let mut session_rounds = 0;
+    for _ in 0..opts.rounds {
+        let shuffle = limits.session_rounds.map(|n| n.max(1)) == Some(session_rounds)
+            || (!opts.csm && deck.dealt() + per_round > cut.max(per_round));
+        if shuffle {
+            deck.shuffle();
+            count.reset();
+            seated = limits.wong_in.is_none();
+            left = false;
+            session_rounds = 0;
+        }
+        let true_count = count.true_count();
+        if limits.wong_out.is_some_and(|w| true_count < f32::from(w)) && seated {
+            seated = false;
+            left = true;
+        }
+        if !seated && !left && limits.wong_in.is_none_or(|w| true_count >= f32::from(w)) {
+            seated = true;
+        }
+        if seated {
+            session_rounds += 1;
+            let cover = limits
+                .cover_every
+                .is_some_and(|n| session_rounds % n.max(1) == 0);
+            let (units, capped) = limits.bet(ramp, true_count, cover);
+            let edge = advantage.at(true_count);
+            report.rounds += 1;
+            report.rounds_with_edge += u32::from(edge > 0.0);
+            report.cover_plays += u32::from(cover);
+            report.capped_bets += u32::from(capped);
+            report.units_bet += u64::from(units);
+            report.units_won += f64::from(units) * edge;
+            report.variance += f64::from(units).powi(2) * advantage.variance;
+        } else {
+            report.rounds_watched += 1;
+        }
+        for _ in 0..per_round {
+            match deck.draw() {
+                Ok(card) => count.update(card),
+                // only if the cut card is past the end of the shoe
+                Err(_) => {
+                    deck.shuffle();
+                    count.reset();
+                }
+            }
+        }
+        if opts.csm {
+            deck.end_round();
+            count.reset();
+        }
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn csm_never_has_edge() {
+        let ramp: BetRamp = "1:1,2:4,3:8,4:12".parse().unwrap();
+        let adv = Advantage::new(None);
+        let opts = SimOpts {
+            rounds: 20_000,
+            ..Default::default()
+        };
+        let shoe = simulate(&opts, &ramp, &adv);
+        let csm = simulate(&SimOpts { csm: true, ..opts }, &ramp, &adv);
+        assert_eq!((shoe.rounds, csm.rounds), (20_000, 20_000));
+        assert!(shoe.rounds_with_edge > 0);
+        assert!(shoe.edge() > csm.edge());
+        assert_eq!(csm.rounds_with_edge, 0);
+        assert_eq!(csm.units_bet, 20_000);
+        assert!((csm.edge() - adv.at(0.0)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn constraints() {
+        let ramp: BetRamp = "1:1,2:4,3:8,4:12".parse().unwrap();
+        let adv = Advantage::new(None);
+        let opts = SimOpts {
+            rounds: 20_000,
+            ..Default::default()
+        };
+        let free = simulate(&opts, &ramp, &adv);
+        let limited = |constraints| {
+            simulate(
+                &SimOpts {
+                    constraints,
+                    ..opts
+                },
+                &ramp,
+                &adv,
+            )
+        };
+        // betting only at +2 and up only bets with the edge, so it's worth more per unit bet
+        let wong = limited(Constraints {
+            wong_in: Some(2),
+            wong_out: Some(2),
+            ..Default::default()
+        });
+        assert_eq!(wong.rounds + wong.rounds_watched, 20_000);
+        assert!(wong.rounds_watched > 0);
+        assert_eq!(wong.rounds_with_edge, wong.rounds);
+        assert!(wong.edge() > free.edge());
+        // flat betting every round is back to the house edge
+        let cover = limited(Constraints {
+            cover_every: Some(1),
+            ..Default::default()
+        });
+        assert_eq!((cover.cover_plays, cover.units_bet), (20_000, 20_000));
+        assert!(cover.edge() < 0.0);
+        assert_eq!(cover.risk_of_ruin(1000.0), 1.0);
+        // the table's limits squeeze the spread
+        let table = limited(Constraints {
+            table_min: Some(2),
+            table_max: Some(4),
+            ..Default::default()
+        });
+        assert!(table.capped_bets > 0);
+        assert!(table.units_bet >= 40_000 && table.units_bet <= 80_000);
+        assert!(table.edge() < free.edge());
+        // one round sessions never get past the first round of a shoe, where the count is zero
+        let short = limited(Constraints {
+            session_rounds: Some(1),
+            ..Default::default()
+        });
+        assert_eq!((short.rounds_with_edge, short.units_bet), (0, 20_000));
+        // as are zero round ones, rather than every round getting a fresh shoe
+        let zero = limited(Constraints {
+            session_rounds: Some(0),
+            ..Default::default()
+        });
+        assert_eq!(zero, short);
+    }
+
+    #[test]
+    fn risk() {
+        let ramp: BetRamp = "1:1,2:4,3:8,4:12".parse().unwrap();
+        let adv = Advantage::new(None);
+        let opts = SimOpts {
+            rounds: 20_000,
+            ..Default::default()
+        };
+        let r = simulate(&opts, &ramp, &adv);
+        assert!(r.units_won > 0.0);
+        assert!(r.std_dev() > 0.0);
+        // more money, less risk
+        assert!(r.risk_of_ruin(100.0) > r.risk_of_ruin(1000.0));
+        assert!(r.risk_of_ruin(0.0) == 1.0);
+    }
+}
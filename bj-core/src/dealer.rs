@@ -0,0 +1,123 @@
+//! How the dealer plays their hand: the house's drawing rules, implemented once.
+//!
+//! The dealer has no choices. They draw until their hand is worth at least 17, and stand there,
+//! except that in H17 games they also hit a soft 17. A natural is never drawn to. Aces count as
+//! 11 whenever that doesn't bust the hand (see Hand::value()), so A,6 is a soft 17 and A,A,5 is
+//! one too, while A,6,T is a hard 17.
+use crate::basicstrategy::rules::Rules;
+use crate::deck::{Card, DeckError};
+use crate::hand::Hand;
+
+/// The dealer's hand as it was after each card, from the two they were dealt to the one they
+/// stood or busted with
+#[derive(Debug, Clone, PartialEq)]
+pub struct DealerResult {
+    sequence: Vec<Hand>,
+}
+
+impl DealerResult {
+    /// The hand the dealer finished with
+    pub fn hand(&self) -> &Hand {
+        // there's always at least the two card hand
+        self.sequence.last().unwrap()
+    }
+
+    /// Every hand the dealer had, starting with the two cards they were dealt
+    pub fn sequence(&self) -> &[Hand] {
+        &self.sequence
+    }
+
+    pub fn value(&self) -> u8 {
+        self.hand().value()
+    }
+
+    pub fn is_bust(&self) -> bool {
+        self.hand().is_bust()
+    }
+
+    /// Whether the dealer had a natural, and so didn't draw
+    pub fn is_blackjack(&self) -> bool {
+        self.hand().is_blackjack()
+    }
+}
+
+/// Whether the dealer draws to the hand. If the rules aren't known, the dealer hits soft 17.
+pub fn draws(hand: &Hand, rules: Option<&Rules>) -> bool {
+    let hits_soft_17 = rules.is_none_or(|r| r.dealer_hits_soft_17());
+    hand.value() < 17 || (hand.value() == 17 && hand.is_soft() && hits_soft_17)
+}
+
+/// Play out the dealer's hand, drawing each card from shoe. If the rules aren't known, the dealer
+/// hits soft 17. Errors if the shoe runs out.
+///
+/// To draw from a Deck, pass `|| deck.draw()` as the shoe.
+pub fn play_out<F>(
+    up: Card,
+    hole: Card,
+    mut shoe: F,
+    rules: Option<&Rules>,
+) -> Result<DealerResult, DeckError>
+where
+    F: FnMut() -> Result<Card, DeckError>,
+{
+    let mut hand = Hand::new(&[up, hole]);
+    let mut sequence = vec![hand.clone()];
+    while !hand.is_blackjack() && draws(&hand, rules) {
+        hand.push(shoe()?);
+        sequence.push(hand.clone());
+    }
+    Ok(DealerResult { sequence })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::deck::{Rank, Suit};
+
+    fn rules(hit_soft_17: bool) -> Rules {
+        Rules {
+            hit_soft_17: hit_soft_17.into(),
+            ..Default::default()
+        }
+    }
+
+    /// Play out the dealer's hand with the first two ranks dealt and the rest as the shoe
+    fn play(ranks: &[Rank], hit_soft_17: bool) -> Result<DealerResult, DeckError> {
+        let card = |r: &Rank| Card::new(*r, Suit::Diamond);
+        let mut shoe = ranks[2..].iter().map(card);
+        let r = rules(hit_soft_17);
+        play_out(
+            card(&ranks[0]),
+            card(&ranks[1]),
+            || shoe.next().ok_or(DeckError::OutOfCards),
+            Some(&r),
+        )
+    }
+
+    #[test]
+    fn drawing_rules() {
+        use Rank::*;
+        // soft 17: H17 hits, S17 stands
+        assert_eq!(play(&[RA, R6, RT], true).unwrap().value(), 17);
+        assert_eq!(play(&[RA, R6, RT], true).unwrap().sequence().len(), 2);
+        assert_eq!(play(&[RA, R6], false).unwrap().value(), 17);
+        // many aces: A,A,A,A,A,A,A is a soft 17, and A,A,A,A,A,A,A,T a hard 17
+        let many = play(&[RA, RA, RA, RA, RA, RA, RA, RT], true).unwrap();
+        assert_eq!(many.sequence().len(), 7);
+        assert_eq!(many.value(), 17);
+        assert!(!many.hand().is_soft());
+        assert_eq!(
+            play(&[RA, RA, RA, RA, RA, RA, RA], false).unwrap().value(),
+            17
+        );
+        // hard 17 never hits, 16 always does
+        assert_eq!(play(&[RT, R7], true).unwrap().sequence().len(), 1);
+        let bust = play(&[RT, R6, RK], false).unwrap();
+        assert!(bust.is_bust());
+        // naturals aren't drawn to, nor is soft 18
+        assert!(play(&[RA, RK], true).unwrap().is_blackjack());
+        assert_eq!(play(&[R7, RA], true).unwrap().value(), 18);
+        // running out of cards
+        assert_eq!(play(&[R2, R3], true), Err(DeckError::OutOfCards));
+    }
+}
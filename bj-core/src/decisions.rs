@@ -0,0 +1,164 @@
+//! The whole sequence of decisions behind a played hand.
+//!
+//! A strategy card answers one question at a time: what to do with this total against this upcard.
+//! A hand that was hit twice and then stood took three answers, one for each of the hands it was
+//! along the way. decision_path() rebuilds them from the cards in the order they were dealt: the
+//! opening two cards, then the hand after each hit. Each Decision is what the card said to do
+//! with the hand at that point, and which button that meant given what was allowed then, e.g. no
+//! doubling or surrendering after the first hit.
+//!
+//! Putting the path next to what the player actually did (first_mistake()) says where they went
+//! wrong, or why each of their choices was right.
+use crate::basicstrategy::rules::{Allowed, SplitState};
+use crate::basicstrategy::BasicStrategy;
+use crate::button::GameButton;
+use crate::correct_resp::expected_button;
+use crate::deck::Card;
+use crate::hand::Hand;
+use crate::resp::Resp;
+use crate::table::{GameDesc, TableError};
+use std::fmt;
+
+/// One point in a hand where the player had to choose what to do
+#[derive(Debug, Clone, PartialEq)]
+pub struct Decision {
+    /// The hand as it was when the decision was made
+    pub hand: Hand,
+    pub dealer: Card,
+    /// What the card says to do with it
+    pub resp: Resp,
+    /// The button that follows resp, given what was allowed at this point
+    pub expected: GameButton,
+}
+
+impl Decision {
+    /// The strategy card cell the decision was looked up in
+    pub fn cell(&self) -> GameDesc {
+        GameDesc::from_hand(&self.hand, self.dealer)
+    }
+
+    /// Whether the card says the hand takes another card here. Doubling does, but only one.
+    pub fn draws(&self) -> bool {
+        self.expected == GameButton::Hit || self.expected == GameButton::Double
+    }
+}
+
+impl fmt::Display for Decision {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.cell().label(), self.expected)?;
+        if GameButton::from(self.resp) != self.expected {
+            write!(f, " ({} isn't allowed)", GameButton::from(self.resp))?;
+        }
+        Ok(())
+    }
+}
+
+/// The decisions the card makes for a hand that ended up with the given cards, in the order they
+/// were dealt. There's one for the opening two cards and one after each card drawn after them,
+/// except after a card that busted the hand. Hands made by splitting should pass their
+/// SplitState, so doubling after splitting and the like are only allowed if the rules say so.
+///
+/// The cards don't have to be the ones the card would have played to: a decision is made at every
+/// step regardless, so the path of a misplayed hand shows where it left the card.
+pub fn decision_path(
+    bs: &BasicStrategy,
+    cards: &[Card],
+    dealer: Card,
+    split: SplitState,
+) -> Result<Vec<Decision>, TableError> {
+    let mut path = vec![];
+    if cards.len() < 2 {
+        return Ok(path);
+    }
+    let mut hand = Hand::new(&cards[..2]);
+    for next in cards[2..].iter().map(Some).chain(std::iter::once(None)) {
+        if hand.is_bust() {
+            break;
+        }
+        let resp = bs.resp(&hand, dealer)?;
+        // only opening hands can double, split, and surrender, which Allowed works out from the
+        // number of cards
+        let allowed = Allowed::for_hand(&hand, dealer, bs.rules.as_ref(), split);
+        path.push(Decision {
+            hand: hand.clone(),
+            dealer,
+            resp,
+            expected: expected_button(resp, allowed),
+        });
+        match next {
+            Some(card) => hand.push(*card),
+            None => break,
+        }
+    }
+    Ok(path)
+}
+
+/// The index of the first decision where the player pressed something other than what the card
+/// says, if any. actions are what the player pressed, in order. If the player stopped drawing
+/// before the card would have, the decision they stopped at is the mistake. If they pressed more
+/// buttons than there are decisions, the extra ones aren't looked at.
+pub fn first_mistake(path: &[Decision], actions: &[GameButton]) -> Option<usize> {
+    path.iter().zip(actions).position(|(d, a)| d.expected != *a)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::deck::{Rank, Suit};
+    use crate::resp::resps_from_buf;
+    use crate::table::Table;
+
+    const T1: &[u8] = include_bytes!("../../t1.txt");
+
+    fn card(rank: Rank) -> Card {
+        Card::new(rank, Suit::Club)
+    }
+
+    #[test]
+    fn path() {
+        let bs = BasicStrategy::new(None, Table::new(resps_from_buf(T1).unwrap()).unwrap());
+        let ten = card(Rank::RT);
+        // 4,3 hit to 9, hit to 12, hit to 17, and stand
+        let cards = [
+            card(Rank::R4),
+            card(Rank::R3),
+            card(Rank::R2),
+            card(Rank::R3),
+            card(Rank::R5),
+        ];
+        let path = decision_path(&bs, &cards, ten, SplitState::default()).unwrap();
+        let expected: Vec<GameButton> = path.iter().map(|d| d.expected).collect();
+        assert_eq!(
+            expected,
+            vec![
+                GameButton::Hit,
+                GameButton::Hit,
+                GameButton::Hit,
+                GameButton::Stand
+            ]
+        );
+        assert_eq!(path[3].to_string(), "Hard 17 vs 10: Stand");
+        assert!(path[..3].iter().all(|d| d.draws()));
+        // standing on 12 was the mistake
+        let actions = [GameButton::Hit, GameButton::Hit, GameButton::Stand];
+        assert_eq!(first_mistake(&path, &actions), Some(2));
+        assert_eq!(first_mistake(&path, &expected), None);
+        // 11 vs 10 doubles, but not once the hand has three cards
+        let cards = [card(Rank::R2), card(Rank::R4), card(Rank::R5)];
+        let path = decision_path(&bs, &cards, ten, SplitState::default()).unwrap();
+        assert_eq!(path[1].resp, Resp::DoubleElseHit);
+        assert_eq!(path[1].expected, GameButton::Hit);
+        assert_eq!(
+            path[1].to_string(),
+            "Hard 11 vs 10: Hit (Double isn't allowed)"
+        );
+        // no decision after busting
+        let cards = [card(Rank::RT), card(Rank::R6), card(Rank::R9)];
+        assert_eq!(
+            decision_path(&bs, &cards, ten, SplitState::default())
+                .unwrap()
+                .len(),
+            1
+        );
+    }
+}
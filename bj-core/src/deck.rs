@@ -1,7 +1,11 @@
+#[cfg(feature = "rand")]
+use crate::shuffle::shuffled_shoe;
+#[cfg(feature = "rand")]
 use rand::prelude::*;
-use serde_derive::{Deserialize, Serialize};
-use std::error::Error;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 use std::fmt;
+use thiserror::Error;
 
 pub const ALL_RANKS: [Rank; 13] = [
     Rank::R2,
@@ -19,6 +23,7 @@ pub const ALL_RANKS: [Rank; 13] = [
     Rank::RA,
 ];
 pub const ALL_SUITS: [Suit; 4] = [Suit::Club, Suit::Diamond, Suit::Heart, Suit::Spade];
+#[cfg(feature = "rand")]
 const DECK_LEN: usize = ALL_RANKS.len() * ALL_SUITS.len();
 const SPADE: &str = "♤";
 const HEART: &str = "♡";
@@ -29,19 +34,23 @@ const CLUB: &str = "♧";
 //const DIAMOND: &str = "♦";
 //const CLUB: &str = "♣";
 
+#[cfg(feature = "rand")]
 pub fn rand_suit() -> Suit {
     *ALL_SUITS.choose(&mut thread_rng()).unwrap()
 }
 
+#[cfg(feature = "rand")]
 fn rand_rank() -> Rank {
     *ALL_RANKS.choose(&mut thread_rng()).unwrap()
 }
 
+#[cfg(feature = "rand")]
 pub fn rand_card() -> Card {
     Card::new(rand_rank(), rand_suit())
 }
 
-#[derive(Hash, PartialEq, Eq, PartialOrd, Clone, Copy, Debug, Serialize, Deserialize)]
+#[derive(Hash, PartialEq, Eq, PartialOrd, Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum Suit {
     Club,
     Diamond,
@@ -60,7 +69,8 @@ impl fmt::Display for Suit {
     }
 }
 
-#[derive(Hash, PartialEq, Eq, PartialOrd, Clone, Copy, Debug, Serialize, Deserialize)]
+#[derive(Hash, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum Rank {
     R2,
     R3,
@@ -114,7 +124,8 @@ impl fmt::Display for Rank {
     }
 }
 
-#[derive(Hash, PartialEq, Eq, PartialOrd, Clone, Copy, Debug, Serialize, Deserialize)]
+#[derive(Hash, PartialEq, Eq, PartialOrd, Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Card {
     suit: Suit,
     rank: Rank,
@@ -144,28 +155,23 @@ impl Card {
     }
 }
 
-#[derive(PartialEq, Debug)]
+#[derive(PartialEq, Debug, Error)]
 pub enum DeckError {
+    #[error("No more cards in deck")]
     OutOfCards,
 }
 
-impl Error for DeckError {}
-
-impl fmt::Display for DeckError {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match self {
-            DeckError::OutOfCards => write!(f, "No more cards in deck"),
-        }
-    }
-}
-
+#[cfg(feature = "rand")]
 #[derive(Default)]
 pub struct Deck {
     cards: Vec<Card>,
     next: usize,
     infinite: bool,
+    /// Whether the cards are in a continuous shuffle machine, see new_csm()
+    csm: bool,
 }
 
+#[cfg(feature = "rand")]
 impl Deck {
     /// Generate a new single deck of cards, shuffled
     pub fn new() -> Self {
@@ -187,8 +193,36 @@ impl Deck {
         d
     }
 
+    /// Generate a continuous shuffle machine (CSM) loaded with `l * DECK_LEN` cards. Every round's
+    /// cards go back into the machine and are shuffled in once the round is over (see
+    /// end_round()), so which cards are left never depends on which were dealt before.
+    pub fn new_csm(l: usize) -> Self {
+        let mut d = Self::with_length(l);
+        d.csm = true;
+        d
+    }
+
+    /// Whether this is a continuous shuffle machine, see new_csm()
+    pub fn is_csm(&self) -> bool {
+        self.csm
+    }
+
+    /// The round is over. A continuous shuffle machine takes back the cards dealt and shuffles
+    /// them in; any other deck carries on from where it is until it is shuffled.
+    pub fn end_round(&mut self) {
+        if self.csm {
+            self.shuffle();
+        }
+    }
+
+    /// How many cards have been drawn since the last shuffle
+    pub fn dealt(&self) -> usize {
+        self.next
+    }
+
     /// Shuffle the deck of cards in-place, and reset its `next` index to 0
     pub fn shuffle(&mut self) {
+        log::debug!("Shuffling {} cards", self.cards.len());
         self.cards.shuffle(&mut thread_rng());
         self.next = 0;
     }
@@ -216,12 +250,26 @@ impl Deck {
             cards: multi,
             next: 0,
             infinite: false,
+            csm: false,
         };
         // shuffle it
         d.shuffle();
         d
     }
 
+    /// Generate a multi-deck with `l * DECK_LEN` cards, shuffled with the seeded shuffle (see
+    /// crate::shuffle) so that it is the same for everyone with the same seed. Shuffling it again
+    /// with shuffle() uses rand as usual.
+    pub fn seeded(l: usize, seed: u64) -> Self {
+        assert!(l >= 1);
+        Self {
+            cards: shuffled_shoe(l, seed),
+            next: 0,
+            infinite: false,
+            csm: false,
+        }
+    }
+
     /// Draw the topmost card and return it, or return and error if, e.g., there are no more cards.
     pub fn draw(&mut self) -> Result<Card, DeckError> {
         if self.infinite {
@@ -239,7 +287,7 @@ impl Deck {
     }
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "rand"))]
 mod tests {
     use super::{Card, Deck, DeckError, DECK_LEN};
     use std::collections::HashMap;
@@ -319,4 +367,36 @@ mod tests {
             assert!(d.draw().is_ok());
         }
     }
+
+    #[test]
+    fn csm_takes_cards_back() {
+        let mut csm = Deck::new_csm(1);
+        let mut shoe = Deck::with_length(1);
+        assert!(csm.is_csm() && !shoe.is_csm());
+        // far more rounds of 5 than one deck holds
+        for _ in 0..DECK_LEN {
+            for _ in 0..5 {
+                csm.draw().unwrap();
+            }
+            assert_eq!(csm.dealt(), 5);
+            csm.end_round();
+            assert_eq!(csm.dealt(), 0);
+        }
+        for _ in 0..5 {
+            shoe.draw().unwrap();
+        }
+        shoe.end_round();
+        assert_eq!(shoe.dealt(), 5);
+    }
+
+    #[test]
+    fn seeded_same() {
+        let mut d1 = Deck::seeded(6, 1234);
+        let mut d2 = Deck::seeded(6, 1234);
+        assert_eq!(d1.cards.len(), 6 * DECK_LEN);
+        for _ in 0..6 * DECK_LEN {
+            assert_eq!(d1.draw().unwrap(), d2.draw().unwrap());
+        }
+        assert_ne!(Deck::seeded(6, 1234).cards, Deck::seeded(6, 1235).cards);
+    }
 }
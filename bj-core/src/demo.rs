@@ -0,0 +1,184 @@
+//! A demonstration mode, where the trainer plays itself for the player to watch.
+//!
+//! A Demo deals hands like any Trainer and plays each one the way the card says to, explaining
+//! every decision. The front end calls Demo::step() every Demo::pace_ms() milliseconds and shows
+//! what it returns: alternately a DemoStep::Deal, a hand to show, and a DemoStep::Play, what was
+//! done with it and why. Nothing the demo plays counts toward the player's stats; it has a Trainer
+//! of its own, which the front end can set up like the player's (e.g. the same Mode) with
+//! Demo::trainer_mut(). Correct splits are played out, so the demo shows those hands too.
+use crate::basicstrategy::BasicStrategy;
+use crate::button::GameButton;
+use crate::deck::Card;
+use crate::hand::Hand;
+use crate::resp::Resp;
+use crate::trainer::{HandSource, Trainer, TrainerError};
+
+/// How long to show each step for, in milliseconds, unless set otherwise
+pub const DEFAULT_PACE_MS: u32 = 2500;
+/// The shortest and longest Demo::set_pace_ms() allows
+pub const MIN_PACE_MS: u32 = 500;
+pub const MAX_PACE_MS: u32 = 10_000;
+
+/// What the demo did with a hand
+#[derive(Debug, PartialEq)]
+pub struct DemoPlay {
+    pub player: Hand,
+    pub dealer: Card,
+    /// What the card says to do
+    pub correct: Resp,
+    /// The button pressed: the correct response, or its fallback if that wasn't allowed
+    pub action: GameButton,
+    /// Why, e.g. "Hard 11 vs 6: the card says Double(Hit)."
+    pub explanation: String,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum DemoStep {
+    /// A hand to show, which the next step plays
+    Deal { player: Hand, dealer: Card },
+    /// The hand last dealt was played
+    Play(DemoPlay),
+}
+
+#[derive(Debug)]
+pub struct Demo {
+    trainer: Trainer,
+    pace_ms: u32,
+    /// Whether the trainer's current hand has been shown, so the next step plays it
+    dealt: bool,
+}
+
+impl Demo {
+    /// A demo of the given card at DEFAULT_PACE_MS
+    pub fn new(bs: BasicStrategy) -> Self {
+        let mut trainer = Trainer::new(bs, HandSource::Uniform);
+        trainer.set_play_splits(true);
+        Self {
+            trainer,
+            pace_ms: DEFAULT_PACE_MS,
+            dealt: false,
+        }
+    }
+
+    /// The trainer that deals and grades the demo's hands
+    pub fn trainer(&self) -> &Trainer {
+        &self.trainer
+    }
+
+    pub fn trainer_mut(&mut self) -> &mut Trainer {
+        &mut self.trainer
+    }
+
+    /// How long to show each step for, in milliseconds
+    pub fn pace_ms(&self) -> u32 {
+        self.pace_ms
+    }
+
+    /// Show each step for the given number of milliseconds, kept between MIN_PACE_MS and
+    /// MAX_PACE_MS. Returns the pace set.
+    pub fn set_pace_ms(&mut self, ms: u32) -> u32 {
+        self.pace_ms = ms.clamp(MIN_PACE_MS, MAX_PACE_MS);
+        self.pace_ms
+    }
+
+    /// Show the next hand, or play the one shown
+    pub fn step(&mut self) -> Result<DemoStep, TrainerError> {
+        if !self.dealt {
+            self.dealt = true;
+            let (player, dealer) = self.trainer.hand();
+            return Ok(DemoStep::Deal {
+                player: player.clone(),
+                dealer,
+            });
+        }
+        let graded = self.trainer.act(self.trainer.correct_action()?)?;
+        self.dealt = false;
+        let mut explanation = format!(
+            "{}: the card says {}.",
+            graded.cell().label(),
+            graded.correct
+        );
+        let first = GameButton::from(graded.correct);
+        if graded.expected != first {
+            explanation.push_str(&format!(
+                " {} isn't allowed here, so {}.",
+                first, graded.expected
+            ));
+        }
+        Ok(DemoStep::Play(DemoPlay {
+            player: graded.player,
+            dealer: graded.dealer,
+            correct: graded.correct,
+            action: graded.action,
+            explanation,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::deck::{Rank, Suit};
+    use crate::table::Table;
+
+    fn card(rank: Rank) -> Card {
+        Card::new(rank, Suit::Club)
+    }
+
+    fn demo() -> Demo {
+        let table = Table::new(std::iter::repeat_n(Resp::DoubleElseHit, 360)).unwrap();
+        Demo::new(BasicStrategy::new(None, table))
+    }
+
+    #[test]
+    fn alternates() {
+        let mut d = demo();
+        for _ in 0..50 {
+            let (player, dealer) = match d.step().unwrap() {
+                DemoStep::Deal { player, dealer } => (player, dealer),
+                s => panic!("Expected a deal, got {:?}", s),
+            };
+            match d.step().unwrap() {
+                DemoStep::Play(play) => {
+                    assert_eq!((&play.player, play.dealer), (&player, dealer));
+                    assert_eq!(play.correct, Resp::DoubleElseHit);
+                    assert!(play.explanation.contains("Double(Hit)"));
+                }
+                s => panic!("Expected a play, got {:?}", s),
+            }
+        }
+        // none of it is the player's
+        assert!(d
+            .trainer()
+            .stats()
+            .values()
+            .all(|s| s.correct() == s.seen()));
+    }
+
+    #[test]
+    fn explains_fallback() {
+        let mut d = demo();
+        let hand = Hand::new(&[card(Rank::R5), card(Rank::R3), card(Rank::R2)]);
+        d.trainer_mut().set_hand(hand, card(Rank::R6));
+        d.step().unwrap();
+        match d.step().unwrap() {
+            DemoStep::Play(play) => {
+                assert_eq!(play.action, GameButton::Hit);
+                assert_eq!(
+                    play.explanation,
+                    "Hard 10 vs 6: the card says Double(Hit). Double isn't allowed here, so Hit."
+                );
+            }
+            s => panic!("Expected a play, got {:?}", s),
+        }
+    }
+
+    #[test]
+    fn pace() {
+        let mut d = demo();
+        assert_eq!(d.pace_ms(), DEFAULT_PACE_MS);
+        assert_eq!(d.set_pace_ms(0), MIN_PACE_MS);
+        assert_eq!(d.set_pace_ms(u32::MAX), MAX_PACE_MS);
+        assert_eq!(d.set_pace_ms(1000), 1000);
+    }
+}
@@ -0,0 +1,332 @@
+//! Drill packs: shareable sets of hands to practice, and what it takes to pass them.
+//!
+//! A pack is plain data, so players can write and share them without touching code. As JSON:
+//!
+//! ```json
+//! {
+//!   "name": "Stiffs vs 9, 10, A",
+//!   "description": "The hands everyone hates to hit",
+//!   "hands": {"cells": {"rows": ["Hard 12", "Hard 16"], "upcards": ["9", "10", "A"]}},
+//!   "scoring": {"hands": 30, "pass_percent": 90}
+//! }
+//! ```
+//!
+//! The hands are either every cell in some rows of the card (named like GameDesc::player_label(),
+//! e.g. "Hard 16", "Soft 18", "8,8") against some upcards (all of them if none are given), or a
+//! list of exact hands written as CompKeys, e.g. {"exact": ["T,6 vs K", "7,5 vs 4"]}, or a list of
+//! cells named like GameDesc::label(), e.g. {"labels": ["Hard 16 vs 10", "A,A vs A"]}. A run of the
+//! pack is scoring.hands hands, and it's passed by getting at least scoring.pass_percent of them
+//! right.
+//!
+//! Packs are only checked when they're validated (DrillPack::validate()), which also works out
+//! exactly which hands they deal.
+use crate::composition::{CompKey, CompKeyError};
+#[cfg(feature = "rand")]
+use crate::deck::Card;
+#[cfg(feature = "rand")]
+use crate::hand::Hand;
+#[cfg(feature = "rand")]
+use crate::playstats::PlayStats;
+use crate::table::GameDesc;
+#[cfg(feature = "rand")]
+use crate::table::Table;
+#[cfg(feature = "rand")]
+use crate::utils::rand_next_hand_where;
+#[cfg(feature = "rand")]
+use rand::prelude::*;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/// The most hands a run of a pack can be
+pub const MAX_PACK_HANDS: u32 = 1000;
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct DrillPack {
+    pub name: String,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub description: String,
+    pub hands: PackHands,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub scoring: Scoring,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum PackHands {
+    /// Every cell in the given rows against the given upcards ("2"-"10", "A"), or against every
+    /// upcard if there are none
+    Cells {
+        rows: Vec<String>,
+        #[cfg_attr(feature = "serde", serde(default))]
+        upcards: Vec<String>,
+    },
+    /// Exactly these hands, as CompKeys like "T,6 vs K"
+    Exact(Vec<String>),
+    /// Exactly these cells, as labels like "Hard 16 vs 10"
+    Labels(Vec<String>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Scoring {
+    /// How many hands a run of the pack is
+    pub hands: u32,
+    /// The percent of them that have to be right to pass
+    pub pass_percent: u32,
+}
+
+impl Default for Scoring {
+    fn default() -> Self {
+        Self {
+            hands: 20,
+            pass_percent: 90,
+        }
+    }
+}
+
+impl Scoring {
+    /// Whether getting correct of scoring.hands right passes
+    pub fn passes(self, correct: u32) -> bool {
+        u64::from(correct) * 100 >= u64::from(self.hands) * u64::from(self.pass_percent)
+    }
+}
+
+/// The hands a valid pack deals
+#[derive(Debug, Clone, PartialEq)]
+pub enum PackDeal {
+    /// Hands from these cells
+    Cells(Vec<GameDesc>),
+    /// These exact hands
+    Exact(Vec<CompKey>),
+}
+
+#[cfg(feature = "rand")]
+impl PackDeal {
+    /// Deal one of the pack's hands. Cells are weighted by how poorly the player has done on them,
+    /// like weighted dealing; exact hands are all equally likely. None if there are no hands to
+    /// deal, which a valid pack always has.
+    pub fn deal(&self, stats: &Table<PlayStats>) -> Option<(Hand, Card)> {
+        match self {
+            Self::Cells(cells) => rand_next_hand_where(stats, |desc| cells.contains(desc)),
+            Self::Exact(hands) => Some(hands.choose(&mut thread_rng())?.rand_hand()),
+        }
+    }
+}
+
+/// How the player is doing on the current run of a pack
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PackProgress {
+    pub played: u32,
+    pub correct: u32,
+    pub scoring: Scoring,
+}
+
+impl PackProgress {
+    pub fn new(scoring: Scoring) -> Self {
+        Self {
+            played: 0,
+            correct: 0,
+            scoring,
+        }
+    }
+
+    /// Whether every hand in the run has been played
+    pub fn is_finished(&self) -> bool {
+        self.played >= self.scoring.hands
+    }
+
+    /// Whether the run was passed, once it's finished
+    pub fn passed(&self) -> Option<bool> {
+        if self.is_finished() {
+            Some(self.scoring.passes(self.correct))
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Error)]
+pub enum DrillPackError {
+    #[error("The pack needs a name")]
+    NoName,
+    /// The pack deals no hands
+    #[error("The pack has no hands")]
+    NoHands,
+    /// Not a row of a strategy card
+    #[error("'{0}' is not a row like 'Hard 16' or '8,8'")]
+    BadRow(String),
+    /// Not a dealer upcard
+    #[error("'{0}' is not an upcard (2-10 or A)")]
+    BadUpcard(String),
+    /// Not a cell of a strategy card
+    #[error("'{0}' is not a cell like 'Hard 16 vs 10'")]
+    BadLabel(String),
+    #[error("{0}")]
+    BadHand(#[from] CompKeyError),
+    /// The scoring is impossible or silly. (hands, pass_percent)
+    #[error(
+        "Scoring must be 1-{} hands and 0-100 percent, not {} hands and {} percent",
+        MAX_PACK_HANDS, .0, .1
+    )]
+    BadScoring(u32, u32),
+}
+
+const ALL_UPCARDS: [&str; 10] = ["2", "3", "4", "5", "6", "7", "8", "9", "10", "A"];
+
+impl DrillPack {
+    /// Check the pack, and work out the hands it deals
+    pub fn validate(&self) -> Result<PackDeal, DrillPackError> {
+        if self.name.trim().is_empty() {
+            return Err(DrillPackError::NoName);
+        }
+        let Scoring {
+            hands,
+            pass_percent,
+        } = self.scoring;
+        if hands == 0 || hands > MAX_PACK_HANDS || pass_percent > 100 {
+            return Err(DrillPackError::BadScoring(hands, pass_percent));
+        }
+        let deal = match &self.hands {
+            PackHands::Cells { rows, upcards } => {
+                let upcards: Vec<&str> = if upcards.is_empty() {
+                    ALL_UPCARDS.to_vec()
+                } else {
+                    upcards.iter().map(|s| s.as_str()).collect()
+                };
+                let mut cells = vec![];
+                for row in rows {
+                    // a row is a cell label without the upcard, so check it against the first
+                    // upcard, which is always there
+                    format!("{} vs 2", row)
+                        .parse::<GameDesc>()
+                        .map_err(|_| DrillPackError::BadRow(row.clone()))?;
+                    for up in &upcards {
+                        let desc = format!("{} vs {}", row, up)
+                            .parse::<GameDesc>()
+                            .map_err(|_| DrillPackError::BadUpcard(up.to_string()))?;
+                        if !cells.contains(&desc) {
+                            cells.push(desc);
+                        }
+                    }
+                }
+                PackDeal::Cells(cells)
+            }
+            PackHands::Labels(labels) => {
+                let mut cells = vec![];
+                for label in labels {
+                    let desc = label
+                        .parse::<GameDesc>()
+                        .map_err(|_| DrillPackError::BadLabel(label.clone()))?;
+                    if !cells.contains(&desc) {
+                        cells.push(desc);
+                    }
+                }
+                PackDeal::Cells(cells)
+            }
+            PackHands::Exact(hands) => PackDeal::Exact(
+                hands
+                    .iter()
+                    .map(|s| s.parse())
+                    .collect::<Result<Vec<CompKey>, _>>()?,
+            ),
+        };
+        let empty = match &deal {
+            PackDeal::Cells(cells) => cells.is_empty(),
+            PackDeal::Exact(hands) => hands.is_empty(),
+        };
+        if empty {
+            return Err(DrillPackError::NoHands);
+        }
+        Ok(deal)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pack(hands: PackHands) -> DrillPack {
+        DrillPack {
+            name: "test".to_string(),
+            description: String::new(),
+            hands,
+            scoring: Scoring::default(),
+        }
+    }
+
+    fn strings(v: &[&str]) -> Vec<String> {
+        v.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn validate() {
+        let stiffs = pack(PackHands::Cells {
+            rows: strings(&["Hard 12", "hard 16"]),
+            upcards: strings(&["9", "T", "A"]),
+        });
+        match stiffs.validate().unwrap() {
+            PackDeal::Cells(cells) => {
+                assert_eq!(cells.len(), 6);
+                assert_eq!(cells[5].label(), "Hard 16 vs A");
+            }
+            d => panic!("{:?}", d),
+        }
+        let all = pack(PackHands::Cells {
+            rows: strings(&["8,8"]),
+            upcards: vec![],
+        });
+        assert!(matches!(all.validate(), Ok(PackDeal::Cells(c)) if c.len() == 10));
+        let exact = pack(PackHands::Exact(strings(&["T,6 vs K", "7,5 vs 4"])));
+        assert!(matches!(exact.validate(), Ok(PackDeal::Exact(h)) if h.len() == 2));
+        let labels = pack(PackHands::Labels(strings(&[
+            "Hard 16 vs 10",
+            "A,A vs A",
+            "hard 16 vs T",
+        ])));
+        assert!(matches!(labels.validate(), Ok(PackDeal::Cells(c)) if c.len() == 2));
+        let bad = |hands| pack(hands).validate().unwrap_err();
+        assert_eq!(
+            bad(PackHands::Cells {
+                rows: strings(&["Hard 30"]),
+                upcards: vec![]
+            }),
+            DrillPackError::BadRow("Hard 30".to_string())
+        );
+        assert_eq!(
+            bad(PackHands::Cells {
+                rows: strings(&["Hard 16"]),
+                upcards: strings(&["1"])
+            }),
+            DrillPackError::BadUpcard("1".to_string())
+        );
+        assert_eq!(bad(PackHands::Exact(vec![])), DrillPackError::NoHands);
+        assert_eq!(
+            bad(PackHands::Labels(strings(&["Hard 16"]))),
+            DrillPackError::BadLabel("Hard 16".to_string())
+        );
+        assert!(matches!(
+            bad(PackHands::Exact(strings(&["T vs 4"]))),
+            DrillPackError::BadHand(_)
+        ));
+        let mut p = exact;
+        p.scoring.pass_percent = 101;
+        assert_eq!(p.validate(), Err(DrillPackError::BadScoring(20, 101)));
+        p.scoring.pass_percent = 90;
+        p.name = " ".to_string();
+        assert_eq!(p.validate(), Err(DrillPackError::NoName));
+        assert!(Scoring::default().passes(18));
+        assert!(!Scoring::default().passes(17));
+        let mut progress = PackProgress::new(Scoring::default());
+        progress.played = 19;
+        progress.correct = 18;
+        assert_eq!(progress.passed(), None);
+        progress.played = 20;
+        assert_eq!(progress.passed(), Some(true));
+    }
+}
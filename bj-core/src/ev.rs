@@ -0,0 +1,793 @@
+//! The expected value of every play in every cell of a strategy card, computed from the rules, so
+//! a card can be generated for any rules (see strategy()) and a custom card checked against them
+//! (see misplays()). Adding up every hand that can be dealt gives the player's edge in a round at
+//! a true count and the round's variance (see edge() and variance()).
+//!
+//! EVs are in units of the initial bet. A cell's EVs are those of every two card hand in it (see
+//! cell_hands()), weighted by how likely each is to be dealt. For each hand, its cards and the
+//! dealer's up card are taken out of a freshly shuffled shoe of the rules' decks (4+ is taken to be
+//! 6), which matters with 1 or 2 decks: 12 vs 4 is a stand there on average, but a hit when it's
+//! a 10 and a 2. Every card after that is drawn from the same odds, as if from an infinite deck of
+//! that makeup, which is exact for an infinite deck and close for a shoe, except that the player's
+//! cards come from what's left once the dealer has a hole card without blackjack (see
+//! player_odds()). Some of what the rules allow is simplified:
+//!
+//! - When the dealer peeks, EVs are for after they've checked and don't have blackjack, as that's
+//!   when the player decides. When they don't, a dealer blackjack is counted in, taking the doubled
+//!   or split bet too if the rules say it loses all bets.
+//! - A split hand is played on as the rules allow (hitting split aces, doubling after splitting),
+//!   but never split again, which slightly undervalues splitting when resplits are allowed.
+//! - After hitting, the player hits or stands, whichever is better. Composition-dependent plays
+//!   aren't considered.
+//! - For variance, the two hands made by splitting are taken to win or lose independently.
+use crate::basicstrategy::rules::{NumDecks, Rules, SplitState};
+use crate::basicstrategy::BasicStrategy;
+use crate::button::GameButton;
+use crate::deck::{Card, Rank, Suit};
+use crate::hand::{Hand, HandType};
+use crate::resp::Resp;
+use crate::table::{dealer_card_from_desc, player_hand_from_desc, GameDesc, Table};
+use std::cmp::Ordering;
+use std::fmt;
+
+/// The chance of drawing each card value, indexed by value - 1 (so aces first and tens last)
+type Odds = [f64; 10];
+
+/// What surrendering gets back
+const SURRENDER_EV: f64 = -0.5;
+/// Smaller differences in EV than this are rounding, not a better play
+const EPSILON: f64 = 1e-9;
+
+/// The EV of each play in a cell, or None if the rules don't allow it there
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ActionEvs {
+    pub stand: f64,
+    /// Hitting, then hitting or standing, whichever is better, until standing or busting
+    pub hit: f64,
+    pub double: Option<f64>,
+    pub split: Option<f64>,
+    pub surrender: Option<f64>,
+}
+
+impl ActionEvs {
+    /// The EV of the given action, if it's allowed
+    pub fn ev(&self, action: GameButton) -> Option<f64> {
+        match action {
+            GameButton::Stand => Some(self.stand),
+            GameButton::Hit => Some(self.hit),
+            GameButton::Double => self.double,
+            GameButton::Split => self.split,
+            GameButton::Surrender => self.surrender,
+        }
+    }
+
+    /// The allowed action with the highest EV, and its EV. Ties go to standing, then hitting,
+    /// doubling, splitting, and surrendering, in that order.
+    pub fn best(&self) -> (GameButton, f64) {
+        let order = [
+            GameButton::Stand,
+            GameButton::Hit,
+            GameButton::Double,
+            GameButton::Split,
+            GameButton::Surrender,
+        ];
+        let mut best = (GameButton::Stand, self.stand);
+        for action in order.iter().copied() {
+            if let Some(ev) = self.ev(action) {
+                if ev > best.1 + EPSILON {
+                    best = (action, ev);
+                }
+            }
+        }
+        best
+    }
+
+    /// The best of hitting and standing, for when nothing else is allowed
+    fn hit_or_stand(&self) -> Resp {
+        if self.hit > self.stand + EPSILON {
+            Resp::Hit
+        } else {
+            Resp::Stand
+        }
+    }
+
+    /// The Resp a strategy card should have in the cell: the best action, falling back on the
+    /// best of what's left when a compound Resp is needed
+    pub fn resp(&self) -> Resp {
+        match self.best().0 {
+            GameButton::Stand => Resp::Stand,
+            GameButton::Hit => Resp::Hit,
+            GameButton::Split => Resp::Split,
+            GameButton::Double => match self.hit_or_stand() {
+                Resp::Hit => Resp::DoubleElseHit,
+                _ => Resp::DoubleElseStand,
+            },
+            GameButton::Surrender => match self.split {
+                Some(split) if split > self.hit.max(self.stand) + EPSILON => {
+                    Resp::SurrenderElseSplit
+                }
+                _ => match self.hit_or_stand() {
+                    Resp::Hit => Resp::SurrenderElseHit,
+                    _ => Resp::SurrenderElseStand,
+                },
+            },
+        }
+    }
+
+    /// The EV of playing the given Resp in the cell, taking its fallback if its first choice isn't
+    /// allowed
+    pub fn resp_ev(&self, resp: Resp) -> f64 {
+        let hit_or_stand = || match resp.hit_or_stand() {
+            Some(Resp::Hit) => self.hit,
+            Some(_) => self.stand,
+            None => self.hit.max(self.stand),
+        };
+        match resp {
+            Resp::Hit => self.hit,
+            Resp::Stand => self.stand,
+            Resp::DoubleElseHit | Resp::DoubleElseStand => self.double.unwrap_or_else(hit_or_stand),
+            Resp::Split => self.split.unwrap_or_else(hit_or_stand),
+            Resp::SurrenderElseHit | Resp::SurrenderElseStand => {
+                self.surrender.unwrap_or_else(hit_or_stand)
+            }
+            Resp::SurrenderElseSplit => self.surrender.or(self.split).unwrap_or_else(hit_or_stand),
+        }
+    }
+}
+
+/// A cell where a strategy card's play is worse than the best one
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Misplay {
+    pub desc: GameDesc,
+    /// What the card says to play
+    pub card: Resp,
+    /// What it should say
+    pub best: Resp,
+    /// How much EV the card's play gives up, in units of the initial bet
+    pub cost: f64,
+}
+
+impl fmt::Display for Misplay {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: {}, but {} is better by {:.4}",
+            self.desc.label(),
+            self.card,
+            self.best,
+            self.cost
+        )
+    }
+}
+
+/// How many cards of each value are in a freshly shuffled shoe for the rules
+fn shoe(rules: &Rules) -> [f64; 10] {
+    let decks = match rules.decks {
+        NumDecks::One => 1.0,
+        NumDecks::Two => 2.0,
+        NumDecks::Three => 3.0,
+        NumDecks::FourPlus => 6.0,
+    };
+    let mut counts = [4.0 * decks; 10];
+    counts[9] = 16.0 * decks;
+    counts
+}
+
+/// The shoe for the rules at a HiLo true count: for every deck, half the count's worth of low
+/// cards (2 through 6) taken out and as many high ones (tens and aces) put in, evenly by rank
+fn shoe_at(rules: &Rules, true_count: f64) -> [f64; 10] {
+    let mut counts = shoe(rules);
+    let per_rank = true_count * counts[0] / 4.0 / 2.0 / 5.0;
+    for c in counts[1..6].iter_mut() {
+        *c = (*c - per_rank).max(0.0);
+    }
+    counts[0] = (counts[0] + per_rank).max(0.0);
+    counts[9] = (counts[9] + 4.0 * per_rank).max(0.0);
+    counts
+}
+
+/// The value of a hand worth sum with aces as 1, counting an ace as 11 if it doesn't bust, and
+/// whether it's soft
+fn value(sum: u8, ace: bool) -> (u8, bool) {
+    if ace && sum + 10 <= 21 {
+        (sum + 10, true)
+    } else {
+        (sum, false)
+    }
+}
+
+/// How the dealer's hand ends, given they don't have blackjack: the chances of 17 through 21, then
+/// of busting
+type DealerOutcomes = [f64; 6];
+
+/// How the dealer's hand ends from each hand they could have, by its sum with aces as 1 and
+/// whether it has an ace
+fn dealer_draws(odds: &Odds, h17: bool) -> [[DealerOutcomes; 2]; 27] {
+    let mut ends = [[[0.0; 6]; 2]; 27];
+    // drawing only makes bigger hands, so work down from the biggest
+    for sum in (2..=26).rev() {
+        for &ace in &[false, true] {
+            let (v, soft) = value(sum, ace);
+            let mut out = [0.0; 6];
+            if v > 21 {
+                out[5] = 1.0;
+            } else if v > 17 || (v == 17 && !(soft && h17)) {
+                out[usize::from(v - 17)] = 1.0;
+            } else {
+                for (i, p) in odds.iter().enumerate() {
+                    let card = i as u8 + 1;
+                    let next = &ends[usize::from(sum + card)][usize::from(ace || card == 1)];
+                    for (o, n) in out.iter_mut().zip(next.iter()) {
+                        *o += p * n;
+                    }
+                }
+            }
+            ends[usize::from(sum)][usize::from(ace)] = out;
+        }
+    }
+    ends
+}
+
+/// Whether a hole card of the given value gives the dealer blackjack with the up card
+fn makes_blackjack(up: u8, hole: u8) -> bool {
+    up + hole == 11 && (up == 1 || hole == 1)
+}
+
+/// How the dealer's hand ends given their up card's value, and the chance they have blackjack
+fn dealer_outcomes(up: u8, odds: &Odds, h17: bool) -> (DealerOutcomes, f64) {
+    let ends = dealer_draws(odds, h17);
+    let mut out = [0.0; 6];
+    let mut blackjack = 0.0;
+    for (i, p) in odds.iter().enumerate() {
+        let hole = i as u8 + 1;
+        if makes_blackjack(up, hole) {
+            blackjack += p;
+        } else {
+            let end = &ends[usize::from(up + hole)][usize::from(up == 1 || hole == 1)];
+            for (o, e) in out.iter_mut().zip(end.iter()) {
+                *o += p * e;
+            }
+        }
+    }
+    // the outcomes given no blackjack
+    for o in out.iter_mut() {
+        *o /= 1.0 - blackjack;
+    }
+    (out, blackjack)
+}
+
+/// The chance of the player drawing each value given the dealer doesn't have blackjack, from the
+/// counts the odds came from. The hole card is one that can't make it, so after an ace up, say,
+/// a ten is a little likelier, which matters with few decks.
+fn player_odds(up: u8, counts: &[f64; 10], odds: &Odds) -> Odds {
+    let no_bj: f64 = (1..=10)
+        .filter(|&hole| !makes_blackjack(up, hole))
+        .map(|hole| odds[usize::from(hole - 1)])
+        .sum();
+    let mut left = *counts;
+    for (hole, c) in (1..=10).zip(left.iter_mut()) {
+        if !makes_blackjack(up, hole) {
+            *c -= odds[usize::from(hole - 1)] / no_bj;
+        }
+    }
+    let total: f64 = left.iter().sum();
+    let mut out = [0.0; 10];
+    for (o, c) in out.iter_mut().zip(left.iter()) {
+        *o = c / total;
+    }
+    out
+}
+
+/// The EVs of the player's hands in one cell, against one dealer up card, and the mean squares of
+/// their results, for working out variance (see variance())
+struct Player<'a> {
+    odds: &'a Odds,
+    dealer: DealerOutcomes,
+    /// The EV of hitting a hand by its sum with aces as 1, and whether it has an ace
+    hit: [[f64; 2]; 22],
+    /// The mean square of the result of hitting, likewise
+    hit_sq: [[f64; 2]; 22],
+}
+
+impl<'a> Player<'a> {
+    fn new(odds: &'a Odds, dealer: DealerOutcomes) -> Self {
+        let mut player = Self {
+            odds,
+            dealer,
+            hit: [[0.0; 2]; 22],
+            hit_sq: [[0.0; 2]; 22],
+        };
+        // hitting only makes bigger hands, so work down from the biggest
+        for sum in (2..=21).rev() {
+            for &ace in &[false, true] {
+                let ev = player.draw(sum, ace, |p, sum, ace| p.play_on(sum, ace).0);
+                let sq = player.draw_sq(sum, ace, |p, sum, ace| p.play_on(sum, ace).1);
+                player.hit[usize::from(sum)][usize::from(ace)] = ev;
+                player.hit_sq[usize::from(sum)][usize::from(ace)] = sq;
+            }
+        }
+        player
+    }
+
+    /// The EV of standing on the given value
+    fn stand(&self, v: u8) -> f64 {
+        if v > 21 {
+            return -1.0;
+        }
+        // the dealer busting, then each hand they can stand on
+        (17..=21)
+            .zip(self.dealer.iter())
+            .fold(self.dealer[5], |ev, (d, p)| match v.cmp(&d) {
+                Ordering::Greater => ev + p,
+                Ordering::Less => ev - p,
+                Ordering::Equal => ev,
+            })
+    }
+
+    /// The mean square of the result of standing on the given value: the chance of anything but
+    /// a push
+    fn stand_sq(&self, v: u8) -> f64 {
+        match v {
+            17..=21 => 1.0 - self.dealer[usize::from(v - 17)],
+            _ => 1.0,
+        }
+    }
+
+    /// The EV of drawing a card to the hand, given the EV of each unbusted hand it could make
+    fn draw<F>(&self, sum: u8, ace: bool, then: F) -> f64
+    where
+        F: Fn(&Self, u8, bool) -> f64,
+    {
+        self.draw_then(sum, ace, -1.0, then)
+    }
+
+    /// The mean square of drawing a card to the hand, given that of each unbusted hand it could
+    /// make
+    fn draw_sq<F>(&self, sum: u8, ace: bool, then: F) -> f64
+    where
+        F: Fn(&Self, u8, bool) -> f64,
+    {
+        self.draw_then(sum, ace, 1.0, then)
+    }
+
+    /// What drawing a card to the hand is worth, given what busting is and what each unbusted hand
+    /// it could make is
+    fn draw_then<F>(&self, sum: u8, ace: bool, bust: f64, then: F) -> f64
+    where
+        F: Fn(&Self, u8, bool) -> f64,
+    {
+        self.odds
+            .iter()
+            .enumerate()
+            .map(|(i, p)| {
+                let card = i as u8 + 1;
+                let (sum, ace) = (sum + card, ace || card == 1);
+                if value(sum, ace).0 > 21 {
+                    p * bust
+                } else {
+                    p * then(self, sum, ace)
+                }
+            })
+            .sum()
+    }
+
+    /// The EV and mean square of a hand that may hit or stand, whichever is better
+    fn play_on(&self, sum: u8, ace: bool) -> (f64, f64) {
+        let v = value(sum, ace).0;
+        let stand = (self.stand(v), self.stand_sq(v));
+        let hit = (self.hit(sum, ace), self.hit_sq(sum, ace));
+        if hit.0 > stand.0 {
+            hit
+        } else {
+            stand
+        }
+    }
+
+    fn hit(&self, sum: u8, ace: bool) -> f64 {
+        self.hit[usize::from(sum)][usize::from(ace)]
+    }
+
+    fn hit_sq(&self, sum: u8, ace: bool) -> f64 {
+        self.hit_sq[usize::from(sum)][usize::from(ace)]
+    }
+
+    fn double(&self, sum: u8, ace: bool) -> f64 {
+        2.0 * self.draw(sum, ace, |p, sum, ace| p.stand(value(sum, ace).0))
+    }
+
+    fn double_sq(&self, sum: u8, ace: bool) -> f64 {
+        4.0 * self.draw_sq(sum, ace, |p, sum, ace| p.stand_sq(value(sum, ace).0))
+    }
+
+    /// The EV and mean square of one of the hands made by splitting a pair of the given value,
+    /// once it's worth sum, played on as the rules allow but not split again
+    fn split_hand(&self, card: u8, sum: u8, ace: bool, dealer: Card, rules: &Rules) -> (f64, f64) {
+        let split = SplitState {
+            splits: 1,
+            aces: card == 1,
+        };
+        let hand = Hand::new(&[rank_card(card), rank_card(sum - card)]);
+        let allowed = rules.allowed(&hand, dealer, split);
+        let v = value(sum, ace).0;
+        let mut best = (self.stand(v), self.stand_sq(v));
+        let hit = (self.hit(sum, ace), self.hit_sq(sum, ace));
+        let double = (self.double(sum, ace), self.double_sq(sum, ace));
+        for (play, ok) in [(hit, allowed.hit), (double, allowed.double)] {
+            if ok && play.0 > best.0 {
+                best = play;
+            }
+        }
+        best
+    }
+
+    /// The EV and mean square of both hands made by splitting a pair of the given value, taking
+    /// the hands' results to be independent
+    fn split(&self, card: u8, dealer: Card, rules: &Rules) -> (f64, f64) {
+        let hand = |p: &Self, sum, ace| p.split_hand(card, sum, ace, dealer, rules);
+        let ev = self.draw(card, card == 1, |p, sum, ace| hand(p, sum, ace).0);
+        let sq = self.draw_sq(card, card == 1, |p, sum, ace| hand(p, sum, ace).1);
+        (2.0 * ev, 2.0 * sq + 2.0 * ev * ev)
+    }
+}
+
+/// A card of the given value, with aces as 1
+fn rank_card(value: u8) -> Card {
+    let rank = match value {
+        1 => Rank::RA,
+        2 => Rank::R2,
+        3 => Rank::R3,
+        4 => Rank::R4,
+        5 => Rank::R5,
+        6 => Rank::R6,
+        7 => Rank::R7,
+        8 => Rank::R8,
+        9 => Rank::R9,
+        _ => Rank::RT,
+    };
+    Card::new(rank, Suit::Club)
+}
+
+/// Every two card hand in the cell, with how likely it is to be dealt from the counts relative to
+/// the others. Hard totals leave out pairs, which are cells of their own, and the hard 20 and 21
+/// cells, which no other two cards make, get the three card hand player_hand_from_desc() gives.
+fn cell_hands(desc: GameDesc, counts: &[f64; 10]) -> Vec<(Hand, f64)> {
+    let two = |a: u8, b: u8| {
+        let weight = counts[usize::from(a - 1)]
+            * (counts[usize::from(b - 1)] - if a == b { 1.0 } else { 0.0 });
+        (Hand::new(&[rank_card(a), rank_card(b)]), weight)
+    };
+    let hands: Vec<(Hand, f64)> = match desc.hand {
+        HandType::Hard => (2..=9)
+            .flat_map(|a| (a + 1..=10).map(move |b| (a, b)))
+            .filter(|(a, b)| a + b == desc.player)
+            .map(|(a, b)| two(a, b))
+            .collect(),
+        HandType::Soft => vec![two(1, desc.player - 11)],
+        HandType::Pair if desc.player == 22 => vec![two(1, 1)],
+        HandType::Pair => vec![two(desc.player / 2, desc.player / 2)],
+    };
+    let total: f64 = hands.iter().map(|(_, w)| w).sum();
+    if hands.is_empty() || total <= 0.0 {
+        // every cell has a hand
+        return vec![(player_hand_from_desc(desc).unwrap(), 1.0)];
+    }
+    hands.into_iter().map(|(h, w)| (h, w / total)).collect()
+}
+
+/// The EVs of a cell: those of every hand in it, weighted by how likely each is
+fn cell_evs(desc: GameDesc, rules: &Rules) -> ActionEvs {
+    // every cell has an up card
+    let dealer = dealer_card_from_desc(desc).unwrap();
+    let mut counts = shoe(rules);
+    counts[usize::from(dealer.value() - 1)] -= 1.0;
+    let hands = cell_hands(desc, &counts);
+    let mut evs = ActionEvs {
+        stand: 0.0,
+        hit: 0.0,
+        double: Some(0.0),
+        split: Some(0.0),
+        surrender: Some(0.0),
+    };
+    let add = |sum: Option<f64>, ev: Option<f64>, weight: f64| Some(sum? + ev? * weight);
+    for (hand, weight) in hands {
+        let hand_evs = hand_evs(&hand, dealer, counts, rules);
+        evs.stand += hand_evs.stand * weight;
+        evs.hit += hand_evs.hit * weight;
+        evs.double = add(evs.double, hand_evs.double, weight);
+        evs.split = add(evs.split, hand_evs.split, weight);
+        evs.surrender = add(evs.surrender, hand_evs.surrender, weight);
+    }
+    evs
+}
+
+/// The EVs of the hand against the up card, with the hand's cards taken out of the counts, which
+/// the up card already is
+fn hand_evs(hand: &Hand, dealer: Card, counts: [f64; 10], rules: &Rules) -> ActionEvs {
+    hand_moments(hand, dealer, counts, rules).0
+}
+
+/// hand_evs(), along with the mean square of each play's result and the chance the dealer has
+/// blackjack. With a dealer who peeks, all but the last are given they don't.
+fn hand_moments(
+    hand: &Hand,
+    dealer: Card,
+    mut counts: [f64; 10],
+    rules: &Rules,
+) -> (ActionEvs, ActionEvs, f64) {
+    for card in hand.cards() {
+        counts[usize::from(card.value() - 1)] -= 1.0;
+    }
+    let total: f64 = counts.iter().sum();
+    let mut odds = [0.0; 10];
+    for (o, c) in odds.iter_mut().zip(counts.iter()) {
+        *o = c / total;
+    }
+    let (outcomes, blackjack) = dealer_outcomes(dealer.value(), &odds, rules.dealer_hits_soft_17());
+    let player_odds = player_odds(dealer.value(), &counts, &odds);
+    let player = Player::new(&player_odds, outcomes);
+    let sum: u8 = hand.cards().map(|c| c.value()).sum();
+    let ace = hand.cards().any(|c| c.rank() == Rank::RA);
+    let allowed = rules.allowed(hand, dealer, SplitState::default());
+    let stand = if hand.is_blackjack() {
+        let pays = rules.blackjack_pays.ratio();
+        (pays, pays * pays)
+    } else {
+        (player.stand(hand.value()), player.stand_sq(hand.value()))
+    };
+    let split = Some(dealer)
+        .filter(|_| allowed.split)
+        .map(|d| player.split(hand.cards[0].value(), d, rules));
+    let mut evs = ActionEvs {
+        stand: stand.0,
+        hit: player.hit(sum, ace),
+        double: Some(player.double(sum, ace)).filter(|_| allowed.double),
+        split: split.map(|s| s.0),
+        surrender: Some(SURRENDER_EV).filter(|_| allowed.surrender),
+    };
+    let mut sqs = ActionEvs {
+        stand: stand.1,
+        hit: player.hit_sq(sum, ace),
+        double: Some(player.double_sq(sum, ace)).filter(|_| allowed.double),
+        split: split.map(|s| s.1),
+        surrender: Some(SURRENDER_EV * SURRENDER_EV).filter(|_| allowed.surrender),
+    };
+    if !rules.dealer_peeks() && blackjack > 0.0 {
+        // the player finds out about a dealer blackjack only after they've played, and it takes
+        // the original bet, or everything if the rules say so
+        let extra = if rules.dealer_bj_takes_all_bets() {
+            2.0
+        } else {
+            1.0
+        };
+        let with_bj = |ev: f64, lost: f64| blackjack * -lost + (1.0 - blackjack) * ev;
+        evs = ActionEvs {
+            stand: with_bj(evs.stand, if hand.is_blackjack() { 0.0 } else { 1.0 }),
+            hit: with_bj(evs.hit, 1.0),
+            double: evs.double.map(|ev| with_bj(ev, extra)),
+            split: evs.split.map(|ev| with_bj(ev, extra)),
+            surrender: evs.surrender.map(|ev| with_bj(ev, 1.0)),
+        };
+        let with_bj = |sq: f64, lost: f64| blackjack * lost * lost + (1.0 - blackjack) * sq;
+        sqs = ActionEvs {
+            stand: with_bj(sqs.stand, if hand.is_blackjack() { 0.0 } else { 1.0 }),
+            hit: with_bj(sqs.hit, 1.0),
+            double: sqs.double.map(|sq| with_bj(sq, extra)),
+            split: sqs.split.map(|sq| with_bj(sq, extra)),
+            surrender: sqs.surrender.map(|sq| with_bj(sq, 1.0)),
+        };
+    }
+    (evs, sqs, blackjack)
+}
+
+/// The EV of every play in every cell under the given rules
+pub fn evs(rules: &Rules) -> Table<ActionEvs> {
+    Table::filled(()).map(|desc, _| cell_evs(desc, rules))
+}
+
+/// A strategy card for the given rules, with the best play in every cell
+pub fn strategy(rules: &Rules) -> BasicStrategy {
+    BasicStrategy::new(Some(rules.clone()), evs(rules).map(|_, evs| evs.resp()))
+}
+
+/// The cells where the card's play is worse than the best one under the given rules, costliest
+/// first. Composition-dependent plays aren't checked.
+pub fn misplays(card: &BasicStrategy, rules: &Rules) -> Vec<Misplay> {
+    let evs = evs(rules);
+    let mut misplays: Vec<Misplay> = card
+        .table
+        .zip_with(&evs, |resp, evs| (resp, evs))
+        .iter()
+        .map(|(desc, (resp, evs))| Misplay {
+            desc: *desc,
+            card: *resp,
+            best: evs.resp(),
+            cost: evs.best().1 - evs.resp_ev(*resp),
+        })
+        .filter(|m| m.cost > EPSILON)
+        .collect();
+    misplays.sort_by(|a, b| b.cost.partial_cmp(&a.cost).unwrap());
+    misplays
+}
+
+/// The mean and mean square of a round's result, in units of the initial bet, with every hand
+/// dealt from the counts and played the best way
+fn round_moments(counts: &[f64; 10], rules: &Rules) -> (f64, f64) {
+    let total: f64 = counts.iter().sum();
+    let mut moments = (0.0, 0.0);
+    for up in 1..=10 {
+        let mut left = *counts;
+        let p_up = left[usize::from(up - 1)] / total;
+        left[usize::from(up - 1)] -= 1.0;
+        let dealer = rank_card(up);
+        for a in 1..=10 {
+            for b in a..=10 {
+                let first = left[usize::from(a - 1)];
+                let second = left[usize::from(b - 1)] - if a == b { 1.0 } else { 0.0 };
+                if p_up <= 0.0 || first <= 0.0 || second <= 0.0 {
+                    continue;
+                }
+                let order = if a == b { 1.0 } else { 2.0 };
+                let p = p_up * order * first / (total - 1.0) * second / (total - 2.0);
+                let hand = Hand::new(&[rank_card(a), rank_card(b)]);
+                let (evs, sqs, blackjack) = hand_moments(&hand, dealer, left, rules);
+                let (action, mut ev) = evs.best();
+                // can't fail: best() only gives allowed actions
+                let mut sq = sqs.ev(action).unwrap();
+                if rules.dealer_peeks() {
+                    // a dealer blackjack takes the bet before the player plays, or pushes theirs
+                    let lost = if hand.is_blackjack() { 0.0 } else { 1.0 };
+                    ev = blackjack * -lost + (1.0 - blackjack) * ev;
+                    sq = blackjack * lost + (1.0 - blackjack) * sq;
+                }
+                moments.0 += p * ev;
+                moments.1 += p * sq;
+            }
+        }
+    }
+    moments
+}
+
+/// The player's edge in a round at the given HiLo true count, as a fraction of the initial bet.
+/// Negative when the house has it. This is the EV of a round dealt from a shoe skewed to the count,
+/// with every hand played the best way for that shoe, so it assumes the index plays are made too.
+/// Insurance isn't taken.
+pub fn edge(rules: &Rules, true_count: f32) -> f64 {
+    round_moments(&shoe_at(rules, f64::from(true_count)), rules).0
+}
+
+/// The variance of a round's result off the top of the shoe, in squared units of the initial bet.
+/// Doubling and splitting push it above 1.
+pub fn variance(rules: &Rules) -> f64 {
+    let (ev, sq) = round_moments(&shoe(rules), rules);
+    sq - ev * ev
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::basicstrategy::rules::{BlackjackPayout, NoPeekLoss, Surrender};
+    use crate::hand::HandType;
+    use crate::validate::check_card;
+
+    fn rules(peek: bool, no_peek_loss: NoPeekLoss) -> Rules {
+        Rules {
+            hit_soft_17: true.into(),
+            peek_bj: peek.into(),
+            surrender: Surrender::Yes,
+            no_peek_loss,
+            ..Default::default()
+        }
+    }
+
+    fn cell(hand: HandType, player: u8, dealer: u8) -> GameDesc {
+        GameDesc::new(hand, player, dealer)
+    }
+
+    #[test]
+    fn dealer() {
+        let mut odds = [1.0 / 13.0; 10];
+        odds[9] = 4.0 / 13.0;
+        for up in 1..=10 {
+            let (out, bj) = dealer_outcomes(up, &odds, true);
+            assert!((out.iter().sum::<f64>() - 1.0).abs() < 1e-12);
+            match up {
+                1 => assert!((bj - 4.0 / 13.0).abs() < 1e-12),
+                10 => assert!((bj - 1.0 / 13.0).abs() < 1e-12),
+                _ => assert_eq!(bj, 0.0),
+            }
+        }
+        // a 6 busts about 42% of the time when the dealer stands on soft 17, and more when not
+        let bust = |h17| dealer_outcomes(6, &odds, h17).0[5];
+        assert!((bust(false) - 0.4228).abs() < 1e-3);
+        assert!(bust(true) > bust(false));
+    }
+
+    #[test]
+    fn known_plays() {
+        let bs = strategy(&rules(true, NoPeekLoss::OriginalBetsOnly));
+        assert!(check_card(&bs).is_empty());
+        for (desc, resp) in &[
+            (cell(HandType::Hard, 16, 10), Resp::SurrenderElseHit),
+            (cell(HandType::Hard, 16, 6), Resp::Stand),
+            (cell(HandType::Hard, 12, 2), Resp::Hit),
+            (cell(HandType::Hard, 12, 4), Resp::Stand),
+            (cell(HandType::Hard, 11, 6), Resp::DoubleElseHit),
+            (cell(HandType::Hard, 20, 10), Resp::Stand),
+            (cell(HandType::Soft, 18, 3), Resp::DoubleElseStand),
+            (cell(HandType::Soft, 18, 9), Resp::Hit),
+            (cell(HandType::Pair, 16, 6), Resp::Split),
+            (cell(HandType::Pair, 20, 6), Resp::Stand),
+            (cell(HandType::Pair, 22, 11), Resp::Split),
+        ] {
+            assert_eq!(bs.table.get_desc(*desc), Some(*resp), "{}", desc);
+        }
+        // more money out against a dealer who might have blackjack is worse when it's all lost
+        let eleven = cell(HandType::Hard, 11, 10);
+        assert_eq!(bs.table.get_desc(eleven), Some(Resp::DoubleElseHit));
+        let enhc = strategy(&rules(false, NoPeekLoss::AllBets));
+        assert_eq!(enhc.table.get_desc(eleven), Some(Resp::Hit));
+    }
+
+    #[test]
+    fn few_decks() {
+        let mut rules = rules(true, NoPeekLoss::OriginalBetsOnly);
+        rules.decks = NumDecks::One;
+        rules.hit_soft_17 = false.into();
+        rules.surrender = Surrender::No;
+        let bs = strategy(&rules);
+        // averaged over the hands that make it, 12 vs 4 stands with one deck, though it's a hit
+        // when the 12 is a 10 and a 2
+        let twelve = cell(HandType::Hard, 12, 4);
+        assert_eq!(bs.table.get_desc(twelve), Some(Resp::Stand));
+        let mut counts = shoe(&rules);
+        counts[3] -= 1.0;
+        let ten_two = Hand::new(&[rank_card(10), rank_card(2)]);
+        let evs = hand_evs(&ten_two, rank_card(4), counts, &rules);
+        assert_eq!(evs.resp(), Resp::Hit);
+        let eleven = cell(HandType::Hard, 11, 11);
+        assert_eq!(bs.table.get_desc(eleven), Some(Resp::DoubleElseHit));
+        // with no blackjack under the ace, the player's next card is likelier a ten
+        let soft18 = cell(HandType::Soft, 18, 11);
+        assert_eq!(bs.table.get_desc(soft18), Some(Resp::Stand));
+        rules.hit_soft_17 = true.into();
+        let bs = strategy(&rules);
+        for up in 5..=6 {
+            let eight = cell(HandType::Hard, 8, up);
+            assert_eq!(bs.table.get_desc(eight), Some(Resp::DoubleElseHit));
+        }
+    }
+
+    #[test]
+    fn round() {
+        let rules = rules(true, NoPeekLoss::OriginalBetsOnly);
+        let top = edge(&rules, 0.0);
+        assert!(top < -0.002 && top > -0.008);
+        assert!(edge(&rules, 2.0) > 0.0);
+        assert!(variance(&rules) > 1.2 && variance(&rules) < 1.4);
+        // a worse blackjack payout costs about what the rules say it does
+        let six_five = Rules {
+            blackjack_pays: BlackjackPayout::SixToFive,
+            ..rules
+        };
+        let cost = top - edge(&six_five, 0.0);
+        assert!((cost - six_five.payout_cost()).abs() < 1e-3);
+    }
+
+    #[test]
+    fn check_misplays() {
+        let rules = rules(true, NoPeekLoss::OriginalBetsOnly);
+        let mut bs = strategy(&rules);
+        assert!(misplays(&bs, &rules).is_empty());
+        let eleven = cell(HandType::Hard, 11, 6);
+        bs.table = bs
+            .table
+            .map(|desc, resp| if desc == eleven { Resp::Stand } else { resp });
+        let found = misplays(&bs, &rules);
+        assert_eq!(found.len(), 1);
+        assert_eq!(
+            (found[0].desc, found[0].card, found[0].best),
+            (eleven, Resp::Stand, Resp::DoubleElseHit)
+        );
+        assert!(found[0].cost > 0.5);
+    }
+}
@@ -0,0 +1,109 @@
+//! Everything that's saved, to disk or to a browser's storage, has to keep loading after the types
+//! behind it change. testdata/formats has what each saved format looked like at each point it
+//! changed, and these tests load every one of them.
+//!
+//! Never edit a fixture. Each *-current fixture also has to serialize back exactly as it is, so a
+//! change to a format fails here first. When that's intended, rename the old fixture after what
+//! the new version adds to it (like stats-streak.json, from before last_seen), save the new format
+//! as the *-current one, and add checks that the old one loads as it should.
+use crate::basicstrategy::rules::{
+    BlackjackPayout, NoPeekLoss, NumDecks, ResplitAces, Rules, Surrender,
+};
+use crate::basicstrategy::BasicStrategy;
+use crate::playstats::PlayStats;
+use crate::resp::Resp;
+use crate::table::{GameDesc, Table};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+fn fixture(name: &str) -> String {
+    let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("testdata/formats")
+        .join(name);
+    std::fs::read_to_string(&path).unwrap_or_else(|e| panic!("{}: {}", path.display(), e))
+}
+
+fn load<T: DeserializeOwned>(name: &str) -> T {
+    serde_json::from_str(&fixture(name)).unwrap_or_else(|e| panic!("{} doesn't load: {}", name, e))
+}
+
+/// Load the fixture, and check it serializes back the same
+fn round_trip<T: DeserializeOwned + Serialize>(name: &str) -> T {
+    let val: T = load(name);
+    let before: serde_json::Value = load(name);
+    assert_eq!(
+        serde_json::to_value(&val).unwrap(),
+        before,
+        "{} serializes differently now",
+        name
+    );
+    val
+}
+
+fn desc(label: &str) -> GameDesc {
+    label.parse().unwrap()
+}
+
+#[test]
+fn stats() {
+    let current: Table<PlayStats> = round_trip("stats-current.json");
+    let streak: Table<PlayStats> = load("stats-streak.json");
+    let oldest: Table<PlayStats> = load("stats-seen-correct.json");
+    // every version has the counts
+    let counts = |t: &Table<PlayStats>| t.map(|_, s| (s.seen(), s.correct()));
+    assert_eq!(counts(&oldest), counts(&current));
+    assert_eq!(counts(&streak), counts(&current));
+    let cell = desc("Hard 5 vs 2");
+    let s = current.get_desc(cell).unwrap();
+    assert_eq!((s.last_seen(), s.prior()), (Some(1_600_000_000), Some(60)));
+    let s = current.get_desc(desc("Hard 6 vs 9")).unwrap();
+    assert_eq!((s.seen(), s.correct(), s.streak()), (8, 6, 5));
+    assert_eq!(streak.get_desc(desc("Hard 6 vs 9")).unwrap().streak(), 5);
+    // what older versions didn't have starts out empty
+    let s = streak.get_desc(cell).unwrap();
+    assert_eq!((s.last_seen(), s.prior()), (None, None));
+    assert!(oldest.values().all(|s| s.streak() == 0));
+}
+
+#[test]
+fn cards() {
+    let current: BasicStrategy = round_trip("card-current.json");
+    assert_eq!(
+        current.rules,
+        Some(Rules {
+            decks: NumDecks::Two,
+            hit_soft_17: false.into(),
+            double_after_split: false.into(),
+            peek_bj: false.into(),
+            surrender: Surrender::NotAce,
+            no_peek_loss: NoPeekLoss::AllBets,
+            max_splits: 2.into(),
+            resplit_aces: true.into(),
+            hit_split_aces: true.into(),
+            blackjack_pays: BlackjackPayout::SixToFive,
+        })
+    );
+    assert_eq!(
+        current.composition,
+        vec![("T,2 vs 3".parse().unwrap(), Resp::Stand)]
+    );
+    assert_eq!(current.unlearned, vec![desc("Hard 16 vs 10")]);
+    assert_eq!(
+        current.table.get_desc(desc("8,8 vs 10")),
+        Some(Resp::SurrenderElseHit)
+    );
+
+    // from before any rules past surrender, composition-dependent plays, or partial cards
+    let original: BasicStrategy = load("card-original-rules.json");
+    let rules = original.rules.as_ref().unwrap();
+    assert_eq!(rules.decks, NumDecks::FourPlus);
+    assert_eq!(rules.no_peek_loss, NoPeekLoss::OriginalBetsOnly);
+    assert_eq!(rules.max_splits, Default::default());
+    assert_eq!(rules.resplit_aces, ResplitAces::from(false));
+    assert_eq!(rules.blackjack_pays, BlackjackPayout::ThreeToTwo);
+    assert!(original.composition.is_empty() && !original.is_partial());
+    assert_eq!(
+        original.table.get_desc(desc("Hard 11 vs A")),
+        Some(Resp::DoubleElseHit)
+    );
+}
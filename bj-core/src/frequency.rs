@@ -0,0 +1,156 @@
+//! How often each cell of a strategy card comes up in real play, and accuracy weighted by it.
+//!
+//! Frequencies are exact for a shuffled infinite deck, and are the chance that a round puts the
+//! player in the cell, so they add up to more than 1 when hitting is counted. Every round starts
+//! with an opening hand, which lands in one cell. If a strategy card is given, hands it says to hit
+//! are followed into the cells the next card puts them in, for as long as it keeps saying to hit.
+//! Hands made by splitting aren't followed, nor are blackjacks, which the player doesn't act on.
+//!
+//! Getting hard 16 vs 10 wrong costs far more often than getting 5,5 vs A wrong, and
+//! weighted_accuracy() says so: it's the player's accuracy with each cell counting as much as it
+//! comes up.
+use crate::basicstrategy::BasicStrategy;
+use crate::deck::{Card, Suit, ALL_RANKS};
+use crate::hand::Hand;
+use crate::playstats::PlayStats;
+use crate::resp::Resp;
+use crate::table::{GameDesc, Table};
+use std::collections::{BTreeMap, HashMap};
+
+/// The chance of drawing any one rank from an infinite deck
+const RANK_CHANCE: f64 = 1.0 / 13.0;
+
+/// Whether the card says to keep hitting the hand. Opening hands can double, split, and surrender
+/// instead, all of which end the hand as far as the card is concerned.
+fn keeps_hitting(bs: &BasicStrategy, hand: &Hand, dealer: Card) -> bool {
+    let resp = match bs.resp(hand, dealer) {
+        Ok(resp) => resp,
+        Err(_) => return false,
+    };
+    if hand.cards().count() == 2 {
+        resp == Resp::Hit
+    } else {
+        resp.hit_or_stand() == Some(Resp::Hit)
+    }
+}
+
+/// The chance per round of the player acting on a hand in each cell. If bs is given, hands it says
+/// to hit are followed. See the module docs.
+pub fn cell_frequencies(bs: Option<&BasicStrategy>) -> Table<f64> {
+    let card = |rank| Card::new(rank, Suit::Club);
+    let mut freq: HashMap<GameDesc, f64> = HashMap::new();
+    for dealer in ALL_RANKS.iter().map(|r| card(*r)) {
+        // hands still being hit, by (total counting aces as 1, has an ace), which is all that
+        // decides where they go next. Every hit raises the total, so working from the lowest
+        // total up sees each one once.
+        let mut hitting: BTreeMap<(u8, bool), (Hand, f64)> = BTreeMap::new();
+        let mut add = |hand: Hand, chance: f64, hitting: &mut BTreeMap<_, _>| {
+            *freq
+                .entry(GameDesc::from_hand(&hand, dealer))
+                .or_insert(0.0) += chance;
+            if bs.is_some_and(|bs| keeps_hitting(bs, &hand, dealer)) {
+                let key = (
+                    hand.cards().map(|c| c.value()).sum::<u8>(),
+                    hand.cards().any(|c| c.value() == 1),
+                );
+                hitting.entry(key).or_insert((hand, 0.0)).1 += chance;
+            }
+        };
+        let chance = RANK_CHANCE.powi(3);
+        for first in ALL_RANKS.iter() {
+            for second in ALL_RANKS.iter() {
+                let hand = Hand::new(&[card(*first), card(*second)]);
+                if !hand.is_blackjack() {
+                    add(hand, chance, &mut hitting);
+                }
+            }
+        }
+        while let Some(&key) = hitting.keys().next() {
+            let (hand, chance) = hitting.remove(&key).unwrap();
+            for rank in ALL_RANKS.iter() {
+                let mut next = hand.clone();
+                next.push(card(*rank));
+                if !next.is_bust() {
+                    add(next, chance * RANK_CHANCE, &mut hitting);
+                }
+            }
+        }
+    }
+    let mut table = Table::filled(0.0);
+    for (desc, v) in table.iter_mut() {
+        *v = freq.get(desc).copied().unwrap_or(0.0);
+    }
+    table
+}
+
+/// The fraction of hands the player got right, with each seen cell counting in proportion to its
+/// frequency. None if no cell with a frequency has been seen.
+pub fn weighted_accuracy(stats: &Table<PlayStats>, freq: &Table<f64>) -> Option<f64> {
+    let (correct, total) = stats
+        .iter()
+        .filter(|(_, s)| s.seen() > 0)
+        .filter_map(|(desc, s)| {
+            let f = freq.get_desc(*desc)?;
+            Some((f * f64::from(s.correct()) / f64::from(s.seen()), f))
+        })
+        .fold((0.0, 0.0), |(c, t), (cell_c, cell_t)| {
+            (c + cell_c, t + cell_t)
+        });
+    if total > 0.0 {
+        Some(correct / total)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::deck::Rank;
+    use crate::resp::resps_from_buf;
+
+    const T1: &[u8] = include_bytes!("../../t1.txt");
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-9
+    }
+
+    #[test]
+    fn frequencies() {
+        let opening = cell_frequencies(None);
+        // everything but blackjacks
+        assert!(close(opening.values().sum(), 1.0 - 8.0 / 169.0));
+        let hard16 = Hand::new(&[
+            Card::new(Rank::RT, Suit::Club),
+            Card::new(Rank::R6, Suit::Club),
+        ]);
+        let ten = Card::new(Rank::RT, Suit::Club);
+        // T,6 6,T 9,7 7,9, with 4 times the chance of a ten, against a ten
+        let f = opening.get(&hard16, ten).unwrap();
+        assert!(close(f, 10.0 / 169.0 * 4.0 / 13.0));
+        // hitting 12-15 against a ten makes more 16s
+        let bs = BasicStrategy::new(None, Table::new(resps_from_buf(T1).unwrap()).unwrap());
+        let played = cell_frequencies(Some(&bs));
+        assert!(played.get(&hard16, ten).unwrap() > f);
+        assert!(played.values().sum::<f64>() > 1.0);
+    }
+
+    #[test]
+    fn weighted() {
+        let freq = cell_frequencies(None);
+        let mut stats = Table::new(vec![PlayStats::new(); 360]).unwrap();
+        assert_eq!(weighted_accuracy(&stats, &freq), None);
+        let club = |r| Card::new(r, Suit::Club);
+        let hard16 = Hand::new(&[club(Rank::RT), club(Rank::R6)]);
+        let aces = Hand::new(&[club(Rank::RA), club(Rank::RA)]);
+        let mut right = PlayStats::new();
+        right.inc(true);
+        let mut wrong = PlayStats::new();
+        wrong.inc(false);
+        // right on a common hand, wrong on a rare one
+        stats.update(&hard16, club(Rank::RT), right).unwrap();
+        stats.update(&aces, club(Rank::R6), wrong).unwrap();
+        let acc = weighted_accuracy(&stats, &freq).unwrap();
+        assert!(acc > 0.9, "{}", acc);
+    }
+}
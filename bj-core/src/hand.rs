@@ -1,18 +1,25 @@
-use crate::basicstrategy::rules::Surrender;
-use crate::deck::{rand_suit, Card, Rank};
+use crate::basicstrategy::rules::{SplitState, Surrender};
+#[cfg(feature = "rand")]
+use crate::deck::rand_suit;
+use crate::deck::{Card, Rank};
 use crate::table::GameDesc;
+#[cfg(feature = "rand")]
 use rand::prelude::*;
-use serde_derive::{Deserialize, Serialize};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 use std::fmt;
+use thiserror::Error;
 
-#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone, Serialize, Deserialize)]
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum HandType {
     Hard,
     Soft,
     Pair,
 }
 
-#[derive(Debug, PartialEq, Eq, Hash, Clone, Serialize, Deserialize)]
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Hand {
     pub(crate) cards: Vec<Card>,
 }
@@ -39,6 +46,18 @@ impl Hand {
         }
     }
 
+    /// Which part of a strategy card the hand is found in. Pairs are pairs even if they're soft
+    /// (aces).
+    pub fn hand_type(&self) -> HandType {
+        if self.is_pair() {
+            HandType::Pair
+        } else if self.is_soft() {
+            HandType::Soft
+        } else {
+            HandType::Hard
+        }
+    }
+
     /// Returns whether or not a hand is soft. A hand is soft if it has at least 1 ace that can be
     /// worth either 1 or 11 without busting.
     pub fn is_soft(&self) -> bool {
@@ -92,6 +111,18 @@ impl Hand {
         self.value() > 21
     }
 
+    /// Whether or not the hand is a natural: an ace and a ten-valued card as its only two cards.
+    /// A hand made by splitting can look like one, but the caller has to know it isn't.
+    pub fn is_blackjack(&self) -> bool {
+        self.cards.len() == 2 && self.value() == 21
+    }
+
+    /// Whether or not the hand is a natural, given where it came from. An ace and a ten-valued
+    /// card made by splitting are only 21, and win even money like any other 21.
+    pub fn is_natural(&self, split: SplitState) -> bool {
+        split.splits == 0 && self.is_blackjack()
+    }
+
     /// Whether or not the hand is a pair of same-ranked cards (never true for 3+ cards)
     pub fn is_pair(&self) -> bool {
         if self.cards.len() > 2 {
@@ -154,27 +185,17 @@ impl Hand {
     }
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Error)]
 pub enum HandError {
+    #[error("Impossible to split hand {0}")]
     CannotSplit(Hand),
+    #[error(
+        "Cannot make {:?} hand with value {} (against dealer {})",
+        .0.hand, .0.player, .0.dealer
+    )]
     ImpossibleGameDesc(GameDesc),
 }
 
-impl std::error::Error for HandError {}
-
-impl fmt::Display for HandError {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match self {
-            HandError::ImpossibleGameDesc(desc) => write!(
-                f,
-                "Cannot make {:?} hand with value {} (against dealer {})",
-                desc.hand, desc.player, desc.dealer
-            ),
-            HandError::CannotSplit(hand) => write!(f, "Impossible to split hand {}", hand),
-        }
-    }
-}
-
 /// Generate and random vector of cards that constitute a soft hand of the given value while
 /// respecting the given min and max length (inclusive).
 ///
@@ -191,6 +212,7 @@ impl fmt::Display for HandError {
 /// amount. E.g.: soft 18 with all aces is 8 cards long (11 + 7*1).
 ///
 /// The generation of a hard hand is delegated to cards_hard_sum_to().
+#[cfg(feature = "rand")]
 fn cards_soft_sum_to(amt: u8, min_len: u8, max_len: u8) -> Vec<Card> {
     // no such thing as a soft hand worth less than 12 or more than 21
     assert!(amt >= 12);
@@ -223,7 +245,7 @@ fn cards_soft_sum_to(amt: u8, min_len: u8, max_len: u8) -> Vec<Card> {
             10 => *[Rank::RT, Rank::RJ, Rank::RQ, Rank::RK]
                 .choose(&mut rng)
                 .unwrap(),
-            v => unreachable!(format!("Impossible to return card with value {}", v)),
+            v => unreachable!("Impossible to return card with value {}", v),
         };
         cards.push(Card::new(rank, rand_suit()));
         cards
@@ -250,6 +272,7 @@ fn cards_soft_sum_to(amt: u8, min_len: u8, max_len: u8) -> Vec<Card> {
 /// to be able to do this now, and it makes reasoning about max_len easier
 /// - Refuse a min_len less than 2, a max_len less than the min_len (it can be equal, however), and
 /// enforce a max_len of at least 3 if amount requested is 20 or 21.
+#[cfg(feature = "rand")]
 fn cards_hard_sum_to(amt: u8, min_len: u8, max_len: u8) -> Vec<Card> {
     assert!(amt >= 5);
     assert!(amt <= 21);
@@ -284,7 +307,7 @@ fn cards_hard_sum_to(amt: u8, min_len: u8, max_len: u8) -> Vec<Card> {
                     11 => Rank::RJ,
                     12 => Rank::RQ,
                     13 => Rank::RK,
-                    v => unreachable!(format!("Impossible to return card with value {}", v)),
+                    v => unreachable!("Impossible to return card with value {}", v),
                 };
                 v.push(Card::new(rank, rand_suit()));
                 remaining -= rank.value();
@@ -310,6 +333,7 @@ fn cards_hard_sum_to(amt: u8, min_len: u8, max_len: u8) -> Vec<Card> {
     hand.cards
 }
 
+#[cfg(feature = "rand")]
 pub fn rand_hand(desc: GameDesc) -> Result<Hand, HandError> {
     let mut rng = thread_rng();
     let s1 = rand_suit();
@@ -363,16 +387,47 @@ pub fn rand_hand(desc: GameDesc) -> Result<Hand, HandError> {
     Ok(h)
 }
 
+/// Generate a random hand of three or four cards in the given cell, like one the player reaches by
+/// hitting, on which doubling and surrendering aren't allowed. Pairs, hard 5, and soft 13-15 (which
+/// would be mostly aces) aren't made this way, and return HandError::ImpossibleGameDesc.
+#[cfg(feature = "rand")]
+pub fn rand_multi_card_hand(desc: GameDesc) -> Result<Hand, HandError> {
+    let cards = match desc.hand {
+        HandType::Hard if (6..=21).contains(&desc.player) => cards_hard_sum_to(desc.player, 3, 4),
+        // the ace plus a hard hand of at least two cards
+        HandType::Soft if (16..=21).contains(&desc.player) => cards_soft_sum_to(desc.player, 3, 4),
+        _ => return Err(HandError::ImpossibleGameDesc(desc)),
+    };
+    let h = Hand::new(&cards);
+    assert_eq!(h.value(), desc.player);
+    Ok(h)
+}
+
 #[cfg(test)]
-mod tests {
-    use super::{rand_hand, Hand, HandError, HandType};
+pub(crate) mod tests {
+    #[cfg(feature = "rand")]
+    use super::{rand_hand, rand_multi_card_hand, HandType};
+    use super::{Hand, HandError};
     use crate::basicstrategy::rules::Surrender;
     use crate::deck::{Card, Rank, Suit, ALL_RANKS};
+    #[cfg(feature = "rand")]
     use crate::table::GameDesc;
     const SUIT: Suit = Suit::Club;
+    #[cfg(feature = "rand")]
     const DEALER_VAL: u8 = 2;
+    #[cfg(feature = "rand")]
     const RAND_REPS: usize = 1000;
 
+    /// A hand of the given ranks, for tests that don't care about suits
+    pub(crate) fn hand(ranks: &[Rank]) -> Hand {
+        Hand::new(
+            &ranks
+                .iter()
+                .map(|r| Card::new(*r, SUIT))
+                .collect::<Vec<_>>(),
+        )
+    }
+
     fn all_2card_hands() -> Vec<Hand> {
         let mut hands = vec![];
         for r1 in ALL_RANKS.iter() {
@@ -386,6 +441,7 @@ mod tests {
     }
 
     #[test]
+    #[cfg(feature = "rand")]
     fn rand_pair_bad() {
         for _ in 0..RAND_REPS {
             // cannot ask for a random pair with value zero, odd value, or even value larger than 22
@@ -400,6 +456,7 @@ mod tests {
     }
 
     #[test]
+    #[cfg(feature = "rand")]
     fn rand_pair_good() {
         for _ in 0..RAND_REPS {
             // test all valid pairs
@@ -417,6 +474,36 @@ mod tests {
     }
 
     #[test]
+    #[cfg(feature = "rand")]
+    fn rand_multi_card() {
+        for _ in 0..100 {
+            for (hand, v) in (6..=21)
+                .map(|v| (HandType::Hard, v))
+                .chain((16..=21).map(|v| (HandType::Soft, v)))
+            {
+                let desc = GameDesc::new(hand, v, DEALER_VAL);
+                let h = rand_multi_card_hand(desc).unwrap();
+                assert!((3..=4).contains(&h.cards().count()), "{}", h);
+                assert_eq!((h.value(), h.hand_type()), (v, hand), "{}", h);
+                assert!(!h.can_double());
+            }
+        }
+        for desc in [
+            GameDesc::new(HandType::Hard, 5, DEALER_VAL),
+            GameDesc::new(HandType::Soft, 15, DEALER_VAL),
+            GameDesc::new(HandType::Pair, 16, DEALER_VAL),
+        ]
+        .iter()
+        {
+            assert_eq!(
+                rand_multi_card_hand(*desc),
+                Err(HandError::ImpossibleGameDesc(*desc))
+            );
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "rand")]
     fn rand_soft_bad() {
         for _ in 0..RAND_REPS {
             // cannot ask for a soft hand outside of valid soft hand range
@@ -428,6 +515,7 @@ mod tests {
     }
 
     #[test]
+    #[cfg(feature = "rand")]
     fn rand_soft_good() {
         for _ in 0..RAND_REPS {
             // test all valid soft hands
@@ -441,6 +529,7 @@ mod tests {
     }
 
     #[test]
+    #[cfg(feature = "rand")]
     fn rand_hard_bad() {
         for _ in 0..RAND_REPS {
             for v in 0..=1 {
@@ -451,6 +540,7 @@ mod tests {
     }
 
     #[test]
+    #[cfg(feature = "rand")]
     fn rand_hard_good() {
         for _ in 0..RAND_REPS {
             for v in 5..=21 {
@@ -0,0 +1,100 @@
+//! The dealer's hole card, and when it comes into play.
+//!
+//! The dealer is dealt two cards: the up card, which the player sees, and the hole card, which
+//! they don't. Before the player acts:
+//!
+//! 1. If the up card is an ace, the player is offered insurance, or even money if they have a
+//!    blackjack.
+//! 2. If the game is a peek game and the up card is an ace or ten-valued, the dealer checks the
+//!    hole card. A dealer blackjack ends the round right away, before the player can double or
+//!    split.
+//!
+//! Otherwise the hole card stays face down until the player has played every hand, and is
+//! revealed when the dealer plays theirs. In no-peek games that's when a dealer blackjack is
+//! found, and Rules::no_peek_loss says what it takes.
+//!
+//! Basic strategy never takes insurance or even money. Counters take both once the true count is
+//! at or above the insurance index, +3 with HiLo. See takes_insurance().
+use crate::basicstrategy::rules::Rules;
+use crate::deck::{Card, Rank};
+use crate::hand::Hand;
+
+/// Whether to take insurance, or even money, which is the same bet. Without a true count, i.e.
+/// playing basic strategy, never. With one, when it's at or above index.
+pub fn takes_insurance(true_count: Option<f32>, index: i8) -> bool {
+    true_count.is_some_and(|tc| tc >= f32::from(index))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DealerHand {
+    up: Card,
+    hole: Card,
+}
+
+impl DealerHand {
+    pub fn new(up: Card, hole: Card) -> Self {
+        Self { up, hole }
+    }
+
+    pub fn up(&self) -> Card {
+        self.up
+    }
+
+    pub fn hole(&self) -> Card {
+        self.hole
+    }
+
+    /// Whether the player is offered insurance (or even money) before anything else happens
+    pub fn offers_insurance(&self) -> bool {
+        self.up.rank() == Rank::RA
+    }
+
+    /// Whether the dealer checks the hole card for blackjack before the player acts. If the rules
+    /// aren't known, the dealer peeks, as in most shoe games.
+    pub fn peeks(&self, rules: Option<&Rules>) -> bool {
+        rules.is_none_or(|r| r.dealer_peeks()) && (self.offers_insurance() || self.up.value() == 10)
+    }
+
+    /// Whether the dealer has blackjack. Only something the player gets to know once the dealer
+    /// has peeked (see peeks()) or the hole card is revealed.
+    pub fn is_blackjack(&self) -> bool {
+        self.reveal().is_blackjack()
+    }
+
+    /// The dealer's two cards, face up, for them to play
+    pub fn reveal(&self) -> Hand {
+        Hand::new(&[self.up, self.hole])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::deck::Suit;
+
+    fn dealer(up: Rank, hole: Rank) -> DealerHand {
+        DealerHand::new(Card::new(up, Suit::Club), Card::new(hole, Suit::Heart))
+    }
+
+    #[test]
+    fn peek() {
+        let no_peek = Rules {
+            hit_soft_17: true.into(),
+            peek_bj: false.into(),
+            ..Default::default()
+        };
+        let ace_king = dealer(Rank::RA, Rank::RK);
+        assert!(ace_king.offers_insurance() && ace_king.is_blackjack());
+        assert!(ace_king.peeks(None));
+        assert!(!ace_king.peeks(Some(&no_peek)));
+        let queen_ace = dealer(Rank::RQ, Rank::RA);
+        assert!(!queen_ace.offers_insurance() && queen_ace.is_blackjack());
+        assert!(queen_ace.peeks(None));
+        let nine_ace = dealer(Rank::R9, Rank::RA);
+        assert!(!nine_ace.peeks(None) && !nine_ace.is_blackjack());
+        assert_eq!(nine_ace.reveal().value(), 20);
+        assert!(!takes_insurance(None, 3));
+        assert!(!takes_insurance(Some(2.9), 3));
+        assert!(takes_insurance(Some(3.0), 3));
+    }
+}
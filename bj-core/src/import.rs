@@ -0,0 +1,368 @@
+//! Import play history exported by other trainers into PlayStats.
+//!
+//! Other trainers export history as CSV with one row per decision, and they don't agree on what
+//! the columns are called or how cards and actions are written. A CsvMapping says which columns
+//! hold what. Presets cover common layouts, and anything else can be described with a mapping of
+//! its own (e.g. a JSON file).
+//!
+//! Each row needs the player's cards and the dealer's upcard, plus either the action taken or
+//! whether it was correct. When the action is known it is graded against our own card, so the
+//! imported stats mean the same thing as stats earned here. The other trainer's own grading is
+//! only used when the action isn't exported.
+//!
+//! Cards are read leniently: "A7", "A,7", "a 7", and "10-6" are all fine. Rows that can't be read
+//! are skipped and reported rather than failing the whole import.
+use crate::basicstrategy::rules::Surrender;
+use crate::basicstrategy::BasicStrategy;
+use crate::button::GameButton;
+use crate::correct_resp::is_correct_resp_button;
+use crate::deck::{Card, Rank, Suit};
+use crate::hand::Hand;
+use crate::playstats::PlayStats;
+use crate::table::Table;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/// Which columns, by header name, hold what. Header names are matched ignoring case and
+/// surrounding whitespace.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct CsvMapping {
+    /// The player's cards
+    pub player: String,
+    /// The dealer's upcard
+    pub dealer: String,
+    /// The action the player took
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub action: Option<String>,
+    /// Whether the other trainer said the action was correct
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub correct: Option<String>,
+    #[cfg_attr(feature = "serde", serde(default = "def_delimiter"))]
+    pub delimiter: char,
+}
+
+fn def_delimiter() -> char {
+    ','
+}
+
+/// The names of the mappings CsvMapping::preset() knows
+pub const PRESETS: [&str; 2] = ["generic", "graded"];
+
+impl CsvMapping {
+    /// A built in mapping by name. "generic" is columns hand, dealer, action, and correct (which
+    /// is ignored as action is present). "graded" is for trainers that only export whether each
+    /// decision was right: columns hand, dealer, and correct.
+    pub fn preset(name: &str) -> Option<Self> {
+        let m = |action: Option<&str>, correct: Option<&str>| Self {
+            player: "hand".to_string(),
+            dealer: "dealer".to_string(),
+            action: action.map(str::to_string),
+            correct: correct.map(str::to_string),
+            delimiter: def_delimiter(),
+        };
+        match name {
+            "generic" => Some(m(Some("action"), Some("correct"))),
+            "graded" => Some(m(None, Some("correct"))),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Error)]
+pub enum ImportError {
+    /// The input has no header row
+    #[error("Nothing to import: no header row")]
+    Empty,
+    /// The mapping names a column the header doesn't have
+    #[error("No column named '{0}'")]
+    MissingColumn(String),
+    /// The mapping has neither an action nor a correct column, so nothing can be graded
+    #[error("Mapping needs an action or correct column")]
+    NoGrade,
+}
+
+/// What an import did
+#[derive(Debug, Default, PartialEq)]
+pub struct ImportSummary {
+    /// The number of rows added to the stats
+    pub imported: u32,
+    /// The (1-based) line number of every row that was skipped, and why
+    pub skipped: Vec<(usize, String)>,
+}
+
+/// Split a line of CSV into fields. Fields may be quoted, with "" for a literal quote inside
+/// quotes. Quoted fields can't span lines.
+fn split_line(line: &str, delimiter: char) -> Vec<String> {
+    let mut fields = vec![];
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        if quoted {
+            if c == '"' {
+                if chars.peek() == Some(&'"') {
+                    field.push('"');
+                    chars.next();
+                } else {
+                    quoted = false;
+                }
+            } else {
+                field.push(c);
+            }
+        } else if c == '"' {
+            quoted = true;
+        } else if c == delimiter {
+            fields.push(std::mem::take(&mut field));
+        } else {
+            field.push(c);
+        }
+    }
+    fields.push(field);
+    fields
+}
+
+/// Read cards written as ranks, e.g. "A7", "10,6", or "k q". Suits don't matter, so every card is
+/// a spade.
+fn parse_cards(s: &str) -> Result<Vec<Card>, String> {
+    let mut cards = vec![];
+    let mut chars = s.chars().peekable();
+    while let Some(c) = chars.next() {
+        let rank = match c.to_ascii_uppercase() {
+            '2' => Rank::R2,
+            '3' => Rank::R3,
+            '4' => Rank::R4,
+            '5' => Rank::R5,
+            '6' => Rank::R6,
+            '7' => Rank::R7,
+            '8' => Rank::R8,
+            '9' => Rank::R9,
+            '1' if chars.peek() == Some(&'0') => {
+                chars.next();
+                Rank::RT
+            }
+            'T' => Rank::RT,
+            'J' => Rank::RJ,
+            'Q' => Rank::RQ,
+            'K' => Rank::RK,
+            'A' => Rank::RA,
+            ' ' | ',' | '-' | '/' | '+' | ';' => continue,
+            _ => return Err(format!("'{}' is not a card", s)),
+        };
+        cards.push(Card::new(rank, Suit::Spade));
+    }
+    Ok(cards)
+}
+
+fn parse_action(s: &str) -> Option<GameButton> {
+    match s.trim().to_ascii_lowercase().as_str() {
+        "h" | "hit" => Some(GameButton::Hit),
+        "s" | "stand" | "stay" => Some(GameButton::Stand),
+        "d" | "dd" | "double" | "double down" => Some(GameButton::Double),
+        "p" | "sp" | "split" => Some(GameButton::Split),
+        "r" | "su" | "sur" | "surrender" => Some(GameButton::Surrender),
+        _ => None,
+    }
+}
+
+fn parse_correct(s: &str) -> Option<bool> {
+    match s.trim().to_ascii_lowercase().as_str() {
+        "1" | "true" | "yes" | "y" | "correct" | "right" => Some(true),
+        "0" | "false" | "no" | "n" | "incorrect" | "wrong" => Some(false),
+        _ => None,
+    }
+}
+
+/// Grade one row, returning the hand and whether the player was correct
+fn grade_row(
+    row: &[String],
+    cols: &Columns,
+    bs: &BasicStrategy,
+    surrender: Surrender,
+) -> Result<(Hand, Card, bool), String> {
+    let field = |i: usize| row.get(i).map(|s| s.trim()).unwrap_or("");
+    let player = parse_cards(field(cols.player))?;
+    if player.len() < 2 {
+        return Err(format!("'{}' is not a whole hand", field(cols.player)));
+    }
+    let dealer = match parse_cards(field(cols.dealer))?.as_slice() {
+        [c] => *c,
+        _ => return Err(format!("'{}' is not one card", field(cols.dealer))),
+    };
+    let player = Hand::new(&player);
+    if let Some(i) = cols.action {
+        if let Some(action) = parse_action(field(i)) {
+            let correct = bs.resp(&player, dealer).map_err(|e| e.to_string())?;
+            let is_correct = is_correct_resp_button(action, correct, (&player, dealer), surrender);
+            return Ok((player, dealer, is_correct));
+        }
+    }
+    match cols.correct.and_then(|i| parse_correct(field(i))) {
+        Some(is_correct) => Ok((player, dealer, is_correct)),
+        None => Err("no readable action or correctness".to_string()),
+    }
+}
+
+struct Columns {
+    player: usize,
+    dealer: usize,
+    action: Option<usize>,
+    correct: Option<usize>,
+}
+
+/// Import the CSV into the stats, grading against the given card. Rows that can't be read are
+/// skipped and listed in the summary.
+pub fn import_csv(
+    csv: &str,
+    mapping: &CsvMapping,
+    bs: &BasicStrategy,
+    stats: &mut Table<PlayStats>,
+) -> Result<ImportSummary, ImportError> {
+    if mapping.action.is_none() && mapping.correct.is_none() {
+        return Err(ImportError::NoGrade);
+    }
+    let mut lines = csv
+        .lines()
+        .enumerate()
+        .filter(|(_, l)| !l.trim().is_empty());
+    let header = match lines.next() {
+        Some((_, l)) => split_line(l, mapping.delimiter),
+        None => return Err(ImportError::Empty),
+    };
+    let find = |name: &str| {
+        header
+            .iter()
+            .position(|h| h.trim().eq_ignore_ascii_case(name.trim()))
+            .ok_or_else(|| ImportError::MissingColumn(name.to_string()))
+    };
+    let cols = Columns {
+        player: find(&mapping.player)?,
+        dealer: find(&mapping.dealer)?,
+        action: mapping.action.as_deref().map(find).transpose()?,
+        correct: mapping.correct.as_deref().map(find).transpose()?,
+    };
+    let surrender = match &bs.rules {
+        None => Surrender::Yes,
+        Some(rules) => rules.surrender,
+    };
+    let mut summary = ImportSummary::default();
+    for (i, line) in lines {
+        let row = split_line(line, mapping.delimiter);
+        match grade_row(&row, &cols, bs, surrender) {
+            Ok((player, dealer, is_correct)) => match stats.get(&player, dealer) {
+                Ok(mut stat) => {
+                    stat.inc(is_correct);
+                    stats.update(&player, dealer, stat).ok();
+                    summary.imported += 1;
+                }
+                // e.g. a bust hand from a graded row, which the card was never asked about
+                Err(e) => summary.skipped.push((i + 1, e.to_string())),
+            },
+            Err(e) => summary.skipped.push((i + 1, e)),
+        }
+    }
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::resp::resps_from_buf;
+
+    fn card() -> BasicStrategy {
+        let table = Table::new(resps_from_buf(&include_bytes!("../../t1.txt")[..]).unwrap());
+        BasicStrategy::new(None, table.unwrap())
+    }
+
+    fn stats() -> Table<PlayStats> {
+        Table::new(vec![PlayStats::new(); 360]).unwrap()
+    }
+
+    fn seen(stats: &Table<PlayStats>, player: &str, dealer: &str) -> (u32, u32) {
+        let player = Hand::new(&parse_cards(player).unwrap());
+        let dealer = parse_cards(dealer).unwrap()[0];
+        let s = stats.get(&player, dealer).unwrap();
+        (s.seen(), s.correct())
+    }
+
+    #[test]
+    fn csv_fields() {
+        assert_eq!(split_line("a,b,,c", ','), vec!["a", "b", "", "c"]);
+        assert_eq!(
+            split_line(r#""A,7",6,"say ""hi""""#, ','),
+            vec!["A,7", "6", r#"say "hi""#]
+        );
+        assert_eq!(split_line("a;b", ';'), vec!["a", "b"]);
+    }
+
+    #[test]
+    fn cards() {
+        let ranks = |s| {
+            parse_cards(s)
+                .unwrap()
+                .iter()
+                .map(|c| c.rank())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(ranks("A7"), vec![Rank::RA, Rank::R7]);
+        assert_eq!(ranks("10-6"), vec![Rank::RT, Rank::R6]);
+        assert_eq!(ranks("k, q"), vec![Rank::RK, Rank::RQ]);
+        assert!(parse_cards("1").is_err());
+        assert!(parse_cards("soft 18").is_err());
+    }
+
+    #[test]
+    fn import_generic() {
+        // the correct column disagrees with our card on the first row, and loses
+        let csv = "Hand,Dealer,Action,Correct\n\
+                   8 8,6,split,no\n\
+                   \"10,6\",10,Stand,yes\n\
+                   \n\
+                   A7,3,D,\n\
+                   Z9,3,H,yes\n\
+                   9 7,T,fold,yes\n";
+        let mut stats = stats();
+        let mapping = CsvMapping::preset("generic").unwrap();
+        let summary = import_csv(csv, &mapping, &card(), &mut stats).unwrap();
+        assert_eq!(summary.imported, 4);
+        assert_eq!(summary.skipped.len(), 1);
+        assert_eq!(summary.skipped[0].0, 6);
+        assert_eq!(seen(&stats, "88", "6"), (1, 1));
+        assert_eq!(seen(&stats, "T6", "T"), (2, 1));
+        assert_eq!(seen(&stats, "A7", "3"), (1, 1));
+    }
+
+    #[test]
+    fn import_graded_without_cell() {
+        // only the action is checked against the card, so a graded row can name a bust hand
+        let csv = "hand,dealer,correct\nKQ5,6,yes\n9 7,T,no\n";
+        let mut stats = stats();
+        let mapping = CsvMapping::preset("graded").unwrap();
+        let summary = import_csv(csv, &mapping, &card(), &mut stats).unwrap();
+        assert_eq!(summary.imported, 1);
+        assert_eq!(summary.skipped.len(), 1);
+        assert_eq!(summary.skipped[0].0, 2);
+        assert!(summary.skipped[0].1.contains("bust"));
+        assert_eq!(seen(&stats, "97", "T"), (1, 0));
+    }
+
+    #[test]
+    fn import_errors() {
+        let mut stats = stats();
+        let mut mapping = CsvMapping::preset("graded").unwrap();
+        assert_eq!(
+            import_csv("", &mapping, &card(), &mut stats),
+            Err(ImportError::Empty)
+        );
+        assert_eq!(
+            import_csv("hand,upcard,correct\n", &mapping, &card(), &mut stats),
+            Err(ImportError::MissingColumn("dealer".to_string()))
+        );
+        mapping.correct = None;
+        assert_eq!(
+            import_csv("hand,dealer\n", &mapping, &card(), &mut stats),
+            Err(ImportError::NoGrade)
+        );
+    }
+}
@@ -0,0 +1,214 @@
+//! A journal of real casino sessions, and what they add up to.
+//!
+//! Each entry is one session: when and where, the rules it was played under, how long, the buy-in,
+//! and the result (what was won, or lost if negative). The summary gives the win rate per hour and
+//! its standard deviation, which is what counters compare against their expectations.
+//!
+//! Entries that record the average bet are also compared to what the house edge says to expect.
+//! There's no record of the counts, so that's flat betting at the average bet for HANDS_PER_HOUR
+//! hands an hour at the advantage the caller gives for each session, usually what kelly::Advantage
+//! gives for a true count of 0. A counter should beat it; the difference is roughly what the count
+//! earned.
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use thiserror::Error;
+
+/// How many hands an hour a typical table deals
+pub const HANDS_PER_HOUR: f64 = 100.0;
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct JournalEntry {
+    /// Any format, but YYYY-MM-DD sorts
+    pub date: String,
+    pub venue: String,
+    /// The rules played under, as the name of a strategy card that has them
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub rules: Option<String>,
+    pub hours: f64,
+    pub buy_in: f64,
+    /// What the session won, or lost if negative
+    pub result: f64,
+    /// The average bet, if it was kept track of
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub avg_bet: Option<f64>,
+}
+
+#[derive(Debug, PartialEq, Error)]
+pub enum JournalError {
+    #[error("A session can't last {0} hours")]
+    BadHours(f64),
+    #[error("A buy-in can't be {0}")]
+    BadBuyIn(f64),
+    #[error("An average bet can't be {0}")]
+    BadAvgBet(f64),
+}
+
+impl JournalEntry {
+    pub fn check(&self) -> Result<(), JournalError> {
+        if self.hours.is_nan() || self.hours <= 0.0 {
+            return Err(JournalError::BadHours(self.hours));
+        }
+        if self.buy_in.is_nan() || self.buy_in < 0.0 {
+            return Err(JournalError::BadBuyIn(self.buy_in));
+        }
+        match self.avg_bet {
+            Some(b) if b.is_nan() || b <= 0.0 => Err(JournalError::BadAvgBet(b)),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl fmt::Display for JournalEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {}: {:+.2} in {} hours on a {:.2} buy-in",
+            self.date, self.venue, self.result, self.hours, self.buy_in
+        )?;
+        if let Some(rules) = &self.rules {
+            write!(f, " ({})", rules)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct JournalSummary {
+    pub sessions: usize,
+    pub hours: f64,
+    /// The total result
+    pub result: f64,
+    /// The result per hour
+    pub win_rate: f64,
+    /// The standard deviation of the result over an hour. None with only one session.
+    pub std_dev: Option<f64>,
+    /// The expected result of the sessions with an average bet, and how many hours they were, if
+    /// any have one
+    pub theoretical: Option<(f64, f64)>,
+}
+
+impl fmt::Display for JournalSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{} sessions, {:.1} hours", self.sessions, self.hours)?;
+        write!(
+            f,
+            "Result: {:+.2} ({:+.2}/hour)",
+            self.result, self.win_rate
+        )?;
+        if let Some(sd) = self.std_dev {
+            write!(f, "\nStandard deviation: {:.2}/hour", sd)?;
+        }
+        if let Some((ev, hours)) = self.theoretical {
+            write!(
+                f,
+                "\nTheoretical: {:+.2} over the {:.1} hours with an average bet",
+                ev, hours
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Add up the journal. advantage gives the player's advantage in each session, as a fraction of
+/// the bet. None if there are no entries.
+pub fn summarize(
+    entries: &[JournalEntry],
+    advantage: impl Fn(&JournalEntry) -> f64,
+) -> Option<JournalSummary> {
+    if entries.is_empty() {
+        return None;
+    }
+    let hours: f64 = entries.iter().map(|e| e.hours).sum();
+    let result: f64 = entries.iter().map(|e| e.result).sum();
+    let win_rate = result / hours;
+    // sessions are weighted by their length: a session of h hours has h times the variance of one
+    // hour
+    let n = entries.len();
+    let std_dev = if n > 1 {
+        let sum: f64 = entries
+            .iter()
+            .map(|e| (e.result - win_rate * e.hours).powi(2) / e.hours)
+            .sum();
+        Some((sum / (n - 1) as f64).sqrt())
+    } else {
+        None
+    };
+    let with_bet: Vec<(&JournalEntry, f64)> = entries
+        .iter()
+        .filter_map(|e| e.avg_bet.map(|b| (e, b)))
+        .collect();
+    let theoretical = if with_bet.is_empty() {
+        None
+    } else {
+        let ev = with_bet
+            .iter()
+            .map(|(e, bet)| bet * HANDS_PER_HOUR * e.hours * advantage(e))
+            .sum();
+        Some((ev, with_bet.iter().map(|(e, _)| e.hours).sum()))
+    };
+    Some(JournalSummary {
+        sessions: n,
+        hours,
+        result,
+        win_rate,
+        std_dev,
+        theoretical,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(hours: f64, result: f64, avg_bet: Option<f64>) -> JournalEntry {
+        JournalEntry {
+            date: "2026-01-01".to_string(),
+            venue: "Golden Nugget".to_string(),
+            rules: None,
+            hours,
+            buy_in: 1000.0,
+            result,
+            avg_bet,
+        }
+    }
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-9
+    }
+
+    #[test]
+    fn summary() {
+        assert_eq!(summarize(&[], |_| 0.0), None);
+        let one = summarize(&[entry(2.0, 100.0, None)], |_| 0.0).unwrap();
+        assert_eq!(
+            (one.win_rate, one.std_dev, one.theoretical),
+            (50.0, None, None)
+        );
+        let entries = [
+            entry(2.0, 200.0, Some(25.0)),
+            entry(4.0, -100.0, None),
+            entry(2.0, 300.0, Some(25.0)),
+        ];
+        let s = summarize(&entries, |_| -0.005).unwrap();
+        assert_eq!((s.sessions, s.hours, s.result), (3, 8.0, 400.0));
+        assert!(close(s.win_rate, 50.0));
+        // (100^2/2 + 300^2/4 + 200^2/2) / 2
+        assert!(close(s.std_dev.unwrap(), (23750.0_f64).sqrt()));
+        // 25 a hand, 100 hands an hour, for 4 hours, at -0.5%
+        let (ev, hours) = s.theoretical.unwrap();
+        assert!(close(ev, -50.0));
+        assert!(close(hours, 4.0));
+        assert!(s.to_string().contains("+50.00/hour"));
+        assert_eq!(
+            entry(0.0, 0.0, None).check(),
+            Err(JournalError::BadHours(0.0))
+        );
+        assert_eq!(
+            entry(1.0, 0.0, Some(-5.0)).check(),
+            Err(JournalError::BadAvgBet(-5.0))
+        );
+        assert_eq!(entry(1.0, -50.0, Some(5.0)).check(), Ok(()));
+    }
+}
@@ -0,0 +1,106 @@
+//! Kelly bet sizing: how much of a bankroll to bet given an advantage.
+//!
+//! Betting advantage / variance of the bankroll maximizes its long term growth. Most counters bet
+//! some fraction of that, usually a half, as the full Kelly bet swings the bankroll wildly and
+//! overbetting an advantage that's been overestimated is far worse than underbetting it.
+//!
+//! The advantage at a true count and the variance of a hand come from the ev module for the rules
+//! (see ev::edge() and ev::variance()), or for a typical shoe game if they aren't known (see
+//! Rules::default()). Working them out takes a while, so Advantage does it once for every whole true
+//! count from MIN_COUNT to MAX_COUNT, and goes between them in a straight line.
+use crate::basicstrategy::rules::Rules;
+use crate::ev;
+
+/// The lowest true count the advantage is worked out at. Below it, the line through it and the
+/// next is carried on.
+pub const MIN_COUNT: i8 = -6;
+/// The highest true count the advantage is worked out at, likewise
+pub const MAX_COUNT: i8 = 6;
+
+/// The player's advantage at each true count under some rules, and the variance of a hand
+#[derive(Debug, Clone, PartialEq)]
+pub struct Advantage {
+    /// The variance of one hand, in squared units of the initial bet. Doubling and splitting push
+    /// it above 1.
+    pub variance: f64,
+    /// The advantage at each true count from MIN_COUNT to MAX_COUNT
+    by_count: Vec<f64>,
+}
+
+impl Advantage {
+    /// Work out the advantage for the given rules, or for Rules::default() if they aren't known
+    pub fn new(rules: Option<&Rules>) -> Self {
+        let typical = Rules::default();
+        let rules = rules.unwrap_or(&typical);
+        Self {
+            variance: ev::variance(rules),
+            by_count: (MIN_COUNT..=MAX_COUNT)
+                .map(|tc| ev::edge(rules, f32::from(tc)))
+                .collect(),
+        }
+    }
+
+    /// The player's advantage at the given true count, as a fraction of their bet. Negative when
+    /// the house has the edge.
+    pub fn at(&self, true_count: f32) -> f64 {
+        let tc = f64::from(true_count) - f64::from(MIN_COUNT);
+        // the worked out counts on either side, or the nearest two past the ends
+        let low = (tc.floor().max(0.0) as usize).min(self.by_count.len() - 2);
+        let (a, b) = (self.by_count[low], self.by_count[low + 1]);
+        a + (b - a) * (tc - low as f64)
+    }
+}
+
+/// The fraction of the bankroll the Kelly criterion says to bet. 0 if there's no advantage.
+pub fn kelly_fraction(advantage: f64, variance: f64) -> f64 {
+    if advantage <= 0.0 || variance <= 0.0 {
+        0.0
+    } else {
+        advantage / variance
+    }
+}
+
+/// How much of the given bankroll to bet, betting fraction of full Kelly (e.g. 0.5 for half
+/// Kelly). In whatever units the bankroll is in.
+pub fn kelly_bet(bankroll: f64, advantage: f64, variance: f64, fraction: f64) -> f64 {
+    bankroll * kelly_fraction(advantage, variance) * fraction
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::basicstrategy::rules::BlackjackPayout;
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-9
+    }
+
+    #[test]
+    fn kelly() {
+        let adv = Advantage::new(None);
+        let rules = Rules::default();
+        // worked out counts match ev, and counts between them are in between
+        assert!(close(adv.at(0.0), ev::edge(&rules, 0.0)));
+        assert!(close(adv.at(2.0), ev::edge(&rules, 2.0)));
+        assert!(close(adv.at(1.5), (adv.at(1.0) + adv.at(2.0)) / 2.0));
+        assert!(adv.at(8.0) > adv.at(6.0));
+        assert!(adv.at(-8.0) < adv.at(-6.0));
+        // the house has about half a percent off the top, and each true count is worth about as
+        // much to the player
+        assert!(adv.at(0.0) < -0.003 && adv.at(0.0) > -0.006);
+        assert!(adv.at(1.0) - adv.at(0.0) > 0.004 && adv.at(1.0) - adv.at(0.0) < 0.006);
+        assert!(adv.variance > 1.2 && adv.variance < 1.4);
+        assert_eq!(kelly_fraction(adv.at(-2.0), adv.variance), 0.0);
+        assert!(close(kelly_fraction(0.0133, 1.33), 0.01));
+        assert!(close(kelly_bet(10_000.0, 0.0133, 1.33, 0.5), 50.0));
+        // 6:5 costs about 4 true counts, as the short paid blackjacks come more often as the count
+        // goes up
+        let six_five = Rules {
+            blackjack_pays: BlackjackPayout::SixToFive,
+            ..rules
+        };
+        let adv = Advantage::new(Some(&six_five));
+        assert!(adv.at(4.0) < 0.0);
+        assert!(adv.at(5.0) > 0.0);
+    }
+}
@@ -0,0 +1,123 @@
+//! A kiosk, where strangers take turns at one screen, e.g. a booth at a blackjack class.
+//!
+//! When nobody has touched the kiosk for a while, it resets so the next visitor starts fresh,
+//! and then shows the strategy chart one table at a time to draw people in until someone does.
+//! The front end calls Kiosk::input() on every key press or click, and Kiosk::tick() every second
+//! or so, and does whatever the tick returns. Times are in seconds, from any clock that only goes
+//! forward.
+use crate::hand::HandType;
+
+/// How long the kiosk sits idle before resetting, unless set otherwise
+pub const DEFAULT_IDLE_SECS: f64 = 180.0;
+/// How long each table of the chart is shown, unless set otherwise
+pub const DEFAULT_CHART_SECS: f64 = 10.0;
+/// The tables of the chart, in the order they're shown
+pub const CHART_TABLES: [HandType; 3] = [HandType::Hard, HandType::Soft, HandType::Pair];
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum KioskStep {
+    /// Nobody has used the kiosk for a while: throw away the last visitor's stats and state
+    Reset,
+    /// Show this table of the strategy chart
+    Chart(HandType),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Attract {
+    /// The index in CHART_TABLES of the next table to show
+    next: usize,
+    /// When to show it
+    at: f64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Kiosk {
+    idle_secs: f64,
+    chart_secs: f64,
+    last_input: f64,
+    /// Set while the chart is being shown, until the next input
+    attract: Option<Attract>,
+}
+
+impl Kiosk {
+    /// A kiosk that was last used now, with the default times
+    pub fn new(now: f64) -> Self {
+        Self::with_times(DEFAULT_IDLE_SECS, DEFAULT_CHART_SECS, now)
+    }
+
+    pub fn with_times(idle_secs: f64, chart_secs: f64, now: f64) -> Self {
+        Self {
+            idle_secs,
+            chart_secs,
+            last_input: now,
+            attract: None,
+        }
+    }
+
+    /// Someone just used the kiosk. Returns whether the chart was being shown, so the front end
+    /// can take it down and put the trainer back.
+    pub fn input(&mut self, now: f64) -> bool {
+        self.last_input = now;
+        self.attract.take().is_some()
+    }
+
+    /// Whether the chart is being shown
+    pub fn is_attracting(&self) -> bool {
+        self.attract.is_some()
+    }
+
+    /// What to do now, if anything. The kiosk resets once it's been idle for long enough, and
+    /// then starts on the chart with the next tick.
+    pub fn tick(&mut self, now: f64) -> Option<KioskStep> {
+        match self.attract.as_mut() {
+            None if now - self.last_input >= self.idle_secs => {
+                self.attract = Some(Attract { next: 0, at: now });
+                Some(KioskStep::Reset)
+            }
+            None => None,
+            Some(attract) if now >= attract.at => {
+                let table = CHART_TABLES[attract.next];
+                attract.next = (attract.next + 1) % CHART_TABLES.len();
+                attract.at = now + self.chart_secs;
+                Some(KioskStep::Chart(table))
+            }
+            Some(_) => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resets_then_cycles() {
+        let mut k = Kiosk::with_times(60.0, 5.0, 0.0);
+        assert_eq!(k.tick(30.0), None);
+        // using it puts off the reset
+        assert!(!k.input(50.0));
+        assert_eq!(k.tick(100.0), None);
+        assert_eq!(k.tick(110.0), Some(KioskStep::Reset));
+        assert!(k.is_attracting());
+        let mut shown = vec![];
+        for now in 111..=126 {
+            if let Some(step) = k.tick(f64::from(now)) {
+                shown.push((now, step));
+            }
+        }
+        assert_eq!(
+            shown,
+            vec![
+                (111, KioskStep::Chart(HandType::Hard)),
+                (116, KioskStep::Chart(HandType::Soft)),
+                (121, KioskStep::Chart(HandType::Pair)),
+                (126, KioskStep::Chart(HandType::Hard)),
+            ]
+        );
+        // a visitor takes the chart down, and it only comes back after another reset
+        assert!(k.input(130.0));
+        assert!(!k.is_attracting());
+        assert_eq!(k.tick(189.0), None);
+        assert_eq!(k.tick(190.0), Some(KioskStep::Reset));
+    }
+}
@@ -1,9 +1,57 @@
+pub mod anonid;
+pub mod bankroll;
 pub mod basicstrategy;
+pub mod betramp;
+pub mod breakdown;
+pub mod button;
+pub mod composition;
+pub mod confidence;
+pub mod confusion;
+pub mod correct_resp;
 pub mod count;
+#[cfg(feature = "rand")]
+pub mod countsim;
+pub mod dealer;
+pub mod decisions;
 pub mod deck;
+#[cfg(feature = "rand")]
+pub mod demo;
+pub mod drillpack;
+pub mod ev;
+#[cfg(all(test, feature = "serde"))]
+mod formats;
+pub mod frequency;
 pub mod hand;
+pub mod holecard;
+pub mod import;
+pub mod journal;
+pub mod kelly;
+pub mod kiosk;
+pub mod looseparse;
+pub mod luck;
+pub mod mastery;
+pub mod memorize;
+pub mod notes;
+#[cfg(feature = "rand")]
+pub mod odds;
+pub mod onboarding;
+pub mod outcome;
+pub mod pairdrill;
+pub mod placement;
 pub mod playstats;
+pub mod practice;
+#[cfg(feature = "serde")]
+pub mod proto;
 pub mod rendertable;
 pub mod resp;
+pub mod round;
+pub mod settle;
+#[cfg(feature = "rand")]
+pub mod shared;
+pub mod shuffle;
 pub mod table;
+#[cfg(feature = "rand")]
+pub mod trainer;
 pub mod utils;
+pub mod validate;
+pub mod weakcells;
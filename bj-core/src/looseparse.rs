@@ -0,0 +1,339 @@
+//! A forgiving parser for strategy card grids, e.g. as produced by OCRing a photo of a paper card.
+//!
+//! resps_from_buf() throws away every character it doesn't understand, which is great for our own
+//! hand-written files but makes it impossible to tell *where* messy input went wrong. This parser
+//! works line by line instead:
+//!
+//! - Everything after a '#' is a comment.
+//! - '|', ',', ';' and tabs are treated as whitespace.
+//! - Common unicode lookalikes (fullwidth letters, Cyrillic and Greek letters that look like H, S,
+//!   P, etc.) are normalized to ASCII, and case is fixed up ("DH", "dh", and "dH" are all "Dh").
+//! - Leading row labels ("16", "A,7", "8-8", "T:", "Hard 16", "Pair 8,8") are dropped.
+//! - Lines that are only dealer labels ("2 3 4 5 6 7 8 9 10 A") or that start with a word
+//!   ("Hard totals", "Dealer upcard") are skipped.
+//!
+//! Every remaining non-empty line is a row of the card. Rows are expected in the same order as
+//! Table::new() wants them: 17 hard rows, 9 soft rows, then 10 pair rows. Every problem found is
+//! reported with the line it was on and the row of the card it would have been, so the user can
+//! go fix their OCR output.
+use crate::hand::HandType;
+use crate::resp::Resp;
+use crate::table::GameDesc;
+use thiserror::Error;
+
+const ROWS: usize = 17 + 9 + 10;
+const COLS: usize = 10;
+
+#[derive(Debug, PartialEq, Error)]
+pub enum LooseErrorKind {
+    /// The row has the wrong number of cells. (expected, found)
+    #[error("expected {0} cells but found {1}")]
+    WrongCellCount(usize, usize),
+    /// The cell in the given column (0-indexed, dealer 2 is column 0) isn't a valid Resp
+    #[error(
+        "'{1}' vs dealer {} is not a valid response",
+        GameDesc::new(HandType::Hard, 5, *.0 as u8 + 2).dealer_label()
+    )]
+    BadCell(usize, String),
+    /// There are more rows than a card has
+    #[error("too many rows")]
+    ExtraRow,
+    /// There are fewer rows than a card has. (expected, found)
+    #[error("expected {0} rows but found {1}")]
+    MissingRows(usize, usize),
+}
+
+#[derive(Debug, PartialEq, Error)]
+#[error("{}{}{kind}", line_prefix(*.line), row_prefix(*.row))]
+pub struct LooseError {
+    /// 1-indexed line in the input, or 0 if the error isn't about a specific line
+    pub line: usize,
+    /// The row of the strategy card this line was parsed as, if any
+    pub row: Option<(HandType, u8)>,
+    pub kind: LooseErrorKind,
+}
+
+/// "line 3: " for errors on a line, for LooseError's message
+fn line_prefix(line: usize) -> String {
+    match line {
+        0 => String::new(),
+        line => format!("line {}: ", line),
+    }
+}
+
+/// "Hard 16 row: " for errors in a row, for LooseError's message
+fn row_prefix(row: Option<(HandType, u8)>) -> String {
+    match row {
+        None => String::new(),
+        Some((ty, player)) => format!("{} row: ", GameDesc::new(ty, player, 2).player_label()),
+    }
+}
+
+/// The hand type and player value of the given row of a strategy card
+fn row_desc(row: usize) -> Option<(HandType, u8)> {
+    match row {
+        0..=16 => Some((HandType::Hard, 5 + row as u8)),
+        17..=25 => Some((HandType::Soft, 13 + (row - 17) as u8)),
+        26..=35 => Some((HandType::Pair, 4 + 2 * (row - 26) as u8)),
+        _ => None,
+    }
+}
+
+/// Map a single unicode char to the ASCII char it is most likely supposed to be
+fn normalize_char(c: char) -> char {
+    match c {
+        // fullwidth ASCII
+        '\u{FF01}'..='\u{FF5E}' => std::char::from_u32(c as u32 - 0xFEE0).unwrap_or(c),
+        // Cyrillic and Greek lookalikes
+        'Н' | 'Η' | 'Һ' => 'H',
+        'н' | 'һ' => 'h',
+        'Ѕ' => 'S',
+        'ѕ' => 's',
+        'Р' | 'Ρ' => 'P',
+        'р' | 'ρ' => 'p',
+        'Д' => 'D',
+        'д' => 'd',
+        'А' | 'Α' => 'A',
+        'Т' | 'Τ' => 'T',
+        // separators
+        '|' | ',' | ';' | '\t' | '\u{00A0}' | '\u{3000}' => ' ',
+        _ => c,
+    }
+}
+
+/// Whether the token looks like a row or column label instead of a cell
+fn is_label(tok: &str) -> bool {
+    !tok.is_empty()
+        && tok
+            .chars()
+            .all(|c| c.is_ascii_digit() || "AaTt-+/:().".contains(c))
+}
+
+/// Whether the token is the word that starts a row label like "Hard 16" or "Pair 8,8"
+fn is_row_word(tok: &str) -> bool {
+    ["hard", "soft", "pair", "pairs"]
+        .iter()
+        .any(|w| tok.eq_ignore_ascii_case(w))
+}
+
+/// Parse a single cell, fixing up case. "dh", "DH", and "Dh" are all DoubleElseHit.
+fn parse_cell(tok: &str) -> Option<Resp> {
+    let mut chars = tok.chars();
+    let first = chars.next()?.to_ascii_uppercase();
+    let second = chars.next().map(|c| c.to_ascii_lowercase());
+    if chars.next().is_some() {
+        return None;
+    }
+    match (first, second) {
+        ('H', None) => Some(Resp::Hit),
+        ('S', None) => Some(Resp::Stand),
+        ('P', None) => Some(Resp::Split),
+        ('D', Some('h')) => Some(Resp::DoubleElseHit),
+        ('D', Some('s')) => Some(Resp::DoubleElseStand),
+        ('R', Some('h')) => Some(Resp::SurrenderElseHit),
+        ('R', Some('s')) => Some(Resp::SurrenderElseStand),
+        ('R', Some('p')) => Some(Resp::SurrenderElseSplit),
+        _ => None,
+    }
+}
+
+/// Parse the messy grid text in `s` into the 360 responses that make up a strategy card.
+///
+/// On failure, returns every problem that was found instead of just the first.
+pub fn resps_from_loose_str(s: &str) -> Result<Vec<Resp>, Vec<LooseError>> {
+    let mut resps = Vec::with_capacity(ROWS * COLS);
+    let mut errs = vec![];
+    let mut row = 0;
+    for (line_idx, line) in s.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or("");
+        let line: String = line.chars().map(normalize_char).collect();
+        let mut toks: Vec<&str> = line.split_whitespace().collect();
+        // "Hard 16", "Soft A,7", and "Pair 8-8" label a row. "Hard totals" is a header.
+        if toks.len() > 1 && is_row_word(toks[0]) && is_label(toks[1]) {
+            toks.remove(0);
+        }
+        // section headers and lines of only labels are not rows
+        if toks.is_empty() || toks.iter().all(|t| is_label(t)) {
+            continue;
+        }
+        if let Some(first) = toks.iter().find(|t| !is_label(t)) {
+            if first.len() > 2 && first.chars().all(char::is_alphabetic) {
+                continue;
+            }
+        }
+        // drop the row label(s)
+        while !toks.is_empty() && is_label(toks[0]) {
+            toks.remove(0);
+        }
+        let desc = row_desc(row);
+        let line_num = line_idx + 1;
+        row += 1;
+        if desc.is_none() {
+            errs.push(LooseError {
+                line: line_num,
+                row: None,
+                kind: LooseErrorKind::ExtraRow,
+            });
+            continue;
+        }
+        if toks.len() != COLS {
+            errs.push(LooseError {
+                line: line_num,
+                row: desc,
+                kind: LooseErrorKind::WrongCellCount(COLS, toks.len()),
+            });
+            continue;
+        }
+        for (col, tok) in toks.iter().enumerate() {
+            match parse_cell(tok) {
+                Some(resp) => resps.push(resp),
+                None => errs.push(LooseError {
+                    line: line_num,
+                    row: desc,
+                    kind: LooseErrorKind::BadCell(col, tok.to_string()),
+                }),
+            }
+        }
+    }
+    if row < ROWS {
+        errs.push(LooseError {
+            line: 0,
+            row: None,
+            kind: LooseErrorKind::MissingRows(ROWS, row),
+        });
+    }
+    if errs.is_empty() {
+        Ok(resps)
+    } else {
+        Err(errs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::resp::resps_from_buf;
+
+    const T1: &str = "
+H  H  H  H  H  H  H  H  H  H
+H  H  H  H  H  H  H  H  H  H
+H  H  H  H  H  H  H  H  H  H
+H  H  H  H  H  H  H  H  H  H
+H  Dh Dh Dh Dh H  H  H  H  H
+Dh Dh Dh Dh Dh Dh Dh Dh H  H
+Dh Dh Dh Dh Dh Dh Dh Dh Dh Dh
+H  H  S  S  S  H  H  H  H  H
+S  S  S  S  S  H  H  H  H  H
+S  S  S  S  S  H  H  H  H  H
+S  S  S  S  S  H  H  H  H  H
+S  S  S  S  S  H  H  H  H  H
+S  S  S  S  S  S  S  S  S  S
+S  S  S  S  S  S  S  S  S  S
+S  S  S  S  S  S  S  S  S  S
+S  S  S  S  S  S  S  S  S  S
+S  S  S  S  S  S  S  S  S  S
+H  H  H  Dh Dh H  H  H  H  H
+H  H  H  Dh Dh H  H  H  H  H
+H  H  Dh Dh Dh H  H  H  H  H
+H  H  Dh Dh Dh H  H  H  H  H
+H  Dh Dh Dh Dh H  H  H  H  H
+Ds Ds Ds Ds Ds S  S  H  H  H
+S  S  S  S  Ds S  S  S  S  S
+S  S  S  S  S  S  S  S  S  S
+S  S  S  S  S  S  S  S  S  S
+P  P  P  P  P  P  H  H  H  H
+P  P  P  P  P  P  H  H  H  H
+H  H  H  P  P  H  H  H  H  H
+Dh Dh Dh Dh Dh Dh Dh Dh H  H
+P  P  P  P  P  H  H  H  H  H
+P  P  P  P  P  P  H  H  H  H
+P  P  P  P  P  P  P  P  P  P
+P  P  P  P  P  S  P  P  S  S
+S  S  S  S  S  S  S  S  S  S
+P  P  P  P  P  P  P  P  P  P
+";
+
+    #[test]
+    fn same_as_strict() {
+        // clean input parses the same as the strict parser
+        let strict = resps_from_buf(T1.as_bytes()).unwrap();
+        assert_eq!(resps_from_loose_str(T1).unwrap(), strict);
+    }
+
+    #[test]
+    fn messy_same_as_strict() {
+        // labels, headers, separators, case, and lookalike letters are all handled
+        let strict = resps_from_buf(T1.as_bytes()).unwrap();
+        let mut messy = String::from("Hard totals\n   2 | 3 | 4 | 5 | 6 | 7 | 8 | 9 | 10 | A\n");
+        for (i, line) in T1.lines().filter(|l| !l.is_empty()).enumerate() {
+            let line = line
+                .replace("Dh", "DH")
+                .replace("S ", "Ѕ ")
+                .replace("H ", "Ｈ ")
+                .replace("P ", "Р ");
+            messy.push_str(&format!("{}: | {}\n", i + 5, line));
+        }
+        assert_eq!(resps_from_loose_str(&messy).unwrap(), strict);
+    }
+
+    #[test]
+    fn labelled_rows_same_as_strict() {
+        // rows labelled with their hand type aren't mistaken for headers
+        let strict = resps_from_buf(T1.as_bytes()).unwrap();
+        let mut labelled = String::from("Hard totals\n");
+        for (i, line) in T1.lines().filter(|l| !l.is_empty()).enumerate() {
+            let label = match row_desc(i).unwrap() {
+                (HandType::Hard, player) => format!("Hard {}", player),
+                (HandType::Soft, player) => format!("soft A,{}", player - 11),
+                (HandType::Pair, player) => format!("PAIR {0}-{0}", player / 2),
+            };
+            labelled.push_str(&format!("{} | {}\n", label, line));
+        }
+        assert_eq!(resps_from_loose_str(&labelled).unwrap(), strict);
+    }
+
+    #[test]
+    fn bad_cell_reported() {
+        let s = T1.replacen(
+            "Dh Dh Dh Dh Dh Dh Dh Dh H  H",
+            "Dh Dh Dh Dh Dh Dh Dh Dx H  H",
+            1,
+        );
+        let errs = resps_from_loose_str(&s).unwrap_err();
+        assert_eq!(errs.len(), 1);
+        assert_eq!(errs[0].row, Some((HandType::Hard, 10)));
+        assert_eq!(errs[0].kind, LooseErrorKind::BadCell(7, "Dx".to_string()));
+    }
+
+    #[test]
+    fn short_row_reported() {
+        let s = T1.replacen(
+            "Ds Ds Ds Ds Ds S  S  H  H  H",
+            "Ds Ds Ds Ds Ds S  S  H  H",
+            1,
+        );
+        let errs = resps_from_loose_str(&s).unwrap_err();
+        assert_eq!(errs.len(), 1);
+        assert_eq!(errs[0].row, Some((HandType::Soft, 18)));
+        assert_eq!(errs[0].kind, LooseErrorKind::WrongCellCount(10, 9));
+    }
+
+    #[test]
+    fn missing_and_extra_rows() {
+        let lines: Vec<&str> = T1.lines().filter(|l| !l.is_empty()).collect();
+        let short = lines[..30].join("\n");
+        let errs = resps_from_loose_str(&short).unwrap_err();
+        assert_eq!(
+            errs,
+            vec![LooseError {
+                line: 0,
+                row: None,
+                kind: LooseErrorKind::MissingRows(36, 30),
+            }]
+        );
+        let long = format!("{}\n{}", T1, lines[0]);
+        let errs = resps_from_loose_str(&long).unwrap_err();
+        assert_eq!(errs.len(), 1);
+        assert_eq!(errs[0].kind, LooseErrorKind::ExtraRow);
+    }
+}
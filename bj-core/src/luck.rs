@@ -0,0 +1,312 @@
+//! Were you actually unlucky? How a played session's result compares to what was expected.
+//!
+//! Each hand of the session's log has its bet, the true count it was played at, and what it won
+//! or lost. The expected result and variance of each hand come from kelly::Advantage, and the
+//! session's result is treated as normally distributed around their sums. That's a fair
+//! approximation after a few dozen hands.
+//! The percentile of the actual result then says how unusual it was: losing 12 units when 0.5 was
+//! expected might be a bottom-9% outcome, which is bad luck, not necessarily bad play.
+//!
+//! Logs are CSV with a header naming the columns. bet and result are required, true_count is 0 if
+//! missing, and any other columns (like the hand) are ignored. Results are net, so a won double
+//! of a 10 unit bet is 20 and a lost hand is -10. Blank lines and lines starting with # are
+//! skipped.
+//!
+//! ```text
+//! bet,true_count,hand,result
+//! 10,0,T6 v T,-10
+//! 20,2,AJ v 7,30
+//! ```
+//!
+//! session_log() reads a log a hand at a time, and analyze() goes through the hands once, so a log
+//! of millions of simulated hands can be analyzed without holding it all in memory.
+use crate::kelly::Advantage;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::borrow::Borrow;
+use std::fmt;
+use std::io::{self, BufRead, Lines};
+use std::iter::Enumerate;
+use thiserror::Error;
+
+/// One hand of a played session
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct SessionHand {
+    pub bet: f64,
+    pub true_count: f32,
+    /// What the hand won, or lost if negative, in the same units as the bet
+    pub result: f64,
+}
+
+#[derive(Debug, Error)]
+pub enum SessionLogError {
+    #[error("{0}")]
+    Io(#[from] std::io::Error),
+    /// The log has no header
+    #[error("The log is empty")]
+    Empty,
+    /// The header doesn't have this required column
+    #[error("The log's header has no {0} column")]
+    MissingColumn(&'static str),
+    /// (line number, what's wrong with it)
+    #[error("Line {0}: {1}")]
+    BadLine(usize, String),
+}
+
+/// The hands of a session log, read a line at a time so even a very long log needs little memory.
+/// Made by session_log().
+pub struct SessionLog<R> {
+    lines: Enumerate<Lines<R>>,
+    bet_col: usize,
+    result_col: usize,
+    tc_col: Option<usize>,
+}
+
+/// The next line that isn't blank or a comment, and its line number
+fn next_line<R: BufRead>(lines: &mut Enumerate<Lines<R>>) -> Option<io::Result<(usize, String)>> {
+    for (i, l) in lines {
+        match l {
+            Ok(l) if l.trim().is_empty() || l.trim_start().starts_with('#') => continue,
+            Ok(l) => return Some(Ok((i + 1, l))),
+            Err(e) => return Some(Err(e)),
+        }
+    }
+    None
+}
+
+/// Start reading a session log, checking its header. See the module docs for the format.
+pub fn session_log<R: BufRead>(r: R) -> Result<SessionLog<R>, SessionLogError> {
+    let mut lines = r.lines().enumerate();
+    let (_, header) = next_line(&mut lines).ok_or(SessionLogError::Empty)??;
+    let columns: Vec<String> = header
+        .split(',')
+        .map(|c| c.trim().to_ascii_lowercase())
+        .collect();
+    let column = |name| columns.iter().position(|c| c == name);
+    Ok(SessionLog {
+        bet_col: column("bet").ok_or(SessionLogError::MissingColumn("bet"))?,
+        result_col: column("result").ok_or(SessionLogError::MissingColumn("result"))?,
+        tc_col: column("true_count"),
+        lines,
+    })
+}
+
+impl<R: BufRead> SessionLog<R> {
+    fn parse(&self, n: usize, line: &str) -> Result<SessionHand, SessionLogError> {
+        let fields: Vec<&str> = line.split(',').map(|f| f.trim()).collect();
+        let field = |col: usize, name: &str| -> Result<&str, SessionLogError> {
+            fields
+                .get(col)
+                .copied()
+                .ok_or_else(|| SessionLogError::BadLine(n, format!("No {}", name)))
+        };
+        let bad =
+            |name: &str, v: &str| SessionLogError::BadLine(n, format!("Bad {} '{}'", name, v));
+        let bet = field(self.bet_col, "bet")?;
+        let bet: f64 = bet
+            .parse()
+            .ok()
+            .filter(|b: &f64| *b > 0.0)
+            .ok_or_else(|| bad("bet", bet))?;
+        let result = field(self.result_col, "result")?;
+        let result: f64 = result.parse().map_err(|_| bad("result", result))?;
+        let true_count = match self.tc_col {
+            Some(col) => {
+                let tc = field(col, "true_count")?;
+                tc.parse().map_err(|_| bad("true_count", tc))?
+            }
+            None => 0.0,
+        };
+        Ok(SessionHand {
+            bet,
+            true_count,
+            result,
+        })
+    }
+}
+
+impl<R: BufRead> Iterator for SessionLog<R> {
+    type Item = Result<SessionHand, SessionLogError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        Some(match next_line(&mut self.lines)? {
+            Ok((n, line)) => self.parse(n, &line),
+            Err(e) => Err(e.into()),
+        })
+    }
+}
+
+/// Read all the hands of a session log. See the module docs for the format.
+pub fn read_session_log(r: impl BufRead) -> Result<Vec<SessionHand>, SessionLogError> {
+    session_log(r)?.collect()
+}
+
+/// How a session's result compares to what was expected
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LuckReport {
+    pub hands: usize,
+    /// The total of the bets
+    pub wagered: f64,
+    /// The expected result, negative when the house has the edge
+    pub expected: f64,
+    /// The standard deviation of the result
+    pub std_dev: f64,
+    pub actual: f64,
+    /// The percent of sessions that would have done worse, 0-100
+    pub percentile: f64,
+}
+
+impl fmt::Display for LuckReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{} hands, {:.1} units wagered", self.hands, self.wagered)?;
+        writeln!(
+            f,
+            "Expected {:+.1} units ± {:.1}",
+            self.expected, self.std_dev
+        )?;
+        let (side, pct) = if self.percentile < 50.0 {
+            ("bottom", self.percentile)
+        } else {
+            ("top", 100.0 - self.percentile)
+        };
+        write!(
+            f,
+            "Your {:+.1} units was a {}-{:.0}% outcome",
+            self.actual,
+            side,
+            pct.max(1.0)
+        )
+    }
+}
+
+/// The chance a standard normal variable is at most z
+pub fn normal_cdf(z: f64) -> f64 {
+    // Abramowitz and Stegun 7.1.26, good to about 1e-7
+    let x = z.abs() / std::f64::consts::SQRT_2;
+    let t = 1.0 / (1.0 + 0.327_591_1 * x);
+    let poly = t
+        * (0.254_829_592
+            + t * (-0.284_496_736
+                + t * (1.421_413_741 + t * (-1.453_152_027 + t * 1.061_405_429))));
+    let erf = 1.0 - poly * (-x * x).exp();
+    if z >= 0.0 {
+        0.5 * (1.0 + erf)
+    } else {
+        0.5 * (1.0 - erf)
+    }
+}
+
+/// Compare the session's result to what was expected given the player's advantage at each count
+/// and the variance of a hand. None if there are no hands.
+///
+/// The hands are only gone through once, so they can be streamed from a log too big to hold, e.g.
+/// with session_log().
+pub fn analyze<I>(hands: I, advantage: &Advantage) -> Option<LuckReport>
+where
+    I: IntoIterator,
+    I::Item: Borrow<SessionHand>,
+{
+    let (mut count, mut wagered, mut expected, mut var, mut actual) = (0, 0.0, 0.0, 0.0, 0.0);
+    for h in hands {
+        let h = h.borrow();
+        count += 1;
+        wagered += h.bet;
+        expected += h.bet * advantage.at(h.true_count);
+        var += h.bet * h.bet * advantage.variance;
+        actual += h.result;
+    }
+    if count == 0 {
+        return None;
+    }
+    let std_dev = f64::sqrt(var);
+    let percentile = if std_dev > 0.0 {
+        normal_cdf((actual - expected) / std_dev) * 100.0
+    } else {
+        50.0
+    };
+    Some(LuckReport {
+        hands: count,
+        wagered,
+        expected,
+        std_dev,
+        actual,
+        percentile,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: f64, b: f64, tol: f64) -> bool {
+        (a - b).abs() < tol
+    }
+
+    #[test]
+    fn cdf() {
+        assert!(close(normal_cdf(0.0), 0.5, 1e-7));
+        assert!(close(normal_cdf(1.96), 0.975, 1e-4));
+        assert!(close(normal_cdf(-1.0), 0.158_655, 1e-5));
+    }
+
+    #[test]
+    fn log() {
+        let log = "# Tuesday\nBet, true_count, hand, result\n\n10,0,T6 v T,-10\n20,2,AJ v 7,30\n";
+        let hands = read_session_log(log.as_bytes()).unwrap();
+        assert_eq!(
+            hands[1],
+            SessionHand {
+                bet: 20.0,
+                true_count: 2.0,
+                result: 30.0
+            }
+        );
+        let hands = read_session_log("result,bet\n-1,1\n".as_bytes()).unwrap();
+        assert_eq!(hands[0].true_count, 0.0);
+        assert!(matches!(
+            read_session_log("bet\n1\n".as_bytes()),
+            Err(SessionLogError::MissingColumn("result"))
+        ));
+        assert!(matches!(
+            read_session_log("bet,result\n1,-1\n0,1\n".as_bytes()),
+            Err(SessionLogError::BadLine(3, _))
+        ));
+        assert!(matches!(
+            read_session_log("bet,result\n1\n".as_bytes()),
+            Err(SessionLogError::BadLine(2, _))
+        ));
+        assert!(matches!(
+            read_session_log("".as_bytes()),
+            Err(SessionLogError::Empty)
+        ));
+    }
+
+    #[test]
+    fn luck() {
+        let mut adv = Advantage::new(None);
+        adv.variance = 1.33;
+        assert_eq!(analyze(&[] as &[SessionHand], &adv), None);
+        // 100 flat bets at TC 0 lose about half a unit expected, with a std dev of about 11.5
+        let hands = vec![
+            SessionHand {
+                bet: 1.0,
+                true_count: 0.0,
+                result: -0.15,
+            };
+            100
+        ];
+        let report = analyze(&hands, &adv).unwrap();
+        assert!(close(report.expected, 100.0 * adv.at(0.0), 1e-9));
+        assert!(report.expected < -0.3 && report.expected > -0.6);
+        assert!(close(report.actual, -15.0, 1e-9));
+        assert!(close(report.std_dev, 133f64.sqrt(), 1e-9));
+        assert!(report.percentile < 15.0 && report.percentile > 5.0);
+        let log = "bet,result\n".to_string() + &"1,-0.15\n".repeat(100);
+        let streamed = session_log(log.as_bytes()).unwrap().map(Result::unwrap);
+        assert_eq!(analyze(streamed, &adv), Some(report));
+        assert!(report
+            .to_string()
+            .ends_with("Your -15.0 units was a bottom-10% outcome"));
+    }
+}
@@ -0,0 +1,159 @@
+//! Mastery goals for each table of a strategy card, and how close the player is to them.
+//!
+//! A cell is mastered once the player has seen it at least min_seen times and gotten at least
+//! min_percent of them right. Each of the hard, soft, and pair tables has its own goal, as pairs
+//! come up far less often than hard hands and may deserve an easier one. Progress on a table is
+//! how many of its cells are mastered, e.g. "Pairs mastered: 38/100 cells at ≥90% over ≥5
+//! attempts", which is also what anything gating on a table should check.
+use crate::hand::HandType;
+use crate::playstats::PlayStats;
+use crate::table::Table;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// What it takes to master a cell
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct MasteryGoal {
+    /// The percent of the cell's hands that have to be right
+    pub min_percent: u32,
+    /// How many times the cell has to have been seen
+    pub min_seen: u32,
+}
+
+impl Default for MasteryGoal {
+    fn default() -> Self {
+        Self {
+            min_percent: 90,
+            min_seen: 5,
+        }
+    }
+}
+
+impl MasteryGoal {
+    pub fn is_mastered(self, stats: PlayStats) -> bool {
+        stats.seen() >= self.min_seen
+            && stats.seen() > 0
+            && u64::from(stats.correct()) * 100
+                >= u64::from(stats.seen()) * u64::from(self.min_percent)
+    }
+}
+
+/// A goal for each table of the card
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(default))]
+pub struct MasteryGoals {
+    pub hard: MasteryGoal,
+    pub soft: MasteryGoal,
+    pub pair: MasteryGoal,
+}
+
+impl MasteryGoals {
+    pub fn get(&self, hand: HandType) -> MasteryGoal {
+        match hand {
+            HandType::Hard => self.hard,
+            HandType::Soft => self.soft,
+            HandType::Pair => self.pair,
+        }
+    }
+
+    pub fn get_mut(&mut self, hand: HandType) -> &mut MasteryGoal {
+        match hand {
+            HandType::Hard => &mut self.hard,
+            HandType::Soft => &mut self.soft,
+            HandType::Pair => &mut self.pair,
+        }
+    }
+}
+
+/// How many cells of one table are mastered
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct TableProgress {
+    pub hand: HandType,
+    pub mastered: u32,
+    pub cells: u32,
+    pub goal: MasteryGoal,
+}
+
+impl TableProgress {
+    /// Whether every cell of the table is mastered
+    pub fn is_complete(&self) -> bool {
+        self.mastered == self.cells
+    }
+}
+
+impl fmt::Display for TableProgress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self.hand {
+            HandType::Hard => "Hard hands",
+            HandType::Soft => "Soft hands",
+            HandType::Pair => "Pairs",
+        };
+        write!(
+            f,
+            "{} mastered: {}/{} cells at ≥{}% over ≥{} attempts",
+            name, self.mastered, self.cells, self.goal.min_percent, self.goal.min_seen
+        )
+    }
+}
+
+/// Progress toward the goals on the hard, soft, and pair tables, in that order
+pub fn mastery_progress(stats: &Table<PlayStats>, goals: &MasteryGoals) -> Vec<TableProgress> {
+    [HandType::Hard, HandType::Soft, HandType::Pair]
+        .iter()
+        .map(|&hand| {
+            let goal = goals.get(hand);
+            let (mastered, cells) = stats
+                .iter()
+                .filter(|(desc, _)| desc.hand == hand)
+                .fold((0, 0), |(m, c), (_, s)| {
+                    (m + u32::from(goal.is_mastered(*s)), c + 1)
+                });
+            TableProgress {
+                hand,
+                mastered,
+                cells,
+                goal,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::table::GameDesc;
+
+    #[test]
+    fn progress() {
+        let goal = MasteryGoal::default();
+        let stats = |seen, correct| PlayStats::from_counts(seen, correct, 0).unwrap();
+        assert!(goal.is_mastered(stats(10, 9)));
+        assert!(!goal.is_mastered(stats(10, 8)));
+        assert!(!goal.is_mastered(stats(4, 4)));
+        let zero = MasteryGoal {
+            min_percent: 0,
+            min_seen: 0,
+        };
+        assert!(!zero.is_mastered(stats(0, 0)));
+        let mut table = Table::new(vec![PlayStats::new(); 360]).unwrap();
+        for (desc, s) in table.iter_mut() {
+            if *desc == "8,8 vs A".parse::<GameDesc>().unwrap() || desc.hand == HandType::Soft {
+                *s = stats(5, 5);
+            }
+        }
+        let mut goals = MasteryGoals::default();
+        let progress = mastery_progress(&table, &goals);
+        assert_eq!(progress[0].mastered, 0);
+        assert_eq!(progress[0].cells, 170);
+        assert!(progress[1].is_complete());
+        assert_eq!(
+            progress[2].to_string(),
+            "Pairs mastered: 1/100 cells at ≥90% over ≥5 attempts"
+        );
+        goals.get_mut(HandType::Pair).min_seen = 6;
+        assert_eq!(mastery_progress(&table, &goals)[2].mastered, 0);
+    }
+}
@@ -0,0 +1,122 @@
+//! Chart memorization: study a strategy card that hides more of itself as the player learns it.
+//!
+//! Every cell the player has mastered (see crate::mastery) is hidden, so the visible card shrinks
+//! to what they don't know yet. A player who can't remember a hidden cell can reveal it, which
+//! counts as getting it wrong: its streak is lost and weighted dealing brings it up more often. A
+//! revealed cell stays shown until the player has answered it correctly REDRILL_STREAK times in a
+//! row, and is hidden again after that if it is still mastered.
+use crate::mastery::MasteryGoals;
+use crate::playstats::PlayStats;
+use crate::rendertable::CellVisibility;
+use crate::table::{GameDesc, Table};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// How many correct answers in a row a revealed cell needs before it can be hidden again
+pub const REDRILL_STREAK: u32 = 3;
+
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Memorize {
+    /// The cells the player has revealed and not yet re-drilled
+    revealed: Vec<GameDesc>,
+}
+
+impl Memorize {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Which cells to show, given the player's stats and mastery goals
+    pub fn visibility(
+        &self,
+        stats: &Table<PlayStats>,
+        goals: &MasteryGoals,
+    ) -> Table<CellVisibility> {
+        stats.map(|desc, stat| {
+            if self.is_revealed(desc, stat) {
+                CellVisibility::Revealed
+            } else if goals.get(desc.hand).is_mastered(stat) {
+                CellVisibility::Hidden
+            } else {
+                CellVisibility::Shown
+            }
+        })
+    }
+
+    /// Reveal the given cell, counting it as a miss in stats. Returns false, changing nothing, if
+    /// the cell isn't hidden.
+    pub fn reveal(
+        &mut self,
+        desc: GameDesc,
+        stats: &mut Table<PlayStats>,
+        goals: &MasteryGoals,
+    ) -> bool {
+        if self.visibility(stats, goals).get_desc(desc) != Some(CellVisibility::Hidden) {
+            return false;
+        }
+        for (_, stat) in stats.iter_mut().filter(|(d, _)| **d == desc) {
+            stat.inc(false);
+        }
+        self.revealed.push(desc);
+        true
+    }
+
+    /// Forget the revealed cells that have been re-drilled, so they can be hidden again
+    pub fn prune(&mut self, stats: &Table<PlayStats>) {
+        self.revealed
+            .retain(|desc| stats.get_desc(*desc).unwrap_or_default().streak() < REDRILL_STREAK);
+    }
+
+    /// The cells the player has revealed and not yet re-drilled
+    pub fn revealed(&self) -> &[GameDesc] {
+        &self.revealed
+    }
+
+    fn is_revealed(&self, desc: GameDesc, stat: PlayStats) -> bool {
+        self.revealed.contains(&desc) && stat.streak() < REDRILL_STREAK
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::table::NUM_CELLS;
+
+    #[test]
+    fn hide_and_reveal() {
+        let goals = MasteryGoals::default();
+        let mut stats = Table::new(vec![PlayStats::new(); NUM_CELLS]).unwrap();
+        let cell: GameDesc = "Hard 16 vs 10".parse().unwrap();
+        for (_, stat) in stats.iter_mut().filter(|(d, _)| **d == cell) {
+            stat.inc_by(20, true);
+        }
+        let mut m = Memorize::new();
+        let vis = m.visibility(&stats, &goals);
+        assert_eq!(vis.get_desc(cell), Some(CellVisibility::Hidden));
+        let count = |vis: &Table<CellVisibility>, v| vis.values().filter(|c| **c == v).count();
+        assert_eq!(count(&vis, CellVisibility::Shown), NUM_CELLS - 1);
+        // only hidden cells can be revealed
+        let other = "Hard 15 vs 10".parse().unwrap();
+        assert!(!m.reveal(other, &mut stats, &goals));
+        assert!(m.reveal(cell, &mut stats, &goals));
+        assert!(!m.reveal(cell, &mut stats, &goals));
+        let stat = stats.get_desc(cell).unwrap();
+        assert_eq!((stat.seen(), stat.correct(), stat.streak()), (21, 20, 0));
+        // still mastered, but shown until re-drilled
+        assert!(goals.hard.is_mastered(stat));
+        let vis = m.visibility(&stats, &goals);
+        assert_eq!(vis.get_desc(cell), Some(CellVisibility::Revealed));
+        for i in 0..REDRILL_STREAK {
+            m.prune(&stats);
+            assert_eq!(m.revealed(), &[cell], "{}", i);
+            for (_, stat) in stats.iter_mut().filter(|(d, _)| **d == cell) {
+                stat.inc(true);
+            }
+        }
+        m.prune(&stats);
+        assert!(m.revealed().is_empty());
+        let vis = m.visibility(&stats, &goals);
+        assert_eq!(vis.get_desc(cell), Some(CellVisibility::Hidden));
+    }
+}
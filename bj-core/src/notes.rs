@@ -0,0 +1,130 @@
+//! Notes the player attaches to cells of a strategy card, e.g. "dealer 9 is not a bust card!"
+//!
+//! Unlike a Table, most cells have no note, so Notes only stores the cells that do.
+use crate::deck::Card;
+use crate::hand::Hand;
+use crate::table::GameDesc;
+#[cfg(feature = "serde")]
+use crate::table::{dealer_card_from_desc, player_hand_from_desc};
+#[cfg(feature = "serde")]
+use serde::ser::SerializeSeq;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::HashMap;
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Notes(HashMap<GameDesc, String>);
+
+impl Notes {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The note for the cell the given hand falls in, if any
+    pub fn get(&self, player: &Hand, dealer: Card) -> Option<&str> {
+        self.get_desc(GameDesc::from_hand(player, dealer))
+    }
+
+    pub fn get_desc(&self, desc: GameDesc) -> Option<&str> {
+        self.0.get(&desc).map(|s| s.as_str())
+    }
+
+    /// Set the note for the cell the given hand falls in. An empty (or all whitespace) note removes
+    /// the cell's note.
+    pub fn set(&mut self, player: &Hand, dealer: Card, note: &str) {
+        self.set_desc(GameDesc::from_hand(player, dealer), note)
+    }
+
+    pub fn set_desc(&mut self, desc: GameDesc, note: &str) {
+        let note = note.trim();
+        if note.is_empty() {
+            self.0.remove(&desc);
+        } else {
+            self.0.insert(desc, note.to_string());
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&GameDesc, &str)> {
+        self.0.iter().map(|(k, v)| (k, v.as_str()))
+    }
+}
+
+// Serialized as a list of (GameDesc, note) pairs, like Table, as JSON can't have GameDesc keys
+#[cfg(feature = "serde")]
+impl Serialize for Notes {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut seq = serializer.serialize_seq(Some(self.0.len()))?;
+        for e in self.0.iter() {
+            seq.serialize_element(&e)?;
+        }
+        seq.end()
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for Notes {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let v: Vec<(GameDesc, String)> = Vec::deserialize(deserializer)?;
+        let mut notes = Self::new();
+        for (desc, note) in v {
+            player_hand_from_desc(desc).map_err(serde::de::Error::custom)?;
+            dealer_card_from_desc(desc).map_err(serde::de::Error::custom)?;
+            notes.set_desc(desc, &note);
+        }
+        Ok(notes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::deck::{Rank, Suit};
+
+    fn card(rank: Rank) -> Card {
+        Card::new(rank, Suit::Club)
+    }
+
+    #[test]
+    fn same_cell_same_note() {
+        let mut notes = Notes::new();
+        let h1 = Hand::new(&[card(Rank::R9), card(Rank::R7)]);
+        let h2 = Hand::new(&[card(Rank::RT), card(Rank::R6)]);
+        notes.set(&h1, card(Rank::R9), " dealer 9 is not a bust card! ");
+        assert_eq!(
+            notes.get(&h2, card(Rank::R9)),
+            Some("dealer 9 is not a bust card!")
+        );
+        assert_eq!(notes.get(&h2, card(Rank::RT)), None);
+        // pair of 8s is a different cell than hard 16
+        let h3 = Hand::new(&[card(Rank::R8), card(Rank::R8)]);
+        assert_eq!(notes.get(&h3, card(Rank::R9)), None);
+        notes.set(&h2, card(Rank::R9), "  ");
+        assert!(notes.is_empty());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_roundtrip() {
+        let mut notes = Notes::new();
+        let h = Hand::new(&[card(Rank::RA), card(Rank::RA)]);
+        notes.set(&h, card(Rank::RA), "always split aces");
+        let s = serde_json::to_string(&notes).unwrap();
+        assert_eq!(serde_json::from_str::<Notes>(&s).unwrap(), notes);
+        let bad = r#"[[{"hand":"Hard","player":3,"dealer":2},"impossible"]]"#;
+        assert!(serde_json::from_str::<Notes>(bad).is_err());
+    }
+}
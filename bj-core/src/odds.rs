@@ -0,0 +1,367 @@
+//! Whether each way of dealing hands deals them as often as it should.
+//!
+//! Players ask whether the "random" hands are really random. check() answers with Pearson's
+//! chi-squared test: deal a lot of hands, count how many land in each cell, and see whether the
+//! counts are further from what's expected than chance would explain. A small p-value means they
+//! are, and that the generator is biased. Cells too rare to expect MIN_EXPECTED hands in are
+//! grouped together so the test stays valid, and a hand in a cell the generator should never deal
+//! fails the check outright.
+use crate::deck::{Card, Suit, ALL_RANKS};
+use crate::frequency::cell_frequencies;
+use crate::hand::Hand;
+use crate::playstats::PlayStats;
+use crate::table::{GameDesc, Table};
+use crate::utils::{
+    hand_weight, rand_next_hand, rand_next_hand_freq_where, uniform_rand_2card_hand,
+};
+use std::collections::HashMap;
+use std::fmt;
+
+/// The usual significance level for check(): a fair generator fails 1 time in 1000
+pub const ALPHA: f64 = 0.001;
+
+/// Cells expected to be dealt fewer times than this are grouped with others
+pub const MIN_EXPECTED: f64 = 5.0;
+
+/// A way of dealing hands
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Generator {
+    /// Two cards for the player and an upcard from a shuffled infinite deck, see
+    /// utils::uniform_rand_2card_hand()
+    Uniform,
+    /// A cell chosen by the player's stats, see utils::rand_next_hand(). It's checked against
+    /// stats that weigh cells differently, so the weighting is tested too.
+    Weighted,
+    /// A cell chosen by how often it comes up in real play, see frequency::cell_frequencies()
+    Realistic,
+}
+
+pub const ALL_GENERATORS: [Generator; 3] = [
+    Generator::Uniform,
+    Generator::Weighted,
+    Generator::Realistic,
+];
+
+impl fmt::Display for Generator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Uniform => write!(f, "uniform"),
+            Self::Weighted => write!(f, "weighted"),
+            Self::Realistic => write!(f, "realistic"),
+        }
+    }
+}
+
+/// Stats that give cells a spread of weights, some retired, and are the same every time
+fn sample_stats() -> Table<PlayStats> {
+    let mut stats = Table::filled(PlayStats::new());
+    for (i, (_, s)) in stats.iter_mut().enumerate() {
+        let seen = (i % 4) as u32 * 5;
+        let correct = seen * (i % 3) as u32 / 2;
+        let streak = if i % 5 == 0 { correct } else { 0 };
+        // can't fail: correct is at most seen, and streak at most correct
+        *s = PlayStats::from_counts(seen, correct, streak).unwrap();
+    }
+    stats
+}
+
+impl Generator {
+    /// The chance of each cell being dealt. Cells that are never dealt are left out.
+    fn expected(self) -> HashMap<GameDesc, f64> {
+        let mut chances = HashMap::new();
+        match self {
+            Self::Uniform => {
+                let card = |rank| Card::new(rank, Suit::Club);
+                let chance = 1.0 / (ALL_RANKS.len() as f64).powi(3);
+                for first in ALL_RANKS.iter() {
+                    for second in ALL_RANKS.iter() {
+                        let hand = Hand::new(&[card(*first), card(*second)]);
+                        for dealer in ALL_RANKS.iter() {
+                            *chances
+                                .entry(GameDesc::from_hand(&hand, card(*dealer)))
+                                .or_insert(0.0) += chance;
+                        }
+                    }
+                }
+            }
+            Self::Weighted => {
+                let stats = sample_stats();
+                for (desc, s) in stats.iter() {
+                    chances.insert(*desc, f64::from(hand_weight(*s, None)));
+                }
+            }
+            Self::Realistic => {
+                for (desc, f) in cell_frequencies(None).iter() {
+                    chances.insert(*desc, *f);
+                }
+            }
+        }
+        chances.retain(|_, c| *c > 0.0);
+        let total: f64 = chances.values().sum();
+        for c in chances.values_mut() {
+            *c /= total;
+        }
+        chances
+    }
+
+    /// Deal the given number of hands, counting how many land in each cell
+    fn deal(self, hands: u32) -> HashMap<GameDesc, u32> {
+        let weighted = sample_stats();
+        let unseen = Table::filled(PlayStats::new());
+        let freq = cell_frequencies(None);
+        let mut counts = HashMap::new();
+        for _ in 0..hands {
+            // can't fail to deal, as there are cells with weight
+            let (player, dealer) = match self {
+                Self::Uniform => uniform_rand_2card_hand(),
+                Self::Weighted => rand_next_hand(&weighted).unwrap(),
+                Self::Realistic => {
+                    rand_next_hand_freq_where(&unseen, Some(&freq), None, |_| true).unwrap()
+                }
+            };
+            *counts
+                .entry(GameDesc::from_hand(&player, dealer))
+                .or_insert(0) += 1;
+        }
+        counts
+    }
+}
+
+/// The result of checking a Generator
+#[derive(Debug, Clone, PartialEq)]
+pub struct OddsReport {
+    pub generator: Generator,
+    pub hands: u32,
+    /// How many groups of cells the counts were compared in
+    pub groups: usize,
+    pub chi_squared: f64,
+    /// The chance of counts at least this far from expected if the generator is fair
+    pub p_value: f64,
+    /// Hands dealt in cells the generator should never deal
+    pub unexpected: u32,
+}
+
+impl OddsReport {
+    /// Whether the generator looks fair at the given significance level, e.g. ALPHA
+    pub fn passes(&self, alpha: f64) -> bool {
+        self.unexpected == 0 && self.p_value >= alpha
+    }
+}
+
+impl fmt::Display for OddsReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: {} hands in {} groups, chi-squared {:.1}, p = {:.3}",
+            self.generator, self.hands, self.groups, self.chi_squared, self.p_value
+        )?;
+        if self.unexpected > 0 {
+            write!(f, ", {} in cells never dealt", self.unexpected)?;
+        }
+        Ok(())
+    }
+}
+
+/// Deal the given number of hands with the generator and test them against the chance of each
+/// cell. Every cell should be expected at least MIN_EXPECTED times, or the rarest are grouped, so
+/// 100,000 hands is plenty.
+pub fn check(generator: Generator, hands: u32) -> OddsReport {
+    let counts = generator.deal(hands);
+    test_counts(generator, hands, &counts, &generator.expected())
+}
+
+/// check() every generator
+pub fn check_all(hands: u32) -> Vec<OddsReport> {
+    ALL_GENERATORS.iter().map(|g| check(*g, hands)).collect()
+}
+
+/// Test the counts of hands in each cell against the chance of each
+fn test_counts(
+    generator: Generator,
+    hands: u32,
+    counts: &HashMap<GameDesc, u32>,
+    chances: &HashMap<GameDesc, f64>,
+) -> OddsReport {
+    let unexpected = counts
+        .iter()
+        .filter(|(desc, _)| !chances.contains_key(desc))
+        .map(|(_, n)| n)
+        .sum();
+    // (expected, observed) per cell, rarest first, so rare cells are grouped with each other
+    let mut cells: Vec<(f64, f64)> = chances
+        .iter()
+        .map(|(desc, c)| {
+            let n = counts.get(desc).copied().unwrap_or(0);
+            (c * f64::from(hands), f64::from(n))
+        })
+        .collect();
+    cells.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+    let mut groups: Vec<(f64, f64)> = vec![];
+    let mut group = (0.0, 0.0);
+    for (e, o) in cells {
+        group = (group.0 + e, group.1 + o);
+        if group.0 >= MIN_EXPECTED {
+            groups.push(group);
+            group = (0.0, 0.0);
+        }
+    }
+    // what's left over is too rare to stand alone
+    match groups.last_mut() {
+        Some(last) => *last = (last.0 + group.0, last.1 + group.1),
+        None if group.0 > 0.0 => groups.push(group),
+        None => {}
+    }
+    let chi_squared = groups.iter().map(|(e, o)| (o - e).powi(2) / e).sum();
+    let p_value = if groups.len() < 2 {
+        1.0
+    } else {
+        chi_squared_p((groups.len() - 1) as f64, chi_squared)
+    };
+    OddsReport {
+        generator,
+        hands,
+        groups: groups.len(),
+        chi_squared,
+        p_value,
+        unexpected,
+    }
+}
+
+/// The chance of a chi-squared statistic of at least x with the given degrees of freedom
+fn chi_squared_p(dof: f64, x: f64) -> f64 {
+    if x <= 0.0 {
+        1.0
+    } else {
+        upper_gamma(dof / 2.0, x / 2.0)
+    }
+}
+
+/// ln(Γ(x)) for x > 0, by the Lanczos approximation
+fn ln_gamma(x: f64) -> f64 {
+    const COEF: [f64; 6] = [
+        76.180_091_729_471_46,
+        -86.505_320_329_416_77,
+        24.014_098_240_830_91,
+        -1.231_739_572_450_155,
+        0.001_208_650_973_866_179,
+        -0.000_005_395_239_384_953,
+    ];
+    let tmp = x + 5.5;
+    let tmp = tmp - (x + 0.5) * tmp.ln();
+    let mut ser = 1.000_000_000_190_015;
+    for (i, c) in COEF.iter().enumerate() {
+        ser += c / (x + 1.0 + i as f64);
+    }
+    -tmp + (2.506_628_274_631_000_5 * ser / x).ln()
+}
+
+/// The regularized upper incomplete gamma function Q(a, x), for a > 0 and x > 0. Uses its series
+/// below a + 1 and its continued fraction above, where each converges quickly.
+fn upper_gamma(a: f64, x: f64) -> f64 {
+    const ITERS: usize = 1000;
+    const EPS: f64 = 1e-14;
+    const TINY: f64 = 1e-300;
+    let front = (-x + a * x.ln() - ln_gamma(a)).exp();
+    if x < a + 1.0 {
+        let mut term = 1.0 / a;
+        let mut sum = term;
+        for n in 1..ITERS {
+            term *= x / (a + n as f64);
+            sum += term;
+            if term.abs() < sum.abs() * EPS {
+                break;
+            }
+        }
+        1.0 - sum * front
+    } else {
+        // modified Lentz's method
+        let mut b = x + 1.0 - a;
+        let mut c = 1.0 / TINY;
+        let mut d = 1.0 / b;
+        let mut h = d;
+        for i in 1..ITERS {
+            let an = -(i as f64) * (i as f64 - a);
+            b += 2.0;
+            d = an * d + b;
+            if d.abs() < TINY {
+                d = TINY;
+            }
+            c = b + an / c;
+            if c.abs() < TINY {
+                c = TINY;
+            }
+            d = 1.0 / d;
+            let delta = d * c;
+            h *= delta;
+            if (delta - 1.0).abs() < EPS {
+                break;
+            }
+        }
+        front * h
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A fair generator fails at this level about once in a million runs, and a biased one still
+    // fails every time
+    const TEST_ALPHA: f64 = 1e-6;
+    const TEST_HANDS: u32 = 20_000;
+
+    #[test]
+    fn p_values() {
+        // critical values from a chi-squared table
+        for (dof, x, p) in &[
+            (1.0, 3.841, 0.05),
+            (10.0, 18.307, 0.05),
+            (10.0, 23.209, 0.01),
+            (100.0, 124.342, 0.05),
+            (2.0, 0.0, 1.0),
+        ] {
+            assert!((chi_squared_p(*dof, *x) - p).abs() < 1e-4, "{} {}", dof, x);
+        }
+    }
+
+    #[test]
+    fn expected_sums_to_one() {
+        for g in ALL_GENERATORS.iter() {
+            let total: f64 = g.expected().values().sum();
+            assert!((total - 1.0).abs() < 1e-9, "{}", g);
+        }
+    }
+
+    #[test]
+    fn generators_are_fair() {
+        for g in ALL_GENERATORS.iter() {
+            let report = check(*g, TEST_HANDS);
+            assert!(report.passes(TEST_ALPHA), "{}", report);
+            assert!(report.groups > 100, "{}", report);
+        }
+    }
+
+    #[test]
+    fn catches_bias() {
+        // weighted hands aren't dealt as often as uniform ones
+        let counts = Generator::Weighted.deal(TEST_HANDS);
+        let report = test_counts(
+            Generator::Uniform,
+            TEST_HANDS,
+            &counts,
+            &Generator::Uniform.expected(),
+        );
+        assert!(!report.passes(TEST_ALPHA), "{}", report);
+        // and a hand where none should be fails however well the rest fit
+        let chances = Generator::Uniform.expected();
+        let mut counts: HashMap<GameDesc, u32> = chances
+            .iter()
+            .map(|(desc, c)| (*desc, (c * f64::from(TEST_HANDS)).round() as u32))
+            .collect();
+        let report = test_counts(Generator::Uniform, TEST_HANDS, &counts, &chances);
+        assert!(report.passes(TEST_ALPHA), "{}", report);
+        counts.insert(GameDesc::new(crate::hand::HandType::Hard, 4, 2), 1);
+        let report = test_counts(Generator::Uniform, TEST_HANDS, &counts, &chances);
+        assert!(!report.passes(TEST_ALPHA) && report.unexpected == 1);
+    }
+}
@@ -0,0 +1,302 @@
+//! What a new player goes through before their first drill.
+//!
+//! Onboarding walks through Steps in order: the player picks the rules they play by (as the
+//! strategy card made for them), answers a placement quiz (see crate::placement) of
+//! PLACEMENT_QUESTIONS hands, and is then recommended a drill to start with based on what they
+//! missed. The front end shows each step and
+//! feeds the player's choices back in; it also decides whether a player is new, and where an
+//! Onboarding is kept between visits.
+//!
+//! The quiz's results seed the player's stats, with its answers and its estimates as priors, so
+//! the first weighted hands are already the ones they don't know.
+use crate::basicstrategy::BasicStrategy;
+use crate::button::GameButton;
+use crate::deck::Card;
+use crate::hand::{Hand, HandType};
+use crate::placement::{self, Placement};
+use crate::playstats::PlayStats;
+use crate::table::{dealer_card_from_desc, player_hand_from_desc, GameDesc, Table, TableError};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/// How many hands the placement quiz asks, one from each of placement::REGIONS
+pub const PLACEMENT_QUESTIONS: usize = placement::REGIONS.len();
+
+/// Players who get fewer than this many placement hands right start with hit or stand only
+pub const HIT_STAND_BELOW: usize = 5;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Step {
+    /// Pick the strategy card for the rules the player plays by
+    PickRules,
+    /// Answer the placement quiz
+    Placement,
+    /// See the recommended starting drill
+    Recommend,
+    /// Onboarding is finished or was skipped
+    Done,
+}
+
+/// The drill a player is recommended to start with
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Recommendation {
+    /// Only hit and stand, for players who missed most of the quiz
+    HitStand,
+    /// The kind of hand the player missed the most of
+    Drill(HandType),
+    /// Every hand, weighted by the player's stats, for players who missed nothing
+    Everything,
+}
+
+#[derive(Debug, PartialEq, Error)]
+pub enum OnboardingError {
+    /// That can't be done at the current step
+    #[error("Can't do that at onboarding step {0:?}")]
+    WrongStep(Step),
+    /// The strategy card has no answer for a placement hand
+    #[error("{0}")]
+    Table(#[from] TableError),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Onboarding {
+    step: Step,
+    /// The name of the strategy card the player picked
+    card: Option<String>,
+    placement: Placement,
+}
+
+impl Default for Onboarding {
+    fn default() -> Self {
+        Self {
+            step: Step::PickRules,
+            card: None,
+            placement: Placement::new(PLACEMENT_QUESTIONS),
+        }
+    }
+}
+
+impl Onboarding {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn step(&self) -> Step {
+        self.step
+    }
+
+    /// The name of the strategy card the player picked, if they've picked one
+    pub fn card(&self) -> Option<&str> {
+        self.card.as_deref()
+    }
+
+    /// The player plays by the rules of the named strategy card. The caller checks that the card
+    /// exists.
+    pub fn pick_rules(&mut self, card: &str) -> Result<(), OnboardingError> {
+        self.expect_step(Step::PickRules)?;
+        self.card = Some(card.to_string());
+        self.step = Step::Placement;
+        Ok(())
+    }
+
+    /// The placement hand to answer next, if the quiz is under way
+    pub fn placement_hand(&self) -> Option<(Hand, Card)> {
+        if self.step != Step::Placement {
+            return None;
+        }
+        let desc = self.placement.next_question()?;
+        Some((
+            player_hand_from_desc(desc).ok()?,
+            dealer_card_from_desc(desc).ok()?,
+        ))
+    }
+
+    /// How many placement hands have been answered, and how many there are
+    pub fn progress(&self) -> (usize, usize) {
+        self.placement.progress()
+    }
+
+    /// Answer the current placement hand by pressing btn, graded against bs. Returns whether the
+    /// answer was correct. The last answer moves on to the recommendation.
+    pub fn answer(&mut self, bs: &BasicStrategy, btn: GameButton) -> Result<bool, OnboardingError> {
+        let desc = match self.step {
+            Step::Placement => self.placement.next_question(),
+            _ => None,
+        }
+        .ok_or(OnboardingError::WrongStep(self.step))?;
+        let correct = placement::grade(bs, desc, btn)?;
+        self.placement.answer(desc, correct);
+        if self.placement.is_done() {
+            self.step = Step::Recommend;
+        }
+        Ok(correct)
+    }
+
+    /// Each placement cell answered so far, and whether it was answered correctly
+    pub fn results(&self) -> impl Iterator<Item = (GameDesc, bool)> + '_ {
+        self.placement.answers().iter().copied()
+    }
+
+    /// The drill to start with, once the quiz is done. Players who missed most of it start with
+    /// hit or stand only; otherwise they drill the kind of hand they missed the largest share of.
+    pub fn recommendation(&self) -> Option<Recommendation> {
+        if !self.placement.is_done() {
+            return None;
+        }
+        let right = self.results().filter(|(_, c)| *c).count();
+        if right < HIT_STAND_BELOW {
+            return Some(Recommendation::HitStand);
+        }
+        let missed_share = |hand: HandType| {
+            let (asked, missed) = self
+                .results()
+                .filter(|(desc, _)| desc.hand == hand)
+                .fold((0, 0), |(a, m), (_, c)| (a + 1, m + u32::from(!c)));
+            if asked == 0 {
+                0.0
+            } else {
+                missed as f32 / asked as f32
+            }
+        };
+        let worst = [HandType::Hard, HandType::Soft, HandType::Pair]
+            .iter()
+            .copied()
+            .map(|hand| (hand, missed_share(hand)))
+            // the first of any tied for worst
+            .fold((HandType::Hard, 0.0), |w, h| if h.1 > w.1 { h } else { w });
+        Some(if worst.1 == 0.0 {
+            Recommendation::Everything
+        } else {
+            Recommendation::Drill(worst.0)
+        })
+    }
+
+    /// Add the placement quiz's answers to the given stats, and give every cell its estimate as a
+    /// prior
+    pub fn seed(&self, stats: &mut Table<PlayStats>) {
+        self.placement.apply(stats);
+    }
+
+    /// The player has seen their recommendation
+    pub fn finish(&mut self) -> Result<(), OnboardingError> {
+        self.expect_step(Step::Recommend)?;
+        self.step = Step::Done;
+        Ok(())
+    }
+
+    /// The player wants no onboarding, at any step
+    pub fn skip(&mut self) {
+        self.step = Step::Done;
+    }
+
+    fn expect_step(&self, step: Step) -> Result<(), OnboardingError> {
+        if self.step == step {
+            Ok(())
+        } else {
+            Err(OnboardingError::WrongStep(self.step))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::resp::Resp;
+
+    fn bs() -> BasicStrategy {
+        // Hit everything except stand on soft hands, so answers are easy to get right or wrong
+        let table = Table::new(
+            std::iter::repeat_n(Resp::Hit, 170)
+                .chain(std::iter::repeat_n(Resp::Stand, 90))
+                .chain(std::iter::repeat_n(Resp::Hit, 100)),
+        )
+        .unwrap();
+        BasicStrategy::new(None, table)
+    }
+
+    /// Answer every placement hand, correctly if right says so
+    fn quiz<F>(ob: &mut Onboarding, right: F)
+    where
+        F: Fn(GameDesc) -> bool,
+    {
+        let bs = bs();
+        while let Some((player, dealer)) = ob.placement_hand() {
+            let desc = GameDesc::from_hand(&player, dealer);
+            let hit = desc.hand != HandType::Soft;
+            let btn = if hit == right(desc) {
+                GameButton::Hit
+            } else {
+                GameButton::Stand
+            };
+            assert_eq!(ob.answer(&bs, btn), Ok(right(desc)));
+        }
+    }
+
+    #[test]
+    fn steps() {
+        let mut ob = Onboarding::new();
+        assert_eq!(ob.step(), Step::PickRules);
+        assert!(ob.placement_hand().is_none());
+        assert_eq!(
+            ob.finish(),
+            Err(OnboardingError::WrongStep(Step::PickRules))
+        );
+        ob.pick_rules("t1").unwrap();
+        assert_eq!(ob.card(), Some("t1"));
+        assert_eq!(ob.step(), Step::Placement);
+        assert_eq!(ob.progress(), (0, PLACEMENT_QUESTIONS));
+        assert_eq!(ob.recommendation(), None);
+        quiz(&mut ob, |_| true);
+        assert_eq!(ob.step(), Step::Recommend);
+        assert_eq!(ob.progress(), (10, 10));
+        assert_eq!(
+            ob.answer(&bs(), GameButton::Hit),
+            Err(OnboardingError::WrongStep(Step::Recommend))
+        );
+        ob.finish().unwrap();
+        assert_eq!(ob.step(), Step::Done);
+        let mut ob = Onboarding::new();
+        ob.skip();
+        assert_eq!(ob.step(), Step::Done);
+    }
+
+    #[test]
+    fn recommendations() {
+        let recommend = |right: fn(GameDesc) -> bool| {
+            let mut ob = Onboarding::new();
+            ob.pick_rules("t1").unwrap();
+            quiz(&mut ob, right);
+            ob.recommendation().unwrap()
+        };
+        assert_eq!(recommend(|_| true), Recommendation::Everything);
+        assert_eq!(recommend(|_| false), Recommendation::HitStand);
+        assert_eq!(
+            recommend(|d| d.hand != HandType::Soft),
+            Recommendation::Drill(HandType::Soft)
+        );
+        assert_eq!(
+            recommend(|d| d.hand != HandType::Pair),
+            Recommendation::Drill(HandType::Pair)
+        );
+    }
+
+    #[test]
+    fn seed() {
+        let mut ob = Onboarding::new();
+        ob.pick_rules("t1").unwrap();
+        quiz(&mut ob, |d| d.hand == HandType::Hard);
+        let mut stats = Table::new(std::iter::repeat_n(PlayStats::new(), 360)).unwrap();
+        ob.seed(&mut stats);
+        let cell = |label: &str| stats.get_desc(label.parse().unwrap()).unwrap();
+        for (desc, correct) in ob.results() {
+            let s = stats.get_desc(desc).unwrap();
+            assert_eq!((s.seen(), s.correct()), (1, u32::from(correct)));
+        }
+        assert!(cell("Hard 16 vs 9").prior() > cell("8,8 vs 9").prior());
+        assert_eq!(stats.values().map(|s| s.seen()).sum::<u32>(), 10);
+    }
+}
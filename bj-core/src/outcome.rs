@@ -0,0 +1,83 @@
+//! How a finished hand did against the dealer, and what that pays.
+//!
+//! A natural (see Hand::is_natural()) beats every other hand, including a 21 made of three or more
+//! cards or by splitting, and pays the rules' blackjack payout instead of even money. Only a
+//! dealer natural ties it. In the same way, a dealer natural beats a player's composed 21.
+use crate::basicstrategy::rules::{BlackjackPayout, SplitState};
+use crate::hand::Hand;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Outcome {
+    /// The player had a natural and the dealer didn't
+    Blackjack,
+    Win,
+    Push,
+    Lose,
+}
+
+impl Outcome {
+    /// What the outcome wins on the given bet, or loses if negative
+    pub fn net(self, bet: f64, pays: BlackjackPayout) -> f64 {
+        match self {
+            Self::Blackjack => bet * pays.ratio(),
+            Self::Win => bet,
+            Self::Push => 0.0,
+            Self::Lose => -bet,
+        }
+    }
+}
+
+/// How the player's finished hand did against the dealer's. split is where the player's hand came
+/// from, and dealer is the dealer's whole hand, which is a natural if it's two cards worth 21.
+pub fn outcome(player: &Hand, split: SplitState, dealer: &Hand) -> Outcome {
+    let natural = player.is_natural(split);
+    let dealer_natural = dealer.is_blackjack();
+    if player.is_bust() {
+        Outcome::Lose
+    } else if natural && dealer_natural {
+        Outcome::Push
+    } else if natural {
+        Outcome::Blackjack
+    } else if dealer_natural || (!dealer.is_bust() && player.value() < dealer.value()) {
+        Outcome::Lose
+    } else if dealer.is_bust() || player.value() > dealer.value() {
+        Outcome::Win
+    } else {
+        Outcome::Push
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::deck::Rank;
+    use crate::hand::tests::hand;
+
+    #[test]
+    fn outcomes() {
+        let none = SplitState::default();
+        let split = SplitState {
+            splits: 1,
+            aces: true,
+        };
+        let bj = hand(&[Rank::RA, Rank::RK]);
+        let composed = hand(&[Rank::R7, Rank::R7, Rank::R7]);
+        let twenty = hand(&[Rank::RT, Rank::RQ]);
+        assert_eq!(outcome(&bj, none, &composed), Outcome::Blackjack);
+        assert_eq!(outcome(&bj, split, &composed), Outcome::Push);
+        assert_eq!(outcome(&bj, split, &twenty), Outcome::Win);
+        assert_eq!(outcome(&bj, none, &bj), Outcome::Push);
+        assert_eq!(outcome(&composed, none, &bj), Outcome::Lose);
+        assert_eq!(outcome(&bj, split, &bj), Outcome::Lose);
+        let bust = hand(&[Rank::RT, Rank::R6, Rank::R9]);
+        assert_eq!(outcome(&bust, none, &bust), Outcome::Lose);
+        assert_eq!(outcome(&twenty, none, &bust), Outcome::Win);
+        assert_eq!(outcome(&twenty, none, &composed), Outcome::Lose);
+        assert_eq!(outcome(&twenty, none, &twenty), Outcome::Push);
+        assert_eq!(
+            Outcome::Blackjack.net(10.0, BlackjackPayout::SixToFive),
+            12.0
+        );
+        assert_eq!(Outcome::Lose.net(10.0, BlackjackPayout::ThreeToTwo), -10.0);
+    }
+}
@@ -0,0 +1,362 @@
+//! The count-cancellation drill: two cards are flashed, and the player says whether together they
+//! cancel (net 0), add to the count, or subtract from it. Fast counters count cards in pairs like
+//! this, so a 5 and a king can be skipped at a glance instead of counted one after the other.
+//!
+//! Pairs are graded under whatever CountSystem the drill is made with. A PairMix says how often
+//! each kind of pair comes up, e.g. mostly cancelling pairs for a player who keeps missing them.
+//! Each answer is timed from when the pair was dealt, capped like practice time at
+//! practice::MAX_ANSWER_SECS, and the player's PairStats keep their record and speed for each
+//! kind of pair.
+use crate::count::CountSystem;
+#[cfg(feature = "rand")]
+use crate::deck::rand_suit;
+use crate::deck::{Card, Rank, Suit, ALL_RANKS};
+use crate::playstats::PlayStats;
+use crate::practice::MAX_ANSWER_SECS;
+#[cfg(feature = "rand")]
+use rand::distributions::WeightedIndex;
+#[cfg(feature = "rand")]
+use rand::prelude::*;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+use thiserror::Error;
+
+/// What a pair of cards does to the count
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(rename_all = "lowercase")
+)]
+pub enum PairNet {
+    Cancel,
+    Add,
+    Subtract,
+}
+
+impl PairNet {
+    pub const ALL: [Self; 3] = [Self::Cancel, Self::Add, Self::Subtract];
+
+    /// What a pair worth the given count does to the count
+    pub fn of(value: i16) -> Self {
+        match value {
+            0 => Self::Cancel,
+            v if v > 0 => Self::Add,
+            _ => Self::Subtract,
+        }
+    }
+}
+
+impl fmt::Display for PairNet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::Cancel => "cancel",
+                Self::Add => "add",
+                Self::Subtract => "subtract",
+            }
+        )
+    }
+}
+
+impl FromStr for PairNet {
+    type Err = PairDrillError;
+
+    /// Parse what Display writes, in any case
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|n| n.to_string().eq_ignore_ascii_case(s.trim()))
+            .ok_or_else(|| PairDrillError::BadAnswer(s.to_string()))
+    }
+}
+
+#[derive(Debug, PartialEq, Error)]
+pub enum PairDrillError {
+    /// Every kind of pair has a weight of 0
+    #[error("The mix doesn't deal any pairs")]
+    EmptyMix,
+    /// The mix asks for this kind of pair, but no pair under the count system is one
+    #[error("No pair of cards would {0} under this count")]
+    NoPairs(PairNet),
+    /// Not "cancel", "add", or "subtract"
+    #[error("'{0}' isn't cancel, add, or subtract")]
+    BadAnswer(String),
+}
+
+/// How often each kind of pair is dealt, as weights relative to each other. E.g. 2, 1, 1 deals
+/// half cancelling pairs.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(default))]
+pub struct PairMix {
+    pub cancel: u32,
+    pub add: u32,
+    pub subtract: u32,
+}
+
+impl Default for PairMix {
+    fn default() -> Self {
+        Self {
+            cancel: 1,
+            add: 1,
+            subtract: 1,
+        }
+    }
+}
+
+impl PairMix {
+    pub fn weight(self, net: PairNet) -> u32 {
+        match net {
+            PairNet::Cancel => self.cancel,
+            PairNet::Add => self.add,
+            PairNet::Subtract => self.subtract,
+        }
+    }
+}
+
+/// The player's record on each kind of pair, and how long their answers took
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(default))]
+pub struct PairStats {
+    pub cancel: PlayStats,
+    pub add: PlayStats,
+    pub subtract: PlayStats,
+    /// The total time taken by every answer, in seconds
+    pub secs: f64,
+}
+
+impl PairStats {
+    pub fn get(&self, net: PairNet) -> PlayStats {
+        match net {
+            PairNet::Cancel => self.cancel,
+            PairNet::Add => self.add,
+            PairNet::Subtract => self.subtract,
+        }
+    }
+
+    fn get_mut(&mut self, net: PairNet) -> &mut PlayStats {
+        match net {
+            PairNet::Cancel => &mut self.cancel,
+            PairNet::Add => &mut self.add,
+            PairNet::Subtract => &mut self.subtract,
+        }
+    }
+
+    /// How many pairs have been answered
+    pub fn answered(&self) -> u32 {
+        PairNet::ALL.iter().map(|n| self.get(*n).seen()).sum()
+    }
+
+    /// The average time an answer took, in seconds, if any have been given
+    pub fn avg_secs(&self) -> Option<f64> {
+        match self.answered() {
+            0 => None,
+            n => Some(self.secs / f64::from(n)),
+        }
+    }
+}
+
+/// How an answer went
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PairGraded {
+    pub correct: bool,
+    /// The right answer
+    pub net: PairNet,
+    /// How long the answer took, in seconds, capped at MAX_ANSWER_SECS
+    pub secs: f64,
+}
+
+pub struct PairDrill<S: CountSystem> {
+    system: S,
+    mix: PairMix,
+    /// Every ordered pair of ranks, by what it does to the count, to deal from
+    #[cfg(feature = "rand")]
+    pairs: Vec<(PairNet, Vec<(Rank, Rank)>)>,
+    /// The pair being shown and when it was dealt, in seconds
+    shown: Option<(Card, Card, f64)>,
+    stats: PairStats,
+}
+
+impl<S: CountSystem> PairDrill<S> {
+    /// A drill dealing pairs in the given mix, graded under the given system. Errors if the mix
+    /// asks for a kind of pair the system can't make, or for no pairs at all.
+    pub fn new(system: S, mix: PairMix) -> Result<Self, PairDrillError> {
+        if PairNet::ALL.iter().all(|n| mix.weight(*n) == 0) {
+            return Err(PairDrillError::EmptyMix);
+        }
+        let value = |r| system.card_value(Card::new(r, Suit::Club));
+        let mut pairs = vec![];
+        for net in PairNet::ALL.iter().copied() {
+            let these: Vec<(Rank, Rank)> = ALL_RANKS
+                .iter()
+                .flat_map(|a| ALL_RANKS.iter().map(move |b| (*a, *b)))
+                .filter(|(a, b)| PairNet::of(value(*a) + value(*b)) == net)
+                .collect();
+            if these.is_empty() && mix.weight(net) > 0 {
+                return Err(PairDrillError::NoPairs(net));
+            }
+            pairs.push((net, these));
+        }
+        Ok(Self {
+            system,
+            mix,
+            #[cfg(feature = "rand")]
+            pairs,
+            shown: None,
+            stats: PairStats::default(),
+        })
+    }
+
+    pub fn mix(&self) -> PairMix {
+        self.mix
+    }
+
+    pub fn stats(&self) -> PairStats {
+        self.stats
+    }
+
+    /// Continue from stats saved earlier
+    pub fn set_stats(&mut self, stats: PairStats) {
+        self.stats = stats;
+    }
+
+    /// The pair being shown, if it hasn't been answered yet
+    pub fn shown(&self) -> Option<(Card, Card)> {
+        self.shown.map(|(a, b, _)| (a, b))
+    }
+
+    /// Deal the next pair at the given time, in seconds, replacing any that wasn't answered
+    #[cfg(feature = "rand")]
+    pub fn deal(&mut self, now: f64) -> (Card, Card) {
+        let mut rng = thread_rng();
+        let weights: Vec<u32> = self
+            .pairs
+            .iter()
+            .map(|(n, _)| self.mix.weight(*n))
+            .collect();
+        // new() made sure some weight isn't 0, and that every kind with weight has pairs
+        let dist = WeightedIndex::new(&weights).unwrap();
+        let (a, b) = *self.pairs[dist.sample(&mut rng)]
+            .1
+            .choose(&mut rng)
+            .unwrap();
+        let (a, b) = (Card::new(a, rand_suit()), Card::new(b, rand_suit()));
+        self.shown = Some((a, b, now));
+        (a, b)
+    }
+
+    /// Grade the player's answer for the pair being shown, given at the given time, in seconds.
+    /// None if no pair is being shown.
+    pub fn answer(&mut self, answer: PairNet, now: f64) -> Option<PairGraded> {
+        let (a, b, dealt) = self.shown.take()?;
+        let net = PairNet::of(self.system.cards_value(&[a, b]));
+        let secs = (now - dealt).clamp(0.0, MAX_ANSWER_SECS);
+        let correct = answer == net;
+        self.stats.get_mut(net).inc(correct);
+        self.stats.secs += secs;
+        Some(PairGraded { correct, net, secs })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::count::HiLo;
+
+    #[test]
+    fn nets() {
+        assert_eq!(PairNet::of(0), PairNet::Cancel);
+        assert_eq!(PairNet::of(2), PairNet::Add);
+        assert_eq!(PairNet::of(-1), PairNet::Subtract);
+        assert_eq!(" Cancel".parse(), Ok(PairNet::Cancel));
+        assert_eq!(
+            "zero".parse::<PairNet>(),
+            Err(PairDrillError::BadAnswer("zero".to_string()))
+        );
+        for net in PairNet::ALL.iter() {
+            assert_eq!(net.to_string().parse(), Ok(*net));
+        }
+    }
+
+    #[test]
+    fn bad_mix() {
+        let none = PairMix {
+            cancel: 0,
+            add: 0,
+            subtract: 0,
+        };
+        assert_eq!(
+            PairDrill::new(HiLo::new(), none).err(),
+            Some(PairDrillError::EmptyMix)
+        );
+        // a count where every card is worth 1 can't cancel
+        struct Ones;
+        impl CountSystem for Ones {
+            fn card_value(&self, _: Card) -> i16 {
+                1
+            }
+        }
+        assert_eq!(
+            PairDrill::new(Ones, PairMix::default()).err(),
+            Some(PairDrillError::NoPairs(PairNet::Cancel))
+        );
+        let add = PairMix { add: 1, ..none };
+        assert!(PairDrill::new(Ones, add).is_ok());
+    }
+
+    #[test]
+    fn grading() {
+        let mut drill = PairDrill::new(HiLo::new(), PairMix::default()).unwrap();
+        assert_eq!(drill.answer(PairNet::Add, 1.0), None);
+        let five_king = (
+            Card::new(Rank::R5, Suit::Club),
+            Card::new(Rank::RK, Suit::Heart),
+        );
+        drill.shown = Some((five_king.0, five_king.1, 10.0));
+        assert_eq!(drill.shown(), Some(five_king));
+        assert_eq!(
+            drill.answer(PairNet::Add, 12.5),
+            Some(PairGraded {
+                correct: false,
+                net: PairNet::Cancel,
+                secs: 2.5,
+            })
+        );
+        assert_eq!(drill.shown(), None);
+        drill.shown = Some((five_king.0, five_king.1, 20.0));
+        let graded = drill.answer(PairNet::Cancel, 1000.0).unwrap();
+        assert!(graded.correct && graded.secs == MAX_ANSWER_SECS);
+        let stats = drill.stats();
+        assert_eq!((stats.cancel.seen(), stats.cancel.correct()), (2, 1));
+        assert_eq!(stats.answered(), 2);
+        assert_eq!(stats.avg_secs(), Some((2.5 + MAX_ANSWER_SECS) / 2.0));
+    }
+
+    #[test]
+    #[cfg(feature = "rand")]
+    fn mix() {
+        let only_cancel = PairMix {
+            cancel: 1,
+            add: 0,
+            subtract: 0,
+        };
+        let hl = HiLo::new();
+        let mut drill = PairDrill::new(HiLo::new(), only_cancel).unwrap();
+        for _ in 0..100 {
+            let (a, b) = drill.deal(0.0);
+            assert_eq!(hl.cards_value(&[a, b]), 0);
+        }
+        let mut drill = PairDrill::new(HiLo::new(), PairMix::default()).unwrap();
+        let mut seen = std::collections::HashSet::new();
+        for _ in 0..300 {
+            let (a, b) = drill.deal(0.0);
+            seen.insert(PairNet::of(hl.cards_value(&[a, b])));
+        }
+        assert_eq!(seen.len(), 3);
+    }
+}
@@ -0,0 +1,238 @@
+//! A short placement quiz that estimates how well a player knows each part of a strategy card.
+//!
+//! The card is split into REGIONS of cells a player tends to know about equally well, like the
+//! stiff hands against a strong upcard. Each question is a cell from the region where an answer
+//! tells the most: the one whose estimate is most uncertain, or the biggest of those tied. A
+//! region's estimate is its fraction correct with one right and one wrong answer assumed up front,
+//! so a region with no answers is a coin flip and the most uncertain of all. The first questions
+//! thus visit every region once, and later ones go back to the regions with mixed answers.
+//!
+//! Once the quiz is done, apply() gives every cell its region's estimate as a prior (see
+//! PlayStats::prior()), so weighted dealing leans toward what the player doesn't know from the
+//! first hand instead of after hundreds of them.
+use crate::basicstrategy::rules::{Allowed, SplitState};
+use crate::basicstrategy::BasicStrategy;
+use crate::button::GameButton;
+use crate::correct_resp::expected_button;
+use crate::playstats::PlayStats;
+use crate::table::{
+    dealer_card_from_desc, parse_cells, player_hand_from_desc, GameDesc, Table, TableError,
+};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// The regions of a strategy card, as rows and upcards for parse_cells(). Every cell is in exactly
+/// one.
+pub const REGIONS: [(&[&str], &[&str]); 10] = [
+    (&["Hard 5-8"], &[]),
+    (&["Hard 9-11"], &[]),
+    (&["Hard 12-16"], &["2-6"]),
+    (&["Hard 12-16"], &["7-A"]),
+    (&["Hard 17-21"], &[]),
+    (&["Soft 13-18"], &[]),
+    (&["Soft 19-21"], &[]),
+    (&["A,A", "8,8"], &[]),
+    (&["5,5", "T,T"], &[]),
+    (&["2,2", "3,3", "4,4", "6,6", "7,7", "9,9"], &[]),
+];
+
+/// The cells of each of REGIONS
+pub fn regions() -> Vec<Vec<GameDesc>> {
+    REGIONS
+        .iter()
+        .map(|(rows, upcards)| parse_cells(rows, upcards).expect("regions are valid cells"))
+        .collect()
+}
+
+/// Whether pressing btn on the opening hand in the given cell is what bs says to do
+pub fn grade(bs: &BasicStrategy, desc: GameDesc, btn: GameButton) -> Result<bool, TableError> {
+    let player = player_hand_from_desc(desc)?;
+    let dealer = dealer_card_from_desc(desc)?;
+    let allowed = Allowed::for_hand(&player, dealer, bs.rules.as_ref(), SplitState::default());
+    Ok(btn == expected_button(bs.resp(&player, dealer)?, allowed))
+}
+
+/// Right and wrong answers, with one of each assumed up front
+#[derive(Clone, Copy)]
+struct Beta {
+    right: f32,
+    wrong: f32,
+}
+
+impl Beta {
+    fn new<'a, I>(answers: I) -> Self
+    where
+        I: Iterator<Item = &'a (GameDesc, bool)>,
+    {
+        answers.fold(
+            Self {
+                right: 1.0,
+                wrong: 1.0,
+            },
+            |b, (_, correct)| Self {
+                right: b.right + f32::from(u8::from(*correct)),
+                wrong: b.wrong + f32::from(u8::from(!*correct)),
+            },
+        )
+    }
+
+    fn mean(self) -> f32 {
+        self.right / (self.right + self.wrong)
+    }
+
+    fn variance(self) -> f32 {
+        let n = self.right + self.wrong;
+        self.right * self.wrong / (n * n * (n + 1.0))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Placement {
+    /// How many questions to ask
+    questions: usize,
+    /// Each cell asked so far, and whether it was answered correctly
+    answers: Vec<(GameDesc, bool)>,
+}
+
+impl Placement {
+    /// A quiz of the given number of questions
+    pub fn new(questions: usize) -> Self {
+        Self {
+            questions,
+            answers: vec![],
+        }
+    }
+
+    /// The cell to ask about next, or None if the quiz is done. The same answers always lead to
+    /// the same question.
+    pub fn next_question(&self) -> Option<GameDesc> {
+        if self.is_done() {
+            return None;
+        }
+        let mut best: Option<((f32, usize), Vec<GameDesc>)> = None;
+        for cells in regions() {
+            let unasked: Vec<GameDesc> = cells
+                .iter()
+                .copied()
+                .filter(|c| !self.answers.iter().any(|(a, _)| a == c))
+                .collect();
+            if unasked.is_empty() {
+                continue;
+            }
+            let info = (self.beta(&cells).variance(), cells.len());
+            if best.as_ref().is_none_or(|(b, _)| info > *b) {
+                best = Some((info, unasked));
+            }
+        }
+        // the middle of what's left, to spread questions across the region's rows and upcards
+        best.map(|(_, unasked)| unasked[unasked.len() / 2])
+    }
+
+    /// Record the answer to the given cell
+    pub fn answer(&mut self, desc: GameDesc, correct: bool) {
+        self.answers.push((desc, correct));
+    }
+
+    /// Each cell asked so far, and whether it was answered correctly
+    pub fn answers(&self) -> &[(GameDesc, bool)] {
+        &self.answers
+    }
+
+    /// How many questions have been answered, and how many there are
+    pub fn progress(&self) -> (usize, usize) {
+        (self.answers.len(), self.questions)
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.answers.len() >= self.questions
+    }
+
+    fn beta(&self, cells: &[GameDesc]) -> Beta {
+        Beta::new(self.answers.iter().filter(|(a, _)| cells.contains(a)))
+    }
+
+    /// The estimated chance, in percent, that the player answers each cell correctly
+    pub fn estimates(&self) -> HashMap<GameDesc, u8> {
+        let mut estimates = HashMap::new();
+        for cells in regions() {
+            let percent = (self.beta(&cells).mean() * 100.0).round() as u8;
+            estimates.extend(cells.into_iter().map(|c| (c, percent)));
+        }
+        estimates
+    }
+
+    /// Add the answers so far to stats, and give every cell its estimate as a prior
+    pub fn apply(&self, stats: &mut Table<PlayStats>) {
+        let estimates = self.estimates();
+        for (desc, stat) in stats.iter_mut() {
+            for (_, correct) in self.answers.iter().filter(|(a, _)| a == desc) {
+                stat.inc(*correct);
+            }
+            stat.set_prior(estimates.get(desc).copied());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hand::HandType;
+    use crate::table::NUM_CELLS;
+
+    #[test]
+    fn regions_cover_card() {
+        let cells: Vec<GameDesc> = regions().into_iter().flatten().collect();
+        assert_eq!(cells.len(), NUM_CELLS);
+        let mut unique = cells.clone();
+        unique.sort();
+        unique.dedup();
+        assert_eq!(unique.len(), NUM_CELLS);
+    }
+
+    #[test]
+    fn questions_spread() {
+        let mut p = Placement::new(10);
+        let mut asked = vec![];
+        while let Some(desc) = p.next_question() {
+            assert!(!asked.contains(&desc));
+            asked.push(desc);
+            p.answer(desc, true);
+        }
+        assert_eq!(p.progress(), (10, 10));
+        // one from each region
+        for cells in regions() {
+            assert_eq!(asked.iter().filter(|d| cells.contains(d)).count(), 1);
+        }
+        // the same answers ask the same questions
+        let mut again = Placement::new(10);
+        for desc in &asked {
+            assert_eq!(again.next_question(), Some(*desc));
+            again.answer(*desc, true);
+        }
+    }
+
+    #[test]
+    fn estimates() {
+        let mut p = Placement::new(10);
+        while let Some(desc) = p.next_question() {
+            p.answer(desc, desc.hand != HandType::Soft);
+        }
+        let est = p.estimates();
+        let cell = |label: &str| est[&label.parse::<GameDesc>().unwrap()];
+        assert_eq!(est.len(), NUM_CELLS);
+        assert!(cell("Soft 17 vs 3") < 50);
+        assert!(cell("Hard 16 vs 10") > 50);
+        let mut stats = Table::new(vec![PlayStats::new(); NUM_CELLS]).unwrap();
+        p.apply(&mut stats);
+        let soft = stats.get_desc("Soft 17 vs 4".parse().unwrap()).unwrap();
+        let hard = stats.get_desc("Hard 16 vs 10".parse().unwrap()).unwrap();
+        assert_eq!(soft.seen(), 0);
+        for (desc, correct) in p.answers() {
+            let s = stats.get_desc(*desc).unwrap();
+            assert_eq!((s.seen(), s.correct()), (1, u32::from(*correct)));
+        }
+        assert!(soft.weight() > hard.weight());
+    }
+}
@@ -17,12 +17,65 @@
 //! - Weight of PlayStats with 1 correct is 0.5 = (1 - 1/2)
 //! - Weight of PlayStats with 2 correct is 0.333 = (1 - 2/3)
 //! - Weight of PlayStats with 1 correct/1 wrong is 0.666 = (1 - 1/3)
+//!
+//! PlayStats also counts how many times in a row the event has most recently been responded to
+//! correctly. Once that streak reaches RETIRE_STREAK the event is considered retired: the player
+//! clearly knows it for now, so it should come up much less often until they get it wrong again.
+//!
+//! Stats that are given the time with mark_seen() remember when the event was last seen. An event
+//! not seen for long enough is stale (see Refresh): whatever the player's record on it, they may
+//! have forgotten it since, so weighted dealing treats it like one they've never seen.
+//!
+//! Stats can also have a prior: an estimate, in percent, of how likely the player is to respond
+//! correctly before they've been seen much, e.g. from a placement quiz. The prior counts as
+//! PRIOR_WEIGHT responses at that rate in the weight, and so matters less the more the event is
+//! seen, but never in seen, correct, or accuracy.
+use crate::confidence::{wilson, Interval};
+#[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 
-#[derive(Deserialize, Serialize, PartialEq, Copy, Clone, Default, Debug)]
+#[derive(PartialEq, Copy, Clone, Default, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct PlayStats {
     seen: u32,
     correct: u32,
+    // Stats saved before streaks were tracked don't have one. Treating them as having no streak
+    // means nothing starts out retired.
+    #[cfg_attr(feature = "serde", serde(default))]
+    streak: u32,
+    /// When the event was last seen, in seconds since the Unix epoch. Stats from before this was
+    /// kept, and ones never given the time, don't have it, and are never stale.
+    #[cfg_attr(feature = "serde", serde(default))]
+    last_seen: Option<u64>,
+    /// The estimated percent correct, see set_prior()
+    #[cfg_attr(feature = "serde", serde(default))]
+    prior: Option<u8>,
+}
+
+/// How many correct responses in a row retire an event
+pub const RETIRE_STREAK: u32 = 5;
+
+/// How many responses a prior counts as in weight()
+pub const PRIOR_WEIGHT: f32 = 3.0;
+
+pub const SECS_PER_DAY: u64 = 24 * 60 * 60;
+
+/// When events count as stale: when they were last seen more than after seconds before now
+#[derive(PartialEq, Copy, Clone, Debug)]
+pub struct Refresh {
+    /// Seconds since the Unix epoch
+    pub now: u64,
+    pub after: u64,
+}
+
+impl Refresh {
+    /// Events not seen in more than the given number of days are stale
+    pub fn after_days(now: u64, days: u32) -> Self {
+        Self {
+            now,
+            after: u64::from(days) * SECS_PER_DAY,
+        }
+    }
 }
 
 impl PlayStats {
@@ -40,7 +93,14 @@ impl PlayStats {
         // will create better weights that result in a better player experience.
         //
         // weight() will NOT equal correct() / seen()
-        1f32 - self.correct as f32 / (self.seen + 1) as f32
+        match self.prior {
+            None => 1f32 - self.correct as f32 / (self.seen + 1) as f32,
+            Some(p) => {
+                let prior_correct = f32::from(p) / 100.0 * PRIOR_WEIGHT;
+                1f32 - (self.correct as f32 + prior_correct)
+                    / (self.seen as f32 + PRIOR_WEIGHT + 1.0)
+            }
+        }
     }
 
     pub fn inc(&mut self, correct: bool) {
@@ -51,7 +111,58 @@ impl PlayStats {
         self.seen += amt;
         if correct {
             self.correct += amt;
+            self.streak += amt;
+        } else if amt > 0 {
+            self.streak = 0;
+        }
+    }
+
+    /// Stats with the given counts, or None if they're impossible
+    pub fn from_counts(seen: u32, correct: u32, streak: u32) -> Option<Self> {
+        if correct > seen || streak > correct {
+            return None;
         }
+        Some(Self {
+            seen,
+            correct,
+            streak,
+            last_seen: None,
+            prior: None,
+        })
+    }
+
+    /// Estimate that the player responds correctly the given percent of the time, capped at 100,
+    /// until they've been seen enough to go by. None removes the estimate.
+    pub fn set_prior(&mut self, percent: Option<u8>) {
+        self.prior = percent.map(|p| p.min(100));
+    }
+
+    /// The estimated percent correct, if there is one. See set_prior().
+    pub fn prior(self) -> Option<u8> {
+        self.prior
+    }
+
+    /// Remember that the event was seen at the given time, in seconds since the Unix epoch
+    pub fn mark_seen(&mut self, now: u64) {
+        self.last_seen = Some(now);
+    }
+
+    /// When the event was last seen, if known. See mark_seen().
+    pub fn last_seen(self) -> Option<u64> {
+        self.last_seen
+    }
+
+    /// Set when the event was last seen, e.g. to what last_seen() returned in a previous session
+    pub fn set_last_seen(&mut self, last_seen: Option<u64>) {
+        self.last_seen = last_seen;
+    }
+
+    /// Whether the event has been seen, but not recently enough for refresh
+    pub fn is_stale(self, refresh: Refresh) -> bool {
+        self.seen > 0
+            && self
+                .last_seen
+                .is_some_and(|t| refresh.now.saturating_sub(t) > refresh.after)
     }
 
     pub fn seen(self) -> u32 {
@@ -61,13 +172,41 @@ impl PlayStats {
     pub fn correct(self) -> u32 {
         self.correct
     }
+
+    /// The number of most recent responses that were all correct
+    pub fn streak(self) -> u32 {
+        self.streak
+    }
+
+    pub fn is_retired(self) -> bool {
+        self.streak >= RETIRE_STREAK
+    }
+
+    /// The streak after the events in other happened after the ones in self
+    fn streak_then(self, other: Self) -> u32 {
+        if other.streak == other.seen {
+            self.streak + other.streak
+        } else {
+            other.streak
+        }
+    }
+
+    /// The fraction correct, with a confidence interval at the given z-score (e.g.
+    /// confidence::Z95). None if nothing has been seen yet.
+    pub fn accuracy(self, z: f64) -> Option<Interval> {
+        wilson(self.correct, self.seen, z)
+    }
 }
 
 impl std::ops::AddAssign for PlayStats {
     fn add_assign(&mut self, rhs: Self) {
+        let streak = self.streak_then(rhs);
         self.inc_by(rhs.correct(), true);
         #[allow(clippy::suspicious_op_assign_impl)]
         self.inc_by(rhs.seen() - rhs.correct(), false);
+        self.streak = streak;
+        self.last_seen = self.last_seen.max(rhs.last_seen);
+        self.prior = self.prior.or(rhs.prior);
     }
 }
 
@@ -82,13 +221,16 @@ impl std::ops::Add for PlayStats {
         new.inc_by(self.seen() - self.correct(), false);
         #[allow(clippy::suspicious_arithmetic_impl)]
         new.inc_by(other.seen() - other.correct(), false);
+        new.streak = self.streak_then(other);
+        new.last_seen = self.last_seen.max(other.last_seen);
+        new.prior = self.prior.or(other.prior);
         new
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::PlayStats;
+    use super::{PlayStats, Refresh, RETIRE_STREAK, SECS_PER_DAY};
     const COUNT_MANY: u32 = 10;
 
     #[test]
@@ -247,4 +389,96 @@ mod tests {
         assert_eq!(s3.seen(), 2 * COUNT_MANY);
         assert_eq!(s3.correct(), COUNT_MANY);
     }
+
+    #[test]
+    fn accuracy() {
+        assert!(PlayStats::new().accuracy(crate::confidence::Z95).is_none());
+        let mut s = PlayStats::new();
+        s.inc_by(23, true);
+        s.inc_by(2, false);
+        let acc = s.accuracy(crate::confidence::Z95).unwrap();
+        assert_eq!(acc.estimate, 0.92);
+        assert!(acc.contains(0.8) && !acc.contains(0.99));
+    }
+
+    #[test]
+    fn streak() {
+        let mut s = PlayStats::new();
+        for i in 1..RETIRE_STREAK {
+            s.inc(true);
+            assert_eq!(s.streak(), i);
+            assert!(!s.is_retired());
+        }
+        s.inc(true);
+        assert!(s.is_retired());
+        s.inc(false);
+        assert_eq!(s.streak(), 0);
+        assert!(!s.is_retired());
+        assert_eq!(s.seen(), RETIRE_STREAK + 1);
+    }
+
+    #[test]
+    fn add_streak() {
+        // the right hand side's events count as happening after the left's
+        let right = PlayStats::from_counts(3, 3, 3).unwrap();
+        let wrong = PlayStats::from_counts(3, 2, 1).unwrap();
+        assert_eq!((right + right).streak(), 6);
+        assert_eq!((right + wrong).streak(), 1);
+        assert_eq!((wrong + right).streak(), 4);
+        assert_eq!((right + PlayStats::new()).streak(), 3);
+        let mut s = wrong;
+        s += right;
+        assert_eq!(s, wrong + right);
+        assert!(PlayStats::from_counts(3, 2, 3).is_none());
+    }
+
+    #[test]
+    fn prior() {
+        let mut s = PlayStats::new();
+        s.set_prior(Some(100));
+        assert_eq!(s.weight(), 1.0 - 3.0 / 4.0);
+        s.set_prior(Some(0));
+        assert_eq!(s.weight(), 1.0);
+        s.set_prior(Some(200));
+        assert_eq!(s.prior(), Some(100));
+        // the more it's seen, the less the prior matters
+        s.inc_by(96, false);
+        assert!(s.weight() > 0.95);
+        assert_eq!((s.seen(), s.correct()), (96, 0));
+        assert_eq!((PlayStats::new() + s).prior(), Some(100));
+        s.set_prior(None);
+        assert_eq!(s.weight(), 1.0);
+    }
+
+    #[test]
+    fn stale() {
+        let refresh = Refresh::after_days(100 * SECS_PER_DAY, 30);
+        let mut s = PlayStats::from_counts(5, 5, 5).unwrap();
+        // never given the time
+        assert!(!s.is_stale(refresh));
+        s.mark_seen(70 * SECS_PER_DAY);
+        assert!(!s.is_stale(refresh));
+        s.mark_seen(70 * SECS_PER_DAY - 1);
+        assert!(s.is_stale(refresh));
+        assert_eq!(s.last_seen(), Some(70 * SECS_PER_DAY - 1));
+        // nothing to forget
+        let mut unseen = PlayStats::new();
+        unseen.mark_seen(0);
+        assert!(!unseen.is_stale(refresh));
+        // the latest time wins
+        let mut recent = PlayStats::new();
+        recent.mark_seen(99 * SECS_PER_DAY);
+        recent.inc(true);
+        assert!(!(s + recent).is_stale(refresh));
+        assert!(!(recent + s).is_stale(refresh));
+        s += recent;
+        assert_eq!(s.last_seen(), Some(99 * SECS_PER_DAY));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn deserialize_without_streak() {
+        let s: PlayStats = serde_json::from_str(r#"{"seen": 7, "correct": 6}"#).unwrap();
+        assert_eq!(s, PlayStats::from_counts(7, 6, 0).unwrap());
+    }
 }
@@ -0,0 +1,165 @@
+//! How long the player has practiced, per calendar day.
+//!
+//! Practice time is the sum of the player's response times: from when a hand (or card, or
+//! whatever is being drilled) is shown to when it's answered. Each answer counts for at most
+//! MAX_ANSWER_SECS, so walking away from an open trainer doesn't count as practice. A
+//! PracticeTimer measures the answers, and a PracticeLog adds them up by day.
+//!
+//! Days are named like "2026-03-14". The front end decides which day it is, as only it knows the
+//! player's time zone; date() gives the day in UTC for those that don't.
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// The most one answer adds to the practice time, in seconds
+pub const MAX_ANSWER_SECS: f64 = 60.0;
+
+/// Seconds practiced, by day
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct PracticeLog(BTreeMap<String, f64>);
+
+impl PracticeLog {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add an answer that took the given number of seconds to the given day, capped at
+    /// MAX_ANSWER_SECS. Nonsense times, like negative ones from a clock that went backward, add
+    /// nothing.
+    pub fn record(&mut self, day: &str, secs: f64) {
+        if secs.is_nan() || secs <= 0.0 {
+            return;
+        }
+        *self.0.entry(day.to_string()).or_insert(0.0) += secs.min(MAX_ANSWER_SECS);
+    }
+
+    /// Seconds practiced on the given day
+    pub fn on(&self, day: &str) -> f64 {
+        self.0.get(day).copied().unwrap_or(0.0)
+    }
+
+    /// Seconds practiced on every day together
+    pub fn total(&self) -> f64 {
+        self.0.values().sum()
+    }
+
+    /// Every day with any practice and how many seconds, oldest first
+    pub fn days(&self) -> impl Iterator<Item = (&str, f64)> {
+        self.0.iter().map(|(day, secs)| (day.as_str(), *secs))
+    }
+}
+
+/// Times the answer to whatever is being shown. Times are in seconds, from any clock that only
+/// goes forward.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct PracticeTimer {
+    shown: Option<f64>,
+}
+
+impl PracticeTimer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Something to answer was just shown
+    pub fn shown(&mut self, now: f64) {
+        self.shown = Some(now);
+    }
+
+    /// It was just answered: how long that took, if it was shown, capped at MAX_ANSWER_SECS.
+    /// The next answer is timed from now, as answering shows the next thing.
+    pub fn answered(&mut self, now: f64) -> Option<f64> {
+        let secs = self
+            .shown
+            .map(|shown| (now - shown).clamp(0.0, MAX_ANSWER_SECS));
+        self.shown = Some(now);
+        secs
+    }
+}
+
+/// The day the given time falls on in UTC, e.g. "2026-03-14", from seconds since the Unix epoch
+pub fn date(secs: u64) -> String {
+    // Howard Hinnant's civil_from_days()
+    let z = (secs / 86400) as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+/// A practice time, shown like "23 minutes" or "1 hour 5 minutes"
+pub struct PracticeTime(pub f64);
+
+impl fmt::Display for PracticeTime {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let minutes = (self.0 / 60.0) as u64;
+        let plural = |n: u64| if n == 1 { "" } else { "s" };
+        let (hours, minutes) = (minutes / 60, minutes % 60);
+        if hours == 0 {
+            write!(f, "{} minute{}", minutes, plural(minutes))
+        } else if minutes == 0 {
+            write!(f, "{} hour{}", hours, plural(hours))
+        } else {
+            write!(
+                f,
+                "{} hour{} {} minute{}",
+                hours,
+                plural(hours),
+                minutes,
+                plural(minutes)
+            )
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn log() {
+        let mut log = PracticeLog::new();
+        log.record("2026-03-14", 3.5);
+        log.record("2026-03-14", 600.0);
+        log.record("2026-03-14", -2.0);
+        log.record("2026-03-15", 10.0);
+        assert_eq!(log.on("2026-03-14"), 3.5 + MAX_ANSWER_SECS);
+        assert_eq!(log.on("2026-03-16"), 0.0);
+        assert_eq!(log.total(), 13.5 + MAX_ANSWER_SECS);
+        assert_eq!(
+            log.days().map(|(day, _)| day).collect::<Vec<_>>(),
+            vec!["2026-03-14", "2026-03-15"]
+        );
+    }
+
+    #[test]
+    fn timer() {
+        let mut t = PracticeTimer::new();
+        assert_eq!(t.answered(5.0), None);
+        assert_eq!(t.answered(7.5), Some(2.5));
+        assert_eq!(t.answered(1000.0), Some(MAX_ANSWER_SECS));
+        t.shown(2000.0);
+        assert_eq!(t.answered(2001.0), Some(1.0));
+    }
+
+    #[test]
+    fn dates() {
+        assert_eq!(date(0), "1970-01-01");
+        assert_eq!(date(951_782_400), "2000-02-29");
+        assert_eq!(date(1_773_532_799), "2026-03-14");
+        assert_eq!(PracticeTime(59.0).to_string(), "0 minutes");
+        assert_eq!(PracticeTime(23.0 * 60.0 + 30.0).to_string(), "23 minutes");
+        assert_eq!(PracticeTime(3600.0).to_string(), "1 hour");
+        assert_eq!(
+            PracticeTime(2.0 * 3600.0 + 60.0).to_string(),
+            "2 hours 1 minute"
+        );
+    }
+}
@@ -0,0 +1,308 @@
+//! The wire format for results the trainers upload, shared by the clients and any server that
+//! takes them (e.g. a leaderboard).
+//!
+//! Every upload is an Upload as JSON: a schema version, the client that sent it, and a body whose
+//! "kind" says what it holds. For example:
+//!
+//! ```text
+//! {"version":1,"client":"time-trial/0.1.0","kind":"time_trial","status":"completed",...}
+//! ```
+//!
+//! The format only changes in ways older readers can live with: new fields get defaults so older
+//! uploads still parse, and readers ignore fields they don't know so newer uploads still parse.
+//! Anything else (renaming or removing a field, changing what one means) needs a new
+//! SCHEMA_VERSION, and readers refuse uploads newer than the version they know. Cards and cells
+//! are sent as text ("Ac", "Hard 16 vs 10") rather than as this crate's types, so changing those
+//! types doesn't change the format.
+//!
+//! An upload can carry the player's anonymous ID (see crate::anonid), so a server can tell which
+//! uploads are from the same player. It's left out if they've opted out.
+use crate::anonid::AnonIdentity;
+use crate::count::AnySystem;
+use crate::deck::{Card, Suit};
+use crate::hand::Hand;
+use crate::playstats::PlayStats;
+use crate::table::{GameDesc, Table};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/// The version of the format this code writes, and the newest it reads
+pub const SCHEMA_VERSION: u32 = 1;
+
+#[derive(Debug, PartialEq, Error)]
+pub enum ProtoError {
+    /// The upload isn't valid JSON, or doesn't match the format
+    #[error("Bad upload: {0}")]
+    Json(String),
+    /// The upload is from a newer version of the format
+    #[error(
+        "Upload is version {0}, but only up to {} is understood",
+        SCHEMA_VERSION
+    )]
+    TooNew(u32),
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Upload {
+    /// The SCHEMA_VERSION the upload was written with
+    pub version: u32,
+    /// What sent the upload, e.g. "time-trial/0.1.0"
+    #[serde(default)]
+    pub client: String,
+    /// The player's AnonId, e.g. "3f2a9c1e-7b4d-4e8f-a0c2-5d6e7f8a9b0c", unless they opted out
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub anon_id: Option<String>,
+    #[serde(flatten)]
+    pub body: Body,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Body {
+    TimeTrial(TimeTrialResults),
+    Counting(CountingResults),
+    Stats(StatsUpload),
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum TrialStatus {
+    InProgress,
+    /// All of the trial's hands were played
+    Completed,
+    /// The player gave up early, so there are fewer hands than the trial has
+    Aborted,
+}
+
+/// A time trial
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct TimeTrialResults {
+    pub status: TrialStatus,
+    pub num_hands: u32,
+    /// How hands were chosen, e.g. "uniform"
+    pub pool: String,
+    /// The surrender rule hands were graded under (see Surrender::id()), as surrender cells are
+    /// graded differently under different rules
+    pub surrender: String,
+    /// Seconds spent paused, which aren't in the hands' times
+    #[serde(default)]
+    pub paused_secs: f64,
+    pub hands: Vec<HandResult>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct HandResult {
+    /// The player's cards, e.g. ["Tc", "6d"]
+    pub player: Vec<String>,
+    pub dealer: String,
+    /// The hand's cell, e.g. "Hard 16 vs 10"
+    pub cell: String,
+    pub correct: bool,
+    /// Seconds from the start of the trial to the answer, not counting time spent paused
+    pub secs: f64,
+}
+
+/// e.g. "Tc" for the ten of clubs. Unlike Card's Display, this is plain ASCII and won't change.
+fn card_text(card: Card) -> String {
+    let suit = match card.suit() {
+        Suit::Club => 'c',
+        Suit::Diamond => 'd',
+        Suit::Heart => 'h',
+        Suit::Spade => 's',
+    };
+    format!("{}{}", card.rank(), suit)
+}
+
+impl HandResult {
+    pub fn new(player: &Hand, dealer: Card, correct: bool, secs: f64) -> Self {
+        Self {
+            player: player.cards().map(|c| card_text(*c)).collect(),
+            dealer: card_text(dealer),
+            cell: GameDesc::from_hand(player, dealer).label(),
+            correct,
+            secs,
+        }
+    }
+}
+
+/// A counting drill
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct CountingResults {
+    pub num_decks: u8,
+    pub num_cards: u16,
+    pub cards_at_a_time: u8,
+    /// Seconds from the first card to the last
+    pub duration_secs: f64,
+    /// The running count after the last card
+    pub running_count: i16,
+    /// The count system, e.g. "hilo" (see count::AnySystem). Uploads from before there was a
+    /// choice are HiLo.
+    #[serde(default = "hilo")]
+    pub system: String,
+}
+
+fn hilo() -> String {
+    AnySystem::HiLo.to_string()
+}
+
+/// A player's stats on a strategy card
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct StatsUpload {
+    pub streak: u32,
+    /// Every cell the player has seen
+    pub cells: Vec<CellStats>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct CellStats {
+    /// e.g. "Hard 16 vs 10"
+    pub cell: String,
+    pub seen: u32,
+    pub correct: u32,
+    #[serde(default)]
+    pub streak: u32,
+}
+
+impl StatsUpload {
+    /// The given stats, in strategy card order
+    pub fn new(stats: &Table<PlayStats>, streak: u32) -> Self {
+        let mut cells: Vec<(&GameDesc, &PlayStats)> =
+            stats.iter().filter(|(_, s)| s.seen() > 0).collect();
+        cells.sort_by_key(|(desc, _)| **desc);
+        Self {
+            streak,
+            cells: cells
+                .into_iter()
+                .map(|(desc, s)| CellStats {
+                    cell: desc.label(),
+                    seen: s.seen(),
+                    correct: s.correct(),
+                    streak: s.streak(),
+                })
+                .collect(),
+        }
+    }
+}
+
+impl Upload {
+    /// An upload of the current SCHEMA_VERSION
+    pub fn new(client: &str, body: Body) -> Self {
+        Self {
+            version: SCHEMA_VERSION,
+            client: client.to_string(),
+            anon_id: None,
+            body,
+        }
+    }
+
+    /// The upload with the player's anonymous ID, or without one if they opted out
+    pub fn with_identity(mut self, identity: &AnonIdentity) -> Self {
+        self.anon_id = identity.id().map(|id| id.to_string());
+        self
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap()
+    }
+
+    /// Parse an upload of any version up to SCHEMA_VERSION
+    pub fn from_json(s: &str) -> Result<Self, ProtoError> {
+        let v: serde_json::Value =
+            serde_json::from_str(s).map_err(|e| ProtoError::Json(e.to_string()))?;
+        // checked first, as a newer upload may not match this version's format at all
+        match v.get("version").and_then(|v| v.as_u64()) {
+            Some(version) if version > u64::from(SCHEMA_VERSION) => {
+                return Err(ProtoError::TooNew(version as u32))
+            }
+            Some(_) => {}
+            None => return Err(ProtoError::Json("no version".to_string())),
+        }
+        serde_json::from_value(v).map_err(|e| ProtoError::Json(e.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::deck::{Rank, Suit};
+
+    fn trial() -> Upload {
+        let hand = Hand::new(&[
+            Card::new(Rank::RT, Suit::Club),
+            Card::new(Rank::R6, Suit::Diamond),
+        ]);
+        let dealer = Card::new(Rank::RT, Suit::Spade);
+        Upload::new(
+            "test/1",
+            Body::TimeTrial(TimeTrialResults {
+                status: TrialStatus::Completed,
+                num_hands: 1,
+                pool: "uniform".to_string(),
+                surrender: "no".to_string(),
+                paused_secs: 0.0,
+                hands: vec![HandResult::new(&hand, dealer, true, 1.5)],
+            }),
+        )
+    }
+
+    #[test]
+    fn round_trip() {
+        let up = trial();
+        assert_eq!(Upload::from_json(&up.to_json()), Ok(up));
+        let mut stats = Table::new(vec![PlayStats::new(); 360]).unwrap();
+        stats.iter_mut().next().unwrap().1.inc(true);
+        let up = Upload::new("test/1", Body::Stats(StatsUpload::new(&stats, 1)));
+        assert_eq!(Upload::from_json(&up.to_json()), Ok(up));
+    }
+
+    #[test]
+    fn anon_id() {
+        let ident: AnonIdentity =
+            serde_json::from_str(r#"{"id":"3f2a9c1e-7b4d-4e8f-a0c2-5d6e7f8a9b0c"}"#).unwrap();
+        let up = trial().with_identity(&ident);
+        assert!(up
+            .to_json()
+            .starts_with(r#"{"version":1,"client":"test/1","anon_id":"3f2a9c1e-"#));
+        assert_eq!(Upload::from_json(&up.to_json()), Ok(up));
+        let up = trial().with_identity(&AnonIdentity::opted_out());
+        assert_eq!(up, trial());
+        assert!(!up.to_json().contains("anon_id"));
+    }
+
+    /// Uploads written by version 1. These must always parse the same.
+    #[test]
+    fn version_1() {
+        let json = r#"{"version":1,"client":"test/1","kind":"time_trial","status":"completed","num_hands":1,"pool":"uniform","surrender":"no","paused_secs":0.0,"hands":[{"player":["Tc","6d"],"dealer":"Ts","cell":"Hard 16 vs 10","correct":true,"secs":1.5}]}"#;
+        assert_eq!(trial().to_json(), json);
+        assert_eq!(Upload::from_json(json), Ok(trial()));
+        let json = r#"{"version":1,"client":"","kind":"counting","num_decks":1,"num_cards":52,"cards_at_a_time":2,"duration_secs":30.5,"running_count":0}"#;
+        match Upload::from_json(json).unwrap().body {
+            Body::Counting(c) => assert_eq!((c.num_cards, c.system.as_str()), (52, "hilo")),
+            b => panic!("Wrong body {:?}", b),
+        }
+        let json = r#"{"version":1,"kind":"stats","streak":3,"cells":[{"cell":"8,8 vs A","seen":2,"correct":1}]}"#;
+        let up = Upload::from_json(json).unwrap();
+        assert_eq!(up.client, "");
+        match up.body {
+            Body::Stats(s) => assert_eq!(s.cells[0].streak, 0),
+            b => panic!("Wrong body {:?}", b),
+        }
+    }
+
+    #[test]
+    fn forward_compatible() {
+        // fields added later, at the top and in a body, are ignored
+        let json = r#"{"version":1,"client":"new/9","signature":"abc","kind":"counting","num_decks":1,"num_cards":52,"cards_at_a_time":1,"duration_secs":3.0,"running_count":2,"true_count":2.0}"#;
+        assert!(Upload::from_json(json).is_ok());
+        let json = r#"{"version":2,"kind":"something new"}"#;
+        assert_eq!(Upload::from_json(json), Err(ProtoError::TooNew(2)));
+        assert!(matches!(
+            Upload::from_json(r#"{"kind":"stats","streak":0,"cells":[]}"#),
+            Err(ProtoError::Json(_))
+        ));
+        assert!(matches!(
+            Upload::from_json(r#"{"version":1,"kind":"nope"}"#),
+            Err(ProtoError::Json(_))
+        ));
+    }
+}
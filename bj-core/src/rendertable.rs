@@ -1,10 +1,76 @@
 use crate::basicstrategy::{rules, BasicStrategy};
+use crate::hand::HandType;
+use crate::notes::Notes;
+use crate::playstats::PlayStats;
 use crate::resp::Resp;
+use crate::table::{GameDesc, Table};
 use std::io::{self, Write};
 
 pub struct HTMLTableRendererOpts {
     pub incl_bs_rules: bool,
     pub cell_onclick_cb: Option<String>,
+    /// The player's notes, shown as tooltips on the cells that have one
+    pub notes: Option<Notes>,
+    /// Which cells to show, e.g. to hide the ones a player has memorized. All are shown if None.
+    pub visibility: Option<Table<CellVisibility>>,
+}
+
+/// How a cell of the card is rendered
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CellVisibility {
+    Shown,
+    /// Blanked out. Clicking it still calls cell_onclick_cb, e.g. to reveal it.
+    Hidden,
+    /// Shown, but marked as having been hidden and revealed
+    Revealed,
+}
+
+/// The cell that cell_onclick_cb is called with the given arguments for: the lowercase table name
+/// ("hard", "soft", or "pair"), the player's hand value (or the value of each card, for pairs),
+/// and the dealer's card's value
+pub fn onclick_cell_desc(table: &str, player: u8, dealer: u8) -> Option<GameDesc> {
+    let desc = match table {
+        "hard" if (5..=21).contains(&player) => GameDesc::new(HandType::Hard, player, dealer),
+        "soft" if (13..=21).contains(&player) => GameDesc::new(HandType::Soft, player, dealer),
+        // pair rows are labeled by card, but keyed by hand value with aces being 22
+        "pair" if (2..=11).contains(&player) => GameDesc::new(HandType::Pair, player * 2, dealer),
+        _ => return None,
+    };
+    if (2..=11).contains(&dealer) {
+        Some(desc)
+    } else {
+        None
+    }
+}
+
+/// Escape text so it can be used in HTML, including inside a quoted attribute
+fn html_escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// The blackjack payout, with what it costs the player if it's worse than 3:2
+fn payout_label(rules: &rules::Rules) -> String {
+    let cost = rules.payout_cost();
+    if cost > 0.0 {
+        format!(
+            "{} (costs {:.2}% of each bet vs 3:2)",
+            rules.blackjack_pays,
+            cost * 100.0
+        )
+    } else {
+        rules.blackjack_pays.to_string()
+    }
 }
 
 pub struct HTMLTableRenderer;
@@ -31,6 +97,10 @@ impl HTMLTableRenderer {
 .double    {{ background-color: #6666ff; }}
 .split     {{ background-color: #00ff00; }}
 .surrender {{ background-color: #dddddd; }}
+.noted     {{ text-decoration: underline dotted; font-weight: bold; }}
+.hidden    {{ width: 1.5em; height: 1.5em; background-color: #eeeeee; cursor: pointer; }}
+.revealed  {{ outline: 2px dashed #333; outline-offset: -2px; }}
+.unlearned {{ width: 1.5em; height: 1.5em; text-align: center; background-color: #eeeeee; color: #999; }}
 </style>
 "
         )?;
@@ -43,15 +113,25 @@ impl HTMLTableRenderer {
 <table>
 <tr><td>Decks</td><td>{decks}</td></tr>
 <tr><td>Soft 17</td><td>{soft_17}</td></tr>
+<tr><td>Blackjack pays</td><td>{pays}</td></tr>
 <tr><td>Double after split</td><td>{das}</td></tr>
+<tr><td>Max splits</td><td>{max_splits}</td></tr>
+<tr><td>Resplit aces</td><td>{rsa}</td></tr>
+<tr><td>Hit split aces</td><td>{hsa}</td></tr>
 <tr><td>Surrender</td><td>{sur}</td></tr>
 <tr><td>Dealer peek</td><td>{peek_bj}</td></tr>
+<tr><td>Unpeeked BJ takes</td><td>{no_peek_loss}</td></tr>
 </table>
     ",
                     decks = rules.decks,
                     soft_17 = rules.hit_soft_17,
+                    pays = payout_label(rules),
                     das = rules.double_after_split,
+                    max_splits = rules.max_splits,
+                    rsa = rules.resplit_aces,
+                    hsa = rules.hit_split_aces,
                     peek_bj = rules.peek_bj,
+                    no_peek_loss = rules.no_peek_loss,
                     sur = rules.surrender,
                 )?;
             } else {
@@ -77,7 +157,7 @@ Source: <a id=strat_source href='https://wizardofodds.com/games/blackjack/strate
 
     fn subtable(
         mut fd: impl Write,
-        v: Vec<&Resp>,
+        v: Vec<&Option<Resp>>,
         table_label: &str,
         opts: &HTMLTableRendererOpts,
     ) -> io::Result<()> {
@@ -109,14 +189,40 @@ Source: <a id=strat_source href='https://wizardofodds.com/games/blackjack/strate
                 writeln!(fd, "<th>{}</th>", s)?;
             }
             let (class, label) = match resp {
-                Resp::Hit => ("hit", "H"),
-                Resp::Stand => ("stand", "S"),
-                Resp::DoubleElseHit => ("double", "Dh"),
-                Resp::DoubleElseStand => ("double", "Ds"),
-                Resp::Split => ("split", "P"),
-                Resp::SurrenderElseHit => ("surrender", "Rh"),
-                Resp::SurrenderElseStand => ("surrender", "Rs"),
-                Resp::SurrenderElseSplit => ("surrender", "Rp"),
+                Some(Resp::Hit) => ("hit", "H"),
+                Some(Resp::Stand) => ("stand", "S"),
+                Some(Resp::DoubleElseHit) => ("double", "Dh"),
+                Some(Resp::DoubleElseStand) => ("double", "Ds"),
+                Some(Resp::Split) => ("split", "P"),
+                Some(Resp::SurrenderElseHit) => ("surrender", "Rh"),
+                Some(Resp::SurrenderElseStand) => ("surrender", "Rs"),
+                Some(Resp::SurrenderElseSplit) => ("surrender", "Rp"),
+                None => ("unlearned", "-"),
+            };
+            let desc = onclick_cell_desc(&table_label.to_lowercase(), player_hand_val, dealer_val)
+                .expect("Impossible cell");
+            let visibility = opts
+                .visibility
+                .as_ref()
+                .and_then(|v| v.get_desc(desc))
+                .unwrap_or(CellVisibility::Shown);
+            let note = opts.notes.as_ref().and_then(|notes| notes.get_desc(desc));
+            let (class, title) = match note {
+                Some(note) => (
+                    format!("'{} noted'", class),
+                    format!(" title='{}'", html_escape(note)),
+                ),
+                None => (class.to_string(), "".to_string()),
+            };
+            // a hidden cell gives nothing away, not even the player's note on it
+            let (class, title, label) = match visibility {
+                CellVisibility::Shown => (class, title, label),
+                CellVisibility::Hidden => ("hidden".to_string(), "".to_string(), "?"),
+                CellVisibility::Revealed => (
+                    format!("'{} revealed'", class.trim_matches('\'')),
+                    title,
+                    label,
+                ),
             };
             let onclick_fn = match &opts.cell_onclick_cb {
                 Some(fn_name) => format!(
@@ -130,8 +236,8 @@ Source: <a id=strat_source href='https://wizardofodds.com/games/blackjack/strate
             };
             writeln!(
                 fd,
-                "<td class={} onclick='{}'>{}</td>",
-                class, onclick_fn, label,
+                "<td class={}{} onclick='{}'>{}</td>",
+                class, title, onclick_fn, label,
             )?;
             dealer_val += 1;
             if i % 10 == 9 {
@@ -149,9 +255,9 @@ Source: <a id=strat_source href='https://wizardofodds.com/games/blackjack/strate
         strat: &BasicStrategy,
         opts: HTMLTableRendererOpts,
     ) -> io::Result<()> {
-        let BasicStrategy { rules, table } = strat;
+        let table = strat.partial_table();
         let (hards, softs, pairs) = table.as_values_sorted();
-        Self::header(&mut fd, rules, &opts)?;
+        Self::header(&mut fd, &strat.rules, &opts)?;
         Self::subtable(&mut fd, hards, "Hard", &opts)?;
         Self::subtable(&mut fd, softs, "Soft", &opts)?;
         Self::subtable(&mut fd, pairs, "Pair", &opts)?;
@@ -170,36 +276,52 @@ impl TXTTableRenderer {
                 "
 # Decks:              {decks}
 # Soft 17:            {soft_17}
+# Blackjack pays:     {pays}
 # Double after split: {das}
+# Max splits:         {max_splits}
+# Resplit aces:       {rsa}
+# Hit split aces:     {hsa}
 # Surrender:          {sur}
 # Dealer peek:        {peek_bj}
+# Unpeeked BJ takes:  {no_peek_loss}
 # Source: https://wizardofodds.com/games/blackjack/strategy/calculator/
     ",
                 decks = rules.decks,
                 soft_17 = rules.hit_soft_17,
+                pays = payout_label(rules),
                 das = rules.double_after_split,
+                max_splits = rules.max_splits,
+                rsa = rules.resplit_aces,
+                hsa = rules.hit_split_aces,
                 sur = rules.surrender,
                 peek_bj = rules.peek_bj,
+                no_peek_loss = rules.no_peek_loss,
             )
         } else {
             writeln!(fd, "# Custom Basic Strategy")
         }
     }
 
-    fn subtable(mut fd: impl Write, v: Vec<&Resp>, label: &str) -> io::Result<()> {
+    /// The two letter code for resp used in text strategy cards, with resp::NOT_LEARNED for a cell
+    /// of a partial card
+    fn code(resp: &Option<Resp>) -> &'static str {
+        match resp {
+            Some(Resp::Hit) => "H ",
+            Some(Resp::Stand) => "S ",
+            Some(Resp::DoubleElseHit) => "Dh",
+            Some(Resp::DoubleElseStand) => "Ds",
+            Some(Resp::Split) => "P ",
+            Some(Resp::SurrenderElseHit) => "Rh",
+            Some(Resp::SurrenderElseStand) => "Rs",
+            Some(Resp::SurrenderElseSplit) => "Rp",
+            None => "- ",
+        }
+    }
+
+    fn subtable(mut fd: impl Write, v: Vec<&Option<Resp>>, label: &str) -> io::Result<()> {
         writeln!(fd, "# {} table", label)?;
         for (i, resp) in v.iter().enumerate() {
-            let label = match resp {
-                Resp::Hit => "H ",
-                Resp::Stand => "S ",
-                Resp::DoubleElseHit => "Dh",
-                Resp::DoubleElseStand => "Ds",
-                Resp::Split => "P ",
-                Resp::SurrenderElseHit => "Rh",
-                Resp::SurrenderElseStand => "Rs",
-                Resp::SurrenderElseSplit => "Rp",
-            };
-            write!(fd, "{} ", label)?;
+            write!(fd, "{} ", Self::code(resp))?;
             if i % 10 == 9 {
                 writeln!(fd)?;
             }
@@ -208,9 +330,9 @@ impl TXTTableRenderer {
     }
 
     pub fn render(mut fd: impl Write, strat: &BasicStrategy) -> io::Result<()> {
-        let BasicStrategy { rules, table } = strat;
+        let table = strat.partial_table();
         let (hards, softs, pairs) = table.as_values_sorted();
-        Self::header(&mut fd, &rules)?;
+        Self::header(&mut fd, &strat.rules)?;
         Self::subtable(&mut fd, hards, "Hard")?;
         writeln!(fd)?;
         Self::subtable(&mut fd, softs, "Soft")?;
@@ -219,4 +341,565 @@ impl TXTTableRenderer {
         writeln!(fd)?;
         Ok(())
     }
+
+    /// Render just the given cells, e.g. from table::parse_cells(), as a grid with a row for each
+    /// player hand and a column for each upcard, in the order they first appear. Cells missing
+    /// from the grid are left blank.
+    pub fn render_cells(
+        mut fd: impl Write,
+        strat: &BasicStrategy,
+        cells: &[GameDesc],
+    ) -> io::Result<()> {
+        let table = strat.partial_table();
+        let mut rows: Vec<GameDesc> = vec![];
+        let mut dealers: Vec<GameDesc> = vec![];
+        for desc in cells {
+            if !rows.iter().any(|r| r.player_label() == desc.player_label()) {
+                rows.push(*desc);
+            }
+            if !dealers.iter().any(|d| d.dealer == desc.dealer) {
+                dealers.push(*desc);
+            }
+        }
+        let width = rows
+            .iter()
+            .map(|r| r.player_label().len())
+            .max()
+            .unwrap_or(0);
+        let mut header = format!("{:width$}", "", width = width);
+        for d in &dealers {
+            header += &format!(" {:2}", d.dealer_label());
+        }
+        writeln!(fd, "{}", header.trim_end())?;
+        for row in &rows {
+            let mut line = format!("{:width$}", row.player_label(), width = width);
+            for d in &dealers {
+                let desc = GameDesc {
+                    dealer: d.dealer,
+                    ..*row
+                };
+                let code = match table.get_desc(desc) {
+                    Some(resp) if cells.contains(&desc) => Self::code(&resp),
+                    _ => "  ",
+                };
+                line += &format!(" {}", code);
+            }
+            writeln!(fd, "{}", line.trim_end())?;
+        }
+        Ok(())
+    }
+}
+
+/// The strategy card as plain text to read or paste anywhere monospaced: the rules, each table as
+/// an aligned grid with the player's hands down the side and the upcards across the top, and a
+/// legend of the codes used. Unlike TXTTableRenderer's output, this isn't meant to be parsed back.
+pub struct TextTableRenderer;
+
+impl TextTableRenderer {
+    fn header(mut fd: impl Write, bs_rules: &Option<rules::Rules>) -> io::Result<()> {
+        let rules = match bs_rules {
+            Some(rules) => rules,
+            None => return writeln!(fd, "Custom Basic Strategy"),
+        };
+        writeln!(fd, "Basic Strategy")?;
+        let lines = [
+            ("Decks", rules.decks.to_string()),
+            ("Soft 17", rules.hit_soft_17.to_string()),
+            ("Blackjack pays", payout_label(rules)),
+            ("Double after split", rules.double_after_split.to_string()),
+            ("Max splits", rules.max_splits.to_string()),
+            ("Resplit aces", rules.resplit_aces.to_string()),
+            ("Hit split aces", rules.hit_split_aces.to_string()),
+            ("Surrender", rules.surrender.to_string()),
+            ("Dealer peek", rules.peek_bj.to_string()),
+            ("Unpeeked BJ takes", rules.no_peek_loss.to_string()),
+        ];
+        for (name, value) in lines.iter() {
+            writeln!(fd, "{:20} {}", name, value)?;
+        }
+        Ok(())
+    }
+
+    /// The rows of one table, e.g. 5 through 21 for hard hands
+    fn rows(hand: HandType) -> Vec<GameDesc> {
+        let players: Vec<u8> = match hand {
+            HandType::Hard => (5..=21).collect(),
+            HandType::Soft => (13..=21).collect(),
+            HandType::Pair => (2..=11).map(|v| v * 2).collect(),
+        };
+        players
+            .into_iter()
+            .map(|p| GameDesc::new(hand, p, 2))
+            .collect()
+    }
+
+    fn subtable(mut fd: impl Write, table: &Table<Option<Resp>>, hand: HandType) -> io::Result<()> {
+        let name = match hand {
+            HandType::Hard => "Hard",
+            HandType::Soft => "Soft",
+            HandType::Pair => "Pair",
+        };
+        let mut header = name.to_string();
+        for dealer in 2..=11 {
+            header += &format!(" {:2}", GameDesc::new(hand, 0, dealer).dealer_label());
+        }
+        writeln!(fd, "{}", header.trim_end())?;
+        for row in Self::rows(hand) {
+            let label = match hand {
+                HandType::Pair => row.player_label(),
+                _ => row.player.to_string(),
+            };
+            let mut line = format!("{:4}", label);
+            for dealer in 2..=11 {
+                let desc = GameDesc { dealer, ..row };
+                let code = match table.get_desc(desc) {
+                    Some(Some(resp)) => resp.code(),
+                    Some(None) => "-",
+                    None => "",
+                };
+                line += &format!(" {:2}", code);
+            }
+            writeln!(fd, "{}", line.trim_end())?;
+        }
+        Ok(())
+    }
+
+    /// What each code on the card means, for the codes it uses
+    fn legend(mut fd: impl Write, table: &Table<Option<Resp>>) -> io::Result<()> {
+        let all = [
+            (Resp::Hit, "Hit"),
+            (Resp::Stand, "Stand"),
+            (Resp::DoubleElseHit, "Double if allowed, else hit"),
+            (Resp::DoubleElseStand, "Double if allowed, else stand"),
+            (Resp::Split, "Split"),
+            (Resp::SurrenderElseHit, "Surrender if allowed, else hit"),
+            (Resp::SurrenderElseStand, "Surrender if allowed, else stand"),
+            (Resp::SurrenderElseSplit, "Surrender if allowed, else split"),
+        ];
+        for (resp, meaning) in all.iter() {
+            if table.values().any(|r| *r == Some(*resp)) {
+                writeln!(fd, "{:2} {}", resp.code(), meaning)?;
+            }
+        }
+        if table.values().any(Option::is_none) {
+            writeln!(fd, "{:2} Not learned yet", "-")?;
+        }
+        Ok(())
+    }
+
+    pub fn render(mut fd: impl Write, strat: &BasicStrategy) -> io::Result<()> {
+        let table = strat.partial_table();
+        Self::header(&mut fd, &strat.rules)?;
+        for hand in [HandType::Hard, HandType::Soft, HandType::Pair].iter() {
+            writeln!(fd)?;
+            Self::subtable(&mut fd, &table, *hand)?;
+        }
+        writeln!(fd)?;
+        Self::legend(&mut fd, &table)?;
+        Ok(())
+    }
+}
+
+/// Renders a strategy card, or the player's accuracy on each of its cells, as a standalone SVG
+/// image for sharing. The layout is the same as HTMLTableRenderer's: hard, soft, and pair tables,
+/// one row per player hand and one column per dealer upcard.
+pub struct SVGTableRenderer;
+
+/// Size of a cell, in pixels
+const SVG_CELL: u32 = 28;
+/// Width of the column of player hand labels
+const SVG_LABEL_W: u32 = 36;
+/// Height of a table's title
+const SVG_TITLE_H: u32 = 28;
+
+impl SVGTableRenderer {
+    /// The fill color and label of a cell of a strategy card, matching HTMLTableRenderer's
+    fn resp_cell(resp: &Option<Resp>) -> (String, String) {
+        let (fill, label) = match resp {
+            Some(Resp::Hit) => ("#ff3333", "H"),
+            Some(Resp::Stand) => ("#ffff00", "S"),
+            Some(Resp::DoubleElseHit) => ("#6666ff", "Dh"),
+            Some(Resp::DoubleElseStand) => ("#6666ff", "Ds"),
+            Some(Resp::Split) => ("#00ff00", "P"),
+            Some(Resp::SurrenderElseHit) => ("#dddddd", "Rh"),
+            Some(Resp::SurrenderElseStand) => ("#dddddd", "Rs"),
+            Some(Resp::SurrenderElseSplit) => ("#dddddd", "Rp"),
+            None => ("#eeeeee", "-"),
+        };
+        (fill.to_string(), label.to_string())
+    }
+
+    /// The fill color and label of a cell of the accuracy heatmap: red at 0% correct through yellow
+    /// to green at 100%, and gray with no label if never seen
+    fn stats_cell(stats: &PlayStats) -> (String, String) {
+        if stats.seen() == 0 {
+            return ("#eeeeee".to_string(), String::new());
+        }
+        let frac = f64::from(stats.correct()) / f64::from(stats.seen());
+        let (red, green) = if frac < 0.5 {
+            (255.0, 510.0 * frac)
+        } else {
+            (510.0 * (1.0 - frac), 255.0)
+        };
+        (
+            format!("#{:02x}{:02x}33", red.round() as u8, green.round() as u8),
+            format!("{:.0}", frac * 100.0),
+        )
+    }
+
+    /// Height of a table with the given number of rows, including its title and header row
+    fn subtable_height(rows: usize) -> u32 {
+        SVG_TITLE_H + SVG_CELL * (rows as u32 + 1)
+    }
+
+    fn subtable<T>(
+        mut fd: impl Write,
+        v: Vec<&T>,
+        table_label: &str,
+        top: u32,
+        cell: impl Fn(&T) -> (String, String),
+    ) -> io::Result<()> {
+        let first_row = match table_label {
+            "Hard" => 5,
+            "Soft" => 13,
+            "Pair" => 2,
+            _ => unreachable!("Impossible table_label"),
+        };
+        let text = |fd: &mut dyn Write, x: u32, y: u32, s: &str| {
+            writeln!(
+                fd,
+                "<text x='{}' y='{}' text-anchor='middle' dominant-baseline='central'>{}</text>",
+                x, y, s
+            )
+        };
+        writeln!(
+            fd,
+            "<text x='0' y='{}' font-size='18' font-weight='bold'>{}</text>",
+            top + SVG_TITLE_H - 8,
+            table_label
+        )?;
+        let header_y = top + SVG_TITLE_H;
+        for dealer in 2..=11 {
+            let s = if dealer == 11 {
+                "A".to_string()
+            } else {
+                dealer.to_string()
+            };
+            let x = SVG_LABEL_W + (dealer - 2) * SVG_CELL + SVG_CELL / 2;
+            text(&mut fd, x, header_y + SVG_CELL / 2, &s)?;
+        }
+        for (i, item) in v.iter().enumerate() {
+            let (row, col) = ((i / 10) as u32, (i % 10) as u32);
+            let y = header_y + SVG_CELL * (row + 1);
+            if col == 0 {
+                let player = first_row + row;
+                let s = if player == 11 && table_label == "Pair" {
+                    "A".to_string()
+                } else {
+                    player.to_string()
+                };
+                text(&mut fd, SVG_LABEL_W / 2, y + SVG_CELL / 2, &s)?;
+            }
+            let x = SVG_LABEL_W + col * SVG_CELL;
+            let (fill, label) = cell(item);
+            writeln!(
+                fd,
+                "<rect x='{}' y='{}' width='{}' height='{}' fill='{}' stroke='#ffffff'/>",
+                x, y, SVG_CELL, SVG_CELL, fill
+            )?;
+            text(&mut fd, x + SVG_CELL / 2, y + SVG_CELL / 2, &label)?;
+        }
+        Ok(())
+    }
+
+    fn render_tables<T>(
+        mut fd: impl Write,
+        title: &str,
+        table: &Table<T>,
+        cell: impl Fn(&T) -> (String, String),
+    ) -> io::Result<()>
+    where
+        T: Copy + PartialEq,
+    {
+        let (hards, softs, pairs) = table.as_values_sorted();
+        let tops = [
+            SVG_TITLE_H,
+            SVG_TITLE_H + Self::subtable_height(hards.len() / 10),
+            SVG_TITLE_H
+                + Self::subtable_height(hards.len() / 10)
+                + Self::subtable_height(softs.len() / 10),
+        ];
+        let height = tops[2] + Self::subtable_height(pairs.len() / 10);
+        let width = SVG_LABEL_W + 10 * SVG_CELL;
+        writeln!(
+            fd,
+            "<svg xmlns='http://www.w3.org/2000/svg' width='{w}' height='{h}' viewBox='0 0 {w} {h}' \
+            font-family='sans-serif' font-size='12' fill='#333333'>",
+            w = width,
+            h = height,
+        )?;
+        writeln!(
+            fd,
+            "<rect width='100%' height='100%' fill='#ffffff'/>\n\
+            <text x='0' y='{}' font-size='20' font-weight='bold'>{}</text>",
+            SVG_TITLE_H - 8,
+            html_escape(title)
+        )?;
+        Self::subtable(&mut fd, hards, "Hard", tops[0], &cell)?;
+        Self::subtable(&mut fd, softs, "Soft", tops[1], &cell)?;
+        Self::subtable(&mut fd, pairs, "Pair", tops[2], &cell)?;
+        writeln!(fd, "</svg>")
+    }
+
+    /// Render the strategy card, titled with a summary of its rules if it has them
+    pub fn render(fd: impl Write, strat: &BasicStrategy) -> io::Result<()> {
+        let title = match &strat.rules {
+            Some(rules) => format!(
+                "Basic Strategy (Decks: {}, Soft 17: {}, Surrender: {})",
+                rules.decks, rules.hit_soft_17, rules.surrender
+            ),
+            None => "Custom Basic Strategy".to_string(),
+        };
+        Self::render_tables(fd, &title, &strat.partial_table(), Self::resp_cell)
+    }
+
+    /// Render the player's accuracy on each cell as a heatmap, with the percent correct in each
+    /// cell they've seen
+    pub fn render_heatmap(fd: impl Write, stats: &Table<PlayStats>) -> io::Result<()> {
+        Self::render_tables(fd, "Accuracy", stats, Self::stats_cell)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::deck::{Card, Rank, Suit};
+    use crate::hand::Hand;
+    use crate::resp::resps_from_buf;
+    use crate::table::Table;
+
+    const T1: &[u8] = include_bytes!("../../t1.txt");
+
+    #[test]
+    fn note_tooltips() {
+        let card = |rank| Card::new(rank, Suit::Club);
+        let mut notes = Notes::new();
+        notes.set(
+            &Hand::new(&[card(Rank::RA), card(Rank::RA)]),
+            card(Rank::RA),
+            "<always> split 'em",
+        );
+        let bs = BasicStrategy::new(None, Table::new(resps_from_buf(T1).unwrap()).unwrap());
+        let mut fd = vec![];
+        let opts = HTMLTableRendererOpts {
+            incl_bs_rules: false,
+            cell_onclick_cb: None,
+            notes: Some(notes),
+            visibility: None,
+        };
+        HTMLTableRenderer::render(&mut fd, &bs, opts).unwrap();
+        let html = String::from_utf8(fd).unwrap();
+        assert_eq!(html.matches(" title=").count(), 1);
+        // the last cell of the pair table is AA vs A
+        let last_cell = html.rsplit("<td ").next().unwrap();
+        assert!(last_cell.starts_with(
+            "class='split noted' title='&lt;always&gt; split &#39;em' onclick=''>P</td>"
+        ));
+    }
+
+    #[test]
+    fn hidden_cells() {
+        let bs = BasicStrategy::new(None, Table::new(resps_from_buf(T1).unwrap()).unwrap());
+        let mut vis = Table::new(vec![CellVisibility::Shown; 360]).unwrap();
+        for (desc, v) in vis.iter_mut() {
+            if desc.hand == HandType::Pair {
+                *v = CellVisibility::Hidden;
+            }
+        }
+        let aces = GameDesc::new(HandType::Pair, 22, 11);
+        for (_, v) in vis.iter_mut().filter(|(d, _)| **d == aces) {
+            *v = CellVisibility::Revealed;
+        }
+        let mut fd = vec![];
+        let opts = HTMLTableRendererOpts {
+            incl_bs_rules: false,
+            cell_onclick_cb: Some("reveal".to_string()),
+            notes: None,
+            visibility: Some(vis),
+        };
+        HTMLTableRenderer::render(&mut fd, &bs, opts).unwrap();
+        let html = String::from_utf8(fd).unwrap();
+        assert_eq!(html.matches("<td class=hidden ").count(), 99);
+        assert!(html.contains("<td class=hidden onclick='reveal(\"pair\", 2, 2)'>?</td>"));
+        let last_cell = html.rsplit("<td ").next().unwrap();
+        assert!(
+            last_cell.starts_with("class='split revealed' onclick='reveal(\"pair\", 11, 11)'>P")
+        );
+        assert_eq!(onclick_cell_desc("pair", 11, 11), Some(aces));
+        assert_eq!(onclick_cell_desc("soft", 12, 11), None);
+    }
+
+    #[test]
+    fn txt_cells() {
+        let bs = BasicStrategy::new(None, Table::new(resps_from_buf(T1).unwrap()).unwrap());
+        let cells = crate::table::parse_cells(&["12-13", "8,8"], &["2-4", "A"]).unwrap();
+        let mut fd = vec![];
+        TXTTableRenderer::render_cells(&mut fd, &bs, &cells).unwrap();
+        assert_eq!(
+            String::from_utf8(fd).unwrap(),
+            "        2  3  4  A\n\
+             Hard 12 H  H  S  H\n\
+             Hard 13 S  S  S  H\n\
+             8,8     P  P  P  P\n"
+        );
+    }
+
+    #[test]
+    fn text() {
+        let bs = BasicStrategy::new(None, Table::new(resps_from_buf(T1).unwrap()).unwrap());
+        let mut fd = vec![];
+        TextTableRenderer::render(&mut fd, &bs).unwrap();
+        let text = String::from_utf8(fd).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines[0], "Custom Basic Strategy");
+        assert_eq!(lines[2], "Hard 2  3  4  5  6  7  8  9  10 A");
+        assert!(lines.contains(&"16   S  S  S  S  S  H  H  H  H  H"));
+        assert!(lines.contains(&"A,A  P  P  P  P  P  P  P  P  P  P"));
+        // every row of every table, plus a header each
+        let rows = lines
+            .iter()
+            .filter(|l| l.split_whitespace().count() == 11)
+            .count();
+        assert_eq!(rows, 36 + 3);
+        assert!(lines.contains(&"Dh Double if allowed, else hit"));
+        // t1 has no surrender
+        assert!(!text.contains("Surrender"));
+    }
+
+    #[test]
+    fn svg() {
+        let table = Table::new(resps_from_buf(T1).unwrap()).unwrap();
+        let bs = BasicStrategy::new(None, table);
+        let mut fd = vec![];
+        SVGTableRenderer::render(&mut fd, &bs).unwrap();
+        let svg = String::from_utf8(fd).unwrap();
+        assert!(svg.starts_with("<svg ") && svg.trim_end().ends_with("</svg>"));
+        // a background plus one per cell
+        assert_eq!(svg.matches("<rect ").count(), 1 + 360);
+        assert!(svg.contains("Custom Basic Strategy"));
+        let mut stats = Table::new(vec![PlayStats::new(); 360]).unwrap();
+        let card = |rank| Card::new(rank, Suit::Club);
+        let mut stat = PlayStats::new();
+        stat.inc(true);
+        stat.inc(false);
+        stats
+            .update(
+                &Hand::new(&[card(Rank::RT), card(Rank::R6)]),
+                card(Rank::RT),
+                stat,
+            )
+            .unwrap();
+        let mut fd = vec![];
+        SVGTableRenderer::render_heatmap(&mut fd, &stats).unwrap();
+        let svg = String::from_utf8(fd).unwrap();
+        assert_eq!(svg.matches("fill='#ffff33'").count(), 1);
+        assert_eq!(svg.matches(">50</text>").count(), 1);
+        assert_eq!(svg.matches("fill='#eeeeee'").count(), 359);
+    }
+
+    #[test]
+    fn partial() {
+        let mut resps: Vec<Option<Resp>> =
+            resps_from_buf(T1).unwrap().into_iter().map(Some).collect();
+        // nothing learned about pairs yet
+        for resp in resps.iter_mut().skip(260) {
+            *resp = None;
+        }
+        let bs = BasicStrategy::from_partial(None, Table::new(resps).unwrap()).unwrap();
+        let mut fd = vec![];
+        TXTTableRenderer::render(&mut fd, &bs).unwrap();
+        let txt = String::from_utf8(fd).unwrap();
+        assert!(txt.contains(&format!("# Pair table\n{}\n", "-  ".repeat(10))));
+        let reread = crate::resp::partial_resps_from_buf(txt.as_bytes()).unwrap();
+        assert_eq!(Table::new(reread).unwrap(), bs.partial_table());
+        let mut fd = vec![];
+        TextTableRenderer::render(&mut fd, &bs).unwrap();
+        let text = String::from_utf8(fd).unwrap();
+        assert!(text.contains("\nA,A  -  -  -  -  -  -  -  -  -  -\n"));
+        assert!(text.trim_end().ends_with("-  Not learned yet"));
+        let mut fd = vec![];
+        let opts = HTMLTableRendererOpts {
+            incl_bs_rules: false,
+            cell_onclick_cb: None,
+            notes: None,
+            visibility: None,
+        };
+        HTMLTableRenderer::render(&mut fd, &bs, opts).unwrap();
+        let html = String::from_utf8(fd).unwrap();
+        assert_eq!(html.matches("<td class=unlearned ").count(), 100);
+    }
+
+    /// The bundled cards, and how each renderer draws them. Rendering regressions show up as a
+    /// diff against the golden files in testdata/render. After a change to how a card should look,
+    /// rerun with BLESS_GOLDEN=1 to rewrite them, and review the diff.
+    #[cfg(feature = "serde")]
+    #[test]
+    fn golden() {
+        type Render = fn(&mut Vec<u8>, &BasicStrategy) -> io::Result<()>;
+        let cards = [
+            (
+                "t1",
+                serde_json::from_slice::<BasicStrategy>(include_bytes!(
+                    "../../bj-data/data/t1.json"
+                ))
+                .unwrap(),
+            ),
+            (
+                "t2",
+                BasicStrategy::new(
+                    None,
+                    Table::new(resps_from_buf(&include_bytes!("../../t2.txt")[..]).unwrap())
+                        .unwrap(),
+                ),
+            ),
+        ];
+        let renderers: [(&str, Render); 4] = [
+            ("html", |fd, bs| {
+                let opts = HTMLTableRendererOpts {
+                    incl_bs_rules: true,
+                    cell_onclick_cb: None,
+                    notes: None,
+                    visibility: None,
+                };
+                HTMLTableRenderer::render(fd, bs, opts)
+            }),
+            ("svg", |fd, bs| SVGTableRenderer::render(fd, bs)),
+            ("txt", |fd, bs| TXTTableRenderer::render(fd, bs)),
+            ("text", |fd, bs| TextTableRenderer::render(fd, bs)),
+        ];
+        let dir = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("testdata/render");
+        let bless = std::env::var_os("BLESS_GOLDEN").is_some();
+        for (name, bs) in &cards {
+            for (kind, render) in &renderers {
+                let mut fd = vec![];
+                render(&mut fd, bs).unwrap();
+                let path = dir.join(format!("{}.{}", name, kind));
+                if bless {
+                    std::fs::create_dir_all(&dir).unwrap();
+                    std::fs::write(&path, &fd).unwrap();
+                    continue;
+                }
+                let golden = std::fs::read(&path).unwrap_or_else(|e| {
+                    panic!("{}: {}. Rerun with BLESS_GOLDEN=1", path.display(), e)
+                });
+                assert!(
+                    fd == golden,
+                    "{} rendered differently than {}. If that's intended, rerun with \
+                     BLESS_GOLDEN=1.",
+                    kind,
+                    path.display()
+                );
+            }
+        }
+    }
 }
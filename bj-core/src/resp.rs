@@ -1,9 +1,12 @@
 use crate::table::NUM_CELLS;
+#[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 use std::fmt;
 use std::io::{self, Read};
+use thiserror::Error;
 
-#[derive(Serialize, Deserialize, PartialEq, Debug, Copy, Clone)]
+#[derive(PartialEq, Eq, Hash, Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum Resp {
     Hit,
     Stand,
@@ -15,33 +18,22 @@ pub enum Resp {
     SurrenderElseSplit,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Error)]
 pub enum RespError {
-    IOError(io::Error),
+    #[error("io error: {0}")]
+    IOError(#[from] io::Error),
+    #[error("Invalid char '{0}'")]
     InvalidChar(char),
+    #[error("'{0}' is not a valid Resp")]
     InvalidCharSequence(String),
+    #[error("'{0}' requires second char, but it doesn't exist")]
     NoSecondChar(char),
-}
-
-impl std::error::Error for RespError {}
-
-impl fmt::Display for RespError {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match self {
-            Self::IOError(e) => write!(f, "io error: {}", e),
-            Self::InvalidChar(c) => write!(f, "Invalid char '{}'", c),
-            Self::InvalidCharSequence(s) => write!(f, "'{}' is not a valid Resp", s),
-            Self::NoSecondChar(c) => {
-                write!(f, "'{}' requires second char, but it doesn't exist", c)
-            }
-        }
-    }
-}
-
-impl From<io::Error> for RespError {
-    fn from(e: io::Error) -> Self {
-        Self::IOError(e)
-    }
+    /// A cell is marked not learned yet, but the card has to be complete
+    #[error(
+        "'{}' marks a cell not learned yet, which only a partial card can have",
+        NOT_LEARNED
+    )]
+    NotLearned,
 }
 
 impl fmt::Display for Resp {
@@ -59,6 +51,41 @@ impl fmt::Display for Resp {
     }
 }
 
+impl Resp {
+    /// Hit or Stand: the action to take when nothing else is allowed. Compound Resps collapse to
+    /// the action they fall back on. Split has no such action, so it and SurrenderElseSplit are
+    /// None.
+    pub fn hit_or_stand(self) -> Option<Resp> {
+        match self {
+            Self::Hit | Self::DoubleElseHit | Self::SurrenderElseHit => Some(Self::Hit),
+            Self::Stand | Self::DoubleElseStand | Self::SurrenderElseStand => Some(Self::Stand),
+            Self::Split | Self::SurrenderElseSplit => None,
+        }
+    }
+
+    /// Whether the Resp splits, first choice or not. Only pairs can be split.
+    pub fn splits(self) -> bool {
+        matches!(self, Self::Split | Self::SurrenderElseSplit)
+    }
+
+    /// The short code for the Resp as it appears in strategy card files, e.g. "Dh"
+    pub fn code(self) -> &'static str {
+        match self {
+            Self::Hit => "H",
+            Self::Stand => "S",
+            Self::DoubleElseHit => "Dh",
+            Self::DoubleElseStand => "Ds",
+            Self::Split => "P",
+            Self::SurrenderElseHit => "Rh",
+            Self::SurrenderElseStand => "Rs",
+            Self::SurrenderElseSplit => "Rp",
+        }
+    }
+}
+
+/// Marks a cell of a partial strategy card as not learned yet, see partial_resps_from_buf()
+pub const NOT_LEARNED: char = '-';
+
 struct RespCharIter(Vec<char>);
 
 impl RespCharIter {
@@ -75,7 +102,8 @@ impl RespCharIter {
 }
 
 impl Iterator for RespCharIter {
-    type Item = Result<Resp, RespError>;
+    /// A Resp, or None for a cell not learned yet
+    type Item = Result<Option<Resp>, RespError>;
     fn next(&mut self) -> Option<Self::Item> {
         let c = match self.0.pop() {
             None => {
@@ -83,6 +111,9 @@ impl Iterator for RespCharIter {
             }
             Some(c) => c,
         };
+        if c == NOT_LEARNED {
+            return Some(Ok(None));
+        }
         // make sure char is allowed in this position
         if !Self::ALL_FIRST.contains(&c) {
             return Some(Err(RespError::InvalidChar(c)));
@@ -103,17 +134,18 @@ impl Iterator for RespCharIter {
             }
             s.push(c2);
         }
-        match s.as_str() {
-            "H" => Some(Ok(Resp::Hit)),
-            "S" => Some(Ok(Resp::Stand)),
-            "P" => Some(Ok(Resp::Split)),
-            "Dh" => Some(Ok(Resp::DoubleElseHit)),
-            "Ds" => Some(Ok(Resp::DoubleElseStand)),
-            "Rh" => Some(Ok(Resp::SurrenderElseHit)),
-            "Rs" => Some(Ok(Resp::SurrenderElseStand)),
-            "Rp" => Some(Ok(Resp::SurrenderElseSplit)),
-            _ => Some(Err(RespError::InvalidCharSequence(s))),
-        }
+        let resp = match s.as_str() {
+            "H" => Resp::Hit,
+            "S" => Resp::Stand,
+            "P" => Resp::Split,
+            "Dh" => Resp::DoubleElseHit,
+            "Ds" => Resp::DoubleElseStand,
+            "Rh" => Resp::SurrenderElseHit,
+            "Rs" => Resp::SurrenderElseStand,
+            "Rp" => Resp::SurrenderElseSplit,
+            _ => return Some(Err(RespError::InvalidCharSequence(s))),
+        };
+        Some(Ok(Some(resp)))
     }
 }
 
@@ -121,11 +153,23 @@ impl Iterator for RespCharIter {
 /// that isn't in "HSDP" (hit, stand, double, split), and return a vector of these parsed
 /// Vec<Resp>.
 pub fn resps_from_buf<R>(buf: R) -> Result<Vec<Resp>, RespError>
+where
+    R: Read,
+{
+    partial_resps_from_buf(buf)?
+        .into_iter()
+        .map(|resp| resp.ok_or(RespError::NotLearned))
+        .collect()
+}
+
+/// Like resps_from_buf(), but for a partial card: a cell the player hasn't learned yet can be
+/// NOT_LEARNED instead of a Resp, and is None. See BasicStrategy::from_partial().
+pub fn partial_resps_from_buf<R>(buf: R) -> Result<Vec<Option<Resp>>, RespError>
 where
     R: Read,
 {
     use readfilter::{CharWhitelist, CommentStrip};
-    let mut buf = CharWhitelist::new(CommentStrip::new(buf), "HSDPRhsp");
+    let mut buf = CharWhitelist::new(CommentStrip::new(buf), "HSDPRhsp-");
     let mut s = String::with_capacity(NUM_CELLS);
     buf.read_to_string(&mut s)?;
     RespCharIter::new(s).collect()
@@ -136,6 +180,14 @@ mod tests {
     use super::*;
     use std::iter::{once, repeat};
 
+    #[test]
+    fn hit_or_stand() {
+        assert_eq!(Resp::DoubleElseHit.hit_or_stand(), Some(Resp::Hit));
+        assert_eq!(Resp::SurrenderElseStand.hit_or_stand(), Some(Resp::Stand));
+        assert_eq!(Resp::Stand.hit_or_stand(), Some(Resp::Stand));
+        assert_eq!(Resp::SurrenderElseSplit.hit_or_stand(), None);
+    }
+
     #[test]
     fn count_doesnt_matter() {
         // it's not resps_from_buf's job to return the right number of elements for a Table. So it
@@ -180,6 +232,22 @@ mod tests {
         }
     }
 
+    #[test]
+    fn not_learned() {
+        let buf = "H - Ds -".as_bytes();
+        assert_eq!(
+            partial_resps_from_buf(buf).unwrap(),
+            vec![Some(Resp::Hit), None, Some(Resp::DoubleElseStand), None]
+        );
+        // only a partial card can have cells not learned yet
+        assert!(matches!(resps_from_buf(buf), Err(RespError::NotLearned)));
+        // but a comment can say anything
+        assert_eq!(
+            resps_from_buf("H # - S".as_bytes()).unwrap(),
+            vec![Resp::Hit]
+        );
+    }
+
     #[test]
     fn no_second_char() {
         // a two-char sequence that ends without the second char is an error
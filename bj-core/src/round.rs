@@ -0,0 +1,318 @@
+//! A round of blackjack played to the end, once the cards are dealt and the dealer has peeked (see
+//! holecard).
+//!
+//! The player plays one hand at a time. Hitting a hand that doesn't bust and splitting keep the
+//! player on a hand, and everything else finishes one. Each hand made by splitting gets its
+//! second card right away, and the first of them is played first. Once every hand is finished,
+//! the dealer plays theirs (see dealer) and every bet is settled (see settle).
+//!
+//! Cards come from whatever the caller draws them from, so the same round can be dealt from a
+//! Deck, a counted shoe, or a list of cards in a test. The rules decide what the player may do,
+//! how the dealer draws, and what's paid. If they aren't known, anything the hand allows goes,
+//! the dealer hits soft 17, and blackjack pays 3:2.
+use crate::basicstrategy::rules::{Rules, SplitState};
+use crate::button::{legal_actions, GameButton};
+use crate::dealer::{play_out, DealerResult};
+use crate::deck::{Card, DeckError, Rank};
+use crate::hand::Hand;
+use crate::holecard::DealerHand;
+use crate::settle::{settle, PlayedHand, Settlement};
+use thiserror::Error;
+
+#[derive(Debug, PartialEq, Error)]
+pub enum RoundError {
+    /// The action isn't allowed on the hand being played
+    #[error("Cannot {} {}", .0.to_string().to_lowercase(), .1)]
+    Illegal(GameButton, Hand),
+    /// Every hand is finished, so there's nothing to act on
+    #[error("Every hand has been played")]
+    NoHand,
+    /// The dealer can't play until every hand is finished
+    #[error("Not every hand has been played")]
+    NotFinished,
+    #[error("{0}")]
+    OutOfCards(#[from] DeckError),
+}
+
+/// What an action did to the hand being played
+#[derive(Debug, Clone, PartialEq)]
+pub enum Acted {
+    /// The player is still playing the hand, or the first hand the split made
+    Playing,
+    /// The hand is finished, as it's now played
+    Finished(PlayedHand),
+}
+
+/// How the round ended
+#[derive(Debug, Clone, PartialEq)]
+pub struct RoundEnd {
+    /// Every hand the player played, in the order they were finished
+    pub hands: Vec<PlayedHand>,
+    pub dealer: DealerResult,
+    /// One result for each of hands, in the same order
+    pub settlement: Settlement,
+}
+
+pub struct Round {
+    dealer: DealerHand,
+    rules: Option<Rules>,
+    /// The hands still to play, each with where it came from. The last one is being played.
+    working: Vec<(Hand, SplitState)>,
+    finished: Vec<PlayedHand>,
+}
+
+impl Round {
+    /// A round where the player was dealt the given hand and the dealer's hole card is still face
+    /// down
+    pub fn new(player: Hand, dealer: DealerHand, rules: Option<Rules>) -> Self {
+        Self {
+            dealer,
+            rules,
+            working: vec![(player, SplitState::default())],
+            finished: vec![],
+        }
+    }
+
+    pub fn dealer(&self) -> DealerHand {
+        self.dealer
+    }
+
+    /// The hand being played and where it came from, or None once every hand is finished
+    pub fn current(&self) -> Option<(&Hand, SplitState)> {
+        self.working.last().map(|(hand, split)| (hand, *split))
+    }
+
+    /// How many hands made by splitting are waiting to be played after the current one
+    pub fn waiting(&self) -> usize {
+        self.working.len().saturating_sub(1)
+    }
+
+    /// Every hand finished so far
+    pub fn finished(&self) -> &[PlayedHand] {
+        &self.finished
+    }
+
+    /// Everything the player has riding on the round, given its bet before any doubling or
+    /// splitting. Doesn't count insurance.
+    pub fn staked(&self, bet: f64) -> f64 {
+        let finished: f64 = self.finished.iter().map(|played| played.stake(bet)).sum();
+        finished + bet * self.working.len() as f64
+    }
+
+    /// What the player may do with the hand being played
+    pub fn legal_actions(&self) -> Vec<GameButton> {
+        match self.current() {
+            None => vec![],
+            Some((hand, split)) => {
+                legal_actions(hand, self.dealer.up(), self.rules.as_ref(), split)
+            }
+        }
+    }
+
+    /// Take the given action on the hand being played, drawing any cards it needs from shoe.
+    /// Nothing changes if it errors.
+    pub fn act<F>(&mut self, action: GameButton, mut shoe: F) -> Result<Acted, RoundError>
+    where
+        F: FnMut() -> Result<Card, DeckError>,
+    {
+        let (hand, split) = self.working.last().ok_or(RoundError::NoHand)?.clone();
+        if !self.legal_actions().contains(&action) {
+            return Err(RoundError::Illegal(action, hand));
+        }
+        let mut played = PlayedHand::new(hand, split);
+        match action {
+            GameButton::Stand => {}
+            GameButton::Hit => {
+                played.hand.push(shoe()?);
+                if !played.hand.is_bust() {
+                    self.working.last_mut().unwrap().0 = played.hand;
+                    return Ok(Acted::Playing);
+                }
+            }
+            GameButton::Double => {
+                played.hand.push(shoe()?);
+                played.doubled = true;
+            }
+            GameButton::Surrender => played.surrendered = true,
+            GameButton::Split => {
+                // legal_actions() already checked that the hand is a pair
+                let (c1, c2) = played.hand.split().unwrap();
+                let (d1, d2) = (shoe()?, shoe()?);
+                let split = SplitState {
+                    splits: split.splits + 1,
+                    aces: c1.rank() == Rank::RA,
+                };
+                self.working.pop();
+                self.working.push((Hand::new(&[c2, d2]), split));
+                self.working.push((Hand::new(&[c1, d1]), split));
+                return Ok(Acted::Playing);
+            }
+        }
+        self.working.pop();
+        self.finished.push(played.clone());
+        Ok(Acted::Finished(played))
+    }
+
+    /// Once every hand is finished, turn over the hole card, have the dealer play, and settle the
+    /// round. bet is the round's bet before any doubling or splitting, and insurance is the size
+    /// of the insurance bet (0 if it wasn't taken). The dealer always plays out their hand, even if
+    /// every one of the player's hands is already settled.
+    pub fn finish<F>(self, bet: f64, insurance: f64, shoe: F) -> Result<RoundEnd, RoundError>
+    where
+        F: FnMut() -> Result<Card, DeckError>,
+    {
+        if !self.working.is_empty() {
+            return Err(RoundError::NotFinished);
+        }
+        let rules = self.rules.as_ref();
+        let dealer = play_out(self.dealer.up(), self.dealer.hole(), shoe, rules)?;
+        let settlement = settle(bet, &self.finished, insurance, dealer.hand(), rules);
+        Ok(RoundEnd {
+            hands: self.finished,
+            dealer,
+            settlement,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::deck::Suit;
+    use crate::hand::tests::hand;
+    use crate::outcome::Outcome;
+    use crate::settle::Settled;
+    use Rank::*;
+
+    fn card(r: Rank) -> Card {
+        Card::new(r, Suit::Club)
+    }
+
+    /// Draws the given ranks in order
+    fn shoe(ranks: &[Rank]) -> impl FnMut() -> Result<Card, DeckError> + '_ {
+        let mut ranks = ranks.iter();
+        move || ranks.next().map(|r| card(*r)).ok_or(DeckError::OutOfCards)
+    }
+
+    fn round(player: &[Rank], up: Rank, hole: Rank) -> Round {
+        Round::new(hand(player), DealerHand::new(card(up), card(hole)), None)
+    }
+
+    #[test]
+    fn hit_and_stand() {
+        let mut r = round(&[R5, R6], RT, R7);
+        assert_eq!(r.act(GameButton::Hit, shoe(&[R2])), Ok(Acted::Playing));
+        assert_eq!(
+            r.current(),
+            Some((&hand(&[R5, R6, R2]), SplitState::default()))
+        );
+        assert_eq!(
+            r.finish(1.0, 0.0, shoe(&[])).err(),
+            Some(RoundError::NotFinished)
+        );
+        let mut r = round(&[R5, R6], RT, R7);
+        r.act(GameButton::Hit, shoe(&[R2])).unwrap();
+        assert_eq!(
+            r.act(GameButton::Stand, shoe(&[])),
+            Ok(Acted::Finished(PlayedHand::new(
+                hand(&[R5, R6, R2]),
+                SplitState::default()
+            )))
+        );
+        assert_eq!(r.current(), None);
+        assert_eq!(r.act(GameButton::Stand, shoe(&[])), Err(RoundError::NoHand));
+        let end = r.finish(1.0, 0.0, shoe(&[])).unwrap();
+        assert_eq!(end.dealer.value(), 17);
+        assert_eq!(end.settlement.net(), -1.0);
+    }
+
+    #[test]
+    fn bust_double_surrender() {
+        let mut r = round(&[RT, R6], RT, R6);
+        match r.act(GameButton::Hit, shoe(&[RK])).unwrap() {
+            Acted::Finished(played) => assert!(played.hand.is_bust()),
+            acted => panic!("{:?}", acted),
+        }
+        // the dealer busts too, but the player busted first
+        let end = r.finish(1.0, 0.0, shoe(&[RT])).unwrap();
+        assert!(end.dealer.is_bust());
+        assert_eq!(end.settlement.net(), -1.0);
+
+        let mut r = round(&[R5, R6], R6, RT);
+        r.act(GameButton::Double, shoe(&[RT])).unwrap();
+        let end = r.finish(1.0, 0.0, shoe(&[RK])).unwrap();
+        assert!(end.hands[0].doubled && end.dealer.is_bust());
+        assert_eq!(end.settlement.net(), 2.0);
+
+        let mut r = round(&[RT, R6], RA, R9);
+        r.act(GameButton::Surrender, shoe(&[])).unwrap();
+        let end = r.finish(1.0, 0.0, shoe(&[])).unwrap();
+        assert_eq!(end.settlement.hands[0].settled, Settled::Surrendered);
+        assert_eq!(end.settlement.net(), -0.5);
+    }
+
+    #[test]
+    fn illegal() {
+        let rules = Rules {
+            hit_soft_17: true.into(),
+            double_after_split: false.into(),
+            ..Default::default()
+        };
+        let dealer = DealerHand::new(card(RT), card(R7));
+        let mut r = Round::new(hand(&[RT, R6]), dealer, Some(rules));
+        assert_eq!(
+            r.act(GameButton::Surrender, shoe(&[])),
+            Err(RoundError::Illegal(GameButton::Surrender, hand(&[RT, R6])))
+        );
+        assert_eq!(
+            r.act(GameButton::Split, shoe(&[])),
+            Err(RoundError::Illegal(GameButton::Split, hand(&[RT, R6])))
+        );
+        // nothing changed, and running out of cards doesn't change anything either
+        assert_eq!(
+            r.act(GameButton::Hit, shoe(&[])),
+            Err(RoundError::OutOfCards(DeckError::OutOfCards))
+        );
+        assert_eq!(r.current().unwrap().0, &hand(&[RT, R6]));
+    }
+
+    #[test]
+    fn splits() {
+        let mut r = round(&[R8, R8], R6, RT);
+        assert_eq!(
+            r.act(GameButton::Split, shoe(&[R3, RT])),
+            Ok(Acted::Playing)
+        );
+        let split = SplitState {
+            splits: 1,
+            aces: false,
+        };
+        assert_eq!(r.current(), Some((&hand(&[R8, R3]), split)));
+        assert_eq!(r.waiting(), 1);
+        assert_eq!(r.staked(5.0), 10.0);
+        r.act(GameButton::Double, shoe(&[R9])).unwrap();
+        assert_eq!(r.staked(5.0), 15.0);
+        assert_eq!(r.current(), Some((&hand(&[R8, RT]), split)));
+        assert_eq!(r.waiting(), 0);
+        r.act(GameButton::Stand, shoe(&[])).unwrap();
+        let end = r.finish(1.0, 0.0, shoe(&[R5])).unwrap();
+        // the dealer's 21 beats both
+        assert_eq!(end.dealer.value(), 21);
+        assert_eq!(end.hands.len(), 2);
+        assert_eq!(end.settlement.net(), -3.0);
+
+        // 21 after splitting aces isn't a natural
+        let mut r = round(&[RA, RA], R9, R8);
+        r.act(GameButton::Split, shoe(&[RK, R9])).unwrap();
+        assert!(r.current().unwrap().1.aces);
+        r.act(GameButton::Stand, shoe(&[])).unwrap();
+        r.act(GameButton::Stand, shoe(&[])).unwrap();
+        let end = r.finish(1.0, 0.0, shoe(&[])).unwrap();
+        let settled: Vec<Settled> = end.settlement.hands.iter().map(|h| h.settled).collect();
+        assert_eq!(
+            settled,
+            vec![Settled::Played(Outcome::Win), Settled::Played(Outcome::Win)]
+        );
+        assert_eq!(end.settlement.net(), 2.0);
+    }
+}
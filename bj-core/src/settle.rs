@@ -0,0 +1,310 @@
+//! Settling every bet on a round once the dealer has played: who gets paid, and how much.
+//!
+//! Each of the player's hands carries the round's bet, or twice it if it was doubled. outcome()
+//! decides how each one did, and naturals are paid the rules' blackjack payout. A surrendered hand
+//! gets half its bet back, unless the dealer turns out to have a natural, which only happens in
+//! games where the dealer doesn't peek. Insurance is a side bet that pays 2:1 if the dealer has a
+//! natural and is lost otherwise, so even money is insuring a blackjack for half the bet.
+//!
+//! When the dealer doesn't peek, a dealer natural costs either every bet on the table or only the
+//! original bet, depending on the rules (see NoPeekLoss). With only the original bet lost, the
+//! first hand loses the round's bet and everything added by doubling or splitting is returned.
+//! If the rules aren't known, only the original bet is lost. Busted hands were lost before the
+//! dealer turned the hole card, so they always lose their whole stake.
+use crate::basicstrategy::rules::{Rules, SplitState};
+use crate::hand::Hand;
+use crate::outcome::{outcome, Outcome};
+
+/// One of the player's hands at the end of the round
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlayedHand {
+    pub hand: Hand,
+    /// Where the hand came from
+    pub split: SplitState,
+    pub doubled: bool,
+    pub surrendered: bool,
+}
+
+impl PlayedHand {
+    /// A hand that was neither doubled nor surrendered
+    pub fn new(hand: Hand, split: SplitState) -> Self {
+        Self {
+            hand,
+            split,
+            doubled: false,
+            surrendered: false,
+        }
+    }
+
+    /// How much the hand has riding on it, given the round's bet
+    pub fn stake(&self, bet: f64) -> f64 {
+        if self.doubled {
+            bet * 2.0
+        } else {
+            bet
+        }
+    }
+}
+
+/// How a hand was settled
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Settled {
+    Played(Outcome),
+    Surrendered,
+    /// The dealer had a natural and only the original bet was lost, and none of it was on this
+    /// hand
+    Returned,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HandResult {
+    pub settled: Settled,
+    /// What the hand had riding on it
+    pub stake: f64,
+    /// What the hand won, or lost if negative
+    pub net: f64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Settlement {
+    /// One for each hand, in the order they were given
+    pub hands: Vec<HandResult>,
+    /// What the insurance bet won, or lost if negative
+    pub insurance: f64,
+}
+
+impl Settlement {
+    /// What the whole round won, or lost if negative
+    pub fn net(&self) -> f64 {
+        self.hands.iter().map(|h| h.net).sum::<f64>() + self.insurance
+    }
+}
+
+/// Settle a round. bet is what the player bet on the round, before any doubling or splitting,
+/// hands are all of their hands, insurance is the size of their insurance bet (0 if they didn't
+/// take it), and dealer is the dealer's whole hand. See the module docs.
+pub fn settle(
+    bet: f64,
+    hands: &[PlayedHand],
+    insurance: f64,
+    dealer: &Hand,
+    rules: Option<&Rules>,
+) -> Settlement {
+    let pays = rules.map(|r| r.blackjack_pays).unwrap_or_default();
+    let dealer_natural = dealer.is_blackjack();
+    let takes_all = rules.is_some_and(|r| r.dealer_bj_takes_all_bets());
+    let hands = hands
+        .iter()
+        .enumerate()
+        .map(|(i, played)| {
+            let stake = played.stake(bet);
+            let (settled, net) = if played.surrendered && !dealer_natural {
+                (Settled::Surrendered, -stake / 2.0)
+            } else if played.surrendered {
+                (Settled::Surrendered, -stake)
+            } else if played.hand.is_bust() {
+                (Settled::Played(Outcome::Lose), -stake)
+            } else {
+                let o = outcome(&played.hand, played.split, dealer);
+                match o {
+                    Outcome::Lose if dealer_natural && !takes_all && i > 0 => {
+                        (Settled::Returned, 0.0)
+                    }
+                    Outcome::Lose if dealer_natural && !takes_all => (Settled::Played(o), -bet),
+                    _ => (Settled::Played(o), o.net(stake, pays)),
+                }
+            };
+            HandResult {
+                settled,
+                stake,
+                net,
+            }
+        })
+        .collect();
+    let insurance = if dealer_natural {
+        insurance * 2.0
+    } else {
+        -insurance
+    };
+    Settlement { hands, insurance }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::basicstrategy::rules::{BlackjackPayout, NoPeekLoss, Surrender};
+    use crate::deck::Rank;
+    use crate::hand::tests::hand;
+
+    fn rules(peek: bool, no_peek_loss: NoPeekLoss, pays: BlackjackPayout) -> Rules {
+        Rules {
+            hit_soft_17: true.into(),
+            peek_bj: peek.into(),
+            surrender: Surrender::Yes,
+            no_peek_loss,
+            blackjack_pays: pays,
+            ..Default::default()
+        }
+    }
+
+    fn nets(s: &Settlement) -> Vec<f64> {
+        s.hands.iter().map(|h| h.net).collect()
+    }
+
+    #[test]
+    fn naturals_and_pushes() {
+        let none = SplitState::default();
+        let bj = hand(&[Rank::RA, Rank::RK]);
+        let twenty = hand(&[Rank::RT, Rank::RQ]);
+        let composed = hand(&[Rank::R7, Rank::R7, Rank::R7]);
+        let s = settle(
+            10.0,
+            &[PlayedHand::new(bj.clone(), none)],
+            0.0,
+            &twenty,
+            None,
+        );
+        assert_eq!(s.hands[0].settled, Settled::Played(Outcome::Blackjack));
+        assert_eq!(s.net(), 15.0);
+        let six_five = rules(
+            true,
+            NoPeekLoss::OriginalBetsOnly,
+            BlackjackPayout::SixToFive,
+        );
+        let s = settle(
+            10.0,
+            &[PlayedHand::new(bj.clone(), none)],
+            0.0,
+            &composed,
+            Some(&six_five),
+        );
+        assert_eq!(s.net(), 12.0);
+        // naturals push, and a composed 21 loses to one
+        let s = settle(10.0, &[PlayedHand::new(bj.clone(), none)], 0.0, &bj, None);
+        assert_eq!(s.hands[0].settled, Settled::Played(Outcome::Push));
+        assert_eq!(s.net(), 0.0);
+        let s = settle(
+            10.0,
+            &[PlayedHand::new(twenty.clone(), none)],
+            0.0,
+            &twenty,
+            None,
+        );
+        assert_eq!(s.net(), 0.0);
+        // even money is the same either way
+        for dealer in [&bj, &twenty].iter() {
+            let s = settle(
+                10.0,
+                &[PlayedHand::new(bj.clone(), none)],
+                5.0,
+                dealer,
+                None,
+            );
+            assert_eq!(s.net(), 10.0);
+        }
+        // insurance on anything else
+        let s = settle(
+            10.0,
+            &[PlayedHand::new(twenty.clone(), none)],
+            5.0,
+            &bj,
+            None,
+        );
+        assert_eq!((s.insurance, s.net()), (10.0, 0.0));
+        let s = settle(10.0, &[PlayedHand::new(twenty, none)], 5.0, &composed, None);
+        assert_eq!((s.insurance, s.net()), (-5.0, -15.0));
+    }
+
+    #[test]
+    fn doubles_splits_and_surrender() {
+        let split = SplitState {
+            splits: 1,
+            aces: true,
+        };
+        let none = SplitState::default();
+        let bj = hand(&[Rank::RA, Rank::RK]);
+        let nineteen = hand(&[Rank::RT, Rank::R9]);
+        let bust = hand(&[Rank::RT, Rank::R6, Rank::R9]);
+        // split aces: a ten is 21, not a natural, and pushes a composed 21
+        let hands = [
+            PlayedHand::new(bj.clone(), split),
+            PlayedHand::new(hand(&[Rank::RA, Rank::R5]), split),
+        ];
+        let composed = hand(&[Rank::R7, Rank::R7, Rank::R7]);
+        let s = settle(10.0, &hands, 0.0, &composed, None);
+        assert_eq!(nets(&s), vec![0.0, -10.0]);
+        let dealer_bust = hand(&[Rank::RT, Rank::R5, Rank::RK]);
+        let s = settle(10.0, &hands, 0.0, &dealer_bust, None);
+        assert_eq!(nets(&s), vec![10.0, 10.0]);
+        // doubles win and lose twice the bet, and a busted double loses even if the dealer busts
+        let mut doubled = PlayedHand::new(nineteen.clone(), none);
+        doubled.doubled = true;
+        let s = settle(10.0, &[doubled.clone()], 0.0, &dealer_bust, None);
+        assert_eq!((s.hands[0].stake, s.net()), (20.0, 20.0));
+        let mut busted = PlayedHand::new(bust, none);
+        busted.doubled = true;
+        assert_eq!(
+            settle(10.0, &[busted], 0.0, &dealer_bust, None).net(),
+            -20.0
+        );
+        // surrender gets half back, unless the dealer had a natural (no peek)
+        let mut surrendered = PlayedHand::new(hand(&[Rank::RT, Rank::R6]), none);
+        surrendered.surrendered = true;
+        let s = settle(10.0, &[surrendered.clone()], 0.0, &nineteen, None);
+        assert_eq!(s.hands[0].settled, Settled::Surrendered);
+        assert_eq!(s.net(), -5.0);
+        assert_eq!(settle(10.0, &[surrendered], 0.0, &bj, None).net(), -10.0);
+    }
+
+    #[test]
+    fn no_peek() {
+        let split = SplitState {
+            splits: 1,
+            aces: false,
+        };
+        let bj = hand(&[Rank::RA, Rank::RK]);
+        let mut first = PlayedHand::new(hand(&[Rank::R8, Rank::R3, Rank::RT]), split);
+        first.doubled = true;
+        let second = PlayedHand::new(hand(&[Rank::R8, Rank::RT]), split);
+        let hands = [first, second];
+        let obo = rules(
+            false,
+            NoPeekLoss::OriginalBetsOnly,
+            BlackjackPayout::ThreeToTwo,
+        );
+        let s = settle(10.0, &hands, 0.0, &bj, Some(&obo));
+        assert_eq!(nets(&s), vec![-10.0, 0.0]);
+        assert_eq!(s.hands[1].settled, Settled::Returned);
+        // unknown rules lose the original bet too
+        assert_eq!(settle(10.0, &hands, 0.0, &bj, None), s);
+        let enhc = rules(false, NoPeekLoss::AllBets, BlackjackPayout::ThreeToTwo);
+        let s = settle(10.0, &hands, 0.0, &bj, Some(&enhc));
+        assert_eq!(nets(&s), vec![-20.0, -10.0]);
+        assert_eq!(s.net(), -30.0);
+    }
+
+    #[test]
+    fn no_peek_busts() {
+        let split = SplitState {
+            splits: 1,
+            aces: false,
+        };
+        let bj = hand(&[Rank::RA, Rank::RK]);
+        let obo = rules(
+            false,
+            NoPeekLoss::OriginalBetsOnly,
+            BlackjackPayout::ThreeToTwo,
+        );
+        // a busted split hand was already lost, so it isn't returned
+        let first = PlayedHand::new(hand(&[Rank::R8, Rank::RT]), split);
+        let busted = PlayedHand::new(hand(&[Rank::R8, Rank::R6, Rank::RT]), split);
+        let s = settle(10.0, &[first, busted], 0.0, &bj, Some(&obo));
+        assert_eq!(nets(&s), vec![-10.0, -10.0]);
+        assert_eq!(s.hands[1].settled, Settled::Played(Outcome::Lose));
+        // and a busted double loses all of it
+        let mut doubled = PlayedHand::new(hand(&[Rank::R6, Rank::R5, Rank::RT, Rank::R9]), split);
+        doubled.doubled = true;
+        let s = settle(10.0, &[doubled], 0.0, &bj, Some(&obo));
+        assert_eq!(nets(&s), vec![-20.0]);
+    }
+}
@@ -0,0 +1,176 @@
+//! Sharing the engine between threads, e.g. between the handlers of an async server running many
+//! players' sessions at once.
+//!
+//! Nothing in this crate keeps global state, and everything random is drawn from rand's
+//! thread_rng(), which every thread has its own of. So sessions on different threads never
+//! contend or share a stream of cards. Every engine type is Send and Sync, which is checked when
+//! this crate builds, so it can live behind a Mutex and move between an async runtime's worker
+//! threads.
+//!
+//! What isn't safe is two requests acting on one player's Trainer at once. SharedTrainer puts it
+//! behind a lock that's only held for the length of a closure, which can't await, so a handler
+//! can't hold it across an .await and stall the runtime. Sessions keys them by whatever the server
+//! identifies a player by, and only locks the whole map long enough to find one.
+use crate::basicstrategy::BasicStrategy;
+use crate::count::StatefulHiLo;
+use crate::deck::Deck;
+use crate::notes::Notes;
+use crate::playstats::PlayStats;
+use crate::table::Table;
+use crate::trainer::Trainer;
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+
+// Fails to build if any of the engine types stops being safe to share
+const _: fn() = || {
+    fn send_sync<T: Send + Sync>() {}
+    send_sync::<BasicStrategy>();
+    send_sync::<Deck>();
+    send_sync::<Notes>();
+    send_sync::<StatefulHiLo>();
+    send_sync::<Table<PlayStats>>();
+    send_sync::<Trainer>();
+};
+
+/// A panic in one request shouldn't lock a player out of their session for good, so a poisoned
+/// lock is taken anyway
+fn lock<T>(m: &Mutex<T>) -> MutexGuard<'_, T> {
+    m.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// One player's Trainer, cheap to clone and safe to use from any thread. Clones share the Trainer.
+#[derive(Debug, Clone)]
+pub struct SharedTrainer(Arc<Mutex<Trainer>>);
+
+impl SharedTrainer {
+    pub fn new(trainer: Trainer) -> Self {
+        Self(Arc::new(Mutex::new(trainer)))
+    }
+
+    /// Run f on the Trainer, with every other use of it waiting until f returns
+    pub fn with<R, F>(&self, f: F) -> R
+    where
+        F: FnOnce(&mut Trainer) -> R,
+    {
+        f(&mut lock(&self.0))
+    }
+}
+
+impl From<Trainer> for SharedTrainer {
+    fn from(trainer: Trainer) -> Self {
+        Self::new(trainer)
+    }
+}
+
+/// Every player's SharedTrainer, by key
+#[derive(Debug)]
+pub struct Sessions<K> {
+    map: Mutex<HashMap<K, SharedTrainer>>,
+}
+
+impl<K> Default for Sessions<K>
+where
+    K: Eq + Hash,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K> Sessions<K>
+where
+    K: Eq + Hash,
+{
+    pub fn new() -> Self {
+        Self {
+            map: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn get(&self, key: &K) -> Option<SharedTrainer> {
+        lock(&self.map).get(key).cloned()
+    }
+
+    /// The session for key, starting it with a Trainer from f if there isn't one. f runs with
+    /// every session locked, so it should be quick.
+    pub fn get_or_insert_with<F>(&self, key: K, f: F) -> SharedTrainer
+    where
+        F: FnOnce() -> Trainer,
+    {
+        lock(&self.map)
+            .entry(key)
+            .or_insert_with(|| SharedTrainer::new(f()))
+            .clone()
+    }
+
+    /// Start a session for key, returning the one it replaced if any
+    pub fn insert(&self, key: K, trainer: Trainer) -> Option<SharedTrainer> {
+        lock(&self.map).insert(key, SharedTrainer::new(trainer))
+    }
+
+    /// End the session for key. Anyone still holding a clone of it can keep using it.
+    pub fn remove(&self, key: &K) -> Option<SharedTrainer> {
+        lock(&self.map).remove(key)
+    }
+
+    pub fn len(&self) -> usize {
+        lock(&self.map).len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::button::GameButton;
+    use crate::resp::resps_from_buf;
+    use crate::trainer::HandSource;
+    use std::thread;
+
+    const T1: &[u8] = include_bytes!("../../t1.txt");
+
+    fn trainer() -> Trainer {
+        let table = Table::new(resps_from_buf(T1).unwrap()).unwrap();
+        Trainer::new(BasicStrategy::new(None, table), HandSource::Uniform)
+    }
+
+    #[test]
+    fn concurrent_sessions() {
+        let sessions = Arc::new(Sessions::new());
+        let handles: Vec<_> = (0..8)
+            .map(|i| {
+                let sessions = Arc::clone(&sessions);
+                thread::spawn(move || {
+                    // two threads per player
+                    let t = sessions.get_or_insert_with(i % 4, trainer);
+                    for _ in 0..50 {
+                        t.with(|t| t.act(GameButton::Stand).unwrap());
+                    }
+                })
+            })
+            .collect();
+        for h in handles {
+            h.join().unwrap();
+        }
+        assert_eq!(sessions.len(), 4);
+        for i in 0..4 {
+            let seen = sessions
+                .get(&i)
+                .unwrap()
+                .with(|t| t.stats().values().map(|s| s.seen()).sum::<u32>());
+            assert_eq!(seen, 100);
+        }
+        // a panic while one request has the lock doesn't end the session
+        let t = sessions.get(&0).unwrap();
+        let t2 = t.clone();
+        let _ = thread::spawn(move || t2.with(|_| panic!("handler bug"))).join();
+        let seen = t.with(|t| t.stats().values().map(|s| s.seen()).sum::<u32>());
+        assert_eq!(seen, 100);
+        assert!(sessions.remove(&0).is_some());
+        assert!(sessions.get(&0).is_none());
+    }
+}
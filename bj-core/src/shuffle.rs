@@ -0,0 +1,168 @@
+//! A seeded shuffle that is specified here exactly, so that any client (in any language, and any
+//! future version of this code) can produce the same sequence of cards from the same seed. This is
+//! what competitive and daily modes must use, as their results are only comparable if everyone was
+//! dealt the same cards. Everything else may keep using rand, whose algorithms can change between
+//! versions.
+//!
+//! # Version 1
+//!
+//! All arithmetic is on unsigned 64 bit integers and wraps on overflow.
+//!
+//! **PRNG.** SplitMix64. The state starts as the seed. To get the next output:
+//!
+//! ```text
+//! state = state + 0x9E3779B97F4A7C15
+//! z = state
+//! z = (z ^ (z >> 30)) * 0xBF58476D1CE4E5B9
+//! z = (z ^ (z >> 27)) * 0x94D049BB133111EB
+//! output z ^ (z >> 31)
+//! ```
+//!
+//! **Bounded integers.** To get an integer in [0, n), let zone = (2^64 - 1) - ((2^64 - 1) mod n).
+//! Take outputs until one, x, is less than zone, and use x mod n. This is free of modulo bias.
+//!
+//! **Shuffle.** Fisher-Yates from the back: for i from len - 1 down to 1, swap items i and j,
+//! where j is a bounded integer in [0, i + 1).
+//!
+//! **Shoe.** Before shuffling, a shoe of N decks is N copies of a deck in this order: clubs,
+//! diamonds, hearts, spades, each 2 through 10, jack, queen, king, ace.
+//!
+//! # Test vectors
+//!
+//! - SplitMix64 with seed 0 first outputs 0xe220a8397b1dcdaf, 0x6e789e6aa1b965f4,
+//!   0x06c45d188009454f.
+//! - Shuffling the integers 0-9 with seed 42 gives 0 9 5 8 6 4 7 2 1 3.
+//! - The first 13 cards of a 1 deck shoe with seed 2024 are 2c 8d 6s Qd Kh As 8h 7d Js 4d 8c 4h Jh.
+//! - The first 13 cards of a 6 deck shoe with seed 20261015 are Qd Jd 2c Td 7c 9h 5c Ad 5s 2s Jh Th
+//!   3d.
+//!
+//! Any change to the above that changes any output needs a new SHUFFLE_VERSION, and old versions
+//! should keep working so old results can still be verified.
+use crate::deck::{Card, ALL_RANKS, ALL_SUITS};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// The version of the shuffle specified above
+pub const SHUFFLE_VERSION: u32 = 1;
+
+/// Everything needed to reproduce a shuffle. Store it with results of anything that was dealt from
+/// a seeded shuffle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ShuffleId {
+    pub version: u32,
+    pub seed: u64,
+}
+
+impl ShuffleId {
+    /// The given seed with the current shuffle version
+    pub fn new(seed: u64) -> Self {
+        Self {
+            version: SHUFFLE_VERSION,
+            seed,
+        }
+    }
+}
+
+/// The SplitMix64 PRNG
+#[derive(Debug, Clone)]
+pub struct SplitMix64 {
+    state: u64,
+}
+
+impl SplitMix64 {
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// An integer in [0, n). Panics if n is 0.
+    pub fn below(&mut self, n: u64) -> u64 {
+        assert!(n > 0);
+        let zone = u64::MAX - (u64::MAX % n);
+        loop {
+            let x = self.next_u64();
+            if x < zone {
+                return x % n;
+            }
+        }
+    }
+}
+
+/// Shuffle the items in place, the same way for the same seed on every platform and version
+pub fn shuffle<T>(items: &mut [T], seed: u64) {
+    let mut rng = SplitMix64::new(seed);
+    for i in (1..items.len()).rev() {
+        let j = rng.below(i as u64 + 1) as usize;
+        items.swap(i, j);
+    }
+}
+
+/// A shoe of the given number of decks shuffled with the given seed. The first card to deal is
+/// first.
+pub fn shuffled_shoe(num_decks: usize, seed: u64) -> Vec<Card> {
+    let mut cards = Vec::with_capacity(num_decks * ALL_SUITS.len() * ALL_RANKS.len());
+    for _ in 0..num_decks {
+        for suit in ALL_SUITS.iter() {
+            for rank in ALL_RANKS.iter() {
+                cards.push(Card::new(*rank, *suit));
+            }
+        }
+    }
+    shuffle(&mut cards, seed);
+    cards
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::deck::Suit;
+
+    // e.g. "Qd", as in the test vectors
+    fn short(cards: &[Card]) -> Vec<String> {
+        cards
+            .iter()
+            .map(|c| {
+                let suit = match c.suit() {
+                    Suit::Club => 'c',
+                    Suit::Diamond => 'd',
+                    Suit::Heart => 'h',
+                    Suit::Spade => 's',
+                };
+                format!("{}{}", c.rank(), suit)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn prng_vectors() {
+        let mut rng = SplitMix64::new(0);
+        assert_eq!(rng.next_u64(), 0xe220a8397b1dcdaf);
+        assert_eq!(rng.next_u64(), 0x6e789e6aa1b965f4);
+        assert_eq!(rng.next_u64(), 0x06c45d188009454f);
+    }
+
+    #[test]
+    fn shuffle_vectors() {
+        let mut v: Vec<u8> = (0..10).collect();
+        shuffle(&mut v, 42);
+        assert_eq!(v, vec![0, 9, 5, 8, 6, 4, 7, 2, 1, 3]);
+        assert_eq!(
+            short(&shuffled_shoe(1, 2024)[..13]).join(" "),
+            "2c 8d 6s Qd Kh As 8h 7d Js 4d 8c 4h Jh"
+        );
+        let shoe = shuffled_shoe(6, 20261015);
+        assert_eq!(shoe.len(), 6 * 52);
+        assert_eq!(
+            short(&shoe[..13]).join(" "),
+            "Qd Jd 2c Td 7c 9h 5c Ad 5s 2s Jh Th 3d"
+        );
+    }
+}
@@ -1,10 +1,15 @@
 use crate::deck::{Card, Rank, Suit};
 use crate::hand::{Hand, HandError, HandType};
-use serde::de::{Deserialize, Deserializer};
-use serde::ser::{Serialize, SerializeSeq, Serializer};
-use std::collections::HashMap;
+use crate::playstats::PlayStats;
+use crate::resp::Resp;
+#[cfg(feature = "serde")]
+use serde::ser::SerializeSeq;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::BTreeMap;
 use std::convert::From;
 use std::fmt;
+use thiserror::Error;
 
 const HARD_CELLS: usize = 17 * 10;
 const SOFT_CELLS: usize = 9 * 10;
@@ -56,10 +61,10 @@ const PAIR_KEYS: [(u8, u8); PAIR_CELLS] = [
     (22, 2), (22, 3), (22, 4), (22, 5), (22, 6), (22, 7), (22, 8), (22, 9), (22, 10), (22, 11),
 ];
 
-/// Key used in Table
-#[derive(
-    PartialEq, Debug, Copy, Clone, Eq, Hash, serde_derive::Serialize, serde_derive::Deserialize,
-)]
+/// Key used in Table. Ordered as on a strategy card: hard, soft, then pair hands, each by player
+/// value then dealer value.
+#[derive(PartialEq, Debug, Copy, Clone, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct GameDesc {
     pub hand: HandType,
     pub player: u8,
@@ -74,6 +79,186 @@ impl GameDesc {
             dealer,
         }
     }
+
+    /// The cell of a strategy card that the given hand falls in
+    pub fn from_hand(player_hand: &Hand, dealer_shows: Card) -> Self {
+        let p = if player_hand.is_pair() && player_hand.cards[0].rank() == Rank::RA {
+            // player having a pair of aces is a special case. Hand::value() returns 12, which
+            // causes a lookup in the pair take for a pair of 6s. Thus aces are stored with keys
+            // with player hand value 22.
+            22
+        } else {
+            player_hand.value()
+        };
+        let d = if dealer_shows.value() == 1 {
+            11
+        } else {
+            dealer_shows.value()
+        };
+        GameDesc::new(player_hand.hand_type(), p, d)
+    }
+
+    /// A human readable name for the cell, e.g. "Hard 16 vs 10", "Soft 18 vs 9", or "8,8 vs A".
+    /// GameDesc's FromStr parses these back.
+    pub fn label(self) -> String {
+        format!("{} vs {}", self.player_label(), self.dealer_label())
+    }
+
+    /// The player half of label(), e.g. "Hard 16" or "8,8". This is also the name of the cell's
+    /// row.
+    pub fn player_label(self) -> String {
+        match self.hand {
+            HandType::Hard => format!("Hard {}", self.player),
+            HandType::Soft => format!("Soft {}", self.player),
+            HandType::Pair if self.player == 22 => "A,A".to_string(),
+            HandType::Pair => format!("{0},{0}", self.player / 2),
+        }
+    }
+
+    /// The dealer half of label(), e.g. "10" or "A". This is also the name of the cell's column.
+    pub fn dealer_label(self) -> String {
+        match self.dealer {
+            11 => "A".to_string(),
+            d => d.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for GameDesc {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.label())
+    }
+}
+
+impl std::str::FromStr for GameDesc {
+    type Err = TableError;
+
+    /// Parse a label like GameDesc::label() makes. Case and extra whitespace don't matter, and T
+    /// is accepted for 10.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let bad = || TableError::BadLabel(s.to_string());
+        let value = label_value;
+        let lower = s.to_ascii_lowercase();
+        let mut halves = lower.split(" vs ");
+        let (player, dealer) = match (halves.next(), halves.next(), halves.next()) {
+            (Some(p), Some(d), None) => (p.trim(), d),
+            _ => return Err(bad()),
+        };
+        let dealer = value(dealer)
+            .filter(|d| (2..=11).contains(d))
+            .ok_or_else(bad)?;
+        let desc = if let Some(v) = player.strip_prefix("hard") {
+            GameDesc::new(HandType::Hard, value(v).ok_or_else(bad)?, dealer)
+        } else if let Some(v) = player.strip_prefix("soft") {
+            GameDesc::new(HandType::Soft, value(v).ok_or_else(bad)?, dealer)
+        } else {
+            let mut cards = player.split(',').map(value);
+            match (cards.next(), cards.next(), cards.next()) {
+                (Some(Some(11)), Some(Some(11)), None) => GameDesc::new(HandType::Pair, 22, dealer),
+                (Some(Some(a)), Some(Some(b)), None) if a == b && a <= 10 => {
+                    GameDesc::new(HandType::Pair, a * 2, dealer)
+                }
+                _ => return Err(bad()),
+            }
+        };
+        // only cells that are on a strategy card
+        player_hand_from_desc(desc).map_err(|_| bad())?;
+        Ok(desc)
+    }
+}
+
+/// A card value as written in a label: 2-10, T, or A (which is 11)
+fn label_value(v: &str) -> Option<u8> {
+    match v.trim().to_ascii_uppercase().as_str() {
+        "A" => Some(11),
+        "T" => Some(10),
+        v => v.parse::<u8>().ok(),
+    }
+}
+
+/// The values from lo to hi, given as "lo-hi" or a single value
+fn label_range(s: &str) -> Option<std::ops::RangeInclusive<u8>> {
+    let mut ends = s.split('-');
+    let (lo, hi) = match (ends.next(), ends.next(), ends.next()) {
+        (Some(v), None, None) => (label_value(v)?, label_value(v)?),
+        (Some(lo), Some(hi), None) => (label_value(lo)?, label_value(hi)?),
+        _ => return None,
+    };
+    if lo > hi {
+        return None;
+    }
+    Some(lo..=hi)
+}
+
+/// The cells in some rows of a strategy card against some upcards, row by row.
+///
+/// Each of rows is one row like GameDesc::player_label() makes ("Hard 16", "Soft 18", "8,8"), or a
+/// range of hard or soft rows like "Hard 12-16" or "Soft 13-18". A bare total or range, e.g. "16"
+/// or "12-16", is hard. Each of upcards is a comma separated list of upcards and ranges of them,
+/// e.g. "2-6,A". No rows means every row, and no upcards means every upcard.
+pub fn parse_cells(rows: &[&str], upcards: &[&str]) -> Result<Vec<GameDesc>, TableError> {
+    let mut dealers = vec![];
+    for spec in upcards {
+        let bad = || TableError::BadUpcards(spec.to_string());
+        for part in spec.split(',') {
+            for d in label_range(part).ok_or_else(bad)? {
+                if !(2..=11).contains(&d) {
+                    return Err(bad());
+                }
+                if !dealers.contains(&d) {
+                    dealers.push(d);
+                }
+            }
+        }
+    }
+    if dealers.is_empty() {
+        dealers = (2..=11).collect();
+    }
+    let mut players = vec![];
+    for spec in rows {
+        let bad = || TableError::BadRows(spec.to_string());
+        let lower = spec.trim().to_ascii_lowercase();
+        let (hand, range) = if let Some(v) = lower.strip_prefix("hard") {
+            (HandType::Hard, v)
+        } else if let Some(v) = lower.strip_prefix("soft") {
+            (HandType::Soft, v)
+        } else if lower.contains(',') {
+            // a pair, which the label parser already knows how to read
+            let desc = format!("{} vs 2", lower)
+                .parse::<GameDesc>()
+                .map_err(|_| bad())?;
+            players.push((desc.hand, desc.player));
+            continue;
+        } else {
+            (HandType::Hard, lower.as_str())
+        };
+        for player in label_range(range).ok_or_else(bad)? {
+            player_hand_from_desc(GameDesc::new(hand, player, 2)).map_err(|_| bad())?;
+            if !players.contains(&(hand, player)) {
+                players.push((hand, player));
+            }
+        }
+    }
+    if players.is_empty() {
+        let rows = HARD_KEYS
+            .iter()
+            .map(|k| (HandType::Hard, k.0))
+            .chain(SOFT_KEYS.iter().map(|k| (HandType::Soft, k.0)))
+            .chain(PAIR_KEYS.iter().map(|k| (HandType::Pair, k.0)));
+        for row in rows {
+            if !players.contains(&row) {
+                players.push(row);
+            }
+        }
+    }
+    Ok(players
+        .into_iter()
+        .flat_map(|(hand, player)| {
+            dealers
+                .iter()
+                .map(move |dealer| GameDesc::new(hand, player, *dealer))
+        })
+        .collect())
 }
 
 /// Get an arbitrary Hand that matches the given GameDesc.
@@ -170,41 +355,59 @@ pub fn dealer_card_from_desc(desc: GameDesc) -> Result<Card, HandError> {
     Ok(card)
 }
 
-#[derive(PartialEq, Debug)]
+/// Where the cell is in a strategy card file: its subtable ("hard", "soft", or "pair"), and its
+/// row and column in that subtable, counting from 1
+fn cell_coords(desc: GameDesc) -> (&'static str, usize, usize) {
+    let (name, keys): (_, &[(u8, u8)]) = match desc.hand {
+        HandType::Hard => ("hard", &HARD_KEYS),
+        HandType::Soft => ("soft", &SOFT_KEYS),
+        HandType::Pair => ("pair", &PAIR_KEYS),
+    };
+    let i = keys
+        .iter()
+        .position(|k| *k == (desc.player, desc.dealer))
+        .unwrap_or(0);
+    (name, i / 10 + 1, i % 10 + 1)
+}
+
+/// Where the cell is on the printed card, e.g. "hard table, row 12, column 5"
+fn cell_place(desc: GameDesc) -> String {
+    let (table, row, col) = cell_coords(desc);
+    format!("{} table, row {}, column {}", table, row, col)
+}
+
+#[derive(PartialEq, Debug, Error)]
 pub enum TableError {
+    #[error(
+        "Table needs {0} elements but was given {1}{}",
+        if .1 > .0 { " or more" } else { "" }
+    )]
     IncorrectFillLength(usize, usize),
+    #[error("Cannot find item at {0}/{1} because hand is bust")]
     HandIsBust(Hand, Card),
+    #[error(
+        "Table missing keys.{}",
+        if .0.is_empty() { String::new() } else { format!(" {}", .0) }
+    )]
     MissingKeys(String),
-}
-
-impl std::error::Error for TableError {}
-
-impl fmt::Display for TableError {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match self {
-            TableError::IncorrectFillLength(expect, given) => write!(
-                f,
-                "Table needs {} elements but was given {}{}",
-                expect,
-                given,
-                if given > expect { " or more" } else { "" }
-            ),
-            TableError::HandIsBust(hand, card) => write!(
-                f,
-                "Cannot find item at {}/{} because hand is bust",
-                hand, card
-            ),
-            TableError::MissingKeys(s) => write!(
-                f,
-                "Table missing keys.{}",
-                if s.is_empty() {
-                    String::new()
-                } else {
-                    String::from(" ") + s
-                }
-            ),
-        }
-    }
+    /// The string isn't a cell label like GameDesc::label() makes
+    #[error("'{0}' is not a strategy card cell")]
+    BadLabel(String),
+    /// The string isn't a row or range of rows, see parse_cells()
+    #[error("'{0}' is not a row like 'Hard 16' or '8,8' or a range like 'Soft 13-18'")]
+    BadRows(String),
+    /// The string isn't a list of upcards, see parse_cells()
+    #[error("'{0}' is not a list of upcards like '2-6,A'")]
+    BadUpcards(String),
+    /// No hand or upcard fits the cell
+    #[error("{0}")]
+    Hand(#[from] HandError),
+    /// The cell has a Resp no strategy card can have there, like a split in the hard table
+    #[error(
+        "{} ({}): {} splits, but only pairs can be split",
+        .0.label(), cell_place(*.0), .1.code()
+    )]
+    IllegalResp(GameDesc, Resp),
 }
 
 /// Store something in each cell of a blackjack strategy card. E.g. the best move to make.
@@ -218,8 +421,11 @@ impl fmt::Display for TableError {
 /// looks like (e.g. if used to store the best move for a player to make), see the blackjack
 /// strategy cards on the Wizard of Odds website:
 /// https://wizardofodds.com/games/blackjack/strategy/calculator/.
-#[derive(Debug, PartialEq)]
-pub struct Table<T>(HashMap<GameDesc, T>)
+///
+/// Iterating over a Table, and serializing one, always goes through its cells in that same order,
+/// so anything made from a Table comes out the same every time.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Table<T>(BTreeMap<GameDesc, T>)
 where
     T: PartialEq + Copy;
 
@@ -236,13 +442,25 @@ where
     where
         I: IntoIterator<Item = T>,
     {
-        let mut t = Self {
-            0: HashMap::with_capacity(NUM_CELLS),
-        };
+        let mut t = Self(BTreeMap::new());
         t.fill(vals)?;
         Ok(t)
     }
 
+    /// A Table with the same value in every cell
+    pub fn filled(val: T) -> Self {
+        let mut t = Self(BTreeMap::new());
+        let keys = HARD_KEYS
+            .iter()
+            .map(|k| (HandType::Hard, k))
+            .chain(SOFT_KEYS.iter().map(|k| (HandType::Soft, k)))
+            .chain(PAIR_KEYS.iter().map(|k| (HandType::Pair, k)));
+        for (hand, (player, dealer)) in keys {
+            t.0.insert(GameDesc::new(hand, *player, *dealer), val);
+        }
+        t
+    }
+
     fn fill<I>(&mut self, vals: I) -> Result<(), TableError>
     where
         I: IntoIterator<Item = T>,
@@ -294,27 +512,7 @@ where
     }
 
     fn key(player_hand: &Hand, dealer_shows: Card) -> GameDesc {
-        let p = if player_hand.is_pair() && player_hand.cards[0].rank() == Rank::RA {
-            // player having a pair of aces is a special case. Hand::value() returns 12, which
-            // causes a lookup in the pair take for a pair of 6s. Thus aces are stored with keys
-            // with player hand value 22.
-            22
-        } else {
-            player_hand.value()
-        };
-        let d = if dealer_shows.value() == 1 {
-            11
-        } else {
-            dealer_shows.value()
-        };
-        let ty = if player_hand.is_pair() {
-            HandType::Pair
-        } else if player_hand.is_soft() {
-            HandType::Soft
-        } else {
-            HandType::Hard
-        };
-        GameDesc::new(ty, p, d)
+        GameDesc::from_hand(player_hand, dealer_shows)
     }
 
     /// Lookup and return the value stored at the given location in the table, if it exists.
@@ -373,6 +571,7 @@ where
     /// An internal-only sanity check for help during final deserialization
     ///
     /// Checks if the given table has all the correct keys in its subtables
+    #[cfg_attr(not(feature = "serde"), allow(dead_code))]
     fn has_all_keys(&self) -> bool {
         // easy check: do all subtables have the right number of keys
         self.0.keys().filter(|k| k.hand == HandType::Hard).count() == HARD_CELLS
@@ -388,9 +587,12 @@ where
     ///
     /// Takes arrays for the hard, soft, and pair subtables, checks they are the correct length,
     /// assumes they have all the right keys in their key/value pairs, and builds the Table.
+    #[cfg_attr(not(feature = "serde"), allow(dead_code))]
     fn from_single_vec(v: Vec<(GameDesc, T)>) -> Result<Self, TableError> {
-        assert_eq!(v.len(), NUM_CELLS);
-        let mut d = HashMap::with_capacity(NUM_CELLS);
+        if v.len() != NUM_CELLS {
+            return Err(TableError::IncorrectFillLength(NUM_CELLS, v.len()));
+        }
+        let mut d = BTreeMap::new();
         for kv in v.into_iter() {
             d.insert(kv.0, kv.1);
         }
@@ -465,6 +667,11 @@ where
         (hards, softs, pairs)
     }
 
+    /// The value in the given cell, if it's in the table
+    pub fn get_desc(&self, desc: GameDesc) -> Option<T> {
+        self.0.get(&desc).copied()
+    }
+
     pub fn values(&self) -> impl Iterator<Item = &T> {
         self.0.values()
     }
@@ -473,9 +680,60 @@ where
         self.0.values_mut()
     }
 
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (&GameDesc, &mut T)> {
+        self.0.iter_mut()
+    }
+
     pub fn iter(&self) -> impl Iterator<Item = (&GameDesc, &T)> {
         self.0.iter()
     }
+
+    /// A table with the value of each cell replaced by f of the cell and its value
+    pub fn map<U, F>(&self, f: F) -> Table<U>
+    where
+        U: PartialEq + Copy,
+        F: Fn(GameDesc, T) -> U,
+    {
+        Table(
+            self.0
+                .iter()
+                .map(|(desc, v)| (*desc, f(*desc, *v)))
+                .collect(),
+        )
+    }
+
+    /// A table with the value of each cell being f of this table's and other's values in it.
+    /// Tables always have every cell, so every cell is in both.
+    pub fn zip_with<U, V, F>(&self, other: &Table<U>, f: F) -> Table<V>
+    where
+        U: PartialEq + Copy,
+        V: PartialEq + Copy,
+        F: Fn(T, U) -> V,
+    {
+        self.map(|desc, v| f(v, other.get_desc(desc).expect("tables have every cell")))
+    }
+
+    /// Combine every cell into one value, starting from init, in strategy card order so that
+    /// order-dependent folds give the same result every time
+    pub fn fold_cells<A, F>(&self, init: A, f: F) -> A
+    where
+        F: Fn(A, GameDesc, T) -> A,
+    {
+        self.0.iter().fold(init, |acc, (desc, v)| f(acc, *desc, *v))
+    }
+
+    /// The cells whose value keep returns true for, in strategy card order. E.g. every cell a card
+    /// says to split in, with `|resp| resp == Resp::Split`.
+    pub fn cells_where<F>(&self, keep: F) -> Vec<GameDesc>
+    where
+        F: Fn(T) -> bool,
+    {
+        self.0
+            .iter()
+            .filter(|(_, v)| keep(**v))
+            .map(|(desc, _)| *desc)
+            .collect()
+    }
 }
 
 impl<T> IntoIterator for Table<T>
@@ -483,7 +741,7 @@ where
     T: PartialEq + Copy,
 {
     type Item = (GameDesc, T);
-    type IntoIter = std::collections::hash_map::IntoIter<GameDesc, T>;
+    type IntoIter = std::collections::btree_map::IntoIter<GameDesc, T>;
 
     fn into_iter(self) -> Self::IntoIter {
         self.0.into_iter()
@@ -495,16 +753,71 @@ where
     T: PartialEq + Copy + std::ops::AddAssign,
 {
     fn add_assign(&mut self, rhs: Self) {
-        for (game_desc, val) in rhs.iter() {
-            let player = player_hand_from_desc(*game_desc).unwrap();
-            let dealer = dealer_card_from_desc(*game_desc).unwrap();
-            let mut agg = self.get(&player, dealer).unwrap();
-            agg += *val;
-            self.update(&player, dealer, agg).unwrap();
+        *self = self.zip_with(&rhs, |mut agg, val| {
+            agg += val;
+            agg
+        });
+    }
+}
+
+impl Table<PlayStats> {
+    /// The fraction of each cell's hands the player got right, or None for cells they haven't
+    /// seen
+    pub fn accuracy(&self) -> Table<Option<f32>> {
+        self.ratio(|s| (s.correct(), s.seen()))
+    }
+
+    /// A ratio of two counts in each cell's stats, given as (numerator, denominator) by f, or None
+    /// for cells where the denominator is 0
+    pub fn ratio<F>(&self, f: F) -> Table<Option<f32>>
+    where
+        F: Fn(PlayStats) -> (u32, u32),
+    {
+        self.map(|_, s| match f(s) {
+            (_, 0) => None,
+            (num, den) => Some(num as f32 / den as f32),
+        })
+    }
+}
+
+impl Table<Resp> {
+    /// Construct a strategy card's Table like Table::new(), but refuse a Resp no card can have in
+    /// its cell (see check_resps())
+    pub fn new_checked<I>(vals: I) -> Result<Self, TableError>
+    where
+        I: IntoIterator<Item = Resp>,
+    {
+        let t = Self::new(vals)?;
+        t.check_resps()?;
+        Ok(t)
+    }
+
+    /// Returns TableError::IllegalResp for the first cell, in card order, with a Resp no strategy
+    /// card can have there: a split anywhere but the pair table. Problems that depend on the
+    /// card's rules are left to crate::validate.
+    pub fn check_resps(&self) -> Result<(), TableError> {
+        match self
+            .iter()
+            .find(|(desc, resp)| resp.splits() && desc.hand != HandType::Pair)
+        {
+            Some((desc, resp)) => Err(TableError::IllegalResp(*desc, *resp)),
+            None => Ok(()),
         }
     }
 }
 
+/// Deserialize a strategy card's Table, refusing it if check_resps() does
+#[cfg(feature = "serde")]
+pub(crate) fn deserialize_resp_table<'de, D>(deserializer: D) -> Result<Table<Resp>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let t = Table::<Resp>::deserialize(deserializer)?;
+    t.check_resps().map_err(serde::de::Error::custom)?;
+    Ok(t)
+}
+
+#[cfg(feature = "serde")]
 impl<T> Serialize for Table<T>
 where
     T: PartialEq + Copy + Serialize,
@@ -521,6 +834,7 @@ where
     }
 }
 
+#[cfg(feature = "serde")]
 impl<'de, T> Deserialize<'de> for Table<T>
 where
     T: PartialEq + Copy + Deserialize<'de>,
@@ -530,8 +844,6 @@ where
         D: Deserializer<'de>,
     {
         let v: Vec<(GameDesc, T)> = Vec::deserialize(deserializer)?;
-        assert_eq!(v.len(), NUM_CELLS);
-        //Ok(Self::from_single_vec(hard, soft, pair))
         Self::from_single_vec(v).map_err(serde::de::Error::custom)
     }
 }
@@ -542,7 +854,6 @@ mod tests {
     use crate::deck::{Card, Rank, Suit, ALL_RANKS};
     use crate::hand::{Hand, HandError, HandType};
     use crate::resp::resps_from_buf;
-    use serde_json;
     use std::iter::repeat;
 
     const SUIT: Suit = Suit::Club;
@@ -721,6 +1032,10 @@ P  P  P  P  P  P  P  P  P  P
                 TableError::IncorrectFillLength(NUM_CELLS, NUM_CELLS + 1)
             );
         }
+        assert_eq!(
+            TableError::IncorrectFillLength(NUM_CELLS, NUM_CELLS + 1).to_string(),
+            "Table needs 360 elements but was given 361 or more"
+        );
     }
 
     #[test]
@@ -735,6 +1050,36 @@ P  P  P  P  P  P  P  P  P  P
         assert!(Table::new(resps_from_buf(T1.as_bytes()).unwrap()).is_ok());
     }
 
+    #[test]
+    fn illegal_resps() {
+        let mut resps = resps_from_buf(T1.as_bytes()).unwrap();
+        assert!(Table::new_checked(resps.clone()).is_ok());
+        // Hard 12 vs 2, and Soft 18 vs A after it
+        resps[70] = Resp::Split;
+        resps[HARD_CELLS + 59] = Resp::SurrenderElseSplit;
+        let hard_12: GameDesc = "Hard 12 vs 2".parse().unwrap();
+        let e = Table::new_checked(resps.clone()).unwrap_err();
+        assert_eq!(e, TableError::IllegalResp(hard_12, Resp::Split));
+        assert_eq!(
+            e.to_string(),
+            "Hard 12 vs 2 (hard table, row 8, column 1): P splits, but only pairs can be split"
+        );
+        resps[70] = Resp::Hit;
+        let e = Table::new_checked(resps.clone()).unwrap_err();
+        assert!(e
+            .to_string()
+            .starts_with("Soft 18 vs A (soft table, row 6, column 10)"));
+        // the same goes for a strategy card's JSON
+        #[cfg(feature = "serde")]
+        {
+            use crate::basicstrategy::BasicStrategy;
+            let bs = BasicStrategy::new(None, Table::new(resps).unwrap());
+            let json = serde_json::to_string(&bs).unwrap();
+            let e = serde_json::from_str::<BasicStrategy>(&json).unwrap_err();
+            assert!(e.to_string().contains("Soft 18 vs A"));
+        }
+    }
+
     #[test]
     fn get_1() {
         // all 2-card hands against all dealer show cards have a best response
@@ -828,6 +1173,7 @@ P  P  P  P  P  P  P  P  P  P
     }
 
     #[test]
+    #[cfg(feature = "serde")]
     fn serialize_identity() {
         // result of serialize -> deserialize is the same as the input
         let t_in = Table::new(0..NUM_CELLS as u16).unwrap();
@@ -961,4 +1307,130 @@ P  P  P  P  P  P  P  P  P  P
         let num_worth_1 = t1.values().filter(|&&v| v == 1).count();
         assert_eq!(num_worth_1, 2);
     }
+
+    #[test]
+    fn filled() {
+        assert_eq!(Table::filled(7), Table::new(vec![7; NUM_CELLS]).unwrap());
+        let desc = GameDesc::new(HandType::Hard, 30, 2);
+        let e = TableError::from(HandError::ImpossibleGameDesc(desc));
+        let source = std::error::Error::source(&e).unwrap();
+        assert_eq!(source.to_string(), e.to_string());
+    }
+
+    #[test]
+    fn card_order() {
+        let t = Table::new(0..NUM_CELLS as u16).unwrap();
+        let values: Vec<u16> = t.values().copied().collect();
+        assert_eq!(values, (0..NUM_CELLS as u16).collect::<Vec<_>>());
+        let descs: Vec<GameDesc> = t.iter().map(|(d, _)| *d).collect();
+        assert_eq!(descs[0], GameDesc::new(HandType::Hard, 5, 2));
+        assert_eq!(descs[NUM_CELLS - 1], GameDesc::new(HandType::Pair, 22, 11));
+        let into: Vec<u16> = t.into_iter().map(|(_, v)| v).collect();
+        assert_eq!(into, values);
+    }
+
+    #[test]
+    fn map_zip_fold() {
+        let t = Table::new(0..360).unwrap();
+        let doubled = t.map(|_, v| v * 2);
+        assert_eq!(
+            doubled.get_desc(GameDesc::new(HandType::Hard, 5, 3)),
+            Some(2)
+        );
+        let pairs = t.map(|desc, _| desc.hand == HandType::Pair);
+        assert_eq!(pairs.values().filter(|p| **p).count(), 100);
+        let diff = doubled.zip_with(&t, |a, b| a - b);
+        assert_eq!(diff, t);
+        // in card order, so the first cell folded in is hard 5 vs 2
+        let order = t.fold_cells(vec![], |mut acc, _, v| {
+            acc.push(v);
+            acc
+        });
+        assert_eq!(order, (0..360).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn accuracy() {
+        let mut stats = Table::new(vec![PlayStats::new(); 360]).unwrap();
+        let desc = GameDesc::new(HandType::Soft, 18, 9);
+        for (d, s) in stats.iter_mut() {
+            if *d == desc {
+                *s = PlayStats::from_counts(4, 3, 1).unwrap();
+            }
+        }
+        let acc = stats.accuracy();
+        assert_eq!(acc.get_desc(desc), Some(Some(0.75)));
+        assert_eq!(acc.values().filter(|a| a.is_none()).count(), 359);
+        let streak = stats.ratio(|s| (s.streak(), s.correct()));
+        assert_eq!(streak.get_desc(desc), Some(Some(1.0 / 3.0)));
+    }
+
+    #[test]
+    fn labels() {
+        let label = |ty, p, d| GameDesc::new(ty, p, d).label();
+        assert_eq!(label(HandType::Hard, 16, 10), "Hard 16 vs 10");
+        assert_eq!(label(HandType::Soft, 18, 9), "Soft 18 vs 9");
+        assert_eq!(label(HandType::Pair, 16, 11), "8,8 vs A");
+        assert_eq!(label(HandType::Pair, 22, 2), "A,A vs 2");
+        // every cell survives a round trip
+        let keys = HARD_KEYS
+            .iter()
+            .map(|k| (HandType::Hard, k))
+            .chain(SOFT_KEYS.iter().map(|k| (HandType::Soft, k)))
+            .chain(PAIR_KEYS.iter().map(|k| (HandType::Pair, k)));
+        for (ty, (p, d)) in keys {
+            let desc = GameDesc::new(ty, *p, *d);
+            assert_eq!(desc.label().parse::<GameDesc>().unwrap(), desc);
+        }
+        let parse = |s: &str| s.parse::<GameDesc>();
+        assert_eq!(
+            parse(" soft 18  vs  t"),
+            Ok(GameDesc::new(HandType::Soft, 18, 10))
+        );
+        assert_eq!(parse("a,a vs a"), Ok(GameDesc::new(HandType::Pair, 22, 11)));
+        for bad in &[
+            "Hard 4 vs 10",
+            "Soft 12 vs 2",
+            "8,9 vs 2",
+            "8,8 vs 1",
+            "8,8",
+            "200,200 vs 2",
+        ] {
+            assert_eq!(parse(bad), Err(TableError::BadLabel(bad.to_string())));
+        }
+    }
+
+    #[test]
+    fn cells() {
+        let labels = |rows: &[&str], upcards: &[&str]| {
+            parse_cells(rows, upcards)
+                .unwrap()
+                .into_iter()
+                .map(|d| d.label())
+                .collect::<Vec<_>>()
+        };
+        let region = labels(&["12-16", "Soft 18", "a,a"], &["2-4", "T,a"]);
+        assert_eq!(region.len(), 7 * 5);
+        assert_eq!(region[0], "Hard 12 vs 2");
+        assert_eq!(region[4], "Hard 12 vs A");
+        assert_eq!(region[34], "A,A vs A");
+        assert_eq!(labels(&["hard 16"], &[]).len(), 10);
+        assert_eq!(labels(&[], &["6"]).len(), 36);
+        assert_eq!(
+            parse_cells(&["Hard 3-5"], &[]),
+            Err(TableError::BadRows("Hard 3-5".to_string()))
+        );
+        assert_eq!(
+            parse_cells(&["16-12"], &[]),
+            Err(TableError::BadRows("16-12".to_string()))
+        );
+        assert_eq!(
+            parse_cells(&["8,9"], &[]),
+            Err(TableError::BadRows("8,9".to_string()))
+        );
+        assert_eq!(
+            parse_cells(&[], &["1-6"]),
+            Err(TableError::BadUpcards("1-6".to_string()))
+        );
+    }
 }
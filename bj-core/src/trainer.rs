@@ -0,0 +1,1789 @@
+//! The basic strategy training loop, independent of any front end.
+//!
+//! Every trainer does the same thing: show the player a hand, take the action they choose, grade
+//! it against a strategy card, update their statistics, and deal the next hand. Trainer does all
+//! of that and leaves only input and output to the front end. A front end:
+//!
+//! 1. Shows Trainer::hand() and enables the buttons for which Trainer::is_legal() is true.
+//! 2. Calls Trainer::act() with whatever the player chose.
+//! 3. Shows the returned Graded result, then goes back to step 1 (act() already dealt the next
+//!    hand).
+//!
+//! The player's notes on the current cell (Trainer::note()) should be shown along with the hand,
+//! and Graded::note along with a wrong answer.
+//!
+//! Beginners can train in Mode::HitStand, which only deals the hands they are ready for and only
+//! asks whether to hit or stand. The front end doesn't need to do anything special for it beyond
+//! step 1.
+//!
+//! Dealt hands are opening hands, unless Trainer::set_multi_card() is on: then about
+//! MULTI_CARD_CHANCE of the hard and soft hands are dealt as three or four cards, like a hand the
+//! player has hit, so doubling and surrendering aren't allowed and the card's fallbacks (the "else
+//! hit" of Dh) are the correct answer. To train on a hand made by splitting, set it with
+//! Trainer::set_hand() and then Trainer::set_split_state(), and the card's rules for doubling,
+//! resplitting, and playing split aces decide what is legal and correct.
+//!
+//! With Trainer::set_play_splits() on, a correct split doesn't end the exercise: each card of the
+//! pair is dealt a second card, and the resulting hands are played out one after the other, left
+//! to right, under the same split rules. A correct hit on a split hand deals it another card.
+//! Every decision is graded like any other, and a wrong one ends the round and deals a new hand.
+//! Hands that leave nothing to decide, like 21 or split aces that can't be hit, are skipped.
+//! Trainer::split_state() says the current hand came from a split, and
+//! Trainer::split_hands_waiting() how many more are still to be played. Drill packs always deal
+//! their own hands, so splits aren't played out while one is set.
+//!
+//! Coach mode (Trainer::set_coach()) watches for rows of the card the player keeps getting wrong.
+//! Once they miss hands in the same row (e.g. soft 18, against any upcard) enough times, the next
+//! COACH_DRILL_HANDS hands are all dealt from that row, then normal dealing resumes. Graded::coach
+//! says when a drill starts or ends, so the front end can tell the player, and Trainer::drill()
+//! is the drill in progress.
+//!
+//! Blackjacks are dealt like any other soft 21. With Trainer::set_even_money() on, a blackjack
+//! against an ace instead asks whether to take even money: while Trainer::even_money_offered(),
+//! no action is legal, and the front end asks the player and calls Trainer::act_even_money().
+//!
+//! Trainer::weighted_accuracy() is the player's accuracy with common hands counting more than
+//! rare ones (see the frequency module). With Trainer::set_frequency_weighting(), weighted dealing
+//! also favors the cells that come up most.
+//!
+//! Weighted dealing can also refresh stale knowledge: with Trainer::set_refresh_after(), a cell
+//! not seen in more than the given number of days is dealt as often as one never seen, however
+//! well the player did on it back then. The trainer has no clock of its own (there isn't one in
+//! wasm), so the front end gives it one with Trainer::set_clock(). With a clock, every graded hand
+//! records when its cell was last seen in the stats.
+//!
+//! Drill packs (see the drillpack module) are played with Trainer::set_pack(). While one is set,
+//! every hand is dealt from it, regardless of the mode, the fixed upcard, or coach mode, and
+//! Trainer::pack_progress() says how the player is doing on the current run of it. Once a run is
+//! finished, the next answer starts a new one.
+//!
+//! Target practice (Trainer::start_target()) drills one cell the player picks, e.g. by clicking it
+//! on the card: the next TARGET_HANDS hands are all in that cell, in varied suits and compositions,
+//! then dealing goes back to normal. It takes over from everything else that decides what's dealt,
+//! and splits aren't played out during it. Graded::target_ended says when it's over, and
+//! Trainer::target() is the target practice in progress.
+//!
+//! To drill one column of the card at a time, Trainer::set_upcard() makes every hand be against
+//! the same dealer upcard. Front ends can use next_drill_upcard() to move on to the next column
+//! each session.
+//!
+//! A wrong answer on a compound response like SurrenderElseStand is either a Mistake::Primary
+//! (the player could surrender but didn't) or a Mistake::Fallback (they couldn't, and didn't
+//! stand). Graded::mistake says which, Graded::fallback_detail() explains a wrong fallback, and
+//! Trainer::confusion() tallies both along with what was pressed against what was correct.
+//!
+//! Persistence is also left to the front end: Trainer::stats(), Trainer::streak(),
+//! Trainer::shields(), Trainer::notes(), and Trainer::hand() are everything needed to rebuild a
+//! Trainer later with Trainer::new(), Trainer::set_stats(), Trainer::set_shields(),
+//! Trainer::set_notes(), and Trainer::set_hand().
+//!
+//! Players who want it can turn on streak shields with Trainer::set_shields_enabled(). Every
+//! streak milestone (see is_streak_milestone()) earns a shield, up to MAX_SHIELDS, and a wrong
+//! answer uses one up instead of ending the streak. The answer still counts as wrong in the
+//! stats. Shields are off by default, and anything where the streak has to be earned straight,
+//! like a time trial or an exam, should leave them off.
+//!
+//! Stats are kept separately for each card, in segments named by BasicStrategy::id(), as stats
+//! graded against one card say little about how well the player knows another. Trainer::stats()
+//! are the stats for the current card, and switching cards switches to the new card's segment.
+//! Front ends that let players switch cards should save stats per segment, using
+//! Trainer::stats_segments(), Trainer::stats_for(), and Trainer::set_stats_for(). When a player
+//! switches to a card they have no stats for, such as a slightly edited copy of their old one, the
+//! front end can offer to carry their old stats over with Trainer::migrate_stats().
+use crate::basicstrategy::rules::{Allowed, SplitState, Surrender};
+use crate::basicstrategy::BasicStrategy;
+use crate::button::GameButton;
+use crate::confusion::Confusion;
+use crate::correct_resp::{expected_button, mistake, Mistake};
+use crate::deck::{rand_card, Card, Rank};
+use crate::drillpack::{DrillPack, DrillPackError, PackDeal, PackProgress};
+use crate::frequency::{cell_frequencies, weighted_accuracy};
+use crate::hand::{rand_multi_card_hand, Hand, HandType};
+use crate::notes::Notes;
+use crate::playstats::{PlayStats, Refresh};
+use crate::resp::Resp;
+use crate::table::{player_hand_from_desc, GameDesc, Table, TableError};
+use crate::utils::{rand_next_hand_freq_where, rand_next_hand_where, uniform_rand_2card_hand};
+use rand::prelude::*;
+use std::collections::HashMap;
+use thiserror::Error;
+
+/// How the next hand is chosen
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub enum HandSource {
+    #[default]
+    /// Each card is drawn from the top of a shuffled infinite deck
+    Uniform,
+    /// A cell of the strategy card is chosen, weighted by how poorly the player has done on it, and
+    /// a random hand constructed to fit it
+    Weighted,
+}
+
+/// Which hands are dealt and which actions the player may take
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub enum Mode {
+    #[default]
+    /// Every hand, and every action the rules allow
+    Full,
+    /// Only hit and stand, for beginners. The correct action is whatever the card says to do when
+    /// doubling, surrendering, and splitting aren't allowed. Pairs and soft hands are only dealt if
+    /// asked for.
+    HitStand { pairs: bool, softs: bool },
+}
+
+impl Mode {
+    /// Whether hands in the given cell are dealt in this mode
+    pub fn deals(self, desc: &GameDesc) -> bool {
+        match self {
+            Self::Full => true,
+            Self::HitStand { pairs, softs } => match desc.hand {
+                HandType::Hard => true,
+                HandType::Soft => softs,
+                HandType::Pair => pairs,
+            },
+        }
+    }
+}
+
+/// With multi-card hands on, the chance that a hard or soft hand is dealt as three or four cards
+pub const MULTI_CARD_CHANCE: f64 = 0.5;
+
+/// How many hands a coach mode drill lasts
+pub const COACH_DRILL_HANDS: u32 = 5;
+
+/// A run of hands all dealt from one row of the card, to drill a row the player keeps missing
+#[derive(Debug, PartialEq, Clone)]
+pub struct Drill {
+    pub hand: HandType,
+    /// The player total of the row, as in GameDesc
+    pub player: u8,
+    /// How many more hands the drill lasts, including the one being dealt
+    pub remaining: u32,
+}
+
+impl Drill {
+    /// The name of the row being drilled, e.g. "Soft 18" or "8,8"
+    pub fn label(&self) -> String {
+        GameDesc::new(self.hand, self.player, 2).player_label()
+    }
+
+    fn contains(&self, desc: &GameDesc) -> bool {
+        desc.hand == self.hand && desc.player == self.player
+    }
+}
+
+/// How many hands target practice on a cell lasts
+pub const TARGET_HANDS: u32 = 10;
+
+/// A run of hands all dealt in one cell of the card, picked by the player
+#[derive(Debug, PartialEq, Clone)]
+pub struct Target {
+    pub desc: GameDesc,
+    /// How many more hands the practice lasts, including the one being dealt
+    pub remaining: u32,
+}
+
+/// A change in coach mode caused by grading a hand
+#[derive(Debug, PartialEq, Clone)]
+pub enum CoachEvent {
+    /// The player has missed this row too often, so the next hands drill it
+    DrillStarted(Drill),
+    /// The drill is over and hands are dealt normally again
+    DrillEnded,
+}
+
+#[derive(Debug, PartialEq, Error)]
+pub enum TrainerError {
+    /// The action isn't allowed for the hand, e.g. splitting a non-pair
+    #[error("{0} is not a legal response to {1}/{2}")]
+    IllegalAction(GameButton, Hand, Card),
+    /// The dealer upcard value isn't 2-11
+    #[error("{0} is not a dealer upcard value (2-10, or 11 for ace)")]
+    BadUpcard(u8),
+    /// The hand is in a cell of a partial card that the player hasn't learned yet, so there's
+    /// nothing to grade it against
+    #[error("{} isn't learned yet on this card", .0.label())]
+    NotLearned(GameDesc),
+    #[error("{0}")]
+    TableError(#[from] TableError),
+}
+
+/// The result of the player acting on a hand
+#[derive(Debug, PartialEq)]
+pub struct Graded {
+    /// What the player did
+    pub action: GameButton,
+    /// What the strategy card says to do, as simplified by the Mode
+    pub correct: Resp,
+    /// The button that follows the correct response given what was allowed. Differs from correct
+    /// when its first choice wasn't allowed, e.g. Stand for SurrenderElseStand without surrender.
+    pub expected: GameButton,
+    /// What the player got wrong, if anything
+    pub mistake: Option<Mistake>,
+    /// The hand the player acted on
+    pub player: Hand,
+    pub dealer: Card,
+    pub is_correct: bool,
+    /// The player's streak before this hand. If they got this hand wrong, this is the streak they
+    /// just lost.
+    pub old_streak: u32,
+    /// The player got the hand wrong, but a streak shield kept their streak going
+    pub shielded: bool,
+    /// The player's note on the hand's cell, if any
+    pub note: Option<String>,
+    /// Whether grading the hand started or ended a coach mode drill
+    pub coach: Option<CoachEvent>,
+    /// Whether this was the last hand of target practice
+    pub target_ended: bool,
+}
+
+impl Graded {
+    /// The strategy card cell the hand was in, e.g. for naming it with GameDesc::label()
+    pub fn cell(&self) -> GameDesc {
+        GameDesc::from_hand(&self.player, self.dealer)
+    }
+
+    /// Why the expected button was the fallback, if the player got the fallback wrong. E.g.
+    /// "Surrender isn't allowed here, so the fallback is Stand."
+    pub fn fallback_detail(&self) -> Option<String> {
+        match self.mistake {
+            Some(Mistake::Fallback) => Some(format!(
+                "{} isn't allowed here, so the fallback is {}.",
+                GameButton::from(self.correct),
+                self.expected
+            )),
+            _ => None,
+        }
+    }
+}
+
+/// The result of the player deciding whether to take even money
+#[derive(Debug, PartialEq)]
+pub struct EvenMoneyGraded {
+    pub took: bool,
+    /// The hand the player decided on, always a blackjack against an ace
+    pub player: Hand,
+    pub dealer: Card,
+    pub is_correct: bool,
+    /// The player's streak before this hand
+    pub old_streak: u32,
+    /// The player got the hand wrong, but a streak shield kept their streak going
+    pub shielded: bool,
+    /// Whether grading the hand started or ended a coach mode drill
+    pub coach: Option<CoachEvent>,
+    /// Whether this was the last hand of target practice
+    pub target_ended: bool,
+}
+
+/// Streaks that are milestones. After the last one, every multiple of it is a milestone too.
+const STREAK_MILESTONES: [u32; 4] = [10, 25, 50, 100];
+
+/// The most streak shields a player can hold at once
+pub const MAX_SHIELDS: u32 = 3;
+
+/// Whether reaching the given streak is worth celebrating, and earns a streak shield
+pub fn is_streak_milestone(streak: u32) -> bool {
+    let last = STREAK_MILESTONES[STREAK_MILESTONES.len() - 1];
+    STREAK_MILESTONES.contains(&streak) || (streak > last && streak.is_multiple_of(last))
+}
+
+/// The upcard to drill after the given one, cycling through 2-10 then ace (11). Starts at 2 if
+/// there was no previous upcard.
+pub fn next_drill_upcard(last: Option<u8>) -> u8 {
+    match last {
+        Some(v) if (2..11).contains(&v) => v + 1,
+        _ => 2,
+    }
+}
+
+/// Whether hands in the given cell are dealt in the given mode and with the given fixed upcard
+fn is_dealt(mode: Mode, upcard: Option<u8>, desc: &GameDesc) -> bool {
+    mode.deals(desc) && upcard.is_none_or(|v| v == desc.dealer)
+}
+
+/// How many uniform hands deal() tries before giving up on finding one the player has learned
+const UNIFORM_TRIES: usize = 1000;
+
+fn new_play_stats() -> Table<PlayStats> {
+    Table::filled(PlayStats::new())
+}
+
+#[derive(Debug)]
+pub struct Trainer {
+    bs: BasicStrategy,
+    /// Stats for the current card
+    stats: Table<PlayStats>,
+    /// Stats for every other card that has any, by BasicStrategy::id()
+    other_stats: HashMap<String, Table<PlayStats>>,
+    streak: u32,
+    /// Streak shields the player holds, if they're enabled
+    shields: u32,
+    shields_enabled: bool,
+    notes: Notes,
+    source: HandSource,
+    mode: Mode,
+    /// If set, the value of the dealer upcard of every hand
+    upcard: Option<u8>,
+    hand: (Hand, Card),
+    /// Where the current hand came from, if it was made by splitting
+    split: SplitState,
+    /// Whether a correct split is followed by playing the hands it makes
+    play_splits: bool,
+    /// Whether some hard and soft hands are dealt as three or four cards
+    multi_card: bool,
+    /// Hands made by splitting that are still to be played, the next one last
+    split_hands: Vec<Hand>,
+    /// If coach mode is on, how many misses in a row of the card start a drill
+    coach: Option<u32>,
+    /// Misses this session in each row of the card, by (hand type, player total), for coach mode
+    misses: HashMap<(HandType, u8), u32>,
+    drill: Option<Drill>,
+    target: Option<Target>,
+    /// Whether blackjacks against an ace ask about even money
+    even_money: bool,
+    /// If weighting by frequency, how often each cell of the card comes up
+    frequencies: Option<Table<f64>>,
+    /// What the player pressed against what was correct, this session
+    confusion: Confusion,
+    /// The drill pack being played, if any
+    pack: Option<ActivePack>,
+    /// The time, in seconds since the Unix epoch, if the front end gave a way to tell it
+    clock: Option<fn() -> u64>,
+    /// If set, weighted dealing treats cells not seen in this many days like unseen ones
+    refresh_after: Option<u32>,
+}
+
+/// A drill pack being played, with the hands it deals and the current run's progress
+#[derive(Debug)]
+struct ActivePack {
+    pack: DrillPack,
+    deal: PackDeal,
+    progress: PackProgress,
+}
+
+impl Trainer {
+    /// Start training on the given card with no stats, and deal the first hand
+    pub fn new(bs: BasicStrategy, source: HandSource) -> Self {
+        let stats = new_play_stats();
+        let hand = Self::deal(source, Mode::Full, None, &bs, &stats, None, None);
+        Self {
+            bs,
+            stats,
+            other_stats: HashMap::new(),
+            streak: 0,
+            shields: 0,
+            shields_enabled: false,
+            notes: Notes::new(),
+            source,
+            mode: Mode::Full,
+            upcard: None,
+            hand,
+            split: SplitState::default(),
+            play_splits: false,
+            multi_card: false,
+            split_hands: vec![],
+            coach: None,
+            misses: HashMap::new(),
+            drill: None,
+            target: None,
+            even_money: false,
+            frequencies: None,
+            confusion: Confusion::new(),
+            pack: None,
+            clock: None,
+            refresh_after: None,
+        }
+    }
+
+    /// Deal a hand in a cell the mode and upcard deal and that the card has learned. If the
+    /// partial card has learned none of those cells, the hand is in one it hasn't.
+    fn deal(
+        source: HandSource,
+        mode: Mode,
+        upcard: Option<u8>,
+        bs: &BasicStrategy,
+        stats: &Table<PlayStats>,
+        freq: Option<&Table<f64>>,
+        refresh: Option<Refresh>,
+    ) -> (Hand, Card) {
+        let keep = |desc: &GameDesc| is_dealt(mode, upcard, desc) && bs.is_learned(*desc);
+        if source == HandSource::Weighted {
+            if let Some(hand) = rand_next_hand_freq_where(stats, freq, refresh, keep) {
+                return hand;
+            }
+        }
+        for _ in 0..UNIFORM_TRIES {
+            let (player, dealer) = uniform_rand_2card_hand();
+            if keep(&GameDesc::from_hand(&player, dealer)) {
+                return (player, dealer);
+            }
+        }
+        // A partial card with few cells learned can be unlikely to be dealt into
+        if let Some(hand) = rand_next_hand_where(stats, keep) {
+            return hand;
+        }
+        log::warn!("No cell the card has learned can be dealt, dealing one it hasn't");
+        // Every mode deals all the hard hands, and any upcard comes up at least 1/13 of the time,
+        // so this won't take long
+        loop {
+            let (player, dealer) = uniform_rand_2card_hand();
+            if is_dealt(mode, upcard, &GameDesc::from_hand(&player, dealer)) {
+                break (player, dealer);
+            }
+        }
+    }
+
+    /// Deal the next hand: in the target cell if there's target practice, from the drill pack if
+    /// one is set, and otherwise as deal_cell() does, but as three or four cards some of the time if
+    /// multi-card hands are on
+    fn deal_next(&self) -> (Hand, Card) {
+        if let Some(hand) = self
+            .target
+            .as_ref()
+            .and_then(|target| rand_next_hand_where(&self.stats, |desc| *desc == target.desc))
+        {
+            return hand;
+        }
+        if let Some(hand) = self
+            .pack
+            .as_ref()
+            .and_then(|a| a.deal.deal(&self.stats))
+            .filter(|(player, dealer)| self.bs.is_learned(GameDesc::from_hand(player, *dealer)))
+        {
+            return hand;
+        }
+        let (player, dealer) = self.deal_cell();
+        if self.multi_card && thread_rng().gen_bool(MULTI_CARD_CHANCE) {
+            if let Ok(player) = rand_multi_card_hand(GameDesc::from_hand(&player, dealer)) {
+                return (player, dealer);
+            }
+        }
+        (player, dealer)
+    }
+
+    /// Deal an opening hand: from the drill's row if there is a drill and the mode deals any of
+    /// it, otherwise as usual
+    fn deal_cell(&self) -> (Hand, Card) {
+        if let Some(drill) = &self.drill {
+            let (mode, upcard) = (self.mode, self.upcard);
+            let keep = |desc: &GameDesc| {
+                is_dealt(mode, upcard, desc) && drill.contains(desc) && self.bs.is_learned(*desc)
+            };
+            if let Some(hand) = rand_next_hand_where(&self.stats, keep) {
+                return hand;
+            }
+        }
+        Self::deal(
+            self.source,
+            self.mode,
+            self.upcard,
+            &self.bs,
+            &self.stats,
+            self.frequencies.as_ref(),
+            self.refresh(),
+        )
+    }
+
+    /// When cells are stale, if refreshing them is on and there's a clock to tell
+    fn refresh(&self) -> Option<Refresh> {
+        let days = self.refresh_after?;
+        let now = self.clock?();
+        Some(Refresh::after_days(now, days))
+    }
+
+    /// Count down the drill in progress, or count a miss in the given cell's row and start a drill
+    /// if there have been enough of them
+    fn coach_after(&mut self, desc: GameDesc, is_correct: bool) -> Option<CoachEvent> {
+        if let Some(drill) = &mut self.drill {
+            drill.remaining -= 1;
+            if drill.remaining == 0 {
+                self.drill = None;
+                return Some(CoachEvent::DrillEnded);
+            }
+            return None;
+        }
+        let needed = self.coach?;
+        if is_correct {
+            return None;
+        }
+        let misses = self.misses.entry((desc.hand, desc.player)).or_insert(0);
+        *misses += 1;
+        if *misses < needed {
+            return None;
+        }
+        *misses = 0;
+        let drill = Drill {
+            hand: desc.hand,
+            player: desc.player,
+            remaining: COACH_DRILL_HANDS,
+        };
+        self.drill = Some(drill.clone());
+        Some(CoachEvent::DrillStarted(drill))
+    }
+
+    /// Count down target practice, returning whether it just ended
+    fn target_after(&mut self) -> bool {
+        let target = match &mut self.target {
+            Some(target) => target,
+            None => return false,
+        };
+        target.remaining -= 1;
+        if target.remaining == 0 {
+            self.target = None;
+            return true;
+        }
+        false
+    }
+
+    /// Deal a new hand if the current one isn't one that would be dealt now
+    fn redeal_if_not_dealt(&mut self) {
+        if self.target.is_some() || self.pack.is_some() {
+            return;
+        }
+        let desc = GameDesc::from_hand(&self.hand.0, self.hand.1);
+        if !is_dealt(self.mode, self.upcard, &desc) || !self.bs.is_learned(desc) {
+            self.next_hand();
+        }
+    }
+
+    /// The hand currently waiting on the player
+    pub fn hand(&self) -> (&Hand, Card) {
+        (&self.hand.0, self.hand.1)
+    }
+
+    /// Replace the hand currently waiting on the player, e.g. with one saved from a previous
+    /// session
+    pub fn set_hand(&mut self, player: Hand, dealer: Card) {
+        self.hand = (player, dealer);
+        self.split = SplitState::default();
+        self.split_hands.clear();
+    }
+
+    /// Throw away the current hand without grading it and deal a new one. Any split hands still
+    /// waiting are thrown away too.
+    pub fn next_hand(&mut self) {
+        self.hand = self.deal_next();
+        self.split = SplitState::default();
+        self.split_hands.clear();
+    }
+
+    /// Whether some hard and soft hands are dealt as three or four cards. See the module docs.
+    pub fn multi_card(&self) -> bool {
+        self.multi_card
+    }
+
+    /// Turn dealing three and four card hands on or off, starting with the next hand
+    pub fn set_multi_card(&mut self, on: bool) {
+        self.multi_card = on;
+    }
+
+    /// Where the current hand came from. Dealt hands are never from a split.
+    pub fn split_state(&self) -> SplitState {
+        self.split
+    }
+
+    /// Say the current hand was made by splitting, so the card's split rules apply to it. This
+    /// lasts until the next hand.
+    pub fn set_split_state(&mut self, split: SplitState) {
+        self.split = split;
+    }
+
+    /// Whether correct splits are played out. See the module docs.
+    pub fn play_splits(&self) -> bool {
+        self.play_splits
+    }
+
+    /// Turn playing out correct splits on or off, starting with the next answer
+    pub fn set_play_splits(&mut self, on: bool) {
+        self.play_splits = on;
+    }
+
+    /// How many hands made by splitting are still to be played after the current one
+    pub fn split_hands_waiting(&self) -> usize {
+        self.split_hands.len()
+    }
+
+    /// The next hand of a split round to play, given the player's correct answer on the current
+    /// hand, or None if the round is over or splits aren't played out
+    fn split_follow_up(&mut self, action: GameButton) -> Option<Hand> {
+        if !self.play_splits || self.pack.is_some() || self.target.is_some() {
+            return None;
+        }
+        let next = match action {
+            GameButton::Split => {
+                let (first, second) = self.hand.0.clone().split().ok()?;
+                self.split = SplitState {
+                    splits: self.split.splits + 1,
+                    aces: first.rank() == Rank::RA,
+                };
+                self.split_hands.push(Hand::new(&[second, rand_card()]));
+                Some(Hand::new(&[first, rand_card()]))
+            }
+            _ if self.split.splits == 0 => return None,
+            GameButton::Hit => {
+                let mut hand = self.hand.0.clone();
+                hand.push(rand_card());
+                Some(hand)
+            }
+            _ => None,
+        };
+        // skip any hand there's nothing to decide on
+        let (dealer, split) = (self.hand.1, self.split);
+        let bs = &self.bs;
+        let waiting = &mut self.split_hands;
+        next.into_iter()
+            .chain(std::iter::from_fn(|| waiting.pop()))
+            .find(|hand| {
+                hand.value() < 21
+                    && bs.is_learned(GameDesc::from_hand(hand, dealer))
+                    && Allowed::for_hand(hand, dealer, bs.rules.as_ref(), split)
+                        .actions()
+                        .len()
+                        > 1
+            })
+    }
+
+    /// What the player may do with the current hand under the card's rules
+    pub fn allowed(&self) -> Allowed {
+        let (player, dealer) = self.hand();
+        Allowed::for_hand(player, dealer, self.bs.rules.as_ref(), self.split)
+    }
+
+    pub fn strategy(&self) -> &BasicStrategy {
+        &self.bs
+    }
+
+    /// Train on a different card. Unless it's identical to the current card, the current stats
+    /// are put aside and the new card's stats are used.
+    pub fn set_strategy(&mut self, bs: BasicStrategy) {
+        let old_id = self.bs.id();
+        self.bs = bs;
+        if self.frequencies.is_some() {
+            self.frequencies = Some(cell_frequencies(Some(&self.bs)));
+        }
+        let new_id = self.bs.id();
+        if old_id != new_id {
+            log::debug!("Switching strategy cards, putting aside the old card's stats");
+            let stats = self
+                .other_stats
+                .remove(&new_id)
+                .unwrap_or_else(new_play_stats);
+            let old_stats = std::mem::replace(&mut self.stats, stats);
+            self.other_stats.insert(old_id, old_stats);
+        }
+        if self
+            .target
+            .as_ref()
+            .is_some_and(|t| !self.bs.is_learned(t.desc))
+        {
+            self.target = None;
+        }
+        self.redeal_if_not_dealt();
+    }
+
+    pub fn source(&self) -> HandSource {
+        self.source
+    }
+
+    /// Change how hands are dealt, starting with the next one
+    pub fn set_source(&mut self, source: HandSource) {
+        self.source = source;
+    }
+
+    pub fn mode(&self) -> Mode {
+        self.mode
+    }
+
+    /// Change which hands are dealt and which actions are allowed. If the current hand isn't one
+    /// the new mode deals, a new hand is dealt.
+    pub fn set_mode(&mut self, mode: Mode) {
+        self.mode = mode;
+        self.redeal_if_not_dealt();
+    }
+
+    /// How many misses in one row of the card start a drill of it, if coach mode is on
+    pub fn coach(&self) -> Option<u32> {
+        self.coach
+    }
+
+    /// Turn coach mode on, starting a drill of a row once the player has missed hands in it the
+    /// given number of times this session, or off with None. Either way, counted misses and any
+    /// drill in progress are forgotten.
+    /// Whether HandSource::Weighted also weights cells by how often they come up in real play
+    pub fn frequency_weighting(&self) -> bool {
+        self.frequencies.is_some()
+    }
+
+    /// Turn weighting dealt cells by their frequency (see frequency::cell_frequencies()) on or
+    /// off, starting with the next hand. Only HandSource::Weighted is affected: uniformly random
+    /// hands already come up as often as they do in real play.
+    pub fn set_frequency_weighting(&mut self, on: bool) {
+        self.frequencies = if on {
+            Some(cell_frequencies(Some(&self.bs)))
+        } else {
+            None
+        };
+    }
+
+    /// Give the trainer a way to tell the time, in seconds since the Unix epoch, or take it away
+    /// with None. Without one, cells aren't timestamped and none are ever stale.
+    pub fn set_clock(&mut self, clock: Option<fn() -> u64>) {
+        self.clock = clock;
+    }
+
+    /// After how many days unseen a cell is dealt as if it had never been seen, if ever
+    pub fn refresh_after(&self) -> Option<u32> {
+        self.refresh_after
+    }
+
+    /// Deal cells not seen in more than the given number of days as often as unseen ones, or stop
+    /// with None, starting with the next hand. Only HandSource::Weighted is affected, and only
+    /// once there's a clock (see set_clock()).
+    pub fn set_refresh_after(&mut self, days: Option<u32>) {
+        self.refresh_after = days;
+    }
+
+    /// Accuracy on the current card with each cell counting as much as it comes up in real play
+    /// with the card. See frequency::weighted_accuracy().
+    pub fn weighted_accuracy(&self) -> Option<f64> {
+        match &self.frequencies {
+            Some(freq) => weighted_accuracy(&self.stats, freq),
+            None => weighted_accuracy(&self.stats, &cell_frequencies(Some(&self.bs))),
+        }
+    }
+
+    /// Whether blackjacks against an ace are played out with an even money decision
+    pub fn even_money(&self) -> bool {
+        self.even_money
+    }
+
+    /// Turn even money decisions on or off. When on, a blackjack against an ace isn't acted on
+    /// like other hands: the front end asks whether to take even money and passes the answer to
+    /// act_even_money().
+    pub fn set_even_money(&mut self, on: bool) {
+        self.even_money = on;
+    }
+
+    /// Whether the current hand needs an even money decision instead of an action
+    pub fn even_money_offered(&self) -> bool {
+        let (player, dealer) = &self.hand;
+        self.even_money && player.is_natural(self.split) && dealer.rank() == Rank::RA
+    }
+
+    pub fn set_coach(&mut self, misses: Option<u32>) {
+        self.coach = misses.filter(|&n| n > 0);
+        self.misses.clear();
+        self.drill = None;
+    }
+
+    /// The coach mode drill in progress, if any
+    pub fn drill(&self) -> Option<&Drill> {
+        self.drill.as_ref()
+    }
+
+    /// What the player has pressed against what was correct this session. Unlike the stats, this
+    /// isn't saved: it starts empty with every new Trainer.
+    pub fn confusion(&self) -> &Confusion {
+        &self.confusion
+    }
+
+    /// Practice the given cell for the next TARGET_HANDS hands, replacing the current hand with one
+    /// in it. Starting over on the same or another cell restarts the count. If the card hasn't
+    /// learned the cell, nothing changes.
+    pub fn start_target(&mut self, desc: GameDesc) -> Result<(), TrainerError> {
+        if !self.bs.is_learned(desc) {
+            return Err(TrainerError::NotLearned(desc));
+        }
+        player_hand_from_desc(desc).map_err(TableError::from)?;
+        log::debug!("Starting target practice on {}", desc.label());
+        self.target = Some(Target {
+            desc,
+            remaining: TARGET_HANDS,
+        });
+        self.next_hand();
+        Ok(())
+    }
+
+    /// End target practice early, if there is any, and deal a hand as usual
+    pub fn stop_target(&mut self) {
+        if self.target.take().is_some() {
+            self.next_hand();
+        }
+    }
+
+    /// The target practice in progress, if any
+    pub fn target(&self) -> Option<&Target> {
+        self.target.as_ref()
+    }
+
+    /// The drill pack being played, if any
+    pub fn pack(&self) -> Option<&DrillPack> {
+        self.pack.as_ref().map(|a| &a.pack)
+    }
+
+    /// Play the given drill pack, starting a new run of it and dealing a hand from it, or stop
+    /// playing one with None. If the pack isn't valid, nothing changes.
+    pub fn set_pack(&mut self, pack: Option<DrillPack>) -> Result<(), DrillPackError> {
+        self.pack = match pack {
+            Some(pack) => Some(ActivePack {
+                deal: pack.validate()?,
+                progress: PackProgress::new(pack.scoring),
+                pack,
+            }),
+            None => None,
+        };
+        self.next_hand();
+        Ok(())
+    }
+
+    /// How the player is doing on the current run of the drill pack, if one is set
+    pub fn pack_progress(&self) -> Option<PackProgress> {
+        self.pack.as_ref().map(|a| a.progress)
+    }
+
+    /// Count an answer toward the drill pack's run, starting a new run if the last one was done
+    fn pack_after(&mut self, is_correct: bool) {
+        if let Some(active) = &mut self.pack {
+            if active.progress.is_finished() {
+                active.progress = PackProgress::new(active.pack.scoring);
+            }
+            active.progress.played += 1;
+            if is_correct {
+                active.progress.correct += 1;
+            }
+        }
+    }
+
+    /// The value (2-11) of the upcard every hand is dealt against, if fixed
+    pub fn upcard(&self) -> Option<u8> {
+        self.upcard
+    }
+
+    /// Deal every hand against a dealer upcard of the given value (2-10, or 11 for ace), or against
+    /// any upcard if None. If the current hand doesn't match, a new hand is dealt.
+    pub fn set_upcard(&mut self, upcard: Option<u8>) -> Result<(), TrainerError> {
+        if let Some(v) = upcard {
+            if !(2..=11).contains(&v) {
+                return Err(TrainerError::BadUpcard(v));
+            }
+        }
+        self.upcard = upcard;
+        self.redeal_if_not_dealt();
+        Ok(())
+    }
+
+    pub fn stats(&self) -> &Table<PlayStats> {
+        &self.stats
+    }
+
+    pub fn streak(&self) -> u32 {
+        self.streak
+    }
+
+    /// Replace the player's stats for the current card, and their streak, e.g. with ones saved
+    /// from a previous session
+    pub fn set_stats(&mut self, stats: Table<PlayStats>, streak: u32) {
+        self.stats = stats;
+        self.streak = streak;
+    }
+
+    /// How many streak shields the player holds. Always 0 if shields aren't enabled.
+    pub fn shields(&self) -> u32 {
+        if self.shields_enabled {
+            self.shields
+        } else {
+            0
+        }
+    }
+
+    /// Replace how many streak shields the player holds, e.g. with how many they had in a
+    /// previous session. At most MAX_SHIELDS are kept.
+    pub fn set_shields(&mut self, shields: u32) {
+        self.shields = shields.min(MAX_SHIELDS);
+    }
+
+    /// Turn streak shields on or off. While off, shields are neither earned nor used, but any the
+    /// player holds are kept for when they're turned back on.
+    pub fn set_shields_enabled(&mut self, enabled: bool) {
+        self.shields_enabled = enabled;
+    }
+
+    /// The name of the current card's stats segment
+    pub fn stats_segment(&self) -> String {
+        self.bs.id()
+    }
+
+    /// The names of every stats segment with any stats, sorted. The current card's is always
+    /// included.
+    pub fn stats_segments(&self) -> Vec<String> {
+        let mut segments: Vec<String> = self
+            .other_stats
+            .iter()
+            .filter(|(_, stats)| stats.values().any(|s| s.seen() > 0))
+            .map(|(id, _)| id.clone())
+            .collect();
+        segments.push(self.stats_segment());
+        segments.sort();
+        segments
+    }
+
+    /// The player's stats in the given segment, if they have any
+    pub fn stats_for(&self, segment: &str) -> Option<&Table<PlayStats>> {
+        if segment == self.stats_segment() {
+            Some(&self.stats)
+        } else {
+            self.other_stats.get(segment)
+        }
+    }
+
+    /// Replace the player's stats in the given segment
+    pub fn set_stats_for(&mut self, segment: &str, stats: Table<PlayStats>) {
+        if segment == self.stats_segment() {
+            self.stats = stats;
+        } else {
+            self.other_stats.insert(segment.to_string(), stats);
+        }
+    }
+
+    /// Move the player's stats in the given segment onto the current card's, as if they had been
+    /// graded against it all along. Stats already in the current card's segment count as having
+    /// come later. Returns false, changing nothing, if the segment has no stats or is the current
+    /// card's.
+    pub fn migrate_stats(&mut self, segment: &str) -> bool {
+        let old = match self.other_stats.remove(segment) {
+            Some(old) => old,
+            None => return false,
+        };
+        self.stats = old.zip_with(&self.stats, |old, new| old + new);
+        true
+    }
+
+    /// Forget the player's stats in every segment, and their streak and shields
+    pub fn clear_stats(&mut self) {
+        for v in self.stats.values_mut() {
+            *v = PlayStats::new();
+        }
+        self.other_stats.clear();
+        self.streak = 0;
+        self.shields = 0;
+        self.confusion = Confusion::new();
+    }
+
+    pub fn notes(&self) -> &Notes {
+        &self.notes
+    }
+
+    /// Replace the player's notes, e.g. with ones saved from a previous session
+    pub fn set_notes(&mut self, notes: Notes) {
+        self.notes = notes;
+    }
+
+    /// The player's note on the current hand's cell, if any
+    pub fn note(&self) -> Option<&str> {
+        self.notes.get(&self.hand.0, self.hand.1)
+    }
+
+    /// Set the player's note on the current hand's cell. An empty note removes it.
+    pub fn set_note(&mut self, note: &str) {
+        self.notes.set(&self.hand.0, self.hand.1, note);
+    }
+
+    /// The surrender rule of the card, or always allowed if the card doesn't say
+    pub fn surrender_rule(&self) -> Surrender {
+        match &self.bs.rules {
+            None => Surrender::Yes,
+            Some(rules) => rules.surrender,
+        }
+    }
+
+    /// Whether the player can take the given action on the current hand
+    pub fn is_legal(&self, action: GameButton) -> bool {
+        if self.even_money_offered() {
+            return false;
+        }
+        if let Mode::HitStand { .. } = self.mode {
+            return action == GameButton::Hit || action == GameButton::Stand;
+        }
+        self.allowed().permits(action)
+    }
+
+    /// Every action the player can take on the current hand, in the order of GameButton::ALL
+    pub fn legal_actions(&self) -> Vec<GameButton> {
+        if self.even_money_offered() {
+            return vec![];
+        }
+        if let Mode::HitStand { .. } = self.mode {
+            return vec![GameButton::Hit, GameButton::Stand];
+        }
+        self.allowed().actions()
+    }
+
+    /// The button the card says to press on the current hand, after any fallback for what isn't
+    /// allowed. Acting with it is always graded correct.
+    pub fn correct_action(&self) -> Result<GameButton, TrainerError> {
+        self.check_learned()?;
+        Ok(expected_button(self.correct_resp()?, self.allowed()))
+    }
+
+    /// TrainerError::NotLearned if the current hand is in a cell the card hasn't learned, e.g.
+    /// one set with set_hand()
+    fn check_learned(&self) -> Result<(), TrainerError> {
+        let desc = GameDesc::from_hand(&self.hand.0, self.hand.1);
+        if self.bs.is_learned(desc) {
+            Ok(())
+        } else {
+            Err(TrainerError::NotLearned(desc))
+        }
+    }
+
+    /// What the card says to do with the current hand in the current mode
+    fn correct_resp(&self) -> Result<Resp, TableError> {
+        let (player, dealer) = self.hand();
+        let resp = self.bs.resp(player, dealer)?;
+        if let Mode::Full = self.mode {
+            let allowed = self.allowed();
+            let must_split = match resp {
+                Resp::Split => true,
+                Resp::SurrenderElseSplit => !allowed.surrender,
+                _ => false,
+            };
+            if must_split && !allowed.split {
+                return self.bs.unsplit_resp(player, dealer);
+            }
+            return Ok(resp);
+        }
+        if let Some(resp) = resp.hit_or_stand() {
+            return Ok(resp);
+        }
+        Ok(self
+            .bs
+            .unsplit_resp(player, dealer)?
+            .hit_or_stand()
+            .unwrap_or(Resp::Hit))
+    }
+
+    /// Grow or end the streak after a hand, or use up a shield to save it. Returns whether a
+    /// shield was used.
+    fn update_streak(&mut self, is_correct: bool) -> bool {
+        let shielded = !is_correct && self.shields_enabled && self.shields > 0;
+        if is_correct {
+            self.streak += 1;
+            if self.shields_enabled && is_streak_milestone(self.streak) {
+                self.shields = (self.shields + 1).min(MAX_SHIELDS);
+            }
+        } else if shielded {
+            self.shields -= 1;
+        } else {
+            self.streak = 0;
+        }
+        shielded
+    }
+
+    /// Take the player's even money decision on the current hand: grade it against basic strategy,
+    /// which never takes it, update stats, and deal the next hand. Stats go in the hand's cell,
+    /// soft 21 against an ace. Does nothing if even money isn't offered.
+    pub fn act_even_money(&mut self, take: bool) -> Result<Option<EvenMoneyGraded>, TrainerError> {
+        if !self.even_money_offered() {
+            return Ok(None);
+        }
+        let is_correct = !take;
+        let (player, dealer) = (&self.hand.0, self.hand.1);
+        let mut stat = self.stats.get(player, dealer)?;
+        stat.inc(is_correct);
+        if let Some(clock) = self.clock {
+            stat.mark_seen(clock());
+        }
+        self.stats.update(player, dealer, stat)?;
+        let old_streak = self.streak;
+        let desc = GameDesc::from_hand(player, dealer);
+        let shielded = self.update_streak(is_correct);
+        let coach = self.coach_after(desc, is_correct);
+        self.pack_after(is_correct);
+        let target_ended = self.target_after();
+        let next = self.deal_next();
+        let (player, dealer) = std::mem::replace(&mut self.hand, next);
+        Ok(Some(EvenMoneyGraded {
+            took: take,
+            player,
+            dealer,
+            is_correct,
+            old_streak,
+            shielded,
+            coach,
+            target_ended,
+        }))
+    }
+
+    /// Take the player's action on the current hand: grade it, update stats, and deal the next
+    /// hand. If the action isn't legal, nothing changes.
+    pub fn act(&mut self, action: GameButton) -> Result<Graded, TrainerError> {
+        if !self.is_legal(action) {
+            return Err(TrainerError::IllegalAction(
+                action,
+                self.hand.0.clone(),
+                self.hand.1,
+            ));
+        }
+        self.check_learned()?;
+        let correct = self.correct_resp()?;
+        let allowed = self.allowed();
+        let (player, dealer) = (&self.hand.0, self.hand.1);
+        // Whether or not the action is correct is more complex than action == correct: if the
+        // correct Resp is DoubleElseHit (or its cousins) then it is not enough to simply check if
+        // the player doubled.
+        let expected = expected_button(correct, allowed);
+        let mistake = mistake(action, correct, allowed);
+        let is_correct = mistake.is_none();
+        self.confusion.record(correct, action, mistake);
+        let mut stat = self.stats.get(player, dealer)?;
+        stat.inc(is_correct);
+        if let Some(clock) = self.clock {
+            stat.mark_seen(clock());
+        }
+        self.stats.update(player, dealer, stat)?;
+        let old_streak = self.streak;
+        let note = self.note().map(|s| s.to_string());
+        let desc = GameDesc::from_hand(player, dealer);
+        let shielded = self.update_streak(is_correct);
+        let coach = self.coach_after(desc, is_correct);
+        self.pack_after(is_correct);
+        let target_ended = self.target_after();
+        let follow_up = if is_correct {
+            self.split_follow_up(action)
+        } else {
+            None
+        };
+        let next = match follow_up {
+            Some(hand) => (hand, dealer),
+            None => {
+                self.split = SplitState::default();
+                self.split_hands.clear();
+                self.deal_next()
+            }
+        };
+        let (player, dealer) = std::mem::replace(&mut self.hand, next);
+        Ok(Graded {
+            action,
+            correct,
+            expected,
+            mistake,
+            player,
+            dealer,
+            is_correct,
+            old_streak,
+            shielded,
+            note,
+            coach,
+            target_ended,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::deck::{Rank, Suit};
+    use crate::playstats::SECS_PER_DAY;
+    use crate::resp::resps_from_buf;
+    use std::collections::HashSet;
+
+    const T1: &[u8] = include_bytes!("../../t1.txt");
+
+    fn trainer() -> Trainer {
+        let table = Table::new(resps_from_buf(T1).unwrap()).unwrap();
+        Trainer::new(BasicStrategy::new(None, table), HandSource::Uniform)
+    }
+
+    fn card(rank: Rank) -> Card {
+        Card::new(rank, Suit::Club)
+    }
+
+    #[test]
+    fn correct_then_wrong() {
+        let mut t = trainer();
+        let eights = Hand::new(&[card(Rank::R8), card(Rank::R8)]);
+        t.set_hand(eights.clone(), card(Rank::R6));
+        let g = t.act(GameButton::Split).unwrap();
+        assert!(g.is_correct);
+        assert_eq!(g.correct, Resp::Split);
+        assert_eq!(g.player, eights);
+        assert_eq!(t.streak(), 1);
+        t.set_hand(eights.clone(), card(Rank::R6));
+        let g = t.act(GameButton::Hit).unwrap();
+        assert!(!g.is_correct);
+        assert_eq!(g.old_streak, 1);
+        assert_eq!(t.streak(), 0);
+        let stat = t.stats().get(&eights, card(Rank::R6)).unwrap();
+        assert_eq!((stat.correct(), stat.seen()), (1, 2));
+    }
+
+    #[test]
+    fn shields() {
+        let mut t = trainer();
+        let eights = Hand::new(&[card(Rank::R8), card(Rank::R8)]);
+        let answer = |t: &mut Trainer, btn| {
+            t.set_hand(eights.clone(), card(Rank::R6));
+            t.act(btn).unwrap()
+        };
+        // off by default: milestones earn nothing
+        t.set_stats(t.stats().clone(), 9);
+        answer(&mut t, GameButton::Split);
+        assert_eq!(t.shields(), 0);
+        t.set_shields_enabled(true);
+        t.set_stats(t.stats().clone(), 24);
+        answer(&mut t, GameButton::Split);
+        assert_eq!((t.streak(), t.shields()), (25, 1));
+        let g = answer(&mut t, GameButton::Hit);
+        assert!(!g.is_correct && g.shielded);
+        assert_eq!((t.streak(), t.shields()), (25, 0));
+        let g = answer(&mut t, GameButton::Hit);
+        assert!(!g.shielded);
+        assert_eq!(t.streak(), 0);
+        // wrong answers still count as wrong
+        let stat = t.stats().get(&eights, card(Rank::R6)).unwrap();
+        assert_eq!((stat.correct(), stat.seen()), (2, 4));
+        t.set_shields(10);
+        assert_eq!(t.shields(), MAX_SHIELDS);
+        // held shields are kept, but not used, while off
+        t.set_shields_enabled(false);
+        let g = answer(&mut t, GameButton::Hit);
+        assert!(!g.shielded);
+        t.set_shields_enabled(true);
+        assert_eq!(t.shields(), MAX_SHIELDS);
+    }
+
+    #[test]
+    fn even_money() {
+        let mut t = trainer();
+        let bj = Hand::new(&[card(Rank::RA), card(Rank::RK)]);
+        t.set_hand(bj.clone(), card(Rank::RA));
+        assert!(!t.even_money_offered());
+        assert_eq!(t.act_even_money(true).unwrap(), None);
+        t.set_even_money(true);
+        assert!(t.even_money_offered());
+        assert!(t.legal_actions().is_empty());
+        assert!(t.act(GameButton::Stand).is_err());
+        let g = t.act_even_money(true).unwrap().unwrap();
+        assert!(g.took && !g.is_correct);
+        t.set_hand(bj.clone(), card(Rank::RA));
+        assert!(t.act_even_money(false).unwrap().unwrap().is_correct);
+        assert_eq!(t.streak(), 1);
+        let stat = t.stats().get(&bj, card(Rank::RA)).unwrap();
+        assert_eq!((stat.seen(), stat.correct()), (2, 1));
+        // only blackjacks against an ace
+        t.set_hand(bj, card(Rank::RT));
+        assert!(!t.even_money_offered());
+    }
+
+    #[test]
+    fn frequency_weighting() {
+        let mut t = trainer();
+        assert_eq!(t.weighted_accuracy(), None);
+        t.set_source(HandSource::Weighted);
+        t.set_frequency_weighting(true);
+        assert!(t.frequency_weighting());
+        // get soft hands wrong and everything else right
+        for _ in 0..100 {
+            let correct = t.correct_resp().unwrap();
+            let action = if t.hand().0.hand_type() != HandType::Soft {
+                expected_button(correct, t.allowed())
+            } else if correct.hit_or_stand() == Some(Resp::Hit) {
+                GameButton::Stand
+            } else {
+                GameButton::Hit
+            };
+            t.act(action).unwrap();
+        }
+        let acc = t.weighted_accuracy().unwrap();
+        assert!(acc > 0.0 && acc < 1.0);
+        t.set_frequency_weighting(false);
+        assert_eq!(t.weighted_accuracy(), Some(acc));
+    }
+
+    #[test]
+    fn refresh() {
+        fn clock() -> u64 {
+            1_000 * SECS_PER_DAY
+        }
+        let mut t = trainer();
+        t.set_source(HandSource::Weighted);
+        t.act(GameButton::Stand).ok();
+        // no clock, no timestamps
+        assert!(t.stats().values().all(|s| s.last_seen().is_none()));
+        t.set_clock(Some(clock));
+        let (player, dealer) = (t.hand().0.clone(), t.hand().1);
+        let correct = t.correct_resp().unwrap();
+        t.act(expected_button(correct, t.allowed())).unwrap();
+        let stat = t.stats().get(&player, dealer).unwrap();
+        assert_eq!(stat.last_seen(), Some(clock()));
+        // every cell known perfectly, but only one of them not seen in a year
+        let mut known = PlayStats::from_counts(50, 50, 50).unwrap();
+        known.mark_seen(clock() - SECS_PER_DAY);
+        let mut stale = known;
+        stale.mark_seen(clock() - 365 * SECS_PER_DAY);
+        let mut stats = Table::new(vec![known; 360]).unwrap();
+        let (stale_hand, stale_dealer) =
+            (Hand::new(&[card(Rank::R9), card(Rank::R7)]), card(Rank::RT));
+        stats.update(&stale_hand, stale_dealer, stale).unwrap();
+        t.set_stats(stats, 0);
+        t.set_refresh_after(Some(30));
+        assert_eq!(t.refresh_after(), Some(30));
+        // the stale cell weighs 1, the others 1/51 * RETIRED_WEIGHT_SCALE each, so it comes up
+        // about 60% of the time
+        let stale_desc = GameDesc::from_hand(&stale_hand, stale_dealer);
+        let dealt = (0..200)
+            .filter(|_| {
+                t.next_hand();
+                GameDesc::from_hand(t.hand().0, t.hand().1) == stale_desc
+            })
+            .count();
+        assert!(dealt > 60, "{}", dealt);
+        t.set_refresh_after(None);
+        let dealt = (0..200)
+            .filter(|_| {
+                t.next_hand();
+                GameDesc::from_hand(t.hand().0, t.hand().1) == stale_desc
+            })
+            .count();
+        assert!(dealt < 20, "{}", dealt);
+    }
+
+    #[test]
+    fn pack() {
+        use crate::drillpack::{PackHands, Scoring};
+        let mut t = trainer();
+        let mut pack = DrillPack {
+            name: "Sixteens".to_string(),
+            description: String::new(),
+            hands: PackHands::Cells {
+                rows: vec!["Hard 16".to_string()],
+                upcards: vec!["10".to_string()],
+            },
+            scoring: Scoring {
+                hands: 3,
+                pass_percent: 60,
+            },
+        };
+        pack.name.clear();
+        assert!(t.set_pack(Some(pack.clone())).is_err());
+        assert_eq!(t.pack(), None);
+        pack.name = "Sixteens".to_string();
+        t.set_pack(Some(pack)).unwrap();
+        let sixteen = |t: &Trainer| GameDesc::from_hand(t.hand().0, t.hand().1).label();
+        for answer in &[GameButton::Hit, GameButton::Hit, GameButton::Stand] {
+            assert_eq!(sixteen(&t), "Hard 16 vs 10");
+            t.act(*answer).unwrap();
+        }
+        let progress = t.pack_progress().unwrap();
+        assert_eq!((progress.played, progress.correct), (3, 2));
+        assert_eq!(progress.passed(), Some(true));
+        // the next answer starts a new run
+        t.act(GameButton::Stand).unwrap();
+        assert_eq!(t.pack_progress().unwrap().played, 1);
+        t.set_pack(None).unwrap();
+        assert_eq!(t.pack_progress(), None);
+    }
+
+    #[test]
+    fn coach_drill() {
+        let mut t = trainer();
+        t.set_source(HandSource::Weighted);
+        t.set_coach(Some(2));
+        let soft18 = |r| (Hand::new(&[card(Rank::RA), card(Rank::R7)]), card(r));
+        // misses against different upcards count toward the same row
+        let wrong = |t: &Trainer| match t.strategy().table.get(t.hand().0, t.hand().1).unwrap() {
+            Resp::Hit => GameButton::Stand,
+            _ => GameButton::Hit,
+        };
+        let (player, dealer) = soft18(Rank::R2);
+        t.set_hand(player, dealer);
+        let g = t.act(wrong(&t)).unwrap();
+        assert!(!g.is_correct);
+        assert_eq!(g.coach, None);
+        let (player, dealer) = soft18(Rank::R9);
+        t.set_hand(player, dealer);
+        let g = t.act(wrong(&t)).unwrap();
+        assert_eq!(
+            g.coach,
+            Some(CoachEvent::DrillStarted(t.drill().unwrap().clone()))
+        );
+        assert_eq!(t.drill().unwrap().label(), "Soft 18");
+        for i in 0..COACH_DRILL_HANDS {
+            let (player, dealer) = t.hand();
+            assert_eq!(
+                GameDesc::from_hand(player, dealer).player_label(),
+                "Soft 18"
+            );
+            let g = t.act(GameButton::Stand).unwrap();
+            if i + 1 == COACH_DRILL_HANDS {
+                assert_eq!(g.coach, Some(CoachEvent::DrillEnded));
+            } else {
+                assert_eq!(g.coach, None);
+            }
+        }
+        assert!(t.drill().is_none());
+    }
+
+    #[test]
+    fn illegal_changes_nothing() {
+        let mut t = trainer();
+        let hand = Hand::new(&[card(Rank::R9), card(Rank::R7)]);
+        t.set_hand(hand.clone(), card(Rank::R6));
+        assert!(!t.is_legal(GameButton::Split));
+        assert_eq!(
+            t.act(GameButton::Split),
+            Err(TrainerError::IllegalAction(
+                GameButton::Split,
+                hand.clone(),
+                card(Rank::R6)
+            ))
+        );
+        assert_eq!(t.hand(), (&hand, card(Rank::R6)));
+        assert_eq!(t.stats().values().map(|s| s.seen()).sum::<u32>(), 0);
+    }
+
+    #[test]
+    fn note_follows_cell() {
+        let mut t = trainer();
+        let hand = Hand::new(&[card(Rank::RT), card(Rank::R6)]);
+        t.set_hand(hand.clone(), card(Rank::R9));
+        assert_eq!(t.note(), None);
+        t.set_note("dealer 9 is not a bust card!");
+        let g = t.act(GameButton::Stand).unwrap();
+        assert_eq!(g.note.as_deref(), Some("dealer 9 is not a bust card!"));
+        t.set_hand(Hand::new(&[card(Rank::R9), card(Rank::R7)]), card(Rank::R9));
+        assert_eq!(t.note(), Some("dealer 9 is not a bust card!"));
+    }
+
+    #[test]
+    fn hit_stand_mode() {
+        let mut t = trainer();
+        t.set_mode(Mode::HitStand {
+            pairs: true,
+            softs: false,
+        });
+        // 11 vs 6 is a double, which becomes a hit
+        t.set_hand(Hand::new(&[card(Rank::R9), card(Rank::R2)]), card(Rank::R6));
+        assert!(!t.is_legal(GameButton::Double));
+        let g = t.act(GameButton::Hit).unwrap();
+        assert!(g.is_correct);
+        assert_eq!(g.correct, Resp::Hit);
+        // 8s vs 6 is a split, so it's played as hard 16, which stands
+        let eights = Hand::new(&[card(Rank::R8), card(Rank::R8)]);
+        t.set_hand(eights, card(Rank::R6));
+        assert!(!t.is_legal(GameButton::Split));
+        assert_eq!(t.act(GameButton::Stand).unwrap().correct, Resp::Stand);
+        // aces are always hit
+        t.set_hand(Hand::new(&[card(Rank::RA), card(Rank::RA)]), card(Rank::R6));
+        assert_eq!(t.act(GameButton::Hit).unwrap().correct, Resp::Hit);
+        for source in &[HandSource::Uniform, HandSource::Weighted] {
+            t.set_source(*source);
+            for _ in 0..100 {
+                let (player, _) = t.hand();
+                assert!(!player.is_soft() || player.is_pair());
+                t.act(GameButton::Stand).unwrap();
+            }
+        }
+    }
+
+    #[test]
+    fn fixed_upcard() {
+        let mut t = trainer();
+        assert_eq!(t.set_upcard(Some(1)), Err(TrainerError::BadUpcard(1)));
+        t.set_upcard(Some(11)).unwrap();
+        for source in &[HandSource::Uniform, HandSource::Weighted] {
+            t.set_source(*source);
+            for _ in 0..100 {
+                assert_eq!(t.hand().1.rank(), Rank::RA);
+                t.act(GameButton::Stand).unwrap();
+            }
+        }
+        assert_eq!(next_drill_upcard(None), 2);
+        assert_eq!(next_drill_upcard(Some(6)), 7);
+        assert_eq!(next_drill_upcard(Some(11)), 2);
+    }
+
+    #[test]
+    fn target_practice() {
+        let mut t = trainer();
+        t.set_upcard(Some(2)).unwrap();
+        let target = GameDesc::new(HandType::Hard, 16, 10);
+        t.start_target(target).unwrap();
+        let mut hands = HashSet::new();
+        for i in 0..TARGET_HANDS {
+            assert_eq!(t.target().unwrap().remaining, TARGET_HANDS - i);
+            let (player, dealer) = t.hand();
+            assert_eq!(GameDesc::from_hand(player, dealer), target);
+            hands.insert((player.clone(), dealer));
+            // the target wins over the upcard being drilled
+            let graded = t.act(GameButton::Stand).unwrap();
+            assert_eq!(graded.target_ended, i == TARGET_HANDS - 1);
+        }
+        assert!(t.target().is_none());
+        assert_eq!(t.hand().1.value(), 2);
+        // 10 hands are very unlikely to all be the same suits and cards
+        assert!(hands.len() > 1);
+        t.start_target(target).unwrap();
+        t.stop_target();
+        assert!(t.target().is_none());
+        // a cell the card hasn't learned can't be practiced
+        let partial = t
+            .strategy()
+            .partial_table()
+            .map(|desc, resp| resp.filter(|_| desc != target));
+        t.set_strategy(BasicStrategy::from_partial(None, partial).unwrap());
+        assert_eq!(
+            t.start_target(target),
+            Err(TrainerError::NotLearned(target))
+        );
+        assert!(t.target().is_none());
+    }
+
+    #[test]
+    fn partial_card() {
+        let full = Table::new(resps_from_buf(T1).unwrap()).unwrap();
+        // only hard 12-16 vs 10 learned so far
+        let learned =
+            |desc: GameDesc| desc.hand == HandType::Hard && (12..=16).contains(&desc.player);
+        let partial =
+            full.map(|desc, resp| Some(resp).filter(|_| learned(desc) && desc.dealer == 10));
+        let mut t = trainer();
+        t.set_strategy(BasicStrategy::from_partial(None, partial).unwrap());
+        for source in &[HandSource::Uniform, HandSource::Weighted] {
+            t.set_source(*source);
+            for _ in 0..100 {
+                let (player, dealer) = t.hand();
+                let desc = GameDesc::from_hand(player, dealer);
+                assert!(learned(desc) && desc.dealer == 10, "{} was dealt", desc);
+                t.act(t.correct_action().unwrap()).unwrap();
+            }
+        }
+        // nothing against a 2 is learned, so there's nothing to grade
+        t.set_upcard(Some(2)).unwrap();
+        let (player, dealer) = (t.hand().0.clone(), t.hand().1);
+        let desc = GameDesc::from_hand(&player, dealer);
+        assert_eq!(t.correct_action(), Err(TrainerError::NotLearned(desc)));
+        assert_eq!(
+            t.act(GameButton::Stand),
+            Err(TrainerError::NotLearned(desc))
+        );
+        assert_eq!(t.hand(), (&player, dealer));
+    }
+
+    #[test]
+    fn stats_per_card() {
+        use crate::basicstrategy::rules::*;
+        let mut t = trainer();
+        assert_eq!(t.surrender_rule(), Surrender::Yes);
+        let hand = Hand::new(&[card(Rank::RT), card(Rank::R6)]);
+        t.set_hand(hand.clone(), card(Rank::RT));
+        t.act(GameButton::Surrender).unwrap();
+        let no_surrender = BasicStrategy::new(
+            Some(Rules {
+                hit_soft_17: true.into(),
+                ..Default::default()
+            }),
+            t.strategy().table.clone(),
+        );
+        let seen = |t: &Trainer| t.stats().get(&hand, card(Rank::RT)).unwrap().seen();
+        t.set_strategy(no_surrender);
+        assert_eq!(seen(&t), 0);
+        t.set_hand(hand.clone(), card(Rank::RT));
+        t.act(GameButton::Hit).unwrap();
+        t.set_hand(hand.clone(), card(Rank::RT));
+        t.act(GameButton::Hit).unwrap();
+        assert_eq!(seen(&t), 2);
+        let original = trainer().strategy().id();
+        let no_id = t.stats_segment();
+        assert_ne!(original, no_id);
+        let yes = t.stats_for(&original).unwrap();
+        assert_eq!(yes.get(&hand, card(Rank::RT)).unwrap().seen(), 1);
+        let mut segments = vec![original.clone(), no_id.clone()];
+        segments.sort();
+        assert_eq!(t.stats_segments(), segments);
+        t.set_strategy(trainer().strategy().clone());
+        assert_eq!(seen(&t), 1);
+        // an edited card is a new segment, until the player carries their stats over
+        let mut edited = t.strategy().clone();
+        edited
+            .table
+            .update(&hand, card(Rank::RT), Resp::Stand)
+            .unwrap();
+        t.set_strategy(edited);
+        assert_eq!(seen(&t), 0);
+        t.set_hand(hand.clone(), card(Rank::RT));
+        t.act(GameButton::Stand).unwrap();
+        assert!(!t.migrate_stats(&t.stats_segment()));
+        assert!(t.migrate_stats(&original));
+        let stat = t.stats().get(&hand, card(Rank::RT)).unwrap();
+        assert_eq!((stat.seen(), stat.correct(), stat.streak()), (2, 1, 1));
+        assert!(t.stats_for(&original).is_none());
+        t.clear_stats();
+        assert!(t.stats_for(&no_id).is_none());
+        assert_eq!(t.stats_segments(), vec![t.stats_segment()]);
+    }
+
+    #[test]
+    fn mistakes() {
+        use crate::basicstrategy::rules::*;
+        let mut t = trainer();
+        let hand = Hand::new(&[card(Rank::RT), card(Rank::R6)]);
+        // T1 has no surrender
+        let mut bs = t.strategy().clone();
+        bs.table
+            .update(&hand, card(Rank::RT), Resp::SurrenderElseHit)
+            .unwrap();
+        t.set_strategy(bs);
+        t.set_hand(hand.clone(), card(Rank::RT));
+        let g = t.act(GameButton::Hit).unwrap();
+        assert_eq!(g.correct, Resp::SurrenderElseHit);
+        assert_eq!(g.mistake, Some(Mistake::Primary));
+        assert_eq!(g.fallback_detail(), None);
+        let mut rules = Rules {
+            hit_soft_17: true.into(),
+            ..Default::default()
+        };
+        let table = t.strategy().table.clone();
+        t.set_strategy(BasicStrategy::new(Some(rules.clone()), table.clone()));
+        t.set_hand(hand.clone(), card(Rank::RT));
+        let g = t.act(GameButton::Stand).unwrap();
+        assert_eq!(g.expected, GameButton::Hit);
+        assert_eq!(g.mistake, Some(Mistake::Fallback));
+        assert_eq!(
+            g.fallback_detail().unwrap(),
+            "Surrender isn't allowed here, so the fallback is Hit."
+        );
+        t.set_hand(hand.clone(), card(Rank::RT));
+        assert_eq!(t.act(GameButton::Hit).unwrap().mistake, None);
+        let c = t.confusion();
+        assert_eq!(c.count(Resp::SurrenderElseHit, GameButton::Stand), 1);
+        assert_eq!(c.count(Resp::SurrenderElseHit, GameButton::Hit), 2);
+        assert_eq!(c.mistakes(Mistake::Fallback), 1);
+        // the confusion matrix is per session, not per card
+        rules.surrender = Surrender::Yes;
+        t.set_strategy(BasicStrategy::new(Some(rules), table));
+        assert_eq!(t.confusion().mistakes(Mistake::Primary), 1);
+        t.clear_stats();
+        assert!(t.confusion().is_empty());
+    }
+
+    #[test]
+    fn split_rules() {
+        use crate::basicstrategy::rules::*;
+        let mut rules = Rules {
+            hit_soft_17: true.into(),
+            double_after_split: false.into(),
+            ..Default::default()
+        };
+        let mut t = trainer();
+        let table = t.strategy().table.clone();
+        t.set_strategy(BasicStrategy::new(Some(rules.clone()), table.clone()));
+        let split = |splits, aces| SplitState { splits, aces };
+        // out of splits, so 8s are played as hard 16
+        let eights = Hand::new(&[card(Rank::R8), card(Rank::R8)]);
+        t.set_hand(eights.clone(), card(Rank::R6));
+        t.set_split_state(split(3, false));
+        assert!(!t.is_legal(GameButton::Split));
+        let g = t.act(GameButton::Stand).unwrap();
+        assert!(g.is_correct);
+        assert_eq!(t.split_state(), SplitState::default());
+        // no doubling after splitting
+        let eleven = Hand::new(&[card(Rank::R6), card(Rank::R5)]);
+        t.set_hand(eleven.clone(), card(Rank::R6));
+        t.set_split_state(split(1, false));
+        assert!(!t.is_legal(GameButton::Double));
+        assert!(t.act(GameButton::Hit).unwrap().is_correct);
+        // split aces can only stand, until the rules allow more
+        let aces = Hand::new(&[card(Rank::RA), card(Rank::RA)]);
+        t.set_hand(aces.clone(), card(Rank::RT));
+        t.set_split_state(split(1, true));
+        assert!(!t.is_legal(GameButton::Hit) && !t.is_legal(GameButton::Split));
+        assert!(t.act(GameButton::Stand).unwrap().is_correct);
+        rules.resplit_aces = true.into();
+        t.set_strategy(BasicStrategy::new(Some(rules), table));
+        t.set_hand(aces, card(Rank::RT));
+        t.set_split_state(split(1, true));
+        assert!(t.is_legal(GameButton::Split));
+        assert!(t.act(GameButton::Split).unwrap().is_correct);
+    }
+
+    #[test]
+    fn play_splits() {
+        let mut t = trainer();
+        let eights = Hand::new(&[card(Rank::R8), card(Rank::R8)]);
+        let right = |t: &Trainer| expected_button(t.correct_resp().unwrap(), t.allowed());
+        t.set_hand(eights.clone(), card(Rank::R6));
+        t.act(GameButton::Split).unwrap();
+        assert_eq!(t.split_state(), SplitState::default());
+        t.set_play_splits(true);
+        t.set_hand(eights.clone(), card(Rank::R6));
+        t.act(GameButton::Split).unwrap();
+        assert_eq!(t.split_state().splits, 1);
+        assert_eq!(t.split_hands_waiting(), 1);
+        assert_eq!(t.hand().0.cards().next(), Some(&card(Rank::R8)));
+        assert_eq!(t.hand().1, card(Rank::R6));
+        // play the round out correctly. Every hand starts with an 8 and ends on a stand, a double,
+        // a bust, or 21.
+        let mut decisions = 0;
+        while t.split_state().splits > 0 {
+            assert_eq!(t.hand().0.cards().next().unwrap().rank(), Rank::R8);
+            assert!(t.hand().0.value() < 21);
+            assert!(t.act(right(&t)).unwrap().is_correct);
+            decisions += 1;
+        }
+        assert!(decisions >= 2);
+        assert_eq!(t.split_hands_waiting(), 0);
+        assert_eq!(
+            t.stats().values().map(|s| s.seen()).sum::<u32>(),
+            decisions + 2
+        );
+        // a wrong answer ends the round
+        t.set_hand(eights, card(Rank::R6));
+        t.act(GameButton::Split).unwrap();
+        let wrong = t
+            .legal_actions()
+            .into_iter()
+            .find(|a| *a != right(&t))
+            .unwrap();
+        assert!(!t.act(wrong).unwrap().is_correct);
+        assert_eq!(t.split_state(), SplitState::default());
+        assert_eq!(t.split_hands_waiting(), 0);
+    }
+
+    #[test]
+    fn multi_card() {
+        let mut t = trainer();
+        t.set_multi_card(true);
+        let mut multi = 0;
+        for _ in 0..200 {
+            let (player, _) = t.hand();
+            if player.cards().count() > 2 {
+                multi += 1;
+                assert!(!t.is_legal(GameButton::Double));
+                assert_ne!(player.hand_type(), HandType::Pair);
+            }
+            t.act(GameButton::Stand).unwrap();
+        }
+        assert!(multi > 20, "{}", multi);
+        // soft 18 vs 3 is Ds, so stand once doubling isn't allowed
+        let soft18 = Hand::new(&[card(Rank::RA), card(Rank::R2), card(Rank::R5)]);
+        t.set_hand(soft18.clone(), card(Rank::R3));
+        assert!(t.act(GameButton::Stand).unwrap().is_correct);
+        t.set_hand(soft18, card(Rank::R3));
+        assert!(!t.act(GameButton::Hit).unwrap().is_correct);
+    }
+
+    #[test]
+    fn deals_next_hand() {
+        let mut t = trainer();
+        for _ in 0..100 {
+            let (player, dealer) = t.hand();
+            let (player, dealer) = (player.clone(), dealer);
+            let g = t.act(GameButton::Stand).unwrap();
+            assert_eq!((g.player, g.dealer), (player, dealer));
+        }
+        assert_eq!(t.stats().values().map(|s| s.seen()).sum::<u32>(), 100);
+        t.clear_stats();
+        assert_eq!(t.stats().values().map(|s| s.seen()).sum::<u32>(), 0);
+    }
+}
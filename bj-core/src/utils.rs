@@ -1,39 +1,151 @@
-use crate::deck::{rand_card, rand_suit, Card, Rank};
+#[cfg(feature = "rand")]
+use crate::basicstrategy::BasicStrategy;
+#[cfg(feature = "rand")]
+use crate::deck::{rand_card, rand_suit, Card};
+#[cfg(feature = "rand")]
 use crate::hand::{rand_hand, Hand};
 use crate::playstats::PlayStats;
+#[cfg(feature = "rand")]
+use crate::playstats::Refresh;
+#[cfg(feature = "rand")]
+use crate::resp::Resp;
+#[cfg(feature = "rand")]
+use crate::table::dealer_card_from_desc;
 use crate::table::{GameDesc, Table};
+#[cfg(feature = "rand")]
 use rand::distributions::WeightedIndex;
+#[cfg(feature = "rand")]
 use rand::prelude::*;
 
-/// Generate a weighted-random next hand using player's statistics
-pub fn rand_next_hand(stats: &Table<PlayStats>) -> (Hand, Card) {
-    let (hands, weights): (Vec<GameDesc>, Vec<f32>) =
-        stats.iter().map(|(tkey, s)| (tkey, s.weight())).unzip();
-    let dist = WeightedIndex::new(&weights).unwrap();
-    //println!("{:?}", weights);
+/// How much less likely a retired hand (see PlayStats::is_retired()) is to be chosen. Retired
+/// hands still come up now and then, and getting one wrong brings it back into the rotation.
+#[cfg(feature = "rand")]
+pub const RETIRED_WEIGHT_SCALE: f32 = 0.1;
+
+/// The weight a hand with the given stats has when choosing the next hand. If refresh is given, a
+/// stale hand (see PlayStats::is_stale()) weighs as much as one never seen, however well the player
+/// has done on it.
+#[cfg(feature = "rand")]
+pub fn hand_weight(stats: PlayStats, refresh: Option<Refresh>) -> f32 {
+    if refresh.is_some_and(|r| stats.is_stale(r)) {
+        PlayStats::new().weight()
+    } else if stats.is_retired() {
+        stats.weight() * RETIRED_WEIGHT_SCALE
+    } else {
+        stats.weight()
+    }
+}
+
+/// The n cells the player does worst on, worst first, with their stats. Only cells that have been
+/// seen count. Cells are ranked by fraction correct, then by how often they've been seen (more
+/// often is worse, as there's more evidence), then by their order on a strategy card, so the result
+/// is the same for the same stats.
+pub fn weakest_cells(stats: &Table<PlayStats>, n: usize) -> Vec<(GameDesc, PlayStats)> {
+    let mut cells: Vec<(GameDesc, PlayStats)> = stats
+        .iter()
+        .filter(|(_, s)| s.seen() > 0)
+        .map(|(desc, s)| (*desc, *s))
+        .collect();
+    cells.sort_by(|(desc_a, a), (desc_b, b)| {
+        // a.correct / a.seen vs b.correct / b.seen, without floats
+        let frac_a = u64::from(a.correct()) * u64::from(b.seen());
+        let frac_b = u64::from(b.correct()) * u64::from(a.seen());
+        frac_a
+            .cmp(&frac_b)
+            .then(b.seen().cmp(&a.seen()))
+            .then(desc_a.cmp(desc_b))
+    });
+    cells.truncate(n);
+    cells
+}
+
+/// Generate a weighted-random next hand using player's statistics. None if no cell has any
+/// weight.
+#[cfg(feature = "rand")]
+pub fn rand_next_hand(stats: &Table<PlayStats>) -> Option<(Hand, Card)> {
+    rand_next_hand_where(stats, |_| true)
+}
+
+/// Generate a weighted-random next hand using player's statistics, only from the cells for which
+/// keep returns true. None if it is false for every cell, or none of those cells have any weight.
+#[cfg(feature = "rand")]
+pub fn rand_next_hand_where<F>(stats: &Table<PlayStats>, keep: F) -> Option<(Hand, Card)>
+where
+    F: Fn(&GameDesc) -> bool,
+{
+    rand_next_hand_freq_where(stats, None, None, keep)
+}
+
+/// Like rand_next_hand_where(), but if freq is given, each cell's weight is also scaled by how often
+/// it comes up in real play (see frequency::cell_frequencies()). If that leaves every kept cell
+/// with no weight, they're weighted by stats alone. If refresh is given, stale cells are boosted
+/// (see hand_weight()).
+#[cfg(feature = "rand")]
+pub fn rand_next_hand_freq_where<F>(
+    stats: &Table<PlayStats>,
+    freq: Option<&Table<f64>>,
+    refresh: Option<Refresh>,
+    keep: F,
+) -> Option<(Hand, Card)>
+where
+    F: Fn(&GameDesc) -> bool,
+{
+    let (hands, mut weights): (Vec<GameDesc>, Vec<f32>) = stats
+        .iter()
+        .filter(|(tkey, _)| keep(tkey))
+        .map(|(tkey, s)| (tkey, hand_weight(*s, refresh)))
+        .unzip();
+    if let Some(freq) = freq {
+        let scaled: Vec<f32> = hands
+            .iter()
+            .zip(&weights)
+            .map(|(tkey, w)| w * freq.get_desc(*tkey).unwrap_or(0.0) as f32)
+            .collect();
+        if scaled.iter().any(|w| *w > 0.0) {
+            weights = scaled;
+        }
+    }
+    // fails if there are no cells, or none with any weight
+    let dist = WeightedIndex::new(&weights).ok()?;
     let tkey = hands[dist.sample(&mut thread_rng())];
-    let hand = rand_hand(tkey);
-    let dealer_suit = rand_suit();
-    let card = match tkey.dealer {
-        2 => Card::new(Rank::R2, dealer_suit),
-        3 => Card::new(Rank::R3, dealer_suit),
-        4 => Card::new(Rank::R4, dealer_suit),
-        5 => Card::new(Rank::R5, dealer_suit),
-        6 => Card::new(Rank::R6, dealer_suit),
-        7 => Card::new(Rank::R7, dealer_suit),
-        8 => Card::new(Rank::R8, dealer_suit),
-        9 => Card::new(Rank::R9, dealer_suit),
-        10 => Card::new(Rank::RT, dealer_suit),
-        11 => Card::new(Rank::RA, dealer_suit),
-        _ => unreachable!(format!(
-            "It is impossible for the dealer to have a card valued at {}",
-            tkey.dealer
-        )),
+    let hand = rand_hand(tkey).ok()?;
+    let card = dealer_card_from_desc(tkey).ok()?;
+    Some((hand, Card::new(card.rank(), rand_suit())))
+}
+
+/// Generate a hand whose answer on the card is one that keep returns true for, e.g. `|resp| resp ==
+/// Resp::Split` for the card's splits, or `|resp| GameButton::from(resp) == GameButton::Surrender`
+/// for every surrender. The cell is weighted by the player's stats if given, and chosen uniformly
+/// if not. Answers are looked up in the card's table, so a hand with a composition-dependent
+/// answer (see BasicStrategy::resp()) may have a different one. None if no cell has such an
+/// answer.
+#[cfg(feature = "rand")]
+pub fn rand_hand_with_resp<F>(
+    bs: &BasicStrategy,
+    stats: Option<&Table<PlayStats>>,
+    keep: F,
+) -> Option<(Hand, Card)>
+where
+    F: Fn(Resp) -> bool,
+{
+    let cells = bs.table.cells_where(keep);
+    if cells.is_empty() {
+        return None;
+    }
+    let unseen;
+    let stats = match stats {
+        Some(stats) => stats,
+        None => {
+            // every cell weighs the same when none have been seen
+            unseen = Table::filled(PlayStats::new());
+            &unseen
+        }
     };
-    (hand.unwrap(), card)
+    rand_next_hand_where(stats, |desc| cells.contains(desc))
 }
 
 /// Generate a random hand as if from a shuffled infinite deck
+#[cfg(feature = "rand")]
 pub fn uniform_rand_2card_hand() -> (Hand, Card) {
     (Hand::new(&[rand_card(), rand_card()]), rand_card())
 }
@@ -42,6 +154,11 @@ pub mod playstats_table {
     use crate::playstats::PlayStats;
     use crate::table::{Table, NUM_CELLS};
 
+    /// Each cell is "correct/seen", "correct/seen/streak" if it has a streak,
+    /// "correct/seen/streak/last_seen" if it knows when it was last seen (see
+    /// PlayStats::mark_seen()), or "correct/seen/streak/last_seen/prior" if it has a prior (see
+    /// PlayStats::set_prior()), where last_seen is empty if it isn't known. Cells without any of
+    /// them keep the old format so that readers from before streaks can still parse most tables.
     pub fn parse_to_string(table: &Table<PlayStats>) -> String {
         // 6 chars per table item, 360 cells in the table.
         // "XX/YY,"      2 for each value, plus '/' and ','
@@ -50,7 +167,33 @@ pub mod playstats_table {
         let mut s = String::with_capacity(EXPECTED_MAX_LEN);
         let (hards, softs, pairs) = table.as_values_sorted();
         for item in hards.iter().chain(softs.iter()).chain(pairs.iter()) {
-            s.push_str(&format!("{}/{},", item.correct(), item.seen()));
+            if let Some(prior) = item.prior() {
+                s.push_str(&format!(
+                    "{}/{}/{}/{}/{},",
+                    item.correct(),
+                    item.seen(),
+                    item.streak(),
+                    item.last_seen().map_or(String::new(), |t| t.to_string()),
+                    prior
+                ));
+            } else if let Some(last_seen) = item.last_seen() {
+                s.push_str(&format!(
+                    "{}/{}/{}/{},",
+                    item.correct(),
+                    item.seen(),
+                    item.streak(),
+                    last_seen
+                ));
+            } else if item.streak() > 0 {
+                s.push_str(&format!(
+                    "{}/{}/{},",
+                    item.correct(),
+                    item.seen(),
+                    item.streak()
+                ));
+            } else {
+                s.push_str(&format!("{}/{},", item.correct(), item.seen()));
+            }
         }
         // remove last comma
         assert!(s.ends_with(','));
@@ -71,9 +214,8 @@ pub mod playstats_table {
         // parse string into Vec<PlayStats>
         let mut v = Vec::with_capacity(NUM_CELLS);
         for fract in fracts {
-            let mut stat = PlayStats::new();
             let parts = fract.split('/').collect::<Vec<&str>>();
-            if parts.len() != 2 {
+            if parts.len() < 2 || parts.len() > 5 {
                 return Err(format!("'{}' is not a valid fraction", fract));
             }
             let correct = match parts[0].parse::<u32>() {
@@ -88,15 +230,48 @@ pub mod playstats_table {
                     return Err(format!("'{}' not a valid u32: {}", parts[0], e));
                 }
             };
+            let streak = match parts.get(2).map(|p| p.parse::<u32>()) {
+                None => 0,
+                Some(Ok(v)) => v,
+                Some(Err(e)) => {
+                    return Err(format!("'{}' not a valid u32: {}", parts[2], e));
+                }
+            };
+            let last_seen = match parts.get(3).map(|p| p.parse::<u64>()) {
+                None => None,
+                Some(_) if parts[3].is_empty() => None,
+                Some(Ok(v)) => Some(v),
+                Some(Err(e)) => {
+                    return Err(format!("'{}' not a valid u64: {}", parts[3], e));
+                }
+            };
+            let prior = match parts.get(4).map(|p| p.parse::<u8>()) {
+                None => None,
+                Some(Ok(v)) if v <= 100 => Some(v),
+                Some(Ok(v)) => return Err(format!("prior {} is more than 100 percent", v)),
+                Some(Err(e)) => {
+                    return Err(format!("'{}' not a valid u8: {}", parts[4], e));
+                }
+            };
             if correct > seen {
                 return Err(format!(
                     "correct {} cannot be greater than seen {}",
                     correct, seen
                 ));
             }
-            stat.inc_by(seen - correct, false);
-            stat.inc_by(correct, true);
-            v.push(stat);
+            match PlayStats::from_counts(seen, correct, streak) {
+                Some(mut stat) => {
+                    stat.set_last_seen(last_seen);
+                    stat.set_prior(prior);
+                    v.push(stat)
+                }
+                None => {
+                    return Err(format!(
+                        "streak {} cannot be greater than correct {}",
+                        streak, correct
+                    ));
+                }
+            }
         }
         // Construct table with Vec
         let table = match Table::new(v.into_iter()) {
@@ -151,6 +326,44 @@ pub mod playstats_table {
             assert_eq!(s_in, s_out);
         }
 
+        #[test]
+        fn identity_streak() {
+            let mut stats = vec![PlayStats::new(); NUM_CELLS];
+            stats[10] = PlayStats::from_counts(110, 100, 7).unwrap();
+            let table_in = Table::new(stats).unwrap();
+            let s = parse_to_string(&table_in);
+            assert!(s.contains(",100/110/7,"));
+            assert_eq!(table_in, parse_from_string(s).unwrap());
+            // streak can't be more than correct
+            let mut s = "2/3/3,".repeat(NUM_CELLS);
+            s.truncate(s.len() - 1);
+            assert!(parse_from_string(s).is_err());
+        }
+
+        #[test]
+        fn identity_last_seen() {
+            let mut stats = vec![PlayStats::new(); NUM_CELLS];
+            stats[10] = PlayStats::from_counts(110, 100, 0).unwrap();
+            stats[10].mark_seen(1_700_000_000);
+            let table_in = Table::new(stats).unwrap();
+            let s = parse_to_string(&table_in);
+            assert!(s.contains(",100/110/0/1700000000,"));
+            assert_eq!(table_in, parse_from_string(s).unwrap());
+        }
+
+        #[test]
+        fn identity_prior() {
+            let mut stats = vec![PlayStats::new(); NUM_CELLS];
+            stats[10].set_prior(Some(80));
+            stats[11].set_prior(Some(0));
+            stats[11].mark_seen(1_700_000_000);
+            let table_in = Table::new(stats).unwrap();
+            let s = parse_to_string(&table_in);
+            assert!(s.contains(",0/0/0//80,0/0/0/1700000000/0,"));
+            assert_eq!(table_in, parse_from_string(s.clone()).unwrap());
+            assert!(parse_from_string(s.replace("//80", "//101")).is_err());
+        }
+
         #[test]
         fn empty_string() {
             assert!(parse_from_string("".into()).is_err());
@@ -187,3 +400,110 @@ pub mod playstats_table {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hand::HandType;
+
+    #[test]
+    fn weakest() {
+        let s = |seen, correct| PlayStats::from_counts(seen, correct, 0).unwrap();
+        let mut v = vec![PlayStats::new(); 360];
+        v[0] = s(4, 2);
+        v[1] = s(2, 1);
+        v[10] = s(9, 9);
+        v[170] = s(2, 1);
+        v[171] = s(3, 0);
+        let stats = Table::new(v).unwrap();
+        let weakest = weakest_cells(&stats, 4);
+        let descs: Vec<_> = weakest
+            .iter()
+            .map(|(d, _)| (d.hand, d.player, d.dealer))
+            .collect();
+        assert_eq!(
+            descs,
+            vec![
+                (HandType::Soft, 13, 3),
+                (HandType::Hard, 5, 2),
+                (HandType::Hard, 5, 3),
+                (HandType::Soft, 13, 2),
+            ]
+        );
+        assert_eq!(weakest[0].1, s(3, 0));
+        assert_eq!(weakest_cells(&stats, 100).len(), 5);
+    }
+
+    #[cfg(feature = "rand")]
+    #[test]
+    fn stale_weight() {
+        use crate::playstats::SECS_PER_DAY;
+        let mut s = PlayStats::from_counts(9, 9, 9).unwrap();
+        s.mark_seen(0);
+        let refresh = Refresh::after_days(60 * SECS_PER_DAY, 30);
+        assert_eq!(
+            hand_weight(s, None),
+            (1.0 - 9.0 / 10.0) * RETIRED_WEIGHT_SCALE
+        );
+        assert_eq!(hand_weight(s, Some(refresh)), 1.0);
+        s.mark_seen(59 * SECS_PER_DAY);
+        assert_eq!(
+            hand_weight(s, Some(refresh)),
+            (1.0 - 9.0 / 10.0) * RETIRED_WEIGHT_SCALE
+        );
+    }
+
+    #[cfg(feature = "rand")]
+    #[test]
+    fn no_cells_to_deal() {
+        let stats = Table::filled(PlayStats::new());
+        assert!(rand_next_hand_where(&stats, |_| false).is_none());
+        let (hand, dealer) = rand_next_hand_where(&stats, |d| d.dealer == 11).unwrap();
+        assert_eq!(GameDesc::from_hand(&hand, dealer).dealer, 11);
+    }
+
+    #[cfg(feature = "rand")]
+    #[test]
+    fn hands_with_resp() {
+        use crate::button::GameButton;
+        use crate::resp::resps_from_buf;
+        use crate::table::NUM_CELLS;
+        let table = Table::new(resps_from_buf(&include_bytes!("../../t1.txt")[..]).unwrap());
+        let bs = BasicStrategy::new(None, table.unwrap());
+        let splits = bs.table.cells_where(|r| r == Resp::Split);
+        assert!(splits.iter().all(|d| d.hand == HandType::Pair));
+        assert!(splits.windows(2).all(|w| w[0] < w[1]));
+        let mut stats = Table::new(vec![PlayStats::new(); NUM_CELLS]).unwrap();
+        for _ in 0..100 {
+            let (hand, dealer) = rand_hand_with_resp(&bs, None, |r| r == Resp::Split).unwrap();
+            assert_eq!(bs.resp(&hand, dealer), Ok(Resp::Split));
+            let (hand, dealer) = rand_hand_with_resp(&bs, Some(&stats), |r| {
+                GameButton::from(r) == GameButton::Double
+            })
+            .unwrap();
+            assert_eq!(
+                GameButton::from(bs.resp(&hand, dealer).unwrap()),
+                GameButton::Double
+            );
+        }
+        // weighted by stats: the one unseen cell comes up almost every time
+        for (desc, stat) in stats.iter_mut() {
+            if *desc != splits[0] {
+                stat.inc_by(1000, true);
+            }
+        }
+        let unseen = (0..100)
+            .filter(|_| {
+                let (hand, dealer) =
+                    rand_hand_with_resp(&bs, Some(&stats), |r| r == Resp::Split).unwrap();
+                GameDesc::from_hand(&hand, dealer) == splits[0]
+            })
+            .count();
+        assert!(unseen > 90, "{}", unseen);
+        // t1 has no surrender
+        assert!(
+            rand_hand_with_resp(&bs, None, |r| GameButton::from(r) == GameButton::Surrender)
+                .is_none()
+        );
+    }
+}
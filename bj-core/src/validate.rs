@@ -0,0 +1,132 @@
+//! Checks that a strategy card makes sense, beyond having a Resp in every cell.
+//!
+//! A Table can only be built with every cell filled, so a card that loads has all 360 of them. It
+//! can still say things no card should: split a hand that isn't a pair, surrender when its own
+//! rules don't allow it, or take a card on 21. check_card() lists everything like that it finds,
+//! so hand-edited and imported cards can be checked before anyone trains on them. Cards that
+//! deserialize, or are built with Table::new_checked(), have already been checked for splits of
+//! hands that aren't pairs.
+use crate::basicstrategy::rules::Surrender;
+use crate::basicstrategy::BasicStrategy;
+use crate::hand::HandType;
+use crate::resp::Resp;
+use crate::table::GameDesc;
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CardProblem {
+    /// Splitting a hand that isn't a pair
+    SplitNotPair(GameDesc, Resp),
+    /// Surrendering, but the card's rules don't allow it
+    SurrenderNotAllowed(GameDesc, Resp),
+    /// Surrendering against an ace, but the card's rules only allow it against anything else
+    SurrenderVsAce(GameDesc, Resp),
+    /// Taking a card on 21
+    DrawsOn21(GameDesc, Resp),
+}
+
+impl fmt::Display for CardProblem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::SplitNotPair(desc, resp) => {
+                write!(f, "{}: {}, but it isn't a pair", desc.label(), resp)
+            }
+            Self::SurrenderNotAllowed(desc, resp) => write!(
+                f,
+                "{}: {}, but the rules don't allow surrender",
+                desc.label(),
+                resp
+            ),
+            Self::SurrenderVsAce(desc, resp) => write!(
+                f,
+                "{}: {}, but the rules don't allow surrender against an ace",
+                desc.label(),
+                resp
+            ),
+            Self::DrawsOn21(desc, resp) => {
+                write!(f, "{}: {}, which takes a card on 21", desc.label(), resp)
+            }
+        }
+    }
+}
+
+/// The problems with the card, in the order of its cells (see Table::iter()). Surrender is only
+/// checked against the rules if the card has them. Composition-dependent plays aren't checked.
+pub fn check_card(bs: &BasicStrategy) -> Vec<CardProblem> {
+    let surrender = bs.rules.as_ref().map(|r| r.surrender);
+    let mut problems = vec![];
+    for (desc, resp) in bs.table.iter() {
+        let (desc, resp) = (*desc, *resp);
+        if resp.splits() && desc.hand != HandType::Pair {
+            problems.push(CardProblem::SplitNotPair(desc, resp));
+        }
+        let surrenders = matches!(
+            resp,
+            Resp::SurrenderElseHit | Resp::SurrenderElseStand | Resp::SurrenderElseSplit
+        );
+        match surrender {
+            Some(Surrender::No) if surrenders => {
+                problems.push(CardProblem::SurrenderNotAllowed(desc, resp))
+            }
+            Some(Surrender::NotAce) if surrenders && desc.dealer == 11 => {
+                problems.push(CardProblem::SurrenderVsAce(desc, resp))
+            }
+            _ => {}
+        }
+        let is_21 = desc.player == 21 && desc.hand != HandType::Pair;
+        if is_21 && !matches!(resp, Resp::Stand | Resp::SurrenderElseStand) {
+            problems.push(CardProblem::DrawsOn21(desc, resp));
+        }
+    }
+    problems
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::basicstrategy::rules::Rules;
+    use crate::resp::resps_from_buf;
+    use crate::table::Table;
+
+    const T1: &[u8] = include_bytes!("../../t1.txt");
+
+    #[test]
+    fn check() {
+        let rules = Rules {
+            hit_soft_17: true.into(),
+            ..Default::default()
+        };
+        let table = Table::new(resps_from_buf(T1).unwrap()).unwrap();
+        let mut bs = BasicStrategy::new(Some(rules), table);
+        assert_eq!(check_card(&bs), vec![]);
+        let cell = |s: &str| s.parse::<GameDesc>().unwrap();
+        let set = |bs: &mut BasicStrategy, s, resp| {
+            let (_, v) = bs.table.iter_mut().find(|(d, _)| **d == cell(s)).unwrap();
+            *v = resp;
+        };
+        set(&mut bs, "Hard 16 vs A", Resp::SurrenderElseHit);
+        set(&mut bs, "Hard 12 vs 2", Resp::Split);
+        set(&mut bs, "Soft 21 vs 6", Resp::DoubleElseHit);
+        let problems = check_card(&bs);
+        assert_eq!(problems.len(), 3);
+        assert!(problems.contains(&CardProblem::SurrenderNotAllowed(
+            cell("Hard 16 vs A"),
+            Resp::SurrenderElseHit
+        )));
+        assert!(problems.contains(&CardProblem::SplitNotPair(
+            cell("Hard 12 vs 2"),
+            Resp::Split
+        )));
+        assert_eq!(
+            CardProblem::DrawsOn21(cell("Soft 21 vs 6"), Resp::DoubleElseHit).to_string(),
+            "Soft 21 vs 6: Double(Hit), which takes a card on 21"
+        );
+        bs.rules.as_mut().unwrap().surrender = Surrender::NotAce;
+        assert!(check_card(&bs).contains(&CardProblem::SurrenderVsAce(
+            cell("Hard 16 vs A"),
+            Resp::SurrenderElseHit
+        )));
+        bs.rules = None;
+        assert_eq!(check_card(&bs).len(), 2);
+    }
+}
@@ -0,0 +1,160 @@
+//! Lists of a player's weakest cells, as exported for sharing, e.g. with a friend or a coach.
+//!
+//! A coach can send a list back as homework. import() checks it, and homework_pack() makes a drill
+//! pack that deals only those cells, e.g. for a time trial. As JSON, a list is worst cell first:
+//!
+//! ```json
+//! [
+//!   {"label": "Hard 16 vs 10", "seen": 40, "correct": 25},
+//!   {"label": "Soft 18 vs 9", "seen": 12, "correct": 8}
+//! ]
+//! ```
+//!
+//! Only the label (see GameDesc::label()) matters when importing, so seen and correct can be left
+//! out of a list written by hand.
+use crate::drillpack::{DrillPack, PackHands, Scoring, MAX_PACK_HANDS};
+use crate::playstats::PlayStats;
+use crate::table::{GameDesc, Table, NUM_CELLS};
+use crate::utils::weakest_cells;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct WeakCell {
+    /// e.g. "Hard 16 vs 10"
+    pub label: String,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub seen: u32,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub correct: u32,
+}
+
+impl WeakCell {
+    pub fn new(desc: GameDesc, stats: PlayStats) -> Self {
+        Self {
+            label: desc.label(),
+            seen: stats.seen(),
+            correct: stats.correct(),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Error)]
+pub enum WeakCellsError {
+    #[error("The list has no cells")]
+    NoCells,
+    /// More cells than a card has
+    #[error("The list has {0} cells, but a card only has {}", NUM_CELLS)]
+    TooMany(usize),
+    /// Not a cell of a strategy card
+    #[error("'{0}' is not a cell like 'Hard 16 vs 10'")]
+    BadLabel(String),
+    /// The cell with this label has more correct than seen
+    #[error("{0} has more hands correct than seen")]
+    BadCounts(String),
+}
+
+/// The player's n weakest cells, worst first, for sharing. See utils::weakest_cells().
+pub fn export(stats: &Table<PlayStats>, n: usize) -> Vec<WeakCell> {
+    weakest_cells(stats, n)
+        .into_iter()
+        .map(|(desc, s)| WeakCell::new(desc, s))
+        .collect()
+}
+
+/// Check a shared list, and return its cells in order without repeats
+pub fn import(cells: &[WeakCell]) -> Result<Vec<GameDesc>, WeakCellsError> {
+    if cells.is_empty() {
+        return Err(WeakCellsError::NoCells);
+    }
+    if cells.len() > NUM_CELLS {
+        return Err(WeakCellsError::TooMany(cells.len()));
+    }
+    let mut descs = vec![];
+    for cell in cells {
+        let desc = cell
+            .label
+            .parse::<GameDesc>()
+            .map_err(|_| WeakCellsError::BadLabel(cell.label.clone()))?;
+        if cell.correct > cell.seen {
+            return Err(WeakCellsError::BadCounts(cell.label.clone()));
+        }
+        if !descs.contains(&desc) {
+            descs.push(desc);
+        }
+    }
+    Ok(descs)
+}
+
+/// A drill pack of the given number of hands, all from the list's cells
+pub fn homework_pack(cells: &[WeakCell], hands: u32) -> Result<DrillPack, WeakCellsError> {
+    let descs = import(cells)?;
+    Ok(DrillPack {
+        name: "Homework".to_string(),
+        description: format!("{} assigned cells", descs.len()),
+        hands: PackHands::Labels(descs.iter().map(|d| d.label()).collect()),
+        scoring: Scoring {
+            hands: hands.clamp(1, MAX_PACK_HANDS),
+            ..Default::default()
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::drillpack::PackDeal;
+
+    fn cell(label: &str, seen: u32, correct: u32) -> WeakCell {
+        WeakCell {
+            label: label.to_string(),
+            seen,
+            correct,
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut stats = Table::filled(PlayStats::new());
+        let weak = GameDesc::new(crate::hand::HandType::Soft, 18, 9);
+        for (desc, s) in stats.iter_mut() {
+            s.inc(true);
+            s.inc(*desc != weak);
+        }
+        let cells = export(&stats, 3);
+        assert_eq!(cells[0], cell("Soft 18 vs 9", 2, 1));
+        let descs = import(&cells).unwrap();
+        assert_eq!(descs.len(), 3);
+        assert_eq!(descs[0], weak);
+        let pack = homework_pack(&cells, 50).unwrap();
+        assert_eq!(pack.scoring.hands, 50);
+        assert_eq!(pack.validate(), Ok(PackDeal::Cells(descs)));
+    }
+
+    #[test]
+    fn bad_lists() {
+        assert_eq!(import(&[]), Err(WeakCellsError::NoCells));
+        assert_eq!(
+            import(&[cell("Hard 16 vs 10", 0, 0), cell("Hard 30 vs 2", 0, 0)]),
+            Err(WeakCellsError::BadLabel("Hard 30 vs 2".to_string()))
+        );
+        assert_eq!(
+            import(&[cell("Hard 16 vs 10", 1, 2)]),
+            Err(WeakCellsError::BadCounts("Hard 16 vs 10".to_string()))
+        );
+        let many = vec![cell("Hard 16 vs 10", 0, 0); NUM_CELLS + 1];
+        assert_eq!(import(&many), Err(WeakCellsError::TooMany(NUM_CELLS + 1)));
+        // repeats are fine, and only count once
+        assert_eq!(import(&many[..2]).unwrap().len(), 1);
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn hand_written() {
+        let cells: Vec<WeakCell> =
+            serde_json::from_str(r#"[{"label": "Soft 18 vs 9"}, {"label": "8,8 vs A"}]"#).unwrap();
+        assert_eq!(import(&cells).unwrap().len(), 2);
+    }
+}
@@ -0,0 +1,271 @@
+//! The data the trainers are built on, separate from the engine that uses it.
+//!
+//! Every dataset is compiled in as the raw bytes of its file in data/, along with a name, a
+//! version, and what it holds. A dataset's version goes up whenever its contents change, so
+//! anything that stores results against a dataset can tell that they may be stale.
+//!
+//! - Strategy cards are bj-core BasicStrategy as JSON: the rules they're for and every cell's
+//!   correct play. t1 is from wizardofodds.com. The rest, one for each number of decks, soft 17
+//!   rule, double after split rule, and surrender rule, are generated by bj_core::ev::strategy().
+//!   Its simplifications cost a little with 1 or 2 decks, so where a close cell there comes out
+//!   differently from the published charts, the card has the published play: 7,7 vs 10 stands (or
+//!   surrenders) with one deck, and 11 vs A doubles with two and S17. Edit them with
+//!   PUBLISHED_OVER_GENERATED and regenerate the cards with `cargo xtask cards`, rather than
+//!   editing the cards by hand.
+//! - Deviations are JSON lists of index plays for HiLo: a cell label (as GameDesc::label() makes),
+//!   the true count at or above which to play at_or_above, and what to play below it. Resps are
+//!   spelled as their bj-core variant names.
+//! - Drill packs are bj-core DrillPack as JSON: a set of hands to practice and what it takes to
+//!   pass them.
+//!
+//! Nothing here needs bj-core. With the "core" feature, the typed constructors (t1(),
+//! illustrious18(), fab4()) and Dataset::drill_pack() parse datasets into bj-core types,
+//! active_index_plays() says which index plays are in effect at a true count, and
+//! strategy_card_for() picks the card for a table's rules.
+#[cfg(feature = "core")]
+mod typed;
+#[cfg(feature = "core")]
+pub use typed::*;
+
+pub const T1_JSON: &[u8] = include_bytes!("../data/t1.json");
+pub const ILLUSTRIOUS18_JSON: &[u8] = include_bytes!("../data/illustrious18.json");
+pub const FAB4_JSON: &[u8] = include_bytes!("../data/fab4.json");
+pub const STIFFS_JSON: &[u8] = include_bytes!("../data/stiffs.json");
+pub const SOFT_DOUBLES_JSON: &[u8] = include_bytes!("../data/soft-doubles.json");
+
+/// The first of the Illustrious 18, which isn't a cell: take insurance at or above this true
+/// count
+pub const INSURANCE_INDEX: i8 = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Kind {
+    StrategyCard,
+    Deviations,
+    DrillPack,
+}
+
+#[derive(Debug)]
+pub struct Dataset {
+    pub name: &'static str,
+    pub version: u32,
+    pub kind: Kind,
+    pub description: &'static str,
+    pub data: &'static [u8],
+}
+
+pub static DATASETS: &[Dataset] = &[
+    Dataset {
+        name: "t1",
+        version: 1,
+        kind: Kind::StrategyCard,
+        description: "4+ decks, dealer hits soft 17, double after split, no surrender",
+        data: T1_JSON,
+    },
+    Dataset {
+        name: "1d-h17-das",
+        version: 3,
+        kind: Kind::StrategyCard,
+        description: "1 deck, dealer hits soft 17, double after split, no surrender",
+        data: include_bytes!("../data/1d-h17-das.json"),
+    },
+    Dataset {
+        name: "1d-h17-das-ls",
+        version: 3,
+        kind: Kind::StrategyCard,
+        description: "1 deck, dealer hits soft 17, double after split, late surrender",
+        data: include_bytes!("../data/1d-h17-das-ls.json"),
+    },
+    Dataset {
+        name: "1d-h17-nodas",
+        version: 3,
+        kind: Kind::StrategyCard,
+        description: "1 deck, dealer hits soft 17, no double after split, no surrender",
+        data: include_bytes!("../data/1d-h17-nodas.json"),
+    },
+    Dataset {
+        name: "1d-h17-nodas-ls",
+        version: 3,
+        kind: Kind::StrategyCard,
+        description: "1 deck, dealer hits soft 17, no double after split, late surrender",
+        data: include_bytes!("../data/1d-h17-nodas-ls.json"),
+    },
+    Dataset {
+        name: "1d-s17-das",
+        version: 3,
+        kind: Kind::StrategyCard,
+        description: "1 deck, dealer stands on soft 17, double after split, no surrender",
+        data: include_bytes!("../data/1d-s17-das.json"),
+    },
+    Dataset {
+        name: "1d-s17-das-ls",
+        version: 3,
+        kind: Kind::StrategyCard,
+        description: "1 deck, dealer stands on soft 17, double after split, late surrender",
+        data: include_bytes!("../data/1d-s17-das-ls.json"),
+    },
+    Dataset {
+        name: "1d-s17-nodas",
+        version: 3,
+        kind: Kind::StrategyCard,
+        description: "1 deck, dealer stands on soft 17, no double after split, no surrender",
+        data: include_bytes!("../data/1d-s17-nodas.json"),
+    },
+    Dataset {
+        name: "1d-s17-nodas-ls",
+        version: 3,
+        kind: Kind::StrategyCard,
+        description: "1 deck, dealer stands on soft 17, no double after split, late surrender",
+        data: include_bytes!("../data/1d-s17-nodas-ls.json"),
+    },
+    Dataset {
+        name: "2d-h17-das",
+        version: 1,
+        kind: Kind::StrategyCard,
+        description: "2 decks, dealer hits soft 17, double after split, no surrender",
+        data: include_bytes!("../data/2d-h17-das.json"),
+    },
+    Dataset {
+        name: "2d-h17-das-ls",
+        version: 1,
+        kind: Kind::StrategyCard,
+        description: "2 decks, dealer hits soft 17, double after split, late surrender",
+        data: include_bytes!("../data/2d-h17-das-ls.json"),
+    },
+    Dataset {
+        name: "2d-h17-nodas",
+        version: 1,
+        kind: Kind::StrategyCard,
+        description: "2 decks, dealer hits soft 17, no double after split, no surrender",
+        data: include_bytes!("../data/2d-h17-nodas.json"),
+    },
+    Dataset {
+        name: "2d-h17-nodas-ls",
+        version: 1,
+        kind: Kind::StrategyCard,
+        description: "2 decks, dealer hits soft 17, no double after split, late surrender",
+        data: include_bytes!("../data/2d-h17-nodas-ls.json"),
+    },
+    Dataset {
+        name: "2d-s17-das",
+        version: 3,
+        kind: Kind::StrategyCard,
+        description: "2 decks, dealer stands on soft 17, double after split, no surrender",
+        data: include_bytes!("../data/2d-s17-das.json"),
+    },
+    Dataset {
+        name: "2d-s17-das-ls",
+        version: 3,
+        kind: Kind::StrategyCard,
+        description: "2 decks, dealer stands on soft 17, double after split, late surrender",
+        data: include_bytes!("../data/2d-s17-das-ls.json"),
+    },
+    Dataset {
+        name: "2d-s17-nodas",
+        version: 3,
+        kind: Kind::StrategyCard,
+        description: "2 decks, dealer stands on soft 17, no double after split, no surrender",
+        data: include_bytes!("../data/2d-s17-nodas.json"),
+    },
+    Dataset {
+        name: "2d-s17-nodas-ls",
+        version: 3,
+        kind: Kind::StrategyCard,
+        description: "2 decks, dealer stands on soft 17, no double after split, late surrender",
+        data: include_bytes!("../data/2d-s17-nodas-ls.json"),
+    },
+    Dataset {
+        name: "4d-h17-das-ls",
+        version: 1,
+        kind: Kind::StrategyCard,
+        description: "4+ decks, dealer hits soft 17, double after split, late surrender",
+        data: include_bytes!("../data/4d-h17-das-ls.json"),
+    },
+    Dataset {
+        name: "4d-h17-nodas",
+        version: 1,
+        kind: Kind::StrategyCard,
+        description: "4+ decks, dealer hits soft 17, no double after split, no surrender",
+        data: include_bytes!("../data/4d-h17-nodas.json"),
+    },
+    Dataset {
+        name: "4d-h17-nodas-ls",
+        version: 1,
+        kind: Kind::StrategyCard,
+        description: "4+ decks, dealer hits soft 17, no double after split, late surrender",
+        data: include_bytes!("../data/4d-h17-nodas-ls.json"),
+    },
+    Dataset {
+        name: "4d-s17-das",
+        version: 1,
+        kind: Kind::StrategyCard,
+        description: "4+ decks, dealer stands on soft 17, double after split, no surrender",
+        data: include_bytes!("../data/4d-s17-das.json"),
+    },
+    Dataset {
+        name: "4d-s17-das-ls",
+        version: 1,
+        kind: Kind::StrategyCard,
+        description: "4+ decks, dealer stands on soft 17, double after split, late surrender",
+        data: include_bytes!("../data/4d-s17-das-ls.json"),
+    },
+    Dataset {
+        name: "4d-s17-nodas",
+        version: 1,
+        kind: Kind::StrategyCard,
+        description: "4+ decks, dealer stands on soft 17, no double after split, no surrender",
+        data: include_bytes!("../data/4d-s17-nodas.json"),
+    },
+    Dataset {
+        name: "4d-s17-nodas-ls",
+        version: 1,
+        kind: Kind::StrategyCard,
+        description: "4+ decks, dealer stands on soft 17, no double after split, late surrender",
+        data: include_bytes!("../data/4d-s17-nodas-ls.json"),
+    },
+    Dataset {
+        name: "illustrious18",
+        version: 2,
+        kind: Kind::Deviations,
+        description: "The Illustrious 18 HiLo index plays for H17 shoe games, less insurance",
+        data: ILLUSTRIOUS18_JSON,
+    },
+    Dataset {
+        name: "fab4",
+        version: 1,
+        kind: Kind::Deviations,
+        description: "The Fab 4 HiLo surrender index plays for shoe games",
+        data: FAB4_JSON,
+    },
+    Dataset {
+        name: "stiffs",
+        version: 1,
+        kind: Kind::DrillPack,
+        description: "Drill pack: hard 12-16 against 9, 10, and A",
+        data: STIFFS_JSON,
+    },
+    Dataset {
+        name: "soft-doubles",
+        version: 1,
+        kind: Kind::DrillPack,
+        description: "Drill pack: soft 13-18 against 3-6",
+        data: SOFT_DOUBLES_JSON,
+    },
+];
+
+/// The dataset with the given name, if there is one
+pub fn dataset(name: &str) -> Option<&'static Dataset> {
+    DATASETS.iter().find(|d| d.name == name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_unique() {
+        for d in DATASETS {
+            assert!(std::ptr::eq(dataset(d.name).unwrap(), d));
+        }
+        assert!(dataset("not a dataset").is_none());
+    }
+}
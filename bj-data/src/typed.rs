@@ -0,0 +1,537 @@
+//! Datasets parsed into bj-core types
+use crate::{dataset, Dataset, Kind, DATASETS, INSURANCE_INDEX, T1_JSON};
+use bj_core::basicstrategy::rules::{NumDecks, Rules, Surrender};
+use bj_core::basicstrategy::BasicStrategy;
+use bj_core::drillpack::{DrillPack, DrillPackError};
+use bj_core::ev;
+use bj_core::resp::Resp;
+use bj_core::table::GameDesc;
+use lazy_static::lazy_static;
+use serde::Deserialize;
+use std::fmt;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum DataError {
+    /// The dataset isn't valid JSON for its kind
+    #[error("Bad dataset JSON: {0}")]
+    Json(#[from] serde_json::Error),
+    /// A deviation names a cell that doesn't exist
+    #[error("'{0}' is not a cell")]
+    BadCell(String),
+    /// The dataset is of a different kind than was asked for
+    #[error("Dataset is {0:?}")]
+    WrongKind(Kind),
+    /// A drill pack parsed but isn't valid
+    #[error("Bad drill pack: {0}")]
+    BadPack(#[source] DrillPackError),
+}
+
+/// An index play: in the given cell, play at_or_above when the true count is at least index,
+/// otherwise play below
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Deviation {
+    pub cell: GameDesc,
+    pub index: i8,
+    pub at_or_above: Resp,
+    pub below: Resp,
+}
+
+impl Deviation {
+    /// What to play at the given true count
+    pub fn resp(&self, true_count: f32) -> Resp {
+        if true_count >= f32::from(self.index) {
+            self.at_or_above
+        } else {
+            self.below
+        }
+    }
+}
+
+/// A play a counter makes differently from the card at some true count
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum IndexPlay {
+    /// Take insurance, or even money
+    Insurance,
+    /// Play the deviation's resp at the count instead of the card's
+    Cell(Deviation, Resp),
+}
+
+impl fmt::Display for IndexPlay {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Insurance => write!(f, "Take insurance"),
+            Self::Cell(d, resp) => write!(f, "{} on {}", resp, d.cell.label()),
+        }
+    }
+}
+
+/// The index plays in effect at the given true count for a counter playing the given card: the
+/// Illustrious 18 and insurance, plus the Fab 4 if the card's rules allow surrender. A deviation
+/// is in effect when what it says to play at the count isn't what the card says, so negative
+/// indexes show up below their index and positive ones at or above it. The indexes are for a
+/// dealer who hits soft 17, like t1. A deviation doesn't fit a card that already plays its
+/// deviating side at a neutral count, as with 11 vs A on cards where the dealer stands on soft 17,
+/// which hit it, and is left out. Where both the Fab 4 and the
+/// Illustrious 18 have a play for a cell, surrendering wins.
+pub fn active_index_plays(true_count: f32, bs: &BasicStrategy) -> Vec<IndexPlay> {
+    let surrender = bs
+        .rules
+        .as_ref()
+        .is_some_and(|r| r.surrender != Surrender::No);
+    let mut devs = if surrender { fab4() } else { vec![] };
+    for d in illustrious18() {
+        if !devs.iter().any(|f| f.cell == d.cell) {
+            devs.push(d);
+        }
+    }
+    let mut plays: Vec<IndexPlay> = devs
+        .into_iter()
+        .filter_map(|d| {
+            let card = bs.table.get_desc(d.cell)?;
+            let fits = match d.index {
+                i if i > 0 => card == d.below,
+                i if i < 0 => card == d.at_or_above,
+                _ => true,
+            };
+            let resp = d.resp(true_count);
+            if fits && card != resp {
+                Some(IndexPlay::Cell(d, resp))
+            } else {
+                None
+            }
+        })
+        .collect();
+    if true_count >= f32::from(INSURANCE_INDEX) {
+        plays.push(IndexPlay::Insurance);
+    }
+    plays
+}
+
+/// e.g. "TC +4.0: Stand on Hard 16 vs 10, Take insurance", or "TC +0.0: Play the card" if there
+/// are no plays
+pub fn index_play_summary(true_count: f32, plays: &[IndexPlay]) -> String {
+    let plays = if plays.is_empty() {
+        "Play the card".to_string()
+    } else {
+        plays
+            .iter()
+            .map(|p| p.to_string())
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    format!("TC {:+.1}: {}", true_count, plays)
+}
+
+#[derive(Deserialize)]
+struct RawDeviation {
+    cell: String,
+    index: i8,
+    at_or_above: Resp,
+    below: Resp,
+}
+
+impl Dataset {
+    /// Parse this dataset as a strategy card
+    pub fn strategy_card(&self) -> Result<BasicStrategy, DataError> {
+        match self.kind {
+            Kind::StrategyCard => Ok(serde_json::from_slice(self.data)?),
+            k => Err(DataError::WrongKind(k)),
+        }
+    }
+
+    /// Parse this dataset as a drill pack, and check that it's valid
+    pub fn drill_pack(&self) -> Result<DrillPack, DataError> {
+        if self.kind != Kind::DrillPack {
+            return Err(DataError::WrongKind(self.kind));
+        }
+        let pack: DrillPack = serde_json::from_slice(self.data)?;
+        pack.validate().map_err(DataError::BadPack)?;
+        Ok(pack)
+    }
+
+    /// Parse this dataset as a list of deviations
+    pub fn deviations(&self) -> Result<Vec<Deviation>, DataError> {
+        if self.kind != Kind::Deviations {
+            return Err(DataError::WrongKind(self.kind));
+        }
+        let raw: Vec<RawDeviation> = serde_json::from_slice(self.data)?;
+        raw.into_iter()
+            .map(|r| {
+                let RawDeviation {
+                    cell,
+                    index,
+                    at_or_above,
+                    below,
+                } = r;
+                Ok(Deviation {
+                    cell: cell.parse().map_err(|_| DataError::BadCell(cell))?,
+                    index,
+                    at_or_above,
+                    below,
+                })
+            })
+            .collect()
+    }
+}
+
+// These can't fail: every dataset is checked by the tests below.
+
+lazy_static! {
+    /// Every bundled strategy card, parsed the first time one is looked up
+    static ref STRATEGY_CARDS: Vec<(&'static Dataset, BasicStrategy)> = DATASETS
+        .iter()
+        .filter(|d| d.kind == Kind::StrategyCard)
+        .map(|d| (d, d.strategy_card().unwrap()))
+        .collect();
+}
+
+/// The t1 strategy card
+pub fn t1() -> BasicStrategy {
+    serde_json::from_slice(T1_JSON).unwrap()
+}
+
+/// The rules a bundled card is made for that are closest to the given ones. Bundled cards are for
+/// 1, 2, or 4+ decks, and a dealer who peeks, with the default rules for splitting and the payout.
+/// 3 decks play like 4+, surrender except against an ace plays like surrender, as the card's
+/// fallbacks cover the ace, and a dealer who doesn't peek but only takes original bets plays like
+/// one who peeks. A dealer who takes all bets doesn't (see strategy_card_for()).
+fn bundled_rules(rules: &Rules) -> Rules {
+    Rules {
+        decks: match rules.decks {
+            NumDecks::Three => NumDecks::FourPlus,
+            ref d => d.clone(),
+        },
+        hit_soft_17: rules.hit_soft_17.clone(),
+        double_after_split: rules.double_after_split.clone(),
+        surrender: match rules.surrender {
+            Surrender::NotAce => Surrender::Yes,
+            s => s,
+        },
+        ..Default::default()
+    }
+}
+
+/// The bundled strategy card for the given rules, or for the closest rules there's a card for
+/// (see bundled_rules()). There's one for every number of decks, soft 17 rule, double after split
+/// rule, and surrender rule. None if a dealer blackjack takes all bets (ENHC), as those games play
+/// too differently for any bundled card to do. Make one with bj_core::ev::strategy() instead.
+pub fn strategy_card_for(rules: &Rules) -> Option<&'static Dataset> {
+    if rules.dealer_bj_takes_all_bets() {
+        return None;
+    }
+    let want = bundled_rules(rules);
+    // can't be None: the tests below check there's a card for every other rule
+    STRATEGY_CARDS
+        .iter()
+        .find(|(_, bs)| bs.rules.as_ref() == Some(&want))
+        .map(|(d, _)| *d)
+}
+
+/// The cells where a generated card has the published play instead of the one
+/// bj_core::ev::strategy() comes up with, by the start of the card's name. A card that surrenders
+/// in the cell still does, and falls back to the published play.
+pub const PUBLISHED_OVER_GENERATED: &[(&str, &str, Resp)] = &[
+    ("1d-", "7,7 vs 10", Resp::Stand),
+    ("2d-s17-", "Hard 11 vs A", Resp::DoubleElseHit),
+];
+
+/// The rules a generated card is for, from its name, e.g. 2 decks, the dealer standing on soft 17,
+/// doubling after splitting, and surrender for "2d-s17-das-ls". The other rules are their
+/// defaults. None if the name isn't of that form.
+fn generated_card_rules(name: &str) -> Option<Rules> {
+    let parts: Vec<&str> = name.split('-').collect();
+    let (decks, soft_17, das, surrender) = match parts.as_slice() {
+        [decks, soft_17, das] => (*decks, *soft_17, *das, Surrender::No),
+        [decks, soft_17, das, "ls"] => (*decks, *soft_17, *das, Surrender::Yes),
+        _ => return None,
+    };
+    let flag = |s: &str, on: &str, off: &str| match s {
+        _ if s == on => Some(true),
+        _ if s == off => Some(false),
+        _ => None,
+    };
+    Some(Rules {
+        decks: match decks {
+            "1d" => NumDecks::One,
+            "2d" => NumDecks::Two,
+            "4d" => NumDecks::FourPlus,
+            _ => return None,
+        },
+        hit_soft_17: flag(soft_17, "h17", "s17")?.into(),
+        double_after_split: flag(das, "das", "nodas")?.into(),
+        surrender,
+        ..Default::default()
+    })
+}
+
+/// The bundled card with the given name the way it's made: bj_core::ev::strategy() for the rules
+/// its name gives, with the published plays in PUBLISHED_OVER_GENERATED. None for a card that isn't
+/// generated, like t1. `cargo xtask cards` writes every generated card's dataset with this.
+pub fn generate_card(name: &str) -> Option<BasicStrategy> {
+    let mut bs = ev::strategy(&generated_card_rules(name)?);
+    let published: Vec<(GameDesc, Resp)> = PUBLISHED_OVER_GENERATED
+        .iter()
+        .filter(|(prefix, _, _)| name.starts_with(prefix))
+        // can't fail: the tests below check every cell parses
+        .map(|(_, cell, resp)| (cell.parse().unwrap(), *resp))
+        .collect();
+    bs.table = bs.table.map(
+        |desc, generated| match published.iter().find(|(d, _)| *d == desc) {
+            None => generated,
+            Some((_, resp)) => match (generated, resp) {
+                (Resp::SurrenderElseHit, Resp::Stand) => Resp::SurrenderElseStand,
+                (Resp::SurrenderElseStand, Resp::Hit) => Resp::SurrenderElseHit,
+                _ => *resp,
+            },
+        },
+    );
+    Some(bs)
+}
+
+/// The Illustrious 18, less insurance (see INSURANCE_INDEX)
+pub fn illustrious18() -> Vec<Deviation> {
+    dataset("illustrious18").unwrap().deviations().unwrap()
+}
+
+/// The Fab 4 surrender deviations
+pub fn fab4() -> Vec<Deviation> {
+    dataset("fab4").unwrap().deviations().unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bj_core::basicstrategy::rules::NoPeekLoss;
+    use bj_core::ev::misplays;
+
+    #[test]
+    fn all_parse() {
+        for d in DATASETS {
+            match d.kind {
+                Kind::StrategyCard => {
+                    let bs = d.strategy_card().unwrap();
+                    assert_eq!(bs.table.values().count(), 360, "{}", d.name);
+                    assert!(d.deviations().is_err());
+                }
+                Kind::Deviations => {
+                    assert!(!d.deviations().unwrap().is_empty(), "{}", d.name);
+                    assert!(d.strategy_card().is_err());
+                }
+                Kind::DrillPack => {
+                    assert!(!d.drill_pack().unwrap().name.is_empty(), "{}", d.name);
+                    assert!(d.deviations().is_err());
+                }
+            }
+        }
+        assert_eq!(illustrious18().len(), 17);
+        assert_eq!(fab4().len(), 4);
+    }
+
+    #[test]
+    fn card_for_every_rule() {
+        let mut rules = t1().rules.unwrap();
+        assert_eq!(strategy_card_for(&rules).unwrap().name, "t1");
+        for decks in &[
+            NumDecks::One,
+            NumDecks::Two,
+            NumDecks::Three,
+            NumDecks::FourPlus,
+        ] {
+            for &h17 in &[true, false] {
+                for &das in &[true, false] {
+                    for &surrender in &[Surrender::No, Surrender::Yes, Surrender::NotAce] {
+                        rules.decks = decks.clone();
+                        rules.hit_soft_17 = h17.into();
+                        rules.double_after_split = das.into();
+                        rules.surrender = surrender;
+                        rules.peek_bj = false.into();
+                        let card = strategy_card_for(&rules).unwrap();
+                        let card = card.strategy_card().unwrap();
+                        assert_eq!(card.rules, Some(bundled_rules(&rules)));
+                    }
+                }
+            }
+        }
+        let d = strategy_card_for(&rules).unwrap();
+        assert_eq!(d.name, "4d-s17-nodas-ls");
+        // no card is for a dealer whose blackjack takes doubles and splits too
+        rules.no_peek_loss = NoPeekLoss::AllBets;
+        assert!(strategy_card_for(&rules).is_none());
+    }
+
+    #[test]
+    fn cards_are_generated() {
+        let mut generated = 0;
+        for d in DATASETS.iter().filter(|d| d.kind == Kind::StrategyCard) {
+            if let Some(bs) = generate_card(d.name) {
+                assert!(
+                    serde_json::to_vec(&bs).unwrap() == d.data,
+                    "{} isn't what generate_card() makes. Run `cargo xtask cards`",
+                    d.name
+                );
+                generated += 1;
+            }
+        }
+        assert_eq!(generated, 23);
+        assert!(generate_card("t1").is_none());
+        assert!(generate_card("3d-s17-das").is_none());
+        assert!(generate_card("2d-s17-das-es").is_none());
+    }
+
+    /// The published cells are the only ones where a card may cost anything by bj_core::ev's
+    /// reckoning
+    #[test]
+    fn generated_cards_are_best() {
+        for d in DATASETS.iter().filter(|d| d.kind == Kind::StrategyCard) {
+            let bs = d.strategy_card().unwrap();
+            let rules = bs.rules.as_ref().unwrap();
+            let cells: Vec<String> = misplays(&bs, rules)
+                .iter()
+                .map(|m| m.desc.label())
+                .collect();
+            let published: Vec<String> = PUBLISHED_OVER_GENERATED
+                .iter()
+                .filter(|(prefix, _, _)| d.name.starts_with(prefix))
+                .map(|(_, cell, _)| cell.parse::<GameDesc>().unwrap().label())
+                .collect();
+            assert!(
+                cells.iter().all(|c| published.contains(c)),
+                "{}: {:?}",
+                d.name,
+                cells
+            );
+        }
+    }
+
+    #[test]
+    fn published_cells() {
+        // a few cells from the published charts for each card with 1 or 2 decks, especially ones
+        // where removing the player's cards and the up card changes the play
+        let both = [
+            ("Hard 12 vs 4", Resp::Stand),
+            ("Hard 9 vs 2", Resp::DoubleElseHit),
+            ("Hard 16 vs 7", Resp::Hit),
+            ("9,9 vs 7", Resp::Stand),
+        ];
+        let one_deck = [
+            ("Hard 11 vs A", Resp::DoubleElseHit),
+            ("Hard 13 vs 2", Resp::Stand),
+            ("Soft 19 vs 6", Resp::DoubleElseStand),
+            ("Soft 13 vs 4", Resp::DoubleElseHit),
+            ("Soft 17 vs 2", Resp::DoubleElseHit),
+        ];
+        let one_deck_h17 = [
+            ("Hard 8 vs 5", Resp::DoubleElseHit),
+            ("Hard 8 vs 6", Resp::DoubleElseHit),
+            ("Soft 18 vs A", Resp::Hit),
+        ];
+        let one_deck_s17 = [("Soft 18 vs A", Resp::Stand)];
+        let two_decks = [
+            ("Hard 11 vs A", Resp::DoubleElseHit),
+            ("Hard 8 vs 6", Resp::Hit),
+        ];
+        let two_decks_h17 = [("Soft 19 vs 6", Resp::DoubleElseStand)];
+        let two_decks_s17 = [("Soft 19 vs 6", Resp::Stand)];
+        let das = [("2,2 vs 2", Resp::Split), ("3,3 vs 2", Resp::Split)];
+        let nodas = [("2,2 vs 2", Resp::Hit), ("3,3 vs 2", Resp::Hit)];
+        let surrender = [("Hard 16 vs 10", Resp::SurrenderElseHit)];
+        let no_surrender = [("Hard 16 vs 10", Resp::Hit)];
+        let mut checked = 0;
+        for d in DATASETS
+            .iter()
+            .filter(|d| d.name.starts_with("1d-") || d.name.starts_with("2d-"))
+        {
+            let bs = d.strategy_card().unwrap();
+            let one = d.name.starts_with("1d-");
+            let h17 = d.name.contains("-h17-");
+            let mut cells: Vec<&(&str, Resp)> = both.iter().collect();
+            cells.extend(match (one, h17) {
+                (true, true) => one_deck
+                    .iter()
+                    .chain(one_deck_h17.iter())
+                    .collect::<Vec<_>>(),
+                (true, false) => one_deck.iter().chain(one_deck_s17.iter()).collect(),
+                (false, true) => two_decks.iter().chain(two_decks_h17.iter()).collect(),
+                (false, false) => two_decks.iter().chain(two_decks_s17.iter()).collect(),
+            });
+            cells.extend(if d.name.contains("-das") {
+                das.iter()
+            } else {
+                nodas.iter()
+            });
+            cells.extend(if !d.name.ends_with("-ls") {
+                no_surrender.iter()
+            } else {
+                surrender.iter()
+            });
+            for (cell, resp) in cells {
+                let desc: GameDesc = cell.parse().unwrap();
+                assert_eq!(
+                    bs.table.get_desc(desc).unwrap(),
+                    *resp,
+                    "{} {}",
+                    d.name,
+                    cell
+                );
+            }
+            checked += 1;
+        }
+        assert_eq!(checked, 16);
+    }
+
+    #[test]
+    fn index_plays() {
+        let t1 = t1();
+        let plays = active_index_plays(0.5, &t1);
+        assert_eq!(
+            plays,
+            vec![IndexPlay::Cell(illustrious18()[0], Resp::Stand)]
+        );
+        assert_eq!(
+            index_play_summary(0.5, &plays),
+            "TC +0.5: Stand on Hard 16 vs 10"
+        );
+        // negative indexes are in effect below them
+        let plays = active_index_plays(-1.5, &t1);
+        assert!(plays.contains(&IndexPlay::Cell(illustrious18()[13], Resp::Hit)));
+        assert!(plays.contains(&IndexPlay::Cell(illustrious18()[15], Resp::Hit)));
+        assert!(!plays.contains(&IndexPlay::Insurance));
+        let plays = active_index_plays(4.0, &t1);
+        assert!(plays.contains(&IndexPlay::Insurance));
+        assert!(plays.contains(&IndexPlay::Cell(illustrious18()[1], Resp::Stand)));
+        // with surrender, surrendering 15 vs 10 replaces standing on it
+        let mut late = t1;
+        late.rules.as_mut().unwrap().surrender = Surrender::Yes;
+        let plays = active_index_plays(4.0, &late);
+        assert!(plays.contains(&IndexPlay::Cell(fab4()[1], Resp::SurrenderElseHit)));
+        assert!(!plays.contains(&IndexPlay::Cell(illustrious18()[1], Resp::Stand)));
+        assert_eq!(
+            index_play_summary(-0.5, &active_index_plays(-0.5, &late)),
+            "TC -0.5: Hit on Hard 12 vs 4"
+        );
+    }
+
+    #[test]
+    fn deviation_resp() {
+        let d = illustrious18()[0];
+        assert_eq!(d.cell, "Hard 16 vs 10".parse::<GameDesc>().unwrap());
+        assert_eq!(d.resp(-0.5), Resp::Hit);
+        assert_eq!(d.resp(0.0), Resp::Stand);
+        // every deviation plays like t1 on the neutral side of its index: below a positive one,
+        // and at or above a negative one
+        let t1 = t1();
+        for d in illustrious18() {
+            let card = t1.table.get_desc(d.cell).unwrap();
+            match d.index {
+                i if i > 0 => assert_eq!(card, d.below, "{}", d.cell.label()),
+                i if i < 0 => assert_eq!(card, d.at_or_above, "{}", d.cell.label()),
+                _ => assert!(
+                    card == d.below || card == d.at_or_above,
+                    "{}",
+                    d.cell.label()
+                ),
+            }
+        }
+    }
+}
@@ -0,0 +1,79 @@
+/*
+ * C interface to the blackjack strategy engine. Link against libbj_ffi.
+ *
+ * Strategy cards and counters are opaque and must be released with the matching *_free function.
+ * Strings returned by this library must be released with bj_string_free(). Functions returning
+ * int return -1 on error.
+ */
+#ifndef BJ_H
+#define BJ_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* rank: 1 is ace, 2-10 are pips, 11-13 are J, Q, K. suit: 0-3 are clubs, diamonds, hearts, spades */
+typedef struct {
+    uint8_t rank;
+    uint8_t suit;
+} BjCard;
+
+/* What a strategy card says to do, as returned by bj_strategy_lookup() */
+enum BjResp {
+    BJ_RESP_HIT = 0,
+    BJ_RESP_STAND = 1,
+    BJ_RESP_DOUBLE_ELSE_HIT = 2,
+    BJ_RESP_DOUBLE_ELSE_STAND = 3,
+    BJ_RESP_SPLIT = 4,
+    BJ_RESP_SURRENDER_ELSE_HIT = 5,
+    BJ_RESP_SURRENDER_ELSE_STAND = 6,
+    BJ_RESP_SURRENDER_ELSE_SPLIT = 7,
+};
+
+/* The single action to take, as returned by bj_strategy_action() */
+enum BjAction {
+    BJ_ACTION_HIT = 0,
+    BJ_ACTION_STAND = 1,
+    BJ_ACTION_DOUBLE = 2,
+    BJ_ACTION_SPLIT = 3,
+    BJ_ACTION_SURRENDER = 4,
+};
+
+/* Surrender rules for bj_strategy_action() */
+enum BjSurrender {
+    BJ_SURRENDER_NO = 0,
+    BJ_SURRENDER_YES = 1,
+    BJ_SURRENDER_NOT_ACE = 2,
+};
+
+typedef struct BjStrategy BjStrategy;
+typedef struct BjCount BjCount;
+
+/* Strategy cards. The from_* functions return NULL on error. */
+BjStrategy *bj_strategy_from_json(const char *json);
+BjStrategy *bj_strategy_from_card(const char *text);
+char *bj_strategy_to_json(const BjStrategy *bs);
+void bj_strategy_free(BjStrategy *bs);
+void bj_string_free(char *s);
+
+/* Returns an enum BjResp */
+int bj_strategy_lookup(const BjStrategy *bs, const BjCard *cards, size_t len, BjCard dealer);
+/* Returns an enum BjAction. surrender is an enum BjSurrender. */
+int bj_strategy_action(const BjStrategy *bs, const BjCard *cards, size_t len, BjCard dealer,
+                       int surrender);
+
+/* HiLo counting. bj_count_new() returns NULL if num_decks is 0. */
+BjCount *bj_count_new(uint8_t num_decks);
+int bj_count_update(BjCount *count, BjCard card);
+int16_t bj_count_running(const BjCount *count);
+float bj_count_true(const BjCount *count);
+void bj_count_free(BjCount *count);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* BJ_H */
@@ -0,0 +1,465 @@
+//! A C interface to the strategy engine, for front ends that can't use the wasm builds (e.g. iOS
+//! and Android apps).
+//!
+//! See include/bj.h for the C declarations. General rules:
+//!
+//! - Strategy cards and counters are opaque pointers created by a `*_new`/`*_from_*` function and
+//!   released by the matching `*_free` function.
+//! - Strings returned to the caller must be released with bj_string_free().
+//! - Functions that can fail return a negative number (or NULL) instead of panicking across the
+//!   FFI boundary.
+//!
+//! Cards are passed as a BjCard: rank 1 is an ace, 2-10 are pips, 11-13 are J, Q, K. Suit is 0-3
+//! for clubs, diamonds, hearts, and spades.
+use bj_core::basicstrategy::rules::{Allowed, SplitState, Surrender};
+use bj_core::basicstrategy::BasicStrategy;
+use bj_core::button::GameButton;
+use bj_core::correct_resp::expected_button;
+use bj_core::count::StatefulHiLo;
+use bj_core::deck::{Card, Rank, Suit};
+use bj_core::hand::Hand;
+use bj_core::resp::{resps_from_buf, Resp};
+use bj_core::table::Table;
+use std::ffi::{CStr, CString};
+use std::os::raw::{c_char, c_int};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::ptr;
+use std::slice;
+
+const ERR: c_int = -1;
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug)]
+pub struct BjCard {
+    pub rank: u8,
+    pub suit: u8,
+}
+
+/// The codes bj_strategy_lookup() returns
+#[repr(C)]
+#[derive(Debug, PartialEq)]
+pub enum BjResp {
+    Hit = 0,
+    Stand = 1,
+    DoubleElseHit = 2,
+    DoubleElseStand = 3,
+    Split = 4,
+    SurrenderElseHit = 5,
+    SurrenderElseStand = 6,
+    SurrenderElseSplit = 7,
+}
+
+impl From<Resp> for BjResp {
+    fn from(r: Resp) -> Self {
+        match r {
+            Resp::Hit => Self::Hit,
+            Resp::Stand => Self::Stand,
+            Resp::DoubleElseHit => Self::DoubleElseHit,
+            Resp::DoubleElseStand => Self::DoubleElseStand,
+            Resp::Split => Self::Split,
+            Resp::SurrenderElseHit => Self::SurrenderElseHit,
+            Resp::SurrenderElseStand => Self::SurrenderElseStand,
+            Resp::SurrenderElseSplit => Self::SurrenderElseSplit,
+        }
+    }
+}
+
+/// The codes bj_strategy_action() returns
+#[repr(C)]
+#[derive(Debug, PartialEq)]
+pub enum BjAction {
+    Hit = 0,
+    Stand = 1,
+    Double = 2,
+    Split = 3,
+    Surrender = 4,
+}
+
+pub struct BjStrategy(BasicStrategy);
+
+pub struct BjCount(StatefulHiLo);
+
+fn card_from_c(c: BjCard) -> Option<Card> {
+    let rank = match c.rank {
+        1 => Rank::RA,
+        2 => Rank::R2,
+        3 => Rank::R3,
+        4 => Rank::R4,
+        5 => Rank::R5,
+        6 => Rank::R6,
+        7 => Rank::R7,
+        8 => Rank::R8,
+        9 => Rank::R9,
+        10 => Rank::RT,
+        11 => Rank::RJ,
+        12 => Rank::RQ,
+        13 => Rank::RK,
+        _ => return None,
+    };
+    let suit = match c.suit {
+        0 => Suit::Club,
+        1 => Suit::Diamond,
+        2 => Suit::Heart,
+        3 => Suit::Spade,
+        _ => return None,
+    };
+    Some(Card::new(rank, suit))
+}
+
+fn surrender_from_c(s: c_int) -> Option<Surrender> {
+    match s {
+        0 => Some(Surrender::No),
+        1 => Some(Surrender::Yes),
+        2 => Some(Surrender::NotAce),
+        _ => None,
+    }
+}
+
+/// Build a Hand out of the caller's array of cards. None if any card is invalid or there are too
+/// few of them.
+unsafe fn hand_from_c(cards: *const BjCard, len: usize) -> Option<Hand> {
+    if cards.is_null() || len < 2 {
+        return None;
+    }
+    let cards = slice::from_raw_parts(cards, len)
+        .iter()
+        .map(|&c| card_from_c(c))
+        .collect::<Option<Vec<Card>>>()?;
+    Some(Hand::new(&cards))
+}
+
+unsafe fn str_from_c<'a>(s: *const c_char) -> Option<&'a str> {
+    if s.is_null() {
+        return None;
+    }
+    CStr::from_ptr(s).to_str().ok()
+}
+
+/// Parse a strategy card from JSON as written by attach-bs. Returns NULL on error.
+///
+/// # Safety
+///
+/// `json` must be NULL or a valid NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn bj_strategy_from_json(json: *const c_char) -> *mut BjStrategy {
+    match str_from_c(json).and_then(|s| serde_json::from_str(s).ok()) {
+        Some(bs) => Box::into_raw(Box::new(BjStrategy(bs))),
+        None => ptr::null_mut(),
+    }
+}
+
+/// Parse a strategy card from the plain text card format, with no rules attached. Returns NULL on
+/// error.
+///
+/// # Safety
+///
+/// `text` must be NULL or a valid NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn bj_strategy_from_card(text: *const c_char) -> *mut BjStrategy {
+    let table = str_from_c(text)
+        .and_then(|s| resps_from_buf(s.as_bytes()).ok())
+        .and_then(|resps| Table::new_checked(resps).ok());
+    match table {
+        Some(table) => Box::into_raw(Box::new(BjStrategy(BasicStrategy::new(None, table)))),
+        None => ptr::null_mut(),
+    }
+}
+
+/// Serialize the strategy card to JSON. Returns NULL on error. The string must be released with
+/// bj_string_free().
+///
+/// # Safety
+///
+/// `bs` must be NULL or a pointer returned by a bj_strategy_from_*() function that hasn't been
+/// freed.
+#[no_mangle]
+pub unsafe extern "C" fn bj_strategy_to_json(bs: *const BjStrategy) -> *mut c_char {
+    let bs = match bs.as_ref() {
+        Some(bs) => bs,
+        None => return ptr::null_mut(),
+    };
+    match serde_json::to_string(&bs.0)
+        .ok()
+        .and_then(|s| CString::new(s).ok())
+    {
+        Some(s) => s.into_raw(),
+        None => ptr::null_mut(),
+    }
+}
+
+/// # Safety
+///
+/// `bs` must be NULL or a pointer returned by a bj_strategy_from_*() function that hasn't been
+/// freed.
+#[no_mangle]
+pub unsafe extern "C" fn bj_strategy_free(bs: *mut BjStrategy) {
+    if !bs.is_null() {
+        drop(Box::from_raw(bs));
+    }
+}
+
+/// # Safety
+///
+/// `s` must be NULL or a string returned by this library that hasn't been freed.
+#[no_mangle]
+pub unsafe extern "C" fn bj_string_free(s: *mut c_char) {
+    if !s.is_null() {
+        drop(CString::from_raw(s));
+    }
+}
+
+/// The BjResp the card says to make with the given hand vs the dealer's up card, or -1 on error.
+///
+/// # Safety
+///
+/// `bs` must be NULL or a valid strategy. `cards` must be NULL or point to `len` BjCards.
+#[no_mangle]
+pub unsafe extern "C" fn bj_strategy_lookup(
+    bs: *const BjStrategy,
+    cards: *const BjCard,
+    len: usize,
+    dealer: BjCard,
+) -> c_int {
+    let (bs, hand, dealer) = match (bs.as_ref(), hand_from_c(cards, len), card_from_c(dealer)) {
+        (Some(bs), Some(hand), Some(dealer)) => (bs, hand, dealer),
+        _ => return ERR,
+    };
+    match bs.0.table.get(&hand, dealer) {
+        Ok(resp) => BjResp::from(resp) as c_int,
+        Err(_) => ERR,
+    }
+}
+
+/// Like bj_strategy_lookup(), but resolve the card's response into the one BjAction to actually
+/// take, given the hand, the card's rules, and the table's surrender rule (0 no, 1 yes, 2 not vs
+/// ace). E.g. Dh becomes Hit if the hand has too many cards to double, and a pair the rules don't
+/// let split is played as its plain total. Returns -1 on error.
+///
+/// # Safety
+///
+/// `bs` must be NULL or a valid strategy. `cards` must be NULL or point to `len` BjCards.
+#[no_mangle]
+pub unsafe extern "C" fn bj_strategy_action(
+    bs: *const BjStrategy,
+    cards: *const BjCard,
+    len: usize,
+    dealer: BjCard,
+    surrender: c_int,
+) -> c_int {
+    let (bs, hand, dealer, surrender) = match (
+        bs.as_ref(),
+        hand_from_c(cards, len),
+        card_from_c(dealer),
+        surrender_from_c(surrender),
+    ) {
+        (Some(bs), Some(hand), Some(dealer), Some(surrender)) => (bs, hand, dealer, surrender),
+        _ => return ERR,
+    };
+    let mut allowed = Allowed::for_hand(&hand, dealer, bs.0.rules.as_ref(), SplitState::default());
+    allowed.surrender = hand.can_surrender(surrender, dealer);
+    let resp = match bs.0.table.get(&hand, dealer) {
+        Ok(Resp::Split) if !allowed.split => bs.0.unsplit_resp(&hand, dealer),
+        Ok(Resp::SurrenderElseSplit) if !allowed.surrender && !allowed.split => {
+            bs.0.unsplit_resp(&hand, dealer)
+        }
+        resp => resp,
+    };
+    let resp = match resp {
+        Ok(resp) => resp,
+        Err(_) => return ERR,
+    };
+    let action = match expected_button(resp, allowed) {
+        GameButton::Hit => BjAction::Hit,
+        GameButton::Stand => BjAction::Stand,
+        GameButton::Double => BjAction::Double,
+        GameButton::Split => BjAction::Split,
+        GameButton::Surrender => BjAction::Surrender,
+    };
+    action as c_int
+}
+
+/// Start a HiLo count of a shoe with the given number of decks. Returns NULL if num_decks is 0.
+#[no_mangle]
+pub extern "C" fn bj_count_new(num_decks: u8) -> *mut BjCount {
+    if num_decks == 0 {
+        return ptr::null_mut();
+    }
+    Box::into_raw(Box::new(BjCount(StatefulHiLo::new(num_decks))))
+}
+
+/// Count the given card. Returns 0 on success and -1 on error, such as if more cards have been
+/// counted than are in the shoe.
+///
+/// # Safety
+///
+/// `count` must be NULL or a pointer returned by bj_count_new() that hasn't been freed.
+#[no_mangle]
+pub unsafe extern "C" fn bj_count_update(count: *mut BjCount, card: BjCard) -> c_int {
+    let (count, card) = match (count.as_mut(), card_from_c(card)) {
+        (Some(count), Some(card)) => (count, card),
+        _ => return ERR,
+    };
+    // StatefulHiLo asserts it isn't given more cards than are in the shoe
+    match catch_unwind(AssertUnwindSafe(|| count.0.update(card))) {
+        Ok(()) => 0,
+        Err(_) => ERR,
+    }
+}
+
+/// # Safety
+///
+/// `count` must be a pointer returned by bj_count_new() that hasn't been freed.
+#[no_mangle]
+pub unsafe extern "C" fn bj_count_running(count: *const BjCount) -> i16 {
+    count.as_ref().map(|c| c.0.running_count()).unwrap_or(0)
+}
+
+/// # Safety
+///
+/// `count` must be a pointer returned by bj_count_new() that hasn't been freed.
+#[no_mangle]
+pub unsafe extern "C" fn bj_count_true(count: *const BjCount) -> f32 {
+    count.as_ref().map(|c| c.0.true_count()).unwrap_or(0.0)
+}
+
+/// # Safety
+///
+/// `count` must be NULL or a pointer returned by bj_count_new() that hasn't been freed.
+#[no_mangle]
+pub unsafe extern "C" fn bj_count_free(count: *mut BjCount) {
+    if !count.is_null() {
+        drop(Box::from_raw(count));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bj_core::basicstrategy::rules::Rules;
+
+    const T1: &[u8] = include_bytes!("../../t1.txt");
+
+    fn strategy() -> *mut BjStrategy {
+        let text = CString::new(T1).unwrap();
+        let bs = unsafe { bj_strategy_from_card(text.as_ptr()) };
+        assert!(!bs.is_null());
+        bs
+    }
+
+    fn card(rank: u8) -> BjCard {
+        BjCard { rank, suit: 0 }
+    }
+
+    #[test]
+    fn lookup_and_action() {
+        let bs = strategy();
+        let eights = [card(8), card(8)];
+        let eleven = [card(5), card(6)];
+        let eleven_3 = [card(5), card(3), card(3)];
+        unsafe {
+            assert_eq!(
+                bj_strategy_lookup(bs, eights.as_ptr(), 2, card(10)),
+                BjResp::Split as c_int
+            );
+            assert_eq!(
+                bj_strategy_lookup(bs, eleven.as_ptr(), 2, card(6)),
+                BjResp::DoubleElseHit as c_int
+            );
+            assert_eq!(
+                bj_strategy_action(bs, eleven.as_ptr(), 2, card(6), 0),
+                BjAction::Double as c_int
+            );
+            // can't double with 3 cards
+            assert_eq!(
+                bj_strategy_action(bs, eleven_3.as_ptr(), 3, card(6), 0),
+                BjAction::Hit as c_int
+            );
+            bj_strategy_free(bs);
+        }
+    }
+
+    #[test]
+    fn action_only_splits_when_allowed() {
+        let bs = strategy();
+        let eights = [card(8), card(8)];
+        unsafe {
+            assert_eq!(
+                bj_strategy_action(bs, eights.as_ptr(), 2, card(10), 0),
+                BjAction::Split as c_int
+            );
+            // a card whose rules don't allow splitting at all plays 8,8 as hard 16
+            (*bs).0.rules = Some(Rules {
+                hit_soft_17: true.into(),
+                max_splits: 0.into(),
+                ..Default::default()
+            });
+            assert_eq!(
+                bj_strategy_action(bs, eights.as_ptr(), 2, card(10), 0),
+                BjAction::Hit as c_int
+            );
+            assert_eq!(
+                bj_strategy_action(bs, eights.as_ptr(), 2, card(6), 0),
+                BjAction::Stand as c_int
+            );
+            bj_strategy_free(bs);
+        }
+    }
+
+    #[test]
+    fn bad_input() {
+        let bs = strategy();
+        let hand = [card(8), card(8)];
+        unsafe {
+            assert_eq!(
+                bj_strategy_lookup(ptr::null(), hand.as_ptr(), 2, card(2)),
+                ERR
+            );
+            assert_eq!(bj_strategy_lookup(bs, ptr::null(), 2, card(2)), ERR);
+            assert_eq!(bj_strategy_lookup(bs, hand.as_ptr(), 1, card(2)), ERR);
+            assert_eq!(bj_strategy_lookup(bs, hand.as_ptr(), 2, card(14)), ERR);
+            assert_eq!(bj_strategy_action(bs, hand.as_ptr(), 2, card(2), 3), ERR);
+            assert!(bj_strategy_from_json(ptr::null()).is_null());
+            let bad = CString::new("not json").unwrap();
+            assert!(bj_strategy_from_json(bad.as_ptr()).is_null());
+            bj_strategy_free(bs);
+        }
+    }
+
+    #[test]
+    fn json_round_trip() {
+        let bs = strategy();
+        let hand = [card(1), card(7)];
+        unsafe {
+            let json = bj_strategy_to_json(bs);
+            assert!(!json.is_null());
+            let bs2 = bj_strategy_from_json(json);
+            assert!(!bs2.is_null());
+            for dealer in 1..=10 {
+                assert_eq!(
+                    bj_strategy_lookup(bs, hand.as_ptr(), 2, card(dealer)),
+                    bj_strategy_lookup(bs2, hand.as_ptr(), 2, card(dealer))
+                );
+            }
+            bj_string_free(json);
+            bj_strategy_free(bs);
+            bj_strategy_free(bs2);
+        }
+    }
+
+    #[test]
+    fn count() {
+        let c = bj_count_new(1);
+        unsafe {
+            for rank in 1..=13 {
+                for suit in 0..4 {
+                    assert_eq!(bj_count_update(c, BjCard { rank, suit }), 0);
+                }
+            }
+            assert_eq!(bj_count_running(c), 0);
+            // the shoe is empty
+            assert_eq!(bj_count_update(c, card(2)), ERR);
+            bj_count_free(c);
+        }
+        assert!(bj_count_new(0).is_null());
+    }
+}
@@ -0,0 +1,466 @@
+//! Python bindings for bj-core.
+//!
+//! Everything is exposed in a Python module named `bj`:
+//!
+//! ```python
+//! import bj
+//! bs = bj.BasicStrategy.default()
+//! hand = bj.Hand([bj.Card("8s"), bj.Card("8d")])
+//! bs.get(hand, bj.Card("Ah"))              # 'P'
+//! bj.is_correct("hit", "Dh", hand, bj.Card("6c"), "no")
+//! bj.strategy(bs.rules_json()).get(hand, bj.Card("Ah"))
+//! ```
+//!
+//! Cards are written as a rank (2-9, T, J, Q, K, A) followed by a suit (c, d, h, s). Responses are
+//! written the same way they are in strategy card files: H, S, Dh, Ds, P, Rh, Rs, Rp. Rules are
+//! written as JSON, the way they are in a card saved with attach-bs.
+
+// The code #[pymethods] generates for functions returning PyResult trips this
+#![allow(clippy::useless_conversion)]
+use bj_core::basicstrategy::rules::{Allowed, Rules, SplitState, Surrender};
+use bj_core::basicstrategy::BasicStrategy;
+use bj_core::button::GameButton;
+use bj_core::correct_resp::is_correct_resp_button;
+use bj_core::decisions::decision_path;
+use bj_core::deck::{Card, Rank, Suit};
+use bj_core::ev;
+use bj_core::hand::{Hand, HandType};
+use bj_core::resp::{resps_from_buf, Resp};
+use bj_core::table::Table;
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use std::collections::HashMap;
+
+/// A cell's label, the card's play, the best play, and how much EV the card's play gives up
+type PyMisplay = (String, &'static str, &'static str, f64);
+/// A cell's hand type, player, dealer, and the EV of each play allowed there
+type PyCellEvs = (&'static str, u8, u8, HashMap<String, f64>);
+
+fn err(e: impl std::fmt::Display) -> PyErr {
+    PyValueError::new_err(e.to_string())
+}
+
+fn parse_card(s: &str) -> PyResult<Card> {
+    let mut chars = s.trim().chars();
+    let rank = match chars.next().map(|c| c.to_ascii_uppercase()) {
+        Some('2') => Rank::R2,
+        Some('3') => Rank::R3,
+        Some('4') => Rank::R4,
+        Some('5') => Rank::R5,
+        Some('6') => Rank::R6,
+        Some('7') => Rank::R7,
+        Some('8') => Rank::R8,
+        Some('9') => Rank::R9,
+        Some('T') => Rank::RT,
+        Some('J') => Rank::RJ,
+        Some('Q') => Rank::RQ,
+        Some('K') => Rank::RK,
+        Some('A') => Rank::RA,
+        _ => return Err(err(format!("Bad rank in card '{}'", s))),
+    };
+    let suit = match chars.next().map(|c| c.to_ascii_lowercase()) {
+        Some('c') => Suit::Club,
+        Some('d') => Suit::Diamond,
+        Some('h') => Suit::Heart,
+        Some('s') => Suit::Spade,
+        _ => return Err(err(format!("Bad suit in card '{}'", s))),
+    };
+    if chars.next().is_some() {
+        return Err(err(format!("Trailing characters in card '{}'", s)));
+    }
+    Ok(Card::new(rank, suit))
+}
+
+fn card_code(card: Card) -> String {
+    let suit = match card.suit() {
+        Suit::Club => 'c',
+        Suit::Diamond => 'd',
+        Suit::Heart => 'h',
+        Suit::Spade => 's',
+    };
+    format!("{}{}", card.rank(), suit)
+}
+
+fn resp_code(resp: Resp) -> &'static str {
+    match resp {
+        Resp::Hit => "H",
+        Resp::Stand => "S",
+        Resp::DoubleElseHit => "Dh",
+        Resp::DoubleElseStand => "Ds",
+        Resp::Split => "P",
+        Resp::SurrenderElseHit => "Rh",
+        Resp::SurrenderElseStand => "Rs",
+        Resp::SurrenderElseSplit => "Rp",
+    }
+}
+
+fn parse_resp(s: &str) -> PyResult<Resp> {
+    Ok(match s {
+        "H" => Resp::Hit,
+        "S" => Resp::Stand,
+        "Dh" => Resp::DoubleElseHit,
+        "Ds" => Resp::DoubleElseStand,
+        "P" => Resp::Split,
+        "Rh" => Resp::SurrenderElseHit,
+        "Rs" => Resp::SurrenderElseStand,
+        "Rp" => Resp::SurrenderElseSplit,
+        _ => return Err(err(format!("Unknown response '{}'", s))),
+    })
+}
+
+fn parse_surrender(s: &str) -> PyResult<Surrender> {
+    Ok(match s {
+        "no" => Surrender::No,
+        "yes" => Surrender::Yes,
+        "notace" => Surrender::NotAce,
+        _ => return Err(err(format!("Unknown surrender rule '{}'", s))),
+    })
+}
+
+fn parse_rules(s: &str) -> PyResult<Rules> {
+    serde_json::from_str(s).map_err(err)
+}
+
+fn hand_type_name(ty: HandType) -> &'static str {
+    match ty {
+        HandType::Hard => "hard",
+        HandType::Soft => "soft",
+        HandType::Pair => "pair",
+    }
+}
+
+/// A single playing card, e.g. Card("Ah")
+#[pyclass(name = "Card", module = "bj", frozen)]
+#[derive(Clone, Copy)]
+struct PyCard(Card);
+
+#[pymethods]
+impl PyCard {
+    #[new]
+    fn new(s: &str) -> PyResult<Self> {
+        Ok(Self(parse_card(s)?))
+    }
+
+    /// Blackjack value of the card. Aces are 1.
+    #[getter]
+    fn value(&self) -> u8 {
+        self.0.value()
+    }
+
+    #[getter]
+    fn rank(&self) -> String {
+        self.0.rank().to_string()
+    }
+
+    fn __str__(&self) -> String {
+        card_code(self.0)
+    }
+
+    fn __repr__(&self) -> String {
+        format!("Card('{}')", card_code(self.0))
+    }
+
+    fn __eq__(&self, other: &Self) -> bool {
+        self.0 == other.0
+    }
+}
+
+/// A player's hand, e.g. Hand([Card("8s"), Card("8d")])
+#[pyclass(name = "Hand", module = "bj")]
+#[derive(Clone)]
+struct PyHand(Hand);
+
+#[pymethods]
+impl PyHand {
+    #[new]
+    fn new(cards: Vec<PyCard>) -> PyResult<Self> {
+        if cards.len() < 2 {
+            return Err(err("A hand needs at least 2 cards"));
+        }
+        Ok(Self(Hand::new(
+            &cards.into_iter().map(|c| c.0).collect::<Vec<Card>>(),
+        )))
+    }
+
+    fn push(&mut self, card: PyCard) {
+        self.0.push(card.0)
+    }
+
+    #[getter]
+    fn cards(&self) -> Vec<PyCard> {
+        self.0.cards().map(|&c| PyCard(c)).collect()
+    }
+
+    /// The best value of the hand: aces count as 11 if that doesn't bust it
+    #[getter]
+    fn value(&self) -> u8 {
+        self.0.value()
+    }
+
+    #[getter]
+    fn is_soft(&self) -> bool {
+        self.0.is_soft()
+    }
+
+    #[getter]
+    fn is_pair(&self) -> bool {
+        self.0.is_pair()
+    }
+
+    #[getter]
+    fn is_bust(&self) -> bool {
+        self.0.is_bust()
+    }
+
+    #[getter]
+    fn can_double(&self) -> bool {
+        self.0.can_double()
+    }
+
+    #[getter]
+    fn can_split(&self) -> bool {
+        self.0.can_split()
+    }
+
+    fn can_surrender(&self, surrender: &str, dealer: PyCard) -> PyResult<bool> {
+        Ok(self.0.can_surrender(parse_surrender(surrender)?, dealer.0))
+    }
+
+    /// Every action that may be taken on this (unsplit) hand, e.g. ["hit", "stand", "double"]
+    fn legal_actions(&self, surrender: &str, dealer: PyCard) -> PyResult<Vec<String>> {
+        let allowed = Allowed::unsplit(&self.0, dealer.0, parse_surrender(surrender)?);
+        Ok(allowed
+            .actions()
+            .iter()
+            .map(|a| a.to_string().to_lowercase())
+            .collect())
+    }
+
+    fn __str__(&self) -> String {
+        self.0.to_string()
+    }
+
+    fn __len__(&self) -> usize {
+        self.0.cards().count()
+    }
+}
+
+/// A basic strategy card: the best response for every cell, and optionally the rules it is for
+#[pyclass(name = "BasicStrategy", module = "bj")]
+#[derive(Clone)]
+struct PyBasicStrategy(BasicStrategy);
+
+#[pymethods]
+impl PyBasicStrategy {
+    /// The card the web trainers use by default, t1
+    #[staticmethod]
+    fn default() -> Self {
+        Self(bj_data::t1())
+    }
+
+    /// Parse a card saved with attach-bs
+    #[staticmethod]
+    fn from_json(s: &str) -> PyResult<Self> {
+        Ok(Self(serde_json::from_str(s).map_err(err)?))
+    }
+
+    /// Parse a strategy card text file with no rules attached
+    #[staticmethod]
+    fn from_card(s: &str) -> PyResult<Self> {
+        let table = Table::new_checked(resps_from_buf(s.as_bytes()).map_err(err)?).map_err(err)?;
+        Ok(Self(BasicStrategy::new(None, table)))
+    }
+
+    fn to_json(&self) -> PyResult<String> {
+        serde_json::to_string(&self.0).map_err(err)
+    }
+
+    /// The rules this card is for as JSON, or None if unknown
+    fn rules_json(&self) -> PyResult<Option<String>> {
+        self.0
+            .rules
+            .as_ref()
+            .map(|r| serde_json::to_string(r).map_err(err))
+            .transpose()
+    }
+
+    /// The rules this card is for as a dict of strings, or None if unknown
+    #[getter]
+    fn rules(&self) -> Option<HashMap<&'static str, String>> {
+        self.0.rules.as_ref().map(|r| {
+            [
+                ("decks", r.decks.to_string()),
+                ("hit_soft_17", r.hit_soft_17.to_string()),
+                ("double_after_split", r.double_after_split.to_string()),
+                ("max_splits", r.max_splits.to_string()),
+                ("resplit_aces", r.resplit_aces.to_string()),
+                ("hit_split_aces", r.hit_split_aces.to_string()),
+                ("blackjack_pays", r.blackjack_pays.to_string()),
+                ("peek_bj", r.peek_bj.to_string()),
+                ("surrender", r.surrender.to_string()),
+                ("no_peek_loss", r.no_peek_loss.to_string()),
+            ]
+            .iter()
+            .cloned()
+            .collect()
+        })
+    }
+
+    /// The best response to the given hand vs the dealer's up card
+    fn get(&self, hand: &PyHand, dealer: PyCard) -> PyResult<&'static str> {
+        Ok(resp_code(self.0.table.get(&hand.0, dealer.0).map_err(err)?))
+    }
+
+    /// The decisions the card makes for a hand dealt the given cards in order, as (cell label,
+    /// response, action) for the opening two cards and again after each card that didn't bust the
+    /// hand, e.g. [("Hard 12 vs 10", "H", "hit"), ("Hard 17 vs 10", "S", "stand")]. The action is
+    /// what the response means given what was allowed at that point.
+    fn decision_path(
+        &self,
+        cards: Vec<PyCard>,
+        dealer: PyCard,
+    ) -> PyResult<Vec<(String, &'static str, String)>> {
+        let cards: Vec<Card> = cards.into_iter().map(|c| c.0).collect();
+        Ok(
+            decision_path(&self.0, &cards, dealer.0, SplitState::default())
+                .map_err(err)?
+                .into_iter()
+                .map(|d| {
+                    (
+                        d.cell().label(),
+                        resp_code(d.resp),
+                        d.expected.to_string().to_lowercase(),
+                    )
+                })
+                .collect(),
+        )
+    }
+
+    /// Every cell as (hand type, player value, dealer value, response). Pair values are the sum of
+    /// the pair with aces being 22, and a dealer ace is 11.
+    fn cells(&self) -> Vec<(&'static str, u8, u8, &'static str)> {
+        self.0
+            .table
+            .iter()
+            .map(|(desc, resp)| {
+                (
+                    hand_type_name(desc.hand),
+                    desc.player,
+                    desc.dealer,
+                    resp_code(*resp),
+                )
+            })
+            .collect()
+    }
+
+    /// The cells where this card's play is worse than the best one under the given rules (or
+    /// the card's own if None), costliest first, e.g. ("Hard 16 vs 10", "S", "Rh", 0.04)
+    #[pyo3(signature = (rules=None))]
+    fn misplays(&self, rules: Option<&str>) -> PyResult<Vec<PyMisplay>> {
+        let rules = match rules {
+            Some(s) => parse_rules(s)?,
+            None => self
+                .0
+                .rules
+                .clone()
+                .ok_or_else(|| err("The card has no rules, so they must be given"))?,
+        };
+        Ok(ev::misplays(&self.0, &rules)
+            .into_iter()
+            .map(|m| (m.desc.label(), resp_code(m.card), resp_code(m.best), m.cost))
+            .collect())
+    }
+}
+
+/// A strategy card with the best play in every cell under the given rules
+#[pyfunction]
+fn strategy(rules: &str) -> PyResult<PyBasicStrategy> {
+    Ok(PyBasicStrategy(ev::strategy(&parse_rules(rules)?)))
+}
+
+/// The EV of every play in every cell under the given rules, in units of the initial bet, as
+/// (hand type, player, dealer, {action: EV}). Plays the rules don't allow are left out.
+#[pyfunction]
+fn evs(rules: &str) -> PyResult<Vec<PyCellEvs>> {
+    let buttons = [
+        GameButton::Hit,
+        GameButton::Stand,
+        GameButton::Double,
+        GameButton::Split,
+        GameButton::Surrender,
+    ];
+    Ok(ev::evs(&parse_rules(rules)?)
+        .iter()
+        .map(|(desc, evs)| {
+            let evs = buttons
+                .iter()
+                .filter_map(|b| evs.ev(*b).map(|ev| (b.to_string().to_lowercase(), ev)))
+                .collect();
+            (hand_type_name(desc.hand), desc.player, desc.dealer, evs)
+        })
+        .collect())
+}
+
+/// Whether taking `action` (hit, stand, double, split, or surrender) is correct when the card
+/// says `correct`. This is more than a simple comparison: if the card says Dh but the hand can't
+/// double, hitting is correct.
+#[pyfunction]
+fn is_correct(
+    action: &str,
+    correct: &str,
+    hand: &PyHand,
+    dealer: PyCard,
+    surrender: &str,
+) -> PyResult<bool> {
+    let btn = match action {
+        "hit" => GameButton::Hit,
+        "stand" => GameButton::Stand,
+        "double" => GameButton::Double,
+        "split" => GameButton::Split,
+        "surrender" => GameButton::Surrender,
+        _ => return Err(err(format!("Unknown action '{}'", action))),
+    };
+    Ok(is_correct_resp_button(
+        btn,
+        parse_resp(correct)?,
+        (&hand.0, dealer.0),
+        parse_surrender(surrender)?,
+    ))
+}
+
+#[pymodule]
+fn bj(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PyCard>()?;
+    m.add_class::<PyHand>()?;
+    m.add_class::<PyBasicStrategy>()?;
+    m.add_function(wrap_pyfunction!(is_correct, m)?)?;
+    m.add_function(wrap_pyfunction!(strategy, m)?)?;
+    m.add_function(wrap_pyfunction!(evs, m)?)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_args() {
+        let ace = parse_card("Ah").unwrap();
+        assert_eq!((ace.rank(), ace.suit()), (Rank::RA, Suit::Heart));
+        assert_eq!(parse_card(" tc ").unwrap(), Card::new(Rank::RT, Suit::Club));
+        assert_eq!(card_code(parse_card("Qs").unwrap()), "Qs");
+        for bad in &["", "1h", "Ax", "A", "Ahh"] {
+            assert!(parse_card(bad).is_err(), "{}", bad);
+        }
+        for code in &["H", "S", "Dh", "Ds", "P", "Rh", "Rs", "Rp"] {
+            assert_eq!(resp_code(parse_resp(code).unwrap()), *code);
+        }
+        assert!(parse_resp("h").is_err());
+        assert_eq!(parse_surrender("notace").unwrap(), Surrender::NotAce);
+        assert!(parse_surrender("late").is_err());
+        let rules = bj_data::t1().rules.unwrap();
+        let json = serde_json::to_string(&rules).unwrap();
+        assert_eq!(parse_rules(&json).unwrap(), rules);
+        assert!(parse_rules("{\"decks\": 7}").is_err());
+    }
+}
@@ -1,60 +1,118 @@
-use bj_core::basicstrategy::{rules, BasicStrategy};
-use bj_core::deck::{Card, Rank};
-use bj_core::hand::Hand;
+use bj_core::breakdown;
+use bj_core::button::GameButton;
+use bj_core::correct_resp::Mistake;
+use bj_core::deck::Card;
+use bj_core::demo::{Demo, DemoStep, MAX_PACE_MS, MIN_PACE_MS};
+use bj_core::hand::{Hand, HandType};
+use bj_core::mastery::mastery_progress as table_mastery_progress;
+use bj_core::memorize::Memorize;
 use bj_core::playstats::PlayStats;
-use bj_core::rendertable::{HTMLTableRenderer, HTMLTableRendererOpts};
-use bj_core::resp::Resp;
+use bj_core::proto::{Body, StatsUpload, Upload};
+use bj_core::rendertable::{
+    onclick_cell_desc, HTMLTableRendererOpts, SVGTableRenderer, TextTableRenderer,
+};
 use bj_core::table::Table;
-use bj_core::utils::{playstats_table, rand_next_hand, uniform_rand_2card_hand};
+use bj_core::trainer::{next_drill_upcard, CoachEvent, Graded, Mode, Trainer, TARGET_HANDS};
+use bj_core::utils::playstats_table;
+use bj_core::weakcells;
+use bj_web_core::anonid;
 use bj_web_core::bs_data;
-use bj_web_core::button::GameButton;
 use bj_web_core::card_char;
-use bj_web_core::correct_resp::is_correct_resp_button;
-use bj_web_core::localstorage::{lskeys, LSVal};
+use bj_web_core::crash;
+use bj_web_core::deal::output_hand;
+use bj_web_core::events::{emit, emit_all, graded_events, GameEvent};
+use bj_web_core::feedback;
+use bj_web_core::init::{BasicStrategyOptions, DealSource, TrainerOptions};
+use bj_web_core::kiosk::{self, KioskStep};
+use bj_web_core::localstorage::{ls_get, ls_set, lskeys};
+use bj_web_core::logging;
+use bj_web_core::packs;
+use bj_web_core::practice;
+use bj_web_core::prefs::{HitStandPrefs, Prefs};
+use bj_web_core::tabs::{self, Claim};
+use bj_web_core::view::{self, output_stats, set_shown, set_text, update_game_buttons, StatsView};
 use std::default::Default;
 use std::sync::Mutex;
 use wasm_bindgen::prelude::*;
-use wasm_bindgen::JsCast;
-use web_sys::{Element, HtmlElement};
 #[macro_use]
 extern crate lazy_static;
 
 const UPLOAD_STATS_EVERY: u16 = 10;
 
-#[derive(Clone, Copy, Debug)]
-enum RandHandType {
-    // each card is drawn from the top of an shuffled inifinite deck
-    Card,
-    // a random cell is chosen from a basic strategy table, and a random hand constructed to fit
-    // that cell
-    Cell,
-}
-
-impl Default for RandHandType {
-    fn default() -> Self {
-        Self::Card
-    }
-}
-
 #[derive(Debug)]
 struct State {
     use_session_storage: bool,
-    rand_hand_type: RandHandType,
     upload_stats_every: u16,
     next_upload_stats: u16,
-    play_stats: Table<PlayStats>,
+    /// The hand on the screen, the card to grade against, and the player's stats. The trainer's
+    /// hand is the authoritative copy; localStorage only has a copy so it survives a page reload,
+    /// and it is written when the hand changes. The card is read from localStorage once in
+    /// rust_init() and never written back, as this page can't change it. The player's notes are
+    /// read in rust_init() and written back whenever they change.
+    trainer: Trainer,
+    /// The trainer as it was just before the most recent answer, so a fat-fingered answer can be
+    /// taken back with undo_last_answer(). Only one answer can be undone, and only until anything
+    /// else changes the stats.
+    undo: Option<Snapshot>,
+    /// The cells revealed in memorization mode. Kept with the stats, as revealing a cell changes
+    /// them.
+    memorize: Memorize,
+    /// The demo playing itself in place of the player's hand, if one is running. The player's
+    /// buttons do nothing while it runs.
+    demo: Option<Demo>,
+    /// This tab's ID, to claim the tab lock with before grading answers
+    tab: u64,
+    /// Whether the page has to reload the player's stats and streak with statistics_into_state()
+    /// before answers are graded or stats uploaded again, because another tab may have saved
+    /// newer ones
+    reload: tabs::Reload,
+    /// The options the trainer was started with, to start it over with when a kiosk resets
+    opts: BasicStrategyOptions,
+}
+
+#[derive(Debug)]
+struct Snapshot {
+    hand: (Hand, Card),
+    stats: Table<PlayStats>,
     streak: u32,
+    shields: u32,
+}
+
+impl Snapshot {
+    fn take(trainer: &Trainer) -> Self {
+        let (player, dealer) = trainer.hand();
+        Self {
+            hand: (player.clone(), dealer),
+            stats: trainer.stats().clone(),
+            streak: trainer.streak(),
+            shields: trainer.shields(),
+        }
+    }
+
+    fn restore(self, trainer: &mut Trainer) {
+        trainer.set_hand(self.hand.0, self.hand.1);
+        trainer.set_stats(self.stats, self.streak);
+        trainer.set_shields(self.shields);
+    }
 }
 
 impl Default for State {
     fn default() -> Self {
         Self {
             use_session_storage: true,
-            rand_hand_type: Default::default(),
             upload_stats_every: UPLOAD_STATS_EVERY,
             next_upload_stats: UPLOAD_STATS_EVERY,
-            play_stats: new_play_stats(),
-            streak: 0,
+            trainer: {
+                let mut trainer = Trainer::new(bs_data::def_bs_card().clone(), Default::default());
+                trainer.set_clock(Some(bj_web_core::now));
+                trainer
+            },
+            undo: None,
+            memorize: Memorize::new(),
+            demo: None,
+            tab: tabs::new_tab_id(),
+            reload: Default::default(),
+            opts: Default::default(),
         }
     }
 }
@@ -63,273 +121,354 @@ lazy_static! {
     static ref STATE: Mutex<State> = Mutex::new(Default::default());
 }
 
-fn new_play_stats() -> Table<PlayStats> {
-    Table::new(std::iter::repeat(PlayStats::new()).take(360)).unwrap()
+#[wasm_bindgen(start)]
+pub fn run() -> Result<(), JsValue> {
+    crash::set_panic_hook("basic-strategy");
+    logging::init();
+    Ok(())
 }
 
-fn def_bs_card() -> BasicStrategy {
-    serde_json::from_reader(bs_data::T1_JSON).unwrap()
+fn set_state(new_state: State) {
+    log::trace!("Setting state {:?}", new_state);
+    let mut old_state = STATE.lock().unwrap();
+    *old_state = new_state;
 }
 
-#[wasm_bindgen]
-extern "C" {
-    #[wasm_bindgen(js_namespace = console)]
-    fn log(s: &str);
+/// How many misses in a row of the card start a coach mode drill
+const COACH_MISSES: u32 = 3;
 
-    fn flash_hint_message(s: &str);
+fn coach_from_prefs(prefs: &Prefs) -> Option<u32> {
+    if prefs.coach {
+        Some(COACH_MISSES)
+    } else {
+        None
+    }
 }
 
-fn debug_log(s: &str) {
-    let _ = s; // "use" param to silence warning when compiling in non-debug mode
-    #[cfg(debug_assertions)]
-    log(s)
+fn mode_from_prefs(prefs: &Prefs) -> Mode {
+    let hs = &prefs.hit_stand;
+    if hs.enabled {
+        Mode::HitStand {
+            pairs: hs.pairs,
+            softs: hs.softs,
+        }
+    } else {
+        Mode::Full
+    }
 }
 
-#[wasm_bindgen(start)]
-pub fn run() -> Result<(), JsValue> {
-    console_error_panic_hook::set_once();
-    Ok(())
+/// Start the trainer. rand_hand_type is 0 to deal hands uniformly at random and 1 to weight them by
+/// the player's stats. If drill_upcard is true, every hand this session is against the same dealer
+/// upcard, and each session drills the upcard after the previous session's.
+///
+/// Kept for pages that haven't moved to rust_init_with_options() yet.
+#[wasm_bindgen]
+pub fn rust_init(rand_hand_type: u8, drill_upcard: bool) {
+    let source = match rand_hand_type {
+        0 => DealSource::Uniform,
+        1 => DealSource::Weighted,
+        // purposefully vague
+        _ => {
+            log::warn!("Invalid option specified");
+            DealSource::Uniform
+        }
+    };
+    init(BasicStrategyOptions {
+        source,
+        drill_upcard,
+        kiosk: None,
+    });
 }
 
-fn set_state(new_state: State) {
-    //debug_log(&format!("Setting state {:?}", new_state));
-    let mut old_state = STATE.lock().unwrap();
-    *old_state = new_state;
+/// Start the trainer with the given options as JSON (see BasicStrategyOptions). Returns why the
+/// options are bad, or an empty string if the trainer started.
+#[wasm_bindgen]
+pub fn rust_init_with_options(options: String) -> String {
+    match BasicStrategyOptions::from_json(&options) {
+        Ok(opts) => {
+            init(opts);
+            String::new()
+        }
+        Err(e) => e.to_string(),
+    }
 }
 
-#[wasm_bindgen]
-pub fn rust_init(rand_hand_type: u8) {
-    set_state(State {
-        rand_hand_type: match rand_hand_type {
-            0 => RandHandType::Card,
-            1 => RandHandType::Cell,
-            // purposefully vague
-            _ => panic!("Invalid option specified"),
-        },
+fn init(opts: BasicStrategyOptions) {
+    if let Some(kiosk) = &opts.kiosk {
+        kiosk::start(kiosk);
+    }
+    set_up(opts);
+}
+
+/// Build the trainer's state from storage and the given options, and show it
+fn set_up(opts: BasicStrategyOptions) {
+    let prefs = Prefs::load();
+    let mut state = State {
+        // nothing is stored in a kiosk, and there's no other tab to share with
+        use_session_storage: prefs.storage.is_session() || kiosk::enabled(),
+        opts: opts.clone(),
         ..Default::default()
-    });
-    let state = STATE.lock().unwrap();
-    let (player_hand, dealer_card) = &*LSVal::from_ls_or_default(
-        state.use_session_storage,
-        lskeys::LS_KEY_EXISTING_HAND,
-        rand_next_hand(&state.play_stats),
-    );
-    output_hand(player_hand, *dealer_card);
+    };
+    state.trainer.set_source(opts.source.into());
+    if let Some((player, dealer)) = ls_get(state.use_session_storage, lskeys::LS_KEY_EXISTING_HAND)
     {
-        let bs_card = LSVal::from_ls_or_default(
-            state.use_session_storage,
-            lskeys::LS_KEY_BS_CARD,
-            def_bs_card(),
-        );
-        update_buttons((player_hand, *dealer_card), &bs_card.rules);
+        state.trainer.set_hand(player, dealer);
+    }
+    // after restoring the hand, so a saved hand the mode doesn't deal gets replaced
+    apply_prefs(&mut state.trainer, &prefs);
+    if opts.drill_upcard {
+        // The drill moves on across sessions, so it is always kept in localStorage
+        let upcard = next_drill_upcard(ls_get(false, lskeys::LS_KEY_DRILL_UPCARD));
+        ls_set(false, lskeys::LS_KEY_DRILL_UPCARD, &upcard);
+        state.trainer.set_upcard(Some(upcard)).unwrap();
+    }
+    save_hand(&state);
+    if let Some(bs_card) = prefs.bs_card.as_deref().and_then(bs_data::bs_card) {
+        state.trainer.set_strategy(bs_card.clone());
+    } else if let Some(bs_card) = ls_get(state.use_session_storage, lskeys::LS_KEY_BS_CARD) {
+        state.trainer.set_strategy(bs_card);
+    }
+    if let Some(notes) = ls_get(state.use_session_storage, lskeys::LS_KEY_NOTES) {
+        state.trainer.set_notes(notes);
+    }
+    if let Some(memorize) = ls_get(state.use_session_storage, lskeys::LS_KEY_MEMORIZE) {
+        state.memorize = memorize;
     }
-    output_stats((player_hand, *dealer_card), &state.play_stats, state.streak);
-    output_resp_table(&*state);
+    output_trainer(&state.trainer);
+    output_resp_table(&state);
+    if !state.use_session_storage {
+        tabs::listen_for_storage_changes(on_storage_change);
+    }
+    practice::shown();
+    set_state(state);
 }
 
-fn output_resp_table(state: &State) {
-    let bs_card = LSVal::from_ls_or_default(
+/// Set up the trainer the way the player's prefs say
+fn apply_prefs(trainer: &mut Trainer, prefs: &Prefs) {
+    trainer.set_mode(mode_from_prefs(prefs));
+    trainer.set_shields_enabled(prefs.streak_shields);
+    trainer.set_coach(coach_from_prefs(prefs));
+    trainer.set_even_money(prefs.even_money);
+    trainer.set_frequency_weighting(prefs.frequency_weighting);
+    trainer.set_refresh_after(prefs.refresh_days);
+    trainer.set_play_splits(prefs.play_splits);
+    trainer.set_multi_card(prefs.multi_card);
+}
+
+/// Another tab changed something in localStorage: pick up its prefs and notes, so this tab
+/// doesn't write old ones back over them. The page keeps the stats, so if the other tab saved
+/// those, ask the page to reload them and hold off grading until it has.
+fn on_storage_change(key: &str) {
+    let mut state = STATE.lock().unwrap();
+    if key == namespace(&state.trainer) {
+        state.reload.changed();
+        emit(GameEvent::StatsChanged);
+        return;
+    }
+    match key {
+        lskeys::LS_KEY_PREFS => {
+            apply_prefs(&mut state.trainer, &Prefs::load());
+            save_hand(&state);
+        }
+        lskeys::LS_KEY_NOTES => {
+            if let Some(notes) = ls_get(state.use_session_storage, lskeys::LS_KEY_NOTES) {
+                state.trainer.set_notes(notes);
+            }
+        }
+        lskeys::LS_KEY_MEMORIZE => {
+            if let Some(memorize) = ls_get(state.use_session_storage, lskeys::LS_KEY_MEMORIZE) {
+                state.memorize = memorize;
+            }
+        }
+        _ => return,
+    }
+    output_trainer(&state.trainer);
+    output_resp_table(&state);
+}
+
+/// Claim the tab lock before grading an answer. Returns false, telling the player why, if the
+/// answer shouldn't be graded: because another tab is using the trainer, or because this tab took
+/// over from one and the page hasn't reloaded its stats yet.
+fn claim_tab(state: &mut State) -> bool {
+    if state.use_session_storage {
+        return true;
+    }
+    let claim = tabs::claim_tab_lock(state.tab);
+    state.reload.claimed(claim);
+    match claim {
+        Claim::Held | Claim::TakenOver if !state.reload.is_pending() => true,
+        Claim::Held | Claim::TakenOver => {
+            feedback::hint(
+                "Picking up where your other tab left off. Answer again once your stats have \
+                loaded.",
+            );
+            emit(GameEvent::TabTakenOver);
+            false
+        }
+        Claim::Refused(_) => {
+            feedback::hint(
+                "The trainer is open in another tab. Answers here aren't graded until that tab \
+                has sat idle for a bit.",
+            );
+            emit(GameEvent::OtherTab);
+            false
+        }
+    }
+}
+
+fn save_hand(state: &State) {
+    let (player, dealer) = state.trainer.hand();
+    ls_set(
         state.use_session_storage,
-        lskeys::LS_KEY_BS_CARD,
-        def_bs_card(),
+        lskeys::LS_KEY_EXISTING_HAND,
+        &(player, dealer),
     );
-    let mut fd: Vec<u8> = vec![];
+}
+
+/// Show the trainer's current hand, the player's note on it, the buttons that are legal for it,
+/// and the player's stats
+fn output_trainer(trainer: &Trainer) {
+    let (player, dealer) = trainer.hand();
+    output_hand(player, dealer);
+    output_note(trainer.note());
+    update_game_buttons(trainer);
+    output_stats(StatsView {
+        hand: (player, dealer),
+        stats: trainer.stats(),
+        streak: trainer.streak(),
+    });
+}
+
+fn output_note(note: Option<&str>) {
+    set_text("hand_note", note.unwrap_or_default());
+    set_shown("hand_note", note.is_some());
+}
+
+/// Show the card, with the cells the player has mastered hidden in memorization mode. Clicking a
+/// cell calls onclick_cell().
+fn output_resp_table(state: &State) {
+    let prefs = Prefs::load();
+    let visibility = if prefs.memorize {
+        Some(
+            state
+                .memorize
+                .visibility(state.trainer.stats(), &prefs.mastery),
+        )
+    } else {
+        None
+    };
     let opts = HTMLTableRendererOpts {
         incl_bs_rules: true,
-        cell_onclick_cb: None,
+        cell_onclick_cb: Some("onclick_cell".to_string()),
+        notes: Some(state.trainer.notes().clone()),
+        visibility,
     };
-    HTMLTableRenderer::render(&mut fd, &*bs_card, opts).unwrap();
-    let win = web_sys::window().expect("should have a window in this context");
-    let doc = win.document().expect("window should have a document");
-    doc.get_element_by_id("strat_html")
-        .expect("should exist strat_html")
-        .dyn_ref::<HtmlElement>()
-        .expect("strat_html should be HtmlElement")
-        .set_inner_html(&String::from_utf8(fd).unwrap());
-}
-
-fn output_hand(player: &Hand, dealer: Card) {
-    let win = web_sys::window().expect("should have a window in this context");
-    let doc = win.document().expect("window should have a document");
-    doc.get_element_by_id("player_cards")
-        .expect("should exist player_cards")
-        .dyn_ref::<HtmlElement>()
-        .expect("player_cards should be HtmlElement")
-        .set_inner_text(&player.cards().map(|&c| card_char(c)).collect::<String>());
-    doc.get_element_by_id("dealer_cards")
-        .expect("should exist dealer_cards")
-        .dyn_ref::<HtmlElement>()
-        .expect("dealer_cards should be HtmlElement")
-        .set_inner_text(&format!("{}", card_char(dealer)));
-}
-
-fn output_stats(current_hand: (&Hand, Card), stat_table: &Table<PlayStats>, streak: u32) {
-    let (correct, seen) = stat_table
-        .values()
-        .fold((0, 0), |(acc_correct, acc_seen), stat| {
-            (acc_correct + stat.correct(), acc_seen + stat.seen())
-        });
-    set_stat(Stat::Correct, correct);
-    set_stat(Stat::Seen, seen);
-    set_stat(Stat::Streak, streak);
-    let stat = stat_table.get(current_hand.0, current_hand.1).unwrap();
-    set_stat(Stat::HandCorrect, stat.correct());
-    set_stat(Stat::HandSeen, stat.seen());
-
-    enum Stat {
-        Correct,
-        Seen,
-        HandCorrect,
-        HandSeen,
-        Streak,
-    }
-
-    fn set_stat(stat: Stat, val: u32) {
-        let win = web_sys::window().expect("should have a window in this context");
-        let doc = win.document().expect("window should have a document");
-        let id = match stat {
-            Stat::Correct => "num_correct",
-            Stat::Seen => "num_seen",
-            Stat::HandCorrect => "hand_num_correct",
-            Stat::HandSeen => "hand_num_seen",
-            Stat::Streak => "num_streak",
-        };
-        doc.get_element_by_id(id)
-            .expect("should exist stat")
-            .dyn_ref::<HtmlElement>()
-            .expect("stat should be HtmlElement")
-            .set_inner_text(&val.to_string())
-    }
-}
-
-fn update_stats(
-    play_stats: &mut Table<PlayStats>,
-    streak: &mut u32,
-    old_hand: (&Hand, Card),
-    old_was_correct: bool,
-) {
-    let mut old_stat = play_stats.get(&old_hand.0, old_hand.1).unwrap();
-    old_stat.inc(old_was_correct);
-    play_stats
-        .update(&old_hand.0, old_hand.1, old_stat)
-        .unwrap();
-    *streak = if old_was_correct { *streak + 1 } else { 0 };
-}
-
-fn update_buttons(hand: (&Hand, Card), rules: &Option<rules::Rules>) {
-    let win = web_sys::window().expect("should have a window in this context");
-    let doc = win.document().expect("window should have a document");
-    let hit_enabled = true;
-    let stand_enabled = true;
-    let double_enabled = hand.0.can_double();
-    let split_enabled = hand.0.can_split();
-    let surrender_enabled = if let Some(rules) = rules {
-        match rules.surrender {
-            rules::Surrender::No => false,
-            rules::Surrender::Yes => true,
-            rules::Surrender::NotAce => hand.1.rank() != Rank::RA,
-        }
+    view::output_resp_table("strat_html", state.trainer.strategy(), opts);
+}
+
+fn set_hint(graded: &Graded) {
+    let s = if graded.is_correct {
+        format!("{} correct.", graded.action)
+    } else if graded.shielded {
+        format!(
+            "{} wrong. Should {} on {}. A streak shield saved your streak of {}.",
+            graded.action,
+            graded.correct,
+            graded.cell().label(),
+            graded.old_streak
+        )
     } else {
-        true
-    };
-    for (id, enabled) in [
-        ("button_hit", hit_enabled),
-        ("button_stand", stand_enabled),
-        ("button_double", double_enabled),
-        ("button_split", split_enabled),
-        ("button_surrender", surrender_enabled),
-    ]
-    .iter()
-    {
-        let class_list = doc
-            .get_element_by_id(id)
-            .expect("should exist button")
-            .dyn_ref::<Element>()
-            .expect("button should be Element")
-            .class_list();
-        if *enabled {
-            class_list
-                .remove_1("hide")
-                .expect("Unable to add hide class");
-        } else {
-            class_list
-                .add_1("hide")
-                .expect("Unable to remove hide class");
+        let s = format!(
+            "{} wrong. Should {} on {}. Streak was {}.",
+            graded.action,
+            graded.correct,
+            graded.cell().label(),
+            graded.old_streak
+        );
+        match &graded.note {
+            Some(note) => format!("{} Your note: {}", s, note),
+            None => s,
         }
-    }
+    };
+    let s = match graded.fallback_detail() {
+        Some(detail) => format!("{} {}", s, detail),
+        None => s,
+    };
+    let s = match &graded.coach {
+        Some(CoachEvent::DrillStarted(drill)) => format!(
+            "{} Next {} hands are {} to practice it.",
+            s,
+            drill.remaining,
+            drill.label()
+        ),
+        Some(CoachEvent::DrillEnded) => format!("{} Drill over.", s),
+        None => s,
+    };
+    feedback::hint(&target_hint(s, graded.target_ended));
 }
 
-fn set_hint(given: GameButton, correct: Resp, hand: (&Hand, Card), is_correct: bool, streak: u32) {
-    let s = if is_correct {
-        format!("{} correct.", given)
+/// The hint, saying target practice is over if it just ended
+fn target_hint(s: String, target_ended: bool) -> String {
+    if target_ended {
+        format!("{} Target practice over.", s)
     } else {
-        format!(
-            "{} wrong. Should {} {} vs {}. Streak was {}.",
-            given, correct, hand.0, hand.1, streak
-        )
+        s
+    }
+}
+
+/// Tell the player how they did once they finish a run of the drill pack
+fn pack_hint(trainer: &Trainer) {
+    let progress = match trainer.pack_progress() {
+        Some(progress) => progress,
+        None => return,
     };
-    flash_hint_message(&s);
+    let s = match progress.passed() {
+        Some(true) => "Pack passed!",
+        Some(false) => "Pack not passed. Try another run.",
+        None => return,
+    };
+    feedback::hint(&format!(
+        "{} {}/{} correct.",
+        s, progress.correct, progress.played
+    ));
 }
 
 fn handle_button(state: &mut State, btn: GameButton) {
-    // the (player_hand, dealer_card) currently on the screen
-    let mut hand: LSVal<(Hand, Card)> =
-        LSVal::from_ls(state.use_session_storage, lskeys::LS_KEY_EXISTING_HAND).unwrap();
-    let bs_card = LSVal::from_ls_or_default(
-        state.use_session_storage,
-        lskeys::LS_KEY_BS_CARD,
-        def_bs_card(),
-    );
-    let surrender_rule = match &bs_card.rules {
-        None => rules::Surrender::Yes,
-        Some(rules) => rules.surrender,
-    };
-    // return early if user didn't even give legal response to this hand
-    if !is_legal_resp(btn, (&hand.0, hand.1), surrender_rule) {
-        log(&format!(
-            "{} is not a legal response to {}/{}",
-            btn, &hand.0, hand.1
-        ));
+    crash::record(&format!("pressed {}", btn));
+    if state.demo.is_some() || !claim_tab(state) {
         return;
     }
-    // the correct response to this (player_hand, dealer_card). We store the bool is_correct as
-    // well because whether or not the response is correct is more complex than button == resp: if
-    // the correct Resp is DoubleElseHit (or its cousins) then it is not enough to simply check if
-    // the Double button was pressed.
-    let correct: Resp = bs_card.table.get(&hand.0, hand.1).unwrap();
-    let is_correct = is_correct_resp_button(btn, correct, (&hand.0, hand.1), surrender_rule);
-    // grab a copy of what the user's existing streak is. If they get the hand wrong, we will want
-    // to display this to them and we will soon be clearing out the localstorage copy of their
-    // streak
-    let old_streak = state.streak;
-    // update state for player statistics
-    update_stats(
-        &mut state.play_stats,
-        &mut state.streak,
-        (&hand.0, hand.1),
-        is_correct,
-    );
+    let snapshot = Snapshot::take(&state.trainer);
+    let graded = match state.trainer.act(btn) {
+        Ok(graded) => graded,
+        // user didn't even give legal response to this hand
+        Err(e) => {
+            log::warn!("{}", e);
+            return;
+        }
+    };
+    state.undo = Some(snapshot);
+    practice::answered();
     // display the "hint": player got it right, or they got it wrong and ___ is correct and ___ was
     // their streak
-    set_hint(btn, correct, (&hand.0, hand.1), is_correct, old_streak);
-    let _ = hand.swap(match state.rand_hand_type {
-        RandHandType::Card => uniform_rand_2card_hand(),
-        RandHandType::Cell => rand_next_hand(&state.play_stats),
-    });
-    output_hand(&hand.0, hand.1);
-    update_buttons((&hand.0, hand.1), &bs_card.rules);
-    // update_stats() will have either incremented their streak or reset it to zero, so we need to
-    // refetch their streak from state
-    output_stats((&hand.0, hand.1), &state.play_stats, state.streak);
-}
-
-fn is_legal_resp(btn: GameButton, hand: (&Hand, Card), surrender_rule: rules::Surrender) -> bool {
-    let (player, dealer) = hand;
-    match btn {
-        GameButton::Hit | GameButton::Stand => true,
-        GameButton::Double => player.can_double(),
-        GameButton::Split => player.can_split(),
-        GameButton::Surrender => player.can_surrender(surrender_rule, dealer),
+    set_hint(&graded);
+    pack_hint(&state.trainer);
+    emit_all(&graded_events(graded.is_correct, state.trainer.streak()));
+    match graded.coach {
+        Some(CoachEvent::DrillStarted(_)) => emit(GameEvent::DrillStarted),
+        Some(CoachEvent::DrillEnded) => emit(GameEvent::DrillEnded),
+        None => {}
+    }
+    save_hand(state);
+    output_trainer(&state.trainer);
+    if Prefs::load().memorize {
+        state.memorize.prune(state.trainer.stats());
+        ls_set(
+            state.use_session_storage,
+            lskeys::LS_KEY_MEMORIZE,
+            &state.memorize,
+        );
+        output_resp_table(state);
     }
 }
 
@@ -381,28 +520,713 @@ pub fn on_button_surrender() {
 #[wasm_bindgen]
 pub fn on_button_clear_stats() {
     let mut state = STATE.lock().unwrap();
-    for v in state.play_stats.values_mut() {
-        *v = PlayStats::new();
-    }
-    state.streak = 0;
-    let (player, dealer) =
-        &*LSVal::from_ls(state.use_session_storage, lskeys::LS_KEY_EXISTING_HAND).unwrap();
-    output_stats((&player, *dealer), &state.play_stats, state.streak);
+    state.trainer.clear_stats();
+    state.undo = None;
+    output_trainer(&state.trainer);
     if state.next_upload_stats > 0 {
         state.next_upload_stats -= 1;
     }
 }
 
+/// Turn beginner mode on or off. In it only hit and stand are allowed, and pairs and soft hands are
+/// only dealt if asked for. If the hand on the screen isn't one that the new mode deals, it is
+/// replaced.
+#[wasm_bindgen]
+pub fn set_hit_stand_mode(enabled: bool, pairs: bool, softs: bool) {
+    let mut prefs = Prefs::load();
+    prefs.hit_stand = HitStandPrefs {
+        enabled,
+        pairs,
+        softs,
+    };
+    prefs.save();
+    let mut state = STATE.lock().unwrap();
+    state.trainer.set_mode(mode_from_prefs(&prefs));
+    // a replaced hand can't be undone back to
+    state.undo = None;
+    save_hand(&state);
+    output_trainer(&state.trainer);
+}
+
+/// Turn coach mode on or off. In it, missing hands in the same row of the card (e.g. soft 18)
+/// enough times makes the next few hands all come from that row.
+#[wasm_bindgen]
+pub fn set_coach_mode(enabled: bool) {
+    let mut prefs = Prefs::load();
+    prefs.coach = enabled;
+    prefs.save();
+    STATE
+        .lock()
+        .unwrap()
+        .trainer
+        .set_coach(coach_from_prefs(&prefs));
+}
+
+/// Turn dealing common hands more often on or off
+#[wasm_bindgen]
+pub fn set_frequency_weighting(enabled: bool) {
+    let mut prefs = Prefs::load();
+    prefs.frequency_weighting = enabled;
+    prefs.save();
+    STATE
+        .lock()
+        .unwrap()
+        .trainer
+        .set_frequency_weighting(enabled);
+}
+
+/// Turn playing out correct splits on or off. With it on, each hand a correct split makes is dealt
+/// a second card and played in turn.
+#[wasm_bindgen]
+pub fn set_play_splits(enabled: bool) {
+    let mut prefs = Prefs::load();
+    prefs.play_splits = enabled;
+    prefs.save();
+    STATE.lock().unwrap().trainer.set_play_splits(enabled);
+}
+
+/// Turn multi-card hands on or off. With it on, some hard and soft hands are dealt as three or
+/// four cards, where the answer is what to do when doubling isn't allowed.
+#[wasm_bindgen]
+pub fn set_multi_card_hands(enabled: bool) {
+    let mut prefs = Prefs::load();
+    prefs.multi_card = enabled;
+    prefs.save();
+    STATE.lock().unwrap().trainer.set_multi_card(enabled);
+}
+
+/// Turn memorization mode on or off. In it, the card hides the cells the player has mastered, so
+/// it shrinks as they learn it. See bj_core::memorize.
+#[wasm_bindgen]
+pub fn set_memorize_mode(enabled: bool) {
+    let mut prefs = Prefs::load();
+    prefs.memorize = enabled;
+    prefs.save();
+    output_resp_table(&STATE.lock().unwrap());
+}
+
+/// Reveal a cell hidden in memorization mode, given as the card's onclick callback gives it (e.g.
+/// "pair", 11, 10 for A,A vs 10). Revealing counts as a miss, and the cell stays shown until the
+/// player has re-drilled it. Returns false if the cell wasn't hidden.
+#[wasm_bindgen]
+pub fn reveal_cell(table: &str, player: u8, dealer: u8) -> bool {
+    let desc = match onclick_cell_desc(table, player, dealer) {
+        Some(desc) => desc,
+        None => return false,
+    };
+    let goals = Prefs::load().mastery;
+    let mut state = STATE.lock().unwrap();
+    let mut stats = state.trainer.stats().clone();
+    if !state.memorize.reveal(desc, &mut stats, &goals) {
+        return false;
+    }
+    let streak = state.trainer.streak();
+    state.trainer.set_stats(stats, streak);
+    // the reveal can't be undone by undoing the answer before it
+    state.undo = None;
+    ls_set(
+        state.use_session_storage,
+        lskeys::LS_KEY_MEMORIZE,
+        &state.memorize,
+    );
+    output_trainer(&state.trainer);
+    output_resp_table(&state);
+    true
+}
+
+/// Handle a click on a cell of the card, given as the card's onclick callback gives it (e.g. "pair",
+/// 11, 10 for A,A vs 10). In memorization mode, a hidden cell is revealed. Otherwise the next
+/// TARGET_HANDS hands are all in the cell, to practice it, and then dealing goes back to normal.
+#[wasm_bindgen]
+pub fn onclick_cell(table: &str, player: u8, dealer: u8) {
+    if Prefs::load().memorize && reveal_cell(table, player, dealer) {
+        return;
+    }
+    let desc = match onclick_cell_desc(table, player, dealer) {
+        Some(desc) => desc,
+        None => return,
+    };
+    let mut state = STATE.lock().unwrap();
+    if state.demo.is_some() {
+        return;
+    }
+    if let Err(e) = state.trainer.start_target(desc) {
+        feedback::hint(&e.to_string());
+        return;
+    }
+    // a replaced hand can't be undone back to
+    state.undo = None;
+    save_hand(&state);
+    output_trainer(&state.trainer);
+    feedback::hint(&format!(
+        "Next {} hands are {} to practice it.",
+        TARGET_HANDS,
+        desc.label()
+    ));
+}
+
+/// The cell being practiced after clicking it and how many hands are left, e.g. "Hard 16 vs 10 (3
+/// left)", or an empty string if there is no target practice
+#[wasm_bindgen]
+pub fn target_practice() -> String {
+    match STATE.lock().unwrap().trainer.target() {
+        Some(target) => format!("{} ({} left)", target.desc.label(), target.remaining),
+        None => String::new(),
+    }
+}
+
+/// End target practice early and deal a hand as usual
+#[wasm_bindgen]
+pub fn stop_target_practice() {
+    let mut state = STATE.lock().unwrap();
+    if state.trainer.target().is_none() {
+        return;
+    }
+    state.trainer.stop_target();
+    state.undo = None;
+    save_hand(&state);
+    output_trainer(&state.trainer);
+}
+
+/// Start the demo, where the trainer plays itself with the player's card and mode. The page calls
+/// demo_step() every so many milliseconds, which this returns, until stop_demo().
+#[wasm_bindgen]
+pub fn start_demo() -> u32 {
+    let prefs = Prefs::load();
+    let mut state = STATE.lock().unwrap();
+    let mut demo = Demo::new(state.trainer.strategy().clone());
+    demo.trainer_mut().set_mode(mode_from_prefs(&prefs));
+    demo.trainer_mut().set_multi_card(prefs.multi_card);
+    let pace = demo.set_pace_ms(prefs.demo_pace_ms);
+    state.demo = Some(demo);
+    pace
+}
+
+/// Take the demo's next step: show its next hand, or play the one shown and say why. Returns the
+/// step as JSON, e.g. {"kind":"play","action":"Double",...} so the page can animate the button,
+/// or an empty string if no demo is running.
+#[wasm_bindgen]
+pub fn demo_step() -> String {
+    let mut state = STATE.lock().unwrap();
+    let demo = match state.demo.as_mut() {
+        Some(demo) => demo,
+        None => return String::new(),
+    };
+    let step = match demo.step() {
+        Ok(step) => step,
+        Err(e) => {
+            log::warn!("{}", e);
+            return String::new();
+        }
+    };
+    match step {
+        DemoStep::Deal { player, dealer } => {
+            output_hand(&player, dealer);
+            update_game_buttons(demo.trainer());
+            serde_json::json!({
+                "kind": "deal",
+                "player": player.cards().map(|&c| card_char(c)).collect::<String>(),
+                "dealer": card_char(dealer).to_string(),
+            })
+        }
+        DemoStep::Play(play) => {
+            feedback::hint(&play.explanation);
+            serde_json::json!({
+                "kind": "play",
+                "action": play.action.to_string(),
+                "explanation": play.explanation,
+            })
+        }
+    }
+    .to_string()
+}
+
+/// Do whatever the kiosk needs doing now, if the trainer is running as one. The page calls this
+/// every second. Returns what was done as JSON: {"kind":"reset"} when the trainer was just
+/// started over for the next visitor, and {"kind":"chart","table":"Soft"} when the page should
+/// show that table of the strategy chart, until kiosk_input() says to put the trainer back.
+/// Returns an empty string if there's nothing to do.
+#[wasm_bindgen]
+pub fn kiosk_tick() -> String {
+    match kiosk::tick() {
+        None => String::new(),
+        Some(KioskStep::Reset) => {
+            let opts = STATE.lock().unwrap().opts.clone();
+            set_up(opts);
+            serde_json::json!({"kind": "reset"}).to_string()
+        }
+        Some(KioskStep::Chart(table)) => serde_json::json!({
+            "kind": "chart",
+            "table": kiosk::table_name(table),
+        })
+        .to_string(),
+    }
+}
+
+/// Stop the demo and put the player's hand back
+#[wasm_bindgen]
+pub fn stop_demo() {
+    let mut state = STATE.lock().unwrap();
+    if state.demo.take().is_some() {
+        output_trainer(&state.trainer);
+    }
+}
+
+/// Show each step of the demo for the given number of milliseconds, within limits. Returns the
+/// pace set, for the page's timer.
+#[wasm_bindgen]
+pub fn set_demo_pace(ms: u32) -> u32 {
+    let mut prefs = Prefs::load();
+    let mut state = STATE.lock().unwrap();
+    let pace = match state.demo.as_mut() {
+        Some(demo) => demo.set_pace_ms(ms),
+        None => ms.clamp(MIN_PACE_MS, MAX_PACE_MS),
+    };
+    prefs.demo_pace_ms = pace;
+    prefs.save();
+    pace
+}
+
+/// How many hands made by splitting are still to be played after the one on the table, so the
+/// page can say e.g. "Split hand, 1 more to play"
+#[wasm_bindgen]
+pub fn split_hands_waiting() -> usize {
+    STATE.lock().unwrap().trainer.split_hands_waiting()
+}
+
+/// Deal hands not seen in more than the given number of days as if they were new, or stop with
+/// undefined
+#[wasm_bindgen]
+pub fn set_refresh_days(days: Option<u32>) {
+    let mut prefs = Prefs::load();
+    prefs.refresh_days = days;
+    prefs.save();
+    STATE.lock().unwrap().trainer.set_refresh_after(days);
+}
+
+/// The fraction of hands the player has gotten right on the current card, with each hand counting
+/// as much as it comes up in real play, or undefined if they haven't played any
+#[wasm_bindgen]
+pub fn weighted_accuracy() -> Option<f64> {
+    STATE.lock().unwrap().trainer.weighted_accuracy()
+}
+
+/// Turn even money decisions on or off. When on, check even_money_offered() before showing a hand.
+#[wasm_bindgen]
+pub fn set_even_money_mode(enabled: bool) {
+    let mut prefs = Prefs::load();
+    prefs.even_money = enabled;
+    prefs.save();
+    let mut state = STATE.lock().unwrap();
+    state.trainer.set_even_money(enabled);
+    output_trainer(&state.trainer);
+}
+
+/// Whether the current hand is a blackjack against an ace that needs an even money decision. If
+/// so, none of the action buttons are shown, and the player should be asked to take or decline
+/// even money, calling on_even_money() with their answer.
+#[wasm_bindgen]
+pub fn even_money_offered() -> bool {
+    STATE.lock().unwrap().trainer.even_money_offered()
+}
+
+#[wasm_bindgen]
+pub fn on_even_money(take: bool) {
+    let mut state = STATE.lock().unwrap();
+    if !claim_tab(&mut state) {
+        return;
+    }
+    let snapshot = Snapshot::take(&state.trainer);
+    let graded = match state.trainer.act_even_money(take) {
+        Ok(Some(graded)) => graded,
+        Ok(None) => return,
+        Err(e) => {
+            log::warn!("{}", e);
+            return;
+        }
+    };
+    state.undo = Some(snapshot);
+    practice::answered();
+    let s = if graded.is_correct {
+        "Declining even money is correct.".to_string()
+    } else if graded.shielded {
+        "Wrong: basic strategy never takes even money. A streak shield saved your streak."
+            .to_string()
+    } else {
+        format!(
+            "Wrong: basic strategy never takes even money. Streak was {}.",
+            graded.old_streak
+        )
+    };
+    feedback::hint(&target_hint(s, graded.target_ended));
+    emit_all(&graded_events(graded.is_correct, state.trainer.streak()));
+    match graded.coach {
+        Some(CoachEvent::DrillStarted(_)) => emit(GameEvent::DrillStarted),
+        Some(CoachEvent::DrillEnded) => emit(GameEvent::DrillEnded),
+        None => {}
+    }
+    save_hand(&state);
+    output_trainer(&state.trainer);
+}
+
+/// The row of the card coach mode is drilling and how many hands are left, e.g. "Soft 18 (3
+/// left)", or an empty string if there is no drill
+#[wasm_bindgen]
+pub fn coach_drill() -> String {
+    match STATE.lock().unwrap().trainer.drill() {
+        Some(drill) => format!("{} ({} left)", drill.label(), drill.remaining),
+        None => String::new(),
+    }
+}
+
+/// The value of the dealer upcard being drilled this session (2-10, or 11 for ace), or undefined if
+/// not drilling an upcard
+#[wasm_bindgen]
+pub fn drill_upcard() -> Option<u8> {
+    STATE.lock().unwrap().trainer.upcard()
+}
+
+/// The names of the bundled drill packs, as a JSON list
+#[wasm_bindgen]
+pub fn drill_pack_names() -> String {
+    serde_json::to_string(&packs::pack_names().collect::<Vec<_>>()).unwrap()
+}
+
+/// Play a drill pack: the bundled one with the given name, or one given as JSON. An empty string
+/// stops playing one. Every hand is dealt from the pack until then, and the hand on the screen is
+/// replaced. Returns why the pack couldn't be loaded, or an empty string if it was.
+#[wasm_bindgen]
+pub fn set_drill_pack(name_or_json: String) -> String {
+    let pack = if name_or_json.is_empty() {
+        None
+    } else {
+        match packs::load_pack(&name_or_json) {
+            Ok(pack) => Some(pack),
+            Err(e) => return e.to_string(),
+        }
+    };
+    let mut state = STATE.lock().unwrap();
+    if let Err(e) = state.trainer.set_pack(pack) {
+        return e.to_string();
+    }
+    // a replaced hand can't be undone back to
+    state.undo = None;
+    save_hand(&state);
+    output_trainer(&state.trainer);
+    String::new()
+}
+
+/// The drill pack being played and how the current run of it is going, as JSON like
+/// {"name": "Soft doubles", "description": "...", "played": 10, "correct": 9, "hands": 24,
+/// "pass_percent": 90, "passed": null}, where passed is true or false once the run is over. An
+/// empty string if no pack is being played.
+#[wasm_bindgen]
+pub fn drill_pack_progress() -> String {
+    let state = STATE.lock().unwrap();
+    let (pack, progress) = match (state.trainer.pack(), state.trainer.pack_progress()) {
+        (Some(pack), Some(progress)) => (pack, progress),
+        _ => return String::new(),
+    };
+    serde_json::json!({
+        "name": pack.name,
+        "description": pack.description,
+        "played": progress.played,
+        "correct": progress.correct,
+        "hands": progress.scoring.hands,
+        "pass_percent": progress.scoring.pass_percent,
+        "passed": progress.passed(),
+    })
+    .to_string()
+}
+
+/// The player's note on the hand currently on the screen, or an empty string
+#[wasm_bindgen]
+pub fn note_from_state() -> String {
+    let state = STATE.lock().unwrap();
+    state.trainer.note().unwrap_or("").to_string()
+}
+
+/// Set the player's note on the hand currently on the screen. An empty note removes it.
+#[wasm_bindgen]
+pub fn on_edit_note(note: String) {
+    let mut state = STATE.lock().unwrap();
+    state.trainer.set_note(&note);
+    ls_set(
+        state.use_session_storage,
+        lskeys::LS_KEY_NOTES,
+        state.trainer.notes(),
+    );
+    output_note(state.trainer.note());
+    output_resp_table(&state);
+}
+
+/// Take back the most recent answer: put its hand back on the screen and its stats and streak back
+/// to how they were. Returns false if there is nothing to undo, e.g. because the last answer was
+/// already undone.
+#[wasm_bindgen]
+pub fn undo_last_answer() -> bool {
+    let mut state = STATE.lock().unwrap();
+    let snapshot = match state.undo.take() {
+        Some(snapshot) => snapshot,
+        None => return false,
+    };
+    snapshot.restore(&mut state.trainer);
+    save_hand(&state);
+    output_trainer(&state.trainer);
+    feedback::hint("Last answer undone.");
+    true
+}
+
+/// The name to keep the stats from play_stats_from_state() under, e.g. "card-0123456789abcdef".
+/// Stats are only comparable with stats graded against the same card, so switching cards switches
+/// to stats kept under a different name.
+#[wasm_bindgen]
+pub fn stats_namespace() -> String {
+    namespace(&STATE.lock().unwrap().trainer)
+}
+
+fn namespace(trainer: &Trainer) -> String {
+    format!("card-{}", trainer.stats_segment())
+}
+
+/// The stats segments the player has stats in this session, as a JSON list of names like
+/// stats_namespace() returns
+#[wasm_bindgen]
+pub fn stats_namespaces() -> String {
+    let state = STATE.lock().unwrap();
+    let names: Vec<String> = state
+        .trainer
+        .stats_segments()
+        .iter()
+        .map(|s| format!("card-{}", s))
+        .collect();
+    serde_json::to_string(&names).unwrap()
+}
+
+/// Give the trainer stats saved under the given name, e.g. so they can be migrated to the current
+/// card. Returns false if the name or stats can't be parsed.
+#[wasm_bindgen]
+pub fn namespace_statistics_into_state(namespace: String, play_stats_s: String) -> bool {
+    let mut state = STATE.lock().unwrap();
+    let segment = match namespace.strip_prefix("card-") {
+        Some(segment) => segment,
+        None => return false,
+    };
+    let table = match playstats_table::parse_from_string(play_stats_s) {
+        Ok(t) => t,
+        Err(e) => {
+            log::warn!("Couldn't parse string to table: {}", e);
+            return false;
+        }
+    };
+    state.trainer.set_stats_for(segment, table);
+    if segment == state.trainer.stats_segment() {
+        state.undo = None;
+        output_trainer(&state.trainer);
+    }
+    true
+}
+
+/// Carry the stats kept under the given name (from stats_namespaces()) over to the current card,
+/// e.g. after the player confirms their edited card is close enough to their old one. Returns
+/// false if there are no such stats.
+#[wasm_bindgen]
+pub fn migrate_stats(namespace: String) -> bool {
+    let mut state = STATE.lock().unwrap();
+    let segment = match namespace.strip_prefix("card-") {
+        Some(segment) => segment,
+        None => return false,
+    };
+    if !state.trainer.migrate_stats(segment) {
+        return false;
+    }
+    state.undo = None;
+    output_trainer(&state.trainer);
+    true
+}
+
+/// The player's n weakest cells on the current card, worst first, as a JSON list of objects with
+/// label (e.g. "Hard 16 vs 10"), seen, and correct. This is the format a time trial can be given to
+/// deal only these cells, e.g. as a coach's homework (see bj_core::weakcells).
+#[wasm_bindgen]
+pub fn weakest_cells(n: usize) -> String {
+    let state = STATE.lock().unwrap();
+    serde_json::to_string(&weakcells::export(state.trainer.stats(), n)).unwrap()
+}
+
+/// The player's accuracy against each dealer upcard on the current card, 2 through A, as a JSON
+/// list of objects with label (e.g. "A"), seen, correct, and accuracy (null if none seen)
+#[wasm_bindgen]
+pub fn accuracy_by_upcard() -> String {
+    let state = STATE.lock().unwrap();
+    serde_json::to_string(&breakdown::by_upcard(state.trainer.stats())).unwrap()
+}
+
+/// The player's accuracy on each row of the current card, as accuracy_by_upcard() but labeled
+/// e.g. "Hard 16", "Soft 18", or "8,8"
+#[wasm_bindgen]
+pub fn accuracy_by_player() -> String {
+    let state = STATE.lock().unwrap();
+    serde_json::to_string(&breakdown::by_player(state.trainer.stats())).unwrap()
+}
+
+/// accuracy_by_upcard() or, if by_player, accuracy_by_player() as CSV, for downloading
+#[wasm_bindgen]
+pub fn accuracy_breakdown_csv(by_player: bool) -> String {
+    let state = STATE.lock().unwrap();
+    let stats = state.trainer.stats();
+    breakdown::to_csv(&if by_player {
+        breakdown::by_player(stats)
+    } else {
+        breakdown::by_upcard(stats)
+    })
+}
+
+/// Progress toward the mastery goals (see Prefs::mastery) on the hard, soft, and pair tables of
+/// the current card, as a JSON list of objects with hand ("Hard", "Soft", or "Pair"), mastered,
+/// cells, the goal (min_percent and min_seen), complete, and a label like "Pairs mastered: 38/100
+/// cells at ≥90% over ≥5 attempts"
+#[wasm_bindgen]
+pub fn mastery_progress() -> String {
+    let goals = Prefs::load().mastery;
+    let state = STATE.lock().unwrap();
+    let progress: Vec<_> = table_mastery_progress(state.trainer.stats(), &goals)
+        .into_iter()
+        .map(|p| {
+            let mut v = serde_json::to_value(p).unwrap();
+            v["complete"] = p.is_complete().into();
+            v["label"] = p.to_string().into();
+            v
+        })
+        .collect();
+    serde_json::to_string(&progress).unwrap()
+}
+
+/// Set the mastery goal for one table of the card: "hard", "soft", or "pair". Returns false if
+/// there's no such table or the percent is over 100.
+#[wasm_bindgen]
+pub fn set_mastery_goal(table: String, min_percent: u32, min_seen: u32) -> bool {
+    let hand = match table.to_ascii_lowercase().as_str() {
+        "hard" => HandType::Hard,
+        "soft" => HandType::Soft,
+        "pair" => HandType::Pair,
+        _ => return false,
+    };
+    if min_percent > 100 {
+        return false;
+    }
+    let mut prefs = Prefs::load();
+    let goal = prefs.mastery.get_mut(hand);
+    goal.min_percent = min_percent;
+    goal.min_seen = min_seen;
+    prefs.save();
+    true
+}
+
+/// What the player pressed against what the card said to do this session, as JSON: an object with
+/// cells (a list of objects with correct, pressed, and count, most common first) and the number of
+/// primary and fallback mistakes
+#[wasm_bindgen]
+pub fn confusion_matrix() -> String {
+    let state = STATE.lock().unwrap();
+    let confusion = state.trainer.confusion();
+    let cells: Vec<_> = confusion
+        .cells()
+        .into_iter()
+        .map(|(correct, pressed, count)| {
+            serde_json::json!({
+                "correct": correct.to_string(),
+                "pressed": pressed.to_string(),
+                "count": count,
+            })
+        })
+        .collect();
+    serde_json::json!({
+        "cells": cells,
+        "primary": confusion.mistakes(Mistake::Primary),
+        "fallback": confusion.mistakes(Mistake::Fallback),
+    })
+    .to_string()
+}
+
+/// The card being trained on as a standalone SVG image, for downloading and sharing
+#[wasm_bindgen]
+pub fn strategy_chart_svg() -> String {
+    let state = STATE.lock().unwrap();
+    let mut fd = vec![];
+    SVGTableRenderer::render(&mut fd, state.trainer.strategy()).unwrap();
+    String::from_utf8(fd).unwrap()
+}
+
+/// The card being trained on as aligned plain text, for copying and pasting
+#[wasm_bindgen]
+pub fn strategy_chart_text() -> String {
+    let state = STATE.lock().unwrap();
+    let mut fd = vec![];
+    TextTableRenderer::render(&mut fd, state.trainer.strategy()).unwrap();
+    String::from_utf8(fd).unwrap()
+}
+
+/// The player's accuracy on each cell of the current card as a standalone SVG heatmap, for
+/// downloading and sharing
+#[wasm_bindgen]
+pub fn stats_heatmap_svg() -> String {
+    let state = STATE.lock().unwrap();
+    let mut fd = vec![];
+    SVGTableRenderer::render_heatmap(&mut fd, state.trainer.stats()).unwrap();
+    String::from_utf8(fd).unwrap()
+}
+
 #[wasm_bindgen]
 pub fn play_stats_from_state() -> String {
     let state = STATE.lock().unwrap();
-    playstats_table::parse_to_string(&state.play_stats)
+    playstats_table::parse_to_string(state.trainer.stats())
+}
+
+/// The player's stats and streak to upload, as JSON in the shared format (see bj_core::proto)
+#[wasm_bindgen]
+pub fn stats_upload_from_state() -> String {
+    let state = STATE.lock().unwrap();
+    Upload::new(
+        concat!("basic-strategy/", env!("CARGO_PKG_VERSION")),
+        Body::Stats(StatsUpload::new(
+            state.trainer.stats(),
+            state.trainer.streak(),
+        )),
+    )
+    .with_identity(&anonid::identity())
+    .to_json()
 }
 
 #[wasm_bindgen]
 pub fn streak_from_state() -> u32 {
     let state = STATE.lock().unwrap();
-    state.streak
+    state.trainer.streak()
+}
+
+/// How many streak shields the player holds, to be saved along with their streak
+#[wasm_bindgen]
+pub fn shields_from_state() -> u32 {
+    STATE.lock().unwrap().trainer.shields()
+}
+
+/// Restore how many streak shields the player holds, e.g. to what shields_from_state() returned
+/// last session
+#[wasm_bindgen]
+pub fn shields_into_state(shields: u32) {
+    let mut state = STATE.lock().unwrap();
+    state.trainer.set_shields(shields);
+    state.undo = None;
+}
+
+/// Turn streak shields on or off. With them on, every streak milestone earns a shield that keeps
+/// the streak going through one wrong answer.
+#[wasm_bindgen]
+pub fn set_streak_shields(enabled: bool) {
+    let mut prefs = Prefs::load();
+    prefs.streak_shields = enabled;
+    prefs.save();
+    STATE.lock().unwrap().trainer.set_shields_enabled(enabled);
 }
 
 #[wasm_bindgen]
@@ -411,24 +1235,30 @@ pub fn statistics_into_state(play_stats_s: String, streak: u32) {
     let table = match playstats_table::parse_from_string(play_stats_s) {
         Ok(t) => t,
         Err(e) => {
-            debug_log(&format!("Couldn\'t parse string to table: {}", e));
+            log::warn!("Couldn't parse string to table: {}", e);
             return;
         }
     };
-    debug_log(&format!(
-        "Storing table in state as well as streak={}",
-        streak
-    ));
-    state.play_stats = table;
-    state.streak = streak;
-    let hand: LSVal<(Hand, Card)> =
-        LSVal::from_ls(state.use_session_storage, lskeys::LS_KEY_EXISTING_HAND).unwrap();
-    output_stats((&hand.0, hand.1), &state.play_stats, state.streak);
+    log::debug!("Storing table in state as well as streak={}", streak);
+    state.trainer.set_stats(table, streak);
+    state.reload.loaded();
+    state.undo = None;
+    output_trainer(&state.trainer);
 }
 
+/// Whether the page has to reload the player's stats and streak with statistics_into_state(),
+/// because another tab may have saved newer ones. Answers aren't graded until it has.
+#[wasm_bindgen]
+pub fn stats_need_reload() -> bool {
+    STATE.lock().unwrap().reload.is_pending()
+}
+
+/// Whether it's time to upload the player's stats. Never while they need reloading, so stale ones
+/// don't overwrite what another tab uploaded.
 #[wasm_bindgen]
 pub fn should_upload_statistics() -> bool {
-    STATE.lock().unwrap().next_upload_stats == 0
+    let state = STATE.lock().unwrap();
+    state.next_upload_stats == 0 && !state.reload.is_pending()
 }
 
 #[wasm_bindgen]
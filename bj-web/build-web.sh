@@ -15,4 +15,3 @@ for CRATE_DIR in basic-strategy time-trial counting; do
     wasm-pack -v build $MODE --target web
     cd -
 done
-# ./format-web-templates.py
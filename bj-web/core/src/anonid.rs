@@ -0,0 +1,41 @@
+//! The player's anonymous ID (see bj_core::anonid), shared by every trainer and kept in
+//! localStorage (never sessionStorage) so it stays the same across sessions. It's made the first
+//! time an upload needs it.
+use crate::localstorage::{ls_get, ls_set, lskeys};
+use bj_core::anonid::AnonIdentity;
+use wasm_bindgen::prelude::*;
+
+/// The player's identity, making them a new ID if they don't have one yet
+pub fn identity() -> AnonIdentity {
+    match ls_get(false, lskeys::LS_KEY_ANON_ID) {
+        Some(ident) => ident,
+        None => {
+            let ident = AnonIdentity::new();
+            ls_set(false, lskeys::LS_KEY_ANON_ID, &ident);
+            ident
+        }
+    }
+}
+
+/// The ID uploads are sent with, or undefined if the player opted out
+#[wasm_bindgen]
+pub fn anon_id() -> Option<String> {
+    identity().id().map(|id| id.to_string())
+}
+
+/// Replace the player's ID with a new one, opting them back in if they had opted out. Returns the
+/// new ID.
+#[wasm_bindgen]
+pub fn regenerate_anon_id() -> String {
+    let mut ident = identity();
+    ident.regenerate();
+    ls_set(false, lskeys::LS_KEY_ANON_ID, &ident);
+    // safe to unwrap because it was just regenerated
+    ident.id().unwrap().to_string()
+}
+
+/// Forget the player's ID and send uploads without one from now on
+#[wasm_bindgen]
+pub fn opt_out_anon_id() {
+    ls_set(false, lskeys::LS_KEY_ANON_ID, &AnonIdentity::opted_out());
+}
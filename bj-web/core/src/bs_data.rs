@@ -1 +1,111 @@
-pub const T1_JSON: &[u8] = include_bytes!("../data/t1.json");
+//! Strategy cards compiled into the binary, from bj-data.
+//!
+//! Each card is stored as JSON and parsed at most once, the first time any card is asked for.
+//! Callers get a reference to the cached card and should only clone it if they need to own or
+//! modify it (e.g. to store it in local storage).
+use bj_core::basicstrategy::rules::Rules;
+use bj_core::basicstrategy::BasicStrategy;
+use bj_data::{Kind, DATASETS};
+use lazy_static::lazy_static;
+use wasm_bindgen::prelude::*;
+
+pub use bj_data::T1_JSON;
+
+lazy_static! {
+    /// (name, card) for every embedded card. The first one, t1, is the default.
+    static ref BS_CARDS: Vec<(&'static str, BasicStrategy)> = DATASETS
+        .iter()
+        .filter(|d| d.kind == Kind::StrategyCard)
+        .map(|d| (d.name, d.strategy_card().unwrap()))
+        .collect();
+}
+
+/// The card to use when the user hasn't picked one
+pub fn def_bs_card() -> &'static BasicStrategy {
+    &BS_CARDS[0].1
+}
+
+/// The embedded card with the given name, if there is one
+pub fn bs_card(name: &str) -> Option<&'static BasicStrategy> {
+    BS_CARDS.iter().find(|(n, _)| *n == name).map(|(_, bs)| bs)
+}
+
+/// The first embedded card made for exactly the given rules, if there is one
+pub fn bs_card_for_rules(rules: &Rules) -> Option<&'static BasicStrategy> {
+    BS_CARDS
+        .iter()
+        .map(|(_, bs)| bs)
+        .find(|bs| bs.rules.as_ref() == Some(rules))
+}
+
+/// The JSON of the embedded card for the given rules, or for the closest ones there's a card for.
+/// None if no embedded card fits them (see bj_data::strategy_card_for()).
+pub fn lookup(rules: &Rules) -> Option<&'static [u8]> {
+    bj_data::strategy_card_for(rules).map(|d| d.data)
+}
+
+/// The name of the embedded card for the given rules as JSON (see lookup()), for the page to save
+/// as the player's card when they pick their table's rules. Returns an empty string if the rules
+/// don't parse or no embedded card fits them.
+#[wasm_bindgen]
+pub fn bs_card_name_for_rules(rules: String) -> String {
+    match serde_json::from_str(&rules) {
+        Ok(rules) => bj_data::strategy_card_for(&rules)
+            .map(|d| d.name.to_string())
+            .unwrap_or_default(),
+        Err(e) => {
+            log::warn!("Bad rules: {}", e);
+            String::new()
+        }
+    }
+}
+
+/// The names of all embedded cards
+pub fn bs_card_names() -> impl Iterator<Item = &'static str> {
+    BS_CARDS.iter().map(|(n, _)| *n)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bj_core::basicstrategy::rules::NoPeekLoss;
+
+    #[test]
+    fn def_is_cached() {
+        assert!(std::ptr::eq(def_bs_card(), def_bs_card()));
+        assert!(std::ptr::eq(def_bs_card(), bs_card("t1").unwrap()));
+    }
+
+    #[test]
+    fn all_names_found() {
+        for name in bs_card_names() {
+            assert!(bs_card(name).is_some());
+        }
+        assert!(bs_card("not a card").is_none());
+    }
+
+    #[test]
+    fn found_by_rules() {
+        let t1 = bs_card("t1").unwrap();
+        let mut rules = t1.rules.clone().unwrap();
+        assert!(std::ptr::eq(bs_card_for_rules(&rules).unwrap(), t1));
+        rules.resplit_aces = true.into();
+        assert!(bs_card_for_rules(&rules).is_none());
+    }
+
+    #[test]
+    fn looked_up() {
+        let mut rules = def_bs_card().rules.clone().unwrap();
+        assert_eq!(lookup(&rules), Some(T1_JSON));
+        rules.hit_soft_17 = false.into();
+        let card = bs_card("4d-s17-das").unwrap();
+        let looked_up: BasicStrategy = serde_json::from_slice(lookup(&rules).unwrap()).unwrap();
+        assert_eq!(looked_up.rules, card.rules);
+        assert!(std::ptr::eq(bs_card_for_rules(&rules).unwrap(), card));
+        rules.peek_bj = false.into();
+        rules.no_peek_loss = NoPeekLoss::AllBets;
+        assert_eq!(lookup(&rules), None);
+        let json = serde_json::to_string(&rules).unwrap();
+        assert_eq!(bs_card_name_for_rules(json), "");
+    }
+}
@@ -0,0 +1,134 @@
+//! Crash reports: what a page was doing when it panicked, kept until the next load.
+//!
+//! console_error_panic_hook only tells the console, which players never look at. A page that calls
+//! set_panic_hook() at startup also gets the panic message and the last RECENT_EVENTS things that
+//! happened saved to localStorage. On the next load, take_crash_report() hands the report to the
+//! page once, so it can offer to send it along with a bug report.
+//!
+//! Every game event (see the events module) is recorded, and pages can record() anything else
+//! that would help, like which button was pressed.
+use crate::localstorage::{ls_get, ls_remove, lskeys};
+use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::panic;
+use std::sync::Once;
+use wasm_bindgen::prelude::*;
+
+/// How many of the most recent events a crash report includes
+pub const RECENT_EVENTS: usize = 20;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CrashReport {
+    /// The page that panicked, as given to set_panic_hook()
+    pub page: String,
+    /// What the panic said, and where
+    pub message: String,
+    /// The most recent events, oldest first
+    pub events: Vec<String>,
+    /// When it panicked, in seconds since the epoch
+    pub when: f64,
+}
+
+/// The last few things that happened, dropping the oldest once it's full
+#[derive(Debug, Clone, PartialEq)]
+pub struct Recent<T> {
+    events: VecDeque<T>,
+    cap: usize,
+}
+
+impl<T> Recent<T>
+where
+    T: Clone,
+{
+    pub fn new(cap: usize) -> Self {
+        Self {
+            events: VecDeque::with_capacity(cap),
+            cap,
+        }
+    }
+
+    pub fn push(&mut self, event: T) {
+        if self.cap == 0 {
+            return;
+        }
+        if self.events.len() == self.cap {
+            self.events.pop_front();
+        }
+        self.events.push_back(event);
+    }
+
+    /// The events, oldest first
+    pub fn to_vec(&self) -> Vec<T> {
+        self.events.iter().cloned().collect()
+    }
+}
+
+thread_local! {
+    // Not behind a Mutex so the panic hook can't deadlock on it if the panic happened while
+    // recording. Wasm is single threaded anyway.
+    static RECENT: RefCell<Recent<String>> = RefCell::new(Recent::new(RECENT_EVENTS));
+}
+
+/// Remember that something happened, in case the page panics soon
+pub fn record(event: &str) {
+    RECENT.with(|r| {
+        if let Ok(mut r) = r.try_borrow_mut() {
+            r.push(event.to_string());
+        }
+    });
+}
+
+/// Save a report of the panic to localStorage, ignoring any errors, as panicking again inside the
+/// panic hook would abort before the console hears about the first one
+fn save_report(page: &str, info: &panic::PanicHookInfo) {
+    let report = CrashReport {
+        page: page.to_string(),
+        message: info.to_string(),
+        events: RECENT.with(|r| r.try_borrow().map(|r| r.to_vec()).unwrap_or_default()),
+        when: js_sys::Date::now() / 1000.0,
+    };
+    let storage = web_sys::window().and_then(|w| w.local_storage().ok().flatten());
+    if let (Some(storage), Ok(json)) = (storage, serde_json::to_string(&report)) {
+        let _ = storage.set_item(lskeys::LS_KEY_CRASH_REPORT, &json);
+    }
+}
+
+static HOOK: Once = Once::new();
+
+/// Log panics to the console like console_error_panic_hook, and also save a CrashReport for the
+/// next load. page names the page in the report. Only the first call does anything.
+pub fn set_panic_hook(page: &'static str) {
+    HOOK.call_once(|| {
+        panic::set_hook(Box::new(move |info| {
+            console_error_panic_hook::hook(info);
+            save_report(page, info);
+        }));
+    });
+}
+
+/// The report of the last panic as JSON, if the page has panicked since this was last called. The
+/// report is only given once, so the page doesn't keep offering to send it.
+#[wasm_bindgen]
+pub fn take_crash_report() -> Option<String> {
+    let report: CrashReport = ls_get(false, lskeys::LS_KEY_CRASH_REPORT)?;
+    ls_remove(false, lskeys::LS_KEY_CRASH_REPORT);
+    serde_json::to_string(&report).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recent() {
+        let mut r = Recent::new(3);
+        for i in 0..5 {
+            r.push(i.to_string());
+        }
+        assert_eq!(r.to_vec(), vec!["2", "3", "4"]);
+        let mut none = Recent::new(0);
+        none.push("x".to_string());
+        assert!(none.to_vec().is_empty());
+    }
+}
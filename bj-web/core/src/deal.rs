@@ -0,0 +1,125 @@
+//! Showing a hand as a sequence of dealt cards, so pages can animate deals the same way in every
+//! trainer.
+//!
+//! A hand is dealt dealer upcard first, then each of the player's cards in order. Each step comes
+//! with the time, in ms since the start of the deal, at which to show its card.
+//!
+//! Pages that want to animate register a hook with set_deal_hook(). output_hand() then empties the
+//! card elements and calls `hook(element_id, card, at_ms)` for every step, in order, where card is
+//! the card's Unicode character. The page should append card to the element with element_id after
+//! at_ms. Pages that don't register a hook get every card shown at once. Time trials keep the clock
+//! running during a deal, so they may not want to animate.
+use crate::card_char;
+use crate::view::set_text;
+use bj_core::deck::Card;
+use bj_core::hand::Hand;
+use std::cell::RefCell;
+use wasm_bindgen::prelude::*;
+
+/// The time between dealing one card and the next
+pub const DEAL_STEP_MS: u32 = 250;
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum DealTarget {
+    Dealer,
+    Player,
+}
+
+impl DealTarget {
+    /// The id of the element that shows this target's cards
+    pub fn element_id(self) -> &'static str {
+        match self {
+            Self::Dealer => "dealer_cards",
+            Self::Player => "player_cards",
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct DealStep {
+    pub target: DealTarget,
+    pub card: Card,
+    /// When to show the card, in ms since the start of the deal
+    pub at_ms: u32,
+}
+
+/// The steps to deal the given hand
+pub fn deal_steps(player: &Hand, dealer: Card) -> Vec<DealStep> {
+    std::iter::once((DealTarget::Dealer, dealer))
+        .chain(player.cards().map(|&c| (DealTarget::Player, c)))
+        .enumerate()
+        .map(|(i, (target, card))| DealStep {
+            target,
+            card,
+            at_ms: i as u32 * DEAL_STEP_MS,
+        })
+        .collect()
+}
+
+thread_local! {
+    // Like the event hook, this can't live in a Mutex
+    static DEAL_HOOK: RefCell<Option<js_sys::Function>> = RefCell::new(None);
+}
+
+/// Register the function to call for every step of a deal, replacing any previous one. Pass
+/// undefined/null to go back to showing hands all at once.
+#[wasm_bindgen]
+pub fn set_deal_hook(hook: Option<js_sys::Function>) {
+    DEAL_HOOK.with(|h| *h.borrow_mut() = hook);
+}
+
+fn set_cards_text(target: DealTarget, s: &str) {
+    set_text(target.element_id(), s);
+}
+
+/// Show the hand in the player_cards and dealer_cards elements, through the page's deal hook if it
+/// has one
+pub fn output_hand(player: &Hand, dealer: Card) {
+    DEAL_HOOK.with(|h| match &*h.borrow() {
+        None => {
+            set_cards_text(
+                DealTarget::Player,
+                &player.cards().map(|&c| card_char(c)).collect::<String>(),
+            );
+            set_cards_text(DealTarget::Dealer, &card_char(dealer).to_string());
+        }
+        Some(hook) => {
+            set_cards_text(DealTarget::Player, "");
+            set_cards_text(DealTarget::Dealer, "");
+            for step in deal_steps(player, dealer) {
+                // as with events, a broken hook isn't worth breaking the game over
+                let _ = hook.call3(
+                    &JsValue::NULL,
+                    &JsValue::from_str(step.target.element_id()),
+                    &JsValue::from_str(&card_char(step.card).to_string()),
+                    &JsValue::from(step.at_ms),
+                );
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bj_core::deck::{Rank, Suit};
+
+    #[test]
+    fn steps() {
+        let c = |r| Card::new(r, Suit::Heart);
+        let player = Hand::new(&[c(Rank::R5), c(Rank::R3), c(Rank::RK)]);
+        let steps = deal_steps(&player, c(Rank::R9));
+        let targets: Vec<_> = steps.iter().map(|s| (s.target, s.card)).collect();
+        assert_eq!(
+            targets,
+            vec![
+                (DealTarget::Dealer, c(Rank::R9)),
+                (DealTarget::Player, c(Rank::R5)),
+                (DealTarget::Player, c(Rank::R3)),
+                (DealTarget::Player, c(Rank::RK)),
+            ]
+        );
+        assert_eq!(steps[0].at_ms, 0);
+        assert_eq!(steps[3].at_ms, 3 * DEAL_STEP_MS);
+    }
+}
@@ -0,0 +1,114 @@
+//! Micro-benchmarks of the work the trainers do, runnable in the browser.
+//!
+//! This is hidden: no page links to it. When someone reports that a page is slow on their device,
+//! ask them to open the browser console on any page and run `run_diagnostics()`, then attach the
+//! JSON it returns. Each timing is the total time for some number of iterations of one operation,
+//! so numbers from different devices can be compared directly.
+use crate::bs_data::{def_bs_card, T1_JSON};
+use bj_core::basicstrategy::BasicStrategy;
+use bj_core::playstats::PlayStats;
+use bj_core::rendertable::{HTMLTableRenderer, HTMLTableRendererOpts};
+use bj_core::table::Table;
+use bj_core::utils::{rand_next_hand, uniform_rand_2card_hand};
+use serde::Serialize;
+use std::hint::black_box;
+use wasm_bindgen::prelude::*;
+
+#[derive(Debug, Serialize)]
+pub struct Timing {
+    pub name: &'static str,
+    pub iters: u32,
+    pub total_ms: f64,
+    pub per_iter_us: f64,
+}
+
+/// Time iters calls of f using the given clock, which returns ms since any fixed point
+pub fn bench<F>(name: &'static str, iters: u32, now: &dyn Fn() -> f64, mut f: F) -> Timing
+where
+    F: FnMut(),
+{
+    let start = now();
+    for _ in 0..iters {
+        f();
+    }
+    let total_ms = now() - start;
+    Timing {
+        name,
+        iters,
+        total_ms,
+        per_iter_us: if iters == 0 {
+            0.0
+        } else {
+            total_ms * 1000.0 / f64::from(iters)
+        },
+    }
+}
+
+/// Run every benchmark, timing them with the given clock
+pub fn run_benchmarks(now: &dyn Fn() -> f64) -> Vec<Timing> {
+    let bs = def_bs_card();
+    let hands: Vec<_> = (0..100).map(|_| uniform_rand_2card_hand()).collect();
+    let mut next = hands.iter().cycle();
+    let stats = Table::filled(PlayStats::new());
+    vec![
+        bench("table lookup", 10_000, now, || {
+            let (player, dealer) = next.next().unwrap();
+            black_box(bs.table.get(player, *dealer).unwrap());
+        }),
+        bench("uniform hand", 1_000, now, || {
+            black_box(uniform_rand_2card_hand());
+        }),
+        bench("weighted hand", 100, now, || {
+            black_box(rand_next_hand(&stats));
+        }),
+        bench("parse card", 20, now, || {
+            let bs: BasicStrategy = serde_json::from_slice(T1_JSON).unwrap();
+            black_box(bs);
+        }),
+        bench("render card", 20, now, || {
+            let mut buf = vec![];
+            let opts = HTMLTableRendererOpts {
+                incl_bs_rules: true,
+                cell_onclick_cb: None,
+                notes: None,
+                visibility: None,
+            };
+            HTMLTableRenderer::render(&mut buf, bs, opts).unwrap();
+            black_box(buf);
+        }),
+    ]
+}
+
+/// Run every benchmark and return the timings as JSON
+#[wasm_bindgen]
+pub fn run_diagnostics() -> String {
+    let perf = web_sys::window()
+        .expect("should have a window in this context")
+        .performance()
+        .expect("window should have performance");
+    serde_json::to_string(&run_benchmarks(&|| perf.now())).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+
+    #[test]
+    fn timings() {
+        // a clock that advances 1ms every time it's read
+        let t = Cell::new(0.0);
+        let now = || {
+            t.set(t.get() + 1.0);
+            t.get()
+        };
+        let mut calls = 0;
+        let timing = bench("test", 4, &now, || calls += 1);
+        assert_eq!(calls, 4);
+        assert_eq!(timing.total_ms, 1.0);
+        assert_eq!(timing.per_iter_us, 250.0);
+        let names: Vec<_> = run_benchmarks(&now).iter().map(|t| t.name).collect();
+        assert_eq!(names.len(), 5);
+        assert!(names.contains(&"render card"));
+    }
+}
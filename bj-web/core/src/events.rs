@@ -0,0 +1,127 @@
+//! Semantic game events, so pages can attach sounds, vibration, etc. to them without duplicating
+//! game state logic in JS.
+//!
+//! A page registers a single hook with set_event_hook(). The hook is called as
+//! `hook(name, value)`, where name is one of the GameEvent names and value is the streak for
+//! streak milestones and 0 otherwise. Pages that never register a hook get no events.
+use crate::crash;
+pub use bj_core::trainer::is_streak_milestone;
+use std::cell::RefCell;
+use wasm_bindgen::prelude::*;
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum GameEvent {
+    /// The player got a hand right
+    Correct,
+    /// The player got a hand wrong
+    Wrong,
+    /// The player's streak just reached a milestone (see is_streak_milestone())
+    StreakMilestone(u32),
+    /// The game has ended, e.g. all the hands of a time trial have been played
+    GameOver,
+    /// Coach mode started drilling a row of the card the player keeps missing
+    DrillStarted,
+    /// The coach mode drill is over
+    DrillEnded,
+    /// An answer wasn't graded because the trainer is open in another tab (see the tabs module)
+    OtherTab,
+    /// This tab took over from another one that may have saved stats, which should be reloaded.
+    /// Hooks are called while the trainer is busy, so reload from a timeout, not the hook itself.
+    TabTakenOver,
+    /// Another tab saved stats over this tab's, which should be reloaded the same way as for
+    /// TabTakenOver
+    StatsChanged,
+    /// A hint that isn't shown, because the player only wants to hear feedback (see the feedback
+    /// module)
+    Hint,
+}
+
+impl GameEvent {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Correct => "correct",
+            Self::Wrong => "wrong",
+            Self::StreakMilestone(_) => "streak_milestone",
+            Self::GameOver => "game_over",
+            Self::DrillStarted => "drill_started",
+            Self::DrillEnded => "drill_ended",
+            Self::OtherTab => "other_tab",
+            Self::TabTakenOver => "tab_taken_over",
+            Self::StatsChanged => "stats_changed",
+            Self::Hint => "hint",
+        }
+    }
+
+    fn value(self) -> u32 {
+        match self {
+            Self::StreakMilestone(streak) => streak,
+            _ => 0,
+        }
+    }
+}
+
+/// The events for a graded hand, given whether it was correct and the player's streak afterward
+pub fn graded_events(is_correct: bool, new_streak: u32) -> Vec<GameEvent> {
+    if !is_correct {
+        vec![GameEvent::Wrong]
+    } else if is_streak_milestone(new_streak) {
+        vec![GameEvent::Correct, GameEvent::StreakMilestone(new_streak)]
+    } else {
+        vec![GameEvent::Correct]
+    }
+}
+
+thread_local! {
+    // JS functions can't be sent between threads, so this can't live in a Mutex like the rest of
+    // our state. Wasm is single threaded anyway.
+    static EVENT_HOOK: RefCell<Option<js_sys::Function>> = RefCell::new(None);
+}
+
+/// Register the function to call on every game event, replacing any previous one. Pass
+/// undefined/null to stop getting events.
+#[wasm_bindgen]
+pub fn set_event_hook(hook: Option<js_sys::Function>) {
+    EVENT_HOOK.with(|h| *h.borrow_mut() = hook);
+}
+
+/// Tell the page's hook, if any, about the event, and record it for crash reports. Errors thrown
+/// by the hook are ignored, as sounds and such aren't worth breaking the game over.
+pub fn emit(event: GameEvent) {
+    crash::record(event.name());
+    // cloned out of the cell, as the hook may call set_event_hook()
+    let hook = EVENT_HOOK.with(|h| h.borrow().clone());
+    if let Some(hook) = hook {
+        let _ = hook.call2(
+            &JsValue::NULL,
+            &JsValue::from_str(event.name()),
+            &JsValue::from(event.value()),
+        );
+    }
+}
+
+pub fn emit_all(events: &[GameEvent]) {
+    for event in events {
+        emit(*event);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn milestones() {
+        let milestones: Vec<u32> = (0..=400).filter(|&s| is_streak_milestone(s)).collect();
+        assert_eq!(milestones, vec![10, 25, 50, 100, 200, 300, 400]);
+    }
+
+    #[test]
+    fn graded() {
+        assert_eq!(graded_events(false, 0), vec![GameEvent::Wrong]);
+        assert_eq!(graded_events(true, 9), vec![GameEvent::Correct]);
+        assert_eq!(
+            graded_events(true, 10),
+            vec![GameEvent::Correct, GameEvent::StreakMilestone(10)]
+        );
+    }
+}
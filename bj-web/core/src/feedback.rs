@@ -0,0 +1,90 @@
+//! Telling the player how they did, the way they asked to be told (see prefs::FeedbackMode).
+//!
+//! Trainers never call the page's hint functions themselves. Feedback on an answer, which pages
+//! usually flash, goes through hint(), and lasting messages like "Paused." go through status().
+//! Pages define `flash_hint_message(s)`, and `set_hint_message(s)` to show a message without any
+//! animation. A page without set_hint_message() only logs what it would have shown without a
+//! flash.
+use crate::events::{emit, GameEvent};
+use crate::prefs::{FeedbackMode, Prefs};
+use wasm_bindgen::prelude::*;
+
+#[wasm_bindgen]
+extern "C" {
+    fn flash_hint_message(s: &str);
+    #[wasm_bindgen(catch)]
+    fn set_hint_message(s: &str) -> Result<(), JsValue>;
+}
+
+/// How a message reaches the player
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum Route {
+    Flash,
+    Static,
+    /// Nothing is shown, and the page's event hook gets a hint event to play a sound for
+    Sound,
+}
+
+/// How feedback on an answer reaches the player in the given mode. Lasting messages are always
+/// shown without a flash, as they stay on the page.
+fn route(mode: FeedbackMode, lasting: bool) -> Route {
+    match (mode, lasting) {
+        (_, true) | (FeedbackMode::Static, false) => Route::Static,
+        (FeedbackMode::Flash, false) => Route::Flash,
+        (FeedbackMode::SoundOnly, false) => Route::Sound,
+    }
+}
+
+fn show(s: &str, lasting: bool) {
+    match route(Prefs::load().feedback, lasting) {
+        Route::Flash => flash_hint_message(s),
+        Route::Static => {
+            if set_hint_message(s).is_err() {
+                log::warn!("This page can't show hints without a flash: {}", s);
+            }
+        }
+        Route::Sound => {
+            log::info!("{}", s);
+            emit(GameEvent::Hint);
+        }
+    }
+}
+
+/// Feedback on what the player just did, e.g. what they should have done instead
+pub fn hint(s: &str) {
+    show(s, false);
+}
+
+/// A message that stays until it's replaced, e.g. a trial's results. Pass "" to clear it.
+pub fn status(s: &str) {
+    show(s, true);
+}
+
+/// The player's feedback mode, "flash", "static", or "sound_only", so pages can tone down their
+/// own animations to match
+#[wasm_bindgen]
+pub fn feedback_mode() -> String {
+    Prefs::load().feedback.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn routes() {
+        assert_eq!(route(FeedbackMode::Flash, false), Route::Flash);
+        assert_eq!(route(FeedbackMode::Static, false), Route::Static);
+        assert_eq!(route(FeedbackMode::SoundOnly, false), Route::Sound);
+        // nothing lasting ever flashes or goes unseen
+        for mode in [
+            FeedbackMode::Flash,
+            FeedbackMode::Static,
+            FeedbackMode::SoundOnly,
+        ]
+        .iter()
+        {
+            assert_eq!(route(*mode, true), Route::Static);
+        }
+    }
+}
@@ -0,0 +1,76 @@
+//! What the pages keep in the browser's storage has to keep loading after the types behind it
+//! change, as in bj-core's formats tests. testdata/formats has what each format looked like at
+//! each point it changed. Never edit a fixture; see bj-core for what to do when a format changes.
+use crate::prefs::{FeedbackMode, Prefs, StoragePolicy, Theme, PREFS_VERSION};
+use crate::records::{RecordKey, Records};
+use bj_core::button::GameButton;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+fn fixture(name: &str) -> String {
+    let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("testdata/formats")
+        .join(name);
+    std::fs::read_to_string(&path).unwrap_or_else(|e| panic!("{}: {}", path.display(), e))
+}
+
+/// Load the fixture, and check it serializes back the same
+fn round_trip<T: DeserializeOwned + Serialize>(name: &str) -> T {
+    let s = fixture(name);
+    let val: T = serde_json::from_str(&s).unwrap_or_else(|e| panic!("{}: {}", name, e));
+    assert_eq!(
+        serde_json::to_value(&val).unwrap(),
+        serde_json::from_str::<serde_json::Value>(&s).unwrap(),
+        "{} serializes differently now",
+        name
+    );
+    val
+}
+
+#[test]
+fn prefs() {
+    let current: Prefs = round_trip("prefs-current.json");
+    assert_eq!(current.buttons.hidden, vec![GameButton::Surrender]);
+    assert_eq!(current.refresh_days, Some(30));
+    assert_eq!(current.counting.bet_ramp.to_string(), "1:1,2:2,3:4");
+    assert_eq!(current.feedback, FeedbackMode::Static);
+
+    // from before the feedback mode, which flashed
+    let mastery = Prefs::from_json(&fixture("prefs-mastery.json")).unwrap();
+    assert_eq!(mastery.feedback, FeedbackMode::Flash);
+    assert_eq!(
+        Prefs {
+            feedback: FeedbackMode::Static,
+            ..mastery
+        },
+        current
+    );
+
+    // the first shared prefs, before most of the trainer options
+    let first = Prefs::from_json(&fixture("prefs-first-settings.json")).unwrap();
+    assert_eq!(first.version, PREFS_VERSION);
+    assert_eq!(
+        (first.theme, first.storage, first.sound),
+        (Theme::Light, StoragePolicy::Local, true)
+    );
+    assert_eq!(first.counting.num_cards, 104);
+    assert!(!first.counting.csm && !first.memorize);
+
+    // the counting trainer's blob, from before prefs were shared
+    let blob = Prefs::from_json(&fixture("prefs-counting-blob.json")).unwrap();
+    assert_eq!(
+        (blob.counting.num_decks, blob.counting.cards_at_a_time),
+        (6, 2)
+    );
+}
+
+#[test]
+fn records() {
+    let records: Records = round_trip("records-current.json");
+    let key = RecordKey {
+        num_hands: 50,
+        pool: "cells: Hard 16 vs 10".to_string(),
+    };
+    let best = records.get(&key).unwrap();
+    assert_eq!((best.num_correct, best.duration), (50, 98.25));
+}
@@ -0,0 +1,246 @@
+//! Building a hand by tapping rank buttons, for pages where the player enters a hand instead of
+//! being dealt one, like the one they just saw at the casino.
+//!
+//! Taps go to whichever side is selected. Player taps add a card, up to the point where the hand
+//! is worth 21 or more, as nobody draws to those. A dealer tap sets the upcard, replacing any that
+//! was there, so a wrong one is fixed by tapping the right one. Suits don't matter to strategy, so
+//! every card is a spade.
+//!
+//! The page drives one HandInput through the hand_input_* exports, and after each one redraws from
+//! hand_input_state(), which is the HandSummary as JSON.
+use bj_core::composition::parse_rank;
+use bj_core::deck::{Card, Suit};
+use bj_core::hand::Hand;
+use bj_core::table::GameDesc;
+use lazy_static::lazy_static;
+use serde::Serialize;
+use std::sync::Mutex;
+use thiserror::Error;
+use wasm_bindgen::prelude::*;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Side {
+    Player,
+    Dealer,
+}
+
+#[derive(Debug, PartialEq, Error)]
+pub enum HandInputError {
+    /// Not a rank, see parse_rank()
+    #[error("'{0}' is not a rank (2-10, J, Q, K, or A)")]
+    BadRank(String),
+    /// The player's hand is worth 21 or more, so can't take another card
+    #[error(
+        "{}",
+        if *.0 > 21 { "The hand is already bust" } else { "Nobody draws to 21" }
+    )]
+    Finished(u8),
+}
+
+/// What the page shows of the hand being entered
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct HandSummary {
+    /// The side taps go to
+    pub side: Side,
+    /// The player's ranks, in the order they were entered, e.g. "T", "6"
+    pub player: Vec<String>,
+    pub dealer: Option<String>,
+    /// The player's total, counting an ace as 11 when that doesn't bust the hand. 0 with no cards.
+    pub value: u8,
+    pub soft: bool,
+    pub bust: bool,
+    pub blackjack: bool,
+    pub pair: bool,
+    /// The strategy card cell, e.g. "Hard 16 vs 10", once there are two player cards and an
+    /// upcard and the hand isn't bust
+    pub cell: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct HandInput {
+    player: Vec<Card>,
+    dealer: Option<Card>,
+    side: Side,
+}
+
+impl Default for HandInput {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl HandInput {
+    /// No cards, with the player selected
+    pub fn new() -> Self {
+        Self {
+            player: vec![],
+            dealer: None,
+            side: Side::Player,
+        }
+    }
+
+    pub fn select(&mut self, side: Side) {
+        self.side = side;
+    }
+
+    /// The player's total and whether it's soft, for any number of cards
+    fn total(&self) -> (u8, bool) {
+        let hard: u8 = self.player.iter().map(|c| c.value()).sum();
+        if hard <= 11 && self.player.iter().any(|c| c.value() == 1) {
+            (hard + 10, true)
+        } else {
+            (hard, false)
+        }
+    }
+
+    /// Tap the rank's button. See the module docs.
+    pub fn add(&mut self, rank: &str) -> Result<(), HandInputError> {
+        let rank = parse_rank(rank).ok_or_else(|| HandInputError::BadRank(rank.to_string()))?;
+        let card = Card::new(rank, Suit::Spade);
+        match self.side {
+            Side::Dealer => self.dealer = Some(card),
+            Side::Player => {
+                let (value, _) = self.total();
+                if value >= 21 {
+                    return Err(HandInputError::Finished(value));
+                }
+                self.player.push(card);
+            }
+        }
+        Ok(())
+    }
+
+    /// Take back the selected side's last card, if it has one
+    pub fn remove(&mut self) -> bool {
+        match self.side {
+            Side::Dealer => self.dealer.take().is_some(),
+            Side::Player => self.player.pop().is_some(),
+        }
+    }
+
+    /// Start over, with the player selected
+    pub fn clear(&mut self) {
+        *self = Self::new();
+    }
+
+    /// The hand, once it's one the player would act on: at least two cards, not bust, and with an
+    /// upcard
+    pub fn hand(&self) -> Option<(Hand, Card)> {
+        let dealer = self.dealer?;
+        if self.player.len() < 2 {
+            return None;
+        }
+        let hand = Hand::new(&self.player);
+        if hand.is_bust() {
+            None
+        } else {
+            Some((hand, dealer))
+        }
+    }
+
+    pub fn summary(&self) -> HandSummary {
+        let (value, soft) = self.total();
+        let two = self.player.len() == 2;
+        let pair = two && Hand::new(&self.player).is_pair();
+        HandSummary {
+            side: self.side,
+            player: self.player.iter().map(|c| c.rank().to_string()).collect(),
+            dealer: self.dealer.map(|c| c.rank().to_string()),
+            value,
+            soft,
+            bust: value > 21,
+            blackjack: two && value == 21,
+            pair,
+            cell: self
+                .hand()
+                .map(|(hand, dealer)| GameDesc::from_hand(&hand, dealer).label()),
+        }
+    }
+}
+
+lazy_static! {
+    static ref INPUT: Mutex<HandInput> = Mutex::new(HandInput::new());
+}
+
+/// Send taps to the dealer's upcard if true, else to the player's hand
+#[wasm_bindgen]
+pub fn hand_input_select(dealer: bool) {
+    let side = if dealer { Side::Dealer } else { Side::Player };
+    INPUT.lock().unwrap().select(side);
+}
+
+/// Tap a rank button. An empty string if that worked, else why not.
+#[wasm_bindgen]
+pub fn hand_input_add(rank: String) -> String {
+    match INPUT.lock().unwrap().add(&rank) {
+        Ok(()) => String::new(),
+        Err(e) => e.to_string(),
+    }
+}
+
+/// Take back the selected side's last card. False if it had none.
+#[wasm_bindgen]
+pub fn hand_input_remove() -> bool {
+    INPUT.lock().unwrap().remove()
+}
+
+#[wasm_bindgen]
+pub fn hand_input_clear() {
+    INPUT.lock().unwrap().clear();
+}
+
+/// The hand being entered, as JSON. See HandSummary.
+#[wasm_bindgen]
+pub fn hand_input_state() -> String {
+    serde_json::to_string(&INPUT.lock().unwrap().summary()).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn entering() {
+        let mut input = HandInput::new();
+        assert_eq!(input.summary().value, 0);
+        input.add("a").unwrap();
+        let s = input.summary();
+        assert_eq!((s.value, s.soft), (11, true));
+        input.add("5").unwrap();
+        input.select(Side::Dealer);
+        input.add("9").unwrap();
+        input.add("10").unwrap();
+        let s = input.summary();
+        assert_eq!(s.player, vec!["A", "5"]);
+        assert_eq!(s.dealer.as_deref(), Some("T"));
+        assert_eq!(s.cell.as_deref(), Some("Soft 16 vs 10"));
+        assert_eq!(
+            input.add("1"),
+            Err(HandInputError::BadRank("1".to_string()))
+        );
+        input.select(Side::Player);
+        input.add("K").unwrap();
+        input.add("6").unwrap();
+        // A,5,K,6 is a hard 22
+        let s = input.summary();
+        assert!(s.bust && !s.soft);
+        assert_eq!(s.cell, None);
+        assert_eq!(input.add("2"), Err(HandInputError::Finished(22)));
+        assert!(input.remove());
+        assert_eq!(input.summary().value, 16);
+        input.clear();
+        input.add("8").unwrap();
+        input.add("8").unwrap();
+        assert!(input.summary().pair);
+        assert_eq!(input.hand(), None);
+        input.clear();
+        input.add("A").unwrap();
+        input.add("J").unwrap();
+        assert!(input.summary().blackjack);
+        assert_eq!(
+            input.add("2").unwrap_err().to_string(),
+            "Nobody draws to 21"
+        );
+    }
+}
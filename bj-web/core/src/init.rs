@@ -0,0 +1,250 @@
+//! The options each trainer's rust_init_with_options() takes, as JSON.
+//!
+//! The original rust_init() functions take bare numbers and flags, which say nothing about what
+//! they mean at the JS call site and are easy to pass in the wrong order. The options structs
+//! name everything, and any field left out takes its default, so `{}` (or an empty string) is a
+//! valid set of options for every trainer. Options are checked when parsed, so a trainer never
+//! starts with ones that make no sense. The old rust_init() functions still work, and build these
+//! structs themselves.
+use bj_core::count::{AnySystem, DECK_LEN};
+use bj_core::kiosk;
+use bj_core::trainer::HandSource;
+use bj_core::weakcells::{self, WeakCell};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+#[derive(Debug, PartialEq, Error)]
+pub enum OptionsError {
+    /// The options aren't valid JSON, or a field has the wrong type
+    #[error("Bad options: {0}")]
+    Json(String),
+    /// The options parse, but make no sense
+    #[error("Invalid options: {0}")]
+    Invalid(String),
+}
+
+/// Options that can be checked once parsed
+pub trait TrainerOptions: Sized + Default + for<'de> Deserialize<'de> {
+    fn validate(&self) -> Result<(), OptionsError>;
+
+    /// Parse and check the options. An empty string is the default options.
+    fn from_json(s: &str) -> Result<Self, OptionsError> {
+        let opts: Self = if s.trim().is_empty() {
+            Self::default()
+        } else {
+            serde_json::from_str(s).map_err(|e| OptionsError::Json(e.to_string()))?
+        };
+        opts.validate()?;
+        Ok(opts)
+    }
+}
+
+/// How the basic strategy trainer deals hands
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum DealSource {
+    /// Uniformly at random
+    Uniform,
+    /// Weighted by the player's stats
+    Weighted,
+}
+
+impl From<DealSource> for HandSource {
+    fn from(s: DealSource) -> Self {
+        match s {
+            DealSource::Uniform => HandSource::Uniform,
+            DealSource::Weighted => HandSource::Weighted,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct BasicStrategyOptions {
+    pub source: DealSource,
+    /// Whether every hand this session is against the same dealer upcard, with each session
+    /// drilling the upcard after the previous session's
+    pub drill_upcard: bool,
+    /// Run as a kiosk (see the kiosk module)
+    pub kiosk: Option<KioskOptions>,
+}
+
+impl Default for BasicStrategyOptions {
+    fn default() -> Self {
+        Self {
+            source: DealSource::Weighted,
+            drill_upcard: false,
+            kiosk: None,
+        }
+    }
+}
+
+impl TrainerOptions for BasicStrategyOptions {
+    fn validate(&self) -> Result<(), OptionsError> {
+        match &self.kiosk {
+            Some(kiosk) => kiosk.validate(),
+            None => Ok(()),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct KioskOptions {
+    /// How long the kiosk sits idle before it resets and starts showing the chart
+    pub idle_minutes: u32,
+    /// How long each table of the chart is shown
+    pub chart_secs: u32,
+}
+
+impl Default for KioskOptions {
+    fn default() -> Self {
+        Self {
+            idle_minutes: (kiosk::DEFAULT_IDLE_SECS / 60.0) as u32,
+            chart_secs: kiosk::DEFAULT_CHART_SECS as u32,
+        }
+    }
+}
+
+impl TrainerOptions for KioskOptions {
+    fn validate(&self) -> Result<(), OptionsError> {
+        if self.idle_minutes == 0 || self.chart_secs == 0 {
+            return Err(OptionsError::Invalid(
+                "A kiosk needs at least 1 idle minute and 1 second per table".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct CountingOptions {
+    /// How many decks the cards are drawn from
+    pub num_decks: u8,
+    /// How many cards are shown in total, which can be less than all the decks so the count
+    /// doesn't end at zero
+    pub num_cards: u16,
+    /// How many cards are shown at once
+    pub cards_at_a_time: u8,
+    /// Deal from a continuous shuffle machine, which takes back the cards shown after every group
+    /// of cards_at_a_time, instead of a shoe
+    pub csm: bool,
+    /// The count system to count with, e.g. "zen"
+    pub system: AnySystem,
+}
+
+impl Default for CountingOptions {
+    fn default() -> Self {
+        Self {
+            num_decks: 1,
+            num_cards: DECK_LEN,
+            cards_at_a_time: 1,
+            csm: false,
+            system: AnySystem::HiLo,
+        }
+    }
+}
+
+impl TrainerOptions for CountingOptions {
+    fn validate(&self) -> Result<(), OptionsError> {
+        if self.num_decks == 0 || self.cards_at_a_time == 0 {
+            return Err(OptionsError::Invalid(
+                "Need at least 1 deck and 1 card at a time".to_string(),
+            ));
+        }
+        if u16::from(self.num_decks) * DECK_LEN < self.num_cards {
+            return Err(OptionsError::Invalid(format!(
+                "{} decks of cards have less than {} cards",
+                self.num_decks, self.num_cards
+            )));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct TimeTrialOptions {
+    /// How many hands the trial is
+    pub num_hands: usize,
+    /// Deal only from these cells, e.g. a list of a friend's or student's weakest cells they
+    /// exported from the basic strategy trainer (see bj_core::weakcells). Every cell is dealt if
+    /// empty.
+    pub weak_cells: Vec<WeakCell>,
+}
+
+impl Default for TimeTrialOptions {
+    fn default() -> Self {
+        Self {
+            num_hands: 50,
+            weak_cells: vec![],
+        }
+    }
+}
+
+impl TrainerOptions for TimeTrialOptions {
+    fn validate(&self) -> Result<(), OptionsError> {
+        if self.num_hands == 0 {
+            return Err(OptionsError::Invalid(
+                "A time trial needs at least 1 hand".to_string(),
+            ));
+        }
+        if !self.weak_cells.is_empty() {
+            weakcells::import(&self.weak_cells)
+                .map_err(|e| OptionsError::Invalid(e.to_string()))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn options() {
+        assert_eq!(
+            BasicStrategyOptions::from_json(""),
+            Ok(BasicStrategyOptions::default())
+        );
+        let bs = BasicStrategyOptions::from_json(r#"{"source": "uniform"}"#).unwrap();
+        assert_eq!(HandSource::from(bs.source), HandSource::Uniform);
+        assert!(!bs.drill_upcard);
+        assert_eq!(bs.kiosk, None);
+        let kiosk = BasicStrategyOptions::from_json(r#"{"kiosk": {"idle_minutes": 5}}"#);
+        assert_eq!(kiosk.unwrap().kiosk.unwrap().chart_secs, 10);
+        assert!(matches!(
+            BasicStrategyOptions::from_json(r#"{"kiosk": {"chart_secs": 0}}"#),
+            Err(OptionsError::Invalid(_))
+        ));
+        assert!(matches!(
+            BasicStrategyOptions::from_json(r#"{"source": 1}"#),
+            Err(OptionsError::Json(_))
+        ));
+        let counting = CountingOptions::from_json(r#"{"num_decks": 2, "num_cards": 100}"#);
+        let counting = counting.unwrap();
+        assert_eq!(counting.cards_at_a_time, 1);
+        assert_eq!(counting.system, AnySystem::HiLo);
+        let zen = CountingOptions::from_json(r#"{"system": "zen"}"#);
+        assert_eq!(zen.unwrap().system, AnySystem::Zen);
+        assert!(matches!(
+            CountingOptions::from_json(r#"{"system": "wong halves"}"#),
+            Err(OptionsError::Json(_))
+        ));
+        assert!(matches!(
+            CountingOptions::from_json(r#"{"num_cards": 53}"#),
+            Err(OptionsError::Invalid(_))
+        ));
+        assert!(matches!(
+            TimeTrialOptions::from_json(r#"{"num_hands": 0}"#),
+            Err(OptionsError::Invalid(_))
+        ));
+        let trial = TimeTrialOptions::from_json(r#"{"weak_cells": [{"label": "8,8 vs A"}]}"#);
+        assert_eq!(trial.unwrap().weak_cells.len(), 1);
+        assert!(matches!(
+            TimeTrialOptions::from_json(r#"{"weak_cells": [{"label": "8,8"}]}"#),
+            Err(OptionsError::Invalid(_))
+        ));
+    }
+}
@@ -0,0 +1,63 @@
+//! Kiosk mode, for a booth where strangers take turns at one screen. See bj_core::kiosk.
+//!
+//! A kiosk keeps nothing: start() clears everything the trainers stored and turns storage off, so
+//! one visitor's stats and prefs never show up for the next. The page calls kiosk_input() on
+//! every key press or click, and the trainer's kiosk_tick() every second, which resets the
+//! trainer after the kiosk has sat idle and then cycles through the strategy chart.
+use crate::init::KioskOptions;
+use crate::localstorage;
+use bj_core::hand::HandType;
+use bj_core::kiosk::Kiosk;
+pub use bj_core::kiosk::KioskStep;
+use lazy_static::lazy_static;
+use std::sync::Mutex;
+use wasm_bindgen::prelude::*;
+
+lazy_static! {
+    static ref KIOSK: Mutex<Option<Kiosk>> = Mutex::new(None);
+}
+
+/// Seconds, from the browser's clock
+fn now() -> f64 {
+    js_sys::Date::now() / 1000.0
+}
+
+/// Run the page as a kiosk. Call before loading anything from storage.
+pub fn start(opts: &KioskOptions) {
+    localstorage::clear_all();
+    localstorage::set_persistence(false);
+    *KIOSK.lock().unwrap() = Some(Kiosk::with_times(
+        f64::from(opts.idle_minutes) * 60.0,
+        f64::from(opts.chart_secs),
+        now(),
+    ));
+}
+
+/// Whether the page is running as a kiosk
+pub fn enabled() -> bool {
+    KIOSK.lock().unwrap().is_some()
+}
+
+/// What the trainer should do now, if it's a kiosk and anything needs doing
+pub fn tick() -> Option<KioskStep> {
+    KIOSK.lock().unwrap().as_mut()?.tick(now())
+}
+
+/// The heading of the chart's table, as the page shows it
+pub fn table_name(table: HandType) -> &'static str {
+    match table {
+        HandType::Hard => "Hard",
+        HandType::Soft => "Soft",
+        HandType::Pair => "Pair",
+    }
+}
+
+/// Someone used the kiosk. Returns true if the page should take the chart down and put the
+/// trainer back. Does nothing if the page isn't a kiosk.
+#[wasm_bindgen]
+pub fn kiosk_input() -> bool {
+    match KIOSK.lock().unwrap().as_mut() {
+        Some(kiosk) => kiosk.input(now()),
+        None => false,
+    }
+}
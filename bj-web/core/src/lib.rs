@@ -1,10 +1,32 @@
+pub mod anonid;
 pub mod bs_data;
-pub mod button;
-pub mod correct_resp;
+pub mod crash;
+pub mod deal;
+pub mod diagnostics;
+pub mod events;
+pub mod feedback;
+#[cfg(test)]
+mod formats;
+pub mod handinput;
+pub mod init;
+pub mod kiosk;
 pub mod localstorage;
+pub mod logging;
+pub mod onboarding;
+pub mod packs;
+pub mod practice;
+pub mod prefs;
+pub mod records;
+pub mod tabs;
+pub mod view;
 
 use bj_core::deck::{Card, Rank, Suit};
 
+/// The time in seconds since the Unix epoch, for Trainer::set_clock()
+pub fn now() -> u64 {
+    (js_sys::Date::now() / 1000.0) as u64
+}
+
 pub fn card_char(card: Card) -> char {
     // https://en.wikipedia.org/wiki/Playing_cards_in_Unicode#Block
     let base: u32 = match card.suit() {
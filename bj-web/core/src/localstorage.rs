@@ -1,10 +1,42 @@
 use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
 use web_sys::Storage;
 
 pub mod lskeys {
     pub const LS_KEY_BS_CARD: &str = "bj-current-bs-card";
     pub const LS_KEY_EXISTING_HAND: &str = "bj-hand";
     pub const LS_KEY_COUNTING_PREFS: &str = "bj-counting-prefs";
+    pub const LS_KEY_NOTES: &str = "bj-notes";
+    pub const LS_KEY_PREFS: &str = "bj-prefs";
+    pub const LS_KEY_RECORDS: &str = "bj-records";
+    pub const LS_KEY_DRILL_UPCARD: &str = "bj-drill-upcard";
+    pub const LS_KEY_TAB_LOCK: &str = "bj-tab-lock";
+    pub const LS_KEY_CRASH_REPORT: &str = "bj-crash-report";
+    pub const LS_KEY_PRACTICE: &str = "bj-practice";
+    pub const LS_KEY_ONBOARDING: &str = "bj-onboarding";
+    pub const LS_KEY_MEMORIZE: &str = "bj-memorize";
+    pub const LS_KEY_ANON_ID: &str = "bj-anon-id";
+    pub const LS_KEY_LOG_LEVEL: &str = "bj-log-level";
+    pub const LS_KEY_PAIR_DRILL: &str = "bj-pair-drill";
+
+    /// Every key above, for clear_all(). Add new keys here too.
+    pub const ALL: [&str; 15] = [
+        LS_KEY_BS_CARD,
+        LS_KEY_EXISTING_HAND,
+        LS_KEY_COUNTING_PREFS,
+        LS_KEY_NOTES,
+        LS_KEY_PREFS,
+        LS_KEY_RECORDS,
+        LS_KEY_DRILL_UPCARD,
+        LS_KEY_TAB_LOCK,
+        LS_KEY_CRASH_REPORT,
+        LS_KEY_PRACTICE,
+        LS_KEY_ONBOARDING,
+        LS_KEY_MEMORIZE,
+        LS_KEY_ANON_ID,
+        LS_KEY_LOG_LEVEL,
+        LS_KEY_PAIR_DRILL,
+    ];
 }
 
 pub struct LSVal<T>
@@ -22,8 +54,17 @@ where
     for<'de> T: Deserialize<'de>,
 {
     pub fn from_ls_or_default(is_session: bool, key: &str, def: T) -> Self {
+        Self::from_ls_or_else(is_session, key, || def)
+    }
+
+    /// Like from_ls_or_default(), but only build the default value if it is actually needed
+    pub fn from_ls_or_else<F>(is_session: bool, key: &str, def: F) -> Self
+    where
+        F: FnOnce() -> T,
+    {
         match ls_get(is_session, key) {
             None => {
+                let def = def();
                 ls_set(is_session, key, &def);
                 Self {
                     key: key.to_owned(),
@@ -85,37 +126,69 @@ where
     }
 }
 
-fn ls(is_session: bool) -> Storage {
-    let win = web_sys::window().expect("should have a window in this context");
+/// Whether the page keeps anything in storage, see set_persistence()
+static PERSIST: AtomicBool = AtomicBool::new(true);
+
+/// Turn storage on or off for the rest of the page's life. With it off nothing is read or
+/// written, as if the browser didn't allow storage, e.g. for a kiosk.
+pub fn set_persistence(on: bool) {
+    PERSIST.store(on, Ordering::Relaxed);
+}
+
+/// The storage, if the browser lets the page have it and persistence is on. Players can turn
+/// storage off, and then nothing is kept, but the trainers still work.
+fn ls(is_session: bool) -> Option<Storage> {
+    if !PERSIST.load(Ordering::Relaxed) {
+        return None;
+    }
+    storage(is_session)
+}
+
+fn storage(is_session: bool) -> Option<Storage> {
+    let win = web_sys::window()?;
     if is_session {
-        win.session_storage()
-            .expect("Err getting session_storage")
-            .expect("None getting session_storage")
+        win.session_storage().ok()?
     } else {
-        win.local_storage()
-            .expect("Err getting local_storage")
-            .expect("None getting local_storage")
+        win.local_storage().ok()?
     }
 }
 
-fn ls_get<T>(is_session: bool, key: &str) -> Option<T>
+/// Read and deserialize the value stored at `key`, if any
+pub fn ls_get<T>(is_session: bool, key: &str) -> Option<T>
 where
     for<'de> T: Deserialize<'de>,
 {
-    if let Some(val) = ls(is_session)
-        .get(key)
-        .expect("Err getting from local storage")
-    {
-        serde_json::from_str(&val).ok()
-    } else {
-        None
-    }
+    let val = ls(is_session)?.get(key).ok()??;
+    serde_json::from_str(&val).ok()
 }
 
-fn ls_set<T>(is_session: bool, key: &str, val: &T)
+/// Serialize and store `val` at `key`
+pub fn ls_set<T>(is_session: bool, key: &str, val: &T)
 where
     T: Serialize,
 {
     let val = serde_json::to_string(&val).unwrap();
-    ls(is_session).set(key, &val).unwrap()
+    // fails if storage is full, which leaves the old value
+    if let Some(ls) = ls(is_session) {
+        let _ = ls.set(key, &val);
+    }
+}
+
+/// Remove whatever is stored at `key`
+pub fn ls_remove(is_session: bool, key: &str) {
+    if let Some(ls) = ls(is_session) {
+        let _ = ls.remove_item(key);
+    }
+}
+
+/// Remove everything any trainer stored, in both localStorage and sessionStorage, even with
+/// persistence off
+pub fn clear_all() {
+    for is_session in [false, true].iter() {
+        if let Some(ls) = storage(*is_session) {
+            for key in lskeys::ALL.iter() {
+                let _ = ls.remove_item(key);
+            }
+        }
+    }
 }
@@ -0,0 +1,126 @@
+//! Leveled logging for the pages, through the `log` crate's macros (log::warn!() and friends).
+//!
+//! Pages call init() at startup. From then on every message at or above the level goes to the
+//! browser console, and the last LOG_ENTRIES of them are kept so the page can attach them to a bug
+//! report with recent_logs(). The level starts at info and can be changed from the console with
+//! set_log_level("debug"), which is remembered across loads.
+use crate::crash::Recent;
+use crate::localstorage::{ls_get, ls_set, lskeys};
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use serde::Serialize;
+use std::cell::RefCell;
+use std::str::FromStr;
+use wasm_bindgen::prelude::*;
+
+/// How many of the most recent log entries are kept
+pub const LOG_ENTRIES: usize = 200;
+
+const DEFAULT_LEVEL: LevelFilter = LevelFilter::Info;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(js_namespace = console, js_name = error)]
+    fn console_error(s: &str);
+    #[wasm_bindgen(js_namespace = console, js_name = warn)]
+    fn console_warn(s: &str);
+    #[wasm_bindgen(js_namespace = console, js_name = info)]
+    fn console_info(s: &str);
+    #[wasm_bindgen(js_namespace = console, js_name = debug)]
+    fn console_debug(s: &str);
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Entry {
+    /// In seconds since the epoch
+    pub when: f64,
+    /// e.g. "WARN"
+    pub level: String,
+    /// The module it was logged from, e.g. "bj_web_counting"
+    pub target: String,
+    pub message: String,
+}
+
+impl Entry {
+    fn new(when: f64, record: &Record) -> Self {
+        Self {
+            when,
+            level: record.level().to_string(),
+            target: record.target().to_string(),
+            message: record.args().to_string(),
+        }
+    }
+}
+
+thread_local! {
+    // Wasm is single threaded, and this is never borrowed while logging
+    static ENTRIES: RefCell<Recent<Entry>> = RefCell::new(Recent::new(LOG_ENTRIES));
+}
+
+struct WebLogger;
+
+impl Log for WebLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= log::max_level()
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        let entry = Entry::new(js_sys::Date::now() / 1000.0, record);
+        let line = format!("[{}] {}: {}", entry.level, entry.target, entry.message);
+        match record.level() {
+            Level::Error => console_error(&line),
+            Level::Warn => console_warn(&line),
+            Level::Info => console_info(&line),
+            Level::Debug | Level::Trace => console_debug(&line),
+        }
+        ENTRIES.with(|e| {
+            if let Ok(mut e) = e.try_borrow_mut() {
+                e.push(entry);
+            }
+        });
+    }
+
+    fn flush(&self) {}
+}
+
+static LOGGER: WebLogger = WebLogger;
+
+/// Start logging at the level last set with set_log_level(), or at info. Only the first call does
+/// anything.
+pub fn init() {
+    if log::set_logger(&LOGGER).is_ok() {
+        let level = ls_get::<String>(false, lskeys::LS_KEY_LOG_LEVEL)
+            .and_then(|s| LevelFilter::from_str(&s).ok())
+            .unwrap_or(DEFAULT_LEVEL);
+        log::set_max_level(level);
+    }
+}
+
+/// Log messages at the given level and above from now on, and on later loads: one of "off",
+/// "error", "warn", "info", "debug", or "trace". Returns false, changing nothing, if it isn't one.
+#[wasm_bindgen]
+pub fn set_log_level(level: &str) -> bool {
+    match LevelFilter::from_str(level) {
+        Ok(level) => {
+            log::set_max_level(level);
+            ls_set(false, lskeys::LS_KEY_LOG_LEVEL, &level.to_string());
+            true
+        }
+        Err(_) => false,
+    }
+}
+
+/// e.g. "INFO"
+#[wasm_bindgen]
+pub fn log_level() -> String {
+    log::max_level().to_string()
+}
+
+/// The most recent log entries as a JSON array, oldest first, for attaching to a bug report
+#[wasm_bindgen]
+pub fn recent_logs() -> String {
+    let entries = ENTRIES.with(|e| e.borrow().to_vec());
+    serde_json::to_string(&entries).unwrap()
+}
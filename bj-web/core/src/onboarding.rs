@@ -0,0 +1,218 @@
+//! Onboarding for players new to every trainer. See bj_core::onboarding.
+//!
+//! A page calls onboarding_needed() before starting its trainer, and if it's true, shows each
+//! step of onboarding_state() until the player finishes or skips it. Where onboarding got to is
+//! always kept in localStorage, so it picks up where it left off on reload and never comes back
+//! once done. Finishing saves the picked card and recommended mode in the prefs, and hands back the
+//! player's stats seeded with the placement quiz, for statistics_into_state().
+use crate::localstorage::{ls_get, ls_set, lskeys};
+use crate::prefs::Prefs;
+use crate::{bs_data, card_char};
+use bj_core::button::GameButton;
+use bj_core::hand::HandType;
+use bj_core::onboarding::{Onboarding, Recommendation, Step};
+use bj_core::playstats::PlayStats;
+use bj_core::table::{GameDesc, Table};
+use bj_core::utils::playstats_table;
+use serde::Serialize;
+use wasm_bindgen::prelude::*;
+
+/// A strategy card to pick at Step::PickRules
+#[derive(Serialize)]
+struct CardChoice {
+    name: &'static str,
+    /// The rules it's for
+    description: &'static str,
+}
+
+/// The drill the player is recommended to start with, at Step::Recommend
+#[derive(Serialize, Debug, PartialEq)]
+struct StartingDrill {
+    recommendation: Recommendation,
+    /// The drill pack to start, for set_drill_pack()
+    pack: Option<&'static str>,
+    description: &'static str,
+}
+
+/// Everything a page needs to show the current step
+#[derive(Serialize)]
+struct OnboardingView {
+    step: Step,
+    cards: Vec<CardChoice>,
+    /// The placement hand to answer, as card characters, and its cell
+    player: String,
+    dealer: String,
+    cell: String,
+    answered: usize,
+    total: usize,
+    drill: Option<StartingDrill>,
+}
+
+impl StartingDrill {
+    fn new(recommendation: Recommendation) -> Self {
+        let (pack, description) = match recommendation {
+            Recommendation::HitStand => (None, "Only hit or stand, until the basics stick"),
+            Recommendation::Drill(HandType::Hard) => (
+                Some("stiffs"),
+                "Hard 12-16, the hands that are easiest to get wrong",
+            ),
+            Recommendation::Drill(HandType::Soft) => {
+                (Some("soft-doubles"), "Soft hands worth doubling")
+            }
+            Recommendation::Drill(HandType::Pair) => {
+                (None, "Every hand, weighted toward the pairs you missed")
+            }
+            Recommendation::Everything => (None, "Every hand, weighted toward the ones you miss"),
+        };
+        Self {
+            recommendation,
+            pack,
+            description,
+        }
+    }
+}
+
+fn load() -> Option<Onboarding> {
+    ls_get(false, lskeys::LS_KEY_ONBOARDING)
+}
+
+fn save(ob: &Onboarding) {
+    ls_set(false, lskeys::LS_KEY_ONBOARDING, ob);
+}
+
+/// Whether the player has used any trainer before: they have prefs, or a hand saved in either
+/// storage
+fn has_history() -> bool {
+    ls_get::<serde_json::Value>(false, lskeys::LS_KEY_PREFS).is_some()
+        || ls_get::<serde_json::Value>(false, lskeys::LS_KEY_COUNTING_PREFS).is_some()
+        || ls_get::<serde_json::Value>(false, lskeys::LS_KEY_EXISTING_HAND).is_some()
+        || ls_get::<serde_json::Value>(true, lskeys::LS_KEY_EXISTING_HAND).is_some()
+}
+
+/// Whether to show onboarding: it was started and not finished, or this is the player's first run
+#[wasm_bindgen]
+pub fn onboarding_needed() -> bool {
+    match load() {
+        Some(ob) => ob.step() != Step::Done,
+        None => !has_history(),
+    }
+}
+
+/// The current step as JSON, see OnboardingView
+#[wasm_bindgen]
+pub fn onboarding_state() -> String {
+    let ob = load().unwrap_or_default();
+    let cards = match ob.step() {
+        Step::PickRules => bs_data::bs_card_names()
+            .map(|name| CardChoice {
+                name,
+                description: bj_data::dataset(name).map_or("", |d| d.description),
+            })
+            .collect(),
+        _ => vec![],
+    };
+    let (player, dealer, cell) = match ob.placement_hand() {
+        Some((player, dealer)) => (
+            player.cards().map(|&c| card_char(c)).collect(),
+            card_char(dealer).to_string(),
+            GameDesc::from_hand(&player, dealer).label(),
+        ),
+        None => Default::default(),
+    };
+    let (answered, total) = ob.progress();
+    serde_json::to_string(&OnboardingView {
+        step: ob.step(),
+        cards,
+        player,
+        dealer,
+        cell,
+        answered,
+        total,
+        drill: ob.recommendation().map(StartingDrill::new),
+    })
+    .unwrap()
+}
+
+/// The player plays by the rules of the named strategy card. Returns why it can't be picked, or
+/// an empty string.
+#[wasm_bindgen]
+pub fn onboarding_pick_rules(card: String) -> String {
+    if bs_data::bs_card(&card).is_none() {
+        return format!("There is no strategy card named '{}'", card);
+    }
+    let mut ob = load().unwrap_or_default();
+    match ob.pick_rules(&card) {
+        Ok(()) => {
+            save(&ob);
+            String::new()
+        }
+        Err(e) => e.to_string(),
+    }
+}
+
+/// Answer the current placement hand with the named button (e.g. "hit"). Returns whether the
+/// answer was correct, or nothing if there's no such button or no hand to answer.
+#[wasm_bindgen]
+pub fn onboarding_answer(button: String) -> Option<bool> {
+    let btn = GameButton::ALL
+        .iter()
+        .copied()
+        .find(|b| b.to_string().eq_ignore_ascii_case(&button))?;
+    let mut ob = load()?;
+    let bs = ob
+        .card()
+        .and_then(bs_data::bs_card)
+        .unwrap_or_else(|| bs_data::def_bs_card());
+    let correct = ob.answer(bs, btn).ok()?;
+    save(&ob);
+    Some(correct)
+}
+
+/// Finish onboarding: save the picked card and, for beginners, hit or stand mode in the prefs.
+/// Returns the given stats (a string from play_stats_from_state(), or empty if the player has
+/// none) with the placement quiz added, or an empty string if onboarding isn't at its last step.
+#[wasm_bindgen]
+pub fn onboarding_finish(play_stats_s: String) -> String {
+    let mut ob = match load() {
+        Some(ob) => ob,
+        None => return String::new(),
+    };
+    if ob.finish().is_err() {
+        return String::new();
+    }
+    let mut prefs = Prefs::load();
+    prefs.bs_card = ob.card().map(String::from);
+    if ob.recommendation() == Some(Recommendation::HitStand) {
+        prefs.hit_stand.enabled = true;
+    }
+    prefs.save();
+    save(&ob);
+    let mut stats = playstats_table::parse_from_string(play_stats_s)
+        .unwrap_or_else(|_| Table::filled(PlayStats::new()));
+    ob.seed(&mut stats);
+    playstats_table::parse_to_string(&stats)
+}
+
+/// The player doesn't want onboarding. It won't be offered again.
+#[wasm_bindgen]
+pub fn onboarding_skip() {
+    let mut ob = load().unwrap_or_default();
+    ob.skip();
+    save(&ob);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn drills_are_bundled() {
+        let packs: Vec<_> = crate::packs::pack_names().collect();
+        for hand in &[HandType::Hard, HandType::Soft, HandType::Pair] {
+            if let Some(pack) = StartingDrill::new(Recommendation::Drill(*hand)).pack {
+                assert!(packs.contains(&pack), "{}", pack);
+            }
+        }
+        assert_eq!(StartingDrill::new(Recommendation::HitStand).pack, None);
+    }
+}
@@ -0,0 +1,40 @@
+//! Drill packs: the ones bundled in bj-data, and ones players bring as JSON.
+use bj_core::drillpack::DrillPack;
+use bj_data::{DataError, Kind, DATASETS};
+
+/// The names of all bundled drill packs
+pub fn pack_names() -> impl Iterator<Item = &'static str> {
+    DATASETS
+        .iter()
+        .filter(|d| d.kind == Kind::DrillPack)
+        .map(|d| d.name)
+}
+
+/// The bundled pack with the given name, or else the pack the string holds as JSON. Either way
+/// the pack is checked to be valid.
+pub fn load_pack(name_or_json: &str) -> Result<DrillPack, DataError> {
+    if let Some(d) = bj_data::dataset(name_or_json) {
+        return d.drill_pack();
+    }
+    let pack: DrillPack = serde_json::from_str(name_or_json)?;
+    pack.validate().map_err(DataError::BadPack)?;
+    Ok(pack)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn load() {
+        for name in pack_names() {
+            assert!(load_pack(name).is_ok(), "{}", name);
+        }
+        assert!(load_pack("t1").is_err());
+        let json = r#"{"name": "Eights", "hands": {"exact": ["8,8 vs T", "8,8 vs A"]}}"#;
+        assert_eq!(load_pack(json).unwrap().scoring.hands, 20);
+        let json = r#"{"name": "Eights", "hands": {"exact": ["8,8 vs 1"]}}"#;
+        assert!(matches!(load_pack(json), Err(DataError::BadPack(_))));
+        assert!(matches!(load_pack("nope"), Err(DataError::Json(_))));
+    }
+}
@@ -0,0 +1,67 @@
+//! Practice time across every trainer, kept per day in localStorage. See bj_core::practice.
+//!
+//! Trainers call shown() when they put something up to answer from a standing start (e.g. on
+//! init), and answered() every time the player answers. Practice time is always kept in
+//! localStorage (never sessionStorage) so it adds up over sessions, and days are the player's
+//! local calendar days.
+use crate::localstorage::{ls_get, ls_set, lskeys};
+use bj_core::practice::{PracticeLog, PracticeTime, PracticeTimer};
+use lazy_static::lazy_static;
+use std::sync::Mutex;
+use wasm_bindgen::prelude::*;
+
+lazy_static! {
+    static ref TIMER: Mutex<PracticeTimer> = Mutex::new(PracticeTimer::new());
+}
+
+/// Seconds, from the browser's clock
+fn now() -> f64 {
+    js_sys::Date::now() / 1000.0
+}
+
+/// The local day, e.g. "2026-03-14"
+fn today() -> String {
+    let d = js_sys::Date::new_0();
+    format!(
+        "{:04}-{:02}-{:02}",
+        d.get_full_year(),
+        d.get_month() + 1,
+        d.get_date()
+    )
+}
+
+fn load() -> PracticeLog {
+    ls_get(false, lskeys::LS_KEY_PRACTICE).unwrap_or_default()
+}
+
+/// Something to answer was just shown
+pub fn shown() {
+    TIMER.lock().unwrap().shown(now());
+}
+
+/// The player just answered: add the time it took to today's practice
+pub fn answered() {
+    if let Some(secs) = TIMER.lock().unwrap().answered(now()) {
+        let mut log = load();
+        log.record(&today(), secs);
+        ls_set(false, lskeys::LS_KEY_PRACTICE, &log);
+    }
+}
+
+/// Seconds practiced today
+#[wasm_bindgen]
+pub fn practice_time_today() -> f64 {
+    load().on(&today())
+}
+
+/// Seconds practiced ever
+#[wasm_bindgen]
+pub fn practice_time_total() -> f64 {
+    load().total()
+}
+
+/// e.g. "23 minutes practiced today"
+#[wasm_bindgen]
+pub fn practice_time_summary() -> String {
+    format!("{} practiced today", PracticeTime(practice_time_today()))
+}
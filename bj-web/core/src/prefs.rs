@@ -0,0 +1,504 @@
+//! Preferences shared by all the trainers, so a single settings page can configure them all.
+//!
+//! Prefs are always kept in localStorage (never sessionStorage), as they decide where everything
+//! else is kept. They are stored as JSON with a version number. Fields missing from stored prefs
+//! take their default value, so adding a field doesn't need a new version; renaming or changing
+//! the meaning of one does, along with a step in Prefs::migrate().
+use crate::localstorage::{ls_get, ls_set, lskeys};
+use bj_core::betramp::BetRamp;
+use bj_core::button::GameButton;
+use bj_core::demo::DEFAULT_PACE_MS;
+use bj_core::mastery::MasteryGoals;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use thiserror::Error;
+use wasm_bindgen::prelude::*;
+
+/// The version of Prefs this code writes
+pub const PREFS_VERSION: u32 = 1;
+
+#[derive(Debug, PartialEq, Error)]
+pub enum PrefsError {
+    /// The prefs aren't valid JSON, or a field has a bad value
+    #[error("Bad prefs: {0}")]
+    Json(String),
+    /// The prefs were written by a newer version of this code
+    #[error(
+        "Prefs are version {0}, but only up to {} is understood",
+        PREFS_VERSION
+    )]
+    TooNew(u32),
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Theme {
+    /// Follow the browser's preference
+    Auto,
+    Light,
+    Dark,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum StoragePolicy {
+    /// Forget hands and stats when the tab is closed
+    Session,
+    /// Keep hands and stats until the browser's storage is cleared
+    Local,
+}
+
+/// How feedback on the player's answers is given. Flashing messages are a problem for
+/// photosensitive players.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum FeedbackMode {
+    /// Flash the message
+    Flash,
+    /// Show the message without any animation
+    Static,
+    /// Show nothing, and leave it to the sounds the page plays for game events
+    SoundOnly,
+}
+
+impl fmt::Display for FeedbackMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::Flash => "flash",
+                Self::Static => "static",
+                Self::SoundOnly => "sound_only",
+            }
+        )
+    }
+}
+
+impl StoragePolicy {
+    /// The is_session argument that the localstorage functions take
+    pub fn is_session(self) -> bool {
+        self == Self::Session
+    }
+}
+
+/// The key that presses each button. Keys are compared case-insensitively.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct KeyBindings {
+    pub hit: char,
+    pub stand: char,
+    pub double: char,
+    pub split: char,
+    pub surrender: char,
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        Self {
+            hit: 'h',
+            stand: 's',
+            double: 'd',
+            split: 'p',
+            surrender: 'r',
+        }
+    }
+}
+
+impl KeyBindings {
+    /// The name of the button bound to the given key, if any
+    pub fn action(&self, key: char) -> Option<&'static str> {
+        let key = key.to_ascii_lowercase();
+        [
+            (self.hit, "hit"),
+            (self.stand, "stand"),
+            (self.double, "double"),
+            (self.split, "split"),
+            (self.surrender, "surrender"),
+        ]
+        .iter()
+        .find(|(k, _)| k.to_ascii_lowercase() == key)
+        .map(|(_, name)| *name)
+    }
+}
+
+/// The order the game buttons are shown in, and which are never shown. Buttons are named as in
+/// KeyBindings, e.g. "hit".
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct ButtonLayout {
+    /// Left to right, e.g. with hit last for left-handed players. Buttons left out go after the
+    /// rest, in their usual order.
+    pub order: Vec<GameButton>,
+    /// Buttons never shown, e.g. surrender when studying a game without it. Hands where the card
+    /// calls for a hidden button can't be answered correctly, so only hide buttons the card
+    /// doesn't use.
+    pub hidden: Vec<GameButton>,
+}
+
+impl Default for ButtonLayout {
+    fn default() -> Self {
+        Self {
+            order: GameButton::ALL.to_vec(),
+            hidden: vec![],
+        }
+    }
+}
+
+impl ButtonLayout {
+    /// Every button once, left to right
+    pub fn order(&self) -> Vec<GameButton> {
+        let mut order: Vec<GameButton> = vec![];
+        for btn in self.order.iter().chain(GameButton::ALL.iter()) {
+            if !order.contains(btn) {
+                order.push(*btn);
+            }
+        }
+        order
+    }
+
+    pub fn is_hidden(&self, btn: GameButton) -> bool {
+        self.hidden.contains(&btn)
+    }
+}
+
+/// The options for the counting trainer, which are the arguments to its rust_init()
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct CountingPrefs {
+    pub num_decks: u8,
+    pub num_cards: u16,
+    pub cards_at_a_time: u8,
+    /// Deal from a continuous shuffle machine instead of a shoe
+    pub csm: bool,
+    /// What to bet at each true count
+    pub bet_ramp: BetRamp,
+}
+
+impl Default for CountingPrefs {
+    fn default() -> Self {
+        Self {
+            num_decks: 1,
+            num_cards: 52,
+            cards_at_a_time: 1,
+            csm: false,
+            bet_ramp: BetRamp::default(),
+        }
+    }
+}
+
+/// Beginner mode for the basic strategy trainers: only hit and stand
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct HitStandPrefs {
+    pub enabled: bool,
+    /// Whether pairs are dealt
+    pub pairs: bool,
+    /// Whether soft hands are dealt
+    pub softs: bool,
+}
+
+impl Default for HitStandPrefs {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            pairs: true,
+            softs: true,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct Prefs {
+    pub version: u32,
+    pub theme: Theme,
+    /// Where trainers keep their current hand, stats, etc.
+    pub storage: StoragePolicy,
+    pub keys: KeyBindings,
+    pub buttons: ButtonLayout,
+    /// The name of a built-in card to train with (see bs_data::bs_card_names()), or None to use
+    /// the card last saved by the custom card page
+    pub bs_card: Option<String>,
+    pub sound: bool,
+    /// How the trainers give feedback on answers (see the feedback module)
+    pub feedback: FeedbackMode,
+    /// Whether the basic strategy trainer earns and uses streak shields. Time trials never do.
+    pub streak_shields: bool,
+    /// Whether the basic strategy trainer drills rows of the card the player keeps missing
+    pub coach: bool,
+    /// Whether the basic strategy trainer asks about even money on blackjacks against an ace
+    pub even_money: bool,
+    /// Whether the basic strategy trainer deals hands that come up often in real play more often
+    pub frequency_weighting: bool,
+    /// If set, the basic strategy trainer deals hands not seen in this many days as if they were
+    /// new, however well the player did on them back then
+    pub refresh_days: Option<u32>,
+    /// Whether the basic strategy trainer plays out the hands made by a correct split
+    pub play_splits: bool,
+    /// Whether the basic strategy trainer sometimes deals hard and soft hands as three or four
+    /// cards, which can't be doubled or surrendered
+    pub multi_card: bool,
+    /// Whether the basic strategy trainer's card hides the cells the player has mastered
+    pub memorize: bool,
+    /// How long the basic strategy trainer's demo shows each step for, in milliseconds
+    pub demo_pace_ms: u32,
+    pub counting: CountingPrefs,
+    pub hit_stand: HitStandPrefs,
+    /// What it takes to master cells of each table of the card
+    pub mastery: MasteryGoals,
+}
+
+impl Default for Prefs {
+    fn default() -> Self {
+        Self {
+            version: PREFS_VERSION,
+            theme: Theme::Auto,
+            storage: StoragePolicy::Session,
+            keys: KeyBindings::default(),
+            buttons: ButtonLayout::default(),
+            bs_card: None,
+            sound: false,
+            feedback: FeedbackMode::Flash,
+            streak_shields: false,
+            coach: false,
+            even_money: false,
+            frequency_weighting: false,
+            refresh_days: None,
+            play_splits: false,
+            multi_card: false,
+            memorize: false,
+            demo_pace_ms: DEFAULT_PACE_MS,
+            counting: CountingPrefs::default(),
+            hit_stand: HitStandPrefs::default(),
+            mastery: MasteryGoals::default(),
+        }
+    }
+}
+
+impl Prefs {
+    /// Parse prefs of any version up to PREFS_VERSION, upgrading them to PREFS_VERSION
+    pub fn from_json(s: &str) -> Result<Self, PrefsError> {
+        Self::from_value(serde_json::from_str(s).map_err(|e| PrefsError::Json(e.to_string()))?)
+    }
+
+    fn from_value(mut v: serde_json::Value) -> Result<Self, PrefsError> {
+        // Prefs from before versioning have no version field
+        let version = v.get("version").and_then(|v| v.as_u64()).unwrap_or(0) as u32;
+        if version > PREFS_VERSION {
+            return Err(PrefsError::TooNew(version));
+        }
+        Self::migrate(&mut v, version);
+        let mut prefs: Self =
+            serde_json::from_value(v).map_err(|e| PrefsError::Json(e.to_string()))?;
+        prefs.version = PREFS_VERSION;
+        Ok(prefs)
+    }
+
+    /// Upgrade the given prefs from the given version to PREFS_VERSION in place
+    fn migrate(v: &mut serde_json::Value, from: u32) {
+        if from < 1 {
+            // Version 0 was the counting trainer's blob, which only had counting options and kept
+            // them at the top level
+            if let Some(obj) = v.as_object_mut() {
+                let counting: serde_json::Map<_, _> = obj
+                    .iter()
+                    .filter(|(k, _)| {
+                        ["num_decks", "num_cards", "cards_at_a_time"].contains(&k.as_str())
+                    })
+                    .map(|(k, v)| (k.clone(), v.clone()))
+                    .collect();
+                obj.insert("counting".to_string(), counting.into());
+            }
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap()
+    }
+
+    /// Read the prefs from localStorage. If there are none, start from the counting trainer's old
+    /// prefs. If those are missing or broken too, use the defaults.
+    pub fn load() -> Self {
+        if let Some(v) = ls_get(false, lskeys::LS_KEY_PREFS) {
+            if let Ok(prefs) = Self::from_value(v) {
+                return prefs;
+            }
+        }
+        ls_get::<String>(false, lskeys::LS_KEY_COUNTING_PREFS)
+            .and_then(|s| Self::from_json(&s).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self) {
+        ls_set(false, lskeys::LS_KEY_PREFS, self);
+    }
+}
+
+/// All prefs as JSON
+#[wasm_bindgen]
+pub fn get_prefs() -> String {
+    Prefs::load().to_json()
+}
+
+/// Replace all prefs with the given JSON. Returns false, leaving the stored prefs alone, if the
+/// JSON isn't valid prefs.
+#[wasm_bindgen]
+pub fn set_prefs(s: String) -> bool {
+    match Prefs::from_json(&s) {
+        Ok(prefs) => {
+            prefs.save();
+            true
+        }
+        Err(_) => false,
+    }
+}
+
+/// Reset all prefs to their defaults
+#[wasm_bindgen]
+pub fn reset_prefs() {
+    Prefs::default().save();
+}
+
+/// Set a single pref, given its name (as in the JSON, with nested prefs separated by dots, e.g.
+/// "keys.hit") and its new value as JSON (e.g. "\"dark\"" or "true"). Returns false, leaving the
+/// stored prefs alone, if there is no such pref or the value is bad.
+#[wasm_bindgen]
+pub fn set_pref(name: &str, value: &str) -> bool {
+    let value: serde_json::Value = match serde_json::from_str(value) {
+        Ok(v) => v,
+        Err(_) => return false,
+    };
+    let mut prefs = serde_json::to_value(Prefs::load()).unwrap();
+    match prefs.pointer_mut(&format!("/{}", name.replace('.', "/"))) {
+        Some(field) if name != "version" => *field = value,
+        _ => return false,
+    }
+    match serde_json::from_value::<Prefs>(prefs) {
+        Ok(prefs) => {
+            prefs.save();
+            true
+        }
+        Err(_) => false,
+    }
+}
+
+/// Get a single pref as JSON, given its name as in set_pref(), or undefined if there is no such
+/// pref
+#[wasm_bindgen]
+pub fn get_pref(name: &str) -> Option<String> {
+    let prefs = serde_json::to_value(Prefs::load()).unwrap();
+    prefs
+        .pointer(&format!("/{}", name.replace('.', "/")))
+        .map(|v| v.to_string())
+}
+
+/// The name of the button (hit, stand, double, split, surrender) bound to the given key, or
+/// undefined if none or the button is hidden (see ButtonLayout)
+#[wasm_bindgen]
+pub fn action_for_key(key: &str) -> Option<String> {
+    let mut chars = key.chars();
+    let prefs = Prefs::load();
+    let action = match (chars.next(), chars.next()) {
+        (Some(c), None) => prefs.keys.action(c)?,
+        _ => return None,
+    };
+    let hidden = prefs
+        .buttons
+        .hidden
+        .iter()
+        .any(|b| b.to_string().eq_ignore_ascii_case(action));
+    if hidden {
+        None
+    } else {
+        Some(action.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn roundtrip() {
+        let prefs = Prefs {
+            theme: Theme::Dark,
+            keys: KeyBindings {
+                hit: 'j',
+                ..Default::default()
+            },
+            bs_card: Some("t1".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(Prefs::from_json(&prefs.to_json()).unwrap(), prefs);
+    }
+
+    #[test]
+    fn missing_fields_default() {
+        let prefs = Prefs::from_json(r#"{"version": 1, "sound": true}"#).unwrap();
+        assert_eq!(
+            prefs,
+            Prefs {
+                sound: true,
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn from_counting_blob() {
+        let prefs = Prefs::from_json(r#"{"num_decks": 6, "cards_at_a_time": 2}"#).unwrap();
+        assert_eq!(prefs.version, PREFS_VERSION);
+        assert_eq!(
+            prefs.counting,
+            CountingPrefs {
+                num_decks: 6,
+                cards_at_a_time: 2,
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn bad_prefs() {
+        assert_eq!(
+            Prefs::from_json(r#"{"version": 99}"#),
+            Err(PrefsError::TooNew(99))
+        );
+        assert!(matches!(
+            Prefs::from_json(r#"{"version": 1, "theme": "purple"}"#),
+            Err(PrefsError::Json(_))
+        ));
+        assert!(matches!(Prefs::from_json("{"), Err(PrefsError::Json(_))));
+    }
+
+    #[test]
+    fn key_actions() {
+        let keys = KeyBindings::default();
+        assert_eq!(keys.action('H'), Some("hit"));
+        assert_eq!(keys.action('p'), Some("split"));
+        assert_eq!(keys.action('x'), None);
+    }
+
+    #[test]
+    fn button_layout() {
+        let prefs = Prefs::from_json(
+            r#"{"buttons": {"order": ["stand", "stand", "hit"], "hidden": ["surrender"]}}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            prefs.buttons.order(),
+            vec![
+                GameButton::Stand,
+                GameButton::Hit,
+                GameButton::Double,
+                GameButton::Split,
+                GameButton::Surrender
+            ]
+        );
+        assert!(prefs.buttons.is_hidden(GameButton::Surrender));
+        assert!(!prefs.buttons.is_hidden(GameButton::Hit));
+        assert_eq!(ButtonLayout::default().order(), GameButton::ALL.to_vec());
+        assert!(Prefs::from_json(r#"{"buttons": {"hidden": ["fold"]}}"#).is_err());
+    }
+}
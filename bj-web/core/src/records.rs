@@ -0,0 +1,150 @@
+//! Personal best time trials, one per trial configuration.
+//!
+//! Records are always kept in localStorage (never sessionStorage) so they survive between
+//! sessions.
+use crate::localstorage::{ls_get, ls_set, lskeys};
+use serde::{Deserialize, Serialize};
+
+/// The trial configuration a record is for. Records for different configurations aren't
+/// comparable.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct RecordKey {
+    pub num_hands: usize,
+    /// How hands are chosen, e.g. "uniform"
+    pub pool: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct TrialRecord {
+    pub num_correct: usize,
+    /// Seconds, not counting time spent paused
+    pub duration: f64,
+    /// When the trial ended, in ms since the epoch
+    pub date: f64,
+}
+
+impl TrialRecord {
+    /// Whether this record beats the other: more correct hands wins, and the faster trial breaks
+    /// ties
+    pub fn beats(&self, other: &Self) -> bool {
+        self.num_correct > other.num_correct
+            || (self.num_correct == other.num_correct && self.duration < other.duration)
+    }
+}
+
+/// Why a finished trial may not be eligible for a record
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Eligibility {
+    /// The player gave up before playing every hand
+    pub aborted: bool,
+    /// The player looked at the strategy card, or something else that helped
+    pub assisted: bool,
+    /// The trial was paused, during which the player could have studied
+    pub paused: bool,
+}
+
+impl Eligibility {
+    pub fn is_eligible(self) -> bool {
+        !self.aborted && !self.assisted && !self.paused
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct Records(Vec<(RecordKey, TrialRecord)>);
+
+impl Records {
+    pub fn get(&self, key: &RecordKey) -> Option<&TrialRecord> {
+        self.0.iter().find(|(k, _)| k == key).map(|(_, r)| r)
+    }
+
+    /// Consider the given trial for a record. Returns true if it is eligible and is a new
+    /// personal best, in which case it has been stored.
+    pub fn submit(&mut self, key: RecordKey, record: TrialRecord, elig: Eligibility) -> bool {
+        if !elig.is_eligible() {
+            return false;
+        }
+        match self.0.iter_mut().find(|(k, _)| *k == key) {
+            Some((_, best)) if record.beats(best) => {
+                *best = record;
+                true
+            }
+            Some(_) => false,
+            None => {
+                self.0.push((key, record));
+                true
+            }
+        }
+    }
+
+    pub fn load() -> Self {
+        ls_get(false, lskeys::LS_KEY_RECORDS).unwrap_or_default()
+    }
+
+    pub fn save(&self) {
+        ls_set(false, lskeys::LS_KEY_RECORDS, self);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ELIGIBLE: Eligibility = Eligibility {
+        aborted: false,
+        assisted: false,
+        paused: false,
+    };
+
+    fn key(num_hands: usize) -> RecordKey {
+        RecordKey {
+            num_hands,
+            pool: "uniform".to_string(),
+        }
+    }
+
+    fn rec(num_correct: usize, duration: f64) -> TrialRecord {
+        TrialRecord {
+            num_correct,
+            duration,
+            date: 0.0,
+        }
+    }
+
+    #[test]
+    fn personal_bests() {
+        let mut r = Records::default();
+        assert!(r.submit(key(10), rec(8, 30.0), ELIGIBLE));
+        // worse, then tied but slower
+        assert!(!r.submit(key(10), rec(7, 10.0), ELIGIBLE));
+        assert!(!r.submit(key(10), rec(8, 31.0), ELIGIBLE));
+        // faster
+        assert!(r.submit(key(10), rec(8, 29.0), ELIGIBLE));
+        // separate configuration
+        assert!(r.submit(key(20), rec(1, 99.0), ELIGIBLE));
+        assert_eq!(r.get(&key(10)), Some(&rec(8, 29.0)));
+        assert_eq!(r.get(&key(20)), Some(&rec(1, 99.0)));
+        assert_eq!(r.get(&key(30)), None);
+    }
+
+    #[test]
+    fn ineligible() {
+        let mut r = Records::default();
+        for elig in &[
+            Eligibility {
+                aborted: true,
+                ..ELIGIBLE
+            },
+            Eligibility {
+                assisted: true,
+                ..ELIGIBLE
+            },
+            Eligibility {
+                paused: true,
+                ..ELIGIBLE
+            },
+        ] {
+            assert!(!r.submit(key(10), rec(10, 1.0), *elig));
+        }
+        assert_eq!(r, Records::default());
+    }
+}
@@ -0,0 +1,167 @@
+//! Keeping a trainer open in several tabs from corrupting its own saved state.
+//!
+//! Each tab holds its own State in memory and writes parts of it to localStorage as it goes, so
+//! two tabs answering hands take turns overwriting each other's streak and stats. Two things keep
+//! them consistent:
+//!
+//! 1. The tab lock. Only the tab holding it records answers. A page claims it with
+//!    claim_tab_lock() before grading each answer, which also keeps it held. A tab that goes
+//!    TAB_LOCK_STALE_SECS without answering loses it to the next tab that asks. If the claim is
+//!    Claim::Refused, the page should tell the player the trainer is open elsewhere instead of
+//!    grading. If it's Claim::TakenOver, another tab may have saved since this one last looked,
+//!    and anything it keeps in memory should be reloaded before going on. Reload tracks that
+//!    until the page says it's done, so answers aren't graded against what's in memory meanwhile.
+//! 2. Storage events. listen_for_storage_changes() calls back with the key whenever another tab
+//!    changes localStorage, so pages can reload notes, prefs, and the like as they change.
+//!
+//! sessionStorage isn't shared between tabs, so pages keeping their state there need neither.
+use crate::localstorage::{ls_get, ls_set, lskeys};
+use serde::{Deserialize, Serialize};
+use std::sync::Once;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+
+/// How long, in seconds, a tab keeps the lock without answering
+pub const TAB_LOCK_STALE_SECS: f64 = 30.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct TabLock {
+    /// The tab holding the lock, see new_tab_id()
+    pub tab: u64,
+    /// When the holder last claimed it, in seconds since the epoch
+    pub claimed: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Claim {
+    /// The tab already held the lock, or nobody did
+    Held,
+    /// Another tab held the lock, but let it go stale
+    TakenOver,
+    /// Another tab holds the lock
+    Refused(TabLock),
+}
+
+impl Claim {
+    /// Whether the tab holds the lock now
+    pub fn is_held(self) -> bool {
+        !matches!(self, Self::Refused(_))
+    }
+}
+
+/// Whether what a tab keeps in memory, like the player's stats and streak, needs reloading before
+/// it can grade answers again
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Reload {
+    pending: bool,
+}
+
+impl Reload {
+    /// Note the claim the tab just made. Taking over means another tab may have saved since this
+    /// one last looked, so a reload is pending until loaded() is called.
+    pub fn claimed(&mut self, claim: Claim) {
+        if claim == Claim::TakenOver {
+            self.pending = true;
+        }
+    }
+
+    /// Another tab saved over what this one keeps in memory
+    pub fn changed(&mut self) {
+        self.pending = true;
+    }
+
+    /// The page reloaded what it keeps in memory
+    pub fn loaded(&mut self) {
+        self.pending = false;
+    }
+
+    pub fn is_pending(self) -> bool {
+        self.pending
+    }
+}
+
+/// What happens when tab tries to claim the lock at time now, if current is the lock as stored
+pub fn claim(current: Option<TabLock>, tab: u64, now: f64) -> Claim {
+    match current {
+        None => Claim::Held,
+        Some(lock) if lock.tab == tab => Claim::Held,
+        Some(lock) if now - lock.claimed >= TAB_LOCK_STALE_SECS => Claim::TakenOver,
+        Some(lock) => Claim::Refused(lock),
+    }
+}
+
+/// A random ID for this tab, to claim the lock with
+pub fn new_tab_id() -> u64 {
+    rand::random()
+}
+
+/// Claim the tab lock in localStorage for the given tab, and keep it if it's held. See the module
+/// docs.
+pub fn claim_tab_lock(tab: u64) -> Claim {
+    let now = js_sys::Date::now() / 1000.0;
+    let c = claim(ls_get(false, lskeys::LS_KEY_TAB_LOCK), tab, now);
+    if c.is_held() {
+        ls_set(
+            false,
+            lskeys::LS_KEY_TAB_LOCK,
+            &TabLock { tab, claimed: now },
+        );
+    }
+    c
+}
+
+static LISTEN: Once = Once::new();
+
+/// Call on_change with the key of every localStorage value another tab changes. Only the first
+/// call does anything, so pages can call it whenever they (re)initialize.
+pub fn listen_for_storage_changes(on_change: fn(&str)) {
+    LISTEN.call_once(|| {
+        let closure = Closure::wrap(Box::new(move |e: web_sys::StorageEvent| {
+            // a null key means the whole storage was cleared
+            on_change(&e.key().unwrap_or_default());
+        }) as Box<dyn FnMut(_)>);
+        web_sys::window()
+            .expect("should have a window in this context")
+            .add_event_listener_with_callback("storage", closure.as_ref().unchecked_ref())
+            .expect("Unable to listen for storage events");
+        // the listener lives as long as the page
+        closure.forget();
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn claims() {
+        let lock = TabLock {
+            tab: 1,
+            claimed: 100.0,
+        };
+        assert_eq!(claim(None, 2, 100.0), Claim::Held);
+        assert_eq!(claim(Some(lock), 1, 1000.0), Claim::Held);
+        assert_eq!(claim(Some(lock), 2, 110.0), Claim::Refused(lock));
+        assert!(!claim(Some(lock), 2, 110.0).is_held());
+        assert_eq!(
+            claim(Some(lock), 2, 100.0 + TAB_LOCK_STALE_SECS),
+            Claim::TakenOver
+        );
+    }
+
+    #[test]
+    fn reloads() {
+        let mut r = Reload::default();
+        r.claimed(Claim::Held);
+        assert!(!r.is_pending());
+        r.claimed(Claim::TakenOver);
+        assert!(r.is_pending());
+        // holding the lock again doesn't make the tab's stats any fresher
+        r.claimed(Claim::Held);
+        assert!(r.is_pending());
+        r.loaded();
+        assert!(!r.is_pending());
+        r.changed();
+        assert!(r.is_pending());
+    }
+}
@@ -0,0 +1,126 @@
+//! Putting a trainer on the page: the DOM helpers every trainer needs, written once.
+//!
+//! Pages have elements with the same ids for the same things (e.g. button_hit, strat_html,
+//! num_streak), so what's shown and how only has to change here. Elements are hidden with the
+//! "hide" class. Everything panics if the element isn't on the page, as that's a broken page, not
+//! something to carry on from.
+use crate::prefs::{ButtonLayout, Prefs};
+use bj_core::basicstrategy::BasicStrategy;
+use bj_core::button::GameButton;
+use bj_core::confidence::{Percent, Z95};
+use bj_core::deck::Card;
+use bj_core::hand::Hand;
+use bj_core::playstats::PlayStats;
+use bj_core::rendertable::{HTMLTableRenderer, HTMLTableRendererOpts};
+use bj_core::table::Table;
+use bj_core::trainer::Trainer;
+use wasm_bindgen::JsCast;
+use web_sys::HtmlElement;
+
+/// The id of each of the game's buttons
+pub const GAME_BUTTONS: [(&str, GameButton); 5] = [
+    ("button_hit", GameButton::Hit),
+    ("button_stand", GameButton::Stand),
+    ("button_double", GameButton::Double),
+    ("button_split", GameButton::Split),
+    ("button_surrender", GameButton::Surrender),
+];
+
+/// The element with the given id
+pub fn element(id: &str) -> HtmlElement {
+    let win = web_sys::window().expect("should have a window in this context");
+    let doc = win.document().expect("window should have a document");
+    doc.get_element_by_id(id)
+        .unwrap_or_else(|| panic!("should exist {}", id))
+        .dyn_into::<HtmlElement>()
+        .unwrap_or_else(|_| panic!("{} should be HtmlElement", id))
+}
+
+pub fn set_text(id: &str, s: &str) {
+    element(id).set_inner_text(s);
+}
+
+pub fn set_html(id: &str, s: &str) {
+    element(id).set_inner_html(s);
+}
+
+/// Show or hide the element
+pub fn set_shown(id: &str, shown: bool) {
+    let class_list = element(id).class_list();
+    if shown {
+        class_list
+            .remove_1("hide")
+            .expect("Unable to remove hide class");
+    } else {
+        class_list.add_1("hide").expect("Unable to add hide class");
+    }
+}
+
+/// The id of the given game button
+fn game_button_id(btn: GameButton) -> &'static str {
+    GAME_BUTTONS.iter().find(|(_, b)| *b == btn).unwrap().0
+}
+
+/// Put the game buttons in the order the layout says, at the end of the element that holds them
+fn order_game_buttons(layout: &ButtonLayout) {
+    let parent = match element(GAME_BUTTONS[0].0).parent_node() {
+        Some(parent) => parent,
+        None => return,
+    };
+    for btn in layout.order() {
+        parent
+            .append_child(&element(game_button_id(btn)))
+            .expect("Unable to move button");
+    }
+}
+
+/// Show the buttons that are legal for the trainer's hand and not hidden by the player's button
+/// layout, in the layout's order, and hide the rest
+pub fn update_game_buttons(trainer: &Trainer) {
+    let layout = Prefs::load().buttons;
+    order_game_buttons(&layout);
+    for (id, btn) in GAME_BUTTONS.iter() {
+        set_shown(id, trainer.is_legal(*btn) && !layout.is_hidden(*btn));
+    }
+}
+
+pub fn hide_game_buttons() {
+    for (id, _) in GAME_BUTTONS.iter() {
+        set_shown(id, false);
+    }
+}
+
+/// Show the strategy card as an HTML table in the element with the given id
+pub fn output_resp_table(id: &str, bs: &BasicStrategy, opts: HTMLTableRendererOpts) {
+    let mut fd: Vec<u8> = vec![];
+    HTMLTableRenderer::render(&mut fd, bs, opts).unwrap();
+    set_html(id, &String::from_utf8(fd).unwrap());
+}
+
+/// The stats to show, see output_stats()
+pub struct StatsView<'a> {
+    /// The hand on the screen, whose own stats are shown next to the totals
+    pub hand: (&'a Hand, Card),
+    pub stats: &'a Table<PlayStats>,
+    pub streak: u32,
+}
+
+/// Show the player's totals, accuracy, and streak, and how they've done on the current hand
+pub fn output_stats(view: StatsView) {
+    let total = view
+        .stats
+        .values()
+        .fold(PlayStats::new(), |acc, stat| acc + *stat);
+    set_text("num_correct", &total.correct().to_string());
+    set_text("num_seen", &total.seen().to_string());
+    // e.g. "92% ± 4%", so a good run of 25 hands doesn't look like mastery
+    let accuracy = total
+        .accuracy(Z95)
+        .map(|acc| Percent(acc).to_string())
+        .unwrap_or_default();
+    set_text("accuracy", &accuracy);
+    set_text("num_streak", &view.streak.to_string());
+    let stat = view.stats.get(view.hand.0, view.hand.1).unwrap();
+    set_text("hand_num_correct", &stat.correct().to_string());
+    set_text("hand_num_seen", &stat.seen().to_string());
+}
@@ -1,24 +1,42 @@
-use bj_core::count::{StatefulHiLo, DECK_LEN};
+use bj_core::betramp::BetRamp;
+use bj_core::count::{AnySystem, StatefulCount, DECK_LEN};
 use bj_core::deck::{Card, Deck};
+use bj_core::holecard::takes_insurance;
+use bj_core::kelly::{kelly_bet, Advantage};
+use bj_core::pairdrill::{PairDrill, PairMix, PairNet};
+use bj_core::proto::{Body, CountingResults, Upload};
+use bj_data::{active_index_plays, index_play_summary, INSURANCE_INDEX};
+use bj_web_core::anonid;
+use bj_web_core::bs_data;
 use bj_web_core::card_char;
-use bj_web_core::localstorage::{lskeys, LSVal};
+use bj_web_core::crash;
+use bj_web_core::events::{emit, GameEvent};
+use bj_web_core::init::{CountingOptions, TrainerOptions};
+use bj_web_core::localstorage::{ls_get, ls_set, lskeys};
+use bj_web_core::logging;
+use bj_web_core::practice;
+use bj_web_core::prefs::Prefs;
+use bj_web_core::view::set_text;
 use js_sys::Date;
 use std::sync::Mutex;
 use wasm_bindgen::prelude::*;
-use wasm_bindgen::JsCast;
-use web_sys::HtmlElement;
 #[macro_use]
 extern crate lazy_static;
 
 struct State {
     deck: Deck,
-    count: StatefulHiLo,
+    count: StatefulCount<AnySystem>,
+    /// num decks the cards are drawn from
+    num_decks: u8,
     /// num cards the user asked to see
     total_cards: u16,
     /// num cards the user has seen so far
     seen_cards: u16,
     /// num cards to show at the user at once
     cards_at_a_time: u8,
+    /// whether the cards go back in the deck after every group of cards_at_a_time, as with a
+    /// continuous shuffle machine
+    csm: bool,
     /// timestamp (in s, not ms) of when we present the first card
     start_time: f64,
     /// timestamp (in s, not ms) of when we first get asked for another card, but there are no more
@@ -32,13 +50,17 @@ impl Default for State {
             // reset in rust_init()
             deck: Deck::with_length(1),
             // reset in rust_init()
-            count: StatefulHiLo::new(1),
+            count: StatefulCount::new(1),
+            // reset in rust_init()
+            num_decks: 1,
             // reset in rust_init()
             total_cards: DECK_LEN,
             // reset in rust_init()
             seen_cards: 0,
             // reset in rust_init()
             cards_at_a_time: 1,
+            // reset in rust_init()
+            csm: false,
             // updated when we show first card
             start_time: 0.0,
             // updated when we cannot show any more cards
@@ -49,17 +71,16 @@ impl Default for State {
 
 lazy_static! {
     static ref STATE: Mutex<State> = Mutex::new(Default::default());
-}
-
-#[wasm_bindgen]
-extern "C" {
-    #[wasm_bindgen(js_namespace = console)]
-    fn log(s: &str);
+    /// The pair drill, separate from the counting drill so the page can run either one
+    static ref PAIR_DRILL: Mutex<Option<PairDrill<AnySystem>>> = Mutex::new(None);
+    /// The player's advantage for kelly_bet_at_count(), worked out the first time it's needed
+    static ref ADVANTAGE: Advantage = Advantage::new(None);
 }
 
 #[wasm_bindgen(start)]
 pub fn run() -> Result<(), JsValue> {
-    console_error_panic_hook::set_once();
+    crash::set_panic_hook("counting");
+    logging::init();
     Ok(())
 }
 
@@ -67,55 +88,111 @@ pub fn run() -> Result<(), JsValue> {
 ///
 /// num_decks is the, well, number of decks of cards we will generate.  num_cards is the number of
 /// cards we will draw from those decks in total, which allows the user to train on less than a
-/// whole number of decks, thus ending on a non-zero count (assuming HiLo).
+/// whole number of decks, thus ending on a non-zero count (assuming a balanced system). system is
+/// the count system's name, e.g. "zen" (see bj_core::count::AnySystem), and HiLo if left out.
 ///
 /// returns false if there was a problem initing (e.g. impossible request), otherwise true.
+///
+/// Kept for pages that haven't moved to rust_init_with_options() yet.
 #[wasm_bindgen]
-pub fn rust_init(num_decks: u8, num_cards: u16, cards_at_a_time: u8) -> bool {
-    if num_decks as u16 * DECK_LEN < num_cards {
-        log(&format!(
-            "{} decks of cards have less than {} cards",
-            num_decks, num_cards
-        ));
+pub fn rust_init(
+    num_decks: u8,
+    num_cards: u16,
+    cards_at_a_time: u8,
+    system: Option<String>,
+) -> bool {
+    let system = match system.as_deref().map(str::parse).transpose() {
+        Ok(system) => system.unwrap_or_default(),
+        Err(e) => {
+            log::warn!("{}", e);
+            return false;
+        }
+    };
+    let opts = CountingOptions {
+        num_decks,
+        num_cards,
+        cards_at_a_time,
+        csm: false,
+        system,
+    };
+    if let Err(e) = opts.validate() {
+        log::warn!("{}", e);
         return false;
     }
+    init(opts);
+    true
+}
+
+/// Initialize our state with the given options as JSON (see CountingOptions). Returns why the
+/// options are bad, or an empty string if the state was initialized.
+#[wasm_bindgen]
+pub fn rust_init_with_options(options: String) -> String {
+    match CountingOptions::from_json(&options) {
+        Ok(opts) => {
+            init(opts);
+            String::new()
+        }
+        Err(e) => e.to_string(),
+    }
+}
+
+fn init(opts: CountingOptions) {
+    let CountingOptions {
+        num_decks,
+        num_cards,
+        cards_at_a_time,
+        csm,
+        system,
+    } = opts;
     let mut state = STATE.lock().unwrap();
-    state.deck = Deck::with_length(num_decks as usize);
-    state.count = StatefulHiLo::new(num_decks);
+    state.deck = if csm {
+        Deck::new_csm(num_decks as usize)
+    } else {
+        Deck::with_length(num_decks as usize)
+    };
+    state.count = StatefulCount::with_system(system, num_decks);
+    state.num_decks = num_decks;
     state.total_cards = num_cards;
     state.seen_cards = 0;
     state.cards_at_a_time = cards_at_a_time;
+    state.csm = csm;
     state.start_time = 0.0;
     state.end_time = 0.0;
-    log(&format!(
-        "Init rust state with {} decks and showing {} cards {} at a time",
-        num_decks, num_cards, cards_at_a_time
-    ));
-    true
+    log::info!(
+        "Init rust state with {} decks{} and showing {} cards {} at a time, counted with {}",
+        num_decks,
+        if csm { " in a CSM" } else { "" },
+        num_cards,
+        cards_at_a_time,
+        system
+    );
 }
 
 fn output_cards(cards: &[Card]) {
-    let win = web_sys::window().expect("should have a window in this context");
-    let doc = win.document().expect("window should have a document");
-    doc.get_element_by_id("cards")
-        .expect("should exist cards")
-        .dyn_ref::<HtmlElement>()
-        .expect("cards should be HtmlElement")
-        .set_inner_text(&cards.iter().map(|&c| card_char(c)).collect::<String>());
+    set_text(
+        "cards",
+        &cards.iter().map(|&c| card_char(c)).collect::<String>(),
+    );
 }
 
 /// Returns true if there was a next card to display, otherwise false
 #[wasm_bindgen]
 pub fn display_next_card() -> bool {
     let mut state = STATE.lock().unwrap();
-    let now = Date::now() / 1000.0; // convert from ms to s
+    // convert from ms to s
+    let now = Date::now() / 1000.0;
+    // every card shown is something to count, so the time until the next one is practice
     if state.seen_cards == 0 {
         state.start_time = now;
+        practice::shown();
+    } else if state.end_time == 0.0 {
+        practice::answered();
     }
     if state.seen_cards >= state.total_cards {
-        log("No next card");
+        log::debug!("No next card");
         if state.end_time == 0.0 {
             state.end_time = now;
+            emit(GameEvent::GameOver);
         }
         return false;
     }
@@ -128,22 +205,98 @@ pub fn display_next_card() -> bool {
         v
     };
     state.count.update_many(&cards);
-    log(&format!(
+    log::debug!(
         "Next cards are {:?} (count: {})",
         //cards.iter().map(|&c| card_char(c)).collect::<String>(),
         &cards,
         state.count.running_count(),
-    ));
+    );
     output_cards(&cards);
+    // The cards go back in the machine, so what was seen says nothing about what's left and the
+    // count starts over. Anything bet on it from here is bet at a true count of zero.
+    if state.csm {
+        state.deck.end_round();
+        state.count.reset();
+    }
     true
 }
 
-/// Returns the current count
+/// Returns the current running count, in the system the drill was started with
 #[wasm_bindgen]
 pub fn current_count() -> i16 {
     STATE.lock().unwrap().count.running_count()
 }
 
+/// Returns the number of units the player's bet ramp bets at the current true count
+#[wasm_bindgen]
+pub fn bet_units() -> u32 {
+    let true_count = STATE.lock().unwrap().count.true_count();
+    Prefs::load().counting.bet_ramp.units(true_count)
+}
+
+/// Returns how much of the given bankroll the Kelly criterion says to bet at the current true
+/// count, betting fraction of full Kelly (e.g. 0.5 for half Kelly). In the bankroll's units. The
+/// edge at each true count is HiLo's in a typical shoe game, so this is rough with any other
+/// system or game.
+#[wasm_bindgen]
+pub fn kelly_bet_at_count(bankroll: f64, fraction: f64) -> f64 {
+    let true_count = STATE.lock().unwrap().count.true_count();
+    kelly_bet(
+        bankroll,
+        ADVANTAGE.at(true_count),
+        ADVANTAGE.variance,
+        fraction,
+    )
+}
+
+/// Returns whether to take insurance, or even money on a blackjack, at the current true count.
+/// Uses HiLo's insurance index.
+#[wasm_bindgen]
+pub fn takes_insurance_at_count() -> bool {
+    let true_count = STATE.lock().unwrap().count.true_count();
+    takes_insurance(Some(true_count), INSURANCE_INDEX)
+}
+
+/// Returns the index plays in effect at the current true count, for after a round, e.g. "TC +4.2:
+/// Stand on Hard 16 vs 10, Take insurance". Plays are relative to the player's chosen built-in
+/// card, or the default card if they use a custom one. The indexes are HiLo's, so they only apply
+/// when counting with HiLo.
+#[wasm_bindgen]
+pub fn index_plays_at_count() -> String {
+    let true_count = STATE.lock().unwrap().count.true_count();
+    let bs = Prefs::load()
+        .bs_card
+        .and_then(|name| bs_data::bs_card(&name))
+        .unwrap_or_else(bs_data::def_bs_card);
+    index_play_summary(true_count, &active_index_plays(true_count, bs))
+}
+
+/// Get the player's bet ramp as text, e.g. "1:1,2:2,3:4"
+#[wasm_bindgen]
+pub fn get_bet_ramp() -> String {
+    Prefs::load().counting.bet_ramp.to_string()
+}
+
+/// Replace the player's bet ramp with the one in the given text, refusing it if its spread is
+/// wider than max_spread (0 means no limit of the player's own). Returns an empty string if the
+/// ramp was saved, otherwise why it wasn't.
+#[wasm_bindgen]
+pub fn set_bet_ramp(s: String, max_spread: u32) -> String {
+    let ramp = match s.parse::<BetRamp>() {
+        Ok(ramp) => ramp,
+        Err(e) => return e.to_string(),
+    };
+    if max_spread > 0 {
+        if let Err(e) = ramp.check_spread(max_spread) {
+            return e.to_string();
+        }
+    }
+    let mut prefs = Prefs::load();
+    prefs.counting.bet_ramp = ramp;
+    prefs.save();
+    String::new()
+}
+
 /// Returns how long it took the player to have us display all cards, or something less than 0 if
 /// not all cards have been shown yet
 #[wasm_bindgen]
@@ -152,22 +305,136 @@ pub fn game_duration() -> f64 {
     // handles the error case by itself
     let state = STATE.lock().unwrap();
     let dur = state.end_time - state.start_time;
-    log(&format!("Duration was: {}", dur));
+    log::debug!("Duration was: {}", dur);
     dur
 }
 
+/// The drill's results to upload, as JSON in the shared format (see bj_core::proto), or undefined
+/// if not all cards have been shown yet
+#[wasm_bindgen]
+pub fn results_from_state() -> Option<String> {
+    let state = STATE.lock().unwrap();
+    if state.end_time == 0.0 {
+        return None;
+    }
+    Some(
+        Upload::new(
+            concat!("counting/", env!("CARGO_PKG_VERSION")),
+            Body::Counting(CountingResults {
+                num_decks: state.num_decks,
+                num_cards: state.total_cards,
+                cards_at_a_time: state.cards_at_a_time,
+                duration_secs: state.end_time - state.start_time,
+                running_count: state.count.running_count(),
+                system: state.count.system().to_string(),
+            }),
+        )
+        .with_identity(&anonid::identity())
+        .to_json(),
+    )
+}
+
 /// Store the given json string representing the preferences for the counting training module in
-/// local storage. No verification is done to ensure the string is valid preferences, let alone
-/// valid json.
+/// the shared prefs. Returns false, storing nothing, if the string isn't valid counting prefs.
 #[wasm_bindgen]
-pub fn set_ls_preferences(s: String) {
-    let mut prefs =
-        LSVal::from_ls_or_default(false, lskeys::LS_KEY_COUNTING_PREFS, "{}".to_string());
-    let _ = prefs.swap(s);
+pub fn set_ls_preferences(s: String) -> bool {
+    let mut prefs = Prefs::load();
+    match serde_json::from_str(&s) {
+        Ok(counting) => {
+            prefs.counting = counting;
+            prefs.save();
+            true
+        }
+        Err(_) => false,
+    }
 }
 
-/// Get the preferences currently in local storage, or just "{}" if none.
+/// Get the counting training module's preferences from the shared prefs as a json string
 #[wasm_bindgen]
 pub fn get_ls_preferences() -> String {
-    LSVal::from_ls_or_default(false, lskeys::LS_KEY_COUNTING_PREFS, "{}".to_string()).clone()
+    serde_json::to_string(&Prefs::load().counting).unwrap()
+}
+
+/// Start the pair drill (see bj_core::pairdrill) with the given mix of pairs as JSON, e.g.
+/// {"cancel": 2, "add": 1, "subtract": 1}, picking up the player's saved stats. Missing weights
+/// default to 1. Pairs are counted with the system the counting drill was started with. Returns
+/// an empty string if it started, otherwise why it didn't.
+#[wasm_bindgen]
+pub fn pair_drill_init(mix: String) -> String {
+    let mix: PairMix = match serde_json::from_str(&mix) {
+        Ok(mix) => mix,
+        Err(e) => return e.to_string(),
+    };
+    let system = *STATE.lock().unwrap().count.system();
+    let mut drill = match PairDrill::new(system, mix) {
+        Ok(drill) => drill,
+        Err(e) => return e.to_string(),
+    };
+    let is_session = Prefs::load().storage.is_session();
+    if let Some(stats) = ls_get(is_session, lskeys::LS_KEY_PAIR_DRILL) {
+        drill.set_stats(stats);
+    }
+    *PAIR_DRILL.lock().unwrap() = Some(drill);
+    String::new()
+}
+
+/// Deal the next pair and show it. Returns the pair's two card characters, or an empty string if
+/// the pair drill hasn't been started.
+#[wasm_bindgen]
+pub fn pair_drill_next() -> String {
+    let mut drill = PAIR_DRILL.lock().unwrap();
+    let drill = match drill.as_mut() {
+        Some(drill) => drill,
+        None => return String::new(),
+    };
+    let (a, b) = drill.deal(Date::now() / 1000.0);
+    log::debug!("Next pair is {:?}", (a, b));
+    practice::shown();
+    output_cards(&[a, b]);
+    [a, b].iter().map(|&c| card_char(c)).collect()
+}
+
+/// Grade the player's answer ("cancel", "add", or "subtract") for the pair being shown and save
+/// their stats. Returns {"correct": bool, "net": the right answer, "secs": how long it took} as
+/// JSON, or undefined if no pair is being shown or the answer isn't one of those.
+#[wasm_bindgen]
+pub fn pair_drill_answer(answer: String) -> Option<String> {
+    let answer: PairNet = match answer.parse() {
+        Ok(answer) => answer,
+        Err(e) => {
+            log::warn!("{}", e);
+            return None;
+        }
+    };
+    let mut drill = PAIR_DRILL.lock().unwrap();
+    let drill = drill.as_mut()?;
+    let graded = drill.answer(answer, Date::now() / 1000.0)?;
+    practice::answered();
+    ls_set(
+        Prefs::load().storage.is_session(),
+        lskeys::LS_KEY_PAIR_DRILL,
+        &drill.stats(),
+    );
+    Some(
+        serde_json::json!({
+            "correct": graded.correct,
+            "net": graded.net,
+            "secs": graded.secs,
+        })
+        .to_string(),
+    )
+}
+
+/// The player's saved pair drill stats as JSON (see bj_core::pairdrill::PairStats)
+#[wasm_bindgen]
+pub fn pair_drill_stats() -> String {
+    let stats = match PAIR_DRILL.lock().unwrap().as_ref() {
+        Some(drill) => drill.stats(),
+        None => ls_get(
+            Prefs::load().storage.is_session(),
+            lskeys::LS_KEY_PAIR_DRILL,
+        )
+        .unwrap_or_default(),
+    };
+    serde_json::to_string(&stats).unwrap()
 }
@@ -1,23 +1,20 @@
 use bj_core::basicstrategy::BasicStrategy;
 use bj_core::hand::HandType;
-use bj_core::rendertable::{HTMLTableRenderer, HTMLTableRendererOpts};
+use bj_core::rendertable::{HTMLTableRendererOpts, SVGTableRenderer, TextTableRenderer};
 use bj_core::resp::Resp;
 use bj_core::table::{dealer_card_from_desc, player_hand_from_desc, GameDesc};
 use bj_web_core::bs_data;
+use bj_web_core::crash;
 use bj_web_core::localstorage::{lskeys, LSVal};
+use bj_web_core::logging;
+use bj_web_core::view::output_resp_table;
 use wasm_bindgen::prelude::*;
 use wasm_bindgen::JsCast;
-use web_sys::{Element, HtmlElement};
+use web_sys::Element;
 
 const LS_KEY_SELECTED_RESP: &str = "bj-custom-card-selected-resp";
 const USE_SESSION_STORAGE: bool = false;
 
-#[wasm_bindgen]
-extern "C" {
-    #[wasm_bindgen(js_namespace = console)]
-    fn log(s: &str);
-}
-
 fn resp_from_str(s: &str) -> Option<Resp> {
     match s {
         "H" => Some(Resp::Hit),
@@ -33,19 +30,13 @@ fn resp_from_str(s: &str) -> Option<Resp> {
 }
 
 fn render_bs_card(bs: &BasicStrategy) {
-    let win = web_sys::window().expect("should have a window in this context");
-    let doc = win.document().expect("window should have a document");
-    let mut buf = vec![];
     let opts = HTMLTableRendererOpts {
         incl_bs_rules: false,
         cell_onclick_cb: Some("onclick_cell".to_string()),
+        notes: None,
+        visibility: None,
     };
-    HTMLTableRenderer::render(&mut buf, bs, opts).unwrap();
-    doc.get_element_by_id("bscard")
-        .expect("should have bscard")
-        .dyn_ref::<HtmlElement>()
-        .expect("bscard should be HtmlElement")
-        .set_inner_html(&String::from_utf8(buf).unwrap());
+    output_resp_table("bscard", bs, opts);
 }
 
 fn set_border_selected_resp(resp: Resp) {
@@ -88,10 +79,12 @@ fn set_border_selected_resp(resp: Resp) {
 
 #[wasm_bindgen(start)]
 pub fn run() -> Result<(), JsValue> {
-    console_error_panic_hook::set_once();
-    let def: BasicStrategy = serde_json::from_reader(bs_data::T1_JSON).unwrap();
+    crash::set_panic_hook("custom-card");
+    logging::init();
     let bs: LSVal<BasicStrategy> =
-        LSVal::from_ls_or_default(USE_SESSION_STORAGE, lskeys::LS_KEY_BS_CARD, def);
+        LSVal::from_ls_or_else(USE_SESSION_STORAGE, lskeys::LS_KEY_BS_CARD, || {
+            bs_data::def_bs_card().clone()
+        });
     {
         let mut resp = LSVal::from_ls_or_default(USE_SESSION_STORAGE, LS_KEY_SELECTED_RESP, None);
         *resp = Some(Resp::Hit);
@@ -106,52 +99,100 @@ pub fn onclick_cell(tbl: &str, mut player: u8, dealer: u8) {
     let resp: LSVal<Option<Resp>> =
         LSVal::from_ls_or_default(USE_SESSION_STORAGE, LS_KEY_SELECTED_RESP, None);
     if resp.is_none() {
-        log("Should have had a selected response at this point, but don't");
+        log::warn!("Should have had a selected response at this point, but don't");
         return;
     }
     let new = resp.unwrap();
-    let (key_player, key_dealer) = {
-        let hand_type = match tbl {
-            "hard" => HandType::Hard,
-            "soft" => HandType::Soft,
-            "pair" => HandType::Pair,
-            _ => panic!(format!("Impossible hand type {}", tbl)),
-        };
-        if tbl == "pair" {
-            player *= 2;
+    let hand_type = match tbl {
+        "hard" => HandType::Hard,
+        "soft" => HandType::Soft,
+        "pair" => HandType::Pair,
+        _ => {
+            log::error!("Impossible hand type {}", tbl);
+            return;
         }
-        let desc = GameDesc {
-            hand: hand_type,
-            player,
-            dealer,
-        };
-        (
-            player_hand_from_desc(desc).unwrap(),
-            dealer_card_from_desc(desc).unwrap(),
-        )
     };
-    let mut bs = {
-        let def: BasicStrategy = serde_json::from_reader(bs_data::T1_JSON).unwrap();
-        LSVal::from_ls_or_default(USE_SESSION_STORAGE, lskeys::LS_KEY_BS_CARD, def)
+    if tbl == "pair" {
+        player *= 2;
+    }
+    let desc = GameDesc {
+        hand: hand_type,
+        player,
+        dealer,
+    };
+    let (key_player, key_dealer) = match (player_hand_from_desc(desc), dealer_card_from_desc(desc))
+    {
+        (Ok(player), Ok(dealer)) => (player, dealer),
+        (Err(e), _) | (_, Err(e)) => {
+            log::warn!("{}", e);
+            return;
+        }
+    };
+    let mut bs = LSVal::from_ls_or_else(USE_SESSION_STORAGE, lskeys::LS_KEY_BS_CARD, || {
+        bs_data::def_bs_card().clone()
+    });
+    let old = match bs.table.get(&key_player, key_dealer) {
+        Ok(old) => old,
+        Err(e) => {
+            log::warn!("{}", e);
+            return;
+        }
     };
-    let old = bs.table.get(&key_player, key_dealer).unwrap();
-    log(&format!(
+    log::debug!(
         "Changing {} {}/{} from {} to {}",
-        tbl, player, dealer, old, new
-    ));
-    bs.table.update(&key_player, key_dealer, new).unwrap();
+        tbl,
+        player,
+        dealer,
+        old,
+        new
+    );
+    if let Err(e) = bs.table.update(&key_player, key_dealer, new) {
+        log::warn!("{}", e);
+        return;
+    }
+    // setting a cell of a partial card is learning it
+    bs.unlearned.retain(|d| *d != desc);
     render_bs_card(&*bs);
 }
 
+/// The card being edited as a standalone SVG image, for downloading and sharing
+#[wasm_bindgen]
+pub fn card_svg() -> String {
+    let bs = LSVal::from_ls_or_else(USE_SESSION_STORAGE, lskeys::LS_KEY_BS_CARD, || {
+        bs_data::def_bs_card().clone()
+    });
+    let mut buf = vec![];
+    SVGTableRenderer::render(&mut buf, &bs).unwrap();
+    String::from_utf8(buf).unwrap()
+}
+
+/// The card being edited as aligned plain text, for copying and pasting
+#[wasm_bindgen]
+pub fn card_text() -> String {
+    let bs = LSVal::from_ls_or_else(USE_SESSION_STORAGE, lskeys::LS_KEY_BS_CARD, || {
+        bs_data::def_bs_card().clone()
+    });
+    let mut buf = vec![];
+    TextTableRenderer::render(&mut buf, &bs).unwrap();
+    String::from_utf8(buf).unwrap()
+}
+
 #[wasm_bindgen]
 pub fn onclick_select_resp(resp_str: &str) {
     let mut stored: LSVal<Option<Resp>> =
         LSVal::from_ls_or_default(USE_SESSION_STORAGE, LS_KEY_SELECTED_RESP, None);
-    let new = Some(resp_from_str(resp_str).unwrap());
-    log(&format!(
+    let new = match resp_from_str(resp_str) {
+        Some(new) => new,
+        None => {
+            log::warn!("{} is not a response", resp_str);
+            return;
+        }
+    };
+    log::debug!(
         "Changing selected resp from {:?} to {:?}",
-        *stored, new,
-    ));
-    *stored = new;
-    set_border_selected_resp(new.unwrap());
+        *stored,
+        Some(new),
+    );
+    *stored = Some(new);
+    set_border_selected_resp(new);
 }
@@ -0,0 +1,98 @@
+/// Measures how long a time trial has taken, not counting time spent paused. All times are in
+/// seconds, and are passed in so the clock can be tested without a browser.
+#[derive(Debug, Default, PartialEq)]
+pub(crate) struct TrialClock {
+    // when the first result came in, if it has
+    start: Option<f64>,
+    // when the current pause started, if paused
+    paused_at: Option<f64>,
+    // total time spent in previous pauses since start
+    paused_total: f64,
+}
+
+impl TrialClock {
+    /// Start the clock, if it hasn't been already
+    pub fn start(&mut self, now: f64) {
+        if self.start.is_none() {
+            self.start = Some(now);
+        }
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused_at.is_some()
+    }
+
+    /// Pause the clock. Returns false if it was already paused.
+    pub fn pause(&mut self, now: f64) -> bool {
+        if self.is_paused() {
+            return false;
+        }
+        self.paused_at = Some(now);
+        true
+    }
+
+    /// Resume the clock. Returns false if it wasn't paused.
+    pub fn resume(&mut self, now: f64) -> bool {
+        match self.paused_at.take() {
+            None => false,
+            Some(paused_at) => {
+                // time paused before the clock started doesn't need excluding
+                if let Some(start) = self.start {
+                    self.paused_total += now - paused_at.max(start);
+                }
+                true
+            }
+        }
+    }
+
+    /// Time since the clock started, minus time spent paused. 0 if not started.
+    pub fn elapsed(&self, now: f64) -> f64 {
+        match self.start {
+            None => 0.0,
+            Some(start) => {
+                let now = self.paused_at.unwrap_or(now);
+                now - start - self.paused_total
+            }
+        }
+    }
+
+    /// Total time spent paused since the clock started
+    pub fn paused(&self, now: f64) -> f64 {
+        match (self.start, self.paused_at) {
+            (Some(start), Some(paused_at)) => self.paused_total + now - paused_at.max(start),
+            _ => self.paused_total,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TrialClock;
+
+    #[test]
+    fn excludes_pauses() {
+        let mut c = TrialClock::default();
+        assert_eq!(c.elapsed(5.0), 0.0);
+        c.start(10.0);
+        assert_eq!(c.elapsed(15.0), 5.0);
+        assert!(c.pause(15.0));
+        assert!(!c.pause(16.0));
+        // frozen while paused
+        assert_eq!(c.elapsed(100.0), 5.0);
+        assert_eq!(c.paused(100.0), 85.0);
+        assert!(c.resume(100.0));
+        assert!(!c.resume(101.0));
+        assert_eq!(c.elapsed(102.0), 7.0);
+        assert_eq!(c.paused(102.0), 85.0);
+    }
+
+    #[test]
+    fn pause_before_start() {
+        let mut c = TrialClock::default();
+        c.pause(1.0);
+        c.resume(50.0);
+        c.start(60.0);
+        assert_eq!(c.elapsed(70.0), 10.0);
+        assert_eq!(c.paused(70.0), 0.0);
+    }
+}
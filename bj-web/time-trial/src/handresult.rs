@@ -1,5 +1,6 @@
 use bj_core::deck::Card;
 use bj_core::hand::Hand;
+use bj_core::proto;
 use serde_derive::{Deserialize, Serialize};
 
 #[derive(Serialize, Deserialize, PartialEq, Debug, Clone)]
@@ -7,6 +8,14 @@ pub(crate) struct HandResult {
     pub player: Hand,
     pub dealer: Card,
     pub correct: bool,
-    // duration (in seconds, not ms) since first result at which point this result was recorded
+    // duration (in seconds, not ms) since first result at which point this result was recorded,
+    // not counting time spent paused
     pub time: f64,
 }
+
+impl HandResult {
+    /// The result as it's uploaded
+    pub fn to_proto(&self) -> proto::HandResult {
+        proto::HandResult::new(&self.player, self.dealer, self.correct, self.time)
+    }
+}
@@ -1,22 +1,30 @@
+mod clock;
 mod handresult;
 
-use bj_core::basicstrategy::{rules, BasicStrategy};
-use bj_core::deck::{Card, Rank};
-use bj_core::hand::Hand;
-use bj_core::rendertable::{HTMLTableRenderer, HTMLTableRendererOpts};
-use bj_core::resp::Resp;
-use bj_core::utils::uniform_rand_2card_hand;
+use bj_core::button::GameButton;
+use bj_core::proto::{Body, TimeTrialResults, TrialStatus, Upload};
+use bj_core::rendertable::HTMLTableRendererOpts;
+use bj_core::table::GameDesc;
+use bj_core::trainer::{Graded, HandSource, Trainer};
+use bj_core::weakcells;
+use bj_web_core::anonid;
 use bj_web_core::bs_data;
-use bj_web_core::button::GameButton;
-use bj_web_core::card_char;
-use bj_web_core::correct_resp::is_correct_resp_button;
-use bj_web_core::localstorage::{lskeys, LSVal};
+use bj_web_core::crash;
+use bj_web_core::deal::output_hand;
+use bj_web_core::events::{emit, emit_all, graded_events, GameEvent};
+use bj_web_core::feedback;
+use bj_web_core::init::{TimeTrialOptions, TrainerOptions};
+use bj_web_core::localstorage::{ls_get, ls_set, lskeys};
+use bj_web_core::logging;
+use bj_web_core::practice;
+use bj_web_core::prefs::Prefs;
+use bj_web_core::records::{Eligibility, RecordKey, Records, TrialRecord};
+use bj_web_core::view::{self, hide_game_buttons, update_game_buttons};
+use clock::TrialClock;
 use handresult::HandResult;
 use js_sys::Date;
 use std::sync::Mutex;
 use wasm_bindgen::prelude::*;
-use wasm_bindgen::JsCast;
-use web_sys::{Element, HtmlElement};
 #[macro_use]
 extern crate lazy_static;
 
@@ -27,8 +35,18 @@ struct State {
     results: Vec<HandResult>,
     // stop when results.len() is this
     num_hands: usize,
-    // timestamp (in seconds, not ms) of first result
-    start_time: f64,
+    // time since the first result, minus time spent paused
+    clock: TrialClock,
+    // whether the player gave up before playing num_hands hands
+    aborted: bool,
+    // whether the player had help (e.g. looked at the strategy card), which rules out a record
+    assisted: bool,
+    // whether the finished trial set a personal best
+    new_pb: bool,
+    // deals and grades hands. Its hand is copied to localStorage whenever it changes
+    trainer: Trainer,
+    // the cells hands are dealt from, if the trial was given a list of weak cells
+    weak_cells: Option<Vec<GameDesc>>,
 }
 
 impl Default for State {
@@ -39,8 +57,14 @@ impl Default for State {
             results: vec![],
             // to be updated on rust_init()
             num_hands: 0,
-            // to be updated on first result
-            start_time: 0.0,
+            // started on first result
+            clock: Default::default(),
+            aborted: false,
+            assisted: false,
+            new_pb: false,
+            // card and hand to be updated on rust_init()
+            trainer: Trainer::new(bs_data::def_bs_card().clone(), HandSource::Uniform),
+            weak_cells: None,
         }
     }
 }
@@ -49,29 +73,43 @@ lazy_static! {
     static ref STATE: Mutex<State> = Mutex::new(Default::default());
 }
 
-fn def_bs_card() -> BasicStrategy {
-    serde_json::from_reader(bs_data::T1_JSON).unwrap()
-}
-
 #[wasm_bindgen]
 extern "C" {
-    #[wasm_bindgen(js_namespace = console)]
-    fn log(s: &str);
-
-    fn flash_hint_message(s: &str);
-    fn set_hint_message(s: &str);
-
-    fn upload_results(buf: &[u8]);
+    fn upload_results(json: &str);
 }
 
 #[wasm_bindgen(start)]
 pub fn run() -> Result<(), JsValue> {
-    console_error_panic_hook::set_once();
+    crash::set_panic_hook("time-trial");
+    logging::init();
     Ok(())
 }
 
+/// Start a trial of num_hands hands. Kept for pages that haven't moved to
+/// rust_init_with_options() yet.
 #[wasm_bindgen]
 pub fn rust_init(num_hands: usize) {
+    init(TimeTrialOptions {
+        num_hands,
+        ..Default::default()
+    });
+}
+
+/// Start a trial with the given options as JSON (see TimeTrialOptions). Returns why the options
+/// are bad, or an empty string if the trial started.
+#[wasm_bindgen]
+pub fn rust_init_with_options(options: String) -> String {
+    match TimeTrialOptions::from_json(&options) {
+        Ok(opts) => {
+            init(opts);
+            String::new()
+        }
+        Err(e) => e.to_string(),
+    }
+}
+
+fn init(opts: TimeTrialOptions) {
+    let num_hands = opts.num_hands;
     let mut state = STATE.lock().unwrap();
     {
         state.num_hands = num_hands;
@@ -82,50 +120,54 @@ pub fn rust_init(num_hands: usize) {
         }
         assert!(state.results.capacity() >= num_hands);
     }
-    output_resp_table(&*state);
-    let hand = &*LSVal::from_ls_or_default(
+    let prefs = Prefs::load();
+    state.use_session_storage = prefs.storage.is_session();
+    if let Some(bs_card) = prefs.bs_card.as_deref().and_then(bs_data::bs_card) {
+        state.trainer.set_strategy(bs_card.clone());
+    } else if let Some(bs_card) = ls_get(state.use_session_storage, lskeys::LS_KEY_BS_CARD) {
+        state.trainer.set_strategy(bs_card);
+    }
+    // options are validated, so the list of weak cells is good
+    state.weak_cells = if opts.weak_cells.is_empty() {
+        None
+    } else {
+        Some(weakcells::import(&opts.weak_cells).unwrap())
+    };
+    let pack = state
+        .weak_cells
+        .as_ref()
+        .map(|_| weakcells::homework_pack(&opts.weak_cells, num_hands as u32).unwrap());
+    state.trainer.set_pack(pack).unwrap();
+    match ls_get(state.use_session_storage, lskeys::LS_KEY_EXISTING_HAND) {
+        // a saved hand may not be one of the weak cells
+        Some((player, dealer)) if state.weak_cells.is_none() => {
+            state.trainer.set_hand(player, dealer)
+        }
+        _ => save_hand(&state),
+    };
+    output_resp_table(&state);
+    let (player, dealer) = state.trainer.hand();
+    output_hand(player, dealer);
+    update_game_buttons(&state.trainer);
+    practice::shown();
+}
+
+fn save_hand(state: &State) {
+    ls_set(
         state.use_session_storage,
         lskeys::LS_KEY_EXISTING_HAND,
-        uniform_rand_2card_hand(),
-    );
-    let bs_card = LSVal::from_ls_or_default(
-        state.use_session_storage,
-        lskeys::LS_KEY_BS_CARD,
-        def_bs_card(),
+        &state.trainer.hand(),
     );
-    output_hand(&hand.0, hand.1);
-    update_game_buttons((&hand.0, hand.1), &bs_card.rules);
 }
 
 fn output_resp_table(state: &State) {
-    let bs_card = LSVal::from_ls_or_default(
-        state.use_session_storage,
-        lskeys::LS_KEY_BS_CARD,
-        def_bs_card(),
-    );
-    let mut fd: Vec<u8> = vec![];
     let opts = HTMLTableRendererOpts {
         incl_bs_rules: true,
         cell_onclick_cb: None,
+        notes: None,
+        visibility: None,
     };
-    HTMLTableRenderer::render(&mut fd, &*bs_card, opts).unwrap();
-    let win = web_sys::window().expect("should have a window in this context");
-    let doc = win.document().expect("window should have a document");
-    doc.get_element_by_id("strat_html")
-        .expect("should exist strat_html")
-        .dyn_ref::<HtmlElement>()
-        .expect("strat_html should be HtmlElement")
-        .set_inner_html(&String::from_utf8(fd).unwrap());
-}
-
-fn is_legal_resp(btn: GameButton, hand: (&Hand, Card), surrender_rule: rules::Surrender) -> bool {
-    let (player, dealer) = hand;
-    match btn {
-        GameButton::Hit | GameButton::Stand => true,
-        GameButton::Double => player.can_double(),
-        GameButton::Split => player.can_split(),
-        GameButton::Surrender => player.can_surrender(surrender_rule, dealer),
-    }
+    view::output_resp_table("strat_html", state.trainer.strategy(), opts);
 }
 
 #[wasm_bindgen]
@@ -161,62 +203,134 @@ pub fn on_button_surrender() {
 #[wasm_bindgen]
 pub fn okay_to_upload_results() -> bool {
     let state = STATE.lock().unwrap();
-    state.num_hands == state.results.len()
+    match state.status() {
+        TrialStatus::Completed => true,
+        TrialStatus::Aborted => !state.results.is_empty(),
+        TrialStatus::InProgress => false,
+    }
+}
+
+impl State {
+    fn record_key(&self) -> RecordKey {
+        let pool = match (&self.weak_cells, self.trainer.source()) {
+            // only trials on the same cells are comparable
+            (Some(cells), _) => {
+                let mut cells = cells.clone();
+                cells.sort();
+                let labels: Vec<String> = cells.iter().map(|d| d.label()).collect();
+                format!("cells: {}", labels.join(", "))
+            }
+            (None, HandSource::Uniform) => "uniform".to_string(),
+            (None, HandSource::Weighted) => "weighted".to_string(),
+        };
+        RecordKey {
+            num_hands: self.num_hands,
+            pool,
+        }
+    }
+
+    fn status(&self) -> TrialStatus {
+        if self.num_hands <= self.results.len() {
+            TrialStatus::Completed
+        } else if self.aborted {
+            TrialStatus::Aborted
+        } else {
+            TrialStatus::InProgress
+        }
+    }
+}
+
+/// Pause the trial, stopping the clock and hiding the game buttons. Returns false if the trial is
+/// over or already paused.
+#[wasm_bindgen]
+pub fn pause() -> bool {
+    let mut state = STATE.lock().unwrap();
+    if state.status() != TrialStatus::InProgress || !state.clock.pause(Date::now() / 1000.0) {
+        return false;
+    }
+    hide_game_buttons();
+    feedback::status("Paused.");
+    true
+}
+
+/// Resume a paused trial. Returns false if it wasn't paused.
+#[wasm_bindgen]
+pub fn resume() -> bool {
+    let mut state = STATE.lock().unwrap();
+    if state.status() != TrialStatus::InProgress || !state.clock.resume(Date::now() / 1000.0) {
+        return false;
+    }
+    update_game_buttons(&state.trainer);
+    feedback::status("");
+    // time spent paused isn't practice
+    practice::shown();
+    true
+}
+
+/// Give up on the trial, keeping the results so far so they can still be uploaded. Returns false
+/// if the trial is already over.
+#[wasm_bindgen]
+pub fn abort() -> bool {
+    let mut state = STATE.lock().unwrap();
+    if state.status() != TrialStatus::InProgress {
+        return false;
+    }
+    state.aborted = true;
+    let now = Date::now() / 1000.0;
+    state.clock.resume(now);
+    hide_game_buttons();
+    let num_correct = state.results.iter().filter(|res| res.correct).count();
+    if state.results.is_empty() {
+        feedback::status("Aborted before playing any hands.");
+    } else {
+        feedback::status(&format!(
+            "Aborted. Did {}/{} of {} hands correctly in {} seconds. Upload results?",
+            num_correct,
+            state.results.len(),
+            state.num_hands,
+            state.clock.elapsed(now),
+        ));
+        show_upload_buttons();
+    }
+    emit(GameEvent::GameOver);
+    true
 }
 
 fn handle_button(state: &mut State, btn: GameButton) {
-    // don't do anything if game over
-    if state.num_hands <= state.results.len() {
+    // don't do anything if game over or paused
+    if state.status() != TrialStatus::InProgress || state.clock.is_paused() {
         return;
     }
-    // the (player_hand, dealer_card) currently on the screen
-    let mut hand: LSVal<(Hand, Card)> =
-        LSVal::from_ls(state.use_session_storage, lskeys::LS_KEY_EXISTING_HAND).unwrap();
-    let bs_card = LSVal::from_ls_or_default(
-        state.use_session_storage,
-        lskeys::LS_KEY_BS_CARD,
-        def_bs_card(),
-    );
-    let surrender_rule = match &bs_card.rules {
-        None => rules::Surrender::Yes,
-        Some(rules) => rules.surrender,
-    };
     // return early if user didn't even give legal response to this hand
-    if !is_legal_resp(btn, (&hand.0, hand.1), surrender_rule) {
-        log(&format!(
-            "{} is not a legal response to {}/{}",
-            btn, &hand.0, hand.1
-        ));
+    if !state.trainer.is_legal(btn) {
+        let (player, dealer) = state.trainer.hand();
+        log::warn!("{} is not a legal response to {}/{}", btn, player, dealer);
         return;
     }
     let now = Date::now() / 1000.0; // convert fro ms to s
-    if state.results.is_empty() {
-        state.start_time = now;
-    }
-    // the correct response to this (player_hand, dealer_card). We store the bool is_correct as
-    // well because whether or not the response is correct is more complex than button == resp: if
-    // the correct Resp is DoubleElseHit (or its cousins) then it is not enough to simply check if
-    // the Double button was pressed.
-    let correct: Resp = bs_card.table.get(&hand.0, hand.1).unwrap();
-    let is_correct = is_correct_resp_button(btn, correct, (&hand.0, hand.1), surrender_rule);
+    state.clock.start(now);
+    // grades the hand and deals a new one
+    let graded = match state.trainer.act(btn) {
+        Ok(graded) => graded,
+        Err(e) => {
+            log::error!("{}", e);
+            return;
+        }
+    };
+    practice::answered();
     // store the result for this hand
     state.results.push(HandResult {
-        player: hand.0.clone(),
-        dealer: hand.1,
-        correct: is_correct,
-        time: now - state.start_time,
+        player: graded.player.clone(),
+        dealer: graded.dealer,
+        correct: graded.is_correct,
+        time: state.clock.elapsed(now),
     });
-    set_hint(
-        btn,
-        correct,
-        (&hand.0, hand.1),
-        is_correct,
-        state.num_hands - state.results.len(),
-    );
-    // generate a new hand
-    let _ = hand.swap(uniform_rand_2card_hand());
-    output_hand(&hand.0, hand.1);
-    update_game_buttons((&hand.0, hand.1), &bs_card.rules);
+    set_hint(&graded, state.num_hands - state.results.len());
+    emit_all(&graded_events(graded.is_correct, state.trainer.streak()));
+    save_hand(state);
+    let (player, dealer) = state.trainer.hand();
+    output_hand(player, dealer);
+    update_game_buttons(&state.trainer);
     // consider ending the game
     if state.results.len() == state.num_hands {
         // game over
@@ -226,145 +340,116 @@ fn handle_button(state: &mut State, btn: GameButton) {
             .results
             .iter()
             .fold(0, |acc, res| acc + if res.correct { 1 } else { 0 });
-        set_hint_message(&format!(
-            "Done! Did {}/{} hands correctly in {} seconds. Upload results?",
-            num_correct, state.num_hands, dur,
+        let mut records = Records::load();
+        let elig = Eligibility {
+            aborted: false,
+            assisted: state.assisted,
+            paused: state.clock.paused(now) > 0.0,
+        };
+        let record = TrialRecord {
+            num_correct,
+            duration: dur,
+            date: Date::now(),
+        };
+        state.new_pb = records.submit(state.record_key(), record, elig);
+        if state.new_pb {
+            records.save();
+        }
+        feedback::status(&format!(
+            "Done! Did {}/{} hands correctly in {} seconds.{} Upload results?",
+            num_correct,
+            state.num_hands,
+            dur,
+            if state.new_pb {
+                " New personal best!"
+            } else {
+                ""
+            },
         ));
         hide_game_buttons();
         show_upload_buttons();
+        emit(GameEvent::GameOver);
     }
 }
 
-fn output_hand(player: &Hand, dealer: Card) {
-    let win = web_sys::window().expect("should have a window in this context");
-    let doc = win.document().expect("window should have a document");
-    doc.get_element_by_id("player_cards")
-        .expect("should exist player_cards")
-        .dyn_ref::<HtmlElement>()
-        .expect("player_cards should be HtmlElement")
-        .set_inner_text(&player.cards().map(|&c| card_char(c)).collect::<String>());
-    doc.get_element_by_id("dealer_cards")
-        .expect("should exist dealer_cards")
-        .dyn_ref::<HtmlElement>()
-        .expect("dealer_cards should be HtmlElement")
-        .set_inner_text(&format!("{}", card_char(dealer)));
-}
-
 fn show_upload_buttons() {
-    let win = web_sys::window().expect("should have a window in this context");
-    let doc = win.document().expect("window should have a document");
     for id in &["button_upload_yes", "button_upload_no"] {
-        let class_list = doc
-            .get_element_by_id(id)
-            .expect("should exist button")
-            .dyn_ref::<Element>()
-            .expect("button should be Element")
-            .class_list();
-        class_list
-            .remove_1("hide")
-            .expect("Unable to remove hide class");
+        view::set_shown(id, true);
     }
 }
 
-fn hide_game_buttons() {
-    let win = web_sys::window().expect("should have a window in this context");
-    let doc = win.document().expect("window should have a document");
-    for id in &[
-        "button_hit",
-        "button_stand",
-        "button_double",
-        "button_split",
-        "button_surrender",
-    ] {
-        let class_list = doc
-            .get_element_by_id(id)
-            .expect("should exist button")
-            .dyn_ref::<Element>()
-            .expect("button should be Element")
-            .class_list();
-        class_list.add_1("hide").expect("Unable to add hide class");
-    }
-}
-
-fn update_game_buttons(hand: (&Hand, Card), rules: &Option<rules::Rules>) {
-    let win = web_sys::window().expect("should have a window in this context");
-    let doc = win.document().expect("window should have a document");
-    let hit_enabled = true;
-    let stand_enabled = true;
-    let double_enabled = hand.0.can_double();
-    let split_enabled = hand.0.can_split();
-    let surrender_enabled = if let Some(rules) = rules {
-        match rules.surrender {
-            rules::Surrender::No => false,
-            rules::Surrender::Yes => true,
-            rules::Surrender::NotAce => hand.1.rank() != Rank::RA,
-        }
-    } else {
-        true
-    };
-    for (id, enabled) in [
-        ("button_hit", hit_enabled),
-        ("button_stand", stand_enabled),
-        ("button_double", double_enabled),
-        ("button_split", split_enabled),
-        ("button_surrender", surrender_enabled),
-    ]
-    .iter()
-    {
-        let class_list = doc
-            .get_element_by_id(id)
-            .expect("should exist button")
-            .dyn_ref::<Element>()
-            .expect("button should be Element")
-            .class_list();
-        if *enabled {
-            class_list
-                .remove_1("hide")
-                .expect("Unable to add hide class");
-        } else {
-            class_list
-                .add_1("hide")
-                .expect("Unable to remove hide class");
-        }
-    }
-}
-
-fn set_hint(
-    given: GameButton,
-    correct: Resp,
-    hand: (&Hand, Card),
-    is_correct: bool,
-    remaining: usize,
-) {
-    let s = if is_correct {
+fn set_hint(graded: &Graded, remaining: usize) {
+    let s = if graded.is_correct {
         format!(
             "{} correct. {} hand{} to go.",
-            given,
+            graded.action,
             remaining,
             if remaining == 1 { "" } else { "s" }
         )
     } else {
         format!(
-            "{} wrong. Should {} {} vs {}. {} hand{} to go.",
-            given,
-            correct,
-            hand.0,
-            hand.1,
+            "{} wrong. Should {} on {}. {}{} hand{} to go.",
+            graded.action,
+            graded.correct,
+            graded.cell().label(),
+            graded.fallback_detail().map_or(String::new(), |d| d + " "),
             remaining,
             if remaining == 1 { "" } else { "s" }
         )
     };
-    flash_hint_message(&s);
+    feedback::hint(&s);
 }
 
+/// The trial's results to upload, as JSON in the shared format (see bj_core::proto)
 #[wasm_bindgen]
-pub fn results_from_state() -> Vec<u8> {
+pub fn results_from_state() -> String {
     let state = STATE.lock().unwrap();
-    serde_cbor::to_vec(&state.results).unwrap()
+    Upload::new(
+        concat!("time-trial/", env!("CARGO_PKG_VERSION")),
+        Body::TimeTrial(TimeTrialResults {
+            status: state.status(),
+            num_hands: state.num_hands as u32,
+            pool: state.record_key().pool,
+            surrender: state.trainer.surrender_rule().id().to_string(),
+            paused_secs: state.clock.paused(Date::now() / 1000.0),
+            hands: state.results.iter().map(HandResult::to_proto).collect(),
+        }),
+    )
+    .with_identity(&anonid::identity())
+    .to_json()
 }
 
 #[wasm_bindgen]
 pub fn clear_results() {
     let mut state = STATE.lock().unwrap();
     state.results.clear();
+    state.clock = Default::default();
+    state.aborted = false;
+    state.assisted = false;
+    state.new_pb = false;
+}
+
+/// Note that the player got help during the trial (e.g. looked at the strategy card), so it can't
+/// set a personal best
+#[wasm_bindgen]
+pub fn mark_assisted() {
+    let mut state = STATE.lock().unwrap();
+    if state.status() == TrialStatus::InProgress {
+        state.assisted = true;
+    }
+}
+
+/// The personal best for the current trial configuration as JSON, or undefined if there is none
+#[wasm_bindgen]
+pub fn personal_best() -> Option<String> {
+    let state = STATE.lock().unwrap();
+    Records::load()
+        .get(&state.record_key())
+        .map(|r| serde_json::to_string(r).unwrap())
+}
+
+/// Whether the trial that just finished set a new personal best
+#[wasm_bindgen]
+pub fn was_new_personal_best() -> bool {
+    STATE.lock().unwrap().new_pb
 }
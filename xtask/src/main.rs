@@ -0,0 +1,332 @@
+//! Workspace chores, run with `cargo xtask <task>`.
+//!
+//! `cargo xtask site [--release] [--out DIR]` builds the whole static site into DIR (www-out by
+//! default). Every trainer in TRAINERS is compiled with wasm-pack, and its glue JS and wasm go next
+//! to the pages. Everything in bj-web/www is copied over, with the pages filled in from their
+//! templates. A page only holds what's its own; the rest comes from the shared layout in
+//! bj-web/layout, so every page has the same head, header, and footer:
+//!
+//! - `<!-- BJ_TMPL_HEAD -->`: the title, the analytics tags, and the stylesheets every page uses
+//! - `<!-- BJ_TMPL_HEADER -->`: the nav bar, which links every trainer's page
+//! - `<!-- BJ_TMPL_FOOTER -->`: the version and contact details
+//!
+//! A page has to import its trainer's glue as './<glue>.js', and every page has to belong to a
+//! trainer, which is checked before anything is built.
+//!
+//! Wiring a new trainer crate into the site is adding it to TRAINERS, and its page to bj-web/www.
+//! It shows up in every page's nav bar from then on.
+//!
+//! `cargo xtask cards` regenerates every generated strategy card in bj-data/data (see
+//! bj_data::generate_card()), and says which ones changed so their versions can be bumped.
+use bj_data::{Kind, DATASETS};
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// A wasm trainer crate, and the page that loads it
+struct Trainer {
+    /// The crate's directory in bj-web
+    dir: &'static str,
+    /// What wasm-pack names its glue, so pages import './<glue>.js'
+    glue: &'static str,
+    page: Option<Page>,
+}
+
+/// A trainer's page in bj-web/www
+#[derive(Clone, Copy)]
+struct Page {
+    file: &'static str,
+    /// Its name in the nav bar
+    nav: &'static str,
+    title: &'static str,
+}
+
+const TRAINERS: &[Trainer] = &[
+    Trainer {
+        dir: "basic-strategy",
+        glue: "bj_web_index",
+        page: Some(Page {
+            file: "index.html",
+            nav: "Game",
+            title: "Basic strategy",
+        }),
+    },
+    Trainer {
+        dir: "custom-card",
+        glue: "bj_web_custom_card",
+        page: Some(Page {
+            file: "custom-card.html",
+            nav: "Customize",
+            title: "Custom strategy card",
+        }),
+    },
+    Trainer {
+        dir: "counting",
+        glue: "bj_web_counting",
+        page: None,
+    },
+    Trainer {
+        dir: "time-trial",
+        glue: "bj_web_time_trial",
+        page: None,
+    },
+];
+
+const TMPL_HEAD: &str = "<!-- BJ_TMPL_HEAD -->";
+const TMPL_HEADER: &str = "<!-- BJ_TMPL_HEADER -->";
+const TMPL_FOOTER: &str = "<!-- BJ_TMPL_FOOTER -->";
+const TMPL_TITLE: &str = "<!-- BJ_TMPL_TITLE -->";
+const TMPL_NAV_BAR: &str = "<!-- BJ_TMPL_NAV_BAR -->";
+const TMPL_VERSION: &str = "<!-- BJ_TMPL_VERSION -->";
+const TMPL_ANALYTICS: &str = "<!-- GOOGLE_SHIT -->";
+
+const ANALYTICS: &str = r#"
+  <!-- Global site tag (gtag.js) - Google Analytics -->
+  <script async src="https://www.googletagmanager.com/gtag/js?id=UA-160379782-1"></script>
+  <script>
+    window.dataLayer = window.dataLayer || [];
+    function gtag(){dataLayer.push(arguments);}
+    gtag('js', new Date());
+
+    gtag('config', 'UA-160379782-1');
+  </script>
+  <script data-ad-client="ca-pub-3834375319956666" async src="https://pagead2.googlesyndication.com/pagead/js/adsbygoogle.js"></script>
+"#;
+
+const LAYOUT_HEAD: &str = include_str!("../../bj-web/layout/head.html");
+const LAYOUT_HEADER: &str = include_str!("../../bj-web/layout/header.html");
+const LAYOUT_FOOTER: &str = include_str!("../../bj-web/layout/footer.html");
+
+/// The nav bar, linking every trainer's page, with the current one marked
+fn nav_bar(current: &Page) -> String {
+    let mut s = String::from(
+        "\n<nav>\n<a href='index.html'><img alt=\"BJ logo\" id=logo src=\"static/logo.png\" /></a>\n<ul>\n",
+    );
+    for page in TRAINERS.iter().filter_map(|t| t.page) {
+        let aria = if page.file == current.file {
+            " aria-current=page"
+        } else {
+            ""
+        };
+        s += &format!(
+            "<li><a href='{}'{}>{}</a></li>\n",
+            page.file, aria, page.nav
+        );
+    }
+    s + "</ul>\n</nav>\n"
+}
+
+/// The trainer page with the given file name
+fn page(file: &str) -> Option<Page> {
+    TRAINERS
+        .iter()
+        .filter_map(|t| t.page)
+        .find(|page| page.file == file)
+}
+
+/// Fill in a page's template. The layout goes in first, as it has templates of its own.
+fn render(template: &str, page: &Page, version: &str) -> String {
+    template
+        .replace(TMPL_HEAD, LAYOUT_HEAD.trim())
+        .replace(TMPL_HEADER, LAYOUT_HEADER.trim())
+        .replace(TMPL_FOOTER, LAYOUT_FOOTER.trim())
+        .replace(TMPL_TITLE, page.title)
+        .replace(TMPL_NAV_BAR, &nav_bar(page))
+        .replace(TMPL_VERSION, version)
+        .replace(TMPL_ANALYTICS, ANALYTICS)
+}
+
+/// Check that the page imports the trainer's glue and uses the shared layout
+fn check_page(page: &str, src: &str, glue: &str) -> Result<(), String> {
+    let import = format!("'./{}.js'", glue);
+    if !src.contains(&import) {
+        return Err(format!(
+            "{} should import its trainer from {}",
+            page, import
+        ));
+    }
+    match [TMPL_HEAD, TMPL_HEADER, TMPL_FOOTER]
+        .iter()
+        .find(|tmpl| !src.contains(*tmpl))
+    {
+        Some(tmpl) => Err(format!("{} should use the shared layout's {}", page, tmpl)),
+        None => Ok(()),
+    }
+}
+
+/// The commit's date and short hash, e.g. "2026-03-14 (1ae6bc8)"
+fn version(root: &Path) -> Result<String, Box<dyn Error>> {
+    let git = |args: &[&str]| -> Result<String, Box<dyn Error>> {
+        let out = Command::new("git").args(args).current_dir(root).output()?;
+        if !out.status.success() {
+            return Err(format!("git {} failed", args.join(" ")).into());
+        }
+        Ok(String::from_utf8(out.stdout)?.trim().to_string())
+    };
+    let commit = git(&["rev-parse", "--short", "HEAD"])?;
+    let date = git(&[
+        "show",
+        "-s",
+        "--format=%cd",
+        "--date=format:%Y-%m-%d",
+        &commit,
+    ])?;
+    Ok(format!("{} ({})", date, commit))
+}
+
+/// Copy the www directory into out, filling in the pages. Glue left over from manual builds is
+/// skipped, as the trainers' own builds replace it.
+fn copy_www(www: &Path, out: &Path, version: &str) -> Result<(), Box<dyn Error>> {
+    fs::create_dir_all(out)?;
+    let stale: Vec<String> = TRAINERS
+        .iter()
+        .flat_map(|t| vec![format!("{}.js", t.glue), format!("{}_bg.wasm", t.glue)])
+        .collect();
+    for entry in fs::read_dir(www)? {
+        let path = entry?.path();
+        let name = path.file_name().unwrap().to_string_lossy().to_string();
+        let dest = out.join(&name);
+        if path.is_dir() {
+            copy_www(&path, &dest, version)?;
+        } else if stale.contains(&name) {
+            continue;
+        } else if name.ends_with(".html") {
+            let page = page(&name).ok_or_else(|| format!("{} isn't any trainer's page", name))?;
+            fs::write(&dest, render(&fs::read_to_string(&path)?, &page, version))?;
+        } else {
+            fs::copy(&path, &dest)?;
+        }
+    }
+    Ok(())
+}
+
+fn site(release: bool, out: &Path) -> Result<(), Box<dyn Error>> {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR")).parent().unwrap();
+    let web = root.join("bj-web");
+    for t in TRAINERS {
+        if let Some(page) = t.page {
+            check_page(
+                page.file,
+                &fs::read_to_string(web.join("www").join(page.file))?,
+                t.glue,
+            )?;
+        }
+    }
+    if out.exists() {
+        fs::remove_dir_all(out)?;
+    }
+    copy_www(&web.join("www"), out, &version(root)?)?;
+    for t in TRAINERS {
+        let pkg = root.join("target").join("xtask").join(t.dir);
+        println!("Building {}", t.dir);
+        let status = Command::new("wasm-pack")
+            .arg("build")
+            .arg(if release { "--release" } else { "--dev" })
+            .args(["--target", "web", "--out-name", t.glue, "--out-dir"])
+            .arg(&pkg)
+            .arg(web.join(t.dir))
+            .status()
+            .map_err(|e| format!("Unable to run wasm-pack: {}", e))?;
+        if !status.success() {
+            return Err(format!("wasm-pack failed to build {}", t.dir).into());
+        }
+        for f in &[format!("{}.js", t.glue), format!("{}_bg.wasm", t.glue)] {
+            fs::copy(pkg.join(f), out.join(f))?;
+        }
+    }
+    println!("Built the site in {}", out.display());
+    Ok(())
+}
+
+/// Write every generated strategy card over its dataset's file
+fn cards() -> Result<(), Box<dyn Error>> {
+    let data = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .parent()
+        .unwrap()
+        .join("bj-data")
+        .join("data");
+    for d in DATASETS.iter().filter(|d| d.kind == Kind::StrategyCard) {
+        let bs = match bj_data::generate_card(d.name) {
+            Some(bs) => bs,
+            None => continue,
+        };
+        let json = serde_json::to_vec(&bs)?;
+        if json != d.data {
+            println!("{} changed, so bump its version", d.name);
+        }
+        fs::write(data.join(format!("{}.json", d.name)), json)?;
+    }
+    Ok(())
+}
+
+fn usage() -> String {
+    "Usage: cargo xtask site [--release] [--out DIR]\n       cargo xtask cards".to_string()
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match args.first().map(|s| s.as_str()) {
+        Some("site") => {
+            let mut release = false;
+            let mut out = PathBuf::from("www-out");
+            let mut rest = args[1..].iter();
+            while let Some(arg) = rest.next() {
+                match arg.as_str() {
+                    "--release" => release = true,
+                    "--out" => out = PathBuf::from(rest.next().ok_or_else(usage)?),
+                    _ => return Err(usage().into()),
+                }
+            }
+            site(release, &out)
+        }
+        Some("cards") if args.len() == 1 => cards(),
+        _ => Err(usage().into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn templates() {
+        let page = "<head><!-- BJ_TMPL_HEAD --></head><!-- BJ_TMPL_HEADER -->\
+                    <script>'./bj_web_index.js'</script><!-- BJ_TMPL_FOOTER -->";
+        let custom = super::page("custom-card.html").unwrap();
+        let s = render(page, &custom, "2026-03-14 (abc1234)");
+        assert!(s.contains("<title>Custom strategy card - BJ Trainer</title>"));
+        assert!(s.contains("<li><a href='index.html'>Game</a></li>"));
+        assert!(s.contains("<li><a href='custom-card.html' aria-current=page>Customize</a></li>"));
+        assert!(s.contains("Version 2026-03-14 (abc1234)"));
+        assert!(s.contains("gtag('config'"));
+        assert!(!s.contains("BJ_TMPL") && !s.contains("GOOGLE_SHIT"));
+        assert!(check_page("a.html", page, "bj_web_index").is_ok());
+        assert!(check_page("a.html", page, "bj_web_custom_card").is_err());
+        let no_footer = page.replace(TMPL_FOOTER, "");
+        assert!(check_page("a.html", &no_footer, "bj_web_index").is_err());
+        assert!(super::page("counting.html").is_none());
+    }
+
+    #[test]
+    fn pages_belong_to_trainers() {
+        let www = Path::new(env!("CARGO_MANIFEST_DIR")).join("../bj-web/www");
+        for t in TRAINERS {
+            assert!(Path::new(env!("CARGO_MANIFEST_DIR"))
+                .join("../bj-web")
+                .join(t.dir)
+                .join("Cargo.toml")
+                .exists());
+            if let Some(page) = t.page {
+                let src = fs::read_to_string(www.join(page.file)).unwrap();
+                check_page(page.file, &src, t.glue).unwrap();
+            }
+        }
+        for entry in fs::read_dir(&www).unwrap() {
+            let name = entry.unwrap().file_name().to_string_lossy().to_string();
+            if name.ends_with(".html") {
+                assert!(super::page(&name).is_some(), "{}", name);
+            }
+        }
+    }
+}